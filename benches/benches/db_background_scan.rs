@@ -0,0 +1,127 @@
+use criterion::{
+    criterion_group,
+    criterion_main,
+    BenchmarkId,
+    Criterion,
+};
+use fuel_core::{
+    database::database_description::on_chain::OnChain,
+    state::rocks_db::{
+        RocksDb,
+        ShallowTempDir,
+    },
+};
+use fuel_core_storage::{
+    column::Column,
+    iter::{
+        IterConfig,
+        IterOptions,
+        IteratorableStore,
+    },
+    kv_store::{
+        BatchOperations,
+        KeyValueStore,
+        WriteOperation,
+    },
+};
+use rand::{
+    rngs::StdRng,
+    Rng,
+    SeedableRng,
+};
+use std::sync::{
+    atomic::{
+        AtomicBool,
+        Ordering,
+    },
+    Arc,
+};
+
+// Use Jemalloc during benchmarks
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+/// The block cache is 1/3 of the capacity, so it fits the hot set, but not the scanned column.
+const CAPACITY: usize = 48 * 1024 * 1024;
+const HOT_ENTRIES: usize = 4_000;
+const SCANNED_ENTRIES: u64 = 200_000;
+const VALUE_SIZE: usize = 1024;
+
+fn fill(db: &RocksDb<OnChain>, rng: &mut StdRng) -> Vec<[u8; 32]> {
+    let hot = (0..HOT_ENTRIES)
+        .map(|_| rng.gen::<[u8; 32]>())
+        .collect::<Vec<_>>();
+    let mut entries = hot.iter().map(|key| {
+        (
+            key.to_vec(),
+            Column::ContractsState,
+            WriteOperation::Insert(Arc::new(vec![1; VALUE_SIZE])),
+        )
+    });
+    db.batch_write(&mut entries).unwrap();
+
+    let mut entries = (0..SCANNED_ENTRIES).map(|i| {
+        (
+            i.to_be_bytes().to_vec(),
+            Column::Coins,
+            WriteOperation::Insert(Arc::new(vec![2; VALUE_SIZE])),
+        )
+    });
+    db.batch_write(&mut entries).unwrap();
+    hot
+}
+
+/// Measures the latency of foreground `get`s of the hot set while the background thread
+/// scans the large column with and without filling the block cache.
+fn get_during_background_scan(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0xF00DF00D);
+    let tmp_dir = ShallowTempDir::new();
+    let db = RocksDb::<OnChain>::default_open(tmp_dir.path(), Some(CAPACITY))
+        .expect("Failed to open the database");
+    let hot = fill(&db, &mut rng);
+    // The recovery from the WAL on reopen moves values into SST files.
+    drop(db);
+    let db = RocksDb::<OnChain>::default_open(tmp_dir.path(), Some(CAPACITY))
+        .expect("Failed to reopen the database");
+
+    let mut group = c.benchmark_group("get during background scan");
+    for fill_cache in [true, false] {
+        let options = IterOptions {
+            fill_cache,
+            ..Default::default()
+        };
+        let stopped = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                while !stopped.load(Ordering::Relaxed) {
+                    let config = IterConfig {
+                        options,
+                        ..Default::default()
+                    };
+                    for item in db.iter(Column::Coins, config) {
+                        item.unwrap();
+                        if stopped.load(Ordering::Relaxed) {
+                            break
+                        }
+                    }
+                }
+            });
+
+            group.bench_with_input(
+                BenchmarkId::new("fill_cache", fill_cache),
+                &hot,
+                |b, hot| {
+                    b.iter(|| {
+                        let key = hot[rng.gen_range(0..hot.len())];
+                        db.get(&key, Column::ContractsState).unwrap()
+                    })
+                },
+            );
+            stopped.store(true, Ordering::Relaxed);
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, get_during_background_scan);
+criterion_main!(benches);
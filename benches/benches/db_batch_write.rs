@@ -0,0 +1,108 @@
+use criterion::{
+    criterion_group,
+    criterion_main,
+    BatchSize,
+    Criterion,
+};
+use fuel_core::{
+    database::database_description::on_chain::OnChain,
+    state::rocks_db::{
+        RocksDb,
+        ShallowTempDir,
+    },
+};
+use fuel_core_storage::{
+    column::Column,
+    kv_store::{
+        BatchOperations,
+        KeyValueStore,
+        WriteOperation,
+    },
+};
+use rand::{
+    rngs::StdRng,
+    Rng,
+    SeedableRng,
+};
+use std::sync::Arc;
+
+// Use Jemalloc during benchmarks
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+const STATE_CHANGES: usize = 4_000;
+const COIN_CHANGES: usize = 1_000;
+
+fn random_bytes(rng: &mut StdRng, len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    rng.fill(bytes.as_mut_slice());
+    bytes
+}
+
+/// The changes of a block: contract state updates, and created and spent coins.
+fn block_changes(rng: &mut StdRng) -> Vec<(Vec<u8>, Column, WriteOperation)> {
+    let mut changes = Vec::with_capacity(STATE_CHANGES + COIN_CHANGES);
+    for _ in 0..STATE_CHANGES {
+        changes.push((
+            random_bytes(rng, 64),
+            Column::ContractsState,
+            WriteOperation::Insert(Arc::new(random_bytes(rng, 32))),
+        ));
+    }
+    for i in 0..COIN_CHANGES {
+        let key = random_bytes(rng, 34);
+        let op = if i % 2 == 0 {
+            WriteOperation::Insert(Arc::new(random_bytes(rng, 32)))
+        } else {
+            WriteOperation::Remove
+        };
+        changes.push((key, Column::Coins, op));
+    }
+    changes
+}
+
+/// Compares the commit of the block with one `WriteBatch` and with one write per entry,
+/// as the default implementation of the `BatchOperations::batch_write` does.
+fn commit_block(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0xF00DF00D);
+    let tmp_dir = ShallowTempDir::new();
+    let db = RocksDb::<OnChain>::default_open(tmp_dir.path(), None)
+        .expect("Failed to open the database");
+
+    let mut group = c.benchmark_group("commit block with 5,000 changes");
+
+    group.bench_function("write batch", |b| {
+        b.iter_batched(
+            || block_changes(&mut rng),
+            |changes| {
+                db.batch_write(&mut changes.into_iter())
+                    .expect("Failed to write the batch")
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("write per entry", |b| {
+        b.iter_batched(
+            || block_changes(&mut rng),
+            |changes| {
+                for (key, column, op) in changes {
+                    // The old values are read for the undo log of the batch.
+                    match op {
+                        WriteOperation::Insert(value) => {
+                            db.replace(&key, column, value).map(|_| ())
+                        }
+                        WriteOperation::Remove => db.take(&key, column).map(|_| ()),
+                        WriteOperation::Merge(operand) => db.merge(&key, column, operand),
+                    }
+                    .expect("Failed to write the entry");
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, commit_block);
+criterion_main!(benches);
@@ -0,0 +1,122 @@
+use criterion::{
+    criterion_group,
+    criterion_main,
+    BenchmarkId,
+    Criterion,
+};
+use fuel_core::{
+    database::database_description::on_chain::OnChain,
+    state::rocks_db::{
+        RocksDb,
+        ShallowTempDir,
+    },
+};
+use fuel_core_storage::{
+    column::Column,
+    kv_store::KeyValueStore,
+};
+use rand::{
+    rngs::StdRng,
+    Rng,
+    SeedableRng,
+};
+use std::sync::Arc;
+
+// Use Jemalloc during benchmarks
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+const ENTRIES: usize = 10_000;
+const VALUE_SIZES: [usize; 3] = [32, 4 * 1024, 64 * 1024];
+
+/// Compares `exists` with the `get` that copies the value. The `ContractsState`
+/// column has the bloom filter, so missing keys are rejected without reading blocks.
+fn exists_vs_get(c: &mut Criterion) {
+    let mut group = c.benchmark_group("exists vs get");
+
+    for value_size in VALUE_SIZES {
+        let mut rng = StdRng::seed_from_u64(0xF00DF00D);
+        let tmp_dir = ShallowTempDir::new();
+        let db = RocksDb::<OnChain>::default_open(tmp_dir.path(), None)
+            .expect("Failed to open the database");
+
+        let keys = (0..ENTRIES)
+            .map(|_| rng.gen::<[u8; 32]>())
+            .collect::<Vec<_>>();
+        for key in &keys {
+            let mut value = vec![0u8; value_size];
+            rng.fill(value.as_mut_slice());
+            db.put(key, Column::ContractsState, Arc::new(value))
+                .expect("Failed to insert the value");
+        }
+        // The recovery from the WAL on reopen moves values into SST files with bloom filters.
+        drop(db);
+        let db = RocksDb::<OnChain>::default_open(tmp_dir.path(), None)
+            .expect("Failed to reopen the database");
+        let missing = (0..ENTRIES)
+            .map(|_| rng.gen::<[u8; 32]>())
+            .collect::<Vec<_>>();
+
+        group.bench_with_input(
+            BenchmarkId::new("get existing", value_size),
+            &keys,
+            |b, keys| {
+                b.iter(|| {
+                    keys.iter()
+                        .filter(|key| {
+                            db.get(key.as_slice(), Column::ContractsState)
+                                .unwrap()
+                                .is_some()
+                        })
+                        .count()
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("exists existing", value_size),
+            &keys,
+            |b, keys| {
+                b.iter(|| {
+                    keys.iter()
+                        .filter(|key| {
+                            db.exists(key.as_slice(), Column::ContractsState).unwrap()
+                        })
+                        .count()
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("get missing", value_size),
+            &missing,
+            |b, keys| {
+                b.iter(|| {
+                    keys.iter()
+                        .filter(|key| {
+                            db.get(key.as_slice(), Column::ContractsState)
+                                .unwrap()
+                                .is_some()
+                        })
+                        .count()
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("exists missing", value_size),
+            &missing,
+            |b, keys| {
+                b.iter(|| {
+                    keys.iter()
+                        .filter(|key| {
+                            db.exists(key.as_slice(), Column::ContractsState).unwrap()
+                        })
+                        .count()
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, exists_vs_get);
+criterion_main!(benches);
@@ -0,0 +1,124 @@
+use criterion::{
+    black_box,
+    criterion_group,
+    criterion_main,
+    BenchmarkGroup,
+    BenchmarkId,
+    Criterion,
+    Throughput,
+};
+use fuel_core::{
+    database::database_description::on_chain::OnChain,
+    state::{
+        in_memory::memory_store::MemoryStore,
+        rocks_db::{
+            RocksDb,
+            ShallowTempDir,
+        },
+    },
+};
+use fuel_core_storage::{
+    column::Column,
+    kv_store::KeyValueStore,
+};
+use rand::{
+    rngs::StdRng,
+    Rng,
+    SeedableRng,
+};
+use std::sync::Arc;
+
+// Use Jemalloc during benchmarks
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+const ENTRIES: usize = 10_000;
+const VALUE_SIZES: [usize; 3] = [32, 4 * 1024, 64 * 1024];
+
+/// Fills the `store` with random values of the `value_size` and returns their keys.
+fn populate(
+    store: &dyn KeyValueStore<Column = Column>,
+    value_size: usize,
+) -> Vec<[u8; 32]> {
+    let mut rng = StdRng::seed_from_u64(0xF00DF00D);
+    let keys = (0..ENTRIES)
+        .map(|_| rng.gen::<[u8; 32]>())
+        .collect::<Vec<_>>();
+    for key in &keys {
+        let mut value = vec![0u8; value_size];
+        rng.fill(value.as_mut_slice());
+        store
+            .put(key, Column::ContractsState, Arc::new(value))
+            .expect("Failed to insert the value");
+    }
+    keys
+}
+
+/// Benchmarks `get`, which copies the value, and `get_with`, which borrows it,
+/// reading the first byte of each value in both cases.
+fn bench_store(
+    group: &mut BenchmarkGroup<'_, criterion::measurement::WallTime>,
+    name: &str,
+    store: &dyn KeyValueStore<Column = Column>,
+    keys: &[[u8; 32]],
+    value_size: usize,
+) {
+    group.bench_with_input(
+        BenchmarkId::new(format!("{name} get"), value_size),
+        keys,
+        |b, keys| {
+            b.iter(|| {
+                keys.iter().fold(0u8, |acc, key| {
+                    let value = store
+                        .get(key, Column::ContractsState)
+                        .unwrap()
+                        .expect("The value exists");
+                    acc ^ value[0]
+                })
+            })
+        },
+    );
+    group.bench_with_input(
+        BenchmarkId::new(format!("{name} get_with"), value_size),
+        keys,
+        |b, keys| {
+            b.iter(|| {
+                keys.iter().fold(0u8, |acc, key| {
+                    let mut first = 0;
+                    let found = store
+                        .get_with(key, Column::ContractsState, &mut |value| {
+                            first = black_box(value)[0]
+                        })
+                        .unwrap();
+                    assert!(found, "The value exists");
+                    acc ^ first
+                })
+            })
+        },
+    );
+}
+
+/// Compares `get` with `get_with` on the RocksDB and the in-memory store. The copy of
+/// the value by the `get` grows with the size of the value, while `get_with` avoids it.
+fn get_vs_get_with(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get vs get_with");
+    group.throughput(Throughput::Elements(ENTRIES as u64));
+
+    for value_size in VALUE_SIZES {
+        let tmp_dir = ShallowTempDir::new();
+        let db = RocksDb::<OnChain>::default_open(tmp_dir.path(), None)
+            .expect("Failed to open the database");
+        let keys = populate(&db, value_size);
+        bench_store(&mut group, "rocksdb", &db, &keys, value_size);
+        drop(db);
+
+        let store = MemoryStore::<OnChain>::default();
+        let keys = populate(&store, value_size);
+        bench_store(&mut group, "memory", &store, &keys, value_size);
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, get_vs_get_with);
+criterion_main!(benches);
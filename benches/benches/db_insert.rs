@@ -0,0 +1,75 @@
+use criterion::{
+    criterion_group,
+    criterion_main,
+    BatchSize,
+    Criterion,
+};
+use fuel_core::{
+    database::Database,
+    state::rocks_db::ShallowTempDir,
+};
+use fuel_core_storage::{
+    tables::Coins,
+    StorageBlindMutate,
+    StorageMutate,
+};
+use fuel_core_types::{
+    entities::coins::coin::CompressedCoin,
+    fuel_tx::UtxoId,
+    fuel_types::Bytes32,
+};
+
+// Use Jemalloc during benchmarks
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+const COINS: u64 = 1_000_000;
+
+fn utxo_id(i: u64) -> UtxoId {
+    let mut tx_id = Bytes32::zeroed();
+    tx_id[..8].copy_from_slice(&i.to_be_bytes());
+    UtxoId::new(tx_id, 0)
+}
+
+fn open() -> (ShallowTempDir, Database) {
+    let tmp_dir = ShallowTempDir::new();
+    let db = Database::open(tmp_dir.path(), None).expect("Failed to open the database");
+    (tmp_dir, db)
+}
+
+/// Compares `insert`, which reads the old value before the write, with the blind `put`.
+fn insert_vs_put(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert 1M coins");
+    group.sample_size(10);
+    let coin = CompressedCoin::default();
+
+    group.bench_function("insert", |b| {
+        b.iter_batched(
+            open,
+            |(_tmp_dir, mut db)| {
+                for i in 0..COINS {
+                    StorageMutate::<Coins>::insert(&mut db, &utxo_id(i), &coin)
+                        .expect("Failed to insert the coin");
+                }
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.bench_function("put", |b| {
+        b.iter_batched(
+            open,
+            |(_tmp_dir, mut db)| {
+                for i in 0..COINS {
+                    StorageBlindMutate::<Coins>::put(&mut db, &utxo_id(i), &coin)
+                        .expect("Failed to put the coin");
+                }
+            },
+            BatchSize::PerIteration,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, insert_vs_put);
+criterion_main!(benches);
@@ -0,0 +1,200 @@
+use criterion::{
+    criterion_group,
+    criterion_main,
+    Criterion,
+};
+use fuel_core::{
+    database::database_description::on_chain::OnChain,
+    state::rocks_db::{
+        RocksDb,
+        ShallowTempDir,
+    },
+};
+use fuel_core_storage::{
+    column::Column,
+    iter::{
+        IterDirection,
+        IteratorableStore,
+    },
+    kv_store::KeyValueStore,
+};
+use rand::{
+    rngs::StdRng,
+    Rng,
+    SeedableRng,
+};
+use std::sync::Arc;
+
+// Use Jemalloc during benchmarks
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+const ENTRIES: usize = 100_000;
+const VALUE_SIZE: usize = 1024;
+
+fn iterate_column(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0xF00DF00D);
+    let tmp_dir = ShallowTempDir::new();
+    let db = RocksDb::<OnChain>::default_open(tmp_dir.path(), None)
+        .expect("Failed to open the database");
+
+    for _ in 0..ENTRIES {
+        let key: [u8; 32] = rng.gen();
+        let mut value = vec![0u8; VALUE_SIZE];
+        rng.fill(value.as_mut_slice());
+        db.put(&key, Column::ContractsState, Arc::new(value))
+            .expect("Failed to insert the value");
+    }
+
+    let mut group = c.benchmark_group("iterate column with 100,000 1KB values");
+
+    group.bench_function("iter_all", |b| {
+        b.iter(|| {
+            db.iter_all(Column::ContractsState, None, None, IterDirection::Forward)
+                .count()
+        })
+    });
+
+    group.bench_function("iter_keys", |b| {
+        b.iter(|| {
+            db.iter_keys(Column::ContractsState, None, None, IterDirection::Forward)
+                .count()
+        })
+    });
+
+    group.finish();
+}
+
+const OWNERS: usize = 10_000;
+const ENTRIES_PER_OWNER: usize = 10;
+
+/// Compares the iteration over the prefix in the `ContractsState` column, that has
+/// the prefix extractor and prefix bloom filters, with the same iteration
+/// in the `ContractsRawCode` column without them.
+fn iterate_prefix_of_owners(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0xF00DF00D);
+    let tmp_dir = ShallowTempDir::new();
+    let db = RocksDb::<OnChain>::default_open(tmp_dir.path(), None)
+        .expect("Failed to open the database");
+
+    let owners = (0..OWNERS)
+        .map(|_| rng.gen::<[u8; 32]>())
+        .collect::<Vec<_>>();
+    for owner in &owners {
+        for _ in 0..ENTRIES_PER_OWNER {
+            let key = [owner.as_slice(), &rng.gen::<[u8; 32]>()].concat();
+            let value: [u8; 32] = rng.gen();
+            for column in [Column::ContractsState, Column::ContractsRawCode] {
+                db.put(&key, column, Arc::new(value.to_vec()))
+                    .expect("Failed to insert the value");
+            }
+        }
+    }
+    // The recovery from the WAL on reopen moves values into SST files with bloom filters.
+    drop(db);
+    let db = RocksDb::<OnChain>::default_open(tmp_dir.path(), None)
+        .expect("Failed to reopen the database");
+    let missing = (0..OWNERS)
+        .map(|_| rng.gen::<[u8; 32]>())
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("iterate prefix of 10,000 owners");
+
+    for (name, column) in [
+        ("prefix seek", Column::ContractsState),
+        ("total order", Column::ContractsRawCode),
+    ] {
+        group.bench_function(format!("{name} existing owners"), |b| {
+            b.iter(|| {
+                owners
+                    .iter()
+                    .map(|owner| {
+                        db.iter_all(
+                            column,
+                            Some(owner.as_slice()),
+                            None,
+                            IterDirection::Forward,
+                        )
+                        .count()
+                    })
+                    .sum::<usize>()
+            })
+        });
+        group.bench_function(format!("{name} missing owners"), |b| {
+            b.iter(|| {
+                missing
+                    .iter()
+                    .map(|owner| {
+                        db.iter_all(
+                            column,
+                            Some(owner.as_slice()),
+                            None,
+                            IterDirection::Forward,
+                        )
+                        .count()
+                    })
+                    .sum::<usize>()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+const HUGE_PREFIX_ENTRIES: usize = 100_000;
+const SMALL_PREFIX_ENTRIES: usize = 10;
+
+/// Scans a small prefix followed by a huge one. The iterate bounds stop the RocksDB
+/// at the end of the small prefix instead of reading the blocks of the huge prefix.
+fn iterate_small_prefix_before_huge_one(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0xF00DF00D);
+    let tmp_dir = ShallowTempDir::new();
+    let db = RocksDb::<OnChain>::default_open(tmp_dir.path(), None)
+        .expect("Failed to open the database");
+
+    // The `Metadata` column doesn't have the prefix extractor,
+    // so only the bounds limit the iteration.
+    let small_prefix = [1u8, 0xFF];
+    let huge_prefix = [2u8];
+    for (prefix, entries) in [
+        (small_prefix.as_slice(), SMALL_PREFIX_ENTRIES),
+        (huge_prefix.as_slice(), HUGE_PREFIX_ENTRIES),
+    ] {
+        for _ in 0..entries {
+            let key = [prefix, &rng.gen::<[u8; 32]>()].concat();
+            let mut value = vec![0u8; VALUE_SIZE];
+            rng.fill(value.as_mut_slice());
+            db.put(&key, Column::Metadata, Arc::new(value))
+                .expect("Failed to insert the value");
+        }
+    }
+    drop(db);
+    let db = RocksDb::<OnChain>::default_open(tmp_dir.path(), None)
+        .expect("Failed to reopen the database");
+
+    let mut group = c.benchmark_group("iterate small prefix before huge one");
+
+    for direction in [IterDirection::Forward, IterDirection::Reverse] {
+        group.bench_function(format!("{direction:?}"), |b| {
+            b.iter(|| {
+                db.iter_all(
+                    Column::Metadata,
+                    Some(small_prefix.as_slice()),
+                    None,
+                    direction,
+                )
+                .count()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    iterate_column,
+    iterate_prefix_of_owners,
+    iterate_small_prefix_before_huge_one
+);
+criterion_main!(benches);
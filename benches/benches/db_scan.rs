@@ -0,0 +1,127 @@
+use criterion::{
+    criterion_group,
+    criterion_main,
+    Criterion,
+    Throughput,
+};
+use fuel_core::{
+    database::database_description::on_chain::OnChain,
+    state::{
+        in_memory::memory_store::MemoryStore,
+        rocks_db::{
+            RocksDb,
+            ShallowTempDir,
+        },
+        TransactableStorage,
+    },
+};
+use fuel_core_storage::{
+    column::Column,
+    iter::{
+        IterDirection,
+        IteratorableStore,
+    },
+    kv_store::{
+        BatchOperations,
+        WriteOperation,
+    },
+};
+use std::{
+    alloc::{
+        GlobalAlloc,
+        Layout,
+    },
+    sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        Arc,
+    },
+};
+
+/// Jemalloc that counts allocations to compare the boxed and the lending iterators.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        tikv_jemallocator::Jemalloc.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        tikv_jemallocator::Jemalloc.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        tikv_jemallocator::Jemalloc.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const ENTRIES: u64 = 1_000_000;
+
+fn fill(storage: &dyn TransactableStorage<Column = Column>) {
+    let mut entries = (0..ENTRIES).map(|i| {
+        (
+            i.to_be_bytes().to_vec(),
+            Column::Coins,
+            WriteOperation::Insert(Arc::new([i.to_be_bytes()[7]; 32].to_vec())),
+        )
+    });
+    storage.batch_write(&mut entries).unwrap();
+}
+
+fn scan_boxed(storage: &dyn TransactableStorage<Column = Column>) -> usize {
+    storage
+        .iter_all(Column::Coins, None, None, IterDirection::Forward)
+        .map(|item| item.unwrap().1.len())
+        .sum()
+}
+
+fn scan_lending(storage: &dyn TransactableStorage<Column = Column>) -> usize {
+    let mut iter = storage.iter_column(Column::Coins, None, None, IterDirection::Forward);
+    let mut sum = 0usize;
+    while let Some(item) = iter.next() {
+        sum = sum.saturating_add(item.unwrap().1.len());
+    }
+    sum
+}
+
+/// Returns the number of allocations done by one `scan`.
+fn allocations(scan: impl Fn() -> usize) -> u64 {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    criterion::black_box(scan());
+    ALLOCATIONS.load(Ordering::Relaxed).saturating_sub(before) as u64
+}
+
+fn db_scan(c: &mut Criterion) {
+    let tmp_dir = ShallowTempDir::new();
+    let rocks_db = RocksDb::<OnChain>::default_open(tmp_dir.path(), None).unwrap();
+    let memory = MemoryStore::<OnChain>::default();
+    let storages: [(&str, &dyn TransactableStorage<Column = Column>); 2] =
+        [("rocksdb", &rocks_db), ("memory", &memory)];
+
+    let mut group = c.benchmark_group("scan_1m_entries");
+    group.sample_size(10);
+    for (name, storage) in storages {
+        fill(storage);
+        // The throughput of each scan is the number of its allocations, so the drop
+        // of the allocation count shows up in the report of criterion.
+        group.throughput(Throughput::Elements(allocations(|| scan_boxed(storage))));
+        group.bench_function(format!("{name}/boxed"), |b| b.iter(|| scan_boxed(storage)));
+        group.throughput(Throughput::Elements(allocations(|| scan_lending(storage))));
+        group.bench_function(format!("{name}/lending"), |b| {
+            b.iter(|| scan_lending(storage))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, db_scan);
+criterion_main!(benches);
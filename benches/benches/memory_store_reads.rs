@@ -0,0 +1,67 @@
+use criterion::{
+    criterion_group,
+    criterion_main,
+    BenchmarkId,
+    Criterion,
+    Throughput,
+};
+use fuel_core::state::in_memory::memory_store::MemoryStore;
+use fuel_core_storage::{
+    column::Column,
+    kv_store::KeyValueStore,
+};
+use std::sync::Arc;
+
+// Use Jemalloc during benchmarks
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+const KEYS: u64 = 100_000;
+const READS_PER_THREAD: u64 = 10_000;
+
+fn populated_store() -> MemoryStore {
+    let store = MemoryStore::default();
+    for i in 0..KEYS {
+        store
+            .put(&i.to_be_bytes(), Column::Coins, Arc::new(vec![0; 32]))
+            .expect("Failed to put the value");
+    }
+    store
+}
+
+/// Measures the read throughput of the in-memory store with the growing number of threads.
+/// Readers don't block each other, so the throughput should scale with threads.
+fn concurrent_reads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("memory store concurrent reads");
+    let store = populated_store();
+
+    for threads in [1u64, 2, 4, 8] {
+        group.throughput(Throughput::Elements(threads * READS_PER_THREAD));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(threads),
+            &threads,
+            |b, &threads| {
+                b.iter(|| {
+                    std::thread::scope(|scope| {
+                        for thread in 0..threads {
+                            let store = &store;
+                            scope.spawn(move || {
+                                for i in 0..READS_PER_THREAD {
+                                    let key = (i * threads + thread) % KEYS;
+                                    store
+                                        .get(&key.to_be_bytes(), Column::Coins)
+                                        .expect("Failed to get the value");
+                                }
+                            });
+                        }
+                    })
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, concurrent_reads);
+criterion_main!(benches);
@@ -25,6 +25,14 @@ use fuel_core::{
         ServiceTrait,
         VMConfig,
     },
+    state::{
+        column_config::{
+            ColumnsConfig,
+            ResourceLimits,
+        },
+        open_mode::OpenMode,
+        slow_log::SlowLogConfig,
+    },
     txpool::Config as TxPoolConfig,
     types::{
         blockchain::primitives::SecretKeyWrapper,
@@ -104,6 +112,112 @@ pub struct Command {
     )]
     pub database_type: DbType,
 
+    /// Refuse to open the database with column families unknown to this version of the node,
+    /// e.g. written by a newer version. By default, unknown column families are ignored.
+    #[arg(long = "db-deny-unknown-columns", env)]
+    pub database_deny_unknown_columns: bool,
+
+    /// What to do when the database at the `DB_PATH` is missing or already exists.
+    /// Production nodes should use `must-exist` after the database is initialized,
+    /// so the wrong path fails the start instead of syncing from scratch.
+    #[arg(
+        long = "db-open-mode",
+        default_value = "create-if-missing",
+        value_enum,
+        env
+    )]
+    pub database_open_mode: OpenMode,
+
+    /// The number of files kept open by each RocksDB database, `0` keeps all files open.
+    /// It is clamped to the half of the soft limit of file descriptors of the process.
+    #[arg(
+        long = "db-max-open-files",
+        default_value_t = ResourceLimits::default().max_open_files.unwrap_or(0),
+        env
+    )]
+    pub database_max_open_files: u32,
+
+    /// The number of background flushes and compactions of each RocksDB database.
+    /// By default, the half of CPUs.
+    #[arg(long = "db-max-background-jobs", env)]
+    pub database_max_background_jobs: Option<u32>,
+
+    /// The rate in bytes per second of writes of background flushes and compactions
+    /// of each RocksDB database. By default, it is unlimited.
+    #[arg(long = "db-background-io-rate", env)]
+    pub database_background_io_rate: Option<u64>,
+
+    /// The memory in bytes shared by the block cache and memtables of all columns
+    /// of each RocksDB database. It replaces `--max-database-cache-size` for RocksDB.
+    #[arg(long = "db-memory-budget", env)]
+    pub database_memory_budget_bytes: Option<usize>,
+
+    /// The size of the write-ahead log in bytes after which RocksDB flushes the oldest memtables.
+    #[arg(
+        long = "db-max-total-wal-size",
+        default_value_t = ResourceLimits::default().max_total_wal_size,
+        env
+    )]
+    pub database_max_total_wal_size: u64,
+
+    /// The size of SST files of RocksDB in bytes. Larger files need fewer file descriptors.
+    #[arg(
+        long = "db-target-file-size",
+        default_value_t = ResourceLimits::default().target_file_size,
+        env
+    )]
+    pub database_target_file_size: u64,
+
+    /// The number of files on the first level of the RocksDB column at which writes are slowed.
+    #[arg(
+        long = "db-level0-slowdown-writes-trigger",
+        default_value_t = ResourceLimits::default().level0_slowdown_writes_trigger,
+        env
+    )]
+    pub database_level0_slowdown_writes_trigger: u32,
+
+    /// The number of files on the first level of the RocksDB column at which writes are stopped.
+    #[arg(
+        long = "db-level0-stop-writes-trigger",
+        default_value_t = ResourceLimits::default().level0_stop_writes_trigger,
+        env
+    )]
+    pub database_level0_stop_writes_trigger: u32,
+
+    /// The size in bytes of data pending for the compaction of the RocksDB column
+    /// at which writes are slowed.
+    #[arg(
+        long = "db-soft-pending-compaction-bytes",
+        default_value_t = ResourceLimits::default().soft_pending_compaction_bytes,
+        env
+    )]
+    pub database_soft_pending_compaction_bytes: u64,
+
+    /// The size in bytes of data pending for the compaction of the RocksDB column
+    /// at which writes are stopped.
+    #[arg(
+        long = "db-hard-pending-compaction-bytes",
+        default_value_t = ResourceLimits::default().hard_pending_compaction_bytes,
+        env
+    )]
+    pub database_hard_pending_compaction_bytes: u64,
+
+    /// Enables the statistics of RocksDB, e.g. hits of the block cache and stalls of writes,
+    /// recorded into metrics. The statistics slow down database operations a little.
+    #[arg(long = "db-statistics", env)]
+    pub database_statistics: bool,
+
+    /// Rejects writes of keys that don't have the format of the column, e.g. truncated ids,
+    /// and reports such keys met by scans. By default, keys are checked only in debug builds.
+    #[arg(long = "db-strict-keys", env)]
+    pub database_strict_keys: bool,
+
+    /// Stores values with checksums verified on every read, so corrupted values fail reads
+    /// instead of being decoded. Each value grows by 5 bytes. Values written without the flag
+    /// are read as is until they are rewritten. The database with checksums requires the flag.
+    #[arg(long = "db-value-checksums", env)]
+    pub database_value_checksums: bool,
+
     /// Specify either an alias to a built-in configuration or filepath to a JSON file.
     #[arg(
         name = "CHAIN_CONFIG",
@@ -197,6 +311,28 @@ pub struct Command {
     #[clap(long = "query-log-threshold-time", default_value = "2s", env)]
     pub query_log_threshold_time: humantime::Duration,
 
+    /// The `get`, `put`, `delete`, and `exists` storage operations slower than it are logged.
+    /// The zero disables the check.
+    #[clap(long = "storage-slow-op-threshold", default_value = "50ms", env)]
+    pub storage_slow_op_threshold: humantime::Duration,
+
+    /// Storage iterators that spent more time yielding items are logged.
+    /// The zero disables the check.
+    #[clap(long = "storage-slow-iter-threshold", default_value = "250ms", env)]
+    pub storage_slow_iter_threshold: humantime::Duration,
+
+    /// Storage iterators that yielded more items are logged. The zero disables the check.
+    #[clap(long = "storage-slow-iter-items", default_value = "100000", env)]
+    pub storage_slow_iter_items: usize,
+
+    /// Storage batch writes slower than it are logged. The zero disables the check.
+    #[clap(long = "storage-slow-batch-threshold", default_value = "250ms", env)]
+    pub storage_slow_batch_threshold: humantime::Duration,
+
+    /// Storage batch writes with more entries are logged. The zero disables the check.
+    #[clap(long = "storage-slow-batch-entries", default_value = "100000", env)]
+    pub storage_slow_batch_entries: usize,
+
     /// Timeout before drop the request.
     #[clap(long = "api-request-timeout", default_value = "30m", env)]
     pub api_request_timeout: humantime::Duration,
@@ -214,6 +350,21 @@ impl Command {
             max_database_cache_size,
             database_path,
             database_type,
+            database_deny_unknown_columns,
+            database_open_mode,
+            database_max_open_files,
+            database_max_background_jobs,
+            database_background_io_rate,
+            database_memory_budget_bytes,
+            database_max_total_wal_size,
+            database_target_file_size,
+            database_level0_slowdown_writes_trigger,
+            database_level0_stop_writes_trigger,
+            database_soft_pending_compaction_bytes,
+            database_hard_pending_compaction_bytes,
+            database_statistics,
+            database_strict_keys,
+            database_value_checksums,
             chain_config,
             vm_backtrace,
             debug,
@@ -238,6 +389,11 @@ impl Command {
             min_connected_reserved_peers,
             time_until_synced,
             query_log_threshold_time,
+            storage_slow_op_threshold,
+            storage_slow_iter_threshold,
+            storage_slow_iter_items,
+            storage_slow_batch_threshold,
+            storage_slow_batch_entries,
             api_request_timeout,
             profiling: _,
         } = self;
@@ -338,6 +494,34 @@ impl Command {
             min_connected_reserved_peers,
             time_until_synced: time_until_synced.into(),
             query_log_threshold_time: query_log_threshold_time.into(),
+            database_metrics: metrics,
+            storage_slow_log: SlowLogConfig {
+                operation_duration: storage_slow_op_threshold.into(),
+                iter_duration: storage_slow_iter_threshold.into(),
+                iter_items: storage_slow_iter_items,
+                batch_write_duration: storage_slow_batch_threshold.into(),
+                batch_write_entries: storage_slow_batch_entries,
+            },
+            database_columns: ColumnsConfig::default()
+                .deny_unknown_column_families(database_deny_unknown_columns)
+                .with_open_mode(database_open_mode)
+                .with_limits(ResourceLimits {
+                    max_open_files: (database_max_open_files != 0)
+                        .then_some(database_max_open_files),
+                    max_background_jobs: database_max_background_jobs,
+                    max_total_wal_size: database_max_total_wal_size,
+                    target_file_size: database_target_file_size,
+                    background_io_bytes_per_sec: database_background_io_rate,
+                    memory_budget_bytes: database_memory_budget_bytes,
+                    level0_slowdown_writes_trigger:
+                        database_level0_slowdown_writes_trigger,
+                    level0_stop_writes_trigger: database_level0_stop_writes_trigger,
+                    soft_pending_compaction_bytes: database_soft_pending_compaction_bytes,
+                    hard_pending_compaction_bytes: database_hard_pending_compaction_bytes,
+                })
+                .enable_statistics(database_statistics)
+                .enable_strict_keys(database_strict_keys)
+                .enable_value_checksums(database_value_checksums),
         };
         Ok(config)
     }
@@ -363,7 +547,13 @@ pub async fn exec(command: Command) -> anyhow::Result<()> {
         _ = shutdown_signal() => {}
     }
 
+    let database = server.shared.database.clone();
     server.stop_and_await().await?;
+    drop(server);
+    // The node is stopped, so the database should be its last user.
+    database
+        .close()
+        .context("The database is not closed cleanly")?;
 
     Ok(())
 }
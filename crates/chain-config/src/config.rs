@@ -45,7 +45,10 @@ mod tests {
         coin::CoinConfig,
         contract::ContractConfig,
         message::MessageConfig,
-        state::StateConfig,
+        state::{
+            StateConfig,
+            StateEntriesSink,
+        },
     };
 
     #[cfg(feature = "std")]
@@ -221,6 +224,142 @@ mod tests {
         assert_eq!(config, deserialized_config);
     }
 
+    /// Collects chunks of the streamed state with indices of their first entries.
+    #[derive(Default)]
+    struct CollectedState {
+        coins: Vec<(usize, Vec<CoinConfig>)>,
+        contracts: Vec<(usize, Vec<ContractConfig>)>,
+        messages: Vec<(usize, Vec<MessageConfig>)>,
+    }
+
+    impl StateEntriesSink for CollectedState {
+        fn coins(
+            &mut self,
+            first_index: usize,
+            coins: Vec<CoinConfig>,
+        ) -> anyhow::Result<()> {
+            if coins.iter().any(|coin| coin.amount == 0) {
+                anyhow::bail!("The coin is empty")
+            }
+            self.coins.push((first_index, coins));
+            Ok(())
+        }
+
+        fn contracts(
+            &mut self,
+            first_index: usize,
+            contracts: Vec<ContractConfig>,
+        ) -> anyhow::Result<()> {
+            self.contracts.push((first_index, contracts));
+            Ok(())
+        }
+
+        fn messages(
+            &mut self,
+            first_index: usize,
+            messages: Vec<MessageConfig>,
+        ) -> anyhow::Result<()> {
+            self.messages.push((first_index, messages));
+            Ok(())
+        }
+    }
+
+    fn streamed(json: &str, chunk_size: usize) -> anyhow::Result<CollectedState> {
+        let mut state = CollectedState::default();
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        let height =
+            StateConfig::deserialize_streamed(&mut deserializer, chunk_size, &mut state)?;
+        deserializer.end()?;
+        assert_eq!(height, Some(7u32.into()));
+        Ok(state)
+    }
+
+    #[test]
+    fn streamed_state_is_passed_by_chunks() {
+        // Given
+        let mut rng = StdRng::seed_from_u64(1);
+        let coins = (0..5)
+            .map(|_| CoinConfig {
+                tx_id: Some(rng.gen()),
+                output_index: Some(rng.gen()),
+                owner: rng.gen(),
+                amount: rng.gen_range(1..u64::MAX),
+                asset_id: rng.gen(),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        let messages = test_message_config()
+            .initial_state
+            .unwrap()
+            .messages
+            .unwrap();
+        let state = StateConfig {
+            coins: Some(coins.clone()),
+            contracts: None,
+            messages: Some(messages.clone()),
+            height: Some(7u32.into()),
+        };
+        let json = serde_json::to_string(&state).unwrap();
+
+        // When
+        let streamed = streamed(&json, 2).unwrap();
+
+        // Then
+        assert_eq!(
+            streamed
+                .coins
+                .iter()
+                .map(|(first, chunk)| (*first, chunk.len()))
+                .collect::<Vec<_>>(),
+            vec![(0, 2), (2, 2), (4, 1)]
+        );
+        assert_eq!(
+            streamed
+                .coins
+                .into_iter()
+                .flat_map(|(_, chunk)| chunk)
+                .collect::<Vec<_>>(),
+            coins
+        );
+        assert!(streamed.contracts.is_empty());
+        assert_eq!(streamed.messages, vec![(0, messages)]);
+    }
+
+    #[test]
+    fn streamed_state_errors_name_the_entry() {
+        // Given
+        let coin = |tx_id: &str, amount: u64| {
+            format!(
+                r#"{{"tx_id":"{tx_id}","owner":"0x{owner}","amount":"0x{amount:016x}","asset_id":"0x{owner}"}}"#,
+                owner = "00".repeat(32)
+            )
+        };
+        let valid = format!("0x{}", "11".repeat(32));
+        let malformed = format!(
+            r#"{{"coins":[{},{},{}],"height":"0x7"}}"#,
+            coin(&valid, 1),
+            coin(&valid, 1),
+            coin("0x11", 1)
+        );
+        let rejected = format!(
+            r#"{{"coins":[{},{}],"height":"0x7"}}"#,
+            coin(&valid, 1),
+            coin(&valid, 0)
+        );
+
+        // When
+        let malformed = streamed(&malformed, 2);
+        let rejected = streamed(&rejected, 2);
+
+        // Then
+        let malformed = format!("{:#}", malformed.err().unwrap());
+        assert!(malformed.contains("coin #2 is malformed"), "{malformed}");
+        assert_eq!(
+            format!("{:#}", rejected.err().unwrap()),
+            "The coin is empty"
+        );
+    }
+
     fn test_config_contract(
         state: bool,
         balances: bool,
@@ -1,15 +1,40 @@
 use crate::serialization::HexNumber;
 
-use fuel_core_storage::Result as StorageResult;
+use fuel_core_storage::{
+    Error as StorageError,
+    Result as StorageResult,
+};
 use fuel_core_types::fuel_types::BlockHeight;
 
+use core::{
+    cell::RefCell,
+    fmt,
+    marker::PhantomData,
+};
 use serde::{
+    de::{
+        DeserializeOwned,
+        DeserializeSeed,
+        Error as _,
+        IgnoredAny,
+        MapAccess,
+        SeqAccess,
+        Visitor,
+    },
+    ser::{
+        Error as _,
+        SerializeSeq,
+        SerializeStruct,
+    },
     Deserialize,
+    Deserializer,
     Serialize,
+    Serializer,
 };
 use serde_with::{
     serde_as,
     skip_serializing_none,
+    As,
 };
 
 use super::{
@@ -18,7 +43,6 @@ use super::{
     message::MessageConfig,
 };
 
-// TODO: do streaming deserialization to handle large state configs
 #[serde_as]
 #[skip_serializing_none]
 #[derive(Default, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
@@ -47,6 +71,413 @@ impl StateConfig {
             height: Some(db.get_block_height()?),
         })
     }
+
+    /// Deserializes the state like the [`StateConfig`], but passes entries to the `sink`
+    /// by chunks of up to `chunk_size` entries as soon as they are deserialized, so the state
+    /// doesn't have to fit into the memory. Returns the height of the state.
+    ///
+    /// The error of the malformed entry names the index of the entry. The error of the `sink`
+    /// stops the deserialization and is returned as is.
+    pub fn deserialize_streamed<'de, D, S>(
+        deserializer: D,
+        chunk_size: usize,
+        sink: &mut S,
+    ) -> anyhow::Result<Option<BlockHeight>>
+    where
+        D: Deserializer<'de>,
+        S: StateEntriesSink,
+    {
+        let mut state = StreamedState {
+            chunk_size: chunk_size.max(1),
+            sink,
+            error: None,
+        };
+        let result = deserializer.deserialize_struct(
+            "StateConfig",
+            &["coins", "contracts", "messages", "height"],
+            StateVisitor { state: &mut state },
+        );
+        if let Some(err) = state.error {
+            return Err(err)
+        }
+        result.map_err(|err| anyhow::anyhow!("The state is malformed: {err}"))
+    }
+}
+
+/// The receiver of entries of the [`StateConfig`] deserialized by the
+/// [`StateConfig::deserialize_streamed`]. Chunks follow the order of entries in the state.
+pub trait StateEntriesSink {
+    /// Receives the chunk of coins that starts with the coin at the `first_index`.
+    fn coins(&mut self, first_index: usize, coins: Vec<CoinConfig>)
+        -> anyhow::Result<()>;
+
+    /// Receives the chunk of contracts that starts with the contract at the `first_index`.
+    fn contracts(
+        &mut self,
+        first_index: usize,
+        contracts: Vec<ContractConfig>,
+    ) -> anyhow::Result<()>;
+
+    /// Receives the chunk of messages that starts with the message at the `first_index`.
+    fn messages(
+        &mut self,
+        first_index: usize,
+        messages: Vec<MessageConfig>,
+    ) -> anyhow::Result<()>;
+}
+
+/// The entry of the [`StateConfig`] that is passed to the [`StateEntriesSink`].
+trait StreamedEntry: DeserializeOwned {
+    const NAME: &'static str;
+
+    fn pass<S>(sink: &mut S, first_index: usize, chunk: Vec<Self>) -> anyhow::Result<()>
+    where
+        S: StateEntriesSink;
+}
+
+impl StreamedEntry for CoinConfig {
+    const NAME: &'static str = "coin";
+
+    fn pass<S>(sink: &mut S, first_index: usize, chunk: Vec<Self>) -> anyhow::Result<()>
+    where
+        S: StateEntriesSink,
+    {
+        sink.coins(first_index, chunk)
+    }
+}
+
+impl StreamedEntry for ContractConfig {
+    const NAME: &'static str = "contract";
+
+    fn pass<S>(sink: &mut S, first_index: usize, chunk: Vec<Self>) -> anyhow::Result<()>
+    where
+        S: StateEntriesSink,
+    {
+        sink.contracts(first_index, chunk)
+    }
+}
+
+impl StreamedEntry for MessageConfig {
+    const NAME: &'static str = "message";
+
+    fn pass<S>(sink: &mut S, first_index: usize, chunk: Vec<Self>) -> anyhow::Result<()>
+    where
+        S: StateEntriesSink,
+    {
+        sink.messages(first_index, chunk)
+    }
+}
+
+/// The state of the streamed deserialization.
+struct StreamedState<'a, S> {
+    chunk_size: usize,
+    sink: &'a mut S,
+    /// The error of the sink that stopped the deserialization.
+    error: Option<anyhow::Error>,
+}
+
+impl<'a, S> StreamedState<'a, S>
+where
+    S: StateEntriesSink,
+{
+    fn pass<T, E>(&mut self, first_index: usize, chunk: Vec<T>) -> Result<(), E>
+    where
+        T: StreamedEntry,
+        E: serde::de::Error,
+    {
+        if chunk.is_empty() {
+            return Ok(())
+        }
+        T::pass(self.sink, first_index, chunk).map_err(|err| {
+            let message = format!("{err:#}");
+            self.error = Some(err);
+            E::custom(message)
+        })
+    }
+}
+
+/// The fields of the [`StateConfig`].
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum Field {
+    Coins,
+    Contracts,
+    Messages,
+    Height,
+    #[serde(other)]
+    Unknown,
+}
+
+/// The height deserialized like the height of the [`StateConfig`].
+#[serde_as]
+#[derive(Deserialize)]
+#[serde(transparent)]
+struct OptionalHeight(#[serde_as(as = "Option<HexNumber>")] Option<BlockHeight>);
+
+struct StateVisitor<'a, 'b, S> {
+    state: &'a mut StreamedState<'b, S>,
+}
+
+impl<'a, 'b, 'de, S> Visitor<'de> for StateVisitor<'a, 'b, S>
+where
+    S: StateEntriesSink,
+{
+    type Value = Option<BlockHeight>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("the state config")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut seen = [false; 4];
+        let mut height = None;
+        while let Some(field) = map.next_key::<Field>()? {
+            let (index, name) = match field {
+                Field::Coins => (0, "coins"),
+                Field::Contracts => (1, "contracts"),
+                Field::Messages => (2, "messages"),
+                Field::Height => (3, "height"),
+                Field::Unknown => {
+                    map.next_value::<IgnoredAny>()?;
+                    continue
+                }
+            };
+            if core::mem::replace(&mut seen[index], true) {
+                return Err(A::Error::duplicate_field(name))
+            }
+            match field {
+                Field::Coins => map.next_value_seed(
+                    EntriesSeed::<_, CoinConfig>::new(&mut *self.state),
+                )?,
+                Field::Contracts => {
+                    map.next_value_seed(EntriesSeed::<_, ContractConfig>::new(
+                        &mut *self.state,
+                    ))?
+                }
+                Field::Messages => map.next_value_seed(
+                    EntriesSeed::<_, MessageConfig>::new(&mut *self.state),
+                )?,
+                Field::Height => height = map.next_value::<OptionalHeight>()?.0,
+                Field::Unknown => {}
+            }
+        }
+        Ok(height)
+    }
+}
+
+/// The optional sequence of entries passed to the sink by chunks.
+struct EntriesSeed<'a, 'b, S, T> {
+    state: &'a mut StreamedState<'b, S>,
+    _entry: PhantomData<T>,
+}
+
+impl<'a, 'b, S, T> EntriesSeed<'a, 'b, S, T> {
+    fn new(state: &'a mut StreamedState<'b, S>) -> Self {
+        Self {
+            state,
+            _entry: PhantomData,
+        }
+    }
+}
+
+impl<'a, 'b, 'de, S, T> DeserializeSeed<'de> for EntriesSeed<'a, 'b, S, T>
+where
+    S: StateEntriesSink,
+    T: StreamedEntry,
+{
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_option(self)
+    }
+}
+
+impl<'a, 'b, 'de, S, T> Visitor<'de> for EntriesSeed<'a, 'b, S, T>
+where
+    S: StateEntriesSink,
+    T: StreamedEntry,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "the sequence of {}s", T::NAME)
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(())
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let chunk_size = self.state.chunk_size;
+        let mut first_index = 0usize;
+        let mut chunk = Vec::with_capacity(chunk_size);
+        loop {
+            let index = first_index.saturating_add(chunk.len());
+            let entry = seq.next_element::<T>().map_err(|err| {
+                A::Error::custom(format!("the {} #{index} is malformed: {err}", T::NAME))
+            })?;
+            let Some(entry) = entry else { break };
+            chunk.push(entry);
+            if chunk.len() == chunk_size {
+                let chunk =
+                    core::mem::replace(&mut chunk, Vec::with_capacity(chunk_size));
+                self.state.pass(first_index, chunk)?;
+                first_index = index.saturating_add(1);
+            }
+        }
+        self.state.pass(first_index, chunk)
+    }
+}
+
+/// The state that is serialized like the [`StateConfig`], but entries are serialized from
+/// iterators one by one, so the state doesn't have to fit into the memory. The iterators
+/// are consumed by the serialization, so the state can be serialized only once.
+pub struct StateConfigStream<Coins, Contracts, Messages> {
+    coins: RefCell<Option<Coins>>,
+    contracts: RefCell<Option<Contracts>>,
+    messages: RefCell<Option<Messages>>,
+    height: BlockHeight,
+    /// The error of the storage that stopped the serialization.
+    error: RefCell<Option<StorageError>>,
+}
+
+impl<Coins, Contracts, Messages> StateConfigStream<Coins, Contracts, Messages>
+where
+    Coins: Iterator<Item = StorageResult<CoinConfig>>,
+    Contracts: Iterator<Item = StorageResult<ContractConfig>>,
+    Messages: Iterator<Item = StorageResult<MessageConfig>>,
+{
+    /// Creates the state of the `height` from iterators over its entries.
+    pub fn new(
+        coins: Coins,
+        contracts: Contracts,
+        messages: Messages,
+        height: BlockHeight,
+    ) -> Self {
+        Self {
+            coins: RefCell::new(Some(coins)),
+            contracts: RefCell::new(Some(contracts)),
+            messages: RefCell::new(Some(messages)),
+            height,
+            error: RefCell::new(None),
+        }
+    }
+
+    /// Returns the error of the storage if it stopped the serialization.
+    pub fn take_error(&self) -> Option<StorageError> {
+        self.error.borrow_mut().take()
+    }
+}
+
+/// The sequence of entries serialized from the iterator.
+struct Entries<'a, I> {
+    iter: &'a RefCell<Option<I>>,
+    error: &'a RefCell<Option<StorageError>>,
+}
+
+impl<'a, I, T> Serialize for Entries<'a, I>
+where
+    I: Iterator<Item = StorageResult<T>>,
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let iter = self
+            .iter
+            .borrow_mut()
+            .take()
+            .ok_or_else(|| S::Error::custom("The state is already serialized"))?;
+        let mut seq = serializer.serialize_seq(None)?;
+        for entry in iter {
+            match entry {
+                Ok(entry) => seq.serialize_element(&entry)?,
+                Err(err) => {
+                    let message = format!("{err:?}");
+                    *self.error.borrow_mut() = Some(err);
+                    return Err(S::Error::custom(message))
+                }
+            }
+        }
+        seq.end()
+    }
+}
+
+/// The height serialized like the height of the [`StateConfig`].
+struct Height(BlockHeight);
+
+impl Serialize for Height {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        As::<HexNumber>::serialize(&self.0, serializer)
+    }
+}
+
+impl<Coins, Contracts, Messages> Serialize
+    for StateConfigStream<Coins, Contracts, Messages>
+where
+    Coins: Iterator<Item = StorageResult<CoinConfig>>,
+    Contracts: Iterator<Item = StorageResult<ContractConfig>>,
+    Messages: Iterator<Item = StorageResult<MessageConfig>>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("StateConfig", 4)?;
+        state.serialize_field(
+            "coins",
+            &Entries {
+                iter: &self.coins,
+                error: &self.error,
+            },
+        )?;
+        state.serialize_field(
+            "contracts",
+            &Entries {
+                iter: &self.contracts,
+                error: &self.error,
+            },
+        )?;
+        state.serialize_field(
+            "messages",
+            &Entries {
+                iter: &self.messages,
+                error: &self.error,
+            },
+        )?;
+        state.serialize_field("height", &Height(self.height))?;
+        state.end()
+    }
 }
 
 pub trait ChainConfigDb {
@@ -11,7 +11,11 @@
 #![deny(warnings)]
 #![deny(unused_variables)]
 
-use fuel_core_storage::Error as StorageError;
+use fuel_core_storage::{
+    kv_store::KeyFormat,
+    DecodeError,
+    Error as StorageError,
+};
 use fuel_core_types::services::executor::Error as ExecutorError;
 
 /// The error occurred during work with any of databases.
@@ -37,15 +41,246 @@ pub enum Error {
         /// the database version expected by this build of fuel-core
         expected: u32,
     },
+    /// The schema version of the data directory doesn't match the version of this build.
+    #[display(
+        fmt = "The database at {} has schema version {found}, but expected {expected}",
+        "path.display()"
+    )]
+    InvalidSchemaVersion {
+        /// the schema version of the data directory
+        found: u32,
+        /// the schema version expected by this build of fuel-core
+        expected: u32,
+        /// the path to the data directory
+        path: std::path::PathBuf,
+    },
+    /// There is no migration between the schema versions.
+    #[display(fmt = "There is no migration from the schema version {from} to {to}")]
+    MissingMigration {
+        /// the schema version to migrate from
+        from: u32,
+        /// the schema version to migrate to
+        to: u32,
+    },
+    /// The entry of the column can't be decoded.
+    #[display(
+        fmt = "Unable to decode the entry of the column `{column}` at the key {key:?}: {error}"
+    )]
+    DecodeEntry {
+        /// the name of the column
+        column: &'static str,
+        /// the raw key of the entry
+        key: Vec<u8>,
+        /// the decoding error
+        error: anyhow::Error,
+    },
+    /// The value of the column can't be decrypted, e.g. it is corrupted.
+    #[display(fmt = "Unable to decrypt the value of the column `{column}`")]
+    Decryption {
+        /// the name of the column
+        column: &'static str,
+    },
+    /// The checksum of the stored value doesn't match the value, e.g. it is corrupted
+    /// on the disk or truncated by the write.
+    #[display(
+        fmt = "The value of the column `{column}` at the key {key:?} is corrupted"
+    )]
+    Corrupted {
+        /// the name of the column
+        column: &'static str,
+        /// the raw key of the entry
+        key: Vec<u8>,
+    },
+    /// The database is encrypted with another key or isn't encrypted.
+    #[display(fmt = "The database is not encrypted with the provided key")]
+    WrongEncryptionKey,
+    /// The database is opened in read-only mode and can't be modified.
+    #[display(fmt = "The database is opened in read-only mode")]
+    ReadOnly,
+    /// The write would grow the in-memory database beyond its capacity.
+    #[display(
+        fmt = "The write requires {required} more bytes, but {usage} of {capacity} bytes are in use"
+    )]
+    OutOfCapacity {
+        /// the capacity of the database in bytes
+        capacity: usize,
+        /// the number of bytes in use before the write
+        usage: usize,
+        /// the number of bytes required by the write
+        required: usize,
+    },
+    /// The write would exceed the limit of changes staged by the transaction.
+    #[display(
+        fmt = "The write requires {attempted} of the {kind}, but the transaction is limited to {limit}"
+    )]
+    LimitExceeded {
+        /// the exceeded limit
+        kind: LimitKind,
+        /// the value of the limit
+        limit: usize,
+        /// the value required by the write
+        attempted: usize,
+    },
+    /// The transaction read the entry that another commit changed before it committed.
+    #[display(
+        fmt = "The entry of the column `{column}` at the key {key:?} was changed by a concurrent commit"
+    )]
+    Conflict {
+        /// the name of the column
+        column: &'static str,
+        /// the raw key of the entry
+        key: Vec<u8>,
+    },
+    /// The transaction failed with the retryable error on each allowed attempt.
+    #[display(fmt = "The transaction failed after {attempts} attempts: {error}")]
+    RetriesExhausted {
+        /// the number of attempts made
+        attempts: usize,
+        /// the error of the last attempt
+        error: Box<Error>,
+    },
+    /// The database is in the bulk import mode, and only the bulk import can write to it.
+    #[display(fmt = "The database is in the bulk import mode")]
+    BulkImportInProgress,
+    /// The operation is not supported by the database.
+    #[display(fmt = "The operation `{_0}` is not supported by the database")]
+    Unsupported(&'static str),
+    /// The data at the height was removed by the pruning.
+    #[display(
+        fmt = "The data at the height {height} is pruned, the earliest available height is {horizon}"
+    )]
+    Pruned {
+        /// the requested height
+        height: u32,
+        /// the earliest height that is not pruned
+        horizon: u32,
+    },
+
+    /// The key doesn't have the format of the column, e.g. the truncated hash.
+    #[display(
+        fmt = "The key of the column `{column}` has {got} bytes, but expected {expected}"
+    )]
+    InvalidKey {
+        /// the name of the column
+        column: &'static str,
+        /// the format of keys of the column
+        expected: KeyFormat,
+        /// the size of the key in bytes
+        got: usize,
+    },
+
+    /// The database is opened for writes by another process or instance of the database.
+    #[display(
+        fmt = "The database at {} is in use by PID {pid} since the unix time {since}",
+        "path.display()"
+    )]
+    Locked {
+        /// the path to the data directory
+        path: std::path::PathBuf,
+        /// the ID of the process that holds the lock
+        pid: u32,
+        /// the time of the acquisition of the lock in seconds since the unix epoch
+        since: u64,
+    },
+
+    /// The key of the sorted ingest isn't greater than the previous key.
+    #[display(
+        fmt = "The key {key:?} of the column `{column}` is out of order for the sorted ingest"
+    )]
+    UnsortedIngest {
+        /// the name of the column
+        column: &'static str,
+        /// the out-of-order key
+        key: Vec<u8>,
+    },
+
+    /// The id of the column was used by another column before, e.g. by the retired one.
+    #[display(
+        fmt = "The id {id} of the column `{column}` was used by the column `{previous}`"
+    )]
+    ColumnIdReused {
+        /// the id of the column
+        id: u32,
+        /// the name of the column
+        column: &'static str,
+        /// the name of the column that used the id before
+        previous: String,
+    },
+    /// The retired column still has data, and dropping it requires the explicit flag.
+    #[display(fmt = "The retired column `{column}` with the id {id} is not empty")]
+    RetiredColumnNotEmpty {
+        /// the id of the column
+        id: u32,
+        /// the name of the column
+        column: &'static str,
+    },
+
+    /// The column is frozen, and writes to it are rejected until it is unfrozen.
+    #[display(fmt = "The column `{_0}` is frozen")]
+    ColumnFrozen(&'static str),
+    /// The multi-batch operation of the type was interrupted, and its intent
+    /// is still in the recovery journal.
+    #[display(fmt = "The operation `{_0}` was interrupted and must be recovered first")]
+    RecoveryPending(String),
+
+    /// The pagination cursor is malformed or doesn't match the request, e.g. it is
+    /// tampered, belongs to another column, or points outside of the requested prefix.
+    #[display(fmt = "The cursor is invalid: {_0}")]
+    InvalidCursor(&'static str),
+
+    /// The operation was cancelled, e.g. by the shutdown of the node.
+    #[display(fmt = "The operation is cancelled")]
+    Cancelled,
+
+    /// The database is shutting down, and new writes are rejected.
+    #[display(fmt = "The database is shutting down")]
+    ShuttingDown,
+
+    /// The database can't be closed, because other clones of its storage are still alive.
+    #[display(fmt = "The database is still referenced by {_0} other clones")]
+    OutstandingReferences(usize),
+
+    /// The I/O of the backend failed, e.g. the disk is full or the file is unavailable.
+    /// The operation may succeed after the cause is resolved.
+    #[display(fmt = "The I/O of the database failed: {_0}")]
+    Io(anyhow::Error),
+    /// The backend failed with the error that doesn't have a dedicated variant.
+    #[display(fmt = "The backend of the database failed: {_0}")]
+    Backend(String),
 
     /// Not related to database error.
     #[from]
     Other(anyhow::Error),
 }
 
-impl From<Error> for anyhow::Error {
-    fn from(error: Error) -> Self {
-        anyhow::Error::msg(error)
+/// The limit of changes staged by the transaction, see [`Error::LimitExceeded`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display)]
+pub enum LimitKind {
+    /// The number of staged keys, including removed keys.
+    #[display(fmt = "staged keys")]
+    Keys,
+    /// The total size of staged values in bytes.
+    #[display(fmt = "staged bytes")]
+    Bytes,
+    /// The size of the single value in bytes.
+    #[display(fmt = "value size")]
+    ValueSize,
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::DecodeEntry { error, .. } => Some(error.as_ref()),
+            Error::RetriesExhausted { error, .. } => Some(error.as_ref()),
+            Error::Io(error) | Error::Other(error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e.into())
     }
 }
 
@@ -55,6 +290,36 @@ impl From<Error> for StorageError {
     }
 }
 
+impl From<StorageError> for Error {
+    /// Unwraps the database error from the storage error, so it keeps its variant
+    /// after the round trip through the storage traits.
+    fn from(e: StorageError) -> Self {
+        match e {
+            StorageError::DatabaseError(error) => match error.downcast::<Error>() {
+                Ok(error) => *error,
+                Err(error) => Error::Backend(error.to_string()),
+            },
+            StorageError::Codec(error) => match error.downcast::<DecodeError>() {
+                Ok(DecodeError {
+                    column,
+                    key,
+                    source,
+                }) => Error::DecodeEntry {
+                    column,
+                    key,
+                    error: source,
+                },
+                Err(error) => Error::Other(error),
+            },
+            StorageError::Other(error) => match error.downcast::<Error>() {
+                Ok(error) => error,
+                Err(error) => Error::Other(error),
+            },
+            e => Error::Other(e.into()),
+        }
+    }
+}
+
 impl From<Error> for ExecutorError {
     fn from(e: Error) -> Self {
         ExecutorError::StorageError(anyhow::anyhow!(StorageError::from(e)))
@@ -0,0 +1,311 @@
+//! Benchmarks of the storage layer with synthetic workloads of
+//! [`fuel_core::state::workload`].
+//!
+//! Each workload runs against every backend enabled by features,
+//! and all data is generated from fixed seeds, so runs are comparable.
+
+use criterion::{
+    criterion_group,
+    criterion_main,
+    BatchSize,
+    BenchmarkId,
+    Criterion,
+    Throughput,
+};
+use fuel_core::{
+    database::database_description::on_chain::OnChain,
+    state::{
+        cursor::iter_from_cursor,
+        in_memory::{
+            memory_store::MemoryStore,
+            transaction::MemoryTransactionView,
+        },
+        workload::{
+            BlockShape,
+            WorkloadGenerator,
+        },
+        DataSource,
+        TransactableStorage,
+    },
+};
+use fuel_core_storage::{
+    column::Column,
+    iter::IterDirection,
+    kv_store::{
+        KeyValueStore,
+        WriteOperation,
+    },
+};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+const SEED: u64 = 0x5EED;
+const COINS_PER_BATCH: usize = 1_000;
+const CONTRACTS: usize = 10;
+const SLOTS_PER_CONTRACT: usize = 1_000;
+const STATE_ACCESSES: usize = 1_000;
+const TRANSACTIONS_PER_BATCH: usize = 100;
+const TRANSACTION_SIZES: [usize; 3] = [1024, 16 * 1024, 128 * 1024];
+const PAGE_SIZES: [usize; 3] = [10, 100, 1_000];
+const BLOCKS: usize = 10;
+
+/// The storage of the benchmark and the directory that keeps its files.
+struct Backend {
+    name: &'static str,
+    storage: DataSource<OnChain>,
+    _dir: Option<TempDir>,
+}
+
+impl Backend {
+    fn storage(&self) -> &dyn TransactableStorage<Column = Column> {
+        self.storage.as_ref()
+    }
+}
+
+/// Opens empty storages of all enabled backends.
+fn backends() -> Vec<Backend> {
+    // Only other backends mutate the list.
+    #[allow(unused_mut)]
+    let mut backends = vec![Backend {
+        name: "memory",
+        storage: Arc::new(MemoryStore::<OnChain>::default()).into(),
+        _dir: None,
+    }];
+    #[cfg(feature = "rocksdb")]
+    {
+        use fuel_core::state::rocks_db::RocksDb;
+
+        let dir = TempDir::new().expect("Failed to create the directory");
+        let db = RocksDb::<OnChain>::default_open(dir.path(), None)
+            .expect("Failed to open the database");
+        backends.push(Backend {
+            name: "rocksdb",
+            storage: Arc::new(db).into(),
+            _dir: Some(dir),
+        });
+    }
+    backends
+}
+
+fn insert(
+    storage: &dyn TransactableStorage<Column = Column>,
+    entries: Vec<(Vec<u8>, Column, WriteOperation)>,
+) {
+    storage
+        .batch_write(&mut entries.into_iter())
+        .expect("Failed to write the batch");
+}
+
+/// Inserts batches of coins: small keys and small values.
+fn coin_inserts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("coin inserts");
+    group.throughput(Throughput::Elements(COINS_PER_BATCH as u64));
+    for backend in backends() {
+        let mut generator = WorkloadGenerator::new(SEED);
+        group.bench_function(backend.name, |b| {
+            b.iter_batched(
+                || {
+                    generator
+                        .coins(COINS_PER_BATCH)
+                        .into_iter()
+                        .map(|(key, value)| {
+                            (key, Column::Coins, WriteOperation::Insert(value))
+                        })
+                        .collect()
+                },
+                |entries| insert(backend.storage(), entries),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// Reads and updates slots of the contract state with the skewed distribution.
+fn contract_state(c: &mut Criterion) {
+    let mut group = c.benchmark_group("contract state");
+    group.throughput(Throughput::Elements(STATE_ACCESSES as u64));
+    for backend in backends() {
+        let mut generator = WorkloadGenerator::new(SEED);
+        let state = generator.contract_state(CONTRACTS, SLOTS_PER_CONTRACT);
+        insert(
+            backend.storage(),
+            state
+                .entries
+                .iter()
+                .map(|(key, value)| {
+                    (
+                        key.clone(),
+                        Column::ContractsState,
+                        WriteOperation::Insert(value.clone()),
+                    )
+                })
+                .collect(),
+        );
+
+        group.bench_function(BenchmarkId::new("get", backend.name), |b| {
+            b.iter_batched(
+                || generator.skewed_keys(&state, STATE_ACCESSES),
+                |keys| {
+                    for key in keys {
+                        backend
+                            .storage()
+                            .get(&key, Column::ContractsState)
+                            .expect("Failed to read the slot");
+                    }
+                },
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("put", backend.name), |b| {
+            b.iter_batched(
+                || generator.skewed_keys(&state, STATE_ACCESSES),
+                |keys| {
+                    for key in keys {
+                        backend
+                            .storage()
+                            .put(&key, Column::ContractsState, Arc::new(vec![1; 32]))
+                            .expect("Failed to write the slot");
+                    }
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// Appends batches of transactions: unique keys and large values.
+fn transaction_appends(c: &mut Criterion) {
+    let mut group = c.benchmark_group("transaction appends");
+    for size in TRANSACTION_SIZES {
+        group.throughput(Throughput::Bytes((TRANSACTIONS_PER_BATCH * size) as u64));
+        for backend in backends() {
+            let mut generator = WorkloadGenerator::new(SEED);
+            group.bench_function(BenchmarkId::new(backend.name, size), |b| {
+                b.iter_batched(
+                    || {
+                        generator
+                            .transactions(TRANSACTIONS_PER_BATCH, size)
+                            .into_iter()
+                            .map(|(key, value)| {
+                                (key, Column::Transactions, WriteOperation::Insert(value))
+                            })
+                            .collect()
+                    },
+                    |entries| insert(backend.storage(), entries),
+                    BatchSize::SmallInput,
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Reads all slots of a contract page by page with cursors.
+fn paginated_prefix_scans(c: &mut Criterion) {
+    let mut group = c.benchmark_group("paginated prefix scans");
+    group.throughput(Throughput::Elements(SLOTS_PER_CONTRACT as u64));
+    for backend in backends() {
+        let mut generator = WorkloadGenerator::new(SEED);
+        let state = generator.contract_state(CONTRACTS, SLOTS_PER_CONTRACT);
+        insert(
+            backend.storage(),
+            state
+                .entries
+                .iter()
+                .map(|(key, value)| {
+                    (
+                        key.clone(),
+                        Column::ContractsState,
+                        WriteOperation::Insert(value.clone()),
+                    )
+                })
+                .collect(),
+        );
+
+        for page_size in PAGE_SIZES {
+            let mut contracts = state.contracts.iter().cycle();
+            group.bench_function(BenchmarkId::new(backend.name, page_size), |b| {
+                b.iter(|| {
+                    let contract = contracts.next().expect("The cycle is infinite");
+                    let mut cursor = None;
+                    let mut entries = 0;
+                    loop {
+                        let page = iter_from_cursor(
+                            backend.storage(),
+                            Column::ContractsState,
+                            Some(contract.as_slice()),
+                            IterDirection::Forward,
+                            cursor.as_ref(),
+                            page_size,
+                        )
+                        .expect("Failed to read the page");
+                        entries += page.entries.len();
+                        cursor = page.next;
+                        if cursor.is_none() {
+                            break
+                        }
+                    }
+                    assert_eq!(entries, SLOTS_PER_CONTRACT);
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Commits changes of blocks through the transaction view.
+fn block_commits(c: &mut Criterion) {
+    let mut group = c.benchmark_group("block commits");
+    let shape = BlockShape::default();
+    for backend in backends() {
+        let mut generator = WorkloadGenerator::new(SEED);
+        let state = generator.contract_state(CONTRACTS, SLOTS_PER_CONTRACT);
+        let mut unspent = vec![];
+        // The first blocks create coins that the measured blocks spend.
+        for _ in 0..BLOCKS {
+            insert(
+                backend.storage(),
+                generator.block(&mut unspent, &state, shape),
+            );
+        }
+
+        group.bench_function(backend.name, |b| {
+            b.iter_batched(
+                || generator.block(&mut unspent, &state, shape),
+                |block| {
+                    let view = MemoryTransactionView::new(backend.storage.clone());
+                    for (key, column, operation) in block {
+                        match operation {
+                            WriteOperation::Insert(value) => {
+                                view.put(&key, column, value)
+                                    .expect("Failed to write the entry");
+                            }
+                            WriteOperation::Remove => {
+                                view.delete(&key, column)
+                                    .expect("Failed to delete the entry");
+                            }
+                            WriteOperation::Merge(_) => {
+                                unreachable!("Blocks don't merge")
+                            }
+                        }
+                    }
+                    view.commit().expect("Failed to commit the block");
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    coin_inserts,
+    contract_state,
+    transaction_appends,
+    paginated_prefix_scans,
+    block_commits
+);
+criterion_main!(benches);
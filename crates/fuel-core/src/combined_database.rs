@@ -1,11 +1,31 @@
-use crate::database::{
-    database_description::{
-        off_chain::OffChain,
-        on_chain::OnChain,
-        relayer::Relayer,
+#[cfg(any(feature = "rocksdb", feature = "redb"))]
+use crate::state::column_config::ColumnsConfig;
+use crate::{
+    database::{
+        consistency::{
+            consistency_check,
+            ConsistencyReport,
+            ConsistencyScope,
+        },
+        database_description::{
+            off_chain::OffChain,
+            on_chain::OnChain,
+            relayer::Relayer,
+        },
+        index_rebuild::{
+            IndexColumn,
+            RebuildStats,
+        },
+        integrity::{
+            owned_coins_check,
+            verify,
+            IntegrityOptions,
+            IntegrityReport,
+        },
+        Database,
+        Result as DatabaseResult,
     },
-    Database,
-    Result as DatabaseResult,
+    state::slow_log::SlowLogConfig,
 };
 use fuel_core_storage::Result as StorageResult;
 use fuel_core_types::{
@@ -35,11 +55,42 @@ impl CombinedDatabase {
     }
 
     #[cfg(feature = "rocksdb")]
-    pub fn open(path: &std::path::Path, capacity: usize) -> DatabaseResult<Self> {
+    pub fn open(
+        path: &std::path::Path,
+        capacity: usize,
+        columns: &ColumnsConfig,
+    ) -> DatabaseResult<Self> {
         // TODO: Use different cache sizes for different databases
-        let on_chain = Database::open(path, capacity)?;
-        let off_chain = Database::open(path, capacity)?;
-        let relayer = Database::open(path, capacity)?;
+        let on_chain = Database::open_with_columns(path, capacity, columns)?;
+        let off_chain = Database::open_with_columns(path, capacity, columns)?;
+        let relayer = Database::open_with_columns(path, capacity, columns)?;
+        Ok(Self {
+            on_chain,
+            off_chain,
+            relayer,
+        })
+    }
+
+    /// Removes locks of the databases at the `path` left by the crashed process, see
+    /// [`Database::force_unlock`]. Returns `true` if any of the locks was removed.
+    #[cfg(feature = "rocksdb")]
+    pub fn force_unlock(path: &std::path::Path) -> DatabaseResult<bool> {
+        let on_chain = Database::<OnChain>::force_unlock(path)?;
+        let off_chain = Database::<OffChain>::force_unlock(path)?;
+        let relayer = Database::<Relayer>::force_unlock(path)?;
+        Ok(on_chain || off_chain || relayer)
+    }
+
+    /// Opens the databases in redb files at the `path`, see [`crate::state::redb_store`].
+    #[cfg(feature = "redb")]
+    pub fn open_redb(
+        path: &std::path::Path,
+        capacity: usize,
+        columns: &ColumnsConfig,
+    ) -> DatabaseResult<Self> {
+        let on_chain = Database::open_redb(path, capacity, columns)?;
+        let off_chain = Database::open_redb(path, capacity, columns)?;
+        let relayer = Database::open_redb(path, capacity, columns)?;
         Ok(Self {
             on_chain,
             off_chain,
@@ -55,6 +106,24 @@ impl CombinedDatabase {
         )
     }
 
+    /// Returns the databases that record the metrics of storage operations.
+    pub fn with_metrics(self) -> Self {
+        Self::new(
+            self.on_chain.with_metrics(),
+            self.off_chain.with_metrics(),
+            self.relayer.with_metrics(),
+        )
+    }
+
+    /// Returns the databases that log storage operations exceeding thresholds of the `config`.
+    pub fn with_slow_log(self, config: SlowLogConfig) -> Self {
+        Self::new(
+            self.on_chain.with_slow_log(config),
+            self.off_chain.with_slow_log(config),
+            self.relayer.with_slow_log(config),
+        )
+    }
+
     pub fn init(
         &mut self,
         block_height: &BlockHeight,
@@ -88,10 +157,74 @@ impl CombinedDatabase {
         &self.relayer
     }
 
+    /// Verifies the integrity of the on-chain database and the references
+    /// of the off-chain database to it. See [`Database::verify_integrity`].
+    pub fn verify_integrity(
+        &self,
+        options: IntegrityOptions,
+    ) -> StorageResult<IntegrityReport> {
+        let mut checks = self.on_chain.integrity_checks();
+        checks.push(owned_coins_check(&self.off_chain, &self.on_chain));
+        verify(&checks, options)
+    }
+
+    /// Verifies the references between columns of the on-chain and off-chain databases,
+    /// see [`ConsistencyScope`]. The check reads the snapshots of the databases,
+    /// so commits that land while it is running don't affect the report.
+    pub fn consistency_check(
+        &self,
+        scope: ConsistencyScope,
+    ) -> StorageResult<ConsistencyReport> {
+        let on_chain = self.on_chain.snapshot()?;
+        let off_chain = self.off_chain.snapshot()?;
+        consistency_check(&on_chain, &off_chain, scope)
+    }
+
+    /// Clears the `index` of the off-chain database and recomputes it from the primary
+    /// data of the on-chain database, e.g. after the index drifted from the chain.
+    /// Returns the number of written entries of the index.
+    ///
+    /// The node must be stopped during the rebuild: the rebuild doesn't read
+    /// the snapshot, and the off-chain worker would update the index while it is cleared.
+    /// The receiver is `&mut`, so the rebuild can't be started through the database
+    /// shared with running services. The rebuild writes its progress with each batch,
+    /// so the rebuild interrupted by the crash or the error resumes on the next call.
+    /// The rebuild of the other index fails until the interrupted one is finished.
+    pub fn rebuild_index(&mut self, index: IndexColumn) -> StorageResult<RebuildStats> {
+        self.off_chain.rebuild_index(&self.on_chain, index)
+    }
+
     pub fn flush(self) -> DatabaseResult<()> {
         self.on_chain.flush()?;
         self.off_chain.flush()?;
         self.relayer.flush()?;
         Ok(())
     }
+
+    /// Starts the shutdown of all databases, see [`Database::begin_shutdown`].
+    pub fn begin_shutdown(&self) {
+        self.on_chain.begin_shutdown();
+        self.off_chain.begin_shutdown();
+        self.relayer.begin_shutdown();
+    }
+
+    /// Completes the shutdown of all databases, see [`Database::finish_shutdown`].
+    pub fn finish_shutdown(&self) -> DatabaseResult<()> {
+        self.on_chain.finish_shutdown()?;
+        self.off_chain.finish_shutdown()?;
+        self.relayer.finish_shutdown()?;
+        Ok(())
+    }
+
+    /// Closes all databases, see [`Database::close`]. Each database is closed
+    /// even if the previous one fails, and the first error is returned.
+    pub fn close(self) -> DatabaseResult<()> {
+        let on_chain = self.on_chain.close();
+        let off_chain = self.off_chain.close();
+        let relayer = self.relayer.close();
+        on_chain?;
+        off_chain?;
+        relayer?;
+        Ok(())
+    }
 }
@@ -9,8 +9,47 @@ use crate::{
         transaction::DatabaseTransaction,
     },
     state::{
-        in_memory::memory_store::MemoryStore,
+        buffered::{
+            BufferedStorage,
+            WriteBufferConfig,
+        },
+        cached::CachedStorage,
+        change_log::{
+            ChangeLogConfig,
+            ChangeLogStorage,
+        },
+        checksummed::ChecksummedStorage,
+        chunked_commit,
+        cursor::{
+            self,
+            Cursor,
+            Page,
+        },
+        guarded::{
+            FreezeGuard,
+            GuardedStorage,
+        },
+        in_memory::{
+            memory_store::MemoryStore,
+            transaction::MemoryTransactionView,
+        },
+        instrumented::InstrumentedStorage,
+        notifying::{
+            NotifyingStorage,
+            StorageEvent,
+        },
+        read_context,
+        slow_log::{
+            SlowLogConfig,
+            SlowLogStorage,
+        },
+        strict_keys::StrictKeysStorage,
+        ttl::TtlStorage,
+        ColumnStats,
         DataSource,
+        EngineStats,
+        IngestStats,
+        ReadView,
     },
 };
 use fuel_core_chain_config::{
@@ -26,10 +65,16 @@ use fuel_core_storage::{
         Encode,
         Encoder,
     },
-    iter::IterDirection,
+    iter::{
+        IterConfig,
+        IterDirection,
+        IterOptions,
+    },
     kv_store::{
         BatchOperations,
         KeyValueStore,
+        UpdateFn,
+        UpdateOutcome,
         Value,
         WriteOperation,
     },
@@ -57,8 +102,12 @@ use std::{
         Formatter,
     },
     marker::Send,
+    ops::Bound,
+    path::Path,
     sync::Arc,
+    time::Duration,
 };
+use tokio::sync::broadcast;
 
 pub use fuel_core_database::Error;
 pub type Result<T> = core::result::Result<T, Error>;
@@ -66,27 +115,54 @@ pub type Result<T> = core::result::Result<T, Error>;
 type DatabaseResult<T> = Result<T>;
 
 // TODO: Extract `Database` and all belongs into `fuel-core-database`.
+use crate::state::column_config::ColumnsConfig;
+#[cfg(feature = "compression")]
+use crate::state::compressed::CompressedStorage;
+#[cfg(feature = "encryption")]
+use crate::state::encrypted::EncryptedStorage;
 #[cfg(feature = "rocksdb")]
-use crate::state::rocks_db::RocksDb;
+use crate::state::open_mode::OpenMode;
+#[cfg(feature = "redb")]
+use crate::state::redb_store::RedbStore;
 #[cfg(feature = "rocksdb")]
-use std::path::Path;
-#[cfg(feature = "rocksdb")]
-use tempfile::TempDir;
+use crate::state::rocks_db::RocksDb;
+#[cfg(any(feature = "rocksdb", feature = "redb"))]
+use temp_database::TempDatabase;
 
 // Storages implementation
+pub mod async_access;
 pub mod balances;
 pub mod block;
 pub mod coin;
+pub mod column_lifecycle;
+pub mod composite_key;
+pub mod consistency;
 pub mod contracts;
 pub mod database_description;
+pub mod dir_lock;
+pub mod dump;
+pub mod index_rebuild;
+pub mod inspect;
+pub mod integrity;
+pub mod maintenance;
 pub mod message;
 pub mod metadata;
+pub mod migration;
+pub mod pruning;
+pub mod recovery;
+pub mod rollback;
+pub mod schema_version;
 pub mod sealed_block;
 pub mod state;
+pub mod state_export;
 pub mod statistic;
 pub mod storage;
+pub mod storage_map;
+#[cfg(any(feature = "rocksdb", feature = "redb"))]
+pub mod temp_database;
 pub mod transaction;
 pub mod transactions;
+pub mod write_pressure;
 
 #[derive(Clone, Debug)]
 pub struct Database<Description = OnChain>
@@ -148,13 +224,193 @@ where
 
     #[cfg(feature = "rocksdb")]
     pub fn open(path: &Path, capacity: impl Into<Option<usize>>) -> DatabaseResult<Self> {
+        Self::open_with_columns(path, capacity, &ColumnsConfig::default())
+    }
+
+    /// Opens the database with the `columns` overrides of the RocksDB configuration.
+    ///
+    /// The database directory is locked until the database and all of its clones are
+    /// dropped, so another read-write open fails with the [`Error::Locked`], see
+    /// [`dir_lock::DirLock`]. Read-only opens don't take the lock.
+    #[cfg(feature = "rocksdb")]
+    pub fn open_with_columns(
+        path: &Path,
+        capacity: impl Into<Option<usize>>,
+        columns: &ColumnsConfig,
+    ) -> DatabaseResult<Self> {
+        use anyhow::Context;
+        let dir = path.join(Description::name());
+        // The missing database isn't created in the `MustExist` mode, so there is nothing to lock.
+        let lock = (columns.open_mode().creates_missing() || dir.exists())
+            .then(|| dir_lock::DirLock::acquire(&dir))
+            .transpose()?;
+        let db = RocksDb::<Description>::open_with_config(
+            path,
+            enum_iterator::all::<Description::Column>().collect(),
+            capacity.into(),
+            columns,
+        )
+        .map_err(Into::<anyhow::Error>::into).context("Failed to open rocksdb, you may need to wipe a pre-existing incompatible db `rm -rf ~/.fuel/db`")?;
+        schema_version::ensure_schema_version::<Description>(
+            &db,
+            &path.join(Description::name()),
+        )?;
+        column_lifecycle::ensure_column_ids::<Description>(&db)?;
+        let mut database = Database {
+            data: StructuredStorage::new(Arc::new(db).into()),
+            _drop: Arc::new(DropResources::from(move || drop(lock))),
+        };
+        // The compaction filter of the RocksDB removes expired entries, so they aren't purged.
+        if !columns.ttl_columns::<Description>().is_empty() {
+            database = database.with_ttl(columns, None)?;
+        }
+        // Checksums are above the TTL, so the compaction filter finds the time of the write
+        // at the end of the stored value.
+        database = database.with_checksums_from(columns)?;
+        // The compression is above the TTL, so the compaction filter sees the time of the write.
+        if !columns.compressed_columns::<Description>().is_empty() {
+            #[cfg(feature = "compression")]
+            {
+                database = database.with_compression(columns)?;
+            }
+            #[cfg(not(feature = "compression"))]
+            return Err(Error::Unsupported("compression"))
+        }
+        if columns.strict_keys_enabled() {
+            database = database.with_strict_keys();
+        }
+        if let Some(chunks) = database.interrupted_chunked_commit()? {
+            tracing::error!(
+                "The chunked commit into the `{}` database was interrupted after {} chunks, \
+                the database contains the partially applied commit",
+                Description::name(),
+                chunks
+            );
+        }
+        for pending in database.pending_recoveries()? {
+            tracing::warn!(
+                "The `{}` operation on the `{}` database was interrupted, \
+                it must be recovered before the operation can run again",
+                pending.operation,
+                Description::name()
+            );
+        }
+
+        Ok(database)
+    }
+
+    #[cfg(feature = "rocksdb")]
+    pub fn open_read_only(
+        path: &Path,
+        capacity: impl Into<Option<usize>>,
+    ) -> DatabaseResult<Self> {
         use anyhow::Context;
-        let db = RocksDb::<Description>::default_open(path, capacity.into()).map_err(Into::<anyhow::Error>::into).context("Failed to open rocksdb, you may need to wipe a pre-existing incompatible db `rm -rf ~/.fuel/db`")?;
+        let db = RocksDb::<Description>::open_read_only(
+            path,
+            enum_iterator::all::<Description::Column>().collect::<Vec<_>>(),
+            capacity.into(),
+        )
+        .map_err(Into::<anyhow::Error>::into)
+        .context("Failed to open rocksdb in read-only mode")?;
+        schema_version::check_schema_version::<Description>(
+            &db,
+            &path.join(Description::name()),
+        )?;
+        // The read-only database can't record the mode of checksums,
+        // so checksums are verified only if the mode is already recorded.
+        let has_checksums =
+            metadata::get_metadata::<metadata::ValueChecksums, Description>(&db)
+                .map_err(Error::from)?
+                .is_some();
+        let database = Database {
+            data: StructuredStorage::new(Arc::new(db).into()),
+            _drop: Default::default(),
+        };
+        if has_checksums {
+            return database.with_checksums()
+        }
+
+        Ok(database)
+    }
+
+    /// Removes the lock of the database at the `path` left by the crashed process,
+    /// see [`dir_lock::DirLock::force_unlock`].
+    #[cfg(feature = "rocksdb")]
+    pub fn force_unlock(path: &Path) -> DatabaseResult<bool> {
+        dir_lock::DirLock::force_unlock(&path.join(Description::name()))
+    }
 
-        Ok(Database {
+    /// Opens the database from the checkpoint created by [`Self::checkpoint`].
+    #[cfg(feature = "rocksdb")]
+    pub fn open_checkpoint(
+        path: &Path,
+        capacity: impl Into<Option<usize>>,
+    ) -> DatabaseResult<Self> {
+        // `Self::open` creates a new database if it is missing.
+        let columns = ColumnsConfig::default().with_open_mode(OpenMode::MustExist);
+        Self::open_with_columns(path, capacity, &columns)
+    }
+
+    /// Restores the database at the `path` from the latest backup created by [`Self::backup_to`].
+    /// It refuses to overwrite a non-empty database unless `force` is set.
+    #[cfg(feature = "rocksdb")]
+    pub fn restore_from(
+        backup_dir: &Path,
+        path: &Path,
+        force: bool,
+    ) -> DatabaseResult<()> {
+        RocksDb::<Description>::restore_from(backup_dir, path, force)
+    }
+
+    /// Opens the database in the redb file at the `path`, see [`RedbStore`].
+    /// The `capacity` is the size of the page cache in bytes. The open mode,
+    /// the strict keys and checksums of values are taken from the `columns`,
+    /// while the tuning of columns applies only to the RocksDB.
+    #[cfg(feature = "redb")]
+    pub fn open_redb(
+        path: &Path,
+        capacity: impl Into<Option<usize>>,
+        columns: &ColumnsConfig,
+    ) -> DatabaseResult<Self> {
+        use anyhow::Context;
+        let db = RedbStore::<Description>::open_with_mode(
+            path,
+            capacity.into(),
+            columns.open_mode(),
+        )
+        .map_err(Into::<anyhow::Error>::into)
+        .context("Failed to open redb")?;
+        schema_version::ensure_schema_version::<Description>(
+            &db,
+            &RedbStore::<Description>::file_path(path),
+        )?;
+        column_lifecycle::ensure_column_ids::<Description>(&db)?;
+        let mut database = Database {
             data: StructuredStorage::new(Arc::new(db).into()),
             _drop: Default::default(),
-        })
+        }
+        .with_checksums_from(columns)?;
+        if columns.strict_keys_enabled() {
+            database = database.with_strict_keys();
+        }
+        if let Some(chunks) = database.interrupted_chunked_commit()? {
+            tracing::error!(
+                "The chunked commit into the `{}` database was interrupted after {} chunks, \
+                the database contains the partially applied commit",
+                Description::name(),
+                chunks
+            );
+        }
+        for pending in database.pending_recoveries()? {
+            tracing::warn!(
+                "The `{}` operation on the `{}` database was interrupted, \
+                it must be recovered before the operation can run again",
+                pending.operation,
+                Description::name()
+            );
+        }
+
+        Ok(database)
     }
 
     pub fn in_memory() -> Self {
@@ -164,31 +420,500 @@ where
         }
     }
 
+    /// Returns the database that records the metrics of storage operations.
+    pub fn with_metrics(self) -> Self {
+        let storage = InstrumentedStorage::new(self.data.as_ref().clone());
+        Self {
+            data: StructuredStorage::new(Arc::new(storage).into()),
+            _drop: self._drop,
+        }
+    }
+
+    /// Returns the database that caches values of up to `capacity` bytes read
+    /// from the storage, see [`CachedStorage`].
+    pub fn with_cache(self, capacity: usize) -> Self {
+        let storage = CachedStorage::new(self.data.as_ref().clone(), capacity);
+        Self {
+            data: StructuredStorage::new(Arc::new(storage).into()),
+            _drop: self._drop,
+        }
+    }
+
+    /// Returns the database that buffers writes of columns of the `config`
+    /// and coalesces them into batches, see [`BufferedStorage`].
+    pub fn with_write_buffer(
+        self,
+        config: WriteBufferConfig<Description::Column>,
+    ) -> Self {
+        let storage = Arc::new(BufferedStorage::new(self.data.as_ref().clone(), &config));
+        if let Some(interval) = config.flush_interval {
+            BufferedStorage::spawn_flush(&storage, interval);
+        }
+        Self {
+            data: StructuredStorage::new(storage.into()),
+            _drop: self._drop,
+        }
+    }
+
+    /// Returns the database that logs storage operations exceeding thresholds of the `config`.
+    pub fn with_slow_log(self, config: SlowLogConfig) -> Self {
+        let storage = SlowLogStorage::new(self.data.as_ref().clone(), config);
+        Self {
+            data: StructuredStorage::new(Arc::new(storage).into()),
+            _drop: self._drop,
+        }
+    }
+
+    /// Returns the database that notifies subscribers about committed changes,
+    /// see [`Self::subscribe`]. Each subscriber buffers up to `capacity` events.
+    pub fn with_notifications(self, capacity: usize) -> Self {
+        let storage = NotifyingStorage::new(self.data.as_ref().clone(), capacity);
+        Self {
+            data: StructuredStorage::new(Arc::new(storage).into()),
+            _drop: self._drop,
+        }
+    }
+
+    /// Subscribes to committed changes of keys of the `column` with the `prefix`.
+    /// Only the database created by [`Self::with_notifications`] accepts subscriptions.
+    pub fn subscribe(
+        &self,
+        column: Description::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Description::Column>>> {
+        self.data.as_ref().subscribe(column, prefix)
+    }
+
+    /// Returns the database that records committed writes into the change log
+    /// of the `config`, see [`Self::replay_change_log`].
+    pub fn with_change_log(
+        self,
+        config: ChangeLogConfig<Description::Column>,
+    ) -> DatabaseResult<Self> {
+        let storage = ChangeLogStorage::new(self.data.as_ref().clone(), config)
+            .map_err(Error::from)?;
+        Ok(Self {
+            data: StructuredStorage::new(Arc::new(storage).into()),
+            _drop: self._drop,
+        })
+    }
+
+    /// Brings the `replica` up to date by applying the change log starting from
+    /// the entry `from_seq`. Returns the sequence number where the next replay starts.
+    /// Only the database created by [`Self::with_change_log`] has the change log.
+    pub fn replay_change_log(
+        &self,
+        from_seq: u64,
+        replica: &Self,
+    ) -> DatabaseResult<u64> {
+        self.data
+            .as_ref()
+            .replay_change_log(from_seq, replica.data.as_ref())
+    }
+
+    /// Removes entries of the change log before the `seq`, e.g. after all replicas
+    /// replayed them. The latest entry is always kept.
+    pub fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.data.as_ref().truncate_change_log(seq)
+    }
+
+    /// Returns the database that expires entries of columns with the TTL from the `config`.
+    /// Expired entries are removed every `purge_interval` if it is set.
+    pub fn with_ttl(
+        self,
+        config: &ColumnsConfig,
+        purge_interval: Option<Duration>,
+    ) -> DatabaseResult<Self> {
+        let storage = Arc::new(TtlStorage::new(self.data.as_ref().clone(), config)?);
+        if let Some(interval) = purge_interval {
+            TtlStorage::spawn_purge(&storage, interval);
+        }
+        Ok(Self {
+            data: StructuredStorage::new(storage.into()),
+            _drop: self._drop,
+        })
+    }
+
+    /// Returns the database that compresses values of columns with the compression
+    /// threshold from the `config`, see [`CompressedStorage`].
+    #[cfg(feature = "compression")]
+    pub fn with_compression(self, config: &ColumnsConfig) -> DatabaseResult<Self> {
+        let storage = CompressedStorage::new(self.data.as_ref().clone(), config)?;
+        Ok(Self {
+            data: StructuredStorage::new(Arc::new(storage).into()),
+            _drop: self._drop,
+        })
+    }
+
+    /// Returns the database that encrypts values with the `key`, see [`EncryptedStorage`].
+    /// Fails if the database is encrypted with another key or has unencrypted values.
+    #[cfg(feature = "encryption")]
+    pub fn with_encryption(self, key: &[u8; 32]) -> DatabaseResult<Self> {
+        let storage = EncryptedStorage::new(self.data.as_ref().clone(), key)?;
+        Ok(Self {
+            data: StructuredStorage::new(Arc::new(storage).into()),
+            _drop: self._drop,
+        })
+    }
+
+    /// Returns the database that stores values with checksums verified on reads,
+    /// see [`ChecksummedStorage`].
+    pub fn with_checksums(self) -> DatabaseResult<Self> {
+        let storage = ChecksummedStorage::new(self.data.as_ref().clone())?;
+        Ok(Self {
+            data: StructuredStorage::new(Arc::new(storage).into()),
+            _drop: self._drop,
+        })
+    }
+
+    /// Wraps the database with checksums if they are enabled by the `columns`,
+    /// otherwise fails if the database has values with checksums.
+    #[cfg(any(feature = "rocksdb", feature = "redb"))]
+    fn with_checksums_from(self, columns: &ColumnsConfig) -> DatabaseResult<Self> {
+        if columns.value_checksums_enabled() {
+            return self.with_checksums()
+        }
+        crate::state::checksummed::ensure_disabled::<Description>(self.data.as_ref())?;
+        Ok(self)
+    }
+
+    /// Returns the database that rejects writes of keys without the format of the column,
+    /// see [`StrictKeysStorage`].
+    pub fn with_strict_keys(self) -> Self {
+        let storage = StrictKeysStorage::new(self.data.as_ref().clone());
+        Self {
+            data: StructuredStorage::new(Arc::new(storage).into()),
+            _drop: self._drop,
+        }
+    }
+
+    /// Returns the database whose columns can be frozen against writes by [`Self::freeze`],
+    /// see [`GuardedStorage`]. It should be the last wrapper of the database.
+    pub fn with_column_guard(self) -> Self {
+        let storage = GuardedStorage::new(self.data.as_ref().clone());
+        Self {
+            data: StructuredStorage::new(Arc::new(storage).into()),
+            _drop: self._drop,
+        }
+    }
+
+    /// Rejects writes to the `columns` with the [`Error::ColumnFrozen`] until the returned
+    /// guard is dropped. Freezes compose, so the column stays frozen while any guard
+    /// freezes it. Only the database created by [`Self::with_column_guard`] can be frozen.
+    pub fn freeze(
+        &self,
+        columns: &[Description::Column],
+    ) -> DatabaseResult<FreezeGuard<Description>> {
+        FreezeGuard::new(self.data.as_ref().clone(), columns.to_vec())
+    }
+
+    #[cfg(feature = "redb")]
+    pub fn redb() -> Self {
+        Self::from_temp(TempDatabase::redb().unwrap())
+    }
+
     #[cfg(feature = "rocksdb")]
     pub fn rocksdb() -> Self {
-        let tmp_dir = TempDir::new().unwrap();
-        let db = RocksDb::<Description>::default_open(tmp_dir.path(), None).unwrap();
+        Self::from_temp(TempDatabase::rocksdb().unwrap())
+    }
+
+    /// Returns the database that removes the directory of the `temp` database
+    /// after the drop of the last clone, once the storage is closed.
+    #[cfg(any(feature = "rocksdb", feature = "redb"))]
+    fn from_temp(temp: TempDatabase<Description>) -> Self {
         Self {
-            data: StructuredStorage::new(Arc::new(db).into()),
-            _drop: Arc::new(
-                {
-                    move || {
-                        // cleanup temp dir
-                        drop(tmp_dir);
-                    }
-                }
-                .into(),
-            ),
+            data: StructuredStorage::new(temp.data_source().clone()),
+            _drop: Default::default(),
         }
+        .with_drop(Box::new(move || drop(temp)))
     }
 
     pub fn transaction(&self) -> DatabaseTransaction<Description> {
         self.into()
     }
 
+    /// Returns the storage of the database, e.g. for tests of the raw key-value access.
+    #[cfg(any(test, feature = "rocksdb", feature = "redb"))]
+    pub(crate) fn data_source(&self) -> &DataSource<Description> {
+        self.data.as_ref()
+    }
+
     pub fn flush(self) -> DatabaseResult<()> {
         self.data.as_ref().flush()
     }
+
+    /// Starts the shutdown of the database: new writes and commits of transactions
+    /// are rejected with [`Error::ShuttingDown`], while writes in progress
+    /// are completed before the call returns.
+    pub fn begin_shutdown(&self) {
+        self.data.as_ref().begin_shutdown()
+    }
+
+    /// Completes the shutdown started by [`Self::begin_shutdown`]: flushes memtables
+    /// and the WAL, and stops the background work of the storage. Unlike [`Self::close`],
+    /// it doesn't require this to be the last clone of the database.
+    pub fn finish_shutdown(&self) -> DatabaseResult<()> {
+        self.data.as_ref().finish_shutdown()
+    }
+
+    /// Closes the database: drains writes, flushes memtables and the WAL, and stops
+    /// the background work of the storage, so the next open doesn't replay the WAL.
+    ///
+    /// The storage is released only when this is its last clone. Otherwise, the storage
+    /// is still flushed, but the error reports the number of other clones, e.g. held by
+    /// maintenance tasks or the write pressure monitor, that must be stopped first.
+    pub fn close(self) -> DatabaseResult<()> {
+        self.begin_shutdown();
+        self.finish_shutdown()?;
+        let others = Arc::strong_count(&self.data.as_ref().0).saturating_sub(1);
+        if others > 0 {
+            return Err(Error::OutstandingReferences(others))
+        }
+        Ok(())
+    }
+
+    /// Returns the statistics of the `column`.
+    pub fn column_stats(
+        &self,
+        column: Description::Column,
+    ) -> DatabaseResult<ColumnStats> {
+        self.data.as_ref().column_stats(column)
+    }
+
+    /// Returns `true` if the `column` has no entries. It reads at most one key,
+    /// so, unlike the [`Self::entries_count`], it is cheap for columns of any size.
+    pub fn is_column_empty(&self, column: Description::Column) -> DatabaseResult<bool> {
+        Ok(!self.has_any(column, &[])?)
+    }
+
+    /// Returns `true` if the `column` has any key with the `prefix`, e.g. whether
+    /// the owner has any coins. The empty `prefix` checks whether the column is non-empty.
+    /// The backend seeks to the first key with the `prefix` without reading values.
+    pub fn has_any(
+        &self,
+        column: Description::Column,
+        prefix: &[u8],
+    ) -> DatabaseResult<bool> {
+        self.data
+            .as_ref()
+            .has_any(column, prefix)
+            .map_err(Error::from)
+    }
+
+    /// Returns the page of at most `limit` entries with the `prefix` of the `column`
+    /// after the `cursor` of the previous page, see [`cursor::iter_from_cursor`].
+    pub fn iter_from_cursor(
+        &self,
+        column: Description::Column,
+        prefix: Option<&[u8]>,
+        direction: IterDirection,
+        cursor: Option<&Cursor>,
+        limit: usize,
+    ) -> DatabaseResult<Page> {
+        let data: &DataSource<Description> = self.data.as_ref();
+        let page = cursor::iter_from_cursor(
+            data.as_ref(),
+            column,
+            prefix,
+            direction,
+            cursor,
+            limit,
+        )?;
+        read_context::record(column, |counters| {
+            for (_, value) in &page.entries {
+                counters.iter_item(value.len());
+            }
+        });
+        Ok(page)
+    }
+
+    /// Atomically replaces the value of the `key` with the result of `f` applied to the
+    /// current value, e.g. increments a counter. `None` from `f` removes the value.
+    /// The result equal to the current value is not written.
+    pub fn update<F>(
+        &self,
+        key: &[u8],
+        column: Description::Column,
+        f: F,
+    ) -> DatabaseResult<UpdateOutcome>
+    where
+        F: FnOnce(Option<&[u8]>) -> Option<Vec<u8>>,
+    {
+        let mut f = Some(f);
+        self.data
+            .as_ref()
+            .update(key, column, &mut |current| {
+                let f = f.take().expect("The backend calls the update only once");
+                f(current)
+            })
+            .map_err(Error::from)
+    }
+
+    /// Returns the number of entries of the `column` from its statistics.
+    /// The RocksDB estimates it by metadata of SST files and memtables, so it may
+    /// differ from the actual number, e.g. after removals that are not compacted yet.
+    /// See [`Self::exact_entries_count`] for the exact number.
+    pub fn entries_count(&self, column: Description::Column) -> DatabaseResult<u64> {
+        Ok(self.column_stats(column)?.keys)
+    }
+
+    /// Returns the exact number of entries of the `column`, counted by the scan of its keys.
+    pub fn exact_entries_count(
+        &self,
+        column: Description::Column,
+    ) -> DatabaseResult<u64> {
+        self.data
+            .as_ref()
+            .iter_keys(column, None, None, IterDirection::Forward)
+            .try_fold(0u64, |count, key| {
+                key.map_err(Error::from)?;
+                Ok(count.saturating_add(1))
+            })
+    }
+
+    /// Returns the internal statistics of the storage engine, e.g. of the RocksDB.
+    /// The in-memory database returns the empty statistics.
+    pub fn engine_stats(&self) -> DatabaseResult<EngineStats> {
+        self.data.as_ref().engine_stats()
+    }
+
+    /// Returns the size in bytes of the entries of the `column` with keys from the `start`
+    /// inclusive to the `end` exclusive, see [`crate::state::TransactableStorage::size_of_range`].
+    /// The size is approximate for the RocksDB.
+    pub fn size_of_range(
+        &self,
+        column: Description::Column,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> DatabaseResult<u64> {
+        self.data.as_ref().size_of_range(column, start, end)
+    }
+
+    /// Returns the size in bytes of the entries of the `column` with keys starting
+    /// with the `prefix`, e.g. of coins of the owner or of the state of the contract.
+    pub fn size_of_prefix(
+        &self,
+        column: Description::Column,
+        prefix: &[u8],
+    ) -> DatabaseResult<u64> {
+        self.data.as_ref().size_of_prefix(column, prefix)
+    }
+
+    /// Creates a consistent point-in-time copy of the database at the `path`.
+    pub fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        self.data.as_ref().checkpoint(path)
+    }
+
+    /// Returns the number of chunks applied by the interrupted chunked commit,
+    /// or `None` if there is no interrupted commit. See [`chunked_commit`].
+    pub fn interrupted_chunked_commit(&self) -> DatabaseResult<Option<u64>> {
+        let data: &DataSource<Description> = self.data.as_ref();
+        chunked_commit::interrupted_chunked_commit::<Description>(data.as_ref())
+            .map_err(Error::from)
+    }
+
+    /// Creates a new incremental backup of the database in the `backup_dir`.
+    pub fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()> {
+        self.data.as_ref().backup_to(backup_dir)
+    }
+
+    /// Compacts the `range` of keys of the `column` to reclaim the space of deleted values.
+    /// The `None` column compacts all columns, and the `None` range compacts the whole column.
+    ///
+    /// The call blocks until the compaction is done, so async code should call it
+    /// from a blocking task.
+    pub fn compact(
+        &self,
+        column: Option<Description::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        self.data.as_ref().compact(column, range)
+    }
+
+    /// Removes all entries of the `column`, e.g. to rebuild the corrupted index
+    /// without removing the whole database. See [`crate::state::TransactableStorage::clear_column`].
+    pub fn clear_column(&self, column: Description::Column) -> DatabaseResult<()> {
+        let data = self.data.as_ref();
+        data.commits().write(|| data.clear_column(column))
+    }
+
+    /// Removes keys of the `column` from the `start` inclusive to the `end` exclusive,
+    /// e.g. the state of the destroyed contract under its prefix.
+    /// See [`crate::state::TransactableStorage::delete_range`].
+    pub fn delete_range(
+        &self,
+        column: Description::Column,
+        start: &[u8],
+        end: &[u8],
+    ) -> DatabaseResult<()> {
+        let data = self.data.as_ref();
+        data.commits().write(|| data.delete_range(column, start, end))
+    }
+
+    /// Writes the `entries` with strictly increasing keys into the `column`, e.g. entries
+    /// of the snapshot exported in the order of keys, bypassing the write path of the RocksDB.
+    /// See [`crate::state::TransactableStorage::ingest_sorted`].
+    pub fn ingest_sorted(
+        &self,
+        column: Description::Column,
+        mut entries: impl Iterator<Item = (Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<IngestStats> {
+        let data = self.data.as_ref();
+        data.commits().write(|| data.ingest_sorted(column, &mut entries))
+    }
+
+    /// Returns the read-only view of the database frozen at the current point in time.
+    /// Reads from the view are consistent even if commits land in between.
+    pub fn read_snapshot(
+        &self,
+    ) -> DatabaseResult<Box<dyn ReadView<Column = Description::Column>>> {
+        self.data.as_ref().read_snapshot()
+    }
+
+    /// Returns the database frozen at the current point in time, e.g. to export the state
+    /// that doesn't change while it is read. Writes into the frozen database never reach
+    /// this database.
+    pub fn snapshot(&self) -> DatabaseResult<Self> {
+        Ok(Self {
+            data: StructuredStorage::new(DataSource::new(
+                self.data.as_ref().snapshot()?,
+            )),
+            _drop: self._drop.clone(),
+        })
+    }
+
+    /// Returns the independent copy of the database, e.g. for tests that start
+    /// from the same prepopulated state. The copy is the in-memory layer over
+    /// the [`Self::snapshot`], so forking doesn't copy the data of the RocksDB.
+    /// Writes into the fork stay in memory, and neither the fork nor this database
+    /// sees writes of the other.
+    pub fn fork(&self) -> DatabaseResult<Self> {
+        let base = DataSource::<Description>::new(self.data.as_ref().snapshot()?);
+        Ok(Self {
+            data: StructuredStorage::new(
+                Arc::new(MemoryTransactionView::new(base)).into(),
+            ),
+            _drop: self._drop.clone(),
+        })
+    }
+
+    /// Returns `true` if no column of the database has entries.
+    pub fn is_empty(&self) -> StorageResult<bool> {
+        for column in enum_iterator::all::<Description::Column>() {
+            if !self.is_column_empty(column)? {
+                return Ok(false)
+            }
+        }
+        Ok(true)
+    }
+
+    /// Returns the aggregated statistics of all columns.
+    pub fn stats(&self) -> DatabaseResult<ColumnStats> {
+        enum_iterator::all::<Description::Column>()
+            .try_fold(ColumnStats::default(), |stats, column| {
+                Ok(stats.merge(self.column_stats(column)?))
+            })
+    }
 }
 
 impl<Description> KeyValueStore for DataSource<Description>
@@ -198,7 +923,7 @@ where
     type Column = Description::Column;
 
     fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
-        self.as_ref().put(key, column, value)
+        self.commits().write(|| self.as_ref().put(key, column, value))
     }
 
     fn replace(
@@ -207,7 +932,7 @@ where
         column: Self::Column,
         value: Value,
     ) -> StorageResult<Option<Value>> {
-        self.as_ref().replace(key, column, value)
+        self.commits().write(|| self.as_ref().replace(key, column, value))
     }
 
     fn write(
@@ -216,19 +941,36 @@ where
         column: Self::Column,
         buf: &[u8],
     ) -> StorageResult<usize> {
-        self.as_ref().write(key, column, buf)
+        self.commits().write(|| self.as_ref().write(key, column, buf))
     }
 
     fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
-        self.as_ref().take(key, column)
+        self.commits().write(|| self.as_ref().take(key, column))
     }
 
     fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
-        self.as_ref().delete(key, column)
+        self.commits().write(|| self.as_ref().delete(key, column))
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        self.commits().write(|| self.as_ref().merge(key, column, operand))
     }
 
     fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
-        self.as_ref().exists(key, column)
+        read_context::entering_read(
+            column,
+            || self.as_ref().exists(key, column),
+            |counters, result| {
+                if let Ok(exists) = result {
+                    counters.get(exists.then_some(0));
+                }
+            },
+        )
     }
 
     fn size_of_value(
@@ -236,11 +978,98 @@ where
         key: &[u8],
         column: Self::Column,
     ) -> StorageResult<Option<usize>> {
-        self.as_ref().size_of_value(key, column)
+        read_context::entering_read(
+            column,
+            || self.as_ref().size_of_value(key, column),
+            |counters, result| {
+                if let Ok(size) = result {
+                    counters.get(size.map(|_| 0));
+                }
+            },
+        )
     }
 
     fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
-        self.as_ref().get(key, column)
+        read_context::entering_read(
+            column,
+            || self.as_ref().get(key, column),
+            |counters, result| {
+                if let Ok(value) = result {
+                    counters.get(value.as_ref().map(|value| value.len()));
+                }
+            },
+        )
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        read_context::entering_read(
+            column,
+            || self.as_ref().multi_get(keys, column),
+            |counters, result| {
+                for value in result.iter().flatten() {
+                    counters.get(value.as_ref().map(|value| value.len()));
+                }
+            },
+        )
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        let (result, _) = read_context::entering_read(
+            column,
+            || {
+                let mut size = None;
+                let result = self.as_ref().get_with(key, column, &mut |value| {
+                    size = Some(value.len());
+                    f(value)
+                });
+                (result, size)
+            },
+            |counters, (result, size)| {
+                if result.is_ok() {
+                    counters.get(*size);
+                }
+            },
+        );
+        result
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<core::result::Result<(), Option<Value>>> {
+        self.commits().write(|| {
+            self.as_ref().compare_and_swap(key, column, expected, new)
+        })
+    }
+
+    fn get_or_insert_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        init: &mut dyn FnMut() -> Vec<u8>,
+    ) -> StorageResult<Value> {
+        self.commits().write(|| self.as_ref().get_or_insert_with(key, column, init))
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        self.commits().write(|| self.as_ref().update(key, column, f))
     }
 
     fn read(
@@ -249,7 +1078,15 @@ where
         column: Self::Column,
         buf: &mut [u8],
     ) -> StorageResult<Option<usize>> {
-        self.as_ref().read(key, column, buf)
+        read_context::entering_read(
+            column,
+            || self.as_ref().read(key, column, buf),
+            |counters, result| {
+                if let Ok(size) = result {
+                    counters.get(*size);
+                }
+            },
+        )
     }
 }
 
@@ -261,7 +1098,7 @@ where
         &self,
         entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
     ) -> StorageResult<()> {
-        self.as_ref().batch_write(entries)
+        self.commits().write(|| self.as_ref().batch_write(entries))
     }
 }
 
@@ -305,12 +1142,52 @@ where
         self.iter_all_filtered::<M, [u8; 0]>(None, start, direction)
     }
 
+    /// Iterates over the table from the `start` for maintenance, e.g. exports and
+    /// consistency checks, with the [`IterOptions::maintenance`], so the scan
+    /// doesn't evict the hot working set from the block cache.
+    pub(crate) fn maintenance_scan<M>(
+        &self,
+        start: Option<&M::Key>,
+    ) -> impl Iterator<Item = StorageResult<(M::OwnedKey, M::OwnedValue)>> + '_
+    where
+        M: Mappable + TableWithBlueprint<Column = Description::Column>,
+        M::Blueprint: Blueprint<M, DataSource>,
+    {
+        self.iter_all_with_options::<M, [u8; 0]>(
+            None,
+            start,
+            None,
+            IterOptions::maintenance(),
+        )
+    }
+
     pub(crate) fn iter_all_filtered<M, P>(
         &self,
         prefix: Option<P>,
         start: Option<&M::Key>,
         direction: Option<IterDirection>,
     ) -> impl Iterator<Item = StorageResult<(M::OwnedKey, M::OwnedValue)>> + '_
+    where
+        M: Mappable + TableWithBlueprint<Column = Description::Column>,
+        M::Blueprint: Blueprint<M, DataSource>,
+        P: AsRef<[u8]>,
+    {
+        self.iter_all_with_options::<M, P>(
+            prefix,
+            start,
+            direction,
+            IterOptions::default(),
+        )
+    }
+
+    /// Iterates over the table with the read `options`, see [`IterOptions`].
+    pub(crate) fn iter_all_with_options<M, P>(
+        &self,
+        prefix: Option<P>,
+        start: Option<&M::Key>,
+        direction: Option<IterDirection>,
+        options: IterOptions,
+    ) -> impl Iterator<Item = StorageResult<(M::OwnedKey, M::OwnedValue)>> + '_
     where
         M: Mappable + TableWithBlueprint<Column = Description::Column>,
         M::Blueprint: Blueprint<M, DataSource>,
@@ -322,29 +1199,32 @@ where
 
         let start = encoder.as_ref().map(|encoder| encoder.as_bytes());
 
-        self.data
-            .as_ref()
-            .iter_all(
-                M::column(),
-                prefix.as_ref().map(|p| p.as_ref()),
-                start.as_ref().map(|cow| cow.as_ref()),
-                direction.unwrap_or_default(),
-            )
-            .map(|val| {
-                val.and_then(|(key, value)| {
-                    let key =
-                        <M::Blueprint as Blueprint<M, DataSource>>::KeyCodec::decode(
-                            key.as_slice(),
-                        )
-                        .map_err(|e| StorageError::Codec(anyhow::anyhow!(e)))?;
-                    let value =
-                        <M::Blueprint as Blueprint<M, DataSource>>::ValueCodec::decode(
-                            value.as_slice(),
-                        )
-                        .map_err(|e| StorageError::Codec(anyhow::anyhow!(e)))?;
-                    Ok((key, value))
-                })
+        let config = IterConfig {
+            prefix: prefix.as_ref().map(|p| p.as_ref()),
+            start: start
+                .as_ref()
+                .map_or(Bound::Unbounded, |cow| Bound::Included(cow.as_ref())),
+            direction: direction.unwrap_or_default(),
+            limit: None,
+            options,
+        };
+        self.data.as_ref().iter(M::column(), config).map(|val| {
+            val.and_then(|(key, value)| {
+                read_context::record(M::column(), |counters| {
+                    counters.iter_item(value.len())
+                });
+                let key = <M::Blueprint as Blueprint<M, DataSource>>::KeyCodec::decode(
+                    key.as_slice(),
+                )
+                .map_err(|e| StorageError::Codec(anyhow::anyhow!(e)))?;
+                let value =
+                    <M::Blueprint as Blueprint<M, DataSource>>::ValueCodec::decode(
+                        value.as_slice(),
+                    )
+                    .map_err(|e| StorageError::Codec(anyhow::anyhow!(e)))?;
+                Ok((key, value))
             })
+        })
     }
 }
 
@@ -501,12 +1381,244 @@ pub fn convert_to_rocksdb_direction(direction: IterDirection) -> rocksdb::Direct
 
 #[cfg(test)]
 mod tests {
+    use super::*;
     use crate::database::database_description::{
         off_chain::OffChain,
         on_chain::OnChain,
         relayer::Relayer,
         DatabaseDescription,
     };
+    use fuel_core_storage::column::Column;
+
+    fn entries(database: &Database) -> Vec<(Vec<u8>, Vec<u8>)> {
+        database
+            .data_source()
+            .iter_all(Column::Coins, None, None, IterDirection::Forward)
+            .map(|item| item.map(|(key, value)| (key, value.to_vec())))
+            .collect::<StorageResult<_>>()
+            .unwrap()
+    }
+
+    fn put(database: &Database, key: u8, value: u8) {
+        database
+            .data_source()
+            .put(&[key], Column::Coins, Arc::new(vec![value]))
+            .unwrap();
+    }
+
+    fn fork_is_isolated_from_the_parent(parent: &Database) {
+        // Given
+        put(parent, 1, 1);
+        put(parent, 2, 2);
+        let fork = parent.fork().unwrap();
+
+        // When
+        put(parent, 1, 10);
+        put(parent, 3, 30);
+        put(&fork, 2, 20);
+        put(&fork, 4, 40);
+        fork.data_source().delete(&[1], Column::Coins).unwrap();
+
+        // Then
+        assert_eq!(
+            entries(parent),
+            vec![(vec![1], vec![10]), (vec![2], vec![2]), (vec![3], vec![30])]
+        );
+        assert_eq!(
+            entries(&fork),
+            vec![(vec![2], vec![20]), (vec![4], vec![40])]
+        );
+    }
+
+    #[test]
+    fn fork_of_the_in_memory_database_is_isolated() {
+        fork_is_isolated_from_the_parent(&Database::default());
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn fork_of_the_rocksdb_is_isolated() {
+        fork_is_isolated_from_the_parent(
+            &temp_database::TempDatabase::rocksdb().unwrap(),
+        );
+    }
+
+    #[cfg(feature = "redb")]
+    #[test]
+    fn fork_of_the_redb_is_isolated() {
+        fork_is_isolated_from_the_parent(&temp_database::TempDatabase::redb().unwrap());
+    }
+
+    fn emptiness_and_count_respect_changes_of_the_view(parent: &Database) {
+        // Given
+        put(parent, 1, 1);
+        put(parent, 2, 2);
+        let fork = parent.fork().unwrap();
+
+        // When
+        fork.data_source().delete(&[1], Column::Coins).unwrap();
+        fork.data_source().delete(&[2], Column::Coins).unwrap();
+        let deleted = (
+            fork.is_column_empty(Column::Coins).unwrap(),
+            fork.entries_count(Column::Coins).unwrap(),
+            fork.exact_entries_count(Column::Coins).unwrap(),
+        );
+        put(&fork, 3, 3);
+        let inserted = (
+            fork.is_column_empty(Column::Coins).unwrap(),
+            fork.entries_count(Column::Coins).unwrap(),
+            fork.exact_entries_count(Column::Coins).unwrap(),
+        );
+
+        // Then
+        assert_eq!(deleted, (true, 0, 0));
+        assert_eq!(inserted, (false, 1, 1));
+        assert!(!parent.is_column_empty(Column::Coins).unwrap());
+        assert_eq!(parent.exact_entries_count(Column::Coins).unwrap(), 2);
+        assert!(parent.is_column_empty(Column::Metadata).unwrap());
+    }
+
+    #[test]
+    fn emptiness_and_count_of_the_in_memory_database_respect_the_view() {
+        emptiness_and_count_respect_changes_of_the_view(&Database::default());
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn emptiness_and_count_of_the_rocksdb_respect_the_view() {
+        emptiness_and_count_respect_changes_of_the_view(
+            &temp_database::TempDatabase::rocksdb().unwrap(),
+        );
+    }
+
+    #[cfg(feature = "redb")]
+    #[test]
+    fn emptiness_and_count_of_the_redb_respect_the_view() {
+        emptiness_and_count_respect_changes_of_the_view(
+            &temp_database::TempDatabase::redb().unwrap(),
+        );
+    }
+
+    fn scans_with_read_options_yield_the_same_entries(database: &Database) {
+        // Given
+        for key in 0..100u8 {
+            put(database, key, key);
+        }
+        let options = [
+            IterOptions::default(),
+            IterOptions::maintenance(),
+            IterOptions {
+                fill_cache: false,
+                readahead_bytes: Some(2 * 1024 * 1024),
+                pin_data: true,
+            },
+        ];
+
+        // When
+        let scans = options.map(|options| {
+            let config = IterConfig {
+                start: Bound::Excluded(&[10]),
+                limit: Some(50),
+                options,
+                ..Default::default()
+            };
+            database
+                .data_source()
+                .iter(Column::Coins, config)
+                .map(|item| item.map(|(key, value)| (key, value.to_vec())))
+                .collect::<StorageResult<Vec<_>>>()
+                .unwrap()
+        });
+
+        // Then
+        let expected = entries(database)[11..61].to_vec();
+        for scan in scans {
+            assert_eq!(scan, expected);
+        }
+    }
+
+    #[test]
+    fn scans_of_the_in_memory_database_ignore_read_options() {
+        scans_with_read_options_yield_the_same_entries(&Database::default());
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn scans_of_the_rocksdb_respect_read_options() {
+        scans_with_read_options_yield_the_same_entries(
+            &temp_database::TempDatabase::rocksdb().unwrap(),
+        );
+    }
+
+    #[cfg(feature = "redb")]
+    #[test]
+    fn scans_of_the_redb_ignore_read_options() {
+        scans_with_read_options_yield_the_same_entries(
+            &temp_database::TempDatabase::redb().unwrap(),
+        );
+    }
+
+    #[cfg(feature = "redb")]
+    #[test]
+    fn database_with_checksums_requires_them_on_open() {
+        // Given
+        let tmp = tempfile::tempdir().unwrap();
+        let columns = ColumnsConfig::default().enable_value_checksums(true);
+        let database =
+            Database::<OnChain>::open_redb(tmp.path(), None, &columns).unwrap();
+        put(&database, 1, 2);
+        drop(database);
+
+        // When
+        let without =
+            Database::<OnChain>::open_redb(tmp.path(), None, &ColumnsConfig::default());
+        let with = Database::<OnChain>::open_redb(tmp.path(), None, &columns).unwrap();
+
+        // Then
+        assert!(without.is_err());
+        assert_eq!(entries(&with), vec![(vec![1], vec![2])]);
+    }
+
+    #[test]
+    fn concurrent_writes_of_the_parent_and_forks_are_isolated() {
+        // Given
+        let parent = Database::<OnChain>::default();
+        for key in 0..10u8 {
+            put(&parent, key, 0);
+        }
+        let forks = (1..=4u8)
+            .map(|_| parent.fork().unwrap())
+            .collect::<Vec<_>>();
+
+        // When
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for key in 0..10u8 {
+                    put(&parent, key, u8::MAX);
+                }
+            });
+            for (index, fork) in forks.iter().enumerate() {
+                let value = u8::try_from(index).unwrap().saturating_add(1);
+                scope.spawn(move || {
+                    for key in 0..10u8 {
+                        put(fork, key, value);
+                    }
+                });
+            }
+        });
+
+        // Then
+        let expected = |value: u8| {
+            (0..10u8)
+                .map(|key| (vec![key], vec![value]))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(entries(&parent), expected(u8::MAX));
+        for (index, fork) in forks.iter().enumerate() {
+            let value = u8::try_from(index).unwrap().saturating_add(1);
+            assert_eq!(entries(fork), expected(value));
+        }
+    }
 
     fn column_keys_not_exceed_count<Description>()
     where
@@ -515,8 +1627,12 @@ mod tests {
         use enum_iterator::all;
         use fuel_core_storage::kv_store::StorageColumn;
         use strum::EnumCount;
+        // Ids of retired columns stay reserved, so ids of columns and retired columns
+        // are dense together.
+        let slots = Description::Column::COUNT
+            .saturating_add(Description::retired_columns().len());
         for column in all::<Description::Column>() {
-            assert!(column.as_usize() < Description::Column::COUNT);
+            assert!(column.as_usize() < slots);
         }
     }
 
@@ -534,4 +1650,127 @@ mod tests {
     fn column_keys_not_exceed_count_test_relayer() {
         column_keys_not_exceed_count::<Relayer>();
     }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn second_read_write_open_fails_with_the_owner_of_the_lock() {
+        // Given
+        let tmp = tempfile::tempdir().unwrap();
+        let database = Database::<OnChain>::open(tmp.path(), None).unwrap();
+
+        // When
+        let result = std::thread::scope(|scope| {
+            scope
+                .spawn(|| Database::<OnChain>::open(tmp.path(), None))
+                .join()
+                .unwrap()
+        });
+
+        // Then
+        let message = result.unwrap_err().to_string();
+        assert!(
+            message.contains(&format!("is in use by PID {}", std::process::id())),
+            "{message}"
+        );
+        assert!(Database::<OnChain>::open_read_only(tmp.path(), None).is_ok());
+        drop(database);
+        assert!(Database::<OnChain>::open(tmp.path(), None).is_ok());
+    }
+
+    #[test]
+    fn decode_failure_through_the_typed_layer_reports_the_entry() {
+        use fuel_core_storage::{
+            kv_store::StorageColumn,
+            tables::Coins,
+            StorageAsMut,
+            StorageAsRef,
+        };
+        use fuel_core_types::{
+            entities::coins::coin::CompressedCoin,
+            fuel_tx::UtxoId,
+        };
+
+        // Given
+        let mut database = Database::<OnChain>::default();
+        let utxo_id = UtxoId::new(Default::default(), 1);
+        database
+            .storage_as_mut::<Coins>()
+            .insert(&utxo_id, &CompressedCoin::default())
+            .unwrap();
+        let key = database
+            .data_source()
+            .iter_keys(Column::Coins, None, None, IterDirection::Forward)
+            .next()
+            .unwrap()
+            .unwrap();
+        database
+            .data_source()
+            .put(&key, Column::Coins, Arc::new(vec![0xFF; 3]))
+            .unwrap();
+
+        // When
+        let result = database.storage::<Coins>().get(&utxo_id);
+
+        // Then
+        match result.map_err(Error::from) {
+            Err(Error::DecodeEntry {
+                column, key: entry, ..
+            }) => {
+                assert_eq!(column, Column::Coins.name());
+                assert_eq!(entry, key);
+            }
+            result => panic!("Expected the decode error, got {result:?}"),
+        }
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn closed_rocksdb_reopens_without_the_wal_recovery() {
+        // Given
+        let tmp = tempfile::TempDir::new().unwrap();
+        let database = Database::<OnChain>::open(tmp.path(), None).unwrap();
+        for key in 0..100u8 {
+            put(&database, key, key);
+        }
+
+        // When
+        database.close().unwrap();
+
+        // Then
+        // Memtables are flushed, so WAL files have nothing to replay.
+        let wal_size = std::fs::read_dir(tmp.path().join(OnChain::name()))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "log"))
+            .map(|path| std::fs::metadata(path).unwrap().len())
+            .sum::<u64>();
+        assert_eq!(wal_size, 0);
+        let reopened = Database::<OnChain>::open(tmp.path(), None).unwrap();
+        assert_eq!(entries(&reopened).len(), 100);
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn close_reports_outstanding_clones_of_the_database() {
+        // Given
+        let tmp = tempfile::TempDir::new().unwrap();
+        let database = Database::<OnChain>::open(tmp.path(), None).unwrap();
+        put(&database, 1, 1);
+        let clone = database.clone();
+
+        // When
+        let result = database.close();
+
+        // Then
+        assert!(
+            matches!(result, Err(Error::OutstandingReferences(1))),
+            "{result:?}"
+        );
+        let write = clone
+            .data_source()
+            .put(&[2], Column::Coins, Arc::new(vec![2]))
+            .map_err(Error::from);
+        assert!(matches!(write, Err(Error::ShuttingDown)), "{write:?}");
+        assert_eq!(entries(&clone), vec![(vec![1], vec![1])]);
+    }
 }
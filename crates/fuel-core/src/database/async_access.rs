@@ -0,0 +1,233 @@
+//! The async access to the database. Storage calls block the current thread, so async
+//! code runs them on the blocking pool of the `tokio` instead of the worker threads.
+//! The number of concurrent blocking calls is bounded, because the blocking pool is
+//! shared with other services of the node.
+
+use crate::database::{
+    database_description::DatabaseDescription,
+    Database,
+};
+use fuel_core_storage::{
+    iter::{
+        IterConfig,
+        IterDirection,
+    },
+    kv_store::{
+        KVItem,
+        KeyValueStore,
+        Value,
+    },
+    Error as StorageError,
+    Result as StorageResult,
+};
+use futures::{
+    Stream,
+    StreamExt,
+};
+use std::ops::Bound;
+use tokio::sync::Semaphore;
+
+/// The maximum number of storage calls running on the blocking pool at the same time.
+pub const MAX_CONCURRENT_BLOCKING_CALLS: usize = 64;
+
+/// The number of items fetched by one blocking call of the [`Database::iter_async`].
+pub const ITER_CHUNK_SIZE: usize = 256;
+
+static BLOCKING_CALLS: Semaphore = Semaphore::const_new(MAX_CONCURRENT_BLOCKING_CALLS);
+
+/// Runs the `f` on the blocking pool.
+async fn run_blocking<T, F>(f: F) -> StorageResult<T>
+where
+    F: FnOnce() -> StorageResult<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let _permit = BLOCKING_CALLS
+        .acquire()
+        .await
+        .map_err(|e| StorageError::Other(e.into()))?;
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| StorageError::Other(e.into()))?
+}
+
+/// The start of the next chunk of the [`Database::iter_async`]. The next chunk
+/// starts after the last key of the previous chunk.
+type ChunkStart = Bound<Vec<u8>>;
+
+impl<Description> Database<Description>
+where
+    Description: DatabaseDescription,
+    Description::Column: Send + 'static,
+{
+    /// Runs the `f` with the database on the blocking pool.
+    /// It is the async version of any read or write of the database.
+    pub async fn run_blocking<T, F>(&self, f: F) -> StorageResult<T>
+    where
+        F: FnOnce(&Self) -> StorageResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let database = self.clone();
+        run_blocking(move || f(&database)).await
+    }
+
+    /// The async version of the [`KeyValueStore::get`].
+    pub async fn get_async(
+        &self,
+        key: Vec<u8>,
+        column: Description::Column,
+    ) -> StorageResult<Option<Value>> {
+        let data = self.data.as_ref().clone();
+        run_blocking(move || data.get(&key, column)).await
+    }
+
+    /// The async version of the [`KeyValueStore::put`].
+    pub async fn put_async(
+        &self,
+        key: Vec<u8>,
+        column: Description::Column,
+        value: Value,
+    ) -> StorageResult<()> {
+        let data = self.data.as_ref().clone();
+        run_blocking(move || data.put(&key, column, value)).await
+    }
+
+    /// The async version of the [`fuel_core_storage::iter::IteratorableStore::iter_all`].
+    /// Items are fetched by chunks of the [`ITER_CHUNK_SIZE`] on the blocking pool, so the
+    /// iterator isn't held across await points, and other tasks make progress between chunks.
+    ///
+    /// Each chunk creates a new iterator, so the stream doesn't see a consistent
+    /// snapshot of the column if commits land between chunks.
+    pub fn iter_async(
+        &self,
+        column: Description::Column,
+        prefix: Option<Vec<u8>>,
+        start: Option<Vec<u8>>,
+        direction: IterDirection,
+    ) -> impl Stream<Item = KVItem> + Send + 'static {
+        let data = self.data.as_ref().clone();
+        let start: ChunkStart = start.map_or(Bound::Unbounded, Bound::Included);
+        futures::stream::unfold(Some(start), move |start| {
+            let data = data.clone();
+            let prefix = prefix.clone();
+            async move {
+                let start = start?;
+                let chunk = run_blocking(move || {
+                    let config = IterConfig {
+                        prefix: prefix.as_deref(),
+                        start: match &start {
+                            Bound::Included(start) => Bound::Included(start.as_slice()),
+                            Bound::Excluded(start) => Bound::Excluded(start.as_slice()),
+                            Bound::Unbounded => Bound::Unbounded,
+                        },
+                        direction,
+                        limit: Some(ITER_CHUNK_SIZE),
+                        ..Default::default()
+                    };
+                    Ok(data.iter(column, config).collect::<Vec<_>>())
+                })
+                .await;
+                match chunk {
+                    Ok(chunk) => {
+                        let next = match chunk.last() {
+                            Some(Ok((key, _))) if chunk.len() == ITER_CHUNK_SIZE => {
+                                Some(Bound::Excluded(key.clone()))
+                            }
+                            _ => None,
+                        };
+                        Some((chunk, next))
+                    }
+                    Err(err) => Some((vec![Err(err)], None)),
+                }
+            }
+        })
+        .flat_map(futures::stream::iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fuel_core_storage::column::Column;
+    use std::sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Arc,
+    };
+
+    fn database_with_coins(count: u32) -> Database {
+        let database = Database::default();
+        for key in 0..count {
+            database
+                .data
+                .as_ref()
+                .put(&key.to_be_bytes(), Column::Coins, Arc::new(vec![1]))
+                .unwrap();
+        }
+        database
+    }
+
+    #[tokio::test]
+    async fn iter_async_yields_the_same_items_as_iter_all() {
+        let database = database_with_coins(1000);
+
+        for direction in [IterDirection::Forward, IterDirection::Reverse] {
+            for (prefix, start) in [
+                (None, None),
+                (Some(vec![0, 0]), Some(300u32.to_be_bytes().to_vec())),
+            ] {
+                let expected = database
+                    .data
+                    .as_ref()
+                    .iter_all(
+                        Column::Coins,
+                        prefix.as_deref(),
+                        start.as_deref(),
+                        direction,
+                    )
+                    .map(|item| item.unwrap().0)
+                    .collect::<Vec<_>>();
+
+                let actual = database
+                    .iter_async(Column::Coins, prefix, start, direction)
+                    .map(|item| item.unwrap().0)
+                    .collect::<Vec<_>>()
+                    .await;
+
+                assert_eq!(actual, expected);
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn long_scan_does_not_starve_cheap_query() {
+        // Given
+        let database = database_with_coins(20_000);
+        let scan_finished = Arc::new(AtomicBool::new(false));
+        let scan = tokio::spawn({
+            let database = database.clone();
+            let scan_finished = scan_finished.clone();
+            async move {
+                let items = database
+                    .iter_async(Column::Coins, None, None, IterDirection::Forward)
+                    .count()
+                    .await;
+                scan_finished.store(true, Ordering::SeqCst);
+                items
+            }
+        });
+        tokio::task::yield_now().await;
+
+        // When
+        let value = database
+            .get_async(7u32.to_be_bytes().to_vec(), Column::Coins)
+            .await
+            .unwrap();
+
+        // Then
+        assert_eq!(value, Some(Arc::new(vec![1])));
+        assert!(!scan_finished.load(Ordering::SeqCst));
+        assert_eq!(scan.await.unwrap(), 20_000);
+    }
+}
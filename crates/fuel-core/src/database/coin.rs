@@ -16,7 +16,6 @@ use fuel_core_storage::{
     Result as StorageResult,
     StorageAsRef,
 };
-use fuel_core_txpool::types::TxId;
 use fuel_core_types::{
     entities::coins::coin::CompressedCoin,
     fuel_tx::{
@@ -33,19 +32,12 @@ impl Database<OffChain> {
         direction: Option<IterDirection>,
     ) -> impl Iterator<Item = StorageResult<UtxoId>> + '_ {
         let start_coin = start_coin.map(|b| owner_coin_id_key(owner, &b));
-        self.iter_all_filtered::<OwnedCoins, _>(
-            Some(*owner), start_coin.as_ref(),
+        self.iter_composite_key::<OwnedCoins, Address, UtxoId, _>(
+            Some(*owner),
+            start_coin.as_ref(),
             direction,
         )
-        // Safety: key is always 64 bytes
-        .map(|res| {
-            res.map(|(key, _)| {
-                UtxoId::new(
-                    TxId::try_from(&key[32..64]).expect("The slice has size 32"),
-                    key[64],
-                )
-            })
-        })
+        .map(|res| res.map(|((_, utxo_id), _)| utxo_id))
     }
 }
 
@@ -60,10 +52,10 @@ impl Database {
         Ok(coin)
     }
 
-    pub fn get_coin_config(&self) -> StorageResult<Option<Vec<CoinConfig>>> {
-        let configs = self
-            .iter_all::<Coins>(None)
-            .map(|raw_coin| -> StorageResult<CoinConfig> {
+    /// Returns the iterator over configs of all unspent coins.
+    pub fn coin_configs(&self) -> impl Iterator<Item = StorageResult<CoinConfig>> + '_ {
+        self.maintenance_scan::<Coins>(None).map(
+            |raw_coin| -> StorageResult<CoinConfig> {
                 let (utxo_id, coin) = raw_coin?;
 
                 Ok(CoinConfig {
@@ -76,7 +68,13 @@ impl Database {
                     amount: *coin.amount(),
                     asset_id: *coin.asset_id(),
                 })
-            })
+            },
+        )
+    }
+
+    pub fn get_coin_config(&self) -> StorageResult<Option<Vec<CoinConfig>>> {
+        let configs = self
+            .coin_configs()
             .collect::<StorageResult<Vec<CoinConfig>>>()?;
 
         Ok(Some(configs))
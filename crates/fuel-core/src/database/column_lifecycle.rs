@@ -0,0 +1,478 @@
+//! The creation and retirement of columns.
+//!
+//! Ids of columns are persisted, so the id of the column never changes and is never
+//! reused by another column. The new column gets the next free id. The removed column
+//! is listed in the [`DatabaseDescription::retired_columns`]: the RocksDB drops its
+//! column family on the next open, and its id stays reserved.
+//!
+//! The metadata column records the [`ColumnRegistry`] of all columns ever created in
+//! the database. The open of the database rejects the column whose id was used by
+//! another column or by the retired one, so the reuse of the id doesn't read the data
+//! of another column. The name is the identity of the column, so renaming the column
+//! is rejected as the reuse of its id too.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        metadata::{
+            get_metadata,
+            set_metadata,
+            CreatedColumns,
+        },
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::TransactableStorage,
+};
+use fuel_core_storage::kv_store::StorageColumn;
+use std::collections::BTreeMap;
+
+/// The column recorded in the [`ColumnRegistry`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RegisteredColumn {
+    /// The name of the column.
+    pub name: String,
+    /// Whether the column is retired.
+    pub retired: bool,
+}
+
+/// All columns ever created in the database by their ids.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ColumnRegistry {
+    columns: BTreeMap<u32, RegisteredColumn>,
+}
+
+impl ColumnRegistry {
+    /// Returns the column recorded with the `id`.
+    pub fn get(&self, id: u32) -> Option<&RegisteredColumn> {
+        self.columns.get(&id)
+    }
+
+    /// Returns recorded columns ordered by their ids.
+    pub fn columns(&self) -> impl Iterator<Item = (u32, &RegisteredColumn)> {
+        self.columns.iter().map(|(id, column)| (*id, column))
+    }
+
+    /// Records the `column` with the `id`. Returns `true` if the registry changed.
+    fn record(&mut self, id: u32, column: RegisteredColumn) -> bool {
+        if self.columns.get(&id) == Some(&column) {
+            return false
+        }
+        self.columns.insert(id, column);
+        true
+    }
+}
+
+/// Returns the registry of columns of the database, empty if it is not recorded yet.
+pub fn column_registry<Description>(
+    db: &dyn TransactableStorage<Column = Description::Column>,
+) -> DatabaseResult<ColumnRegistry>
+where
+    Description: DatabaseDescription,
+{
+    Ok(get_metadata::<CreatedColumns, Description>(db)?.unwrap_or_default())
+}
+
+/// Ensures that no column of the `Description` reuses the id of another column,
+/// retired or recorded in the [`ColumnRegistry`] of the database, without recording
+/// anything. The RocksDB checks it before it drops column families of retired columns,
+/// so the reused id never drops the column family of the live column.
+pub fn check_column_ids<Description>(
+    db: &dyn TransactableStorage<Column = Description::Column>,
+) -> DatabaseResult<()>
+where
+    Description: DatabaseDescription,
+{
+    checked_registry::<Description>(db).map(drop)
+}
+
+/// Ensures that no column of the `Description` reuses the id of another column,
+/// retired or recorded in the [`ColumnRegistry`] of the database, and records
+/// columns and retired columns of the `Description` in the registry.
+pub fn ensure_column_ids<Description>(
+    db: &dyn TransactableStorage<Column = Description::Column>,
+) -> DatabaseResult<()>
+where
+    Description: DatabaseDescription,
+{
+    let retired = Description::retired_columns();
+    let mut registry = checked_registry::<Description>(db)?;
+    let mut changed = false;
+    for column in enum_iterator::all::<Description::Column>() {
+        changed |= registry.record(
+            column.id(),
+            RegisteredColumn {
+                name: column.name().to_string(),
+                retired: false,
+            },
+        );
+    }
+    for column in retired {
+        changed |= registry.record(
+            column.id,
+            RegisteredColumn {
+                name: column.name.to_string(),
+                retired: true,
+            },
+        );
+    }
+    if changed {
+        set_metadata::<CreatedColumns, Description>(db, &registry)?;
+    }
+    Ok(())
+}
+
+/// Returns the registry of the database after the check of ids of columns.
+fn checked_registry<Description>(
+    db: &dyn TransactableStorage<Column = Description::Column>,
+) -> DatabaseResult<ColumnRegistry>
+where
+    Description: DatabaseDescription,
+{
+    let retired = Description::retired_columns();
+    let registry = column_registry::<Description>(db)?;
+    for column in enum_iterator::all::<Description::Column>() {
+        let id = column.id();
+        if let Some(retired) = retired.iter().find(|retired| retired.id == id) {
+            return Err(id_reused(column, retired.name))
+        }
+        match registry.get(id) {
+            Some(recorded) if recorded.retired || recorded.name != column.name() => {
+                return Err(id_reused(column, &recorded.name))
+            }
+            _ => {}
+        }
+    }
+    Ok(registry)
+}
+
+fn id_reused<Column: StorageColumn>(column: Column, previous: &str) -> DatabaseError {
+    DatabaseError::ColumnIdReused {
+        id: column.id(),
+        column: column.name(),
+        previous: previous.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::{
+            on_chain::OnChain,
+            RetiredColumn,
+            RetiredColumns,
+        },
+        state::in_memory::memory_store::MemoryStore,
+    };
+    use fuel_core_storage::column::Column;
+
+    macro_rules! test_database {
+        ($database:ident, $column:ident { $($variant:ident = $id:literal),* }, $retired:expr) => {
+            #[derive(
+                Debug,
+                Copy,
+                Clone,
+                strum_macros::EnumCount,
+                strum_macros::IntoStaticStr,
+                PartialEq,
+                Eq,
+                PartialOrd,
+                Ord,
+                enum_iterator::Sequence,
+                Hash,
+            )]
+            enum $column {
+                $($variant = $id),*
+            }
+
+            impl StorageColumn for $column {
+                fn name(&self) -> &'static str {
+                    self.into()
+                }
+
+                fn id(&self) -> u32 {
+                    *self as u32
+                }
+            }
+
+            #[derive(Clone, Debug)]
+            struct $database;
+
+            impl DatabaseDescription for $database {
+                type Column = $column;
+                type Height = u32;
+
+                fn version() -> u32 {
+                    0
+                }
+
+                fn name() -> &'static str {
+                    "lifecycle"
+                }
+
+                fn metadata_column() -> Self::Column {
+                    $column::Metadata
+                }
+
+                fn prefix(_: &Self::Column) -> Option<usize> {
+                    None
+                }
+
+                fn retired_columns() -> RetiredColumns {
+                    $retired
+                }
+            }
+        };
+    }
+
+    const LEGACY: RetiredColumns = &[RetiredColumn {
+        id: 2,
+        name: "Legacy",
+    }];
+
+    // The first version of the database has the `Legacy` column.
+    test_database!(V1, V1Column { Metadata = 0, Kept = 1, Legacy = 2 }, &[]);
+    // The second version retires the `Legacy` column.
+    test_database!(V2, V2Column { Metadata = 0, Kept = 1 }, LEGACY);
+    // The third version reuses the id of the `Legacy` column without listing it.
+    test_database!(V3, V3Column { Metadata = 0, Kept = 1, Fresh = 2 }, &[]);
+    // The fourth version reuses the id of the `Legacy` column while it is retired.
+    test_database!(V4, V4Column { Metadata = 0, Kept = 1, Fresh = 2 }, LEGACY);
+
+    fn assert_reused_legacy_id(result: DatabaseResult<()>) {
+        assert!(
+            matches!(
+                result,
+                Err(DatabaseError::ColumnIdReused {
+                    id: 2,
+                    column: "Fresh",
+                    ref previous,
+                }) if previous == "Legacy"
+            ),
+            "{result:?}"
+        );
+    }
+
+    #[test]
+    fn ids_of_retired_columns_are_reserved() {
+        // Given
+        let db = MemoryStore::<V4>::default();
+
+        // When
+        let result = ensure_column_ids::<V4>(&db);
+
+        // Then
+        assert_reused_legacy_id(result);
+    }
+
+    #[test]
+    fn registry_records_columns_once() {
+        // Given
+        let db = MemoryStore::<V1>::default();
+        ensure_column_ids::<V1>(&db).unwrap();
+
+        // When
+        ensure_column_ids::<V1>(&db).unwrap();
+
+        // Then
+        let registry = column_registry::<V1>(&db).unwrap();
+        let columns = registry
+            .columns()
+            .map(|(id, column)| (id, column.name.as_str(), column.retired))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            columns,
+            [(0, "Metadata", false), (1, "Kept", false), (2, "Legacy", false)]
+        );
+    }
+
+    #[test]
+    fn registry_rejects_the_reuse_of_ids_of_created_and_retired_columns() {
+        // Given
+        let created = MemoryStore::<V1>::default();
+        ensure_column_ids::<V1>(&created).unwrap();
+        let retired = MemoryStore::<V2>::default();
+        ensure_column_ids::<V2>(&retired).unwrap();
+
+        // When
+        let reused = ensure_column_ids::<V3>(&replay::<V1, V3>(&created));
+        let reused_after_retirement =
+            ensure_column_ids::<V3>(&replay::<V2, V3>(&retired));
+
+        // Then
+        assert_reused_legacy_id(reused);
+        assert_reused_legacy_id(reused_after_retirement);
+    }
+
+    /// Copies the registry of the `From` database into the new `To` database,
+    /// like the upgrade of the node opening the same data directory.
+    fn replay<From, To>(db: &MemoryStore<From>) -> MemoryStore<To>
+    where
+        From: DatabaseDescription,
+        To: DatabaseDescription,
+    {
+        let upgraded = MemoryStore::<To>::default();
+        set_metadata::<CreatedColumns, To>(
+            &upgraded,
+            &column_registry::<From>(db).unwrap(),
+        )
+        .unwrap();
+        upgraded
+    }
+
+    #[test]
+    fn ids_of_on_chain_columns_are_recorded() {
+        // Given
+        let db = MemoryStore::<OnChain>::default();
+
+        // When
+        ensure_column_ids::<OnChain>(&db).unwrap();
+
+        // Then
+        let registry = column_registry::<OnChain>(&db).unwrap();
+        for column in enum_iterator::all::<Column>() {
+            assert_eq!(
+                registry.get(column.id()),
+                Some(&RegisteredColumn {
+                    name: column.name().to_string(),
+                    retired: false,
+                })
+            );
+        }
+    }
+
+    #[cfg(feature = "rocksdb")]
+    mod rocks_db {
+        use super::*;
+        use crate::{
+            database::Database,
+            state::column_config::ColumnsConfig,
+        };
+        use fuel_core_storage::kv_store::KeyValueStore;
+        use std::{
+            path::Path,
+            sync::Arc,
+        };
+        use tempfile::TempDir;
+
+        fn open<Description: DatabaseDescription>(
+            path: &Path,
+            drop_retired: bool,
+        ) -> DatabaseResult<Database<Description>> {
+            let config =
+                ColumnsConfig::default().drop_retired_columns_with_data(drop_retired);
+            Database::<Description>::open_with_columns(path, None, &config)
+        }
+
+        #[test]
+        fn retired_column_with_data_is_dropped_only_with_the_flag() {
+            // Given
+            let tmp = TempDir::new().unwrap();
+            let v1 = open::<V1>(tmp.path(), false).unwrap();
+            v1.data_source()
+                .put(&[1], V1Column::Legacy, Arc::new(vec![1]))
+                .unwrap();
+            v1.data_source()
+                .put(&[1], V1Column::Kept, Arc::new(vec![2]))
+                .unwrap();
+            drop(v1);
+
+            // When
+            let refused = open::<V2>(tmp.path(), false).unwrap_err();
+            let dropped = open::<V2>(tmp.path(), true).unwrap();
+
+            // Then
+            // The error of the RocksDB is wrapped with the context of the open.
+            let DatabaseError::Other(refused) = refused else {
+                panic!("Unexpected error {refused:?}")
+            };
+            assert!(
+                matches!(
+                    refused.downcast_ref::<DatabaseError>(),
+                    Some(DatabaseError::RetiredColumnNotEmpty {
+                        id: 2,
+                        column: "Legacy"
+                    })
+                ),
+                "{refused:?}"
+            );
+            assert_eq!(
+                dropped.data_source().get(&[1], V2Column::Kept).unwrap(),
+                Some(Arc::new(vec![2]))
+            );
+            let registry =
+                column_registry::<V2>(dropped.data_source().0.as_ref()).unwrap();
+            assert_eq!(registry.get(2).map(|column| column.retired), Some(true));
+            drop(dropped);
+            let families = rocksdb::DB::list_cf(
+                &rocksdb::Options::default(),
+                tmp.path().join(V2::name()),
+            )
+            .unwrap();
+            assert!(!families.contains(&"col-2".to_string()), "{families:?}");
+        }
+
+        #[test]
+        fn empty_retired_column_is_dropped_without_the_flag() {
+            // Given
+            let tmp = TempDir::new().unwrap();
+            drop(open::<V1>(tmp.path(), false).unwrap());
+
+            // When
+            let result = open::<V2>(tmp.path(), false);
+
+            // Then
+            assert!(result.is_ok(), "{result:?}");
+        }
+
+        #[test]
+        fn reused_id_of_the_retired_column_never_drops_the_live_column() {
+            // Given
+            let tmp = TempDir::new().unwrap();
+            let v1 = open::<V1>(tmp.path(), false).unwrap();
+            v1.data_source()
+                .put(&[1], V1Column::Legacy, Arc::new(vec![1]))
+                .unwrap();
+            drop(v1);
+
+            // When
+            let result = open::<V4>(tmp.path(), true);
+
+            // Then
+            let Err(DatabaseError::Other(refused)) = result.map(drop) else {
+                panic!("The reuse of the id is accepted")
+            };
+            assert!(
+                matches!(
+                    refused.downcast_ref::<DatabaseError>(),
+                    Some(DatabaseError::ColumnIdReused {
+                        id: 2,
+                        column: "Fresh",
+                        ..
+                    })
+                ),
+                "{refused:?}"
+            );
+            let v1 = open::<V1>(tmp.path(), false).unwrap();
+            assert_eq!(
+                v1.data_source().get(&[1], V1Column::Legacy).unwrap(),
+                Some(Arc::new(vec![1]))
+            );
+        }
+
+        #[test]
+        fn open_rejects_the_reuse_of_the_id_of_the_retired_column() {
+            // Given
+            let tmp = TempDir::new().unwrap();
+            drop(open::<V1>(tmp.path(), false).unwrap());
+            drop(open::<V2>(tmp.path(), false).unwrap());
+
+            // When
+            let result = open::<V3>(tmp.path(), false);
+
+            // Then
+            assert_reused_legacy_id(result.map(drop));
+        }
+    }
+}
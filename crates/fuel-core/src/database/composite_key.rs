@@ -0,0 +1,286 @@
+//! The module provides the typed iteration over tables with composite keys.
+//! The composite key is a concatenation of two fixed-size components,
+//! so the components are split at the size of the first one.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Database,
+    },
+    state::DataSource,
+};
+use fuel_core_storage::{
+    blueprint::Blueprint,
+    codec::{
+        Decode,
+        Encode,
+        Encoder,
+    },
+    iter::IterDirection,
+    structured_storage::TableWithBlueprint,
+    Error as StorageError,
+    Mappable,
+    Result as StorageResult,
+};
+use fuel_core_types::{
+    fuel_tx::{
+        TxId,
+        UtxoId,
+    },
+    fuel_types::{
+        Address,
+        AssetId,
+        Bytes32,
+        ContractId,
+        Nonce,
+    },
+};
+
+/// The fixed-size component of the composite key.
+pub trait KeyComponent: Sized {
+    /// The size of the component in bytes.
+    const LEN: usize;
+
+    /// Decodes the component from the `bytes` of the size [`Self::LEN`].
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+macro_rules! key_component {
+    ($($ty:ty),*) => {
+        $(
+            impl KeyComponent for $ty {
+                const LEN: usize = <$ty>::LEN;
+
+                fn from_bytes(bytes: &[u8]) -> Option<Self> {
+                    <$ty>::try_from(bytes).ok()
+                }
+            }
+        )*
+    };
+}
+
+key_component!(Address, AssetId, Bytes32, ContractId, Nonce);
+
+impl KeyComponent for UtxoId {
+    const LEN: usize = TxId::LEN + 1;
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [tx_id @ .., output_index] => {
+                Some(UtxoId::new(TxId::try_from(tx_id).ok()?, *output_index))
+            }
+            [] => None,
+        }
+    }
+}
+
+/// Splits the composite `key` into components.
+/// Returns an error if the size of the key doesn't match the sizes of components.
+pub fn split_key<K1, K2>(key: &[u8]) -> StorageResult<(K1, K2)>
+where
+    K1: KeyComponent,
+    K2: KeyComponent,
+{
+    let malformed = || {
+        StorageError::Codec(anyhow::anyhow!(
+            "The composite key has {} bytes, but expected {} + {}",
+            key.len(),
+            K1::LEN,
+            K2::LEN
+        ))
+    };
+    if Some(key.len()) != K1::LEN.checked_add(K2::LEN) {
+        return Err(malformed())
+    }
+    let (first, second) = key.split_at(K1::LEN);
+    let first = K1::from_bytes(first).ok_or_else(malformed)?;
+    let second = K2::from_bytes(second).ok_or_else(malformed)?;
+    Ok((first, second))
+}
+
+impl<Description> Database<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Iterates over the table `M` with the composite key and decodes keys into `(K1, K2)`.
+    /// The malformed keys are returned as errors without stopping the iteration.
+    pub(crate) fn iter_composite_key<M, K1, K2, P>(
+        &self,
+        prefix: Option<P>,
+        start: Option<&M::Key>,
+        direction: Option<IterDirection>,
+    ) -> impl Iterator<Item = StorageResult<((K1, K2), M::OwnedValue)>> + '_
+    where
+        M: Mappable + TableWithBlueprint<Column = Description::Column>,
+        M::Blueprint: Blueprint<M, DataSource>,
+        K1: KeyComponent,
+        K2: KeyComponent,
+        P: AsRef<[u8]>,
+    {
+        let encoder = start.map(|start| {
+            <M::Blueprint as Blueprint<M, DataSource>>::KeyCodec::encode(start)
+        });
+
+        let start = encoder.as_ref().map(|encoder| encoder.as_bytes());
+
+        self.data
+            .as_ref()
+            .iter_all(
+                M::column(),
+                prefix.as_ref().map(|p| p.as_ref()),
+                start.as_ref().map(|cow| cow.as_ref()),
+                direction.unwrap_or_default(),
+            )
+            .map(|val| {
+                val.and_then(|(key, value)| {
+                    let key = split_key::<K1, K2>(key.as_slice())?;
+                    let value =
+                        <M::Blueprint as Blueprint<M, DataSource>>::ValueCodec::decode(
+                            value.as_slice(),
+                        )
+                        .map_err(|e| StorageError::Codec(anyhow::anyhow!(e)))?;
+                    Ok((key, value))
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::off_chain::OffChain,
+        fuel_core_graphql_api::storage::coins::{
+            owner_coin_id_key,
+            OwnedCoins,
+        },
+    };
+    use fuel_core_storage::{
+        kv_store::KeyValueStore,
+        StorageAsMut,
+    };
+    use std::sync::Arc;
+
+    fn database_with_coins(owners: &[Address], coins: u8) -> Database<OffChain> {
+        let mut database = Database::<OffChain>::default();
+        for owner in owners {
+            for i in 0..coins {
+                let utxo_id = UtxoId::new(TxId::from([i; 32]), i);
+                database
+                    .storage_as_mut::<OwnedCoins>()
+                    .insert(&owner_coin_id_key(owner, &utxo_id), &())
+                    .unwrap();
+            }
+        }
+        database
+    }
+
+    fn utxo_ids(
+        database: &Database<OffChain>,
+        owner: &Address,
+        direction: IterDirection,
+    ) -> Vec<(Address, UtxoId)> {
+        database
+            .iter_composite_key::<OwnedCoins, Address, UtxoId, _>(
+                Some(*owner),
+                None,
+                Some(direction),
+            )
+            .map(|res| res.unwrap().0)
+            .collect()
+    }
+
+    #[test]
+    fn split_key_returns_components() {
+        // Given
+        let owner = Address::from([1; 32]);
+        let utxo_id = UtxoId::new(TxId::from([2; 32]), 3);
+        let key = owner_coin_id_key(&owner, &utxo_id);
+
+        // When
+        let result = split_key::<Address, UtxoId>(&key);
+
+        // Then
+        assert_eq!(result.unwrap(), (owner, utxo_id));
+    }
+
+    #[test]
+    fn split_key_rejects_keys_of_wrong_size() {
+        let key = [0u8; 65];
+        assert!(split_key::<Address, UtxoId>(&key[..64]).is_err());
+        assert!(split_key::<Address, UtxoId>(&[0u8; 66]).is_err());
+        assert!(split_key::<Address, UtxoId>(&[]).is_err());
+    }
+
+    #[test]
+    fn iter_composite_key_stops_at_the_prefix_boundary() {
+        // Given
+        let owners = [
+            Address::from([1; 32]),
+            Address::from([2; 32]),
+            Address::from([3; 32]),
+        ];
+        let database = database_with_coins(&owners, 3);
+
+        // When
+        let forward = utxo_ids(&database, &owners[1], IterDirection::Forward);
+        let reverse = utxo_ids(&database, &owners[1], IterDirection::Reverse);
+
+        // Then
+        let expected = (0..3u8)
+            .map(|i| (owners[1], UtxoId::new(TxId::from([i; 32]), i)))
+            .collect::<Vec<_>>();
+        assert_eq!(forward, expected);
+        assert_eq!(reverse, expected.into_iter().rev().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn iter_composite_key_reverse_from_start() {
+        // Given
+        let owner = Address::from([1; 32]);
+        let database = database_with_coins(&[owner], 4);
+        let start = owner_coin_id_key(&owner, &UtxoId::new(TxId::from([2; 32]), 2));
+
+        // When
+        let result = database
+            .iter_composite_key::<OwnedCoins, Address, UtxoId, _>(
+                Some(owner),
+                Some(&start),
+                Some(IterDirection::Reverse),
+            )
+            .map(|res| res.unwrap().0 .1.output_index())
+            .collect::<Vec<_>>();
+
+        // Then
+        assert_eq!(result, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn iter_composite_key_returns_error_for_malformed_key() {
+        // Given
+        let owner = Address::from([1; 32]);
+        let database = database_with_coins(&[owner], 2);
+        let mut malformed = owner.to_vec();
+        malformed.push(0);
+        database
+            .data
+            .as_ref()
+            .put(
+                &malformed,
+                <OwnedCoins as TableWithBlueprint>::column(),
+                // The postcard encoding of `()` is empty.
+                Arc::new(vec![]),
+            )
+            .unwrap();
+
+        // When
+        let result = database
+            .iter_composite_key::<OwnedCoins, Address, UtxoId, _>(Some(owner), None, None)
+            .collect::<Vec<_>>();
+
+        // Then
+        assert_eq!(result.len(), 3);
+        assert!(result[0].is_err());
+        assert!(result[1..].iter().all(Result::is_ok));
+    }
+}
@@ -0,0 +1,453 @@
+use crate::{
+    database::{
+        database_description::{
+            off_chain::OffChain,
+            on_chain::OnChain,
+        },
+        Database,
+    },
+    graphql_api::storage::coins::OwnedCoins,
+};
+use fuel_core_storage::{
+    tables::{
+        Coins,
+        ContractsLatestUtxo,
+        FuelBlocks,
+        Messages,
+        SpentMessages,
+        Transactions,
+    },
+    Result as StorageResult,
+    StorageAsRef,
+};
+use fuel_core_types::{
+    entities::contract::ContractUtxoInfo,
+    fuel_tx::{
+        Address,
+        ContractId,
+        TxId,
+        UtxoId,
+    },
+    fuel_types::{
+        BlockHeight,
+        Nonce,
+    },
+};
+use std::ops::RangeInclusive;
+
+/// The part of the chain data verified by the
+/// [`crate::combined_database::CombinedDatabase::consistency_check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsistencyScope {
+    /// Verifies all relationships between columns.
+    All,
+    /// Verifies only the transactions of the blocks in the range of heights.
+    /// The relationships not keyed by the block height are skipped.
+    Heights(RangeInclusive<BlockHeight>),
+}
+
+/// The broken reference between columns of the chain data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsistencyViolation {
+    /// The transaction of the block is missing in the `Transactions` column.
+    MissingTransaction { height: BlockHeight, tx_id: TxId },
+    /// The `OwnedCoins` index of the off-chain database points at the coin
+    /// that is missing in the `Coins` column.
+    MissingOwnedCoin { owner: Address, utxo_id: UtxoId },
+    /// The `OwnedCoins` index of the off-chain database lists the coin
+    /// under the `indexed_owner`, but the coin belongs to the `coin_owner`.
+    OwnedCoinOwnerMismatch {
+        utxo_id: UtxoId,
+        indexed_owner: Address,
+        coin_owner: Address,
+    },
+    /// The latest UTXO of the contract doesn't point at its transaction: the block
+    /// of the tx pointer is missing, or has the `found` transaction at the tx index.
+    UnresolvedContractUtxo {
+        contract_id: ContractId,
+        utxo: ContractUtxoInfo,
+        found: Option<TxId>,
+    },
+    /// The nonce of the message is both in the `Messages` and `SpentMessages` columns.
+    DuplicateMessageNonce { nonce: Nonce },
+}
+
+/// The result of the consistency check.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ConsistencyReport {
+    /// The number of verified entries of all columns.
+    pub entries: u64,
+    /// The found violations in the order of checks.
+    pub violations: Vec<ConsistencyViolation>,
+}
+
+impl ConsistencyReport {
+    /// Returns `true` if the check is finished without violations.
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    fn push(&mut self, violation: Option<ConsistencyViolation>) {
+        self.entries = self.entries.saturating_add(1);
+        self.violations.extend(violation);
+    }
+}
+
+/// Verifies the references between columns of the `on_chain` and `off_chain` databases
+/// in the `scope`. Columns are streamed, so the check doesn't hold a whole column in memory.
+pub(crate) fn consistency_check(
+    on_chain: &Database<OnChain>,
+    off_chain: &Database<OffChain>,
+    scope: ConsistencyScope,
+) -> StorageResult<ConsistencyReport> {
+    let mut report = ConsistencyReport::default();
+    let heights = match scope {
+        ConsistencyScope::All => None,
+        ConsistencyScope::Heights(heights) => Some(heights),
+    };
+
+    let start = heights.as_ref().map(|heights| *heights.start());
+    for entry in on_chain.maintenance_scan::<FuelBlocks>(start.as_ref()) {
+        let (height, block) = entry?;
+        if heights
+            .as_ref()
+            .is_some_and(|heights| height > *heights.end())
+        {
+            break
+        }
+        for tx_id in block.transactions() {
+            if !on_chain.storage::<Transactions>().contains_key(tx_id)? {
+                report
+                    .violations
+                    .push(ConsistencyViolation::MissingTransaction {
+                        height,
+                        tx_id: *tx_id,
+                    });
+            }
+        }
+        report.push(None);
+    }
+    if heights.is_some() {
+        return Ok(report)
+    }
+
+    for entry in off_chain.maintenance_scan::<OwnedCoins>(None) {
+        let (key, _) = entry?;
+        let owner =
+            Address::try_from(&key[..Address::LEN]).expect("The owner is 32 bytes");
+        let tx_id = TxId::try_from(&key[Address::LEN..Address::LEN + TxId::LEN])
+            .expect("The transaction id is 32 bytes");
+        let utxo_id = UtxoId::new(tx_id, key[Address::LEN + TxId::LEN]);
+        let violation = match on_chain.storage::<Coins>().get(&utxo_id)? {
+            None => Some(ConsistencyViolation::MissingOwnedCoin { owner, utxo_id }),
+            Some(coin) if *coin.owner() != owner => {
+                Some(ConsistencyViolation::OwnedCoinOwnerMismatch {
+                    utxo_id,
+                    indexed_owner: owner,
+                    coin_owner: *coin.owner(),
+                })
+            }
+            Some(_) => None,
+        };
+        report.push(violation);
+    }
+
+    // Pointers at or below the genesis block come from the chain config,
+    // so they don't have a transaction in the chain.
+    let genesis = on_chain
+        .iter_all::<FuelBlocks>(None)
+        .next()
+        .transpose()?
+        .map(|(height, _)| height);
+    for entry in on_chain.maintenance_scan::<ContractsLatestUtxo>(None) {
+        let (contract_id, utxo) = entry?;
+        let height = utxo.tx_pointer.block_height();
+        if genesis.map_or(true, |genesis| height <= genesis) {
+            report.push(None);
+            continue
+        }
+        let found = on_chain
+            .storage::<FuelBlocks>()
+            .get(&height)?
+            .and_then(|block| {
+                block
+                    .transactions()
+                    .get(usize::from(utxo.tx_pointer.tx_index()))
+                    .copied()
+            });
+        let violation = (found != Some(*utxo.utxo_id.tx_id())).then_some(
+            ConsistencyViolation::UnresolvedContractUtxo {
+                contract_id,
+                utxo,
+                found,
+            },
+        );
+        report.push(violation);
+    }
+
+    for entry in on_chain.maintenance_scan::<Messages>(None) {
+        let (nonce, _) = entry?;
+        let violation = on_chain
+            .storage::<SpentMessages>()
+            .contains_key(&nonce)?
+            .then_some(ConsistencyViolation::DuplicateMessageNonce { nonce });
+        report.push(violation);
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combined_database::CombinedDatabase;
+    use fuel_core_storage::{
+        column::Column,
+        kv_store::KeyValueStore,
+        structured_storage::TableWithBlueprint,
+        StorageAsMut,
+    };
+    use fuel_core_types::{
+        blockchain::{
+            block::CompressedBlock,
+            header::BlockHeader,
+        },
+        entities::{
+            coins::coin::{
+                CompressedCoin,
+                CompressedCoinV1,
+            },
+            message::MessageV1,
+        },
+        fuel_tx::{
+            Transaction,
+            TxPointer,
+        },
+    };
+    use std::sync::Arc;
+
+    const OWNER: Address = Address::new([3; 32]);
+
+    fn tx_id(height: u8) -> TxId {
+        TxId::from([height; 32])
+    }
+
+    fn block(height: u32, transactions: Vec<TxId>) -> CompressedBlock {
+        let mut header = BlockHeader::default();
+        header.set_block_height(height.into());
+        CompressedBlock::test(header, transactions)
+    }
+
+    /// Returns the database with the genesis and two blocks with one transaction each,
+    /// the owned coin of each transaction, the contract created at the height 2,
+    /// and the unspent message.
+    fn consistent_database() -> CombinedDatabase {
+        let database = CombinedDatabase::default();
+        let mut on_chain = database.on_chain().clone();
+        let mut off_chain = database.off_chain().clone();
+        on_chain
+            .storage_as_mut::<FuelBlocks>()
+            .insert(&0.into(), &block(0, vec![]))
+            .unwrap();
+        for height in 1..=2u8 {
+            let tx_id = tx_id(height);
+            on_chain
+                .storage_as_mut::<Transactions>()
+                .insert(&tx_id, &Transaction::default())
+                .unwrap();
+            on_chain
+                .storage_as_mut::<FuelBlocks>()
+                .insert(
+                    &u32::from(height).into(),
+                    &block(height.into(), vec![tx_id]),
+                )
+                .unwrap();
+            let utxo_id = UtxoId::new(tx_id, 0);
+            let coin: CompressedCoin = CompressedCoinV1 {
+                owner: OWNER,
+                ..Default::default()
+            }
+            .into();
+            on_chain
+                .storage_as_mut::<Coins>()
+                .insert(&utxo_id, &coin)
+                .unwrap();
+            off_chain
+                .storage_as_mut::<OwnedCoins>()
+                .insert(
+                    &crate::graphql_api::storage::coins::owner_coin_id_key(
+                        &OWNER, &utxo_id,
+                    ),
+                    &(),
+                )
+                .unwrap();
+        }
+        for (contract, height) in [(1u8, 0u8), (2, 2)] {
+            on_chain
+                .storage_as_mut::<ContractsLatestUtxo>()
+                .insert(
+                    &ContractId::from([contract; 32]),
+                    &ContractUtxoInfo {
+                        utxo_id: UtxoId::new(tx_id(height), 1),
+                        tx_pointer: TxPointer::new(u32::from(height).into(), 0),
+                    },
+                )
+                .unwrap();
+        }
+        let message = MessageV1 {
+            nonce: Nonce::from([5; 32]),
+            ..Default::default()
+        }
+        .into();
+        on_chain
+            .storage_as_mut::<Messages>()
+            .insert(&Nonce::from([5; 32]), &message)
+            .unwrap();
+        database
+    }
+
+    #[test]
+    fn consistency_check_passes_for_consistent_database() {
+        // Given
+        let database = consistent_database();
+
+        // When
+        let report = database.consistency_check(ConsistencyScope::All).unwrap();
+
+        // Then
+        assert!(report.is_ok(), "{report:?}");
+        // 3 blocks, 2 owned coins, 2 contracts, and 1 message.
+        assert_eq!(report.entries, 8);
+    }
+
+    #[test]
+    fn consistency_check_reports_corrupted_references() {
+        // Given
+        let database = consistent_database();
+        let on_chain = database.on_chain().data.as_ref();
+        let off_chain = database.off_chain().data.as_ref();
+        // The transaction of the block 1 is removed.
+        on_chain
+            .delete(tx_id(1).as_ref(), Column::Transactions)
+            .unwrap();
+        // The coin of the transaction 2 belongs to another owner.
+        let other_owner = Address::from([4; 32]);
+        let coin: CompressedCoin = CompressedCoinV1 {
+            owner: other_owner,
+            ..Default::default()
+        }
+        .into();
+        database
+            .on_chain()
+            .clone()
+            .storage_as_mut::<Coins>()
+            .insert(&UtxoId::new(tx_id(2), 0), &coin)
+            .unwrap();
+        // The index points at the missing coin.
+        let missing = UtxoId::new(tx_id(7), 3);
+        off_chain
+            .put(
+                &crate::graphql_api::storage::coins::owner_coin_id_key(&OWNER, &missing),
+                OwnedCoins::column(),
+                Arc::new(vec![]),
+            )
+            .unwrap();
+        // The contract points at the transaction index out of the block 2.
+        let contract_id = ContractId::from([2; 32]);
+        let utxo = ContractUtxoInfo {
+            utxo_id: UtxoId::new(tx_id(2), 1),
+            tx_pointer: TxPointer::new(2.into(), 1),
+        };
+        database
+            .on_chain()
+            .clone()
+            .storage_as_mut::<ContractsLatestUtxo>()
+            .insert(&contract_id, &utxo)
+            .unwrap();
+        // The unspent message is also spent.
+        let nonce = Nonce::from([5; 32]);
+        on_chain
+            .put(nonce.as_ref(), Column::SpentMessages, Arc::new(vec![]))
+            .unwrap();
+
+        // When
+        let report = database.consistency_check(ConsistencyScope::All).unwrap();
+
+        // Then
+        assert_eq!(
+            report.violations,
+            vec![
+                ConsistencyViolation::MissingTransaction {
+                    height: 1.into(),
+                    tx_id: tx_id(1),
+                },
+                ConsistencyViolation::OwnedCoinOwnerMismatch {
+                    utxo_id: UtxoId::new(tx_id(2), 0),
+                    indexed_owner: OWNER,
+                    coin_owner: other_owner,
+                },
+                ConsistencyViolation::MissingOwnedCoin {
+                    owner: OWNER,
+                    utxo_id: missing,
+                },
+                ConsistencyViolation::UnresolvedContractUtxo {
+                    contract_id,
+                    utxo,
+                    found: None,
+                },
+                ConsistencyViolation::DuplicateMessageNonce { nonce },
+            ]
+        );
+    }
+
+    #[test]
+    fn consistency_check_of_heights_checks_only_blocks_in_range() {
+        // Given
+        let database = consistent_database();
+        let on_chain = database.on_chain().data.as_ref();
+        for height in 1..=2 {
+            on_chain
+                .delete(tx_id(height).as_ref(), Column::Transactions)
+                .unwrap();
+        }
+        on_chain
+            .put(&[5; 32], Column::SpentMessages, Arc::new(vec![]))
+            .unwrap();
+
+        // When
+        let report = database
+            .consistency_check(ConsistencyScope::Heights(2.into()..=2.into()))
+            .unwrap();
+
+        // Then
+        assert_eq!(report.entries, 1);
+        assert_eq!(
+            report.violations,
+            vec![ConsistencyViolation::MissingTransaction {
+                height: 2.into(),
+                tx_id: tx_id(2),
+            }]
+        );
+    }
+
+    #[test]
+    fn consistency_check_ignores_commits_after_it_started() {
+        // Given
+        let database = consistent_database();
+        let snapshot = database.on_chain().snapshot().unwrap();
+
+        // When
+        database
+            .on_chain()
+            .data
+            .as_ref()
+            .delete(tx_id(1).as_ref(), Column::Transactions)
+            .unwrap();
+        let report =
+            consistency_check(&snapshot, database.off_chain(), ConsistencyScope::All)
+                .unwrap();
+
+        // Then
+        assert!(report.is_ok(), "{report:?}");
+    }
+}
@@ -2,6 +2,7 @@ use crate::database::Database;
 use fuel_core_chain_config::ContractConfig;
 use fuel_core_storage::{
     iter::IterDirection,
+    not_found,
     tables::{
         ContractsAssets,
         ContractsInfo,
@@ -31,15 +32,14 @@ impl Database {
         let code: Vec<u8> = self
             .storage::<ContractsRawCode>()
             .get(&contract_id)?
-            .unwrap()
+            .ok_or(not_found!(ContractsRawCode))?
             .into_owned()
             .into();
 
         let (salt, _) = self
             .storage::<ContractsInfo>()
-            .get(&contract_id)
-            .unwrap()
-            .expect("Contract does not exist")
+            .get(&contract_id)?
+            .ok_or(not_found!(ContractsInfo))?
             .into_owned();
 
         let ContractUtxoInfo {
@@ -47,9 +47,8 @@ impl Database {
             tx_pointer,
         } = self
             .storage::<ContractsLatestUtxo>()
-            .get(&contract_id)
-            .unwrap()
-            .expect("contract does not exist")
+            .get(&contract_id)?
+            .ok_or(not_found!(ContractsLatestUtxo))?
             .into_owned();
 
         let state = Some(
@@ -59,7 +58,6 @@ impl Database {
 
                     Ok((*key.state_key(), value))
                 })
-                .filter(|val| val.is_ok())
                 .collect::<StorageResult<Vec<_>>>()?,
         );
 
@@ -70,7 +68,6 @@ impl Database {
 
                     Ok((*key.asset_id(), value))
                 })
-                .filter(|val| val.is_ok())
                 .collect::<StorageResult<Vec<_>>>()?,
         );
 
@@ -95,21 +92,30 @@ impl Database {
     ) -> impl Iterator<Item = StorageResult<(AssetId, Word)>> + '_ {
         let start_asset =
             start_asset.map(|asset| ContractsAssetKey::new(&contract, &asset));
-        self.iter_all_filtered::<ContractsAssets, _>(
+        self.iter_composite_key::<ContractsAssets, ContractId, AssetId, _>(
             Some(contract),
             start_asset.as_ref(),
             direction,
         )
-        .map(|res| res.map(|(key, balance)| (*key.asset_id(), balance)))
+        .map(|res| res.map(|((_, asset_id), balance)| (asset_id, balance)))
     }
 
-    pub fn get_contract_config(&self) -> StorageResult<Option<Vec<ContractConfig>>> {
-        let configs = self
-            .iter_all::<ContractsRawCode>(None)
-            .map(|raw_contract_id| -> StorageResult<ContractConfig> {
+    /// Returns the iterator over configs of all contracts. Each config
+    /// is read with the state and balances of its contract.
+    pub fn contract_configs(
+        &self,
+    ) -> impl Iterator<Item = StorageResult<ContractConfig>> + '_ {
+        self.maintenance_scan::<ContractsRawCode>(None).map(
+            |raw_contract_id| -> StorageResult<ContractConfig> {
                 let contract_id = raw_contract_id?.0;
                 self.get_contract_config_by_id(contract_id)
-            })
+            },
+        )
+    }
+
+    pub fn get_contract_config(&self) -> StorageResult<Option<Vec<ContractConfig>>> {
+        let configs = self
+            .contract_configs()
             .collect::<StorageResult<Vec<ContractConfig>>>()?;
 
         Ok(Some(configs))
@@ -1,3 +1,13 @@
+use crate::{
+    database::{
+        migration::Migrations,
+        Result as DatabaseResult,
+    },
+    state::{
+        column_config::ColumnConfig,
+        TransactableStorage,
+    },
+};
 use core::fmt::Debug;
 use fuel_core_storage::kv_store::StorageColumn;
 
@@ -5,10 +15,62 @@ pub mod off_chain;
 pub mod on_chain;
 pub mod relayer;
 
+/// Asserts in debug builds that the `key` has the [`DatabaseDescription::key_size`]
+/// of the `column`.
+pub(crate) fn debug_assert_key_size<Description>(column: &Description::Column, key: &[u8])
+where
+    Description: DatabaseDescription,
+{
+    if let Some(size) = Description::key_size(column) {
+        debug_assert_eq!(
+            key.len(),
+            size,
+            "The key {key:?} of the column {} doesn't have {size} bytes",
+            column.name()
+        );
+    }
+}
+
+/// The column removed from the database. Its column family is dropped on the next open,
+/// and its id is never reused by another column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetiredColumn {
+    /// The id of the column.
+    pub id: u32,
+    /// The name of the column before it was removed.
+    pub name: &'static str,
+}
+
+/// Columns removed from the database, see [`DatabaseDescription::retired_columns`].
+pub type RetiredColumns = &'static [RetiredColumn];
+
+/// Returns the number of slots of columns of the `Description`, i.e. the highest id
+/// of columns and [retired](DatabaseDescription::retired_columns) columns plus one.
+/// In-memory stores index columns by their ids, so ids of retired columns stay free.
+pub(crate) fn column_slots<Description>() -> usize
+where
+    Description: DatabaseDescription,
+{
+    enum_iterator::all::<Description::Column>()
+        .map(|column| column.as_usize())
+        .chain(
+            Description::retired_columns()
+                .iter()
+                .map(|column| column.id as usize),
+        )
+        .max()
+        .map_or(0, |id| id.saturating_add(1))
+}
+
 /// The description of the database that makes it unique.
 pub trait DatabaseDescription: 'static + Clone + Debug + Send + Sync {
     /// The type of the column used by the database.
-    type Column: StorageColumn + strum::EnumCount + enum_iterator::Sequence;
+    type Column: StorageColumn
+        + Ord
+        + Send
+        + Sync
+        + strum::EnumCount
+        + enum_iterator::Sequence;
     /// The type of the height of the database used to track commits.
     type Height: Copy;
 
@@ -23,6 +85,58 @@ pub trait DatabaseDescription: 'static + Clone + Debug + Send + Sync {
 
     /// Returns the prefix for the column.
     fn prefix(column: &Self::Column) -> Option<usize>;
+
+    /// Returns the size of keys of the column if all keys have the same size,
+    /// e.g. the columns keyed by heights. The size follows from the
+    /// [`StorageColumn::key_format`] of the column, and keys of columns with the size
+    /// are checked by debug assertions on writes.
+    fn key_size(column: &Self::Column) -> Option<usize> {
+        column.key_format().size()
+    }
+
+    /// Returns the debug representation of the decoded entry of the column for
+    /// the [`crate::database::inspect::Inspector`], or the error of the decoding.
+    /// By default, columns don't have known codecs and return `None`.
+    fn debug_entry(
+        _: &Self::Column,
+        _: &[u8],
+        _: &[u8],
+    ) -> Option<Result<String, String>> {
+        None
+    }
+
+    /// Returns the columns removed from the database. Ids of columns are persisted,
+    /// so the removed column is retired instead of reusing its id by another column,
+    /// see [`column_lifecycle`](crate::database::column_lifecycle).
+    /// By default, there are no retired columns.
+    fn retired_columns() -> RetiredColumns {
+        &[]
+    }
+
+    /// Returns the default configuration of the column in the RocksDB.
+    fn column_config(_: &Self::Column) -> ColumnConfig {
+        ColumnConfig::default()
+    }
+
+    /// Returns the migrations of the database ordered by the schema version.
+    /// By default, there are no migrations.
+    fn migrations() -> Migrations<Self> {
+        Migrations::default()
+    }
+
+    /// Migrates the data of the database from the schema version `from` to the `to`.
+    /// It is called when the data directory has an older schema version than [`Self::version`].
+    /// The schema version is updated only after the migration completes successfully.
+    ///
+    /// By default, it runs the [`Self::migrations`], and the database can't be opened
+    /// if any migration between versions is missing.
+    fn migrate(
+        from: u32,
+        to: u32,
+        db: &dyn TransactableStorage<Column = Self::Column>,
+    ) -> DatabaseResult<()> {
+        Self::migrations().migrate(from, to, db)
+    }
 }
 
 /// The metadata of the database contains information about the version and its height.
@@ -46,3 +160,182 @@ impl<Height> DatabaseMetadata<Height> {
         }
     }
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use fuel_core_storage::{
+        iter::{
+            IterDirection,
+            IteratorableStore,
+        },
+        kv_store::{
+            KeyValueStore,
+            MergeOperator,
+            Value,
+            WriteOperation,
+        },
+    };
+    use std::sync::Arc;
+
+    /// The column of the [`MergeDatabase`]. Some of the columns support merges.
+    #[derive(
+        Debug,
+        Copy,
+        Clone,
+        strum_macros::EnumCount,
+        strum_macros::IntoStaticStr,
+        PartialEq,
+        Eq,
+        PartialOrd,
+        Ord,
+        enum_iterator::Sequence,
+        Hash,
+    )]
+    pub enum MergeColumn {
+        Metadata,
+        Counters,
+        Logs,
+    }
+
+    impl StorageColumn for MergeColumn {
+        fn name(&self) -> &'static str {
+            self.into()
+        }
+
+        fn id(&self) -> u32 {
+            *self as u32
+        }
+
+        fn merge_operator(&self) -> Option<MergeOperator> {
+            match self {
+                MergeColumn::Metadata => None,
+                MergeColumn::Counters => Some(MergeOperator::U64Add),
+                MergeColumn::Logs => Some(MergeOperator::Append),
+            }
+        }
+    }
+
+    /// The database used to test merges.
+    #[derive(Clone, Debug)]
+    pub struct MergeDatabase;
+
+    impl DatabaseDescription for MergeDatabase {
+        type Column = MergeColumn;
+        type Height = u32;
+
+        fn version() -> u32 {
+            0
+        }
+
+        fn name() -> &'static str {
+            "merge"
+        }
+
+        fn metadata_column() -> Self::Column {
+            MergeColumn::Metadata
+        }
+
+        fn prefix(_: &Self::Column) -> Option<usize> {
+            None
+        }
+    }
+
+    fn counter(value: u64) -> Value {
+        Arc::new(value.to_be_bytes().to_vec())
+    }
+
+    fn log(value: &[u8]) -> Value {
+        Arc::new(value.to_vec())
+    }
+
+    /// The values stored before the [`merge_operations`].
+    pub(crate) fn merge_base() -> Vec<(Vec<u8>, MergeColumn, WriteOperation)> {
+        vec![
+            (
+                vec![1],
+                MergeColumn::Counters,
+                WriteOperation::Insert(counter(1000)),
+            ),
+            (
+                vec![4],
+                MergeColumn::Counters,
+                WriteOperation::Insert(counter(40)),
+            ),
+            (
+                vec![2],
+                MergeColumn::Logs,
+                WriteOperation::Insert(log(b"base")),
+            ),
+        ]
+    }
+
+    /// The interleaving of puts, merges, and deletes over the [`MergeColumn`]s.
+    pub(crate) fn merge_operations() -> Vec<(Vec<u8>, MergeColumn, WriteOperation)> {
+        use WriteOperation::*;
+        vec![
+            (vec![1], MergeColumn::Counters, Merge(counter(5))),
+            (vec![2], MergeColumn::Counters, Insert(counter(10))),
+            (vec![1], MergeColumn::Logs, Merge(log(b"a"))),
+            (vec![1], MergeColumn::Counters, Merge(counter(7))),
+            (vec![2], MergeColumn::Counters, Remove),
+            (vec![3], MergeColumn::Counters, Merge(counter(2))),
+            (vec![1], MergeColumn::Logs, Merge(log(b"b"))),
+            (vec![4], MergeColumn::Counters, Merge(counter(2))),
+            (vec![1], MergeColumn::Counters, Insert(counter(100))),
+            (vec![2], MergeColumn::Counters, Merge(counter(3))),
+            (vec![1], MergeColumn::Logs, Insert(log(b"x"))),
+            (vec![3], MergeColumn::Counters, Remove),
+            (vec![2], MergeColumn::Logs, Merge(log(b"p"))),
+            (vec![1], MergeColumn::Counters, Merge(counter(1))),
+            (vec![1], MergeColumn::Logs, Merge(log(b"c"))),
+            (vec![4], MergeColumn::Counters, Merge(counter(u64::MAX))),
+        ]
+    }
+
+    /// The state after the [`merge_operations`] are applied to the [`merge_base`].
+    pub(crate) fn merged_state() -> Vec<(MergeColumn, Vec<u8>, Value)> {
+        vec![
+            (MergeColumn::Counters, vec![1], counter(101)),
+            (MergeColumn::Counters, vec![2], counter(3)),
+            (MergeColumn::Counters, vec![4], counter(u64::MAX)),
+            (MergeColumn::Logs, vec![1], log(b"xc")),
+            (MergeColumn::Logs, vec![2], log(b"basep")),
+        ]
+    }
+
+    /// Applies the `operations` one by one via the methods of the `store`.
+    pub(crate) fn apply_merge_operations<S>(
+        store: &S,
+        operations: Vec<(Vec<u8>, MergeColumn, WriteOperation)>,
+    ) where
+        S: KeyValueStore<Column = MergeColumn> + ?Sized,
+    {
+        for (key, column, operation) in operations {
+            match operation {
+                WriteOperation::Insert(value) => store.put(&key, column, value),
+                WriteOperation::Remove => store.delete(&key, column),
+                WriteOperation::Merge(operand) => store.merge(&key, column, operand),
+            }
+            .unwrap();
+        }
+    }
+
+    /// Returns all entries of the `store`.
+    pub(crate) fn merge_entries<S>(store: &S) -> Vec<(MergeColumn, Vec<u8>, Value)>
+    where
+        S: IteratorableStore<Column = MergeColumn> + ?Sized,
+    {
+        enum_iterator::all::<MergeColumn>()
+            .flat_map(|column| {
+                store
+                    .iter_all(column, None, None, IterDirection::Forward)
+                    .map(move |entry| {
+                        let (key, value) = entry.unwrap();
+                        (column, key, value)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
@@ -1,4 +1,36 @@
-use crate::database::database_description::DatabaseDescription;
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        inspect::debug_entry,
+        metadata::MetadataEntry,
+        rollback::UndoLog,
+    },
+    state::column_config::ColumnConfig,
+};
+use fuel_core_storage::{
+    tables::{
+        merkle::{
+            ContractsAssetsMerkleData,
+            ContractsAssetsMerkleMetadata,
+            ContractsStateMerkleData,
+            ContractsStateMerkleMetadata,
+            FuelBlockMerkleData,
+            FuelBlockMerkleMetadata,
+        },
+        Coins,
+        ContractsAssets,
+        ContractsInfo,
+        ContractsLatestUtxo,
+        ContractsRawCode,
+        ContractsState,
+        FuelBlocks,
+        Messages,
+        ProcessedTransactions,
+        SealedBlockConsensus,
+        SpentMessages,
+        Transactions,
+    },
+};
 use fuel_core_types::fuel_types::BlockHeight;
 
 #[derive(Clone, Debug)]
@@ -29,4 +61,68 @@ impl DatabaseDescription for OnChain {
             _ => None,
         }
     }
+
+    fn column_config(column: &Self::Column) -> ColumnConfig {
+        match column {
+            Self::Column::Transactions
+            | Self::Column::FuelBlocks
+            | Self::Column::FuelBlockConsensus
+            | Self::Column::ProcessedTransactions
+            | Self::Column::SpentMessages => ColumnConfig::write_once(),
+            Self::Column::UndoLog | Self::Column::ChangeLog => ColumnConfig::write_only(),
+            _ => ColumnConfig::default(),
+        }
+    }
+
+    fn debug_entry(
+        column: &Self::Column,
+        key: &[u8],
+        value: &[u8],
+    ) -> Option<Result<String, String>> {
+        let decoded = match column {
+            Self::Column::ContractsRawCode => debug_entry::<ContractsRawCode>(key, value),
+            Self::Column::ContractsInfo => debug_entry::<ContractsInfo>(key, value),
+            Self::Column::ContractsState => debug_entry::<ContractsState>(key, value),
+            Self::Column::ContractsLatestUtxo => {
+                debug_entry::<ContractsLatestUtxo>(key, value)
+            }
+            Self::Column::ContractsAssets => debug_entry::<ContractsAssets>(key, value),
+            Self::Column::Coins => debug_entry::<Coins>(key, value),
+            Self::Column::Transactions => debug_entry::<Transactions>(key, value),
+            Self::Column::FuelBlocks => debug_entry::<FuelBlocks>(key, value),
+            Self::Column::FuelBlockMerkleData => {
+                debug_entry::<FuelBlockMerkleData>(key, value)
+            }
+            Self::Column::FuelBlockMerkleMetadata => {
+                debug_entry::<FuelBlockMerkleMetadata>(key, value)
+            }
+            Self::Column::SpentMessages => debug_entry::<SpentMessages>(key, value),
+            Self::Column::ContractsAssetsMerkleData => {
+                debug_entry::<ContractsAssetsMerkleData>(key, value)
+            }
+            Self::Column::ContractsAssetsMerkleMetadata => {
+                debug_entry::<ContractsAssetsMerkleMetadata>(key, value)
+            }
+            Self::Column::ContractsStateMerkleData => {
+                debug_entry::<ContractsStateMerkleData>(key, value)
+            }
+            Self::Column::ContractsStateMerkleMetadata => {
+                debug_entry::<ContractsStateMerkleMetadata>(key, value)
+            }
+            Self::Column::Messages => debug_entry::<Messages>(key, value),
+            Self::Column::ProcessedTransactions => {
+                debug_entry::<ProcessedTransactions>(key, value)
+            }
+            // Values of the metadata have different codecs, so only the key is decoded.
+            Self::Column::Metadata => MetadataEntry::of_key(key)
+                .map(|entry| format!("{entry:?}"))
+                .ok_or_else(|| "The metadata key is unknown".to_string()),
+            Self::Column::FuelBlockConsensus => {
+                debug_entry::<SealedBlockConsensus>(key, value)
+            }
+            Self::Column::UndoLog => debug_entry::<UndoLog>(key, value),
+            Self::Column::ChangeLog => return None,
+        };
+        Some(decoded)
+    }
 }
@@ -11,6 +11,8 @@ use fuel_core_types::blockchain::primitives::DaBlockHeight;
     strum_macros::IntoStaticStr,
     PartialEq,
     Eq,
+    PartialOrd,
+    Ord,
     enum_iterator::Sequence,
     Hash,
 )]
@@ -0,0 +1,281 @@
+//! The advisory lock of the data directory that allows only one writer of the database.
+//!
+//! The RocksDB refuses the second open by its own `LOCK` file too, but with the internal
+//! error that doesn't tell who holds the database. The [`DirLock`] is taken before the
+//! database is opened and records the owner, so the second open fails with
+//! [`DatabaseError::Locked`].
+
+use crate::database::{
+    Error as DatabaseError,
+    Result as DatabaseResult,
+};
+use fs2::FileExt;
+use std::{
+    fs::{
+        File,
+        OpenOptions,
+    },
+    io::Write,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+/// The name of the lock file in the data directory.
+pub const LOCK_FILE_NAME: &str = "fuel.lock";
+
+/// The process that holds the lock, as recorded in the lock file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockOwner {
+    /// The ID of the process.
+    pub pid: u32,
+    /// The time of the acquisition of the lock in seconds since the unix epoch.
+    pub since: u64,
+}
+
+/// The exclusive lock of the data directory. The lock is released when it is dropped,
+/// including the unwinding after the panic, and by the OS when the process exits.
+/// The lock file stays in the directory, and the next owner overwrites it.
+#[derive(Debug)]
+pub struct DirLock {
+    file: File,
+    dir: PathBuf,
+}
+
+impl DirLock {
+    /// Acquires the lock of the `dir`, creating the directory if it is missing.
+    /// Fails with [`DatabaseError::Locked`] if another owner holds the lock.
+    pub fn acquire(dir: &Path) -> DatabaseResult<Self> {
+        std::fs::create_dir_all(dir).map_err(|e| {
+            anyhow::anyhow!("Unable to create the directory {}: {e}", dir.display())
+        })?;
+        let path = dir.join(LOCK_FILE_NAME);
+        // The file isn't truncated before the lock is taken, so the owner stays readable.
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|e| {
+                anyhow::anyhow!("Unable to open the lock file {}: {e}", path.display())
+            })?;
+        if let Err(e) = file.try_lock_exclusive() {
+            if e.kind() == fs2::lock_contended_error().kind() {
+                return Err(locked_error(dir))
+            }
+            return Err(anyhow::anyhow!(
+                "Unable to lock the lock file {}: {e}",
+                path.display()
+            )
+            .into())
+        }
+
+        let since = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+        let owner = format!("{} {since}\n", std::process::id());
+        file.set_len(0)
+            .and_then(|_| file.write_all(owner.as_bytes()))
+            .and_then(|_| file.sync_data())
+            .map_err(|e| {
+                anyhow::anyhow!("Unable to write the lock file {}: {e}", path.display())
+            })?;
+        Ok(Self {
+            file,
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    /// Returns the locked directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Removes the lock of the `dir` that is left held after the crash, e.g. by the child
+    /// process that inherited the lock file. The lock is removed only if the process
+    /// recorded in it doesn't exist anymore, otherwise it fails with [`DatabaseError::Locked`].
+    /// Returns `false` if the directory isn't locked.
+    ///
+    /// The existence of the process is only checked on Linux,
+    /// on other platforms the held lock is never removed.
+    pub fn force_unlock(dir: &Path) -> DatabaseResult<bool> {
+        let path = dir.join(LOCK_FILE_NAME);
+        let Ok(file) = OpenOptions::new().read(true).write(true).open(&path) else {
+            return Ok(false)
+        };
+        if file.try_lock_exclusive().is_ok() {
+            // Nobody holds the lock, the next owner overwrites the file.
+            return Ok(false)
+        }
+        match read_owner(&path) {
+            Some(owner) if !process_exists(owner.pid) => {
+                std::fs::remove_file(&path).map_err(|e| {
+                    anyhow::anyhow!(
+                        "Unable to remove the lock file {}: {e}",
+                        path.display()
+                    )
+                })?;
+                tracing::warn!(
+                    "Removed the lock of {} held by the exited process {}",
+                    dir.display(),
+                    owner.pid
+                );
+                Ok(true)
+            }
+            _ => Err(locked_error(dir)),
+        }
+    }
+}
+
+impl Drop for DirLock {
+    fn drop(&mut self) {
+        // Closing the file releases the lock too, the explicit unlock just doesn't wait for it.
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+/// Returns the owner recorded in the lock file at the `path`.
+pub fn read_owner(path: &Path) -> Option<LockOwner> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let mut parts = content.split_whitespace();
+    let pid = parts.next()?.parse().ok()?;
+    let since = parts.next()?.parse().ok()?;
+    Some(LockOwner { pid, since })
+}
+
+fn locked_error(dir: &Path) -> DatabaseError {
+    match read_owner(&dir.join(LOCK_FILE_NAME)) {
+        Some(LockOwner { pid, since }) => DatabaseError::Locked {
+            path: dir.to_path_buf(),
+            pid,
+            since,
+        },
+        // The owner writes itself right after taking the lock.
+        None => anyhow::anyhow!(
+            "The database at {} is in use by another process",
+            dir.display()
+        )
+        .into(),
+    }
+}
+
+fn process_exists(pid: u32) -> bool {
+    if pid == std::process::id() {
+        return true
+    }
+    #[cfg(target_os = "linux")]
+    {
+        Path::new("/proc").join(pid.to_string()).exists()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_acquire_fails_with_the_owner_of_the_lock() {
+        // Given
+        let dir = tempfile::tempdir().unwrap();
+        let _lock = DirLock::acquire(dir.path()).unwrap();
+
+        // When
+        let result = std::thread::scope(|scope| {
+            scope.spawn(|| DirLock::acquire(dir.path())).join().unwrap()
+        });
+
+        // Then
+        let err = result.unwrap_err();
+        assert!(
+            matches!(err, DatabaseError::Locked { pid, .. } if pid == std::process::id())
+        );
+        let message = err.to_string();
+        assert!(
+            message.contains(&format!("is in use by PID {}", std::process::id())),
+            "{message}"
+        );
+        assert!(
+            message.contains(&dir.path().display().to_string()),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn lock_is_released_on_drop_and_after_the_panic() {
+        // Given
+        let dir = tempfile::tempdir().unwrap();
+        drop(DirLock::acquire(dir.path()).unwrap());
+        let panicked = std::panic::catch_unwind(|| {
+            let _lock = DirLock::acquire(dir.path()).unwrap();
+            panic!("The owner panics");
+        });
+
+        // When
+        let result = DirLock::acquire(dir.path());
+
+        // Then
+        assert!(panicked.is_err());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn force_unlock_refuses_the_lock_of_the_running_process() {
+        // Given
+        let dir = tempfile::tempdir().unwrap();
+        let _lock = DirLock::acquire(dir.path()).unwrap();
+
+        // When
+        let result = DirLock::force_unlock(dir.path());
+
+        // Then
+        assert!(matches!(result, Err(DatabaseError::Locked { .. })));
+        assert!(DirLock::acquire(dir.path()).is_err());
+    }
+
+    #[test]
+    fn force_unlock_of_the_unlocked_directory_does_nothing() {
+        // Given
+        let dir = tempfile::tempdir().unwrap();
+        drop(DirLock::acquire(dir.path()).unwrap());
+
+        // When
+        let removed = DirLock::force_unlock(dir.path()).unwrap();
+
+        // Then
+        assert!(!removed);
+        assert!(dir.path().join(LOCK_FILE_NAME).exists());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn force_unlock_removes_the_lock_of_the_exited_process() {
+        // Given
+        let dir = tempfile::tempdir().unwrap();
+        let stale = DirLock::acquire(dir.path()).unwrap();
+        std::fs::write(dir.path().join(LOCK_FILE_NAME), format!("{} 0\n", u32::MAX))
+            .unwrap();
+
+        // When
+        let removed = DirLock::force_unlock(dir.path()).unwrap();
+
+        // Then
+        assert!(removed);
+        let lock = DirLock::acquire(dir.path()).unwrap();
+        assert_eq!(
+            read_owner(&dir.path().join(LOCK_FILE_NAME)).map(|owner| owner.pid),
+            Some(std::process::id())
+        );
+        drop((stale, lock));
+    }
+}
@@ -0,0 +1,399 @@
+//! The portable dump of one column. The dump doesn't depend on the backend of the database,
+//! so it moves the state between the RocksDB and the in-memory database, or between nodes
+//! with different versions of the RocksDB.
+//!
+//! The dump starts with the header: the [`DUMP_MAGIC`], the [`DUMP_VERSION`] and the id of
+//! the column. Each entry is the [`RECORD_TAG`] followed by the length-prefixed key and
+//! the length-prefixed value. The dump ends with the [`END_TAG`] and the number of entries,
+//! so the truncated dump is detected. Lengths are big-endian `u32`, and the number
+//! of entries is big-endian `u64`.
+
+use crate::database::{
+    database_description::DatabaseDescription,
+    Database,
+};
+use fuel_core_storage::{
+    iter::{
+        IterConfig,
+        IterOptions,
+    },
+    kv_store::{
+        BatchOperations,
+        KeyValueStore,
+        StorageColumn,
+        WriteOperation,
+    },
+    Error as StorageError,
+    Result as StorageResult,
+};
+use std::io::{
+    BufReader,
+    BufWriter,
+    Read,
+    Write,
+};
+
+/// The first bytes of the dump.
+pub const DUMP_MAGIC: [u8; 8] = *b"FUELDUMP";
+
+/// The version of the dump format.
+pub const DUMP_VERSION: u32 = 1;
+
+/// The tag of the entry of the dump.
+const RECORD_TAG: u8 = 1;
+
+/// The tag of the end of the dump.
+const END_TAG: u8 = 0;
+
+/// The maximum number of entries imported by one batch.
+pub const IMPORT_CHUNK_SIZE: usize = 10_000;
+
+/// The way the dump is imported into the column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Entries of the column are removed before the import.
+    Replace,
+    /// Imported entries overwrite existing entries with the same key.
+    Merge,
+    /// The import fails on the first key that already exists in the column.
+    FailOnConflict,
+}
+
+fn io_error(err: std::io::Error) -> StorageError {
+    StorageError::Other(anyhow::anyhow!("Unable to access the dump: {err}"))
+}
+
+fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> StorageResult<()> {
+    let len = u32::try_from(bytes.len()).map_err(|_| {
+        StorageError::Other(anyhow::anyhow!(
+            "The entry of {} bytes is too large for the dump",
+            bytes.len()
+        ))
+    })?;
+    writer.write_all(&len.to_be_bytes()).map_err(io_error)?;
+    writer.write_all(bytes).map_err(io_error)
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> StorageResult<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes).map_err(io_error)?;
+    Ok(bytes)
+}
+
+fn read_bytes(reader: &mut impl Read) -> StorageResult<Vec<u8>> {
+    let len = u32::from_be_bytes(read_array(reader)?);
+    // The buffer grows with the read bytes, so the corrupted length doesn't allocate at once.
+    let mut bytes = vec![];
+    reader
+        .take(u64::from(len))
+        .read_to_end(&mut bytes)
+        .map_err(io_error)?;
+    if bytes.len() != len as usize {
+        return Err(io_error(std::io::ErrorKind::UnexpectedEof.into()))
+    }
+    Ok(bytes)
+}
+
+impl<Description> Database<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Writes all entries of the `column` into the `writer` in the portable dump format.
+    /// Entries are streamed from the iterator, so the export doesn't hold the column
+    /// in memory. Returns the number of exported entries.
+    pub fn export_column(
+        &self,
+        column: Description::Column,
+        writer: impl Write,
+    ) -> StorageResult<u64> {
+        let mut writer = BufWriter::new(writer);
+        writer.write_all(&DUMP_MAGIC).map_err(io_error)?;
+        writer
+            .write_all(&DUMP_VERSION.to_be_bytes())
+            .map_err(io_error)?;
+        writer
+            .write_all(&column.id().to_be_bytes())
+            .map_err(io_error)?;
+
+        let mut entries = 0u64;
+        let config = IterConfig {
+            options: IterOptions::maintenance(),
+            ..Default::default()
+        };
+        for item in self.data.as_ref().iter(column, config) {
+            let (key, value) = item?;
+            writer.write_all(&[RECORD_TAG]).map_err(io_error)?;
+            write_bytes(&mut writer, &key)?;
+            write_bytes(&mut writer, &value)?;
+            entries = entries.saturating_add(1);
+        }
+
+        writer.write_all(&[END_TAG]).map_err(io_error)?;
+        writer.write_all(&entries.to_be_bytes()).map_err(io_error)?;
+        writer.flush().map_err(io_error)?;
+        Ok(entries)
+    }
+
+    /// Imports the dump created by the [`Self::export_column`] from the `reader` into the `column`.
+    /// Entries are written by batches of the [`IMPORT_CHUNK_SIZE`]. Returns the number
+    /// of imported entries.
+    ///
+    /// The header is verified before anything is written. The import of the corrupted
+    /// dump or the conflicting entry fails, but batches before the failure stay written.
+    pub fn import_column(
+        &self,
+        column: Description::Column,
+        reader: impl Read,
+        mode: ImportMode,
+    ) -> StorageResult<u64> {
+        let mut reader = BufReader::new(reader);
+        if read_array(&mut reader)? != DUMP_MAGIC {
+            return Err(anyhow::anyhow!("The stream is not the dump of the column").into())
+        }
+        let version = u32::from_be_bytes(read_array(&mut reader)?);
+        if version != DUMP_VERSION {
+            return Err(
+                anyhow::anyhow!("The dump version {version} is not supported").into(),
+            )
+        }
+        let id = u32::from_be_bytes(read_array(&mut reader)?);
+        if id != column.id() {
+            return Err(anyhow::anyhow!(
+                "The dump of the column {id} can't be imported into the column `{}`",
+                column.name()
+            )
+            .into())
+        }
+
+        let storage = self.data.as_ref();
+        if mode == ImportMode::Replace {
+            storage.clear_column(column)?;
+        }
+        let mut imported = 0u64;
+        let mut chunk = Vec::with_capacity(IMPORT_CHUNK_SIZE);
+        let entries = loop {
+            let [tag] = read_array(&mut reader)?;
+            match tag {
+                RECORD_TAG => {
+                    let key = read_bytes(&mut reader)?;
+                    let value = read_bytes(&mut reader)?;
+                    chunk.push((key, value));
+                }
+                END_TAG => break u64::from_be_bytes(read_array(&mut reader)?),
+                tag => {
+                    return Err(anyhow::anyhow!(
+                        "The dump has the unknown tag {tag} of the entry"
+                    )
+                    .into())
+                }
+            }
+            if chunk.len() == IMPORT_CHUNK_SIZE {
+                imported =
+                    imported.saturating_add(self.import_chunk(column, &mut chunk, mode)?);
+            }
+        };
+        imported = imported.saturating_add(self.import_chunk(column, &mut chunk, mode)?);
+        if entries != imported {
+            return Err(anyhow::anyhow!(
+                "The dump declares {entries} entries, but contains {imported}"
+            )
+            .into())
+        }
+        Ok(imported)
+    }
+
+    /// Writes the `chunk` of imported entries and empties it.
+    fn import_chunk(
+        &self,
+        column: Description::Column,
+        chunk: &mut Vec<(Vec<u8>, Vec<u8>)>,
+        mode: ImportMode,
+    ) -> StorageResult<u64> {
+        let storage = self.data.as_ref();
+        if mode == ImportMode::FailOnConflict {
+            let keys = chunk.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>();
+            let existing = storage.multi_get(&keys, column)?;
+            if let Some((key, _)) =
+                keys.iter().zip(existing).find(|(_, value)| value.is_some())
+            {
+                return Err(anyhow::anyhow!(
+                    "The key {} already exists in the column `{}`",
+                    hex::encode(key),
+                    column.name()
+                )
+                .into())
+            }
+        }
+        let len = chunk.len() as u64;
+        storage.batch_write(
+            &mut chunk
+                .drain(..)
+                .map(|(key, value)| (key, column, WriteOperation::Insert(value.into()))),
+        )?;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::database_description::on_chain::OnChain;
+    use fuel_core_storage::{
+        column::Column,
+        iter::IterDirection,
+        kv_store::Value,
+    };
+    use std::sync::Arc;
+
+    fn entries(count: u32) -> Vec<(Vec<u8>, Value)> {
+        (0..count)
+            .map(|i| {
+                // Every third value is empty.
+                let value = vec![7; (i % 3) as usize];
+                (i.to_be_bytes().to_vec(), Arc::new(value))
+            })
+            .collect()
+    }
+
+    fn filled(database: Database, entries: &[(Vec<u8>, Value)]) -> Database {
+        database
+            .data
+            .as_ref()
+            .batch_write(&mut entries.iter().map(|(key, value)| {
+                (
+                    key.clone(),
+                    Column::Coins,
+                    WriteOperation::Insert(value.clone()),
+                )
+            }))
+            .unwrap();
+        database
+    }
+
+    fn column(database: &Database) -> Vec<(Vec<u8>, Value)> {
+        database
+            .data
+            .as_ref()
+            .iter_all(Column::Coins, None, None, IterDirection::Forward)
+            .map(|item| item.unwrap())
+            .collect()
+    }
+
+    fn dump(database: &Database) -> Vec<u8> {
+        let mut dump = vec![];
+        database.export_column(Column::Coins, &mut dump).unwrap();
+        dump
+    }
+
+    fn backends() -> Vec<Database> {
+        vec![
+            Database::in_memory(),
+            #[cfg(feature = "rocksdb")]
+            Database::rocksdb(),
+            #[cfg(feature = "redb")]
+            Database::redb(),
+        ]
+    }
+
+    #[test]
+    fn dump_round_trips_between_backends() {
+        let count = u32::try_from(IMPORT_CHUNK_SIZE).unwrap() + 5;
+        for count in [0, 10, count] {
+            let entries = entries(count);
+            for source in backends() {
+                for target in backends() {
+                    // Given
+                    let source = filled(source.clone(), &entries);
+                    let dump = dump(&source);
+
+                    // When
+                    let imported = target
+                        .import_column(Column::Coins, dump.as_slice(), ImportMode::Merge)
+                        .unwrap();
+
+                    // Then
+                    assert_eq!(imported, u64::from(count));
+                    assert_eq!(column(&target), entries);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn import_modes_resolve_existing_entries() {
+        // Given
+        let dump = dump(&filled(Database::in_memory(), &entries(4)));
+        let existing = [
+            (vec![0, 0, 0, 1], Arc::new(vec![9])),
+            (vec![9], Arc::new(vec![9])),
+        ];
+
+        // When
+        let replaced = filled(Database::in_memory(), &existing);
+        replaced
+            .import_column(Column::Coins, dump.as_slice(), ImportMode::Replace)
+            .unwrap();
+        let merged = filled(Database::in_memory(), &existing);
+        merged
+            .import_column(Column::Coins, dump.as_slice(), ImportMode::Merge)
+            .unwrap();
+        let conflicting = filled(Database::in_memory(), &existing);
+        let result = conflicting.import_column(
+            Column::Coins,
+            dump.as_slice(),
+            ImportMode::FailOnConflict,
+        );
+
+        // Then
+        assert_eq!(column(&replaced), entries(4));
+        let mut expected = entries(4);
+        expected.push(existing[1].clone());
+        assert_eq!(column(&merged), expected);
+        assert!(result.is_err());
+        assert_eq!(column(&conflicting), existing.to_vec());
+    }
+
+    #[test]
+    fn import_into_another_column_is_rejected() {
+        // Given
+        let dump = dump(&filled(Database::in_memory(), &entries(4)));
+        let target = Database::<OnChain>::in_memory();
+
+        // When
+        let result =
+            target.import_column(Column::Metadata, dump.as_slice(), ImportMode::Merge);
+
+        // Then
+        assert!(result.is_err());
+        assert!(target
+            .data
+            .as_ref()
+            .iter_all(Column::Metadata, None, None, IterDirection::Forward)
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn corrupted_dump_fails_the_import() {
+        let count = u32::try_from(IMPORT_CHUNK_SIZE).unwrap() + 5;
+        let dump = dump(&filled(Database::in_memory(), &entries(count)));
+        // The first entry starts after the header.
+        let mut wrong_tag = dump.clone();
+        wrong_tag[16] = 0xFF;
+        let mut wrong_count = dump.clone();
+        *wrong_count.last_mut().unwrap() = 0;
+
+        for corrupted in [&dump[..dump.len() / 2], &wrong_tag, &wrong_count, &[]] {
+            // Given
+            let target = Database::in_memory();
+
+            // When
+            let result =
+                target.import_column(Column::Coins, corrupted, ImportMode::Merge);
+
+            // Then
+            assert!(result.is_err());
+            assert!(entries(count).starts_with(&column(&target)));
+        }
+    }
+}
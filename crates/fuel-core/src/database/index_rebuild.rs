@@ -0,0 +1,513 @@
+//! The rebuild of derived index columns of the off-chain database from the primary
+//! data of the on-chain database.
+//!
+//! The off-chain worker maintains indexes incrementally from events of the executor,
+//! so the index that drifted from the chain, e.g. after the bug of the worker or the
+//! manual repair of the database, can't be fixed by replaying blocks. The rebuild clears
+//! the index and recomputes it from the primary column in bounded batches. Each batch
+//! records the progress in the same write, so the interrupted rebuild resumes after
+//! the last indexed primary entry instead of starting over.
+//!
+//! The rebuild reads the live on-chain database and writes the live off-chain database,
+//! so it requires the node to be stopped: blocks imported during the rebuild would
+//! update the index that is being cleared and recomputed.
+
+use crate::{
+    database::{
+        database_description::{
+            off_chain::OffChain,
+            on_chain::OnChain,
+        },
+        metadata::{
+            self,
+            get_metadata,
+            metadata_write,
+            remove_metadata,
+        },
+        Database,
+    },
+    fuel_core_graphql_api::storage::{
+        blocks::FuelBlockIdsToHeights,
+        coins::{
+            owner_coin_id_key,
+            OwnedCoins,
+        },
+        messages::{
+            OwnedMessageIds,
+            OwnedMessageKey,
+        },
+        Column as OffChainColumn,
+    },
+    state::DataSource,
+};
+use fuel_core_storage::{
+    blueprint::Blueprint,
+    codec::{
+        Decode,
+        Encode,
+        Encoder,
+    },
+    iter::IterDirection,
+    kv_store::{
+        StorageColumn,
+        Value,
+        WriteOperation,
+    },
+    structured_storage::TableWithBlueprint,
+    tables::{
+        Coins,
+        FuelBlocks,
+        Messages,
+    },
+    Mappable,
+    Result as StorageResult,
+};
+use std::sync::Mutex;
+
+/// The maximum number of primary entries indexed by one batch.
+pub const REBUILD_BATCH_SIZE: usize = 10_000;
+
+/// Serializes rebuilds, so one rebuild doesn't overwrite the progress of another.
+static REBUILD_LOCK: Mutex<()> = Mutex::new(());
+
+/// The derived index column of the off-chain database that can be rebuilt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum IndexColumn {
+    /// The [`OwnedCoins`] index, derived from owners of [`Coins`].
+    OwnedCoins,
+    /// The [`OwnedMessageIds`] index, derived from recipients of [`Messages`].
+    OwnedMessageIds,
+    /// The [`FuelBlockIdsToHeights`] index, derived from ids of [`FuelBlocks`].
+    FuelBlockIdsToHeights,
+}
+
+impl IndexColumn {
+    /// Returns the column of the index in the off-chain database.
+    pub fn column(&self) -> OffChainColumn {
+        match self {
+            Self::OwnedCoins => OffChainColumn::OwnedCoins,
+            Self::OwnedMessageIds => OffChainColumn::OwnedMessageIds,
+            Self::FuelBlockIdsToHeights => OffChainColumn::FuelBlockIdsToHeights,
+        }
+    }
+}
+
+/// The result of the rebuild of the index.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RebuildStats {
+    /// The number of entries of the index written by this call,
+    /// one per entry of the primary column.
+    pub entries: u64,
+    /// Whether this call resumed the interrupted rebuild instead of starting over.
+    pub resumed: bool,
+}
+
+/// The progress of the rebuild in progress. The record exists only while
+/// the rebuild is in progress, and the interrupted rebuild resumes from it.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct IndexRebuildProgress {
+    /// The rebuilt index.
+    index: IndexColumn,
+    /// The key of the last indexed entry of the primary column.
+    last_indexed: Option<Vec<u8>>,
+}
+
+impl IndexRebuildProgress {
+    fn entry(&self) -> (Vec<u8>, OffChainColumn, WriteOperation) {
+        metadata_write::<metadata::IndexRebuildProgress, OffChain>(Some(self))
+    }
+}
+
+impl Database<OffChain> {
+    /// Clears the `index` and recomputes it from the primary data of the `on_chain`
+    /// database. See [`crate::combined_database::CombinedDatabase::rebuild_index`].
+    pub fn rebuild_index(
+        &self,
+        on_chain: &Database<OnChain>,
+        index: IndexColumn,
+    ) -> StorageResult<RebuildStats> {
+        match index {
+            IndexColumn::OwnedCoins => {
+                self.rebuild::<Coins, _>(on_chain, index, |utxo_id, coin| {
+                    encode_entry::<OwnedCoins>(
+                        &owner_coin_id_key(coin.owner(), &utxo_id),
+                        &(),
+                    )
+                })
+            }
+            IndexColumn::OwnedMessageIds => {
+                self.rebuild::<Messages, _>(on_chain, index, |nonce, message| {
+                    encode_entry::<OwnedMessageIds>(
+                        &OwnedMessageKey::new(message.recipient(), &nonce),
+                        &(),
+                    )
+                })
+            }
+            IndexColumn::FuelBlockIdsToHeights => {
+                self.rebuild::<FuelBlocks, _>(on_chain, index, |height, block| {
+                    encode_entry::<FuelBlockIdsToHeights>(&block.id(), &height)
+                })
+            }
+        }
+    }
+
+    fn rebuild<P, F>(
+        &self,
+        on_chain: &Database<OnChain>,
+        index: IndexColumn,
+        index_entry: F,
+    ) -> StorageResult<RebuildStats>
+    where
+        P: Mappable + TableWithBlueprint<Column = fuel_core_storage::column::Column>,
+        P::Blueprint: Blueprint<P, DataSource<OnChain>>,
+        F: Fn(P::OwnedKey, P::OwnedValue) -> (Vec<u8>, Value),
+    {
+        let _guard = REBUILD_LOCK.lock().expect("poisoned");
+        let storage = self.data.as_ref();
+        let column = index.column();
+        let mut stats = RebuildStats::default();
+        let progress = match self.index_rebuild_progress()? {
+            Some(progress) if progress.index == index => {
+                stats.resumed = true;
+                progress
+            }
+            Some(progress) => {
+                return Err(anyhow::anyhow!(
+                    "The rebuild of the `{}` index is in progress, finish it first",
+                    progress.index.column().name()
+                )
+                .into())
+            }
+            None => {
+                // The progress is recorded after the index is cleared, so the rebuild
+                // interrupted before it starts over and clears the index again.
+                storage.clear_column(column)?;
+                let progress = IndexRebuildProgress {
+                    index,
+                    last_indexed: None,
+                };
+                storage.batch_write(&mut [progress.entry()].into_iter())?;
+                progress
+            }
+        };
+
+        let entries = on_chain.data.as_ref().iter_all(
+            P::column(),
+            None,
+            progress.last_indexed.as_deref(),
+            IterDirection::Forward,
+        );
+        // The start is inclusive, and the last indexed entry is already in the index.
+        let mut entries = entries.filter(|entry| {
+            !matches!(
+                (entry, &progress.last_indexed),
+                (Ok((key, _)), Some(last)) if key == last
+            )
+        });
+        loop {
+            let batch = entries
+                .by_ref()
+                .take(REBUILD_BATCH_SIZE)
+                .collect::<StorageResult<Vec<_>>>()?;
+            let Some((last, _)) = batch.last() else { break };
+            let progress = IndexRebuildProgress {
+                index,
+                last_indexed: Some(last.clone()),
+            };
+            let is_last_batch = batch.len() < REBUILD_BATCH_SIZE;
+            let writes = batch
+                .into_iter()
+                .map(|(key, value)| {
+                    let key =
+                        <P::Blueprint as Blueprint<P, DataSource<OnChain>>>::KeyCodec::decode(&key)?;
+                    let value =
+                        <P::Blueprint as Blueprint<P, DataSource<OnChain>>>::ValueCodec::decode(&value)?;
+                    let (key, value) = index_entry(key, value);
+                    Ok((key, column, WriteOperation::Insert(value)))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let written = writes.len() as u64;
+            storage.batch_write(&mut writes.into_iter().chain([progress.entry()]))?;
+            stats.entries = stats.entries.saturating_add(written);
+            if is_last_batch {
+                break
+            }
+        }
+
+        remove_metadata::<metadata::IndexRebuildProgress, OffChain>(storage)?;
+        Ok(stats)
+    }
+
+    fn index_rebuild_progress(&self) -> StorageResult<Option<IndexRebuildProgress>> {
+        get_metadata::<metadata::IndexRebuildProgress, OffChain>(self.data.as_ref())
+    }
+}
+
+/// Encodes the entry of the index `I` like the structured storage does.
+fn encode_entry<I>(key: &I::Key, value: &I::Value) -> (Vec<u8>, Value)
+where
+    I: Mappable + TableWithBlueprint<Column = OffChainColumn>,
+    I::Blueprint: Blueprint<I, DataSource<OffChain>>,
+{
+    let key = <I::Blueprint as Blueprint<I, DataSource<OffChain>>>::KeyCodec::encode(key)
+        .as_bytes()
+        .into_owned();
+    let value =
+        <I::Blueprint as Blueprint<I, DataSource<OffChain>>>::ValueCodec::encode_as_value(
+            value,
+        );
+    (key, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        combined_database::CombinedDatabase,
+        state::{
+            faulty::FaultyStorage,
+            in_memory::memory_store::MemoryStore,
+        },
+    };
+    use fuel_core_storage::{
+        codec::{
+            postcard::Postcard,
+            primitive::utxo_id_to_bytes,
+        },
+        column::Column,
+        kv_store::{
+            BatchOperations,
+            KeyValueStore,
+        },
+        StorageAsMut,
+    };
+    use fuel_core_types::{
+        blockchain::{
+            block::CompressedBlock,
+            header::BlockHeader,
+        },
+        entities::{
+            coins::coin::{
+                CompressedCoin,
+                CompressedCoinV1,
+            },
+            message::MessageV1,
+        },
+        fuel_tx::{
+            Address,
+            TxId,
+            UtxoId,
+        },
+        fuel_types::Nonce,
+    };
+    use std::{
+        collections::BTreeSet,
+        sync::Arc,
+    };
+
+    fn owner(i: u32) -> Address {
+        Address::new([u8::try_from(i % 7).unwrap(); 32])
+    }
+
+    fn utxo_id(i: u32) -> UtxoId {
+        let mut tx_id = [0u8; 32];
+        tx_id[..4].copy_from_slice(&i.to_be_bytes());
+        UtxoId::new(TxId::from(tx_id), 0)
+    }
+
+    fn insert_coins(database: &CombinedDatabase, coins: std::ops::Range<u32>) {
+        database
+            .on_chain()
+            .data
+            .as_ref()
+            .batch_write(&mut coins.map(|i| {
+                let coin: CompressedCoin = CompressedCoinV1 {
+                    owner: owner(i),
+                    ..Default::default()
+                }
+                .into();
+                (
+                    utxo_id_to_bytes(&utxo_id(i)).to_vec(),
+                    Column::Coins,
+                    WriteOperation::Insert(Postcard::encode_as_value(&coin)),
+                )
+            }))
+            .unwrap();
+    }
+
+    /// Returns the index of coins like the off-chain worker builds it.
+    fn owned_coins_reference(coins: std::ops::Range<u32>) -> BTreeSet<Vec<u8>> {
+        coins
+            .map(|i| owner_coin_id_key(&owner(i), &utxo_id(i)).to_vec())
+            .collect()
+    }
+
+    fn index_entries(
+        database: &CombinedDatabase,
+        index: IndexColumn,
+    ) -> BTreeSet<(Vec<u8>, Vec<u8>)> {
+        database
+            .off_chain()
+            .data
+            .as_ref()
+            .iter_all(index.column(), None, None, IterDirection::Forward)
+            .map(|entry| {
+                let (key, value) = entry.unwrap();
+                (key, value.to_vec())
+            })
+            .collect()
+    }
+
+    fn index_keys(database: &CombinedDatabase) -> BTreeSet<Vec<u8>> {
+        index_entries(database, IndexColumn::OwnedCoins)
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    #[test]
+    fn rebuild_restores_the_corrupted_owned_coins_index() {
+        // Given
+        let mut database = CombinedDatabase::in_memory();
+        insert_coins(&database, 0..20);
+        let off_chain = database.off_chain().data.as_ref();
+        for key in owned_coins_reference(0..20) {
+            off_chain
+                .put(&key, OffChainColumn::OwnedCoins, Arc::new(vec![]))
+                .unwrap();
+        }
+        // The index misses the coin, and has the coin of the wrong owner.
+        off_chain
+            .delete(
+                &owner_coin_id_key(&owner(3), &utxo_id(3)),
+                OffChainColumn::OwnedCoins,
+            )
+            .unwrap();
+        off_chain
+            .put(
+                &owner_coin_id_key(&owner(4), &utxo_id(5)),
+                OffChainColumn::OwnedCoins,
+                Arc::new(vec![]),
+            )
+            .unwrap();
+
+        // When
+        let stats = database.rebuild_index(IndexColumn::OwnedCoins).unwrap();
+
+        // Then
+        assert_eq!(
+            stats,
+            RebuildStats {
+                entries: 20,
+                resumed: false
+            }
+        );
+        assert_eq!(index_keys(&database), owned_coins_reference(0..20));
+        assert_eq!(database.off_chain().index_rebuild_progress().unwrap(), None);
+    }
+
+    #[test]
+    fn rebuild_of_messages_and_blocks_matches_the_worker() {
+        // Given
+        let mut database = CombinedDatabase::in_memory();
+        let mut reference = CombinedDatabase::in_memory();
+        for i in 0..5u8 {
+            let nonce = Nonce::from([i; 32]);
+            let message = MessageV1 {
+                recipient: Address::new([i.wrapping_add(1); 32]),
+                nonce,
+                ..Default::default()
+            }
+            .into();
+            database
+                .on_chain_mut()
+                .storage_as_mut::<Messages>()
+                .insert(&nonce, &message)
+                .unwrap();
+            reference
+                .off_chain_mut()
+                .storage_as_mut::<OwnedMessageIds>()
+                .insert(&OwnedMessageKey::new(message.recipient(), &nonce), &())
+                .unwrap();
+
+            let mut header = BlockHeader::default();
+            header.set_block_height(u32::from(i).into());
+            let block = CompressedBlock::test(header, vec![]);
+            database
+                .on_chain_mut()
+                .storage_as_mut::<FuelBlocks>()
+                .insert(&u32::from(i).into(), &block)
+                .unwrap();
+            reference
+                .off_chain_mut()
+                .storage_as_mut::<FuelBlockIdsToHeights>()
+                .insert(&block.id(), &u32::from(i).into())
+                .unwrap();
+        }
+
+        // When
+        let messages = database
+            .rebuild_index(IndexColumn::OwnedMessageIds)
+            .unwrap();
+        let blocks = database
+            .rebuild_index(IndexColumn::FuelBlockIdsToHeights)
+            .unwrap();
+
+        // Then
+        assert_eq!((messages.entries, blocks.entries), (5, 5));
+        for index in [
+            IndexColumn::OwnedMessageIds,
+            IndexColumn::FuelBlockIdsToHeights,
+        ] {
+            assert_eq!(
+                index_entries(&database, index),
+                index_entries(&reference, index)
+            );
+        }
+    }
+
+    #[test]
+    fn interrupted_rebuild_resumes_after_the_last_indexed_entry() {
+        // Given
+        let entries = u32::try_from(REBUILD_BATCH_SIZE)
+            .unwrap()
+            .saturating_mul(3)
+            .saturating_div(2);
+        let faulty = Arc::new(FaultyStorage::<OnChain>::new(Arc::new(MemoryStore::<
+            OnChain,
+        >::default(
+        ))));
+        let mut database = CombinedDatabase::new(
+            Database::new(faulty.clone()),
+            Database::in_memory(),
+            Database::in_memory(),
+        );
+        insert_coins(&database, 0..entries);
+        // The first batch is indexed, and the iteration fails within the second one.
+        faulty.fail_iter_after(REBUILD_BATCH_SIZE.saturating_add(1));
+        assert!(database.rebuild_index(IndexColumn::OwnedCoins).is_err());
+        faulty.reset();
+        assert_eq!(index_keys(&database).len(), REBUILD_BATCH_SIZE);
+        assert!(database
+            .off_chain()
+            .index_rebuild_progress()
+            .unwrap()
+            .is_some());
+        let other = database.rebuild_index(IndexColumn::OwnedMessageIds);
+
+        // When
+        let stats = database.rebuild_index(IndexColumn::OwnedCoins).unwrap();
+
+        // Then
+        assert!(other.is_err());
+        assert_eq!(
+            stats,
+            RebuildStats {
+                entries: u64::from(entries).saturating_sub(REBUILD_BATCH_SIZE as u64),
+                resumed: true
+            }
+        );
+        assert_eq!(index_keys(&database), owned_coins_reference(0..entries));
+        assert_eq!(database.off_chain().index_rebuild_progress().unwrap(), None);
+    }
+}
@@ -0,0 +1,267 @@
+//! The read-only inspection of raw entries of columns for debugging tools. Entries are
+//! returned as they are stored, hex-encoded, so the tools don't need to open the data
+//! directory with the RocksDB directly and bypass the mapping of columns.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Database,
+    },
+    state::DataSource,
+};
+use fuel_core_storage::{
+    blueprint::Blueprint,
+    codec::Decode,
+    iter::IterDirection,
+    kv_store::{
+        KeyValueStore,
+        Value,
+    },
+    structured_storage::TableWithBlueprint,
+    Error as StorageError,
+    Mappable,
+    Result as StorageResult,
+};
+use std::fmt::Debug;
+
+/// The default number of bytes of the value encoded into the [`RawEntry::value`].
+pub const DEFAULT_VALUE_HEX_CAP: usize = 256;
+
+/// The entry of the column as it is stored in the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawEntry {
+    /// The hex-encoded key.
+    pub key: String,
+    /// The length of the value in bytes.
+    pub value_len: usize,
+    /// The hex-encoded value truncated at the value cap of the [`Inspector`].
+    pub value: String,
+    /// The debug representation of the decoded entry, or the error of the decoding.
+    /// It is `None` if the column doesn't have a known codec.
+    pub decoded: Option<Result<String, String>>,
+}
+
+/// The read-only view of raw entries of the database. It never writes into the database.
+#[derive(Debug, Clone, Copy)]
+pub struct Inspector<'a, Description>
+where
+    Description: DatabaseDescription,
+{
+    database: &'a Database<Description>,
+    value_hex_cap: usize,
+}
+
+impl<Description> Database<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Returns the [`Inspector`] of raw entries of the database.
+    pub fn inspect(&self) -> Inspector<'_, Description> {
+        Inspector {
+            database: self,
+            value_hex_cap: DEFAULT_VALUE_HEX_CAP,
+        }
+    }
+}
+
+impl<'a, Description> Inspector<'a, Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Sets the number of bytes of the value encoded into the [`RawEntry::value`].
+    pub fn with_value_hex_cap(mut self, value_hex_cap: usize) -> Self {
+        self.value_hex_cap = value_hex_cap;
+        self
+    }
+
+    /// Returns up to the `limit` first entries of the `column` with the `prefix`.
+    pub fn dump(
+        &self,
+        column: Description::Column,
+        prefix: Option<&[u8]>,
+        limit: usize,
+    ) -> StorageResult<Vec<RawEntry>> {
+        self.database
+            .data
+            .as_ref()
+            .iter_all(column, prefix, None, IterDirection::Forward)
+            .take(limit)
+            .map(|entry| entry.map(|(key, value)| self.entry(column, &key, &value)))
+            .collect()
+    }
+
+    /// Returns the entry of the `column` with the hex-encoded key, optionally prefixed by `0x`.
+    pub fn get_raw(
+        &self,
+        column: Description::Column,
+        key_hex: &str,
+    ) -> StorageResult<Option<RawEntry>> {
+        let key_hex = key_hex.strip_prefix("0x").unwrap_or(key_hex);
+        let key = hex::decode(key_hex).map_err(|e| {
+            StorageError::Other(anyhow::anyhow!("The key is not a hex string: {e}"))
+        })?;
+        let value = self.database.data.as_ref().get(&key, column)?;
+        Ok(value.map(|value| self.entry(column, &key, &value)))
+    }
+
+    fn entry(&self, column: Description::Column, key: &[u8], value: &Value) -> RawEntry {
+        let shown = value.get(..self.value_hex_cap).unwrap_or(value.as_slice());
+        RawEntry {
+            key: hex::encode(key),
+            value_len: value.len(),
+            value: hex::encode(shown),
+            decoded: Description::debug_entry(&column, key, value),
+        }
+    }
+}
+
+/// Decodes the entry of the table `M` for the [`DatabaseDescription::debug_entry`].
+pub(crate) fn debug_entry<M>(key: &[u8], value: &[u8]) -> Result<String, String>
+where
+    M: Mappable + TableWithBlueprint,
+    M::Blueprint: Blueprint<M, DataSource>,
+    M::OwnedKey: Debug,
+    M::OwnedValue: Debug,
+{
+    let key = <M::Blueprint as Blueprint<M, DataSource>>::KeyCodec::decode(key)
+        .map_err(|err| format!("Unable to decode the key: {err}"))?;
+    let value = <M::Blueprint as Blueprint<M, DataSource>>::ValueCodec::decode(value)
+        .map_err(|err| format!("Unable to decode the value: {err}"))?;
+    Ok(format!("{key:?} => {value:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::database_description::on_chain::OnChain;
+    use fuel_core_storage::{
+        column::Column,
+        tables::Coins,
+        StorageAsMut,
+    };
+    use fuel_core_types::{
+        entities::coins::coin::CompressedCoin,
+        fuel_tx::{
+            TxId,
+            UtxoId,
+        },
+    };
+    use std::sync::Arc;
+
+    /// Returns the database with one decodable coin and one coin with the corrupt value.
+    fn database() -> (Database, Vec<u8>, Vec<u8>) {
+        let mut database = Database::default();
+        let valid = UtxoId::new(TxId::from([1; 32]), 0);
+        database
+            .storage_as_mut::<Coins>()
+            .insert(&valid, &CompressedCoin::default())
+            .unwrap();
+        let corrupt = [[2; 32].as_slice(), &[0]].concat();
+        database
+            .data
+            .as_ref()
+            .put(&corrupt, Column::Coins, Arc::new(vec![0xff; 40]))
+            .unwrap();
+        let valid = [[1; 32].as_slice(), &[0]].concat();
+        (database, valid, corrupt)
+    }
+
+    #[test]
+    fn dump_decodes_valid_entries_and_reports_corrupt_ones() {
+        // Given
+        let (database, valid, corrupt) = database();
+
+        // When
+        let entries = database
+            .inspect()
+            .with_value_hex_cap(4)
+            .dump(Column::Coins, None, 10)
+            .unwrap();
+
+        // Then
+        let [first, second] = entries.as_slice() else {
+            panic!("Expected two entries: {entries:?}")
+        };
+        assert_eq!(first.key, hex::encode(valid));
+        assert!(
+            matches!(&first.decoded, Some(Ok(decoded)) if decoded.contains("CompressedCoin")),
+            "{first:?}"
+        );
+        assert_eq!(second.key, hex::encode(corrupt));
+        assert_eq!(second.value_len, 40);
+        assert_eq!(second.value, "ffffffff");
+        assert!(
+            matches!(&second.decoded, Some(Err(err)) if err.contains("value")),
+            "{second:?}"
+        );
+    }
+
+    #[test]
+    fn dump_respects_prefix_and_limit() {
+        // Given
+        let (database, valid, _) = database();
+
+        // When
+        let by_prefix = database
+            .inspect()
+            .dump(Column::Coins, Some(&[1; 32]), 10)
+            .unwrap();
+        let limited = database.inspect().dump(Column::Coins, None, 1).unwrap();
+
+        // Then
+        assert_eq!(by_prefix.len(), 1);
+        assert_eq!(by_prefix[0].key, hex::encode(valid));
+        assert_eq!(limited, by_prefix);
+    }
+
+    #[test]
+    fn get_raw_accepts_hex_keys() {
+        // Given
+        let (database, valid, corrupt) = database();
+        let inspector = database.inspect();
+
+        // When
+        let valid_entry = inspector
+            .get_raw(Column::Coins, &format!("0x{}", hex::encode(valid)))
+            .unwrap();
+        let corrupt_entry = inspector
+            .get_raw(Column::Coins, &hex::encode(corrupt))
+            .unwrap();
+        let missing = inspector.get_raw(Column::Coins, "00").unwrap();
+        let invalid = inspector.get_raw(Column::Coins, "not hex");
+
+        // Then
+        assert!(matches!(valid_entry.unwrap().decoded, Some(Ok(_))));
+        assert!(matches!(corrupt_entry.unwrap().decoded, Some(Err(_))));
+        assert_eq!(missing, None);
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn columns_without_codec_are_not_decoded() {
+        // Given
+        let database = Database::<OnChain>::default();
+        database
+            .data
+            .as_ref()
+            .put(&1u64.to_be_bytes(), Column::ChangeLog, Arc::new(vec![2]))
+            .unwrap();
+
+        // When
+        let entry = database
+            .inspect()
+            .get_raw(Column::ChangeLog, "0000000000000001")
+            .unwrap();
+
+        // Then
+        assert_eq!(
+            entry,
+            Some(RawEntry {
+                key: "0000000000000001".to_string(),
+                value_len: 1,
+                value: "02".to_string(),
+                decoded: None,
+            })
+        );
+    }
+}
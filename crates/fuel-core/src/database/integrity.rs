@@ -0,0 +1,631 @@
+use crate::{
+    database::{
+        database_description::{
+            off_chain::OffChain,
+            on_chain::OnChain,
+            DatabaseDescription,
+        },
+        metadata::MetadataEntry,
+        rollback::UndoLog,
+        Database,
+    },
+    graphql_api::storage::coins::OwnedCoins,
+    state::{
+        throttled::{
+            CancellationToken,
+            IterBudget,
+            Throttle,
+            ThrottledIter,
+        },
+        DataSource,
+    },
+};
+use fuel_core_storage::{
+    blueprint::Blueprint,
+    codec::Decode,
+    column::Column,
+    iter::{
+        IterConfig,
+        IterOptions,
+    },
+    kv_store::{
+        KeyValueStore,
+        StorageColumn,
+        Value,
+    },
+    structured_storage::TableWithBlueprint,
+    tables::{
+        merkle::{
+            ContractsAssetsMerkleData,
+            ContractsAssetsMerkleMetadata,
+            ContractsStateMerkleData,
+            ContractsStateMerkleMetadata,
+            FuelBlockMerkleData,
+            FuelBlockMerkleMetadata,
+        },
+        Coins,
+        ContractsAssets,
+        ContractsInfo,
+        ContractsLatestUtxo,
+        ContractsRawCode,
+        ContractsState,
+        FuelBlocks,
+        Messages,
+        ProcessedTransactions,
+        SealedBlockConsensus,
+        SpentMessages,
+        Transactions,
+    },
+    Mappable,
+    Result as StorageResult,
+};
+use fuel_core_types::fuel_tx::Address;
+use std::ops::Bound;
+
+/// The options of the [`Database::verify_integrity`].
+#[derive(Debug, Clone)]
+pub struct IntegrityOptions {
+    /// The verification stops after finding this number of violations.
+    pub max_errors: Option<usize>,
+    /// The number of entries read from the database at once.
+    pub chunk_size: usize,
+    /// The position to resume the previously stopped verification.
+    pub resume_from: Option<IntegrityCursor>,
+    /// The budget of the iteration over columns, so the verification doesn't
+    /// slow down foreground queries.
+    pub budget: IterBudget,
+    /// The verification fails with the [`crate::database::Error::Cancelled`]
+    /// once the token is cancelled.
+    pub cancellation: CancellationToken,
+}
+
+impl Default for IntegrityOptions {
+    fn default() -> Self {
+        Self {
+            max_errors: None,
+            chunk_size: 1024,
+            resume_from: None,
+            budget: IterBudget::default(),
+            cancellation: CancellationToken::new(),
+        }
+    }
+}
+
+/// The position of the verification returned by the stopped verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrityCursor {
+    /// The index of the column check.
+    check: usize,
+    /// The last verified key of the column.
+    key: Vec<u8>,
+}
+
+/// The number of verified entries of the column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnReport {
+    /// The name of the database.
+    pub database: &'static str,
+    /// The name of the column.
+    pub column: &'static str,
+    /// The number of verified entries.
+    pub entries: u64,
+}
+
+/// The entry of the column that violates the integrity of the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The name of the database.
+    pub database: &'static str,
+    /// The name of the column.
+    pub column: &'static str,
+    /// The key of the entry.
+    pub key: Vec<u8>,
+    /// The description of the violation.
+    pub reason: String,
+}
+
+/// The result of the integrity verification.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IntegrityReport {
+    /// The verified columns.
+    pub checked: Vec<ColumnReport>,
+    /// The found violations.
+    pub violations: Vec<Violation>,
+    /// The position to resume the verification if it was stopped by the `max_errors`.
+    pub resume_from: Option<IntegrityCursor>,
+}
+
+impl IntegrityReport {
+    /// Returns `true` if the verification is finished without violations.
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty() && self.resume_from.is_none()
+    }
+}
+
+type ReadChunk<'a> =
+    Box<dyn Fn(Option<&[u8]>, usize) -> StorageResult<Vec<(Vec<u8>, Value)>> + 'a>;
+type CheckEntry<'a> = Box<dyn Fn(&[u8], &[u8]) -> StorageResult<Option<String>> + 'a>;
+
+/// The verification of all entries of one column.
+pub(crate) struct ColumnCheck<'a> {
+    database: &'static str,
+    column: &'static str,
+    read_chunk: ReadChunk<'a>,
+    check_entry: CheckEntry<'a>,
+}
+
+impl<'a> ColumnCheck<'a> {
+    /// Verifies that the keys and values of the table `M` can be decoded.
+    fn table<M, Description>(db: &'a Database<Description>) -> Self
+    where
+        Description: DatabaseDescription,
+        M: Mappable + TableWithBlueprint<Column = Description::Column> + 'a,
+        M::Blueprint: Blueprint<M, DataSource>,
+    {
+        Self::table_with::<M, Description, _>(db, |_, _| Ok(None))
+    }
+
+    /// Verifies that the keys and values of the table `M` can be decoded,
+    /// and that the decoded entry satisfies the `references`.
+    fn table_with<M, Description, F>(db: &'a Database<Description>, references: F) -> Self
+    where
+        Description: DatabaseDescription,
+        M: Mappable + TableWithBlueprint<Column = Description::Column> + 'a,
+        M::Blueprint: Blueprint<M, DataSource>,
+        F: Fn(M::OwnedKey, M::OwnedValue) -> StorageResult<Option<String>> + 'a,
+    {
+        let column = M::column();
+        Self {
+            database: Description::name(),
+            column: column.name(),
+            read_chunk: read_chunk(db, column),
+            check_entry: Box::new(move |key, value| {
+                let key =
+                    match <M::Blueprint as Blueprint<M, DataSource>>::KeyCodec::decode(
+                        key,
+                    ) {
+                        Ok(key) => key,
+                        Err(err) => {
+                            return Ok(Some(format!("Unable to decode the key: {err}")))
+                        }
+                    };
+                let value =
+                    match <M::Blueprint as Blueprint<M, DataSource>>::ValueCodec::decode(
+                        value,
+                    ) {
+                        Ok(value) => value,
+                        Err(err) => {
+                            return Ok(Some(format!("Unable to decode the value: {err}")))
+                        }
+                    };
+                references(key, value)
+            }),
+        }
+    }
+
+    /// Verifies that every key of the metadata column belongs to the [`MetadataEntry`].
+    fn metadata<Description>(db: &'a Database<Description>) -> Self
+    where
+        Description: DatabaseDescription,
+    {
+        let column = Description::metadata_column();
+        Self {
+            database: Description::name(),
+            column: column.name(),
+            read_chunk: read_chunk(db, column),
+            check_entry: Box::new(|key, _| {
+                Ok(MetadataEntry::of_key(key)
+                    .is_none()
+                    .then(|| "The metadata key is unknown".to_string()))
+            }),
+        }
+    }
+}
+
+fn read_chunk<Description>(
+    db: &Database<Description>,
+    column: Description::Column,
+) -> ReadChunk<'_>
+where
+    Description: DatabaseDescription,
+{
+    Box::new(move |start, limit| {
+        // The `start` was already verified.
+        let config = IterConfig {
+            start: start.map_or(Bound::Unbounded, Bound::Excluded),
+            limit: Some(limit),
+            options: IterOptions::maintenance(),
+            ..Default::default()
+        };
+        db.data.as_ref().iter(column, config).collect()
+    })
+}
+
+impl Database<OnChain> {
+    /// Verifies the integrity of all columns of the database: the keys have the expected
+    /// length, the values can be decoded, the transactions of blocks exist, and the keys
+    /// of the metadata column are known.
+    /// Columns are read in chunks, so the verification doesn't hold the whole database in memory.
+    pub fn verify_integrity(
+        &self,
+        options: IntegrityOptions,
+    ) -> StorageResult<IntegrityReport> {
+        verify(&self.integrity_checks(), options)
+    }
+
+    pub(crate) fn integrity_checks(&self) -> Vec<ColumnCheck<'_>> {
+        vec![
+            ColumnCheck::table::<ContractsRawCode, _>(self),
+            ColumnCheck::table::<ContractsInfo, _>(self),
+            ColumnCheck::table::<ContractsState, _>(self),
+            ColumnCheck::table::<ContractsLatestUtxo, _>(self),
+            ColumnCheck::table::<ContractsAssets, _>(self),
+            ColumnCheck::table::<Coins, _>(self),
+            ColumnCheck::table::<Transactions, _>(self),
+            ColumnCheck::table_with::<FuelBlocks, _, _>(self, |_, block| {
+                for tx_id in block.transactions() {
+                    if !self
+                        .data
+                        .as_ref()
+                        .exists(tx_id.as_ref(), Column::Transactions)?
+                    {
+                        return Ok(Some(format!(
+                            "The transaction {tx_id} of the block is missing"
+                        )))
+                    }
+                }
+                Ok(None)
+            }),
+            ColumnCheck::table::<FuelBlockMerkleData, _>(self),
+            ColumnCheck::table::<FuelBlockMerkleMetadata, _>(self),
+            ColumnCheck::table::<SpentMessages, _>(self),
+            ColumnCheck::table::<ContractsAssetsMerkleData, _>(self),
+            ColumnCheck::table::<ContractsAssetsMerkleMetadata, _>(self),
+            ColumnCheck::table::<ContractsStateMerkleData, _>(self),
+            ColumnCheck::table::<ContractsStateMerkleMetadata, _>(self),
+            ColumnCheck::table::<Messages, _>(self),
+            ColumnCheck::table::<ProcessedTransactions, _>(self),
+            ColumnCheck::table::<SealedBlockConsensus, _>(self),
+            ColumnCheck::table::<UndoLog, _>(self),
+            ColumnCheck::metadata(self),
+        ]
+    }
+}
+
+/// Verifies that every owned coin of the off-chain database exists in the on-chain database.
+pub(crate) fn owned_coins_check<'a>(
+    off_chain: &'a Database<OffChain>,
+    on_chain: &'a Database<OnChain>,
+) -> ColumnCheck<'a> {
+    ColumnCheck::table_with::<OwnedCoins, _, _>(off_chain, |key, _| {
+        let coin_id = &key[Address::LEN..];
+        if on_chain.data.as_ref().exists(coin_id, Column::Coins)? {
+            Ok(None)
+        } else {
+            Ok(Some(format!(
+                "The owned coin 0x{} is missing",
+                hex::encode(coin_id)
+            )))
+        }
+    })
+}
+
+/// Runs the `checks` one by one and collects the violations.
+pub(crate) fn verify(
+    checks: &[ColumnCheck],
+    options: IntegrityOptions,
+) -> StorageResult<IntegrityReport> {
+    let IntegrityOptions {
+        max_errors,
+        chunk_size,
+        resume_from,
+        budget,
+        cancellation,
+    } = options;
+    let mut throttle = Throttle::new(budget).with_cancellation(cancellation);
+    let chunk_size = chunk_size.max(1);
+    let (first_check, mut last_key) = match resume_from {
+        Some(IntegrityCursor { check, key }) => (check, Some(key)),
+        None => (0, None),
+    };
+    let mut report = IntegrityReport::default();
+
+    for (index, check) in checks.iter().enumerate().skip(first_check) {
+        let mut entries = 0u64;
+        loop {
+            let chunk = (check.read_chunk)(last_key.as_deref(), chunk_size)?;
+            let Some((key, _)) = chunk.last() else { break };
+            let chunk_key = key.clone();
+
+            for entry in
+                ThrottledIter::with_throttle(chunk.into_iter().map(Ok), &mut throttle)
+            {
+                let (key, value) = entry?;
+                entries = entries.saturating_add(1);
+                if let Some(reason) = (check.check_entry)(&key, &value)? {
+                    report.violations.push(Violation {
+                        database: check.database,
+                        column: check.column,
+                        key: key.clone(),
+                        reason,
+                    });
+                    if max_errors.is_some_and(|max| report.violations.len() >= max) {
+                        report.checked.push(ColumnReport {
+                            database: check.database,
+                            column: check.column,
+                            entries,
+                        });
+                        report.resume_from = Some(IntegrityCursor { check: index, key });
+                        return Ok(report)
+                    }
+                }
+            }
+            last_key = Some(chunk_key);
+        }
+        last_key = None;
+        report.checked.push(ColumnReport {
+            database: check.database,
+            column: check.column,
+            entries,
+        });
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        combined_database::CombinedDatabase,
+        graphql_api::storage::coins::owner_coin_id_key,
+    };
+    use fuel_core_storage::{
+        kv_store::KeyValueStore,
+        StorageAsMut,
+    };
+    use fuel_core_types::{
+        blockchain::block::CompressedBlock,
+        entities::coins::coin::CompressedCoin,
+        fuel_tx::{
+            Transaction,
+            TxId,
+            UtxoId,
+        },
+    };
+    use std::sync::Arc;
+
+    /// Returns the database with one valid block, its transaction, and the coin.
+    fn valid_database() -> Database {
+        let mut database = Database::default();
+        let tx_id = TxId::from([1; 32]);
+        database
+            .storage::<Transactions>()
+            .insert(&tx_id, &Transaction::default())
+            .unwrap();
+        database
+            .storage::<FuelBlocks>()
+            .insert(
+                &0.into(),
+                &CompressedBlock::test(Default::default(), vec![tx_id]),
+            )
+            .unwrap();
+        database
+            .storage::<Coins>()
+            .insert(&UtxoId::new(tx_id, 0), &CompressedCoin::default())
+            .unwrap();
+        database
+    }
+
+    /// Corrupts the `database` with three violations in different columns.
+    fn corrupt(database: &mut Database) {
+        let data = database.data.as_ref();
+        data.put(&[1, 2, 3], Column::Coins, Arc::new(vec![]))
+            .unwrap();
+        data.put(&[7; 32], Column::Transactions, Arc::new(vec![0xff; 3]))
+            .unwrap();
+        database
+            .storage::<FuelBlocks>()
+            .insert(
+                &1.into(),
+                &CompressedBlock::test(Default::default(), vec![TxId::from([2; 32])]),
+            )
+            .unwrap();
+    }
+
+    fn reported(report: &IntegrityReport) -> Vec<(&'static str, Vec<u8>)> {
+        report
+            .violations
+            .iter()
+            .map(|violation| (violation.column, violation.key.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn verify_integrity_passes_for_valid_database() {
+        // Given
+        let database = valid_database();
+
+        // When
+        let report = database.verify_integrity(Default::default()).unwrap();
+
+        // Then
+        assert!(report.is_ok(), "{report:?}");
+        let entries = |column| {
+            report
+                .checked
+                .iter()
+                .find(|report| report.column == column)
+                .unwrap()
+                .entries
+        };
+        assert_eq!(entries("Transactions"), 1);
+        assert_eq!(entries("FuelBlocks"), 1);
+        assert_eq!(entries("Coins"), 1);
+    }
+
+    #[test]
+    fn verify_integrity_reports_all_violations() {
+        // Given
+        let mut database = valid_database();
+        corrupt(&mut database);
+
+        // When
+        let report = database
+            .verify_integrity(IntegrityOptions {
+                chunk_size: 1,
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Then
+        assert_eq!(
+            reported(&report),
+            vec![
+                ("Coins", vec![1, 2, 3]),
+                ("Transactions", vec![7; 32]),
+                ("FuelBlocks", 1u32.to_be_bytes().to_vec()),
+            ]
+        );
+        assert!(report.violations[2].reason.contains("is missing"));
+        assert_eq!(report.resume_from, None);
+    }
+
+    #[test]
+    fn verify_integrity_stops_on_max_errors_and_resumes() {
+        // Given
+        let mut database = valid_database();
+        corrupt(&mut database);
+        let full_report = database.verify_integrity(Default::default()).unwrap();
+
+        // When
+        let mut violations = vec![];
+        let mut resume_from = None;
+        let mut runs = 0;
+        loop {
+            let report = database
+                .verify_integrity(IntegrityOptions {
+                    max_errors: Some(1),
+                    chunk_size: 1,
+                    resume_from,
+                    ..Default::default()
+                })
+                .unwrap();
+            runs += 1;
+            assert!(report.violations.len() <= 1);
+            violations.extend(report.violations);
+            resume_from = report.resume_from;
+            if resume_from.is_none() {
+                break
+            }
+        }
+
+        // Then
+        assert_eq!(runs, 4);
+        assert_eq!(violations, full_report.violations);
+    }
+
+    #[test]
+    fn verify_integrity_reports_unknown_metadata_keys() {
+        // Given
+        let database = valid_database();
+        let data = database.data.as_ref();
+        let known = MetadataEntry::SchemaVersion.key();
+        let marker = [MetadataEntry::MigrationProgress.key(), &[0, 0, 0, 1]].concat();
+        for key in [known, marker.as_slice(), b"unknown"] {
+            data.put(key, Column::Metadata, Arc::new(vec![1])).unwrap();
+        }
+
+        // When
+        let report = database.verify_integrity(Default::default()).unwrap();
+
+        // Then
+        assert_eq!(reported(&report), vec![("Metadata", b"unknown".to_vec())]);
+        assert!(report.violations[0].reason.contains("unknown"));
+    }
+
+    #[test]
+    fn verify_integrity_stops_on_cancellation() {
+        // Given
+        let database = valid_database();
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+
+        // When
+        let result = database.verify_integrity(IntegrityOptions {
+            cancellation,
+            ..Default::default()
+        });
+
+        // Then
+        assert_eq!(
+            format!("{:?}", result.unwrap_err()),
+            format!(
+                "{:?}",
+                fuel_core_storage::Error::from(crate::database::Error::Cancelled)
+            )
+        );
+    }
+
+    #[test]
+    fn budgeted_verify_integrity_takes_at_least_the_floor_time() {
+        // Given
+        let database = valid_database();
+        let data = database.data.as_ref();
+        for key in 0..100u8 {
+            data.put(&[key; 32], Column::ContractsRawCode, Arc::new(vec![1]))
+                .unwrap();
+        }
+        let started = std::time::Instant::now();
+
+        // When
+        let report = database
+            .verify_integrity(IntegrityOptions {
+                budget: IterBudget {
+                    items_per_sec: Some(500),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Then
+        assert!(report.is_ok(), "{report:?}");
+        let entries = report
+            .checked
+            .iter()
+            .map(|column| column.entries)
+            .sum::<u64>();
+        let floor =
+            std::time::Duration::from_secs_f64(entries.saturating_sub(1) as f64 / 500.0);
+        assert!(entries >= 100);
+        assert!(started.elapsed() >= floor, "{:?}", started.elapsed());
+    }
+
+    #[test]
+    fn combined_verify_integrity_reports_owned_coin_without_coin() {
+        // Given
+        let database = CombinedDatabase::new(
+            valid_database(),
+            Default::default(),
+            Default::default(),
+        );
+        let owner = Address::from([3; 32]);
+        let existing = owner_coin_id_key(&owner, &UtxoId::new(TxId::from([1; 32]), 0));
+        let missing = owner_coin_id_key(&owner, &UtxoId::new(TxId::from([1; 32]), 1));
+        for key in [existing, missing] {
+            database
+                .off_chain()
+                .clone()
+                .storage::<OwnedCoins>()
+                .insert(&key, &())
+                .unwrap();
+        }
+
+        // When
+        let report = database.verify_integrity(Default::default()).unwrap();
+
+        // Then
+        assert_eq!(reported(&report), vec![("OwnedCoins", missing.to_vec())]);
+    }
+}
@@ -0,0 +1,532 @@
+//! The scheduler of manual flushes and compactions of the RocksDB.
+//!
+//! The RocksDB flushes and compacts in the background at its own discretion, and large
+//! compactions landing during the block production cause spikes of the latency. The
+//! [`MaintenanceHandle`] runs the thread that watches the [`EngineStats`] of the database
+//! and flushes and compacts columns with the pending work itself, but only during quiet
+//! windows of the [`MaintenanceConfig`] or while the caller reports that the node is idle.
+//! The rate of the background I/O, including manual compactions, is limited by the
+//! [`ResourceLimits::background_io_bytes_per_sec`], because the RocksDB applies
+//! the rate limiter at open.
+//!
+//! The storage reports the statistics of the engine, so other backends, e.g. the in-memory
+//! one, never have the pending work, and the scheduler isn't started for them.
+//!
+//! [`ResourceLimits::background_io_bytes_per_sec`]: crate::state::column_config::ResourceLimits::background_io_bytes_per_sec
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Database,
+        Result as DatabaseResult,
+    },
+    state::{
+        DataSource,
+        EngineStats,
+    },
+};
+use fuel_core_storage::kv_store::StorageColumn;
+use std::{
+    sync::{
+        atomic::{
+            AtomicBool,
+            AtomicU64,
+            Ordering,
+        },
+        Arc,
+    },
+    thread::JoinHandle,
+    time::{
+        Duration,
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+/// The length of the day, quiet windows repeat every day.
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The daily window of the low load, e.g. the night in the region of most users.
+/// Times are offsets from the midnight in UTC. The window with the `end` before
+/// the `start` spans the midnight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuietWindow {
+    /// The start of the window, inclusive.
+    pub start: Duration,
+    /// The end of the window, exclusive.
+    pub end: Duration,
+}
+
+impl QuietWindow {
+    /// Returns `true` if the `time_of_day` is within the window.
+    pub fn contains(&self, time_of_day: Duration) -> bool {
+        if self.start <= self.end {
+            self.start <= time_of_day && time_of_day < self.end
+        } else {
+            self.start <= time_of_day || time_of_day < self.end
+        }
+    }
+}
+
+/// The configuration of the [`MaintenanceHandle`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaintenanceConfig {
+    /// Disabled maintenance never starts the thread.
+    pub enabled: bool,
+    /// The interval between checks of the statistics.
+    pub interval: Duration,
+    /// The windows when the maintenance runs without the idle signal.
+    pub quiet_windows: Vec<QuietWindow>,
+    /// The number of memtables waiting for the flush in any column that triggers the flush.
+    pub immutable_memtables: u64,
+    /// The estimated bytes of the pending compaction of the column that trigger its compaction.
+    pub pending_compaction_bytes: u64,
+    /// The number of files on the first level of the column that trigger its compaction.
+    pub level0_files: u64,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: Duration::from_secs(10),
+            quiet_windows: vec![],
+            immutable_memtables: 1,
+            pending_compaction_bytes: 256 * 1024 * 1024,
+            // The RocksDB starts the compaction of the first level at 4 files by default.
+            level0_files: 2,
+        }
+    }
+}
+
+/// The work of one run of the maintenance.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MaintenancePlan<Column> {
+    /// Memtables of all columns are flushed.
+    pub flush: bool,
+    /// The columns that are compacted.
+    pub compact: Vec<Column>,
+}
+
+impl<Column> MaintenancePlan<Column> {
+    /// Returns `true` if there is nothing to do.
+    pub fn is_empty(&self) -> bool {
+        !self.flush && self.compact.is_empty()
+    }
+}
+
+impl MaintenanceConfig {
+    /// Returns the work for the `stats` of the `Description` database.
+    pub fn plan<Description>(
+        &self,
+        stats: &EngineStats,
+    ) -> MaintenancePlan<Description::Column>
+    where
+        Description: DatabaseDescription,
+    {
+        let flush = stats
+            .columns
+            .iter()
+            .any(|column| column.immutable_memtables >= self.immutable_memtables);
+        let compact = enum_iterator::all::<Description::Column>()
+            .filter(|column| {
+                stats.columns.iter().any(|stats| {
+                    stats.column == column.name()
+                        && (stats.pending_compaction_bytes
+                            >= self.pending_compaction_bytes
+                            || stats.level0_files >= self.level0_files)
+                })
+            })
+            .collect();
+        MaintenancePlan { flush, compact }
+    }
+
+    /// Returns `true` if the maintenance may run at the time of the day `now`.
+    pub fn is_quiet(&self, now: Duration) -> bool {
+        let time_of_day = Duration::from_secs(now.as_secs().rem_euclid(DAY.as_secs()));
+        self.quiet_windows
+            .iter()
+            .any(|window| window.contains(time_of_day))
+    }
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    idle: AtomicBool,
+    stopped: AtomicBool,
+    runs: AtomicU64,
+}
+
+/// The handle of the maintenance thread. The thread stops after the [`Self::stop`]
+/// or the drop of the handle.
+#[derive(Debug)]
+pub struct MaintenanceHandle {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MaintenanceHandle {
+    /// Starts the maintenance of the `storage` with the `config`. The disabled maintenance
+    /// and the maintenance of the storage without the engine statistics do nothing.
+    pub fn start<Description>(
+        storage: DataSource<Description>,
+        config: MaintenanceConfig,
+    ) -> DatabaseResult<Self>
+    where
+        Description: DatabaseDescription,
+    {
+        let shared = Arc::new(Shared::default());
+        let has_engine = !storage.engine_stats()?.columns.is_empty();
+        if !config.enabled || !has_engine {
+            return Ok(Self {
+                shared,
+                thread: None,
+            })
+        }
+
+        let thread = std::thread::spawn({
+            let shared = shared.clone();
+            move || {
+                while !shared.stopped.load(Ordering::Acquire) {
+                    std::thread::park_timeout(config.interval);
+                    if shared.stopped.load(Ordering::Acquire) {
+                        break
+                    }
+                    match run_once(&storage, &config, &shared) {
+                        Ok(true) => {
+                            shared.runs.fetch_add(1, Ordering::AcqRel);
+                        }
+                        Ok(false) => {}
+                        Err(err) => tracing::warn!(
+                            "The maintenance of the `{}` database failed: {:?}",
+                            Description::name(),
+                            err
+                        ),
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    /// Reports whether the node is idle, e.g. between blocks. The idle node is maintained
+    /// outside of quiet windows too.
+    pub fn set_idle(&self, idle: bool) {
+        self.shared.idle.store(idle, Ordering::Release);
+    }
+
+    /// Returns `true` if the maintenance thread is running.
+    pub fn is_running(&self) -> bool {
+        self.thread.is_some() && !self.shared.stopped.load(Ordering::Acquire)
+    }
+
+    /// Returns the number of runs that flushed or compacted the database.
+    pub fn runs(&self) -> u64 {
+        self.shared.runs.load(Ordering::Acquire)
+    }
+
+    /// Stops the thread and waits for the run in progress, if any.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.shared.stopped.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MaintenanceHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Flushes and compacts the `storage` if it is the time and there is the work.
+/// Returns `true` if there was the work.
+fn run_once<Description>(
+    storage: &DataSource<Description>,
+    config: &MaintenanceConfig,
+    shared: &Shared,
+) -> DatabaseResult<bool>
+where
+    Description: DatabaseDescription,
+{
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    if !shared.idle.load(Ordering::Acquire) && !config.is_quiet(now) {
+        return Ok(false)
+    }
+    let stats = storage.engine_stats()?;
+    let plan = config.plan::<Description>(&stats);
+    if plan.is_empty() {
+        return Ok(false)
+    }
+    tracing::debug!(
+        "The maintenance of the `{}` database: {:?}",
+        Description::name(),
+        plan
+    );
+    if plan.flush {
+        storage.flush()?;
+    }
+    for column in plan.compact {
+        if shared.stopped.load(Ordering::Acquire) {
+            break
+        }
+        storage.compact(Some(column), None)?;
+    }
+    Ok(true)
+}
+
+impl<Description> Database<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Starts the scheduler of flushes and compactions of the database,
+    /// see [`MaintenanceHandle`].
+    pub fn start_maintenance(
+        &self,
+        config: MaintenanceConfig,
+    ) -> DatabaseResult<MaintenanceHandle> {
+        MaintenanceHandle::start(self.data.as_ref().clone(), config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::on_chain::OnChain,
+        state::ColumnEngineStats,
+    };
+    use fuel_core_storage::column::Column;
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    fn stats(columns: Vec<ColumnEngineStats>) -> EngineStats {
+        EngineStats {
+            columns,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn plan_flushes_and_compacts_columns_with_pending_work() {
+        // Given
+        let config = MaintenanceConfig::default();
+        let stats = stats(vec![
+            ColumnEngineStats {
+                column: Column::Coins.name(),
+                immutable_memtables: 1,
+                ..Default::default()
+            },
+            ColumnEngineStats {
+                column: Column::ContractsState.name(),
+                pending_compaction_bytes: config.pending_compaction_bytes,
+                ..Default::default()
+            },
+            ColumnEngineStats {
+                column: Column::Transactions.name(),
+                level0_files: config.level0_files,
+                ..Default::default()
+            },
+            ColumnEngineStats {
+                column: Column::Messages.name(),
+                level0_files: 1,
+                ..Default::default()
+            },
+        ]);
+
+        // When
+        let plan = config.plan::<OnChain>(&stats);
+
+        // Then
+        assert!(plan.flush);
+        assert_eq!(
+            plan.compact,
+            vec![Column::ContractsState, Column::Transactions]
+        );
+    }
+
+    #[test]
+    fn plan_of_the_storage_without_pending_work_is_empty() {
+        // Given
+        let config = MaintenanceConfig::default();
+        let stats = stats(vec![ColumnEngineStats {
+            column: Column::Coins.name(),
+            level0_files: 1,
+            ..Default::default()
+        }]);
+
+        // When
+        let plan = config.plan::<OnChain>(&stats);
+
+        // Then
+        assert!(plan.is_empty());
+    }
+
+    #[test]
+    fn quiet_windows_may_span_the_midnight() {
+        // Given
+        let config = MaintenanceConfig {
+            quiet_windows: vec![
+                QuietWindow {
+                    start: 2 * HOUR,
+                    end: 4 * HOUR,
+                },
+                QuietWindow {
+                    start: 23 * HOUR,
+                    end: HOUR,
+                },
+            ],
+            ..Default::default()
+        };
+        let day = DAY * 100;
+
+        // Then
+        assert!(config.is_quiet(day + 3 * HOUR));
+        assert!(!config.is_quiet(day + 4 * HOUR));
+        assert!(config.is_quiet(day + 23 * HOUR + HOUR / 2));
+        assert!(config.is_quiet(day + HOUR / 2));
+        assert!(!config.is_quiet(day + 12 * HOUR));
+        assert!(!MaintenanceConfig::default().is_quiet(day));
+    }
+
+    #[test]
+    fn maintenance_of_the_in_memory_database_does_nothing() {
+        // Given
+        let database = Database::<OnChain>::in_memory();
+        let config = MaintenanceConfig {
+            enabled: true,
+            ..Default::default()
+        };
+
+        // When
+        let handle = database.start_maintenance(config).unwrap();
+
+        // Then
+        assert!(!handle.is_running());
+        handle.stop();
+    }
+
+    #[cfg(feature = "rocksdb")]
+    mod rocksdb {
+        use super::*;
+        use crate::state::{
+            column_config::{
+                ColumnConfig,
+                ColumnsConfig,
+            },
+            rocks_db::RocksDb,
+            TransactableStorage,
+        };
+        use fuel_core_storage::kv_store::KeyValueStore;
+        use std::time::Instant;
+
+        /// Returns the RocksDB with several files on the first level of the `Coins`.
+        fn database_with_pending_work(dir: &std::path::Path) -> Database<OnChain> {
+            let columns = ColumnsConfig::default().with_column(
+                OnChain::name(),
+                Column::Coins.name(),
+                ColumnConfig {
+                    write_buffer_size: 64 * 1024,
+                    ..Default::default()
+                },
+            );
+            let db = RocksDb::<OnChain>::open_with_config(
+                dir,
+                enum_iterator::all::<Column>().collect(),
+                None,
+                &columns,
+            )
+            .unwrap();
+            for round in 0..3u32 {
+                for key in 0..100u32 {
+                    let key = [round.to_be_bytes(), key.to_be_bytes()].concat();
+                    db.put(&key, Column::Coins, Arc::new(vec![1; 1024]))
+                        .unwrap();
+                }
+                db.flush().unwrap();
+            }
+            Database {
+                data: fuel_core_storage::structured_storage::StructuredStorage::new(
+                    Arc::new(db).into(),
+                ),
+                _drop: Default::default(),
+            }
+        }
+
+        fn level0_files(database: &Database<OnChain>) -> u64 {
+            database
+                .engine_stats()
+                .unwrap()
+                .columns
+                .into_iter()
+                .find(|stats| stats.column == Column::Coins.name())
+                .unwrap()
+                .level0_files
+        }
+
+        fn config() -> MaintenanceConfig {
+            MaintenanceConfig {
+                enabled: true,
+                interval: Duration::from_millis(10),
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn idle_signal_triggers_the_compaction_of_pending_work() {
+            // Given
+            let tmp = tempfile::tempdir().unwrap();
+            let database = database_with_pending_work(tmp.path());
+            assert!(level0_files(&database) >= 2);
+            let handle = database.start_maintenance(config()).unwrap();
+            std::thread::sleep(Duration::from_millis(50));
+            let runs_while_busy = handle.runs();
+
+            // When
+            handle.set_idle(true);
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while handle.runs() == 0 && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+
+            // Then
+            assert_eq!(runs_while_busy, 0);
+            assert!(handle.runs() > 0);
+            assert!(level0_files(&database) < 2);
+            handle.stop();
+        }
+
+        #[test]
+        fn disabled_maintenance_never_runs() {
+            // Given
+            let tmp = tempfile::tempdir().unwrap();
+            let database = database_with_pending_work(tmp.path());
+            let files = level0_files(&database);
+            let config = MaintenanceConfig {
+                enabled: false,
+                ..config()
+            };
+
+            // When
+            let handle = database.start_maintenance(config).unwrap();
+            handle.set_idle(true);
+            std::thread::sleep(Duration::from_millis(100));
+
+            // Then
+            assert!(!handle.is_running());
+            assert_eq!(handle.runs(), 0);
+            assert_eq!(level0_files(&database), files);
+        }
+    }
+}
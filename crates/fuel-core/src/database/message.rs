@@ -54,9 +54,12 @@ impl Database {
             .map(|res| res.map(|(_, message)| message))
     }
 
-    pub fn get_message_config(&self) -> StorageResult<Option<Vec<MessageConfig>>> {
-        let configs = self
-            .all_messages(None, None)
+    /// Returns the iterator over configs of all unspent messages.
+    pub fn message_configs(
+        &self,
+    ) -> impl Iterator<Item = StorageResult<MessageConfig>> + '_ {
+        self.maintenance_scan::<Messages>(None)
+            .map(|res| res.map(|(_, message)| message))
             .filter_map(|msg| {
                 // Return only unspent messages
                 if let Ok(msg) = msg {
@@ -81,6 +84,11 @@ impl Database {
                     da_height: msg.da_height(),
                 })
             })
+    }
+
+    pub fn get_message_config(&self) -> StorageResult<Option<Vec<MessageConfig>>> {
+        let configs = self
+            .message_configs()
             .collect::<StorageResult<Vec<MessageConfig>>>()?;
 
         Ok(Some(configs))
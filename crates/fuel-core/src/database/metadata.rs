@@ -1,18 +1,34 @@
 use crate::{
     database::{
+        column_lifecycle::ColumnRegistry,
         database_description::{
             DatabaseDescription,
             DatabaseMetadata,
         },
+        index_rebuild::IndexRebuildProgress as IndexRebuildProgressValue,
+        pruning::PruningProgress as PruningProgressValue,
         storage::UseStructuredImplementation,
         Database,
         Error as DatabaseError,
     },
-    state::DataSource,
+    state::{
+        checksummed::ChecksumMode,
+        DataSource,
+    },
 };
 use fuel_core_storage::{
     blueprint::plain::Plain,
-    codec::postcard::Postcard,
+    codec::{
+        postcard::Postcard,
+        primitive::Primitive,
+        raw::Raw,
+        Decode,
+        Encode,
+    },
+    kv_store::{
+        KeyValueStore,
+        WriteOperation,
+    },
     not_found,
     structured_storage::{
         StructuredStorage,
@@ -23,7 +39,10 @@ use fuel_core_storage::{
     Result as StorageResult,
     StorageAsRef,
 };
-use fuel_core_types::fuel_merkle::storage::StorageMutate;
+use fuel_core_types::{
+    fuel_merkle::storage::StorageMutate,
+    fuel_types::BlockHeight,
+};
 
 /// The table that stores all metadata about the database.
 pub struct MetadataTable<Description>(core::marker::PhantomData<Description>);
@@ -107,3 +126,303 @@ where
         Ok(*metadata.height())
     }
 }
+
+/// The records of the metadata column. Keys of the metadata column that don't belong
+/// to any record are reported as unknown by the integrity verification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, enum_iterator::Sequence)]
+pub enum MetadataEntry {
+    /// The [`DatabaseMetadata`] of the [`MetadataTable`].
+    DatabaseMetadata,
+    /// The schema version of the data directory.
+    SchemaVersion,
+    /// The earliest height that is not pruned.
+    PruningHorizon,
+    /// The progress of the pruning in progress.
+    PruningProgress,
+    /// The number of chunks applied by the chunked commit in progress.
+    ChunkedCommitJournal,
+    /// The record that checks the encryption key.
+    EncryptionKeyCheck,
+    /// The progress markers of migrations, one per migrated column.
+    MigrationProgress,
+    /// The mode of checksums of values.
+    ValueChecksums,
+    /// The progress of the rebuild of the index in progress.
+    IndexRebuildProgress,
+    /// The intents of multi-batch operations in progress, one per type of the operation,
+    /// see [`recovery`](crate::database::recovery).
+    RecoveryJournal,
+    /// The ids and names of all columns ever created in the database.
+    CreatedColumns,
+}
+
+impl MetadataEntry {
+    /// Returns the key of the record in the metadata column, or the prefix of keys
+    /// if the record [`is_prefix`](Self::is_prefix). Keys are stored on disk,
+    /// so they must never change.
+    pub const fn key(&self) -> &'static [u8] {
+        match self {
+            // The `()` key of the `MetadataTable` is encoded by the `Postcard` as no bytes.
+            Self::DatabaseMetadata => b"",
+            Self::SchemaVersion => b"schema_version",
+            Self::PruningHorizon => b"pruning_horizon",
+            Self::PruningProgress => b"pruning_progress",
+            Self::ChunkedCommitJournal => b"chunked_commit_in_progress",
+            Self::EncryptionKeyCheck => b"encryption_key_check",
+            Self::MigrationProgress => b"migration_progress",
+            Self::ValueChecksums => b"value_checksums",
+            Self::IndexRebuildProgress => b"index_rebuild_progress",
+            Self::RecoveryJournal => b"recovery_journal/",
+            Self::CreatedColumns => b"created_columns",
+        }
+    }
+
+    /// Returns `true` if the [`key`](Self::key) is the prefix of keys of several records.
+    pub const fn is_prefix(&self) -> bool {
+        matches!(self, Self::MigrationProgress | Self::RecoveryJournal)
+    }
+
+    /// Returns the record of the `key` of the metadata column, or `None` if the key is unknown.
+    pub fn of_key(key: &[u8]) -> Option<Self> {
+        enum_iterator::all::<Self>().find(|entry| {
+            if entry.is_prefix() {
+                key.starts_with(entry.key())
+            } else {
+                key == entry.key()
+            }
+        })
+    }
+}
+
+/// The typed record of the metadata column.
+pub trait MetadataKey {
+    /// The record of the metadata column.
+    const ENTRY: MetadataEntry;
+    /// The type of the value.
+    type Value;
+    /// The codec of the value.
+    type Codec: Encode<Self::Value> + Decode<Self::Value>;
+}
+
+/// The schema version of the data directory.
+pub struct SchemaVersion;
+
+impl MetadataKey for SchemaVersion {
+    const ENTRY: MetadataEntry = MetadataEntry::SchemaVersion;
+    type Value = u32;
+    type Codec = Primitive<4>;
+}
+
+/// The earliest height that is not pruned.
+pub struct PruningHorizon;
+
+impl MetadataKey for PruningHorizon {
+    const ENTRY: MetadataEntry = MetadataEntry::PruningHorizon;
+    type Value = BlockHeight;
+    type Codec = Primitive<4>;
+}
+
+/// The progress of the pruning in progress.
+pub(crate) struct PruningProgress;
+
+impl MetadataKey for PruningProgress {
+    const ENTRY: MetadataEntry = MetadataEntry::PruningProgress;
+    type Value = PruningProgressValue;
+    type Codec = Postcard;
+}
+
+/// The number of chunks applied by the chunked commit in progress.
+pub struct ChunkedCommitJournal;
+
+impl MetadataKey for ChunkedCommitJournal {
+    const ENTRY: MetadataEntry = MetadataEntry::ChunkedCommitJournal;
+    type Value = u64;
+    type Codec = Primitive<8>;
+}
+
+/// The encrypted record that checks the encryption key.
+pub struct EncryptionKeyCheck;
+
+impl MetadataKey for EncryptionKeyCheck {
+    const ENTRY: MetadataEntry = MetadataEntry::EncryptionKeyCheck;
+    type Value = Vec<u8>;
+    type Codec = Raw;
+}
+
+/// The mode of checksums of values, see [`ChecksumMode`].
+pub struct ValueChecksums;
+
+impl MetadataKey for ValueChecksums {
+    const ENTRY: MetadataEntry = MetadataEntry::ValueChecksums;
+    type Value = ChecksumMode;
+    type Codec = Postcard;
+}
+
+/// The progress of the rebuild of the index in progress.
+pub(crate) struct IndexRebuildProgress;
+
+impl MetadataKey for IndexRebuildProgress {
+    const ENTRY: MetadataEntry = MetadataEntry::IndexRebuildProgress;
+    type Value = IndexRebuildProgressValue;
+    type Codec = Postcard;
+}
+
+/// The ids and names of all columns ever created in the database.
+pub(crate) struct CreatedColumns;
+
+impl MetadataKey for CreatedColumns {
+    const ENTRY: MetadataEntry = MetadataEntry::CreatedColumns;
+    type Value = ColumnRegistry;
+    type Codec = Postcard;
+}
+
+/// Returns the value of the record `K` of the metadata column, or `None` if it is not stored.
+pub fn get_metadata<K, Description>(
+    store: &(impl KeyValueStore<Column = Description::Column> + ?Sized),
+) -> StorageResult<Option<K::Value>>
+where
+    K: MetadataKey,
+    Description: DatabaseDescription,
+{
+    store
+        .get(K::ENTRY.key(), Description::metadata_column())?
+        .map(|value| K::Codec::decode(value.as_slice()).map_err(StorageError::Other))
+        .transpose()
+}
+
+/// Stores the `value` of the record `K` in the metadata column.
+pub fn set_metadata<K, Description>(
+    store: &(impl KeyValueStore<Column = Description::Column> + ?Sized),
+    value: &K::Value,
+) -> StorageResult<()>
+where
+    K: MetadataKey,
+    Description: DatabaseDescription,
+{
+    store.put(
+        K::ENTRY.key(),
+        Description::metadata_column(),
+        K::Codec::encode_as_value(value),
+    )
+}
+
+/// Removes the record `K` from the metadata column.
+pub fn remove_metadata<K, Description>(
+    store: &(impl KeyValueStore<Column = Description::Column> + ?Sized),
+) -> StorageResult<()>
+where
+    K: MetadataKey,
+    Description: DatabaseDescription,
+{
+    store.delete(K::ENTRY.key(), Description::metadata_column())
+}
+
+/// Returns the operation of the batch that stores the `value` of the record `K`,
+/// or removes the record if the `value` is `None`.
+pub fn metadata_write<K, Description>(
+    value: Option<&K::Value>,
+) -> (Vec<u8>, Description::Column, WriteOperation)
+where
+    K: MetadataKey,
+    Description: DatabaseDescription,
+{
+    let operation = match value {
+        Some(value) => WriteOperation::Insert(K::Codec::encode_as_value(value)),
+        None => WriteOperation::Remove,
+    };
+    (
+        K::ENTRY.key().to_vec(),
+        Description::metadata_column(),
+        operation,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::on_chain::OnChain,
+        state::in_memory::{
+            memory_store::MemoryStore,
+            transaction::MemoryTransactionView,
+        },
+    };
+    use fuel_core_storage::{
+        codec::Encoder,
+        column::Column,
+        kv_store::BatchOperations,
+    };
+    use std::sync::Arc;
+
+    #[test]
+    fn keys_of_metadata_entries_are_unique() {
+        let entries = enum_iterator::all::<MetadataEntry>().collect::<Vec<_>>();
+        for (i, a) in entries.iter().enumerate() {
+            for b in &entries[i + 1..] {
+                assert_ne!(a.key(), b.key(), "{a:?} and {b:?} have the same key");
+                for (prefix, other) in [(a, b), (b, a)] {
+                    assert!(
+                        !(prefix.is_prefix() && other.key().starts_with(prefix.key())),
+                        "The key of {other:?} starts with the prefix of {prefix:?}"
+                    );
+                }
+            }
+            assert_eq!(MetadataEntry::of_key(a.key()), Some(*a));
+        }
+    }
+
+    #[test]
+    fn key_of_database_metadata_is_the_key_of_metadata_table() {
+        assert_eq!(
+            Postcard::encode(&()).as_bytes().as_ref(),
+            MetadataEntry::DatabaseMetadata.key()
+        );
+    }
+
+    #[test]
+    fn of_key_finds_prefixed_records_and_rejects_unknown_keys() {
+        // Given
+        let marker = [MetadataEntry::MigrationProgress.key(), &[0, 0, 0, 1]].concat();
+
+        // When
+        let found = MetadataEntry::of_key(&marker);
+        let unknown = MetadataEntry::of_key(b"schema_version_2");
+
+        // Then
+        assert_eq!(found, Some(MetadataEntry::MigrationProgress));
+        assert_eq!(unknown, None);
+    }
+
+    #[test]
+    fn metadata_round_trips_inside_the_transaction_view() {
+        // Given
+        let store = Arc::new(MemoryStore::<OnChain>::default());
+        let view = MemoryTransactionView::<OnChain>::new(store.clone());
+
+        // When
+        set_metadata::<SchemaVersion, OnChain>(&view, &7).unwrap();
+        view.batch_write(
+            &mut [metadata_write::<PruningHorizon, OnChain>(Some(&5.into()))].into_iter(),
+        )
+        .unwrap();
+
+        // Then
+        assert_eq!(
+            get_metadata::<SchemaVersion, OnChain>(&view).unwrap(),
+            Some(7)
+        );
+        assert_eq!(
+            get_metadata::<PruningHorizon, OnChain>(&view).unwrap(),
+            Some(5.into())
+        );
+        assert_eq!(
+            get_metadata::<SchemaVersion, OnChain>(&*store).unwrap(),
+            None
+        );
+        view.commit().unwrap();
+        assert_eq!(
+            store.get(b"schema_version", Column::Metadata).unwrap(),
+            Some(Arc::new(7u32.to_be_bytes().to_vec()))
+        );
+    }
+}
@@ -0,0 +1,458 @@
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        metadata::MetadataEntry,
+        schema_version::set_schema_version,
+        storage_map::StorageMap,
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::{
+        in_memory::transaction::WriteEntry,
+        TransactableStorage,
+    },
+};
+use fuel_core_storage::{
+    codec::raw::Raw,
+    iter::IterDirection,
+    kv_store::{
+        StorageColumn,
+        Value,
+        WriteOperation,
+    },
+};
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    sync::Arc,
+};
+
+/// The prefix of the keys in the metadata column that store the progress of migrations.
+const PROGRESS_PREFIX: &[u8] = MetadataEntry::MigrationProgress.key();
+/// The progress marker of the column with all entries migrated.
+const COMPLETED: u8 = 0;
+/// The progress marker of the column followed by the last migrated key.
+const IN_PROGRESS: u8 = 1;
+
+/// The migration of the data from the previous schema version to the [`Migration::version`].
+pub trait Migration<Description>: Debug + Send + Sync
+where
+    Description: DatabaseDescription,
+{
+    /// Returns the schema version of the data after the migration.
+    fn version(&self) -> u32;
+
+    /// Runs the migration. The migration may be interrupted at any point,
+    /// so it should track its progress(for example, with [`migrate_column`])
+    /// to resume from the same point without applying changes twice.
+    fn run(
+        &self,
+        db: &dyn TransactableStorage<Column = Description::Column>,
+    ) -> DatabaseResult<()>;
+}
+
+/// The registry of migrations ordered by the schema version.
+#[derive(Debug)]
+pub struct Migrations<Description>
+where
+    Description: DatabaseDescription,
+{
+    migrations: BTreeMap<u32, Box<dyn Migration<Description>>>,
+}
+
+impl<Description> Default for Migrations<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn default() -> Self {
+        Self {
+            migrations: Default::default(),
+        }
+    }
+}
+
+impl<Description> Migrations<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Registers the `migration`. It replaces the migration with the same version.
+    pub fn with<M>(mut self, migration: M) -> Self
+    where
+        M: Migration<Description> + 'static,
+    {
+        self.migrations
+            .insert(migration.version(), Box::new(migration));
+        self
+    }
+
+    /// Runs migrations one by one from the schema version `from` to the `to`.
+    /// The schema version is bumped after each completed migration,
+    /// so already completed migrations are not repeated after an interruption.
+    pub fn migrate(
+        &self,
+        from: u32,
+        to: u32,
+        db: &dyn TransactableStorage<Column = Description::Column>,
+    ) -> DatabaseResult<()> {
+        let mut current = from;
+        while current < to {
+            let next = current.saturating_add(1);
+            let migration =
+                self.migrations
+                    .get(&next)
+                    .ok_or(DatabaseError::MissingMigration {
+                        from: current,
+                        to: next,
+                    })?;
+            migration.run(db)?;
+            clear_progress::<Description>(db, next)?;
+            set_schema_version::<Description>(db, next)?;
+            current = next;
+        }
+        Ok(())
+    }
+}
+
+/// Migrates all entries of the `column` for the migration to the schema `version`.
+/// Each entry is passed to the `migrate` that returns the writes replacing it.
+/// The writes must not insert new keys after the current key into the `column`
+/// because they would be migrated again.
+///
+/// Entries are read in batches of the `batch_size` and committed with a progress marker
+/// in one [`BatchOperations::batch_write`](fuel_core_storage::kv_store::BatchOperations::batch_write),
+/// so the interrupted migration resumes from the last committed batch.
+pub fn migrate_column<Description, F>(
+    db: &dyn TransactableStorage<Column = Description::Column>,
+    version: u32,
+    column: Description::Column,
+    batch_size: usize,
+    mut migrate: F,
+) -> DatabaseResult<()>
+where
+    Description: DatabaseDescription,
+    F: FnMut(Vec<u8>, Value) -> DatabaseResult<Vec<WriteEntry<Description::Column>>>,
+{
+    let progress_key = progress_key(version, column);
+    let mut last_key = match progress_markers::<Description>(db).get(&progress_key)? {
+        None => None,
+        Some(progress) => match progress.split_first() {
+            Some((&COMPLETED, [])) => return Ok(()),
+            Some((&IN_PROGRESS, key)) => Some(key.to_vec()),
+            _ => return Err(DatabaseError::Codec),
+        },
+    };
+    let batch_size = batch_size.max(1);
+
+    loop {
+        // The iterator is recreated for each batch to not hold it during writes.
+        let entries = db
+            .iter_all(column, None, last_key.as_deref(), IterDirection::Forward)
+            .filter(|entry| {
+                !matches!((entry, &last_key), (Ok((key, _)), Some(last)) if key == last)
+            })
+            .take(batch_size)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(DatabaseError::from)?;
+        let completed = entries.len() < batch_size;
+
+        let mut writes = vec![];
+        for (key, value) in entries {
+            last_key = Some(key.clone());
+            writes.extend(migrate(key, value)?);
+        }
+        let progress = match (&last_key, completed) {
+            (_, true) | (None, _) => vec![COMPLETED],
+            (Some(key), false) => [&[IN_PROGRESS], key.as_slice()].concat(),
+        };
+        writes.push((
+            progress_key.clone(),
+            Description::metadata_column(),
+            WriteOperation::Insert(Arc::new(progress)),
+        ));
+        db.batch_write(&mut writes.into_iter())
+            .map_err(DatabaseError::from)?;
+
+        if completed {
+            return Ok(())
+        }
+    }
+}
+
+/// The reference migration that re-encodes all values of the column.
+/// The encoding is not changed, so the migration doesn't modify the data.
+#[derive(Debug, Clone, Copy)]
+pub struct ReencodeColumn<Column> {
+    /// The schema version after the migration.
+    pub version: u32,
+    /// The migrated column.
+    pub column: Column,
+    /// The number of entries committed at once.
+    pub batch_size: usize,
+}
+
+impl<Description> Migration<Description> for ReencodeColumn<Description::Column>
+where
+    Description: DatabaseDescription,
+    Description::Column: Send + Sync,
+{
+    fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn run(
+        &self,
+        db: &dyn TransactableStorage<Column = Description::Column>,
+    ) -> DatabaseResult<()> {
+        migrate_column::<Description, _>(
+            db,
+            self.version,
+            self.column,
+            self.batch_size,
+            |key, value| {
+                let value = value.as_ref().clone();
+                Ok(vec![(
+                    key,
+                    self.column,
+                    WriteOperation::Insert(Arc::new(value)),
+                )])
+            },
+        )
+    }
+}
+
+fn progress_key<Column: StorageColumn>(version: u32, column: Column) -> Vec<u8> {
+    [
+        PROGRESS_PREFIX,
+        &version.to_be_bytes(),
+        &column.id().to_be_bytes(),
+    ]
+    .concat()
+}
+
+/// The progress markers of migrations, keyed by [`progress_key`].
+fn progress_markers<Description>(
+    db: &dyn TransactableStorage<Column = Description::Column>,
+) -> StorageMap<
+    dyn TransactableStorage<Column = Description::Column> + '_,
+    Vec<u8>,
+    Vec<u8>,
+    Raw,
+    Raw,
+>
+where
+    Description: DatabaseDescription,
+{
+    StorageMap::new(db, Description::metadata_column())
+}
+
+/// Removes the progress markers of the migration to the schema `version`.
+fn clear_progress<Description>(
+    db: &dyn TransactableStorage<Column = Description::Column>,
+    version: u32,
+) -> DatabaseResult<()>
+where
+    Description: DatabaseDescription,
+{
+    let prefix = [PROGRESS_PREFIX, &version.to_be_bytes()].concat();
+    let keys = progress_markers::<Description>(db)
+        .iter(Some(&prefix), IterDirection::Forward)
+        .map(|entry| {
+            entry.map(|(key, _)| {
+                (key, Description::metadata_column(), WriteOperation::Remove)
+            })
+        })
+        .collect::<DatabaseResult<Vec<_>>>()?;
+    db.batch_write(&mut keys.into_iter())
+        .map_err(DatabaseError::from)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{
+            database_description::on_chain::OnChain,
+            schema_version::schema_version,
+        },
+        state::in_memory::memory_store::MemoryStore,
+    };
+    use fuel_core_storage::{
+        column::Column,
+        iter::IteratorableStore,
+        kv_store::KeyValueStore,
+    };
+    use std::sync::Mutex;
+
+    fn store_with_coins(count: u8) -> MemoryStore {
+        let db = MemoryStore::default();
+        for i in 0..count {
+            db.put(&[i], Column::Coins, Arc::new(vec![i])).unwrap();
+        }
+        db
+    }
+
+    fn coins(db: &MemoryStore) -> Vec<(Vec<u8>, Vec<u8>)> {
+        IteratorableStore::iter_all(db, Column::Coins, None, None, IterDirection::Forward)
+            .map(|entry| {
+                let (key, value) = entry.unwrap();
+                (key, value.to_vec())
+            })
+            .collect()
+    }
+
+    /// Increments the value, so applying it twice is visible.
+    fn increment(key: Vec<u8>, value: Value) -> DatabaseResult<Vec<WriteEntry<Column>>> {
+        let value = value.iter().map(|byte| byte.saturating_add(100)).collect();
+        Ok(vec![(
+            key,
+            Column::Coins,
+            WriteOperation::Insert(Arc::new(value)),
+        )])
+    }
+
+    #[test]
+    fn migrate_column_applies_each_entry_once() {
+        // Given
+        let db = store_with_coins(10);
+
+        // When
+        migrate_column::<OnChain, _>(&db, 1, Column::Coins, 3, increment).unwrap();
+        migrate_column::<OnChain, _>(&db, 1, Column::Coins, 3, increment).unwrap();
+
+        // Then
+        let expected = (0..10u8)
+            .map(|i| (vec![i], vec![i.saturating_add(100)]))
+            .collect::<Vec<_>>();
+        assert_eq!(coins(&db), expected);
+    }
+
+    #[test]
+    fn interrupted_migrate_column_resumes_from_progress_marker() {
+        // Given
+        let db = store_with_coins(10);
+        let mut processed = 0;
+        let result =
+            migrate_column::<OnChain, _>(&db, 1, Column::Coins, 3, |key, value| {
+                processed += 1;
+                if processed == 8 {
+                    return Err(anyhow::anyhow!("The migration is killed").into())
+                }
+                increment(key, value)
+            });
+        assert!(result.is_err());
+        // Only the first two batches are committed.
+        let migrated = coins(&db)
+            .into_iter()
+            .filter(|(key, value)| value[0] != key[0])
+            .count();
+        assert_eq!(migrated, 6);
+
+        // When
+        let mut resumed = vec![];
+        migrate_column::<OnChain, _>(&db, 1, Column::Coins, 3, |key, value| {
+            resumed.push(key[0]);
+            increment(key, value)
+        })
+        .unwrap();
+
+        // Then
+        assert_eq!(resumed, vec![6, 7, 8, 9]);
+        let expected = (0..10u8)
+            .map(|i| (vec![i], vec![i.saturating_add(100)]))
+            .collect::<Vec<_>>();
+        assert_eq!(coins(&db), expected);
+    }
+
+    #[derive(Debug)]
+    struct RecordingMigration {
+        version: u32,
+        runs: Arc<Mutex<Vec<u32>>>,
+    }
+
+    impl Migration<OnChain> for RecordingMigration {
+        fn version(&self) -> u32 {
+            self.version
+        }
+
+        fn run(
+            &self,
+            _: &dyn TransactableStorage<Column = Column>,
+        ) -> DatabaseResult<()> {
+            self.runs.lock().unwrap().push(self.version);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn migrations_run_in_version_order() {
+        // Given
+        let db = MemoryStore::<OnChain>::default();
+        let runs = Arc::new(Mutex::new(vec![]));
+        let migrations = [3, 1, 2].into_iter().fold(
+            Migrations::<OnChain>::default(),
+            |migrations, version| {
+                migrations.with(RecordingMigration {
+                    version,
+                    runs: runs.clone(),
+                })
+            },
+        );
+
+        // When
+        migrations.migrate(0, 3, &db).unwrap();
+
+        // Then
+        assert_eq!(*runs.lock().unwrap(), vec![1, 2, 3]);
+        assert_eq!(schema_version::<OnChain>(&db).unwrap(), Some(3));
+    }
+
+    #[test]
+    fn migrations_fail_on_missing_version() {
+        // Given
+        let db = MemoryStore::<OnChain>::default();
+        let runs = Arc::new(Mutex::new(vec![]));
+        let migrations = Migrations::<OnChain>::default().with(RecordingMigration {
+            version: 1,
+            runs: runs.clone(),
+        });
+
+        // When
+        let result = migrations.migrate(0, 2, &db);
+
+        // Then
+        assert!(matches!(
+            result,
+            Err(DatabaseError::MissingMigration { from: 1, to: 2 })
+        ));
+        assert_eq!(*runs.lock().unwrap(), vec![1]);
+        assert_eq!(schema_version::<OnChain>(&db).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn reencode_column_keeps_data_and_clears_progress() {
+        // Given
+        let db = store_with_coins(5);
+        let before = coins(&db);
+        let migrations = Migrations::<OnChain>::default().with(ReencodeColumn {
+            version: 1,
+            column: Column::Coins,
+            batch_size: 2,
+        });
+
+        // When
+        migrations.migrate(0, 1, &db).unwrap();
+
+        // Then
+        assert_eq!(coins(&db), before);
+        let progress = IteratorableStore::iter_all(
+            &db,
+            Column::Metadata,
+            Some(PROGRESS_PREFIX),
+            None,
+            IterDirection::Forward,
+        )
+        .count();
+        assert_eq!(progress, 0);
+    }
+}
@@ -0,0 +1,487 @@
+//! The pruning of the old data for nodes that don't need the whole history.
+//!
+//! Entries of height-keyed columns below the pruning horizon are removed in bounded
+//! batches. The horizon is recorded in the metadata column, so queries of pruned heights
+//! fail with the [`DatabaseError::Pruned`] instead of returning nothing.
+
+use crate::{
+    database::{
+        database_description::{
+            on_chain::OnChain,
+            DatabaseDescription,
+        },
+        metadata::{
+            self,
+            get_metadata,
+            metadata_write,
+            remove_metadata,
+            PruningHorizon,
+        },
+        Database,
+        Error as DatabaseError,
+    },
+    state::throttled::{
+        CancellationToken,
+        IterBudget,
+        Throttle,
+        ThrottledIter,
+    },
+};
+use fuel_core_storage::{
+    codec::height::BlockHeightKey,
+    column::Column,
+    iter::{
+        IterConfig,
+        IterOptions,
+    },
+    kv_store::{
+        BatchOperations,
+        StorageColumn,
+        WriteOperation,
+    },
+    Result as StorageResult,
+};
+use fuel_core_types::fuel_types::BlockHeight;
+use std::{
+    ops::Bound,
+    sync::Mutex,
+};
+
+/// The maximum number of entries removed by one batch.
+pub const PRUNING_BATCH_SIZE: usize = 10_000;
+
+/// Serializes prunings, so one pruning doesn't overwrite the progress of another.
+static PRUNING_LOCK: Mutex<()> = Mutex::new(());
+
+/// The configuration of the pruning.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PruningConfig {
+    /// The number of the latest blocks to keep. The `None` keeps all blocks,
+    /// like the archival node does. The latest block is always kept.
+    pub keep_blocks: Option<u64>,
+    /// The pruned columns. Keys of the columns must be [`BlockHeightKey`]s.
+    pub columns: Vec<Column>,
+    /// The budget of the iteration over the pruned columns, so the pruning doesn't
+    /// slow down foreground queries.
+    pub budget: IterBudget,
+}
+
+impl PruningConfig {
+    /// Returns the pruning horizon when the `latest` height is the latest block,
+    /// or `None` if nothing should be pruned.
+    pub fn horizon(&self, latest: BlockHeight) -> Option<BlockHeight> {
+        let keep = self.keep_blocks?.max(1);
+        let horizon = u64::from(*latest).saturating_add(1).saturating_sub(keep);
+        let horizon = u32::try_from(horizon).unwrap_or(u32::MAX);
+        (horizon > 0).then(|| horizon.into())
+    }
+}
+
+/// The progress of the pruning in progress. The record exists only while
+/// the pruning is in progress, and the interrupted pruning resumes from it.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct PruningProgress {
+    /// The height below which entries are removed.
+    horizon: u32,
+    /// The id of the column and the key of the last removed entry.
+    last_pruned: Option<(u32, Vec<u8>)>,
+}
+
+impl PruningProgress {
+    fn entry(&self) -> (Vec<u8>, Column, WriteOperation) {
+        metadata_write::<metadata::PruningProgress, OnChain>(Some(self))
+    }
+}
+
+impl Database<OnChain> {
+    /// Prunes the data of the `config` below the horizon of the latest height.
+    /// Returns the number of removed entries.
+    pub fn prune(&self, config: &PruningConfig) -> StorageResult<u64> {
+        self.prune_with_cancellation(config, CancellationToken::new())
+    }
+
+    /// Prunes the data of the `config` like the [`Database::prune`], but stops with
+    /// the [`DatabaseError::Cancelled`] once the `token` is cancelled. The next pruning
+    /// resumes from the last removed batch.
+    pub fn prune_with_cancellation(
+        &self,
+        config: &PruningConfig,
+        token: CancellationToken,
+    ) -> StorageResult<u64> {
+        match config.horizon(self.latest_height()?) {
+            Some(horizon) => self.prune_below_throttled(
+                horizon,
+                &config.columns,
+                &mut Throttle::new(config.budget).with_cancellation(token),
+            ),
+            None => Ok(0),
+        }
+    }
+
+    /// Removes entries of the `columns` below the `height` and records the `height`
+    /// as the pruning horizon. Returns the number of removed entries.
+    ///
+    /// Entries are removed in batches of the [`PRUNING_BATCH_SIZE`], so the pruning doesn't
+    /// hold the storage for long, and writes of new blocks above the horizon go on
+    /// concurrently. Each batch records the progress, so the interrupted pruning resumes
+    /// from the last removed key. The pruning below the current horizon does nothing.
+    pub fn prune_below(
+        &self,
+        height: BlockHeight,
+        columns: &[Column],
+    ) -> StorageResult<u64> {
+        self.prune_below_throttled(
+            height,
+            columns,
+            &mut Throttle::new(IterBudget::default()),
+        )
+    }
+
+    /// Removes entries like the [`Database::prune_below`], but iterates over
+    /// the `columns` within the budget of the `throttle`.
+    pub fn prune_below_throttled(
+        &self,
+        height: BlockHeight,
+        columns: &[Column],
+        throttle: &mut Throttle,
+    ) -> StorageResult<u64> {
+        if let Some(column) = columns
+            .iter()
+            .find(|column| OnChain::key_size(column) != Some(BlockHeightKey::LEN))
+        {
+            return Err(anyhow::anyhow!(
+                "The column `{}` is not keyed by the height",
+                column.name()
+            )
+            .into())
+        }
+
+        let _guard = PRUNING_LOCK.lock().expect("poisoned");
+        let storage = self.data.as_ref();
+        let progress = match self.pruning_progress()? {
+            Some(progress) => PruningProgress {
+                horizon: progress.horizon.max(*height),
+                ..progress
+            },
+            None if self
+                .pruning_horizon()?
+                .is_some_and(|horizon| horizon >= height) =>
+            {
+                return Ok(0)
+            }
+            None => PruningProgress {
+                horizon: *height,
+                last_pruned: None,
+            },
+        };
+
+        // The horizon is recorded before the first removal,
+        // so the data is reported as pruned while it is being removed.
+        let horizon = BlockHeightKey::from(progress.horizon);
+        storage.batch_write(
+            &mut [
+                metadata_write::<PruningHorizon, OnChain>(Some(&progress.horizon.into())),
+                progress.entry(),
+            ]
+            .into_iter(),
+        )?;
+
+        let mut removed = 0u64;
+        for column in columns {
+            let mut start = match &progress.last_pruned {
+                Some((id, key)) if *id == column.id() => Some(key.clone()),
+                _ => None,
+            };
+            loop {
+                let config = IterConfig {
+                    start: start.as_deref().map_or(Bound::Unbounded, Bound::Included),
+                    options: IterOptions::maintenance(),
+                    ..Default::default()
+                };
+                let keys = storage
+                    .iter(*column, config)
+                    .map(|item| item.map(|(key, _)| key));
+                let keys = ThrottledIter::with_throttle(keys, &mut *throttle)
+                    .take_while(|key| {
+                        !matches!(key, Ok(key) if key.as_slice() >= horizon.as_ref())
+                    })
+                    .take(PRUNING_BATCH_SIZE)
+                    .collect::<StorageResult<Vec<_>>>()?;
+                let Some(last) = keys.last().cloned() else {
+                    break
+                };
+                let is_last_batch = keys.len() < PRUNING_BATCH_SIZE;
+                removed = removed.saturating_add(keys.len() as u64);
+
+                let progress = PruningProgress {
+                    horizon: progress.horizon,
+                    last_pruned: Some((column.id(), last.clone())),
+                };
+                storage.batch_write(
+                    &mut keys
+                        .into_iter()
+                        .map(|key| (key, *column, WriteOperation::Remove))
+                        .chain([progress.entry()]),
+                )?;
+                if is_last_batch {
+                    break
+                }
+                // The last key is removed, so the next batch starts after it.
+                start = Some(last);
+            }
+        }
+
+        remove_metadata::<metadata::PruningProgress, OnChain>(storage)?;
+        Ok(removed)
+    }
+
+    /// Returns the earliest height that is not pruned, or `None` if nothing is pruned.
+    pub fn pruning_horizon(&self) -> StorageResult<Option<BlockHeight>> {
+        get_metadata::<PruningHorizon, OnChain>(self.data.as_ref())
+    }
+
+    /// Returns the [`DatabaseError::Pruned`] if the data at the `height` is pruned.
+    pub fn ensure_not_pruned(&self, height: &BlockHeight) -> StorageResult<()> {
+        match self.pruning_horizon()? {
+            Some(horizon) if horizon > *height => Err(DatabaseError::Pruned {
+                height: **height,
+                horizon: *horizon,
+            }
+            .into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn pruning_progress(&self) -> StorageResult<Option<PruningProgress>> {
+        get_metadata::<metadata::PruningProgress, OnChain>(self.data.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::faulty::FaultyStorage;
+    use fuel_core_storage::{
+        iter::IterDirection,
+        kv_store::Value,
+        Error as StorageError,
+    };
+    use std::sync::Arc;
+
+    const COLUMNS: [Column; 2] = [Column::FuelBlockConsensus, Column::UndoLog];
+
+    fn value(height: u32) -> Value {
+        Arc::new(height.to_be_bytes().to_vec())
+    }
+
+    fn fill(database: &Database, heights: std::ops::Range<u32>) {
+        let storage = database.data.as_ref();
+        storage
+            .batch_write(&mut heights.flat_map(|height| {
+                COLUMNS.map(|column| {
+                    (
+                        BlockHeightKey::from(height).as_ref().to_vec(),
+                        column,
+                        WriteOperation::Insert(value(height)),
+                    )
+                })
+            }))
+            .unwrap();
+    }
+
+    fn heights(database: &Database, column: Column) -> Vec<u32> {
+        database
+            .data
+            .as_ref()
+            .iter_keys(column, None, None, IterDirection::Forward)
+            .map(|key| {
+                u32::from(BlockHeightKey::try_from(key.unwrap().as_slice()).unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn prune_below_removes_old_entries_and_records_the_horizon() {
+        // Given
+        let database = Database::default();
+        fill(&database, 0..50);
+        database
+            .data
+            .as_ref()
+            .put(&[1], Column::Metadata, value(1))
+            .unwrap();
+
+        // When
+        let removed = database.prune_below(30.into(), &COLUMNS).unwrap();
+
+        // Then
+        assert_eq!(removed, 60);
+        for column in COLUMNS {
+            assert_eq!(heights(&database, column), (30..50).collect::<Vec<_>>());
+        }
+        assert_eq!(database.pruning_horizon().unwrap(), Some(30.into()));
+        assert_eq!(database.pruning_progress().unwrap(), None);
+        assert_eq!(
+            database.data.as_ref().get(&[1], Column::Metadata).unwrap(),
+            Some(value(1))
+        );
+    }
+
+    #[test]
+    fn second_prune_below_the_horizon_does_nothing() {
+        // Given
+        let faulty = Arc::new(FaultyStorage::<OnChain>::new(Arc::new(
+            crate::state::in_memory::memory_store::MemoryStore::<OnChain>::default(),
+        )));
+        let database = Database::new(faulty.clone());
+        fill(&database, 0..50);
+        database.prune_below(30.into(), &COLUMNS).unwrap();
+        fill(&database, 50..60);
+        faulty.fail_batch_write();
+        faulty.fail_on(Column::UndoLog, crate::state::faulty::Op::Iter);
+        let operations = faulty.operations();
+
+        // When
+        let removed = database.prune_below(20.into(), &COLUMNS).unwrap();
+        let removed_again = database.prune_below(30.into(), &COLUMNS).unwrap();
+
+        // Then
+        assert_eq!((removed, removed_again), (0, 0));
+        // Only the progress and the horizon are read by each call.
+        assert_eq!(faulty.operations().saturating_sub(operations), 4);
+        assert_eq!(database.pruning_horizon().unwrap(), Some(30.into()));
+    }
+
+    #[test]
+    fn interrupted_pruning_resumes_from_the_last_removed_key() {
+        // Given
+        let entries = u32::try_from(PRUNING_BATCH_SIZE).unwrap().saturating_mul(2);
+        let faulty = Arc::new(FaultyStorage::<OnChain>::new(Arc::new(
+            crate::state::in_memory::memory_store::MemoryStore::<OnChain>::default(),
+        )));
+        let database = Database::new(faulty.clone());
+        fill(&database, 0..entries);
+        // Reads of the progress and the horizon, the write of the horizon,
+        // the iteration and the removal of the first batch succeed.
+        faulty.fail_after(5);
+        assert!(database.prune_below(entries.into(), &COLUMNS[..1]).is_err());
+        faulty.reset();
+        let progress = database.pruning_progress().unwrap().unwrap();
+        let last_removed = u32::try_from(PRUNING_BATCH_SIZE).unwrap().saturating_sub(1);
+        assert_eq!(
+            progress.last_pruned,
+            Some((
+                Column::FuelBlockConsensus.id(),
+                BlockHeightKey::from(last_removed).as_ref().to_vec()
+            ))
+        );
+        // The pruning is already reported while it is in progress.
+        assert!(database
+            .ensure_not_pruned(&entries.saturating_sub(1).into())
+            .is_err());
+
+        // When
+        let removed = database.prune_below(entries.into(), &COLUMNS[..1]).unwrap();
+
+        // Then
+        assert_eq!(removed, PRUNING_BATCH_SIZE as u64);
+        assert!(heights(&database, Column::FuelBlockConsensus).is_empty());
+        assert_eq!(database.pruning_progress().unwrap(), None);
+    }
+
+    #[test]
+    fn cancelled_pruning_keeps_the_progress_and_resumes() {
+        // Given
+        let database = Database::default();
+        fill(&database, 0..10);
+        let token = CancellationToken::new();
+        token.cancel();
+        let mut throttle = Throttle::new(IterBudget::default()).with_cancellation(token);
+
+        // When
+        let result = database.prune_below_throttled(5.into(), &COLUMNS, &mut throttle);
+
+        // Then
+        assert_eq!(
+            format!("{:?}", result.unwrap_err()),
+            format!("{:?}", StorageError::from(DatabaseError::Cancelled))
+        );
+        assert_eq!(heights(&database, COLUMNS[0]), (0..10).collect::<Vec<_>>());
+        assert!(database.pruning_progress().unwrap().is_some());
+        assert_eq!(database.prune_below(5.into(), &COLUMNS).unwrap(), 10);
+        assert_eq!(heights(&database, COLUMNS[0]), (5..10).collect::<Vec<_>>());
+        assert_eq!(database.pruning_progress().unwrap(), None);
+    }
+
+    #[test]
+    fn budgeted_pruning_takes_at_least_the_floor_time() {
+        // Given
+        let database = Database::default();
+        fill(&database, 0..200);
+        let mut throttle = Throttle::new(IterBudget {
+            items_per_sec: Some(1_000),
+            ..Default::default()
+        });
+        let started = std::time::Instant::now();
+
+        // When
+        let removed = database
+            .prune_below_throttled(100.into(), &COLUMNS, &mut throttle)
+            .unwrap();
+
+        // Then
+        assert_eq!(removed, 200);
+        // Each column yields 100 removed keys and the first key above the horizon.
+        let floor = std::time::Duration::from_secs_f64(201.0 / 1_000.0);
+        assert!(started.elapsed() >= floor, "{:?}", started.elapsed());
+    }
+
+    #[test]
+    fn queries_of_pruned_heights_fail_with_the_pruned_error() {
+        // Given
+        let database = Database::default();
+        fill(&database, 0..10);
+        database.prune_below(5.into(), &COLUMNS).unwrap();
+
+        // When
+        let pruned = database.get_sealed_block_header(&3.into());
+        let missing = database.get_sealed_block_header(&20.into());
+
+        // Then
+        let expected = StorageError::from(DatabaseError::Pruned {
+            height: 3,
+            horizon: 5,
+        });
+        assert_eq!(
+            format!("{:?}", pruned.unwrap_err()),
+            format!("{expected:?}")
+        );
+        assert!(missing.unwrap().is_none());
+    }
+
+    #[test]
+    fn prune_below_rejects_columns_not_keyed_by_the_height() {
+        let database = Database::default();
+
+        let result = database.prune_below(5.into(), &[Column::Coins]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn horizon_keeps_the_latest_blocks() {
+        let config = |keep_blocks| PruningConfig {
+            keep_blocks,
+            ..Default::default()
+        };
+
+        assert_eq!(config(None).horizon(100.into()), None);
+        assert_eq!(config(Some(10)).horizon(100.into()), Some(91.into()));
+        assert_eq!(config(Some(0)).horizon(100.into()), Some(100.into()));
+        assert_eq!(config(Some(101)).horizon(100.into()), None);
+        assert_eq!(
+            config(Some(1)).horizon(u32::MAX.into()),
+            Some(u32::MAX.into())
+        );
+    }
+}
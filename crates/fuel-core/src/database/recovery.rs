@@ -0,0 +1,443 @@
+//! The journal of multi-batch operations, so the node can finish them after a crash.
+//!
+//! Before the first batch, the operation registers its intent in the metadata column:
+//! the type of the operation, its parameters, and the cursor of its progress. Each batch
+//! of the operation advances the cursor in the same batch write as its data, and the last
+//! batch removes the intent. The journal uses only the [`KeyValueStore`], so it works
+//! with any backend.
+//!
+//! The intents left by the interrupted operations are reported on the open of the database
+//! as [`PendingRecovery`]s. The [`RecoveryHandlers`] resumes each of them with the handler
+//! registered for its type of the operation.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        metadata::MetadataEntry,
+        Database,
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::{
+        in_memory::transaction::WriteEntry,
+        DataSource,
+    },
+};
+use fuel_core_storage::{
+    codec::{
+        postcard::Postcard,
+        Decode,
+        Encode,
+    },
+    iter::{
+        IterDirection,
+        IteratorableStore,
+    },
+    kv_store::{
+        KeyValueStore,
+        WriteOperation,
+    },
+    Error as StorageError,
+    Result as StorageResult,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+};
+
+/// The prefix of the keys in the metadata column that store intents of operations.
+const JOURNAL_PREFIX: &[u8] = MetadataEntry::RecoveryJournal.key();
+
+/// The intent of the multi-batch operation, stored in the journal until the operation
+/// is completed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingRecovery {
+    /// The type of the operation. Only one operation of each type can be in progress.
+    pub operation: String,
+    /// The parameters of the operation, encoded by the operation.
+    pub parameters: Vec<u8>,
+    /// The progress of the operation, encoded by the operation,
+    /// or `None` if no batch is written yet.
+    pub cursor: Option<Vec<u8>>,
+}
+
+impl PendingRecovery {
+    /// Creates the intent of the `operation` with `parameters` and without the progress.
+    pub fn new(operation: impl Into<String>, parameters: Vec<u8>) -> Self {
+        Self {
+            operation: operation.into(),
+            parameters,
+            cursor: None,
+        }
+    }
+
+    fn key(&self) -> Vec<u8> {
+        [JOURNAL_PREFIX, self.operation.as_bytes()].concat()
+    }
+
+    /// Returns the write of the intent into the journal.
+    pub fn write<Description>(&self) -> WriteEntry<Description::Column>
+    where
+        Description: DatabaseDescription,
+    {
+        (
+            self.key(),
+            Description::metadata_column(),
+            WriteOperation::Insert(Postcard::encode_as_value(self)),
+        )
+    }
+
+    /// Moves the cursor to the `cursor` and returns the write of the intent.
+    /// The write must be the part of the batch that makes the progress,
+    /// so the journal never runs ahead or behind the data.
+    pub fn advance<Description>(
+        &mut self,
+        cursor: Vec<u8>,
+    ) -> WriteEntry<Description::Column>
+    where
+        Description: DatabaseDescription,
+    {
+        self.cursor = Some(cursor);
+        self.write::<Description>()
+    }
+
+    /// Returns the removal of the intent from the journal, for the last batch of the operation.
+    pub fn completion<Description>(&self) -> WriteEntry<Description::Column>
+    where
+        Description: DatabaseDescription,
+    {
+        (
+            self.key(),
+            Description::metadata_column(),
+            WriteOperation::Remove,
+        )
+    }
+}
+
+/// Registers the `intent` in the journal before the first batch of the operation.
+/// Fails with the [`DatabaseError::RecoveryPending`] if the operation of the same type
+/// is already in progress or is interrupted.
+pub fn register_intent<Description>(
+    store: &(impl KeyValueStore<Column = Description::Column> + ?Sized),
+    intent: &PendingRecovery,
+) -> StorageResult<()>
+where
+    Description: DatabaseDescription,
+{
+    store
+        .compare_and_swap(
+            &intent.key(),
+            Description::metadata_column(),
+            None,
+            Postcard::encode_as_value(intent),
+        )?
+        .map_err(|_| DatabaseError::RecoveryPending(intent.operation.clone()).into())
+}
+
+/// Removes the intent of the `operation` from the journal.
+pub fn complete_intent<Description>(
+    store: &(impl KeyValueStore<Column = Description::Column> + ?Sized),
+    operation: &str,
+) -> StorageResult<()>
+where
+    Description: DatabaseDescription,
+{
+    store.delete(
+        &[JOURNAL_PREFIX, operation.as_bytes()].concat(),
+        Description::metadata_column(),
+    )
+}
+
+/// Returns intents of all operations in the journal.
+pub fn pending_recoveries<Description>(
+    store: &(impl IteratorableStore<Column = Description::Column> + ?Sized),
+) -> StorageResult<Vec<PendingRecovery>>
+where
+    Description: DatabaseDescription,
+{
+    store
+        .iter_all(
+            Description::metadata_column(),
+            Some(JOURNAL_PREFIX),
+            None,
+            IterDirection::Forward,
+        )
+        .map(|entry| {
+            let (_, value) = entry?;
+            Postcard::decode(value.as_slice()).map_err(StorageError::Other)
+        })
+        .collect()
+}
+
+/// The handler that drives the interrupted operation to the completion from its intent.
+/// The intent is removed from the journal after the handler succeeds.
+pub type ResumeHandler<Description> = Box<
+    dyn Fn(&Database<Description>, &PendingRecovery) -> StorageResult<()> + Send + Sync,
+>;
+
+/// The [`ResumeHandler`]s of types of operations.
+pub struct RecoveryHandlers<Description>
+where
+    Description: DatabaseDescription,
+{
+    handlers: HashMap<String, ResumeHandler<Description>>,
+}
+
+impl<Description> Default for RecoveryHandlers<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn default() -> Self {
+        Self {
+            handlers: HashMap::new(),
+        }
+    }
+}
+
+impl<Description> Debug for RecoveryHandlers<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecoveryHandlers")
+            .field("operations", &self.handlers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<Description> RecoveryHandlers<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Registers the `handler` of the `operation`, replacing the previous one.
+    pub fn with<F>(mut self, operation: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(&Database<Description>, &PendingRecovery) -> StorageResult<()>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.handlers.insert(operation.into(), Box::new(handler));
+        self
+    }
+
+    /// Resumes all interrupted operations of the `database` that have handlers,
+    /// and returns intents of operations without handlers. They stay in the journal.
+    pub fn recover(
+        &self,
+        database: &Database<Description>,
+    ) -> StorageResult<Vec<PendingRecovery>> {
+        let mut unhandled = vec![];
+        for pending in database.pending_recoveries()? {
+            let Some(handler) = self.handlers.get(&pending.operation) else {
+                unhandled.push(pending);
+                continue
+            };
+            tracing::info!(
+                "Resuming the interrupted `{}` operation on the `{}` database",
+                pending.operation,
+                Description::name()
+            );
+            handler(database, &pending)?;
+            complete_intent::<Description>(database.data.as_ref(), &pending.operation)?;
+        }
+        Ok(unhandled)
+    }
+}
+
+impl<Description> Database<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Returns intents of interrupted multi-batch operations, see [`RecoveryHandlers`].
+    pub fn pending_recoveries(&self) -> DatabaseResult<Vec<PendingRecovery>> {
+        let data: &DataSource<Description> = self.data.as_ref();
+        pending_recoveries::<Description>(data.as_ref()).map_err(DatabaseError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::on_chain::OnChain,
+        state::{
+            faulty::FaultyStorage,
+            in_memory::memory_store::MemoryStore,
+        },
+    };
+    use fuel_core_storage::{
+        column::Column,
+        kv_store::BatchOperations,
+    };
+    use std::sync::Arc;
+
+    const COPY_COINS: &str = "copy_coins";
+    const BATCH_SIZE: usize = 3;
+
+    /// Copies coins into messages in batches, tracking the progress in the journal.
+    fn copy_coins(
+        database: &Database<OnChain>,
+        intent: &PendingRecovery,
+    ) -> StorageResult<()> {
+        let mut intent = intent.clone();
+        let store = database.data_source();
+        let start = intent.cursor.clone();
+        let mut entries = store
+            .iter_all(
+                Column::Coins,
+                None,
+                start.as_deref(),
+                IterDirection::Forward,
+            )
+            .filter(
+                |entry| !matches!(entry, Ok((key, _)) if Some(key) == start.as_ref()),
+            );
+        loop {
+            let batch = entries
+                .by_ref()
+                .take(BATCH_SIZE)
+                .collect::<StorageResult<Vec<_>>>()?;
+            let Some((last, _)) = batch.last() else {
+                return store
+                    .batch_write(&mut [intent.completion::<OnChain>()].into_iter())
+            };
+            let progress = intent.advance::<OnChain>(last.clone());
+            let mut writes = batch
+                .into_iter()
+                .map(|(key, value)| {
+                    (key, Column::Messages, WriteOperation::Insert(value))
+                })
+                .chain([progress]);
+            store.batch_write(&mut writes)?;
+        }
+    }
+
+    fn start_copy_coins(database: &Database<OnChain>) -> StorageResult<()> {
+        let intent = PendingRecovery::new(COPY_COINS, vec![]);
+        register_intent::<OnChain>(database.data_source(), &intent)?;
+        copy_coins(database, &intent)
+    }
+
+    fn column(database: &Database<OnChain>, column: Column) -> Vec<Vec<u8>> {
+        database
+            .data_source()
+            .iter_all(column, None, None, IterDirection::Forward)
+            .map(|entry| entry.unwrap().0)
+            .collect()
+    }
+
+    fn interrupted_operation_is_completed_after_restart(store: DataSource<OnChain>) {
+        // Given
+        for key in 0..10u8 {
+            store
+                .put(&[key], Column::Coins, Arc::new(vec![key]))
+                .unwrap();
+        }
+        let faulty = Arc::new(FaultyStorage::new(store.clone()));
+        faulty.fail_iter_after(BATCH_SIZE + 1);
+        let crashed = Database::<OnChain>::new(faulty.clone());
+        let result = start_copy_coins(&crashed);
+        assert!(result.is_err());
+
+        // When
+        let restarted = Database::<OnChain>::new(store);
+        let pending = restarted.pending_recoveries().unwrap();
+        let unhandled = RecoveryHandlers::default()
+            .with(COPY_COINS, copy_coins)
+            .recover(&restarted)
+            .unwrap();
+
+        // Then
+        assert_eq!(
+            pending,
+            vec![PendingRecovery {
+                operation: COPY_COINS.to_string(),
+                parameters: vec![],
+                cursor: Some(vec![2]),
+            }]
+        );
+        assert!(unhandled.is_empty());
+        assert_eq!(
+            column(&restarted, Column::Messages),
+            column(&restarted, Column::Coins)
+        );
+        assert_eq!(restarted.pending_recoveries().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn interrupted_operation_is_completed_after_restart_on_in_memory() {
+        interrupted_operation_is_completed_after_restart(
+            Arc::new(MemoryStore::<OnChain>::default()).into(),
+        );
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn interrupted_operation_is_completed_after_restart_on_rocksdb() {
+        let database =
+            crate::database::temp_database::TempDatabase::<OnChain>::rocksdb().unwrap();
+        interrupted_operation_is_completed_after_restart(database.data_source().clone());
+    }
+
+    #[cfg(feature = "redb")]
+    #[test]
+    fn interrupted_operation_is_completed_after_restart_on_redb() {
+        let database =
+            crate::database::temp_database::TempDatabase::<OnChain>::redb().unwrap();
+        interrupted_operation_is_completed_after_restart(database.data_source().clone());
+    }
+
+    #[test]
+    fn registering_the_operation_in_progress_fails() {
+        // Given
+        let database = Database::<OnChain>::in_memory();
+        let intent = PendingRecovery::new(COPY_COINS, vec![1]);
+        register_intent::<OnChain>(database.data_source(), &intent).unwrap();
+
+        // When
+        let result = register_intent::<OnChain>(
+            database.data_source(),
+            &PendingRecovery::new(COPY_COINS, vec![2]),
+        );
+
+        // Then
+        assert_eq!(
+            format!("{:?}", result.unwrap_err()),
+            format!(
+                "{:?}",
+                StorageError::from(DatabaseError::RecoveryPending(
+                    COPY_COINS.to_string()
+                ))
+            )
+        );
+        assert_eq!(database.pending_recoveries().unwrap(), vec![intent]);
+    }
+
+    #[test]
+    fn operations_without_handlers_stay_in_the_journal() {
+        // Given
+        let database = Database::<OnChain>::in_memory();
+        let unknown = PendingRecovery::new("unknown", vec![]);
+        register_intent::<OnChain>(database.data_source(), &unknown).unwrap();
+        register_intent::<OnChain>(
+            database.data_source(),
+            &PendingRecovery::new(COPY_COINS, vec![]),
+        )
+        .unwrap();
+
+        // When
+        let unhandled = RecoveryHandlers::default()
+            .with(COPY_COINS, copy_coins)
+            .recover(&database)
+            .unwrap();
+
+        // Then
+        assert_eq!(unhandled, vec![unknown.clone()]);
+        assert_eq!(database.pending_recoveries().unwrap(), vec![unknown]);
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{
+    database::{
+        database_description::on_chain::OnChain,
+        storage::UseStructuredImplementation,
+        Database,
+    },
+    state::{
+        in_memory::transaction::MemoryTransactionView,
+        DataSource,
+    },
+};
+use fuel_core_storage::{
+    blueprint::plain::Plain,
+    codec::{
+        height::BlockHeightKey,
+        postcard::Postcard,
+        primitive::Primitive,
+    },
+    column::Column,
+    iter::IterDirection,
+    kv_store::{
+        KeyValueStore,
+        StorageColumn,
+        WriteOperation,
+    },
+    structured_storage::{
+        StructuredStorage,
+        TableWithBlueprint,
+    },
+    transactional::Transaction,
+    Error as StorageError,
+    Mappable,
+    Result as StorageResult,
+};
+use fuel_core_types::fuel_types::BlockHeight;
+
+/// The entry of the undo log that restores the previous value of the `key` in the `column`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct UndoEntry {
+    /// The id of the column.
+    pub column: u32,
+    /// The key of the entry.
+    pub key: Vec<u8>,
+    /// The value before the block or `None` if the entry didn't exist.
+    pub value: Option<Vec<u8>>,
+}
+
+/// The table stores the undo log of each block by its height. The undo log contains
+/// the previous values of all entries modified by the block, so the block can be rolled back.
+pub struct UndoLog;
+
+impl Mappable for UndoLog {
+    type Key = Self::OwnedKey;
+    type OwnedKey = BlockHeight;
+    type Value = [UndoEntry];
+    type OwnedValue = Vec<UndoEntry>;
+}
+
+impl TableWithBlueprint for UndoLog {
+    type Blueprint = Plain<Primitive<4>, Postcard>;
+    type Column = Column;
+
+    fn column() -> Self::Column {
+        Column::UndoLog
+    }
+}
+
+impl UseStructuredImplementation<UndoLog> for StructuredStorage<DataSource<OnChain>> {}
+
+/// Returns the undo log that reverts the pending changes of the `view`.
+pub(crate) fn undo_log(
+    view: &MemoryTransactionView<OnChain>,
+) -> StorageResult<Vec<UndoEntry>> {
+    Ok(view
+        .undo_writes()?
+        .into_iter()
+        // The undo log itself is removed on rollback.
+        .filter(|(_, column, _)| *column != Column::UndoLog)
+        .map(|(key, column, undo)| UndoEntry {
+            column: column.id(),
+            key,
+            value: match undo {
+                WriteOperation::Insert(value) => Some(value.to_vec()),
+                WriteOperation::Remove => None,
+                WriteOperation::Merge(_) => {
+                    unreachable!("The undo writes restore values without merges")
+                }
+            },
+        })
+        .collect())
+}
+
+impl Database<OnChain> {
+    /// Rolls back all blocks above the `height` committed via
+    /// [`DatabaseTransaction::commit_block`](crate::database::transaction::DatabaseTransaction::commit_block).
+    /// Blocks are reverted in reverse order within one transaction,
+    /// so either all of them are rolled back or the database is untouched.
+    pub fn rollback_to(&self, height: BlockHeight) -> StorageResult<()> {
+        let mut transaction = self.transaction();
+        let blocks = self
+            .iter_all::<UndoLog>(Some(IterDirection::Reverse))
+            .take_while(|block| {
+                !matches!(block, Ok((block_height, _)) if *block_height <= height)
+            });
+
+        for block in blocks {
+            let (block_height, undo_log) = block?;
+            let changes: &DataSource = transaction.data.as_ref();
+            for entry in undo_log {
+                let column = enum_iterator::all::<Column>()
+                    .find(|column| column.id() == entry.column)
+                    .ok_or_else(|| {
+                        StorageError::Other(anyhow::anyhow!(
+                            "The undo log of the block {block_height} has unknown column {}",
+                            entry.column
+                        ))
+                    })?;
+                match entry.value {
+                    Some(value) => changes.put(&entry.key, column, value.into())?,
+                    None => changes.delete(&entry.key, column)?,
+                }
+            }
+            changes
+                .delete(BlockHeightKey::from(block_height).as_ref(), Column::UndoLog)?;
+        }
+
+        transaction.commit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::WriteDurability;
+    use fuel_core_storage::StorageAsMut;
+    use std::sync::Arc;
+
+    /// Commits the block at the `height` that modifies several columns.
+    fn commit_block(database: &Database, height: u32) {
+        let mut transaction = database.transaction();
+        let changes: &DataSource = transaction.data.as_ref();
+        let value = Arc::new(height.to_be_bytes().to_vec());
+        let [a, b] = [height, height % 3].map(|i| u8::try_from(i).unwrap());
+
+        changes.put(&[a], Column::Coins, value.clone()).unwrap();
+        changes
+            .put(&[b], Column::ContractsState, value.clone())
+            .unwrap();
+        changes.put(&[0], Column::Metadata, value).unwrap();
+        if height > 2 {
+            changes
+                .delete(&[a.saturating_sub(2)], Column::Coins)
+                .unwrap();
+        }
+        transaction
+            .commit_block(height.into(), WriteDurability::Buffered)
+            .unwrap();
+    }
+
+    fn all_columns(database: &Database) -> Vec<(Column, Vec<u8>, Vec<u8>)> {
+        let data: &DataSource = database.data.as_ref();
+        enum_iterator::all::<Column>()
+            .flat_map(|column| {
+                data.iter_all(column, None, None, IterDirection::Forward)
+                    .map(move |item| {
+                        let (key, value) = item.unwrap();
+                        (column, key, value.to_vec())
+                    })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rollback_to_restores_state_of_earlier_height() {
+        // Given
+        let database = Database::default();
+        let expected = Database::default();
+        for height in 1..=7 {
+            commit_block(&database, height);
+        }
+        for height in 1..=4 {
+            commit_block(&expected, height);
+        }
+
+        // When
+        database.rollback_to(4.into()).unwrap();
+
+        // Then
+        assert_eq!(all_columns(&database), all_columns(&expected));
+    }
+
+    #[test]
+    fn rollback_to_current_height_is_noop() {
+        // Given
+        let database = Database::default();
+        for height in 1..=3 {
+            commit_block(&database, height);
+        }
+        let before = all_columns(&database);
+
+        // When
+        database.rollback_to(3.into()).unwrap();
+
+        // Then
+        assert_eq!(all_columns(&database), before);
+    }
+
+    #[test]
+    fn undo_logs_are_iterated_in_the_order_of_heights() {
+        // Given
+        let mut database = Database::default();
+        let heights = [
+            0u32,
+            1,
+            255,
+            256,
+            65_535,
+            65_536,
+            16_777_215,
+            16_777_216,
+            u32::MAX - 1,
+            u32::MAX,
+        ];
+        // The insertion order doesn't match the order of heights.
+        for height in heights.iter().rev() {
+            database
+                .storage_as_mut::<UndoLog>()
+                .insert(&(*height).into(), &[])
+                .unwrap();
+        }
+
+        for direction in [IterDirection::Forward, IterDirection::Reverse] {
+            // When
+            let iterated = database
+                .iter_all::<UndoLog>(Some(direction))
+                .map(|item| u32::from(item.unwrap().0))
+                .collect::<Vec<_>>();
+
+            // Then
+            let mut expected = heights.to_vec();
+            if direction == IterDirection::Reverse {
+                expected.reverse();
+            }
+            assert_eq!(iterated, expected);
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "doesn't have 4 bytes")]
+    fn height_key_of_wrong_size_is_rejected() {
+        let database = Database::default();
+        let data: &DataSource = database.data.as_ref();
+        data.put(&7u64.to_be_bytes(), Column::UndoLog, Arc::new(vec![]))
+            .unwrap();
+    }
+
+    #[test]
+    fn rollback_to_genesis_clears_all_blocks() {
+        // Given
+        let database = Database::default();
+        for height in 1..=3 {
+            commit_block(&database, height);
+        }
+
+        // When
+        database.rollback_to(0.into()).unwrap();
+
+        // Then
+        assert_eq!(all_columns(&database), vec![]);
+    }
+}
@@ -0,0 +1,227 @@
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        metadata::{
+            get_metadata,
+            set_metadata,
+            SchemaVersion,
+        },
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::TransactableStorage,
+};
+use std::{
+    cmp::Ordering,
+    path::Path,
+};
+
+/// Returns the schema version of the data directory or `None` if it is not stored yet.
+pub fn schema_version<Description>(
+    db: &dyn TransactableStorage<Column = Description::Column>,
+) -> DatabaseResult<Option<u32>>
+where
+    Description: DatabaseDescription,
+{
+    get_metadata::<SchemaVersion, Description>(db).map_err(DatabaseError::from)
+}
+
+/// Ensures that the data directory at the `path` has the schema version expected by
+/// [`DatabaseDescription::version`]. The version is stored on the first open, and the older
+/// versions are migrated with [`DatabaseDescription::migrate`].
+pub fn ensure_schema_version<Description>(
+    db: &dyn TransactableStorage<Column = Description::Column>,
+    path: &Path,
+) -> DatabaseResult<()>
+where
+    Description: DatabaseDescription,
+{
+    let expected = Description::version();
+    let Some(found) = schema_version::<Description>(db)? else {
+        return set_schema_version::<Description>(db, expected)
+    };
+
+    match found.cmp(&expected) {
+        Ordering::Equal => Ok(()),
+        Ordering::Less => match Description::migrate(found, expected, db) {
+            Ok(()) => set_schema_version::<Description>(db, expected),
+            Err(DatabaseError::MissingMigration { .. }) => {
+                Err(invalid_version(found, expected, path))
+            }
+            Err(err) => Err(err),
+        },
+        Ordering::Greater => Err(invalid_version(found, expected, path)),
+    }
+}
+
+/// Checks the schema version of the data directory at the `path` without modifying it.
+/// The data directory without the schema version is accepted.
+pub fn check_schema_version<Description>(
+    db: &dyn TransactableStorage<Column = Description::Column>,
+    path: &Path,
+) -> DatabaseResult<()>
+where
+    Description: DatabaseDescription,
+{
+    let expected = Description::version();
+    match schema_version::<Description>(db)? {
+        Some(found) if found != expected => Err(invalid_version(found, expected, path)),
+        _ => Ok(()),
+    }
+}
+
+/// Stores the schema `version` of the data directory.
+pub(crate) fn set_schema_version<Description>(
+    db: &dyn TransactableStorage<Column = Description::Column>,
+    version: u32,
+) -> DatabaseResult<()>
+where
+    Description: DatabaseDescription,
+{
+    set_metadata::<SchemaVersion, Description>(db, &version).map_err(DatabaseError::from)
+}
+
+fn invalid_version(found: u32, expected: u32, path: &Path) -> DatabaseError {
+    DatabaseError::InvalidSchemaVersion {
+        found,
+        expected,
+        path: path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{
+            database_description::relayer::DummyColumn,
+            metadata::MetadataEntry,
+        },
+        state::in_memory::memory_store::MemoryStore,
+    };
+    use fuel_core_storage::kv_store::KeyValueStore;
+    use std::sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering as AtomicOrdering,
+        },
+        Arc,
+    };
+
+    static MIGRATIONS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Clone, Debug)]
+    struct TestDatabase;
+
+    impl DatabaseDescription for TestDatabase {
+        type Column = DummyColumn;
+        type Height = u32;
+
+        fn version() -> u32 {
+            2
+        }
+
+        fn name() -> &'static str {
+            "test"
+        }
+
+        fn metadata_column() -> Self::Column {
+            DummyColumn::Metadata
+        }
+
+        fn prefix(_: &Self::Column) -> Option<usize> {
+            None
+        }
+
+        fn migrate(
+            from: u32,
+            to: u32,
+            _: &dyn TransactableStorage<Column = Self::Column>,
+        ) -> DatabaseResult<()> {
+            match (from, to) {
+                (1, 2) => {
+                    MIGRATIONS.fetch_add(1, AtomicOrdering::SeqCst);
+                    Ok(())
+                }
+                _ => Err(DatabaseError::MissingMigration { from, to }),
+            }
+        }
+    }
+
+    fn tamper(db: &MemoryStore<TestDatabase>, version: u32) {
+        db.put(
+            MetadataEntry::SchemaVersion.key(),
+            DummyColumn::Metadata,
+            Arc::new(version.to_be_bytes().to_vec()),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn first_open_stores_schema_version() {
+        // Given
+        let db = MemoryStore::<TestDatabase>::default();
+
+        // When
+        ensure_schema_version::<TestDatabase>(&db, Path::new("db")).unwrap();
+
+        // Then
+        assert_eq!(schema_version::<TestDatabase>(&db).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn reopen_with_same_schema_version_passes() {
+        // Given
+        let db = MemoryStore::<TestDatabase>::default();
+        ensure_schema_version::<TestDatabase>(&db, Path::new("db")).unwrap();
+
+        // When
+        let result = ensure_schema_version::<TestDatabase>(&db, Path::new("db"));
+
+        // Then
+        assert!(result.is_ok());
+        assert!(check_schema_version::<TestDatabase>(&db, Path::new("db")).is_ok());
+    }
+
+    #[test]
+    fn open_with_tampered_schema_version_fails() {
+        for version in [0, 3] {
+            // Given
+            let db = MemoryStore::<TestDatabase>::default();
+            tamper(&db, version);
+
+            // When
+            let result = ensure_schema_version::<TestDatabase>(&db, Path::new("db"));
+
+            // Then
+            let err = result.expect_err("The mismatched version should be refused");
+            assert!(matches!(
+                err,
+                DatabaseError::InvalidSchemaVersion { found, expected: 2, ref path }
+                    if found == version && path == Path::new("db")
+            ));
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "The database at db has schema version {version}, but expected 2"
+                )
+            );
+            assert_eq!(schema_version::<TestDatabase>(&db).unwrap(), Some(version));
+        }
+    }
+
+    #[test]
+    fn registered_migration_runs_exactly_once() {
+        // Given
+        let db = MemoryStore::<TestDatabase>::default();
+        tamper(&db, 1);
+
+        // When
+        ensure_schema_version::<TestDatabase>(&db, Path::new("db")).unwrap();
+        ensure_schema_version::<TestDatabase>(&db, Path::new("db")).unwrap();
+
+        // Then
+        assert_eq!(MIGRATIONS.load(AtomicOrdering::SeqCst), 1);
+        assert_eq!(schema_version::<TestDatabase>(&db).unwrap(), Some(2));
+    }
+}
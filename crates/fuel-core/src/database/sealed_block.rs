@@ -43,6 +43,7 @@ impl Database {
 
             Ok(Some(sealed_block))
         } else {
+            self.ensure_not_pruned(height)?;
             Ok(None)
         }
     }
@@ -89,6 +90,7 @@ impl Database {
 
             Ok(Some(sealed_block))
         } else {
+            self.ensure_not_pruned(height)?;
             Ok(None)
         }
     }
@@ -0,0 +1,210 @@
+//! The export of the current state into the [`StateConfig`] format, e.g. for the regenesis
+//! or to start the local network from the real state.
+//!
+//! [`StateConfig`]: fuel_core_chain_config::StateConfig
+
+use crate::database::Database;
+use fuel_core_chain_config::StateConfigStream;
+use fuel_core_storage::{
+    Error as StorageError,
+    Result as StorageResult,
+};
+use fuel_core_types::fuel_types::BlockHeight;
+use std::io::{
+    BufWriter,
+    Write,
+};
+
+impl Database {
+    /// Writes the unspent coins, the contracts with their state and balances, and the unspent
+    /// messages into the `writer` as the JSON of the [`StateConfig`]. Returns the height
+    /// of the exported state, which is also recorded in the export.
+    ///
+    /// The state is read from the [`Database::snapshot`], so blocks committed during the export
+    /// don't tear it. Entries are written one by one, so the export doesn't hold the state
+    /// in memory.
+    ///
+    /// [`StateConfig`]: fuel_core_chain_config::StateConfig
+    pub fn export_state_config(&self, writer: impl Write) -> StorageResult<BlockHeight> {
+        let snapshot = self.snapshot()?;
+        let height = snapshot.latest_height()?;
+        let state = StateConfigStream::new(
+            snapshot.coin_configs(),
+            snapshot.contract_configs(),
+            snapshot.message_configs(),
+            height,
+        );
+
+        let mut writer = BufWriter::new(writer);
+        let result = serde_json::to_writer(&mut writer, &state);
+        if let Some(err) = state.take_error() {
+            return Err(err)
+        }
+        result.map_err(|e| StorageError::Other(e.into()))?;
+        writer.flush().map_err(|e| StorageError::Other(e.into()))?;
+        Ok(height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::{
+        config::Config,
+        FuelService,
+    };
+    use fuel_core_chain_config::{
+        ChainConfig,
+        CoinConfig,
+        ContractConfig,
+        MessageConfig,
+        StateConfig,
+    };
+    use fuel_core_storage::{
+        tables::Coins,
+        StorageAsMut,
+    };
+    use fuel_core_types::{
+        blockchain::primitives::DaBlockHeight,
+        fuel_asm::op,
+        fuel_tx::{
+            Contract,
+            UtxoId,
+        },
+        fuel_types::Salt,
+    };
+    use rand::{
+        rngs::StdRng,
+        Rng,
+        SeedableRng,
+    };
+
+    fn state_config(rng: &mut StdRng) -> StateConfig {
+        let coins = (0..5u32)
+            .map(|i| CoinConfig {
+                tx_id: Some(rng.gen()),
+                output_index: Some(rng.gen()),
+                tx_pointer_block_height: Some(i.into()),
+                tx_pointer_tx_idx: Some(rng.gen()),
+                maturity: Some(rng.gen::<u32>().into()),
+                owner: rng.gen(),
+                amount: rng.gen(),
+                asset_id: rng.gen(),
+            })
+            .collect();
+        let contracts = (0..3u8)
+            .map(|i| {
+                let salt: Salt = rng.gen();
+                let contract = Contract::from(op::ret(i).to_bytes().to_vec());
+                let root = contract.root();
+                let mut state =
+                    (0..4).map(|_| (rng.gen(), rng.gen())).collect::<Vec<_>>();
+                state.sort();
+                let mut balances =
+                    (0..4).map(|_| (rng.gen(), rng.gen())).collect::<Vec<_>>();
+                balances.sort();
+                ContractConfig {
+                    contract_id: contract.id(
+                        &salt,
+                        &root,
+                        &Contract::default_state_root(),
+                    ),
+                    code: contract.into(),
+                    salt,
+                    state: Some(state),
+                    balances: Some(balances),
+                    tx_id: Some(rng.gen()),
+                    output_index: Some(rng.gen()),
+                    tx_pointer_block_height: Some(u32::from(i).into()),
+                    tx_pointer_tx_idx: Some(rng.gen()),
+                }
+            })
+            .collect();
+        let messages = (0..3u8)
+            .map(|i| MessageConfig {
+                sender: rng.gen(),
+                recipient: rng.gen(),
+                nonce: rng.gen(),
+                amount: rng.gen(),
+                data: vec![i; usize::from(i)],
+                da_height: DaBlockHeight(u64::from(i)),
+            })
+            .collect();
+        StateConfig {
+            coins: Some(coins),
+            contracts: Some(contracts),
+            messages: Some(messages),
+            height: Some(10u32.into()),
+        }
+    }
+
+    /// Sorts entries of the `state`, because the export orders them by keys.
+    fn sorted(mut state: StateConfig) -> StateConfig {
+        if let Some(coins) = state.coins.as_mut() {
+            coins.sort_by_key(|coin| (coin.tx_id, coin.output_index));
+        }
+        if let Some(contracts) = state.contracts.as_mut() {
+            contracts.sort_by_key(|contract| contract.contract_id);
+        }
+        if let Some(messages) = state.messages.as_mut() {
+            messages.sort_by_key(|message| message.nonce);
+        }
+        state
+    }
+
+    async fn initialized(state: &StateConfig) -> Database {
+        let config = Config {
+            chain_conf: ChainConfig {
+                initial_state: Some(state.clone()),
+                ..ChainConfig::local_testnet()
+            },
+            ..Config::local_node()
+        };
+        let database = Database::default();
+        FuelService::from_database(database.clone(), config)
+            .await
+            .unwrap();
+        database
+    }
+
+    #[tokio::test]
+    async fn export_equals_the_initial_state() {
+        // Given
+        let state = state_config(&mut StdRng::seed_from_u64(7));
+        let database = initialized(&state).await;
+
+        // When
+        let mut json = vec![];
+        let height = database.export_state_config(&mut json).unwrap();
+
+        // Then
+        let exported: StateConfig = serde_json::from_slice(&json).unwrap();
+        assert_eq!(height, 10u32.into());
+        assert_eq!(sorted(exported), sorted(state));
+    }
+
+    #[tokio::test]
+    async fn export_is_not_affected_by_commits_during_it() {
+        // Given
+        let state = state_config(&mut StdRng::seed_from_u64(7));
+        let mut database = initialized(&state).await;
+        let snapshot = database.snapshot().unwrap();
+
+        // When
+        let coin = database.coin_configs().next().unwrap().unwrap();
+        database
+            .storage_as_mut::<Coins>()
+            .remove(&UtxoId::new(
+                coin.tx_id.unwrap(),
+                coin.output_index.unwrap(),
+            ))
+            .unwrap();
+
+        // Then
+        let mut json = vec![];
+        snapshot.export_state_config(&mut json).unwrap();
+        let exported: StateConfig = serde_json::from_slice(&json).unwrap();
+        assert_eq!(sorted(exported), sorted(state));
+        assert_eq!(database.coin_configs().count(), 4);
+    }
+}
@@ -45,6 +45,7 @@ use fuel_core_storage::{
     StorageAsMut,
     StorageAsRef,
     StorageBatchMutate,
+    StorageBlindMutate,
     StorageInspect,
     StorageMutate,
     StorageRead,
@@ -143,6 +144,22 @@ where
     }
 }
 
+impl<Description, M> StorageBlindMutate<M> for Database<Description>
+where
+    Description: DatabaseDescription,
+    M: Mappable,
+    StructuredStorage<DataSource<Description>>:
+        StorageBlindMutate<M, Error = StorageError> + UseStructuredImplementation<M>,
+{
+    fn put(&mut self, key: &M::Key, value: &M::Value) -> StorageResult<()> {
+        StorageBlindMutate::<M>::put(&mut self.data, key, value)
+    }
+
+    fn delete(&mut self, key: &M::Key) -> StorageResult<()> {
+        StorageBlindMutate::<M>::delete(&mut self.data, key)
+    }
+}
+
 impl<Description, Key, M> MerkleRootStorage<Key, M> for Database<Description>
 where
     Description: DatabaseDescription,
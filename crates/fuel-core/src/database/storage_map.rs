@@ -0,0 +1,275 @@
+//! The module provides the typed access to a single column of the key-value store.
+//! Unlike tables, the map is not bound to the column at compile time, so it can be
+//! used for entries of the shared columns, like the metadata column.
+
+use crate::database::{
+    Error as DatabaseError,
+    Result as DatabaseResult,
+};
+use fuel_core_storage::{
+    codec::{
+        Decode,
+        Encode,
+        Encoder,
+    },
+    iter::{
+        IterDirection,
+        IteratorableStore,
+    },
+    kv_store::StorageColumn,
+};
+use std::marker::PhantomData;
+
+/// The typed map over the `column` of the `store`. Keys are encoded with the `KeyCodec`,
+/// and values are encoded with the `ValueCodec`.
+pub struct StorageMap<'a, S, K, V, KeyCodec, ValueCodec>
+where
+    S: ?Sized + IteratorableStore,
+{
+    store: &'a S,
+    column: S::Column,
+    _types: PhantomData<fn() -> (K, V)>,
+    _codecs: PhantomData<(KeyCodec, ValueCodec)>,
+}
+
+impl<'a, S, K, V, KeyCodec, ValueCodec> StorageMap<'a, S, K, V, KeyCodec, ValueCodec>
+where
+    S: ?Sized + IteratorableStore,
+    KeyCodec: Encode<K> + Decode<K>,
+    ValueCodec: Encode<V> + Decode<V>,
+{
+    /// Creates the map over the `column` of the `store`.
+    pub fn new(store: &'a S, column: S::Column) -> Self {
+        Self {
+            store,
+            column,
+            _types: PhantomData,
+            _codecs: PhantomData,
+        }
+    }
+
+    /// Returns the value under the `key`, if any.
+    pub fn get(&self, key: &K) -> DatabaseResult<Option<V>> {
+        let key = KeyCodec::encode(key);
+        let key = key.as_bytes();
+        self.store
+            .get(key.as_ref(), self.column)
+            .map_err(DatabaseError::from)?
+            .map(|value| self.decode_value(key.as_ref(), value.as_slice()))
+            .transpose()
+    }
+
+    /// Puts the `value` under the `key`, overwriting the previous value.
+    pub fn insert(&self, key: &K, value: &V) -> DatabaseResult<()> {
+        let key = KeyCodec::encode(key);
+        self.store
+            .put(
+                key.as_bytes().as_ref(),
+                self.column,
+                ValueCodec::encode_as_value(value),
+            )
+            .map_err(DatabaseError::from)?;
+        Ok(())
+    }
+
+    /// Removes the value under the `key`, if any.
+    pub fn remove(&self, key: &K) -> DatabaseResult<()> {
+        let key = KeyCodec::encode(key);
+        self.store
+            .delete(key.as_bytes().as_ref(), self.column)
+            .map_err(DatabaseError::from)?;
+        Ok(())
+    }
+
+    /// Returns `true` if the map contains the value under the `key`.
+    pub fn contains(&self, key: &K) -> DatabaseResult<bool> {
+        let key = KeyCodec::encode(key);
+        self.store
+            .exists(key.as_bytes().as_ref(), self.column)
+            .map_err(DatabaseError::from)
+    }
+
+    /// Iterates over the entries with the key starting with the `prefix` in the `direction`.
+    pub fn iter(
+        &self,
+        prefix: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> impl Iterator<Item = DatabaseResult<(K, V)>> + 'a
+    where
+        K: 'a,
+        V: 'a,
+        KeyCodec: 'a,
+        ValueCodec: 'a,
+    {
+        let column = self.column;
+        let map = Self::new(self.store, column);
+        self.store
+            .iter_all(column, prefix, None, direction)
+            .map(move |entry| {
+                let (key, value) = entry.map_err(DatabaseError::from)?;
+                let decoded_key = KeyCodec::decode(key.as_slice())
+                    .map_err(|error| map.decode_error(key.as_slice(), error))?;
+                let value = map.decode_value(key.as_slice(), value.as_slice())?;
+                Ok((decoded_key, value))
+            })
+    }
+
+    fn decode_value(&self, key: &[u8], value: &[u8]) -> DatabaseResult<V> {
+        ValueCodec::decode(value).map_err(|error| self.decode_error(key, error))
+    }
+
+    fn decode_error(&self, key: &[u8], error: anyhow::Error) -> DatabaseError {
+        DatabaseError::DecodeEntry {
+            column: self.column.name(),
+            key: key.to_vec(),
+            error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::on_chain::OnChain,
+        state::in_memory::{
+            memory_store::MemoryStore,
+            transaction::MemoryTransactionView,
+        },
+    };
+    use fuel_core_storage::{
+        codec::{
+            postcard::Postcard,
+            primitive::Primitive,
+            raw::Raw,
+        },
+        column::Column,
+        kv_store::KeyValueStore,
+    };
+    use rand::{
+        rngs::StdRng,
+        Rng,
+        SeedableRng,
+    };
+    use std::sync::Arc;
+
+    type Balances<'a, S> = StorageMap<'a, S, Vec<u8>, u64, Raw, Primitive<8>>;
+
+    #[test]
+    fn codecs_round_trip_random_entries() {
+        let mut rng = StdRng::seed_from_u64(0xF00D);
+        let store = MemoryStore::<OnChain>::default();
+        let map = StorageMap::<_, u32, (String, Vec<u8>), Primitive<4>, Postcard>::new(
+            &store,
+            Column::Metadata,
+        );
+
+        for _ in 0..1000 {
+            let key = rng.gen::<u32>();
+            let len = rng.gen_range(0..64);
+            let value = (
+                (0..len).map(|_| rng.gen::<char>()).collect::<String>(),
+                (0..len).map(|_| rng.gen::<u8>()).collect::<Vec<_>>(),
+            );
+
+            map.insert(&key, &value).unwrap();
+
+            assert_eq!(map.get(&key).unwrap(), Some(value));
+            assert!(map.contains(&key).unwrap());
+        }
+    }
+
+    #[test]
+    fn remove_deletes_the_value() {
+        // Given
+        let store = MemoryStore::<OnChain>::default();
+        let map = Balances::new(&store, Column::Metadata);
+        map.insert(&vec![1], &10).unwrap();
+
+        // When
+        map.remove(&vec![1]).unwrap();
+
+        // Then
+        assert_eq!(map.get(&vec![1]).unwrap(), None);
+        assert!(!map.contains(&vec![1]).unwrap());
+    }
+
+    #[test]
+    fn iter_decodes_entries_with_prefix() {
+        // Given
+        let store = MemoryStore::<OnChain>::default();
+        let map = Balances::new(&store, Column::Metadata);
+        for (key, value) in [(vec![1, 1], 11), (vec![1, 2], 12), (vec![2, 1], 21)] {
+            map.insert(&key, &value).unwrap();
+        }
+
+        // When
+        let forward = map
+            .iter(Some(&[1]), IterDirection::Forward)
+            .collect::<DatabaseResult<Vec<_>>>()
+            .unwrap();
+        let reverse = map
+            .iter(None, IterDirection::Reverse)
+            .collect::<DatabaseResult<Vec<_>>>()
+            .unwrap();
+
+        // Then
+        assert_eq!(forward, vec![(vec![1, 1], 11), (vec![1, 2], 12)]);
+        assert_eq!(
+            reverse,
+            vec![(vec![2, 1], 21), (vec![1, 2], 12), (vec![1, 1], 11)]
+        );
+    }
+
+    #[test]
+    fn decode_failure_reports_column_and_key() {
+        // Given
+        let store = MemoryStore::<OnChain>::default();
+        store
+            .put(&[7], Column::Metadata, Arc::new(vec![1, 2, 3]))
+            .unwrap();
+        let map = Balances::new(&store, Column::Metadata);
+
+        // When
+        let get = map.get(&vec![7]);
+        let iter = map.iter(None, IterDirection::Forward).next().unwrap();
+
+        // Then
+        for result in [get.map(|_| ()), iter.map(|_| ())] {
+            match result {
+                Err(DatabaseError::DecodeEntry { column, key, .. }) => {
+                    assert_eq!(column, Column::Metadata.name());
+                    assert_eq!(key, vec![7]);
+                }
+                _ => panic!("Expected the decode error, got {result:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn map_over_transaction_view_is_committed_with_it() {
+        // Given
+        let store = Arc::new(MemoryStore::<OnChain>::default());
+        let view = MemoryTransactionView::<OnChain>::new(store.clone());
+        Balances::new(&view, Column::Metadata)
+            .insert(&vec![1], &10)
+            .unwrap();
+        assert_eq!(
+            Balances::new(store.as_ref(), Column::Metadata)
+                .get(&vec![1])
+                .unwrap(),
+            None
+        );
+
+        // When
+        view.commit().unwrap();
+
+        // Then
+        assert_eq!(
+            Balances::new(store.as_ref(), Column::Metadata)
+                .get(&vec![1])
+                .unwrap(),
+            Some(10)
+        );
+    }
+}
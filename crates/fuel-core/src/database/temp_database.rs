@@ -0,0 +1,275 @@
+//! The database in the temporary directory that is removed on drop, see [`TempDatabase`].
+
+use crate::{
+    database::{
+        database_description::{
+            on_chain::OnChain,
+            DatabaseDescription,
+        },
+        Database,
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::TransactableStorage,
+};
+use std::{
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::{
+        Arc,
+        Weak,
+    },
+};
+use tempfile::TempDir;
+
+/// The prefix of names of temporary directories, to find leftovers of killed processes.
+const DIR_PREFIX: &str = "fuel-core-db-";
+
+/// The database opened in the unique temporary directory. The database is closed
+/// and the directory is removed on drop, also when the test panics.
+///
+/// The storage is shared by clones of the [`Database`] and its transactions.
+/// The directory is removed after the drop of the database held by the `TempDatabase`.
+/// If the storage is still used by clones that outlived it, the warning is logged,
+/// because the backend may still write into the removed directory.
+#[derive(Debug)]
+pub struct TempDatabase<Description = OnChain>
+where
+    Description: DatabaseDescription,
+{
+    /// `None` only during the drop of the `TempDatabase`.
+    database: Option<Database<Description>>,
+    /// The storage of the `database`, to detect whether it is closed.
+    storage: Weak<dyn TransactableStorage<Column = Description::Column>>,
+    /// `None` after [`Self::keep`].
+    dir: Option<TempDir>,
+    path: PathBuf,
+}
+
+impl<Description> TempDatabase<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Opens the database by the `open` in the new temporary directory.
+    pub fn open_with<F>(open: F) -> DatabaseResult<Self>
+    where
+        F: FnOnce(&Path) -> DatabaseResult<Database<Description>>,
+    {
+        let dir = tempfile::Builder::new()
+            .prefix(DIR_PREFIX)
+            .tempdir()
+            .map_err(|e| DatabaseError::Other(e.into()))?;
+        let database = open(dir.path())?;
+        Ok(Self {
+            storage: Arc::downgrade(&database.data_source().0),
+            database: Some(database),
+            path: dir.path().to_path_buf(),
+            dir: Some(dir),
+        })
+    }
+
+    /// Opens the RocksDB in the new temporary directory.
+    #[cfg(feature = "rocksdb")]
+    pub fn rocksdb() -> DatabaseResult<Self> {
+        use crate::state::rocks_db::RocksDb;
+        Self::open_with(|path| {
+            let db = RocksDb::<Description>::default_open(path, None)?;
+            Ok(Database::new(Arc::new(db)))
+        })
+    }
+
+    /// Opens the redb in the new temporary directory.
+    #[cfg(feature = "redb")]
+    pub fn redb() -> DatabaseResult<Self> {
+        use crate::state::redb_store::RedbStore;
+        Self::open_with(|path| {
+            let db = RedbStore::<Description>::open(path, None)?;
+            Ok(Database::new(Arc::new(db)))
+        })
+    }
+
+    /// Returns the path of the directory of the database.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Closes the database and opens it again by the `open` in the same directory,
+    /// e.g. to check that the data survives the restart.
+    pub fn reopen_with<F>(&mut self, open: F) -> DatabaseResult<()>
+    where
+        F: FnOnce(&Path) -> DatabaseResult<Database<Description>>,
+    {
+        self.close();
+        let database = open(&self.path)?;
+        self.storage = Arc::downgrade(&database.data_source().0);
+        self.database = Some(database);
+        Ok(())
+    }
+
+    /// Closes the database and keeps its directory, e.g. to inspect the database
+    /// of the failed test. Returns the path of the directory.
+    pub fn keep(mut self) -> PathBuf {
+        self.close();
+        if let Some(dir) = self.dir.take() {
+            // The directory is no longer removed on drop.
+            let _ = dir.into_path();
+        }
+        self.path.clone()
+    }
+
+    fn close(&mut self) {
+        drop(self.database.take());
+        if self.storage.strong_count() > 0 {
+            tracing::warn!(
+                "The temporary `{}` database at {} is still used after the close",
+                Description::name(),
+                self.path.display()
+            );
+        }
+    }
+}
+
+impl<Description> Deref for TempDatabase<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Target = Database<Description>;
+
+    fn deref(&self) -> &Self::Target {
+        self.database
+            .as_ref()
+            .expect("The database is open until the drop")
+    }
+}
+
+impl<Description> DerefMut for TempDatabase<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.database
+            .as_mut()
+            .expect("The database is open until the drop")
+    }
+}
+
+impl<Description> Drop for TempDatabase<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn drop(&mut self) {
+        // The backend must close its files before the removal of the directory.
+        self.close();
+        if let Some(dir) = self.dir.take() {
+            if let Err(err) = dir.close() {
+                tracing::warn!(
+                    "Unable to remove the temporary database at {}: {:?}",
+                    self.path.display(),
+                    err
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fuel_core_storage::{
+        column::Column,
+        kv_store::KeyValueStore,
+    };
+
+    type Open = fn() -> DatabaseResult<TempDatabase>;
+
+    fn backends() -> Vec<Open> {
+        vec![
+            #[cfg(feature = "rocksdb")]
+            TempDatabase::rocksdb,
+            #[cfg(feature = "redb")]
+            TempDatabase::redb,
+        ]
+    }
+
+    fn put_into_columns(database: &TempDatabase) {
+        for column in [Column::Metadata, Column::Coins, Column::Transactions] {
+            database
+                .data_source()
+                .put(&[1], column, Arc::new(vec![1]))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn directory_is_removed_on_drop() {
+        for open in backends() {
+            // Given
+            let database = open().unwrap();
+            put_into_columns(&database);
+            let path = database.path().to_path_buf();
+            assert!(path.exists());
+
+            // When
+            drop(database);
+
+            // Then
+            assert!(!path.exists());
+        }
+    }
+
+    #[test]
+    fn directory_is_removed_when_the_test_panics() {
+        for open in backends() {
+            // Given
+            let mut path = None;
+
+            // When
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let database = open().unwrap();
+                put_into_columns(&database);
+                path = Some(database.path().to_path_buf());
+                panic!("The failed assertion");
+            }));
+
+            // Then
+            assert!(result.is_err());
+            assert!(!path.unwrap().exists());
+        }
+    }
+
+    #[test]
+    fn kept_directory_survives_the_drop() {
+        for open in backends() {
+            // Given
+            let database = open().unwrap();
+            put_into_columns(&database);
+
+            // When
+            let path = database.keep();
+
+            // Then
+            assert!(path.exists());
+            std::fs::remove_dir_all(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn directories_of_databases_are_unique() {
+        for open in backends() {
+            // Given
+            let first = open().unwrap();
+
+            // When
+            let second = open().unwrap();
+
+            // Then
+            assert_ne!(first.path(), second.path());
+        }
+    }
+}
@@ -1,17 +1,30 @@
 use crate::{
     database::{
-        database_description::DatabaseDescription,
+        database_description::{
+            on_chain::OnChain,
+            DatabaseDescription,
+        },
+        rollback::{
+            undo_log,
+            UndoLog,
+        },
         Database,
+        Error as DatabaseError,
+        Result as DatabaseResult,
     },
     state::{
         in_memory::transaction::MemoryTransactionView,
         DataSource,
+        WriteDurability,
     },
 };
 use fuel_core_storage::{
     transactional::Transaction,
     Result as StorageResult,
+    StorageBlindMutate,
 };
+use fuel_core_types::fuel_types::BlockHeight;
+use rand::Rng;
 use std::{
     fmt::Debug,
     ops::{
@@ -19,8 +32,76 @@ use std::{
         DerefMut,
     },
     sync::Arc,
+    time::Duration,
 };
 
+/// The delay before the next attempt of [`Database::transaction_with_retry`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backoff {
+    /// The same delay before each retry.
+    Fixed(Duration),
+    /// The delay doubles with each retry, starting from `initial` and capped by `max`.
+    /// The `jitter` picks the random delay up to it, so concurrent writers
+    /// that conflicted with each other don't retry at the same time.
+    Exponential {
+        /// The delay before the first retry.
+        initial: Duration,
+        /// The upper bound of the delay.
+        max: Duration,
+        /// Whether the delay is randomized.
+        jitter: bool,
+    },
+}
+
+impl Backoff {
+    /// Returns the delay before the `retry`, starting from 1.
+    pub fn delay(&self, retry: u32) -> Duration {
+        match *self {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential {
+                initial,
+                max,
+                jitter,
+            } => {
+                let factor = 1u32
+                    .checked_shl(retry.saturating_sub(1))
+                    .unwrap_or(u32::MAX);
+                let delay = initial.saturating_mul(factor).min(max);
+                match jitter {
+                    true => rand::thread_rng().gen_range(Duration::ZERO..=delay),
+                    false => delay,
+                }
+            }
+        }
+    }
+}
+
+/// The policy of retries of [`Database::transaction_with_retry`].
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one.
+    pub max_attempts: usize,
+    /// The delay between attempts.
+    pub backoff: Backoff,
+    /// Returns `true` if the attempt failed with the error may succeed if repeated.
+    /// Other errors are returned without retries.
+    pub is_retryable: fn(&DatabaseError) -> bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            backoff: Backoff::Exponential {
+                initial: Duration::from_millis(1),
+                max: Duration::from_millis(100),
+                jitter: true,
+            },
+            is_retryable: |error| matches!(error, DatabaseError::Conflict { .. }),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct DatabaseTransaction<Description>
 where
@@ -89,16 +170,256 @@ where
     }
 }
 
+impl<Description> DatabaseTransaction<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Commits the changes with the `durability`. The [`WriteDurability::Sync`]
+    /// returns only after the changes are on the disk.
+    pub fn commit_with(&mut self, durability: WriteDurability) -> StorageResult<()> {
+        self.changes.commit_with(durability)
+    }
+
+    /// Commits the changes like [`Self::commit_with`], but returns the
+    /// [`DatabaseError::Conflict`] as it is, see [`MemoryTransactionView::try_commit_with`].
+    pub fn try_commit_with(&mut self, durability: WriteDurability) -> DatabaseResult<()> {
+        self.changes.try_commit_with(durability)
+    }
+}
+
+impl<Description> Database<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Creates the transaction whose commit fails with the [`DatabaseError::Conflict`]
+    /// if a concurrent commit changed values read by it,
+    /// see [`MemoryTransactionView::with_conflict_detection`].
+    pub fn transaction_with_conflict_detection(
+        &self,
+    ) -> DatabaseTransaction<Description> {
+        let data = Arc::new(MemoryTransactionView::with_conflict_detection(
+            transaction_base(self.data.as_ref()),
+        ));
+        DatabaseTransaction {
+            changes: data.clone(),
+            database: Database::<Description>::new(data),
+        }
+    }
+
+    /// Runs `f` in the transaction with the conflict detection and commits it.
+    /// If `f` or the commit fails with the error retryable by the `policy`, `f` is run
+    /// again in the new transaction after the backoff. Other errors are returned at once.
+    /// When all attempts fail, returns the [`DatabaseError::RetriesExhausted`].
+    pub fn transaction_with_retry<F, R>(
+        &self,
+        policy: RetryPolicy,
+        mut f: F,
+    ) -> DatabaseResult<R>
+    where
+        F: FnMut(&mut DatabaseTransaction<Description>) -> DatabaseResult<R>,
+    {
+        let mut attempts = 0usize;
+        loop {
+            attempts = attempts.saturating_add(1);
+            let mut transaction = self.transaction_with_conflict_detection();
+            let error = match f(&mut transaction).and_then(|result| {
+                transaction.try_commit_with(WriteDurability::Buffered)?;
+                Ok(result)
+            }) {
+                Ok(result) => return Ok(result),
+                Err(error) => error,
+            };
+            if !(policy.is_retryable)(&error) {
+                return Err(error)
+            }
+            if attempts >= policy.max_attempts {
+                return Err(DatabaseError::RetriesExhausted {
+                    attempts,
+                    error: Box::new(error),
+                })
+            }
+            let retry = u32::try_from(attempts).unwrap_or(u32::MAX);
+            std::thread::sleep(policy.backoff.delay(retry));
+        }
+    }
+}
+
+impl DatabaseTransaction<OnChain> {
+    /// Commits the changes of the block at the `height` together with its undo log,
+    /// so the block can be rolled back later by [`Database::rollback_to`].
+    /// Blocks announced to peers should be committed with [`WriteDurability::Sync`],
+    /// while the bulk import may use [`WriteDurability::Buffered`].
+    pub fn commit_block(
+        &mut self,
+        height: BlockHeight,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        let undo_log = undo_log(&self.changes)?;
+        StorageBlindMutate::<UndoLog>::put(&mut self.database, &height, &undo_log)?;
+        self.commit_with(durability)
+    }
+}
+
+/// Returns the storage that the new transaction view over the `source` is created over,
+/// see [`crate::state::TransactableStorage::transaction_base`].
+fn transaction_base<Description>(
+    source: &DataSource<Description>,
+) -> DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    match source.transaction_base() {
+        // The base is the same data source, so it shares the sequence of writes.
+        Some(base) => DataSource(base, source.1.clone()),
+        None => source.clone(),
+    }
+}
+
 impl<Description> From<&Database<Description>> for DatabaseTransaction<Description>
 where
     Description: DatabaseDescription,
 {
     fn from(source: &Database<Description>) -> Self {
-        let database: &DataSource<Description> = source.data.as_ref();
-        let data = Arc::new(MemoryTransactionView::new(database.clone()));
+        let data = Arc::new(MemoryTransactionView::new(transaction_base(
+            source.data.as_ref(),
+        )));
         Self {
             changes: data.clone(),
             database: Database::<Description>::new(data),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fuel_core_storage::{
+        column::Column,
+        kv_store::KeyValueStore,
+    };
+
+    fn policy(max_attempts: usize) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            backoff: Backoff::Fixed(Duration::ZERO),
+            ..Default::default()
+        }
+    }
+
+    /// Increments the counter of the transaction, and overwrites it concurrently
+    /// while `conflicts` remain, so the commit of the transaction conflicts.
+    fn increment(
+        database: &Database,
+        transaction: &mut DatabaseTransaction<OnChain>,
+        conflicts: &mut usize,
+    ) -> DatabaseResult<u8> {
+        let value = transaction
+            .data_source()
+            .get(&[1], Column::Metadata)
+            .map_err(DatabaseError::from)?
+            .map_or(0, |value| value[0]);
+        transaction
+            .data_source()
+            .put(
+                &[1],
+                Column::Metadata,
+                Arc::new(vec![value.saturating_add(1)]),
+            )
+            .map_err(DatabaseError::from)?;
+        if *conflicts > 0 {
+            *conflicts = conflicts.saturating_sub(1);
+            let concurrent = u8::try_from(*conflicts).unwrap_or(u8::MAX);
+            database
+                .data_source()
+                .put(&[1], Column::Metadata, Arc::new(vec![concurrent]))
+                .map_err(DatabaseError::from)?;
+        }
+        Ok(value.saturating_add(1))
+    }
+
+    #[test]
+    fn conflicting_transaction_is_retried_until_it_commits() {
+        // Given
+        let database = Database::<OnChain>::default();
+        let mut conflicts = 3;
+        let mut attempts = 0usize;
+
+        // When
+        let result = database.transaction_with_retry(policy(5), |transaction| {
+            attempts = attempts.saturating_add(1);
+            increment(&database, transaction, &mut conflicts)
+        });
+
+        // Then
+        assert_eq!(result.unwrap(), 1);
+        assert_eq!(attempts, 4);
+        assert_eq!(
+            database.data_source().get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+    }
+
+    #[test]
+    fn permanent_error_is_returned_after_one_attempt() {
+        // Given
+        let database = Database::<OnChain>::default();
+        let mut attempts = 0usize;
+
+        // When
+        let result =
+            database.transaction_with_retry(policy(5), |_| -> DatabaseResult<()> {
+                attempts = attempts.saturating_add(1);
+                Err(DatabaseError::Other(anyhow::anyhow!("The permanent abort")))
+            });
+
+        // Then
+        assert!(matches!(result, Err(DatabaseError::Other(_))));
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn exhausted_retries_return_the_number_of_attempts() {
+        // Given
+        let database = Database::<OnChain>::default();
+        let mut conflicts = 10;
+
+        // When
+        let result = database.transaction_with_retry(policy(3), |transaction| {
+            increment(&database, transaction, &mut conflicts)
+        });
+
+        // Then
+        let Err(DatabaseError::RetriesExhausted { attempts, error }) = result else {
+            panic!("Unexpected result {result:?}")
+        };
+        assert_eq!(attempts, 3);
+        assert!(matches!(*error, DatabaseError::Conflict { .. }));
+    }
+
+    #[test]
+    fn exponential_backoff_is_capped() {
+        // Given
+        let backoff = Backoff::Exponential {
+            initial: Duration::from_millis(10),
+            max: Duration::from_millis(50),
+            jitter: false,
+        };
+
+        // When
+        let delays = (1..=5)
+            .map(|retry| backoff.delay(retry))
+            .collect::<Vec<_>>();
+
+        // Then
+        assert_eq!(
+            delays,
+            [10, 20, 40, 50, 50].map(Duration::from_millis).to_vec()
+        );
+        let jittered = Backoff::Exponential {
+            initial: Duration::from_millis(10),
+            max: Duration::from_millis(50),
+            jitter: true,
+        };
+        assert!(jittered.delay(100) <= Duration::from_millis(50));
+    }
+}
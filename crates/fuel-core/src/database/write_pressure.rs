@@ -0,0 +1,269 @@
+//! The watch of the [`WritePressure`] of the database.
+//!
+//! The storage reports its pressure on request, so the caller can check it before
+//! the large write, e.g. the import of the batch of blocks. The [`WritePressureMonitor`]
+//! polls the pressure in the background and publishes its changes to the watch channel,
+//! so upper layers back off while the RocksDB delays or stops writes until flushes
+//! and compactions catch up, or while the in-memory database approaches its capacity.
+//!
+//! The monitor counts the time in each state of the pressure in the
+//! `storage_write_pressure_micros` metric. The time is attributed to the state
+//! observed by the last poll, so it is precise up to the interval of polls.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Database,
+    },
+    state::{
+        DataSource,
+        WritePressure,
+    },
+};
+use fuel_core_metrics::storage_metrics::{
+    storage_metrics,
+    WritePressureLabels,
+};
+use std::{
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Arc,
+    },
+    thread::JoinHandle,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+use tokio::sync::watch;
+
+/// The handle of the thread that polls the [`WritePressure`] of the database.
+/// The thread stops after the [`Self::stop`] or the drop of the handle, and receivers
+/// of the channel see the last observed pressure after that.
+#[derive(Debug)]
+pub struct WritePressureMonitor {
+    sender: Arc<watch::Sender<WritePressure>>,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WritePressureMonitor {
+    /// Starts polling the pressure of the `storage` every `interval`.
+    pub fn start<Description>(
+        storage: DataSource<Description>,
+        interval: Duration,
+    ) -> Self
+    where
+        Description: DatabaseDescription,
+    {
+        let initial = storage.write_pressure();
+        let sender = Arc::new(watch::Sender::new(initial));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let thread = std::thread::spawn({
+            let sender = sender.clone();
+            let stopped = stopped.clone();
+            move || {
+                let mut pressure = initial;
+                let mut since = Instant::now();
+                loop {
+                    std::thread::park_timeout(interval);
+                    let now = Instant::now();
+                    record_time::<Description>(
+                        pressure,
+                        now.saturating_duration_since(since),
+                    );
+                    since = now;
+                    if stopped.load(Ordering::Acquire) {
+                        break
+                    }
+
+                    pressure = storage.write_pressure();
+                    sender.send_if_modified(|current| {
+                        if *current == pressure {
+                            return false
+                        }
+                        tracing::debug!(
+                            "The write pressure of the `{}` database changed from {:?} to {:?}",
+                            Description::name(),
+                            current,
+                            pressure
+                        );
+                        *current = pressure;
+                        true
+                    });
+                }
+            }
+        });
+        Self {
+            sender,
+            stopped,
+            thread: Some(thread),
+        }
+    }
+
+    /// Returns the receiver of changes of the pressure.
+    pub fn subscribe(&self) -> watch::Receiver<WritePressure> {
+        self.sender.subscribe()
+    }
+
+    /// Returns the pressure observed by the last poll.
+    pub fn current(&self) -> WritePressure {
+        *self.sender.borrow()
+    }
+
+    /// Stops the thread and waits for the poll in progress, if any.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stopped.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for WritePressureMonitor {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn record_time<Description>(pressure: WritePressure, elapsed: Duration)
+where
+    Description: DatabaseDescription,
+{
+    storage_metrics()
+        .write_pressure_micros
+        .get_or_create(&WritePressureLabels {
+            database: Description::name(),
+            state: pressure.state(),
+        })
+        .inc_by(u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX));
+}
+
+impl<Description> Database<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Returns whether writes into the database are slowed or stalled right now,
+    /// see [`TransactableStorage::write_pressure`].
+    ///
+    /// [`TransactableStorage::write_pressure`]: crate::state::TransactableStorage::write_pressure
+    pub fn write_pressure(&self) -> WritePressure {
+        self.data.as_ref().write_pressure()
+    }
+
+    /// Starts polling the pressure of writes into the database every `interval`,
+    /// see [`WritePressureMonitor`].
+    pub fn monitor_write_pressure(&self, interval: Duration) -> WritePressureMonitor {
+        WritePressureMonitor::start(self.data.as_ref().clone(), interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::on_chain::OnChain,
+        state::{
+            in_memory::memory_store::MemoryStore,
+            StallReason,
+        },
+    };
+    use fuel_core_storage::{
+        column::Column,
+        kv_store::KeyValueStore,
+    };
+
+    fn pressure_micros(state: &'static str) -> u64 {
+        storage_metrics()
+            .write_pressure_micros
+            .get_or_create(&WritePressureLabels {
+                database: OnChain::name(),
+                state,
+            })
+            .get()
+    }
+
+    async fn wait_for(
+        receiver: &mut watch::Receiver<WritePressure>,
+        expected: WritePressure,
+    ) -> WritePressure {
+        let pressure = tokio::time::timeout(
+            Duration::from_secs(10),
+            receiver.wait_for(|pressure| *pressure == expected),
+        )
+        .await
+        .expect("The pressure didn't change in time")
+        .unwrap();
+        *pressure
+    }
+
+    #[tokio::test]
+    async fn subscribers_see_transitions_of_the_pressure_and_the_recovery() {
+        // Given
+        let store = Arc::new(MemoryStore::<OnChain>::with_capacity_limit(100));
+        let database = Database::<OnChain>::new(store.clone());
+        let monitor = database.monitor_write_pressure(Duration::from_millis(1));
+        let mut receiver = monitor.subscribe();
+        let stalled_before = pressure_micros("stalled");
+        let initial = *receiver.borrow_and_update();
+
+        // When
+        store
+            .put(&[0], Column::Metadata, Arc::new(vec![0; 84]))
+            .unwrap();
+        let slowed =
+            wait_for(&mut receiver, WritePressure::Slowed(StallReason::MemoryCap)).await;
+        store
+            .put(&[0], Column::Metadata, Arc::new(vec![0; 99]))
+            .unwrap();
+        let stalled = wait_for(
+            &mut receiver,
+            WritePressure::Stalled(StallReason::MemoryCap),
+        )
+        .await;
+        let rejected = store.put(&[1], Column::Metadata, Arc::new(vec![0]));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        store.delete(&[0], Column::Metadata).unwrap();
+        let recovered = wait_for(&mut receiver, WritePressure::Ok).await;
+        monitor.stop();
+
+        // Then
+        assert_eq!(initial, WritePressure::Ok);
+        assert_eq!(slowed, WritePressure::Slowed(StallReason::MemoryCap));
+        assert_eq!(stalled, WritePressure::Stalled(StallReason::MemoryCap));
+        assert!(rejected.is_err());
+        assert_eq!(recovered, WritePressure::Ok);
+        assert_eq!(database.write_pressure(), WritePressure::Ok);
+        assert!(pressure_micros("stalled") > stalled_before);
+    }
+
+    #[test]
+    fn pressure_of_the_store_without_the_capacity_is_always_ok() {
+        // Given
+        let store = Arc::new(MemoryStore::<OnChain>::default());
+        let database = Database::<OnChain>::new(store.clone());
+        let monitor = database.monitor_write_pressure(Duration::from_millis(1));
+
+        // When
+        for key in 0..100u8 {
+            store
+                .put(&[key], Column::Metadata, Arc::new(vec![key; 1024]))
+                .unwrap();
+        }
+        std::thread::sleep(Duration::from_millis(10));
+
+        // Then
+        assert_eq!(database.write_pressure(), WritePressure::Ok);
+        assert_eq!(monitor.current(), WritePressure::Ok);
+        assert!(!monitor.subscribe().has_changed().unwrap());
+    }
+}
@@ -214,6 +214,7 @@ pub mod worker {
         transactional::Transactional,
         Error as StorageError,
         Result as StorageResult,
+        StorageBlindMutate,
         StorageMutate,
     };
     use fuel_core_types::{
@@ -231,10 +232,10 @@ pub mod worker {
     pub trait OffChainDatabase:
         Send
         + Sync
-        + StorageMutate<OwnedMessageIds, Error = StorageError>
-        + StorageMutate<OwnedCoins, Error = StorageError>
+        + StorageBlindMutate<OwnedMessageIds, Error = StorageError>
+        + StorageBlindMutate<OwnedCoins, Error = StorageError>
         + StorageMutate<MetadataTable<OffChain>, Error = StorageError>
-        + StorageMutate<FuelBlockIdsToHeights, Error = StorageError>
+        + StorageBlindMutate<FuelBlockIdsToHeights, Error = StorageError>
         + Transactional<Storage = Self>
     {
         fn record_tx_id_owner(
@@ -15,6 +15,8 @@ pub mod transactions;
     strum_macros::IntoStaticStr,
     PartialEq,
     Eq,
+    PartialOrd,
+    Ord,
     enum_iterator::Sequence,
     Hash,
 )]
@@ -54,3 +56,25 @@ impl StorageColumn for Column {
         self.as_u32()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ids are persisted, so reordering or renumbering columns must fail this test.
+    #[test]
+    fn ids_of_columns_are_stable() {
+        for column in enum_iterator::all::<Column>() {
+            let expected = match column {
+                Column::Metadata => 0,
+                Column::OwnedCoins => 1,
+                Column::TransactionStatus => 2,
+                Column::TransactionsByOwnerBlockIdx => 3,
+                Column::OwnedMessageIds => 4,
+                Column::Statistic => 5,
+                Column::FuelBlockIdsToHeights => 6,
+            };
+            assert_eq!(column.id(), expected, "The id of {column:?} changed");
+        }
+    }
+}
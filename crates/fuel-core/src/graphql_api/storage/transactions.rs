@@ -1,6 +1,7 @@
 use fuel_core_storage::{
     blueprint::plain::Plain,
     codec::{
+        height::BlockHeightKey,
         manual::Manual,
         postcard::Postcard,
         raw::Raw,
@@ -62,7 +63,7 @@ impl TableWithBlueprint for TransactionStatuses {
 }
 
 const TX_INDEX_SIZE: usize = size_of::<TransactionIndex>();
-const BLOCK_HEIGHT: usize = size_of::<BlockHeight>();
+const BLOCK_HEIGHT: usize = BlockHeightKey::LEN;
 const INDEX_SIZE: usize = Address::LEN + BLOCK_HEIGHT + TX_INDEX_SIZE;
 
 fn owned_tx_index_key(
@@ -75,7 +76,7 @@ fn owned_tx_index_key(
     // owner + block_height + tx_idx
     default[0..Address::LEN].copy_from_slice(owner.as_ref());
     default[Address::LEN..Address::LEN + BLOCK_HEIGHT]
-        .copy_from_slice(height.to_bytes().as_ref());
+        .copy_from_slice(BlockHeightKey::from(height).as_ref());
     default[Address::LEN + BLOCK_HEIGHT..].copy_from_slice(tx_idx.to_be_bytes().as_ref());
     default
 }
@@ -109,14 +110,14 @@ impl From<[u8; INDEX_SIZE]> for OwnedTransactionIndexKey {
     fn from(bytes: [u8; INDEX_SIZE]) -> Self {
         let owner: [u8; 32] = bytes[..32].try_into().expect("It's an array of 32 bytes");
         // the first 32 bytes are the owner, which is already known when querying
-        let mut block_height_bytes: [u8; 4] = Default::default();
-        block_height_bytes.copy_from_slice(&bytes[32..36]);
+        let block_height =
+            BlockHeightKey::try_from(&bytes[32..36]).expect("It's an array of 4 bytes");
         let mut tx_idx_bytes: [u8; 2] = Default::default();
         tx_idx_bytes.copy_from_slice(&bytes.as_ref()[36..38]);
 
         Self {
             owner: Address::from(owner),
-            block_height: u32::from_be_bytes(block_height_bytes).into(),
+            block_height: block_height.into(),
             tx_idx: u16::from_be_bytes(tx_idx_bytes),
         }
     }
@@ -163,13 +164,13 @@ impl From<OwnedTransactionIndexKey> for OwnedTransactionIndexCursor {
 
 impl From<Vec<u8>> for OwnedTransactionIndexCursor {
     fn from(bytes: Vec<u8>) -> Self {
-        let mut block_height_bytes: [u8; 4] = Default::default();
-        block_height_bytes.copy_from_slice(&bytes[..4]);
+        let block_height =
+            BlockHeightKey::try_from(&bytes[..4]).expect("The cursor has the height");
         let mut tx_idx_bytes: [u8; 2] = Default::default();
         tx_idx_bytes.copy_from_slice(&bytes[4..6]);
 
         Self {
-            block_height: u32::from_be_bytes(block_height_bytes).into(),
+            block_height: block_height.into(),
             tx_idx: u16::from_be_bytes(tx_idx_bytes),
         }
     }
@@ -178,7 +179,7 @@ impl From<Vec<u8>> for OwnedTransactionIndexCursor {
 impl From<OwnedTransactionIndexCursor> for Vec<u8> {
     fn from(cursor: OwnedTransactionIndexCursor) -> Self {
         let mut bytes = Vec::with_capacity(8);
-        bytes.extend(cursor.block_height.to_bytes());
+        bytes.extend_from_slice(BlockHeightKey::from(cursor.block_height).as_ref());
         bytes.extend(cursor.tx_idx.to_be_bytes());
         bytes
     }
@@ -34,6 +34,7 @@ use fuel_core_services::{
 use fuel_core_storage::{
     Result as StorageResult,
     StorageAsMut,
+    StorageBlindMutate,
 };
 use fuel_core_types::{
     blockchain::block::Block,
@@ -98,10 +99,11 @@ where
 
         let height = block.header().height();
         let block_id = block.id();
-        transaction
-            .as_mut()
-            .storage::<FuelBlockIdsToHeights>()
-            .insert(&block_id, height)?;
+        StorageBlindMutate::<FuelBlockIdsToHeights>::put(
+            transaction.as_mut(),
+            &block_id,
+            height,
+        )?;
 
         let total_tx_count = transaction
             .as_mut()
@@ -146,32 +148,29 @@ where
         for event in events {
             match event.deref() {
                 Event::MessageImported(message) => {
-                    block_st_transaction
-                        .storage_as_mut::<OwnedMessageIds>()
-                        .insert(
-                            &OwnedMessageKey::new(message.recipient(), message.nonce()),
-                            &(),
-                        )?;
+                    StorageBlindMutate::<OwnedMessageIds>::put(
+                        block_st_transaction,
+                        &OwnedMessageKey::new(message.recipient(), message.nonce()),
+                        &(),
+                    )?;
                 }
                 Event::MessageConsumed(message) => {
-                    block_st_transaction
-                        .storage_as_mut::<OwnedMessageIds>()
-                        .remove(&OwnedMessageKey::new(
-                            message.recipient(),
-                            message.nonce(),
-                        ))?;
+                    StorageBlindMutate::<OwnedMessageIds>::delete(
+                        block_st_transaction,
+                        &OwnedMessageKey::new(message.recipient(), message.nonce()),
+                    )?;
                 }
                 Event::CoinCreated(coin) => {
                     let coin_by_owner = owner_coin_id_key(&coin.owner, &coin.utxo_id);
-                    block_st_transaction
-                        .storage_as_mut::<OwnedCoins>()
-                        .insert(&coin_by_owner, &())?;
+                    StorageBlindMutate::<OwnedCoins>::put(
+                        block_st_transaction,
+                        &coin_by_owner,
+                        &(),
+                    )?;
                 }
                 Event::CoinConsumed(coin) => {
                     let key = owner_coin_id_key(&coin.owner, &coin.utxo_id);
-                    block_st_transaction
-                        .storage_as_mut::<OwnedCoins>()
-                        .remove(&key)?;
+                    StorageBlindMutate::<OwnedCoins>::delete(block_st_transaction, &key)?;
                 }
             }
         }
@@ -3,6 +3,10 @@
 #![deny(unused_crate_dependencies)]
 #![deny(warnings)]
 
+// The dev-dependency of benchmarks.
+#[cfg(test)]
+use criterion as _;
+
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[doc(no_inline)]
@@ -19,8 +19,8 @@ use fuel_core_services::{
     StateWatcher,
 };
 use fuel_core_storage::{
+    column::Column,
     transactional::AtomicView,
-    IsNotFound,
 };
 use std::net::SocketAddr;
 use tracing::warn;
@@ -116,6 +116,25 @@ impl FuelService {
                     CombinedDatabase::open(
                         &config.database_path,
                         config.max_database_cache_size,
+                        &config.database_columns,
+                    )?
+                }
+            }
+            #[cfg(feature = "redb")]
+            DbType::Redb => {
+                if config.database_path.as_os_str().is_empty() {
+                    warn!("No redb path configured, initializing in-memory database");
+                    CombinedDatabase::in_memory()
+                } else {
+                    tracing::info!(
+                        "Opening redb database {:?} with cache size \"{}\"",
+                        config.database_path,
+                        config.max_database_cache_size
+                    );
+                    CombinedDatabase::open_redb(
+                        &config.database_path,
+                        config.max_database_cache_size,
+                        &config.database_columns,
                     )?
                 }
             }
@@ -214,6 +233,13 @@ pub struct Task {
 impl Task {
     /// Private inner method for initializing the fuel service task
     pub fn new(mut database: CombinedDatabase, config: Config) -> anyhow::Result<Task> {
+        if config.storage_slow_log.is_enabled() {
+            database = database.with_slow_log(config.storage_slow_log);
+        }
+        if config.database_metrics {
+            database = database.with_metrics();
+        }
+
         // initialize state
         tracing::info!("Initializing database");
         let block_height = config
@@ -256,18 +282,19 @@ impl RunnableService for Task {
         let on_view = self.shared.database.on_chain().latest_view();
         let off_view = self.shared.database.off_chain().latest_view();
         // check if chain is initialized
-        if let Err(err) = on_view.get_genesis() {
-            if err.is_not_found() {
-                let result = execute_genesis_block(&self.shared.config, &on_view)?;
-
-                self.shared.block_importer.commit_result(result).await?;
-
-                let off_chain_db_transaction = genesis::off_chain::execute_genesis_block(
-                    &self.shared.config,
-                    &off_view,
-                )?;
-                off_chain_db_transaction.commit()?;
-            }
+        let is_fresh = on_view
+            .run_blocking(|view| Ok(view.is_column_empty(Column::FuelBlocks)?))
+            .await?;
+        if is_fresh {
+            let result = execute_genesis_block(&self.shared.config, &on_view)?;
+
+            self.shared.block_importer.commit_result(result).await?;
+
+            let off_chain_db_transaction = genesis::off_chain::execute_genesis_block(
+                &self.shared.config,
+                &off_view,
+            )?;
+            off_chain_db_transaction.commit()?;
         }
 
         for service in &self.services {
@@ -310,7 +337,11 @@ impl RunnableTask for Task {
                 );
             }
         }
-        self.shared.database.flush()?;
+        // Services are stopped, so the remaining writes are drained, and new ones
+        // are rejected, before the final flush of the database. The background work
+        // is stopped too, so the next open doesn't replay the WAL.
+        self.shared.database.begin_shutdown();
+        self.shared.database.finish_shutdown()?;
         Ok(())
     }
 }
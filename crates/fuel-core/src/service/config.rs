@@ -1,3 +1,7 @@
+use crate::state::{
+    column_config::ColumnsConfig,
+    slow_log::SlowLogConfig,
+};
 use clap::ValueEnum;
 use fuel_core_chain_config::{
     default_consensus_dev_key,
@@ -69,6 +73,12 @@ pub struct Config {
     pub time_until_synced: Duration,
     /// Time to wait after submitting a query before debug info will be logged about query.
     pub query_log_threshold_time: Duration,
+    /// Records the metrics of storage operations. When disabled, the storage isn't instrumented.
+    pub database_metrics: bool,
+    /// The thresholds of storage operations that are logged as slow.
+    pub storage_slow_log: SlowLogConfig,
+    /// The overrides of the RocksDB configuration of columns.
+    pub database_columns: ColumnsConfig,
 }
 
 impl Config {
@@ -114,6 +124,9 @@ impl Config {
             min_connected_reserved_peers: 0,
             time_until_synced: Duration::ZERO,
             query_log_threshold_time: Duration::from_secs(2),
+            database_metrics: false,
+            storage_slow_log: SlowLogConfig::default(),
+            database_columns: ColumnsConfig::default(),
         }
     }
 
@@ -169,4 +182,7 @@ pub struct VMConfig {
 pub enum DbType {
     InMemory,
     RocksDb,
+    /// The redb database, see [`crate::state::redb_store`].
+    #[cfg(feature = "redb")]
+    Redb,
 }
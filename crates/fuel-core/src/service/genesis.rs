@@ -7,6 +7,7 @@ use fuel_core_chain_config::{
     CoinConfig,
     ContractConfig,
     GenesisCommitment,
+    MessageConfig,
     StateConfig,
 };
 use fuel_core_executor::refs::ContractRef;
@@ -53,6 +54,7 @@ use fuel_core_types::{
     },
     fuel_types::{
         bytes::WORD_SIZE,
+        BlockHeight,
         Bytes32,
         ContractId,
     },
@@ -63,6 +65,7 @@ use fuel_core_types::{
 };
 use itertools::Itertools;
 
+pub mod import;
 pub mod off_chain;
 
 /// Performs the importing of the genesis block from the snapshot.
@@ -105,7 +108,20 @@ pub fn execute_genesis_block(
 }
 
 pub fn create_genesis_block(config: &Config) -> Block {
-    let block = Block::new(
+    // The initial height is defined by the `ChainConfig`.
+    // If it is `None` then it will be zero.
+    let height = config
+        .chain_conf
+        .initial_state
+        .as_ref()
+        .map(|config| config.height.unwrap_or_else(|| 0u32.into()))
+        .unwrap_or_else(|| 0u32.into());
+    genesis_block_at(height)
+}
+
+/// Creates the genesis block at the `height`.
+fn genesis_block_at(height: BlockHeight) -> Block {
+    Block::new(
         PartialBlockHeader {
             application: ApplicationHeader::<Empty> {
                 // TODO: Set `da_height` based on the chain config.
@@ -115,14 +131,7 @@ pub fn create_genesis_block(config: &Config) -> Block {
             consensus: ConsensusHeader::<Empty> {
                 // The genesis is a first block, so previous root is zero.
                 prev_root: Bytes32::zeroed(),
-                // The initial height is defined by the `ChainConfig`.
-                // If it is `None` then it will be zero.
-                height: config
-                    .chain_conf
-                    .initial_state
-                    .as_ref()
-                    .map(|config| config.height.unwrap_or_else(|| 0u32.into()))
-                    .unwrap_or_else(|| 0u32.into()),
+                height,
                 time: fuel_core_types::tai64::Tai64::UNIX_EPOCH,
                 generated: Empty,
             },
@@ -130,8 +139,7 @@ pub fn create_genesis_block(config: &Config) -> Block {
         // Genesis block doesn't have any transaction.
         vec![],
         &[],
-    );
-    block
+    )
 }
 
 #[cfg(feature = "test-helpers")]
@@ -160,32 +168,39 @@ fn init_coin_state(
     if let Some(state) = &state {
         if let Some(coins) = &state.coins {
             for coin in coins {
-                let coin = create_coin_from_config(coin, &mut generated_output_index);
-                let utxo_id = coin.utxo_id;
-                let compressed_coin = coin.compress();
+                let (coin, root) = init_coin(db, coin, &mut generated_output_index)?;
                 // ensure coin can't point to blocks in the future
-                if compressed_coin.tx_pointer().block_height()
-                    > state.height.unwrap_or_default()
-                {
+                if coin.tx_pointer.block_height() > state.height.unwrap_or_default() {
                     return Err(anyhow!(
                         "coin tx_pointer height cannot be greater than genesis block"
                     ))
                 }
-
-                if db
-                    .storage::<Coins>()
-                    .insert(&utxo_id, &compressed_coin)?
-                    .is_some()
-                {
-                    return Err(anyhow!("Coin should not exist"))
-                }
-                coins_tree.push(compressed_coin.root()?.as_slice())
+                coins_tree.push(root.as_slice())
             }
         }
     }
     Ok(coins_tree.root())
 }
 
+/// Inserts the coin of the `config` and returns it with its root.
+fn init_coin(
+    db: &mut Database,
+    config: &CoinConfig,
+    generated_output_index: &mut u64,
+) -> anyhow::Result<(Coin, MerkleRoot)> {
+    let coin = create_coin_from_config(config, generated_output_index);
+    let compressed_coin = coin.compress();
+    if db
+        .storage::<Coins>()
+        .insert(&coin.utxo_id, &compressed_coin)?
+        .is_some()
+    {
+        return Err(anyhow!("Coin should not exist"))
+    }
+    let root = compressed_coin.root()?;
+    Ok((coin, root))
+}
+
 fn init_contracts(
     db: &mut Database,
     state: &Option<StateConfig>,
@@ -196,88 +211,95 @@ fn init_contracts(
         if let Some(contracts) = &state.contracts {
             for (generated_output_index, contract_config) in contracts.iter().enumerate()
             {
-                let contract = Contract::from(contract_config.code.as_slice());
-                let salt = contract_config.salt;
-                let root = contract.root();
-                let contract_id = contract_config.contract_id;
-                let utxo_id = if let (Some(tx_id), Some(output_idx)) =
-                    (contract_config.tx_id, contract_config.output_index)
-                {
-                    UtxoId::new(tx_id, output_idx)
-                } else {
-                    #[allow(clippy::cast_possible_truncation)]
-                    UtxoId::new(
-                        // generated transaction id([0..[out_index/255]])
-                        Bytes32::try_from(
-                            (0..(Bytes32::LEN - WORD_SIZE))
-                                .map(|_| 0u8)
-                                .chain(
-                                    (generated_output_index as u64 / 255)
-                                        .to_be_bytes()
-                                        .into_iter(),
-                                )
-                                .collect_vec()
-                                .as_slice(),
-                        )
-                        .expect("Incorrect genesis transaction id byte length"),
-                        generated_output_index as u8,
-                    )
-                };
-                let tx_pointer = if let (Some(block_height), Some(tx_idx)) = (
-                    contract_config.tx_pointer_block_height,
-                    contract_config.tx_pointer_tx_idx,
-                ) {
-                    TxPointer::new(block_height, tx_idx)
-                } else {
-                    TxPointer::default()
-                };
-
+                let (tx_pointer, root) =
+                    init_contract(db, contract_config, generated_output_index)?;
                 if tx_pointer.block_height() > state.height.unwrap_or_default() {
                     return Err(anyhow!(
                         "contract tx_pointer cannot be greater than genesis block"
                     ))
                 }
-
-                // insert contract code
-                if db
-                    .storage::<ContractsRawCode>()
-                    .insert(&contract_id, contract.as_ref())?
-                    .is_some()
-                {
-                    return Err(anyhow!("Contract code should not exist"))
-                }
-
-                // insert contract root
-                if db
-                    .storage::<ContractsInfo>()
-                    .insert(&contract_id, &(salt, root))?
-                    .is_some()
-                {
-                    return Err(anyhow!("Contract info should not exist"))
-                }
-                if db
-                    .storage::<ContractsLatestUtxo>()
-                    .insert(
-                        &contract_id,
-                        &ContractUtxoInfo {
-                            utxo_id,
-                            tx_pointer,
-                        },
-                    )?
-                    .is_some()
-                {
-                    return Err(anyhow!("Contract utxo should not exist"))
-                }
-                init_contract_state(db, &contract_id, contract_config)?;
-                init_contract_balance(db, &contract_id, contract_config)?;
-                contracts_tree
-                    .push(ContractRef::new(&mut *db, contract_id).root()?.as_slice());
+                contracts_tree.push(root.as_slice());
             }
         }
     }
     Ok(contracts_tree.root())
 }
 
+/// Inserts the contract of the `contract_config` with its state and balances.
+/// Returns the `TxPointer` of the contract and its root.
+fn init_contract(
+    db: &mut Database,
+    contract_config: &ContractConfig,
+    generated_output_index: usize,
+) -> anyhow::Result<(TxPointer, MerkleRoot)> {
+    let contract = Contract::from(contract_config.code.as_slice());
+    let salt = contract_config.salt;
+    let root = contract.root();
+    let contract_id = contract_config.contract_id;
+    let utxo_id = if let (Some(tx_id), Some(output_idx)) =
+        (contract_config.tx_id, contract_config.output_index)
+    {
+        UtxoId::new(tx_id, output_idx)
+    } else {
+        #[allow(clippy::cast_possible_truncation)]
+        UtxoId::new(
+            // generated transaction id([0..[out_index/255]])
+            Bytes32::try_from(
+                (0..(Bytes32::LEN - WORD_SIZE))
+                    .map(|_| 0u8)
+                    .chain((generated_output_index as u64 / 255).to_be_bytes())
+                    .collect_vec()
+                    .as_slice(),
+            )
+            .expect("Incorrect genesis transaction id byte length"),
+            generated_output_index as u8,
+        )
+    };
+    let tx_pointer = if let (Some(block_height), Some(tx_idx)) = (
+        contract_config.tx_pointer_block_height,
+        contract_config.tx_pointer_tx_idx,
+    ) {
+        TxPointer::new(block_height, tx_idx)
+    } else {
+        TxPointer::default()
+    };
+
+    // insert contract code
+    if db
+        .storage::<ContractsRawCode>()
+        .insert(&contract_id, contract.as_ref())?
+        .is_some()
+    {
+        return Err(anyhow!("Contract code should not exist"))
+    }
+
+    // insert contract root
+    if db
+        .storage::<ContractsInfo>()
+        .insert(&contract_id, &(salt, root))?
+        .is_some()
+    {
+        return Err(anyhow!("Contract info should not exist"))
+    }
+    if db
+        .storage::<ContractsLatestUtxo>()
+        .insert(
+            &contract_id,
+            &ContractUtxoInfo {
+                utxo_id,
+                tx_pointer,
+            },
+        )?
+        .is_some()
+    {
+        return Err(anyhow!("Contract utxo should not exist"))
+    }
+    init_contract_state(db, &contract_id, contract_config)?;
+    init_contract_balance(db, &contract_id, contract_config)?;
+    let root = ContractRef::new(&mut *db, contract_id).root()?;
+    Ok((tx_pointer, root))
+}
+
 fn init_contract_state(
     db: &mut Database,
     contract_id: &ContractId,
@@ -298,16 +320,8 @@ fn init_da_messages(
     if let Some(state) = &state {
         if let Some(message_state) = &state.messages {
             for msg in message_state {
-                let message: Message = msg.clone().into();
-
-                if db
-                    .storage::<Messages>()
-                    .insert(message.id(), &message)?
-                    .is_some()
-                {
-                    return Err(anyhow!("Message should not exist"))
-                }
-                message_tree.push(message.root()?.as_slice());
+                let (_, root) = init_da_message(db, msg)?;
+                message_tree.push(root.as_slice());
             }
         }
     }
@@ -315,6 +329,24 @@ fn init_da_messages(
     Ok(message_tree.root())
 }
 
+/// Inserts the message of the `config` and returns it with its root.
+fn init_da_message(
+    db: &mut Database,
+    config: &MessageConfig,
+) -> anyhow::Result<(Message, MerkleRoot)> {
+    let message: Message = config.clone().into();
+
+    if db
+        .storage::<Messages>()
+        .insert(message.id(), &message)?
+        .is_some()
+    {
+        return Err(anyhow!("Message should not exist"))
+    }
+    let root = message.root()?;
+    Ok((message, root))
+}
+
 fn init_contract_balance(
     db: &mut Database,
     contract_id: &ContractId,
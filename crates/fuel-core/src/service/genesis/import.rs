@@ -0,0 +1,523 @@
+//! The import of the state snapshot in the [`StateConfig`] format, e.g. created by the
+//! [`Database::export_state_config`], into the empty database. The imported state becomes
+//! the state of the genesis block at the height of the snapshot, so the node continues
+//! the chain from the snapshot instead of the height zero.
+
+use crate::{
+    combined_database::CombinedDatabase,
+    database::{
+        database_description::{
+            off_chain::OffChain,
+            DatabaseDescription,
+        },
+        Database,
+    },
+    graphql_api::worker_service,
+    service::{
+        config::Config,
+        genesis::{
+            genesis_block_at,
+            init_coin,
+            init_contract,
+            init_da_message,
+        },
+    },
+};
+use anyhow::Context;
+use fuel_core_chain_config::{
+    CoinConfig,
+    ContractConfig,
+    GenesisCommitment,
+    MessageConfig,
+    StateConfig,
+    StateEntriesSink,
+};
+use fuel_core_storage::transactional::{
+    StorageTransaction,
+    Transactional,
+};
+use fuel_core_types::{
+    blockchain::{
+        consensus::{
+            Consensus,
+            Genesis,
+        },
+        SealedBlock,
+    },
+    fuel_merkle::binary::root_calculator::MerkleRootCalculator,
+    fuel_types::BlockHeight,
+    services::{
+        block_importer::{
+            ImportResult,
+            UncommittedResult as UncommittedImportResult,
+        },
+        executor::Event,
+    },
+};
+use itertools::Itertools;
+use std::{
+    borrow::Cow,
+    io::{
+        BufReader,
+        Read,
+    },
+};
+
+/// The maximum number of entries of the snapshot written by one batch.
+pub const IMPORT_CHUNK_SIZE: usize = 10_000;
+
+/// Imports the state snapshot from the `reader` and commits the genesis block of the state
+/// at the height of the snapshot. The snapshot is read entry by entry and written by chunks
+/// of the [`IMPORT_CHUNK_SIZE`], so it doesn't have to fit into the memory. The off-chain
+/// indexes of coins and messages are built from the imported entries. Returns the height
+/// of the imported state.
+///
+/// The import refuses the database that already has entries, unless it is `force`d,
+/// in which case the on-chain and off-chain databases are cleared first. The error of the
+/// invalid entry names the index of the entry in the snapshot. Chunks written before
+/// the error stay in the database, so the next attempt must be forced.
+///
+/// The state and balances are nested into their contract in the snapshot, so the snapshot
+/// can't have the state of an undeclared contract.
+pub async fn import_state_config(
+    config: &Config,
+    database: &CombinedDatabase,
+    reader: impl Read,
+    force: bool,
+) -> anyhow::Result<BlockHeight> {
+    let on_chain = database.on_chain();
+    let off_chain = database.off_chain();
+    if !on_chain.is_empty()? || !off_chain.is_empty()? {
+        if !force {
+            return Err(anyhow::anyhow!(
+                "The database is not empty, the snapshot can be imported only into the empty database"
+            ))
+        }
+        clear(on_chain)?;
+        clear(off_chain)?;
+    }
+
+    let mut importer = StateImporter::new(on_chain, off_chain);
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(reader));
+    let height = StateConfig::deserialize_streamed(
+        &mut deserializer,
+        IMPORT_CHUNK_SIZE,
+        &mut importer,
+    )?
+    .unwrap_or_default();
+    deserializer
+        .end()
+        .map_err(|err| anyhow::anyhow!("The snapshot is malformed: {err}"))?;
+
+    if let Some((tx_pointer_height, entry)) = importer.highest_tx_pointer {
+        if tx_pointer_height > height {
+            return Err(anyhow::anyhow!(
+                "The {entry} of the snapshot is invalid: tx_pointer height cannot be greater than genesis block"
+            ))
+        }
+    }
+
+    let genesis = Genesis {
+        chain_config_hash: config.chain_conf.root()?.into(),
+        coins_root: importer.coins_root.root().into(),
+        contracts_root: importer.contracts_root.root().into(),
+        messages_root: importer.messages_root.root().into(),
+    };
+    let block = SealedBlock {
+        entity: genesis_block_at(height),
+        consensus: Consensus::Genesis(genesis),
+    };
+    let result = UncommittedImportResult::new(
+        ImportResult::new_from_local(block, vec![], vec![]),
+        Transactional::transaction(on_chain),
+    );
+    let block_importer = fuel_core_importer::Importer::new(
+        config.block_importer.clone(),
+        on_chain.clone(),
+        (),
+        (),
+    );
+    block_importer.commit_result(result).await?;
+    Ok(height)
+}
+
+fn clear<Description>(database: &Database<Description>) -> anyhow::Result<()>
+where
+    Description: DatabaseDescription,
+{
+    for column in enum_iterator::all::<Description::Column>() {
+        database.clear_column(column)?;
+    }
+    Ok(())
+}
+
+/// Writes chunks of the snapshot into the database and accumulates roots of the genesis.
+struct StateImporter<'a> {
+    on_chain: &'a Database,
+    off_chain: &'a Database<OffChain>,
+    coins_root: MerkleRootCalculator,
+    contracts_root: MerkleRootCalculator,
+    messages_root: MerkleRootCalculator,
+    generated_output_index: u64,
+    /// The highest height of the `TxPointer` among imported coins and contracts,
+    /// with the entry that has it. The height of the snapshot may follow its entries,
+    /// so it is verified after the import.
+    highest_tx_pointer: Option<(BlockHeight, String)>,
+}
+
+impl<'a> StateImporter<'a> {
+    fn new(on_chain: &'a Database, off_chain: &'a Database<OffChain>) -> Self {
+        Self {
+            on_chain,
+            off_chain,
+            coins_root: MerkleRootCalculator::new(),
+            contracts_root: MerkleRootCalculator::new(),
+            messages_root: MerkleRootCalculator::new(),
+            generated_output_index: 0,
+            highest_tx_pointer: None,
+        }
+    }
+
+    fn record_tx_pointer(&mut self, height: BlockHeight, entry: impl FnOnce() -> String) {
+        if self
+            .highest_tx_pointer
+            .as_ref()
+            .map_or(true, |(highest, _)| height > *highest)
+        {
+            self.highest_tx_pointer = Some((height, entry()));
+        }
+    }
+
+    /// Commits the on-chain and off-chain changes of the chunk.
+    fn commit(
+        on_chain: StorageTransaction<Database>,
+        off_chain: StorageTransaction<Database<OffChain>>,
+    ) -> anyhow::Result<()> {
+        on_chain.commit()?;
+        off_chain.commit()?;
+        Ok(())
+    }
+}
+
+impl<'a> StateEntriesSink for StateImporter<'a> {
+    fn coins(
+        &mut self,
+        first_index: usize,
+        coins: Vec<CoinConfig>,
+    ) -> anyhow::Result<()> {
+        let mut on_chain = Transactional::transaction(self.on_chain);
+        let mut off_chain = Transactional::transaction(self.off_chain);
+        let mut events = Vec::with_capacity(coins.len());
+        for (index, config) in (first_index..).zip(coins.iter()) {
+            let entry = || format!("coin #{index}");
+            let (coin, root) =
+                init_coin(on_chain.as_mut(), config, &mut self.generated_output_index)
+                    .with_context(|| {
+                        format!("The {} of the snapshot is invalid", entry())
+                    })?;
+            self.record_tx_pointer(coin.tx_pointer.block_height(), entry);
+            self.coins_root.push(root.as_slice());
+            events.push(Cow::Owned(Event::CoinCreated(coin)));
+        }
+        worker_service::Task::process_executor_events(
+            events.into_iter(),
+            off_chain.as_mut(),
+        )?;
+        Self::commit(on_chain, off_chain)
+    }
+
+    fn contracts(
+        &mut self,
+        first_index: usize,
+        contracts: Vec<ContractConfig>,
+    ) -> anyhow::Result<()> {
+        let mut on_chain = Transactional::transaction(self.on_chain);
+        for (index, config) in (first_index..).zip(contracts.iter()) {
+            let entry = || format!("contract #{index}");
+            let (tx_pointer, root) = validate_contract(config)
+                .and_then(|_| init_contract(on_chain.as_mut(), config, index))
+                .with_context(|| format!("The {} of the snapshot is invalid", entry()))?;
+            self.record_tx_pointer(tx_pointer.block_height(), entry);
+            self.contracts_root.push(root.as_slice());
+        }
+        on_chain.commit()?;
+        Ok(())
+    }
+
+    fn messages(
+        &mut self,
+        first_index: usize,
+        messages: Vec<MessageConfig>,
+    ) -> anyhow::Result<()> {
+        let mut on_chain = Transactional::transaction(self.on_chain);
+        let mut off_chain = Transactional::transaction(self.off_chain);
+        let mut events = Vec::with_capacity(messages.len());
+        for (index, config) in (first_index..).zip(messages.iter()) {
+            let (message, root) = init_da_message(on_chain.as_mut(), config)
+                .with_context(|| {
+                    format!("The message #{index} of the snapshot is invalid")
+                })?;
+            self.messages_root.push(root.as_slice());
+            events.push(Cow::Owned(Event::MessageImported(message)));
+        }
+        worker_service::Task::process_executor_events(
+            events.into_iter(),
+            off_chain.as_mut(),
+        )?;
+        Self::commit(on_chain, off_chain)
+    }
+}
+
+/// Verifies that the state and balances of the contract don't repeat keys, because
+/// the repeated key would silently overwrite the previous value.
+fn validate_contract(config: &ContractConfig) -> anyhow::Result<()> {
+    if let Some(key) = config
+        .state
+        .iter()
+        .flatten()
+        .map(|(key, _)| key)
+        .duplicates()
+        .next()
+    {
+        return Err(anyhow::anyhow!(
+            "The state of the contract has the duplicated key {key}"
+        ))
+    }
+    if let Some(asset_id) = config
+        .balances
+        .iter()
+        .flatten()
+        .map(|(asset_id, _)| asset_id)
+        .duplicates()
+        .next()
+    {
+        return Err(anyhow::anyhow!(
+            "The balances of the contract have the duplicated asset {asset_id}"
+        ))
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::genesis::{
+        execute_genesis_block,
+        off_chain,
+    };
+    use fuel_core_chain_config::ChainConfig;
+    use fuel_core_types::{
+        blockchain::primitives::DaBlockHeight,
+        fuel_asm::op,
+        fuel_tx::Contract,
+        fuel_types::Salt,
+    };
+    use rand::{
+        rngs::StdRng,
+        Rng,
+        SeedableRng,
+    };
+
+    /// The random state ordered by keys, like the export orders it.
+    fn state_config(rng: &mut StdRng, coins: u32) -> StateConfig {
+        let mut coins = (0..coins)
+            .map(|i| CoinConfig {
+                tx_id: Some(rng.gen()),
+                output_index: Some(rng.gen()),
+                tx_pointer_block_height: Some((i % 10).into()),
+                tx_pointer_tx_idx: Some(rng.gen()),
+                maturity: Some(rng.gen::<u32>().into()),
+                owner: rng.gen(),
+                amount: rng.gen(),
+                asset_id: rng.gen(),
+            })
+            .collect::<Vec<_>>();
+        coins.sort_by_key(|coin| (coin.tx_id, coin.output_index));
+        let mut contracts = (0..3u8)
+            .map(|i| {
+                let salt: Salt = rng.gen();
+                let contract = Contract::from(op::ret(i).to_bytes().to_vec());
+                let root = contract.root();
+                let mut state =
+                    (0..4).map(|_| (rng.gen(), rng.gen())).collect::<Vec<_>>();
+                state.sort();
+                let mut balances =
+                    (0..4).map(|_| (rng.gen(), rng.gen())).collect::<Vec<_>>();
+                balances.sort();
+                ContractConfig {
+                    contract_id: contract.id(
+                        &salt,
+                        &root,
+                        &Contract::default_state_root(),
+                    ),
+                    code: contract.into(),
+                    salt,
+                    state: Some(state),
+                    balances: Some(balances),
+                    tx_id: Some(rng.gen()),
+                    output_index: Some(rng.gen()),
+                    tx_pointer_block_height: Some(u32::from(i).into()),
+                    tx_pointer_tx_idx: Some(rng.gen()),
+                }
+            })
+            .collect::<Vec<_>>();
+        contracts.sort_by_key(|contract| contract.contract_id);
+        let mut messages = (0..3u8)
+            .map(|i| MessageConfig {
+                sender: rng.gen(),
+                recipient: rng.gen(),
+                nonce: rng.gen(),
+                amount: rng.gen(),
+                data: vec![i; usize::from(i)],
+                da_height: DaBlockHeight(u64::from(i)),
+            })
+            .collect::<Vec<_>>();
+        messages.sort_by_key(|message| message.nonce);
+        StateConfig {
+            coins: Some(coins),
+            contracts: Some(contracts),
+            messages: Some(messages),
+            height: Some(10u32.into()),
+        }
+    }
+
+    fn config(state: Option<StateConfig>) -> Config {
+        Config {
+            chain_conf: ChainConfig {
+                initial_state: state,
+                ..ChainConfig::local_testnet()
+            },
+            ..Config::local_node()
+        }
+    }
+
+    /// Initializes the database from the genesis of the `config`, like the node does.
+    async fn genesis(config: &Config) -> CombinedDatabase {
+        let database = CombinedDatabase::in_memory();
+        let result = execute_genesis_block(config, database.on_chain()).unwrap();
+        fuel_core_importer::Importer::new(
+            config.block_importer.clone(),
+            database.on_chain().clone(),
+            (),
+            (),
+        )
+        .commit_result(result)
+        .await
+        .unwrap();
+        off_chain::execute_genesis_block(config, database.off_chain())
+            .unwrap()
+            .commit()
+            .unwrap();
+        database
+    }
+
+    /// The byte-level dump of every column of the database.
+    fn dump<Description>(database: &Database<Description>) -> Vec<Vec<u8>>
+    where
+        Description: DatabaseDescription,
+    {
+        enum_iterator::all::<Description::Column>()
+            .map(|column| {
+                let mut dump = vec![];
+                database.export_column(column, &mut dump).unwrap();
+                dump
+            })
+            .collect()
+    }
+
+    fn export(database: &CombinedDatabase) -> Vec<u8> {
+        let mut json = vec![];
+        database.on_chain().export_state_config(&mut json).unwrap();
+        json
+    }
+
+    #[tokio::test]
+    async fn imported_snapshot_reproduces_the_exported_node() {
+        // Given
+        let coins = u32::try_from(IMPORT_CHUNK_SIZE).unwrap() + 5;
+        let state = state_config(&mut StdRng::seed_from_u64(7), coins);
+        let node_a = genesis(&config(Some(state))).await;
+        let snapshot = export(&node_a);
+
+        // When
+        let node_b = CombinedDatabase::in_memory();
+        let height =
+            import_state_config(&config(None), &node_b, snapshot.as_slice(), false)
+                .await
+                .unwrap();
+
+        // Then
+        assert_eq!(height, 10u32.into());
+        assert_eq!(dump(node_b.on_chain()), dump(node_a.on_chain()));
+        assert_eq!(dump(node_b.off_chain()), dump(node_a.off_chain()));
+    }
+
+    #[tokio::test]
+    async fn import_refuses_non_empty_database_unless_forced() {
+        // Given
+        let state = state_config(&mut StdRng::seed_from_u64(7), 5);
+        let snapshot = export(&genesis(&config(Some(state.clone()))).await);
+        let other = state_config(&mut StdRng::seed_from_u64(8), 5);
+        let database = genesis(&config(Some(other))).await;
+        let before = dump(database.on_chain());
+
+        // When
+        let refused =
+            import_state_config(&config(None), &database, snapshot.as_slice(), false)
+                .await;
+        let unchanged = dump(database.on_chain());
+        import_state_config(&config(None), &database, snapshot.as_slice(), true)
+            .await
+            .unwrap();
+
+        // Then
+        assert!(refused.is_err());
+        assert_eq!(unchanged, before);
+        let expected = genesis(&config(Some(state))).await;
+        assert_eq!(dump(database.on_chain()), dump(expected.on_chain()));
+        assert_eq!(dump(database.off_chain()), dump(expected.off_chain()));
+    }
+
+    #[tokio::test]
+    async fn invalid_entries_are_reported_with_their_index() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let state = state_config(&mut rng, 5);
+        let json = |state: &StateConfig| serde_json::to_vec(state).unwrap();
+
+        let mut duplicated_coin = state.clone();
+        let coins = duplicated_coin.coins.as_mut().unwrap();
+        coins[3] = coins[1].clone();
+        let mut duplicated_key = state.clone();
+        let contract = &mut duplicated_key.contracts.as_mut().unwrap()[2];
+        let slots = contract.state.as_mut().unwrap();
+        slots[1].0 = slots[0].0;
+        let mut future_pointer = state.clone();
+        future_pointer.coins.as_mut().unwrap()[4].tx_pointer_block_height =
+            Some(11u32.into());
+        let tx_id = hex::encode(state.coins.as_ref().unwrap()[2].tx_id.unwrap());
+        let malformed_key = String::from_utf8(json(&state))
+            .unwrap()
+            .replace(&tx_id, &tx_id[..10])
+            .into_bytes();
+
+        for (snapshot, entry) in [
+            (json(&duplicated_coin), "coin #3"),
+            (json(&duplicated_key), "contract #2"),
+            (json(&future_pointer), "coin #4"),
+            (malformed_key, "coin #2"),
+        ] {
+            // Given
+            let database = CombinedDatabase::in_memory();
+
+            // When
+            let result =
+                import_state_config(&config(None), &database, snapshot.as_slice(), false)
+                    .await;
+
+            // Then
+            let err = format!("{:#}", result.unwrap_err());
+            assert!(err.contains(entry), "{err}");
+            assert!(database.on_chain().get_genesis().is_err());
+        }
+    }
+}
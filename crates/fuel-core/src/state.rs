@@ -4,43 +4,185 @@ use crate::{
             on_chain::OnChain,
             DatabaseDescription,
         },
+        Error as DatabaseError,
         Result as DatabaseResult,
     },
-    state::in_memory::{
-        memory_store::MemoryStore,
-        transaction::MemoryTransactionView,
+    state::{
+        column_iter::ColumnIter,
+        in_memory::{
+            memory_store::MemoryStore,
+            transaction::MemoryTransactionView,
+        },
+        notifying::StorageEvent,
+        store_dump::StoreDump,
     },
 };
 use fuel_core_storage::{
     iter::{
+        BoxedIter,
         IterDirection,
         IteratorableStore,
     },
-    kv_store::BatchOperations,
+    kv_store::{
+        BatchOperations,
+        KVItem,
+        KeyValueStore,
+        StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Result as StorageResult,
 };
 use std::{
+    any::Any,
     fmt::Debug,
-    sync::Arc,
+    path::Path,
+    sync::{
+        atomic::{
+            AtomicU64,
+            Ordering,
+        },
+        Arc,
+        RwLock,
+        RwLockWriteGuard,
+    },
 };
+use tokio::sync::broadcast;
 
+pub mod buffered;
+pub mod cached;
+pub mod change_log;
+pub mod checksummed;
+pub mod chunked_commit;
+pub mod column_config;
+pub mod column_iter;
+#[cfg(feature = "compression")]
+pub mod compressed;
+pub mod cursor;
+pub mod diff;
+#[cfg(test)]
+mod differential;
+#[cfg(feature = "encryption")]
+pub mod encrypted;
+#[cfg(any(test, feature = "test-helpers"))]
+pub mod faulty;
+pub mod guarded;
+pub mod historical;
+pub mod hybrid;
 pub mod in_memory;
+pub mod instrumented;
+pub mod notifying;
+pub mod open_mode;
+pub mod overlay;
+pub mod read_context;
+#[cfg(feature = "redb")]
+pub mod redb_store;
 #[cfg(feature = "rocksdb")]
 pub mod rocks_db;
+#[cfg(feature = "rocksdb")]
+pub mod rocks_db_key_iterator;
+pub mod slow_log;
+pub mod store_dump;
+pub mod strict_keys;
+pub mod swappable;
+#[cfg(test)]
+pub(crate) mod test_helpers;
+pub mod throttled;
+pub mod tiered;
+pub mod ttl;
+#[cfg(any(test, feature = "test-helpers"))]
+pub mod workload;
 
 type DataSourceInner<Column> = Arc<dyn TransactableStorage<Column = Column>>;
 
 #[derive(Clone, Debug)]
-pub struct DataSource<Description = OnChain>(DataSourceInner<Description::Column>)
+pub struct DataSource<Description = OnChain>(
+    pub(crate) DataSourceInner<Description::Column>,
+    pub(crate) Arc<CommitSequence>,
+)
 where
     Description: DatabaseDescription;
 
+impl<Description> DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    pub(crate) fn new(inner: DataSourceInner<Description::Column>) -> Self {
+        Self(inner, Arc::default())
+    }
+
+    /// Returns the sequence of writes into the data source, shared by its clones.
+    pub(crate) fn commits(&self) -> &CommitSequence {
+        &self.1
+    }
+}
+
+/// Counts writes into the data source, so views with the conflict detection can tell
+/// whether anything was written since they started reading without re-reading keys.
+///
+/// Writes hold the lock shared and bump the counter when they are done. The commit of
+/// the view with the conflict detection holds it exclusively from the validation until
+/// its write is done, so no write sneaks in between them.
+#[derive(Debug, Default)]
+pub(crate) struct CommitSequence {
+    lock: RwLock<()>,
+    seq: AtomicU64,
+}
+
+impl CommitSequence {
+    /// Returns the number of writes done so far.
+    pub(crate) fn current(&self) -> u64 {
+        self.seq.load(Ordering::Acquire)
+    }
+
+    /// Runs the `write` and counts it. The failed write is counted too,
+    /// because it may have been applied partially.
+    pub(crate) fn write<R>(&self, write: impl FnOnce() -> R) -> R {
+        let _guard = self.lock.read().expect("poisoned lock");
+        let result = write();
+        self.seq.fetch_add(1, Ordering::AcqRel);
+        result
+    }
+
+    /// Keeps other writes out until the returned guard is dropped.
+    pub(crate) fn exclusive(&self) -> ExclusiveCommit<'_> {
+        ExclusiveCommit {
+            _guard: self.lock.write().expect("poisoned lock"),
+            seq: &self.seq,
+        }
+    }
+}
+
+/// The exclusive access to the data source, see [`CommitSequence::exclusive`].
+pub(crate) struct ExclusiveCommit<'a> {
+    _guard: RwLockWriteGuard<'a, ()>,
+    seq: &'a AtomicU64,
+}
+
+impl ExclusiveCommit<'_> {
+    /// Returns the number of writes done so far.
+    pub(crate) fn current(&self) -> u64 {
+        self.seq.load(Ordering::Acquire)
+    }
+
+    /// Runs the `write` and counts it. Returns the result of the `write` and
+    /// the number of writes done after it.
+    pub(crate) fn write<R>(&self, write: impl FnOnce() -> R) -> (R, u64) {
+        let result = write();
+        let seq = self.seq.fetch_add(1, Ordering::AcqRel).wrapping_add(1);
+        (result, seq)
+    }
+}
+
 impl<Description> From<Arc<MemoryTransactionView<Description>>>
     for DataSource<Description>
 where
     Description: DatabaseDescription,
 {
     fn from(inner: Arc<MemoryTransactionView<Description>>) -> Self {
-        Self(inner)
+        Self::new(inner)
     }
 }
 
@@ -50,7 +192,188 @@ where
     Description: DatabaseDescription,
 {
     fn from(inner: Arc<rocks_db::RocksDb<Description>>) -> Self {
-        Self(inner)
+        Self::new(inner)
+    }
+}
+
+#[cfg(feature = "redb")]
+impl<Description> From<Arc<redb_store::RedbStore<Description>>>
+    for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<redb_store::RedbStore<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+#[cfg(any(test, feature = "test-helpers"))]
+impl<Description> From<Arc<faulty::FaultyStorage<Description>>>
+    for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<faulty::FaultyStorage<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<Description> From<Arc<guarded::GuardedStorage<Description>>>
+    for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<guarded::GuardedStorage<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<Description> From<Arc<hybrid::HybridStore<Description>>> for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<hybrid::HybridStore<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<Description> From<Arc<buffered::BufferedStorage<Description>>>
+    for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<buffered::BufferedStorage<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<Description> From<Arc<cached::CachedStorage<Description>>>
+    for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<cached::CachedStorage<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<Description> From<Arc<checksummed::ChecksummedStorage<Description>>>
+    for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<checksummed::ChecksummedStorage<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+#[cfg(feature = "compression")]
+impl<Description> From<Arc<compressed::CompressedStorage<Description>>>
+    for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<compressed::CompressedStorage<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl<Description> From<Arc<encrypted::EncryptedStorage<Description>>>
+    for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<encrypted::EncryptedStorage<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<Description> From<Arc<instrumented::InstrumentedStorage<Description>>>
+    for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<instrumented::InstrumentedStorage<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<Description> From<Arc<notifying::NotifyingStorage<Description>>>
+    for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<notifying::NotifyingStorage<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<Description> From<Arc<slow_log::SlowLogStorage<Description>>>
+    for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<slow_log::SlowLogStorage<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<Description> From<Arc<strict_keys::StrictKeysStorage<Description>>>
+    for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<strict_keys::StrictKeysStorage<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<Description> From<Arc<swappable::SwappableStorage<Description>>>
+    for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<swappable::SwappableStorage<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<Description> From<Arc<overlay::OverlayStorage<Description>>>
+    for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<overlay::OverlayStorage<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<Description> From<Arc<change_log::ChangeLogStorage<Description>>>
+    for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<change_log::ChangeLogStorage<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<Description> From<Arc<tiered::TieredStore<Description>>> for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<tiered::TieredStore<Description>>) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<Description> From<Arc<ttl::TtlStorage<Description>>> for DataSource<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn from(inner: Arc<ttl::TtlStorage<Description>>) -> Self {
+        Self::new(inner)
     }
 }
 
@@ -59,7 +382,7 @@ where
     Description: DatabaseDescription,
 {
     fn from(inner: Arc<MemoryStore<Description>>) -> Self {
-        Self(inner)
+        Self::new(inner)
     }
 }
 
@@ -86,5 +409,595 @@ where
 pub trait TransactableStorage:
     IteratorableStore + BatchOperations + Debug + Send + Sync
 {
+    /// Makes all writes done before the call durable, i.e., they survive a crash of the process
+    /// or the machine. The storage without durability returns `Ok` without doing anything.
     fn flush(&self) -> DatabaseResult<()>;
+
+    /// Writes the batch of the entries with the `durability`. The storage without
+    /// durability modes writes the batch in the same way as [`BatchOperations::batch_write`].
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        let _ = durability;
+        self.batch_write(entries)
+    }
+
+    /// Returns the iterator over the `column` that lends its items instead of allocating
+    /// them, see [`ColumnIter`]. It yields the same items as [`IteratorableStore::iter_all`].
+    /// The storage without its own lending iterator falls back to the boxed one.
+    fn iter_column(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> ColumnIter<'_> {
+        ColumnIter::boxed(self.iter_all(column, prefix, start, direction))
+    }
+
+    /// Returns the statistics of the `column`. The values may be approximate.
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats>;
+
+    /// Returns the internal statistics of the storage engine.
+    /// The storage without its own engine returns the empty statistics.
+    fn engine_stats(&self) -> DatabaseResult<EngineStats> {
+        Ok(EngineStats::default())
+    }
+
+    /// Returns whether writes into the storage are slowed or stalled right now, so upper
+    /// layers can back off before writes block, see [`WritePressure`].
+    /// The storage that never delays writes returns [`WritePressure::Ok`].
+    fn write_pressure(&self) -> WritePressure {
+        WritePressure::Ok
+    }
+
+    /// Returns the size in bytes of the entries of the `column` with keys from the `start`
+    /// inclusive to the `end` exclusive. The `None` end means the end of the column.
+    ///
+    /// The size on disk of the persistent storage is approximate, it includes
+    /// the overhead of indexes and the compression. The storage without the approximation
+    /// returns the exact size of keys and values.
+    fn size_of_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> DatabaseResult<u64> {
+        self.iter_range(column, None, Some(start), end, IterDirection::Forward)
+            .try_fold(0u64, |size, item| {
+                let (key, value) = item.map_err(DatabaseError::from)?;
+                Ok(size
+                    .saturating_add(key.len() as u64)
+                    .saturating_add(value.len() as u64))
+            })
+    }
+
+    /// Returns the size in bytes of the entries of the `column` with keys starting
+    /// with the `prefix`, see [`Self::size_of_range`].
+    fn size_of_prefix(&self, column: Self::Column, prefix: &[u8]) -> DatabaseResult<u64> {
+        let end = next_prefix(prefix.to_vec());
+        self.size_of_range(column, prefix, end.as_deref())
+    }
+
+    /// Creates a consistent point-in-time copy of the storage at the `path`.
+    /// Writes that happen while the checkpoint is taken are not included into it.
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()>;
+
+    /// Creates a new backup of the storage in the `backup_dir` while the storage keeps serving requests.
+    /// Backups in the same directory are incremental: files shared with previous backups are not copied.
+    fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()>;
+
+    /// Compacts the `range` of keys of the `column` to reclaim the space of deleted
+    /// and overwritten values. The `None` column means all columns, and the `None` range
+    /// means the whole column. The range includes both bounds.
+    ///
+    /// The compaction blocks the calling thread until it is done,
+    /// but it doesn't block other operations with the storage.
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()>;
+
+    /// Removes all entries of the `column`. It is much cheaper than removing entries
+    /// one by one, so it is suitable for rebuilding the whole column.
+    ///
+    /// The clear is a write like any other for concurrent iterators: the iterator created
+    /// before the clear either finishes over the old entries or ends without an error,
+    /// depending on whether the storage iterates over the point in time.
+    /// The transaction view stages the clear, so it reaches the data source on the commit.
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()>;
+
+    /// Removes the `keys` of the `column` at once and returns the number of distinct keys
+    /// that existed before the call. The key listed twice is removed and counted once.
+    ///
+    /// The removal is expanded into one [`WriteOperation::Remove`] per distinct key, so
+    /// the change log and the replication see it as ordinary removals of the batch.
+    /// By default, keys are read by one [`KeyValueStore::multi_get`] and removed by one
+    /// [`BatchOperations::batch_write`], e.g. one `WriteBatch` of the RocksDB.
+    fn multi_delete(
+        &self,
+        column: Self::Column,
+        keys: &[Vec<u8>],
+    ) -> DatabaseResult<usize> {
+        let keys = distinct_keys(keys);
+        let existing = self
+            .multi_get(&keys, column)
+            .map_err(DatabaseError::from)?
+            .iter()
+            .filter(|value| value.is_some())
+            .count();
+        self.batch_write(
+            &mut keys
+                .into_iter()
+                .map(|key| (key, column, WriteOperation::Remove)),
+        )
+        .map_err(DatabaseError::from)?;
+        Ok(existing)
+    }
+
+    /// Removes all keys of the `column` from the `start` inclusive to the `end` exclusive.
+    /// The range is empty if the `start` isn't less than the `end`.
+    ///
+    /// The RocksDB writes one range tombstone instead of a tombstone per key, so it is
+    /// suitable for removing millions of keys, e.g. the state of the destroyed contract.
+    /// By default, the range is expanded into one [`WriteOperation::Remove`] per key
+    /// inside it, so the change log and the replication see it as ordinary removals.
+    fn delete_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: &[u8],
+    ) -> DatabaseResult<()> {
+        if start >= end {
+            return Ok(())
+        }
+        let keys = self
+            .iter_keys(column, None, Some(start), IterDirection::Forward)
+            .take_while(|key| key.as_ref().map_or(true, |key| key.as_slice() < end))
+            .collect::<StorageResult<Vec<_>>>()
+            .map_err(DatabaseError::from)?;
+        self.batch_write(
+            &mut keys
+                .into_iter()
+                .map(|key| (key, column, WriteOperation::Remove)),
+        )
+        .map_err(DatabaseError::from)
+    }
+
+    /// Writes the `entries` with strictly increasing keys into the `column`, overwriting
+    /// entries with the same keys. The key that isn't greater than the previous one fails
+    /// the ingest with [`DatabaseError::UnsortedIngest`], and nothing is written then.
+    ///
+    /// The RocksDB builds SST files from entries and ingests them into the column family,
+    /// so entries skip the WAL, memtables, and compactions of upper levels. By default,
+    /// entries are collected and written by one [`BatchOperations::batch_write`], so
+    /// wrappers that transform values or observe writes see them as an ordinary batch.
+    fn ingest_sorted(
+        &self,
+        column: Self::Column,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<IngestStats> {
+        let mut stats = IngestStats::default();
+        let mut batch: Vec<(Vec<u8>, Vec<u8>)> = vec![];
+        for (key, value) in entries {
+            ensure_sorted(
+                column,
+                batch.last().map(|(previous, _)| previous.as_slice()),
+                &key,
+            )?;
+            stats.record(&key, &value);
+            batch.push((key, value));
+        }
+        self.batch_write(
+            &mut batch.into_iter().map(|(key, value)| {
+                (key, column, WriteOperation::Insert(Arc::new(value)))
+            }),
+        )
+        .map_err(DatabaseError::from)?;
+        Ok(stats)
+    }
+
+    /// Returns the copy of the storage frozen at the current point in time.
+    /// Writes into the storage after the call are not visible through the copy,
+    /// and the copy itself rejects writes or doesn't propagate them to the storage.
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>>;
+
+    /// Returns the read-only view of the storage at the current point in time.
+    /// The view is released when it is dropped.
+    fn read_snapshot(&self) -> DatabaseResult<Box<dyn ReadView<Column = Self::Column>>>
+    where
+        Self::Column: 'static,
+    {
+        Ok(Box::new(self.snapshot()?))
+    }
+
+    /// Returns all entries of the storage in the canonical order, see [`StoreDump`].
+    fn dump(&self) -> DatabaseResult<StoreDump>
+    where
+        Self::Column: enum_iterator::Sequence,
+    {
+        StoreDump::capture(self)
+    }
+
+    /// Returns the storage that new transaction views are created over instead of this
+    /// storage, or `None` to create them over this storage. The storage that replaces its
+    /// underlying storage at runtime returns the current one, so the view reads and commits
+    /// into the same storage until it is dropped, see [`swappable::SwappableStorage`].
+    fn transaction_base(&self) -> Option<DataSourceInner<Self::Column>> {
+        None
+    }
+
+    /// Subscribes to committed changes of keys of the `column` with the `prefix`,
+    /// see [`notifying::NotifyingStorage`]. The storage without notifications
+    /// returns the error.
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        let _ = (column, prefix);
+        Err(DatabaseError::Unsupported("subscriptions"))
+    }
+
+    /// Applies the change log starting from the entry `from_seq` to the `into` storage
+    /// and returns the sequence number of the next entry, see [`change_log::ChangeLogStorage`].
+    /// The storage without the change log returns the error.
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        let _ = (from_seq, into);
+        Err(DatabaseError::Unsupported("change log"))
+    }
+
+    /// Removes entries of the change log before the `seq`.
+    /// The storage without the change log returns the error.
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        let _ = seq;
+        Err(DatabaseError::Unsupported("change log"))
+    }
+
+    /// Rejects writes of the `columns` until each of them is unfrozen as many times
+    /// as it was frozen, see [`guarded::GuardedStorage`]. The storage without the guard
+    /// returns the error.
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        let _ = columns;
+        Err(DatabaseError::Unsupported("column freezing"))
+    }
+
+    /// Reverts one [`Self::freeze_columns`] of the `columns`.
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        let _ = columns;
+    }
+
+    /// Starts the shutdown of the storage: new writes are rejected with
+    /// [`DatabaseError::ShuttingDown`], and the call returns after writes in progress
+    /// are completed. The storage without draining ignores the call.
+    fn begin_shutdown(&self) {}
+
+    /// Completes the shutdown started by [`Self::begin_shutdown`]: makes all writes
+    /// durable and stops the background work of the storage, so the next open doesn't
+    /// recover anything. The storage without background work only flushes.
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        self.flush()
+    }
+
+    /// Returns the storage itself as [`Any`], so the code that needs the operations
+    /// specific to the backend can downcast it to the concrete type. The wrappers return
+    /// themselves, not the storage they wrap.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<Column> dyn TransactableStorage<Column = Column>
+where
+    Column: StorageColumn + 'static,
+{
+    /// Returns the RocksDB behind the storage, or `None` if the storage is another backend
+    /// or a wrapper around the RocksDB.
+    #[cfg(feature = "rocksdb")]
+    pub fn as_rocks<Description>(&self) -> Option<&rocks_db::RocksDb<Description>>
+    where
+        Description: DatabaseDescription<Column = Column>,
+    {
+        self.as_any().downcast_ref()
+    }
+}
+
+/// The read-only view of the storage at the point in time when it was created.
+/// It allows multiple reads to observe the same state of the storage.
+pub trait ReadView: Debug + Send + Sync {
+    /// The type of the column.
+    type Column: StorageColumn;
+
+    /// Returns the value from the storage.
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>>;
+
+    /// Checks if the value exists in the storage.
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool>;
+
+    /// Returns the values from the storage for each of the `keys` in the same order.
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>>;
+
+    /// Returns an iterator over the values in the storage.
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem>;
+}
+
+impl<Column> ReadView for DataSourceInner<Column>
+where
+    Column: StorageColumn,
+{
+    type Column = Column;
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        KeyValueStore::get(self.as_ref(), key, column)
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        KeyValueStore::exists(self.as_ref(), key, column)
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        KeyValueStore::multi_get(self.as_ref(), keys, column)
+    }
+
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        IteratorableStore::iter_all(self.as_ref(), column, prefix, start, direction)
+    }
+}
+
+/// The durability of the batch write.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WriteDurability {
+    /// The write is durable after the storage syncs its log by itself or on [`TransactableStorage::flush`].
+    /// A crash of the machine may lose the latest buffered writes.
+    #[default]
+    Buffered,
+    /// The write is synced to the disk before the call returns.
+    Sync,
+}
+
+/// Returns the smallest key greater than all keys starting with the `prefix`.
+/// The `None` means overflow, e.g. of the prefix of `0xFF` bytes, so there is
+/// no following prefix.
+pub(crate) fn next_prefix(mut prefix: Vec<u8>) -> Option<Vec<u8>> {
+    while let Some(byte) = prefix.pop() {
+        if let Some(new_byte) = byte.checked_add(1) {
+            prefix.push(new_byte);
+            return Some(prefix)
+        }
+    }
+    None
+}
+
+/// Returns the `keys` without duplicates in the ascending order.
+pub(crate) fn distinct_keys(keys: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    let mut keys = keys.to_vec();
+    keys.sort_unstable();
+    keys.dedup();
+    keys
+}
+
+/// Fails with [`DatabaseError::UnsortedIngest`] if the `key` of the sorted ingest
+/// isn't greater than the `previous` key.
+pub(crate) fn ensure_sorted<Column>(
+    column: Column,
+    previous: Option<&[u8]>,
+    key: &[u8],
+) -> DatabaseResult<()>
+where
+    Column: StorageColumn,
+{
+    match previous {
+        Some(previous) if previous >= key => Err(DatabaseError::UnsortedIngest {
+            column: column.name(),
+            key: key.to_vec(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// The function decoding the stored value for [`update_encoded`], like [`UpdateFn`]
+/// for the update itself.
+pub(crate) type DecodeFn<'a> = dyn Fn(&[u8]) -> StorageResult<Option<Vec<u8>>> + 'a;
+
+/// Updates the value of the `key` in the `inner` storage that keeps values encoded
+/// by the wrapper, e.g. compressed or encrypted. The `f` receives the `decode`d value,
+/// and its output is `encode`d inside the update of the `inner` storage, so the update
+/// is as atomic as the update of the `inner` storage. The output equal to the current
+/// value leaves the stored value untouched.
+pub(crate) fn update_encoded<S>(
+    inner: &S,
+    key: &[u8],
+    column: S::Column,
+    f: &mut UpdateFn<'_>,
+    decode: &DecodeFn<'_>,
+    encode: &dyn Fn(Vec<u8>) -> StorageResult<Vec<u8>>,
+) -> StorageResult<UpdateOutcome>
+where
+    S: KeyValueStore + ?Sized,
+{
+    let mut error = None;
+    let mut outcome = UpdateOutcome::Unchanged;
+    inner.update(key, column, &mut |stored| {
+        let unchanged = stored.map(<[u8]>::to_vec);
+        let current = match stored.map(decode).transpose() {
+            Ok(current) => current.flatten(),
+            Err(err) => {
+                error = Some(err);
+                return unchanged
+            }
+        };
+        let new = f(current.as_deref());
+        outcome = UpdateOutcome::of(current.as_deref(), new.as_deref());
+        if !outcome.is_changed() {
+            return unchanged
+        }
+        match new.map(encode).transpose() {
+            Ok(new) => new,
+            Err(err) => {
+                error = Some(err);
+                outcome = UpdateOutcome::Unchanged;
+                unchanged
+            }
+        }
+    })?;
+    match error {
+        Some(err) => Err(err),
+        None => Ok(outcome),
+    }
+}
+
+/// The outcome of the [`TransactableStorage::ingest_sorted`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IngestStats {
+    /// The number of ingested entries.
+    pub entries: u64,
+    /// The size of keys and values of ingested entries in bytes.
+    pub bytes: u64,
+    /// The number of SST files ingested into the RocksDB, `0` for other backends.
+    pub files: u64,
+}
+
+impl IngestStats {
+    pub(crate) fn record(&mut self, key: &[u8], value: &[u8]) {
+        self.entries = self.entries.saturating_add(1);
+        self.bytes = self
+            .bytes
+            .saturating_add(key.len() as u64)
+            .saturating_add(value.len() as u64);
+    }
+}
+
+/// The statistics of the column in the storage.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize,
+)]
+pub struct ColumnStats {
+    /// The number of keys in the column.
+    pub keys: u64,
+    /// The size of the column in bytes.
+    pub size: u64,
+}
+
+impl ColumnStats {
+    /// Returns the statistics of the column created from the entries.
+    pub fn from_entries<'a>(entries: impl Iterator<Item = (&'a [u8], &'a [u8])>) -> Self {
+        entries.fold(Self::default(), |stats, (key, value)| {
+            stats.merge(Self {
+                keys: 1,
+                size: (key.len() as u64).saturating_add(value.len() as u64),
+            })
+        })
+    }
+
+    /// Returns the sum of the statistics.
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            keys: self.keys.saturating_add(other.keys),
+            size: self.size.saturating_add(other.size),
+        }
+    }
+}
+
+/// The internal statistics of the storage engine, e.g. of the RocksDB.
+/// The storage without its own engine returns the empty statistics.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EngineStats {
+    /// The number of reads of blocks served by the block cache.
+    /// Counted only with the enabled statistics of the engine.
+    pub block_cache_hits: u64,
+    /// The number of reads of blocks that missed the block cache.
+    /// Counted only with the enabled statistics of the engine.
+    pub block_cache_misses: u64,
+    /// The total time of stalled writes in microseconds.
+    /// Counted only with the enabled statistics of the engine.
+    pub stall_micros: u64,
+    /// The size of entries in the block cache in bytes.
+    pub block_cache_usage: u64,
+    /// The size of memtables of all columns in bytes.
+    pub memtable_usage: u64,
+    /// The memory budget of the block cache and memtables in bytes, `0` if unlimited.
+    pub memory_budget: u64,
+    /// The number of compactions that are running.
+    pub running_compactions: u64,
+    /// Whether writes are stopped until flushes and compactions catch up.
+    pub writes_stopped: bool,
+    /// The statistics of columns.
+    pub columns: Vec<ColumnEngineStats>,
+}
+
+/// The pressure of writes on the storage, see [`TransactableStorage::write_pressure`].
+/// The more severe pressure is greater, so the storage over several storages
+/// reports the maximum of their pressures.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WritePressure {
+    /// Writes proceed at full speed.
+    #[default]
+    Ok,
+    /// Writes are delayed by the storage until it catches up.
+    Slowed(StallReason),
+    /// Writes are blocked or rejected until the storage catches up.
+    Stalled(StallReason),
+}
+
+impl WritePressure {
+    /// Returns the name of the state of the pressure without the reason, e.g. for metrics.
+    pub fn state(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Slowed(_) => "slowed",
+            Self::Stalled(_) => "stalled",
+        }
+    }
+}
+
+/// The reason of the [`WritePressure`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StallReason {
+    /// Too many files on the first level of the LSM tree wait for the compaction.
+    Level0Files,
+    /// Too much data waits for the compaction.
+    PendingCompactionBytes,
+    /// Too many memtables wait for the flush.
+    Memtables,
+    /// The in-memory storage approaches its capacity.
+    MemoryCap,
+}
+
+/// The internal statistics of the column in the storage engine, see [`EngineStats`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ColumnEngineStats {
+    /// The name of the column.
+    pub column: &'static str,
+    /// The estimated size of data that compactions have to rewrite
+    /// to bring the column into the target shape. Large values stall writes.
+    pub pending_compaction_bytes: u64,
+    /// The number of memtables that are waiting for the flush.
+    pub immutable_memtables: u64,
+    /// The number of files on the first level. Too many files stall writes.
+    pub level0_files: u64,
 }
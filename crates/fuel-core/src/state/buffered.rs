@@ -0,0 +1,1011 @@
+//! The write-coalescing buffer of columns that tolerate delayed persistence,
+//! see [`BufferedStorage`].
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::{
+        column_iter::ColumnIter,
+        notifying::StorageEvent,
+        tiered::merge_tiers,
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        EngineStats,
+        IterDirection,
+        TransactableStorage,
+        WriteDurability,
+        WritePressure,
+    },
+};
+use core::ops::Bound;
+use fuel_core_storage::{
+    iter::{
+        is_start_within_prefix,
+        BoxedIter,
+        IntoBoxedIter,
+        IteratorableStore,
+    },
+    kv_store::{
+        merge_value,
+        KVItem,
+        KeyValueStore,
+        StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Result as StorageResult,
+};
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+        HashMap,
+    },
+    path::Path,
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Arc,
+        Mutex,
+    },
+    time::Duration,
+};
+use tokio::sync::broadcast;
+
+/// The configuration of the [`BufferedStorage`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WriteBufferConfig<Column> {
+    /// The columns whose writes are buffered. Writes of other columns go straight
+    /// to the inner storage, so only columns that can lose their latest writes
+    /// on the crash may be listed here.
+    pub columns: BTreeSet<Column>,
+    /// The size of buffered keys and values in bytes that triggers the flush.
+    pub max_buffered_bytes: usize,
+    /// The interval of the background flush, or `None` to flush only by the size
+    /// and on [`TransactableStorage::flush`].
+    pub flush_interval: Option<Duration>,
+}
+
+/// The buffered entries of columns. The `None` value is the buffered removal.
+#[derive(Debug)]
+struct Buffer<Column> {
+    columns: BTreeMap<Column, BTreeMap<Vec<u8>, Option<Value>>>,
+    /// The size of buffered keys and values in bytes.
+    size: usize,
+}
+
+impl<Column> Default for Buffer<Column> {
+    fn default() -> Self {
+        Self {
+            columns: Default::default(),
+            size: 0,
+        }
+    }
+}
+
+fn entry_size(key: &[u8], value: &Option<Value>) -> usize {
+    key.len()
+        .saturating_add(value.as_ref().map_or(0, |value| value.len()))
+}
+
+/// Returns `true` if both buffered values are the same write.
+fn same_write(left: &Option<Value>, right: &Option<Value>) -> bool {
+    match (left, right) {
+        (Some(left), Some(right)) => Arc::ptr_eq(left, right),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+impl<Column> Buffer<Column>
+where
+    Column: StorageColumn + Ord,
+{
+    fn get(&self, column: Column, key: &[u8]) -> Option<&Option<Value>> {
+        self.columns.get(&column)?.get(key)
+    }
+
+    fn stage(&mut self, column: Column, key: Vec<u8>, value: Option<Value>) {
+        self.size = self.size.saturating_add(entry_size(&key, &value));
+        let entries = self.columns.entry(column).or_default();
+        if let Some(old) = entries.get(&key) {
+            self.size = self.size.saturating_sub(entry_size(&key, old));
+        }
+        entries.insert(key, value);
+    }
+
+    /// Removes the flushed entry unless it was overwritten during the flush.
+    fn remove_flushed(&mut self, column: Column, key: &[u8], flushed: &Option<Value>) {
+        let Some(entries) = self.columns.get_mut(&column) else {
+            return
+        };
+        if entries
+            .get(key)
+            .is_some_and(|current| same_write(current, flushed))
+        {
+            entries.remove(key);
+            self.size = self.size.saturating_sub(entry_size(key, flushed));
+        }
+    }
+
+    fn clear_column(&mut self, column: Column) {
+        if let Some(entries) = self.columns.remove(&column) {
+            let size = entries.iter().fold(0usize, |size, (key, value)| {
+                size.saturating_add(entry_size(key, value))
+            });
+            self.size = self.size.saturating_sub(size);
+        }
+    }
+}
+
+/// The storage that buffers writes of configured columns in memory and writes them
+/// into the inner storage by one batch. The buffer keeps only the latest value
+/// of the key, so the key written many times between flushes is written once.
+///
+/// Reads of buffered columns see buffered writes. The buffer is flushed when its size
+/// exceeds [`WriteBufferConfig::max_buffered_bytes`], by the background thread
+/// started by [`BufferedStorage::spawn_flush`], on [`TransactableStorage::flush`],
+/// before snapshots and checkpoints, and on the drop of the storage.
+///
+/// The batch mixing buffered and other columns loses its atomicity: entries of other
+/// columns are written at once, while buffered entries are written by the flush.
+#[derive(Debug)]
+pub struct BufferedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    inner: DataSourceInner<Description::Column>,
+    columns: BTreeSet<Description::Column>,
+    max_buffered_bytes: usize,
+    buffer: Mutex<Buffer<Description::Column>>,
+    /// Serializes flushes and coarse operations that bypass the buffer.
+    flush_lock: Mutex<()>,
+    /// Set by the [`TransactableStorage::begin_shutdown`]. Writes are refused after it.
+    shutting_down: AtomicBool,
+}
+
+impl<Description> BufferedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Wraps the `inner` storage with the buffer of columns of the `config`.
+    /// The [`WriteBufferConfig::flush_interval`] is applied by [`Self::spawn_flush`].
+    pub fn new<D>(inner: D, config: &WriteBufferConfig<Description::Column>) -> Self
+    where
+        D: Into<DataSource<Description>>,
+    {
+        Self {
+            inner: inner.into().0,
+            columns: config.columns.clone(),
+            max_buffered_bytes: config.max_buffered_bytes,
+            buffer: Default::default(),
+            flush_lock: Mutex::new(()),
+            shutting_down: AtomicBool::new(false),
+        }
+    }
+
+    /// Starts the thread that flushes the buffer every `interval`.
+    /// The thread stops after the drop of the storage.
+    pub fn spawn_flush(storage: &Arc<Self>, interval: Duration) {
+        let storage = Arc::downgrade(storage);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            let Some(storage) = storage.upgrade() else {
+                break
+            };
+            if let Err(err) = storage.flush_buffer(WriteDurability::Buffered) {
+                tracing::warn!(
+                    "Unable to flush the write buffer of the `{}` database: {:?}",
+                    Description::name(),
+                    err
+                );
+            }
+        });
+    }
+
+    /// Returns the size of buffered keys and values in bytes.
+    pub fn buffered_bytes(&self) -> usize {
+        self.buffer().size
+    }
+
+    /// Writes all buffered entries into the inner storage by one batch.
+    pub fn flush_buffer(&self, durability: WriteDurability) -> StorageResult<()> {
+        let _guard = self.flush_lock.lock().expect("poisoned");
+        self.flush_locked(durability)
+    }
+
+    /// Flushes the buffer. The caller holds the `flush_lock`.
+    ///
+    /// Entries stay in the buffer until the inner storage has them, so reads never
+    /// miss the write in flight.
+    fn flush_locked(&self, durability: WriteDurability) -> StorageResult<()> {
+        let flushed = {
+            let buffer = self.buffer();
+            buffer
+                .columns
+                .iter()
+                .flat_map(|(column, entries)| {
+                    entries
+                        .iter()
+                        .map(|(key, value)| (key.clone(), *column, value.clone()))
+                })
+                .collect::<Vec<_>>()
+        };
+        if flushed.is_empty() {
+            return Ok(())
+        }
+
+        self.inner.batch_write_with(
+            &mut flushed.iter().map(|(key, column, value)| {
+                let op = match value {
+                    Some(value) => WriteOperation::Insert(value.clone()),
+                    None => WriteOperation::Remove,
+                };
+                (key.clone(), *column, op)
+            }),
+            durability,
+        )?;
+
+        let mut buffer = self.buffer();
+        for (key, column, value) in flushed {
+            buffer.remove_flushed(column, &key, &value);
+        }
+        Ok(())
+    }
+
+    fn flush_if_full(&self) {
+        if self.buffer().size < self.max_buffered_bytes {
+            return
+        }
+        // The write is already buffered, so it isn't lost if the flush fails.
+        // The buffer is flushed again by the next trigger.
+        if let Err(err) = self.flush_buffer(WriteDurability::Buffered) {
+            tracing::warn!(
+                "Unable to flush the full write buffer of the `{}` database: {:?}",
+                Description::name(),
+                err
+            );
+        }
+    }
+
+    /// Flushes the buffer before the operation that bypasses it.
+    fn flush_before(&self, durability: WriteDurability) -> DatabaseResult<()> {
+        self.flush_buffer(durability).map_err(DatabaseError::from)
+    }
+
+    fn buffer(&self) -> std::sync::MutexGuard<'_, Buffer<Description::Column>> {
+        self.buffer.lock().expect("poisoned")
+    }
+
+    fn is_buffered(&self, column: Description::Column) -> bool {
+        self.columns.contains(&column)
+    }
+
+    /// Refuses the write after the shutdown began. The caller holds the lock
+    /// of the buffer, so the shutdown flushes all writes staged before it.
+    fn ensure_writable(&self) -> StorageResult<()> {
+        if self.shutting_down.load(Ordering::Acquire) {
+            return Err(DatabaseError::ShuttingDown.into())
+        }
+        Ok(())
+    }
+
+    /// Returns the buffered value if the `key` is buffered, or reads the inner storage.
+    fn current(
+        &self,
+        buffer: &Buffer<Description::Column>,
+        key: &[u8],
+        column: Description::Column,
+    ) -> StorageResult<Option<Value>> {
+        match buffer.get(column, key) {
+            Some(value) => Ok(value.clone()),
+            None => self.inner.get(key, column),
+        }
+    }
+
+    /// Buffers the value returned by the `update` of the current value of the `key`.
+    fn stage_update<T>(
+        &self,
+        key: &[u8],
+        column: Description::Column,
+        update: impl FnOnce(Option<Value>) -> StorageResult<(Option<Value>, T)>,
+    ) -> StorageResult<T> {
+        let result = {
+            let mut buffer = self.buffer();
+            self.ensure_writable()?;
+            let current = self.current(&buffer, key, column)?;
+            let (value, result) = update(current)?;
+            buffer.stage(column, key.to_vec(), value);
+            result
+        };
+        self.flush_if_full();
+        Ok(result)
+    }
+
+    /// Returns buffered entries of the `column` in the order of the iteration.
+    fn buffered_range(
+        &self,
+        column: Description::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> Vec<StorageResult<(Vec<u8>, Option<Value>)>> {
+        let buffer = self.buffer();
+        let Some(entries) = buffer.columns.get(&column) else {
+            return vec![]
+        };
+        let prefix = prefix.unwrap_or_default();
+        let in_prefix = |(key, _): &(&Vec<u8>, &Option<Value>)| key.starts_with(prefix);
+        let range: Box<dyn Iterator<Item = (&Vec<u8>, &Option<Value>)>> =
+            match (direction, start) {
+                (IterDirection::Forward, start) => {
+                    let from = start.unwrap_or(prefix);
+                    Box::new(
+                        entries
+                            .range::<[u8], _>((Bound::Included(from), Bound::Unbounded))
+                            .take_while(in_prefix),
+                    )
+                }
+                (IterDirection::Reverse, Some(start)) => Box::new(
+                    entries
+                        .range::<[u8], _>((Bound::Unbounded, Bound::Included(start)))
+                        .rev()
+                        .take_while(in_prefix),
+                ),
+                (IterDirection::Reverse, None) => Box::new(
+                    entries
+                        .range::<[u8], _>((Bound::Included(prefix), Bound::Unbounded))
+                        .rev()
+                        .skip_while(move |entry| !in_prefix(entry))
+                        .take_while(in_prefix),
+                ),
+            };
+        range
+            .map(|(key, value)| Ok((key.clone(), value.clone())))
+            .collect()
+    }
+}
+
+impl<Description> Drop for BufferedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn drop(&mut self) {
+        if let Err(err) = self.flush_buffer(WriteDurability::Sync) {
+            tracing::error!(
+                "Unable to flush the write buffer of the `{}` database on shutdown: {:?}",
+                Description::name(),
+                err
+            );
+        }
+    }
+}
+
+impl<Description> KeyValueStore for BufferedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        if !self.is_buffered(column) {
+            return self.inner.put(key, column, value)
+        }
+        {
+            let mut buffer = self.buffer();
+            self.ensure_writable()?;
+            buffer.stage(column, key.to_vec(), Some(value));
+        }
+        self.flush_if_full();
+        Ok(())
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        if !self.is_buffered(column) {
+            return self.inner.replace(key, column, value)
+        }
+        self.stage_update(key, column, |old| Ok((Some(value), old)))
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        if !self.is_buffered(column) {
+            return self.inner.write(key, column, buf)
+        }
+        self.put(key, column, Arc::new(buf.to_vec()))?;
+        Ok(buf.len())
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        if !self.is_buffered(column) {
+            return self.inner.take(key, column)
+        }
+        self.stage_update(key, column, |old| Ok((None, old)))
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        if !self.is_buffered(column) {
+            return self.inner.delete(key, column)
+        }
+        self.buffer().stage(column, key.to_vec(), None);
+        self.flush_if_full();
+        Ok(())
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        if !self.is_buffered(column) {
+            return self.inner.merge(key, column, operand)
+        }
+        self.stage_update(key, column, |existing| {
+            let value =
+                merge_value(column, existing.as_deref().map(Vec::as_slice), &operand)?;
+            Ok((Some(value), ()))
+        })
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        if self.is_buffered(column) {
+            if let Some(value) = self.buffer().get(column, key) {
+                return Ok(value.is_some())
+            }
+        }
+        self.inner.exists(key, column)
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        if self.is_buffered(column) {
+            if let Some(value) = self.buffer().get(column, key) {
+                return Ok(value.as_ref().map(|value| value.len()))
+            }
+        }
+        self.inner.size_of_value(key, column)
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        if self.is_buffered(column) {
+            if let Some(value) = self.buffer().get(column, key) {
+                return Ok(value.clone())
+            }
+        }
+        self.inner.get(key, column)
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        if !self.is_buffered(column) {
+            return self.inner.get_with(key, column, f)
+        }
+        Ok(self.get(key, column)?.map(|value| f(&value)).is_some())
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        if !self.is_buffered(column) {
+            return self.inner.multi_get(keys, column)
+        }
+        keys.iter().map(|key| self.get(key, column)).collect()
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        if !self.is_buffered(column) {
+            return self.inner.compare_and_swap(key, column, expected, new)
+        }
+        self.stage_update(key, column, |current| {
+            if current.as_deref().map(Vec::as_slice) == expected {
+                Ok((Some(new), Ok(())))
+            } else {
+                Ok((current.clone(), Err(current)))
+            }
+        })
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        if !self.is_buffered(column) {
+            return self.inner.update(key, column, f)
+        }
+        self.stage_update(key, column, |current| {
+            let new = f(current.as_deref().map(Vec::as_slice));
+            let outcome =
+                UpdateOutcome::of(current.as_deref().map(Vec::as_slice), new.as_deref());
+            match outcome.is_changed() {
+                true => Ok((new.map(Arc::new), outcome)),
+                false => Ok((current, outcome)),
+            }
+        })
+    }
+}
+
+impl<Description> IteratorableStore for BufferedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        if !self.is_buffered(column) || !is_start_within_prefix(prefix, start) {
+            return self.inner.iter_all(column, prefix, start, direction)
+        }
+        let buffered = self.buffered_range(column, prefix, start, direction);
+        if buffered.is_empty() {
+            return self.inner.iter_all(column, prefix, start, direction)
+        }
+
+        let persisted = self
+            .inner
+            .iter_all(column, prefix, start, direction)
+            .map(|item| item.map(|(key, value)| (key, Some(value))))
+            .into_boxed();
+        merge_tiers(
+            buffered.into_iter().into_boxed(),
+            persisted,
+            direction,
+            |(key, _)| key.as_slice(),
+        )
+        .filter_map(|item| match item {
+            Ok((key, Some(value))) => Some(Ok((key, value))),
+            // Buffered removals hide persisted entries.
+            Ok((_, None)) => None,
+            Err(err) => Some(Err(err)),
+        })
+        .into_boxed()
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        if !self.is_buffered(column) {
+            return self.inner.delete_all(column, prefix)
+        }
+        let _guard = self.flush_lock.lock().expect("poisoned");
+        self.flush_locked(WriteDurability::Buffered)?;
+        self.inner.delete_all(column, prefix)
+    }
+}
+
+impl<Description> BatchOperations for BufferedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for BufferedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        let mut direct = Vec::new();
+        let mut buffered = Vec::new();
+        for (key, column, op) in entries {
+            match self.is_buffered(column) {
+                true => buffered.push((key, column, op)),
+                false => direct.push((key, column, op)),
+            }
+        }
+
+        {
+            let mut buffer = self.buffer();
+            self.ensure_writable()?;
+            // Merges are resolved before any write, so the failed merge writes nothing.
+            // The batch may merge the key written earlier in the same batch, so its
+            // latest values are tracked.
+            let mut latest = HashMap::<(usize, Vec<u8>), Option<Value>>::new();
+            let mut staged = Vec::with_capacity(buffered.len());
+            for (key, column, op) in buffered {
+                let value = match op {
+                    WriteOperation::Insert(value) => Some(value),
+                    WriteOperation::Remove => None,
+                    WriteOperation::Merge(operand) => {
+                        let existing = match latest.get(&(column.as_usize(), key.clone()))
+                        {
+                            Some(existing) => existing.clone(),
+                            None => self.current(&buffer, &key, column)?,
+                        };
+                        Some(merge_value(
+                            column,
+                            existing.as_deref().map(Vec::as_slice),
+                            &operand,
+                        )?)
+                    }
+                };
+                if column.merge_operator().is_some() {
+                    latest.insert((column.as_usize(), key.clone()), value.clone());
+                }
+                staged.push((key, column, value));
+            }
+
+            if !direct.is_empty() {
+                self.inner
+                    .batch_write_with(&mut direct.into_iter(), durability)?;
+            }
+            for (key, column, value) in staged {
+                buffer.stage(column, key, value);
+            }
+        }
+
+        match durability {
+            WriteDurability::Sync => self.flush_buffer(durability),
+            WriteDurability::Buffered => {
+                self.flush_if_full();
+                Ok(())
+            }
+        }
+    }
+
+    fn iter_column(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> ColumnIter<'_> {
+        match self.is_buffered(column) {
+            true => ColumnIter::boxed(self.iter_all(column, prefix, start, direction)),
+            false => self.inner.iter_column(column, prefix, start, direction),
+        }
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        self.flush_before(WriteDurability::Buffered)?;
+        self.inner.flush()
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        self.inner.column_stats(column)
+    }
+
+    fn engine_stats(&self) -> DatabaseResult<EngineStats> {
+        self.inner.engine_stats()
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        self.inner.write_pressure()
+    }
+
+    fn size_of_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> DatabaseResult<u64> {
+        self.inner.size_of_range(column, start, end)
+    }
+
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        self.flush_before(WriteDurability::Sync)?;
+        self.inner.checkpoint(path)
+    }
+
+    fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()> {
+        self.flush_before(WriteDurability::Sync)?;
+        self.inner.backup_to(backup_dir)
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        self.inner.compact(column, range)
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        let _guard = self.flush_lock.lock().expect("poisoned");
+        self.buffer().clear_column(column);
+        self.inner.clear_column(column)
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        // The snapshot of the inner storage must contain buffered writes.
+        self.flush_before(WriteDurability::Buffered)?;
+        self.inner.snapshot()
+    }
+
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        self.inner.subscribe(column, prefix)
+    }
+
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.flush_before(WriteDurability::Buffered)?;
+        self.inner.replay_change_log(from_seq, into)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.inner.truncate_change_log(seq)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        self.inner.freeze_columns(columns)
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        self.inner.unfreeze_columns(columns)
+    }
+
+    fn begin_shutdown(&self) {
+        // Writes staged before the flag are flushed into the inner storage
+        // before it starts refusing them.
+        {
+            let _buffer = self.buffer();
+            self.shutting_down.store(true, Ordering::Release);
+        }
+        if let Err(err) = self.flush_buffer(WriteDurability::Buffered) {
+            tracing::error!(
+                "Unable to flush the write buffer of the `{}` database on shutdown: {:?}",
+                Description::name(),
+                err
+            );
+        }
+        self.inner.begin_shutdown()
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        self.flush_before(WriteDurability::Sync)?;
+        self.inner.finish_shutdown()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::on_chain::OnChain,
+        state::{
+            faulty::FaultyStorage,
+            in_memory::memory_store::MemoryStore,
+        },
+    };
+    use fuel_core_storage::column::Column;
+
+    const BUFFERED: Column = Column::Coins;
+    const DIRECT: Column = Column::Transactions;
+
+    fn buffered(
+        max_buffered_bytes: usize,
+    ) -> (Arc<FaultyStorage<OnChain>>, Arc<BufferedStorage<OnChain>>) {
+        let inner = Arc::new(FaultyStorage::new(Arc::new(MemoryStore::default())));
+        let config = WriteBufferConfig {
+            columns: [BUFFERED].into_iter().collect(),
+            max_buffered_bytes,
+            flush_interval: None,
+        };
+        let store = Arc::new(BufferedStorage::new(inner.clone(), &config));
+        (inner, store)
+    }
+
+    fn value(byte: u8) -> Value {
+        Arc::new(vec![byte])
+    }
+
+    #[test]
+    fn repeated_writes_of_the_key_are_written_once() {
+        // Given
+        let (inner, store) = buffered(usize::MAX);
+        let before = inner.operations();
+
+        // When
+        for i in 0..100 {
+            store.put(&[1], BUFFERED, value(i)).unwrap();
+            store.put(&[2], DIRECT, value(i)).unwrap();
+        }
+        let writes_before_flush = inner.operations().saturating_sub(before);
+        store.flush_buffer(WriteDurability::Buffered).unwrap();
+
+        // Then
+        let writes = inner.operations().saturating_sub(before);
+        // All writes of the direct column reach the inner storage, while the buffered
+        // column is written by one batch of the flush.
+        assert_eq!(writes_before_flush, 100);
+        assert_eq!(writes, 101);
+        assert_eq!(inner.get(&[1], BUFFERED).unwrap(), Some(value(99)));
+        assert_eq!(inner.get(&[2], DIRECT).unwrap(), Some(value(99)));
+        assert_eq!(store.buffered_bytes(), 0);
+    }
+
+    #[test]
+    fn reads_see_buffered_writes() {
+        // Given
+        let (inner, store) = buffered(usize::MAX);
+        inner.put(&[1], BUFFERED, value(1)).unwrap();
+        inner.put(&[2], BUFFERED, value(2)).unwrap();
+
+        // When
+        store.put(&[1], BUFFERED, value(10)).unwrap();
+        store.delete(&[2], BUFFERED).unwrap();
+
+        // Then
+        assert_eq!(store.get(&[1], BUFFERED).unwrap(), Some(value(10)));
+        assert_eq!(store.get(&[2], BUFFERED).unwrap(), None);
+        assert!(!store.exists(&[2], BUFFERED).unwrap());
+        assert_eq!(store.size_of_value(&[1], BUFFERED).unwrap(), Some(1));
+        assert_eq!(
+            store.replace(&[1], BUFFERED, value(11)).unwrap(),
+            Some(value(10))
+        );
+        assert_eq!(
+            store
+                .compare_and_swap(&[1], BUFFERED, Some(&[11]), value(12))
+                .unwrap(),
+            Ok(())
+        );
+        assert_eq!(inner.get(&[1], BUFFERED).unwrap(), Some(value(1)));
+        assert_eq!(inner.get(&[2], BUFFERED).unwrap(), Some(value(2)));
+    }
+
+    #[test]
+    fn iter_all_merges_buffered_and_persisted_entries() {
+        // Given
+        let (inner, store) = buffered(usize::MAX);
+        for key in [1u8, 3, 5] {
+            inner.put(&[0, key], BUFFERED, value(key)).unwrap();
+        }
+        inner.put(&[1, 0], BUFFERED, value(0)).unwrap();
+
+        // When
+        store.put(&[0, 2], BUFFERED, value(20)).unwrap();
+        store.put(&[0, 3], BUFFERED, value(30)).unwrap();
+        store.delete(&[0, 5], BUFFERED).unwrap();
+        store.put(&[1, 1], BUFFERED, value(11)).unwrap();
+
+        // Then
+        let collect = |start: Option<&[u8]>, direction| {
+            store
+                .iter_all(BUFFERED, Some(&[0]), start, direction)
+                .map(|item| item.map(|(key, value)| (key, value[0])))
+                .collect::<StorageResult<Vec<_>>>()
+                .unwrap()
+        };
+        let forward = vec![(vec![0, 1], 1), (vec![0, 2], 20), (vec![0, 3], 30)];
+        assert_eq!(collect(None, IterDirection::Forward), forward);
+        let reverse = forward.iter().rev().cloned().collect::<Vec<_>>();
+        assert_eq!(collect(None, IterDirection::Reverse), reverse);
+        assert_eq!(
+            collect(Some(&[0, 2]), IterDirection::Reverse),
+            reverse[1..].to_vec()
+        );
+        assert_eq!(
+            collect(Some(&[0, 2]), IterDirection::Forward),
+            forward[1..].to_vec()
+        );
+    }
+
+    #[test]
+    fn full_buffer_is_flushed() {
+        // Given
+        let (inner, store) = buffered(10);
+
+        // When
+        for key in 0..4u8 {
+            store.put(&[key], BUFFERED, value(key)).unwrap();
+        }
+        store.put(&[4], BUFFERED, Arc::new(vec![4; 8])).unwrap();
+
+        // Then
+        assert_eq!(store.buffered_bytes(), 0);
+        for key in 0..4u8 {
+            assert_eq!(inner.get(&[key], BUFFERED).unwrap(), Some(value(key)));
+        }
+    }
+
+    #[test]
+    fn failed_flush_keeps_buffered_writes() {
+        // Given
+        let (inner, store) = buffered(usize::MAX);
+        store.put(&[1], BUFFERED, value(1)).unwrap();
+        inner.fail_batch_write();
+
+        // When
+        store.flush_buffer(WriteDurability::Buffered).unwrap_err();
+
+        // Then
+        assert_eq!(store.get(&[1], BUFFERED).unwrap(), Some(value(1)));
+        inner.reset();
+        store.flush_buffer(WriteDurability::Buffered).unwrap();
+        assert_eq!(inner.get(&[1], BUFFERED).unwrap(), Some(value(1)));
+    }
+
+    #[test]
+    fn background_thread_flushes_the_buffer() {
+        // Given
+        let (inner, store) = buffered(usize::MAX);
+        store.put(&[1], BUFFERED, value(1)).unwrap();
+
+        // When
+        BufferedStorage::spawn_flush(&store, Duration::from_millis(10));
+
+        // Then
+        let flushed = (0..200).any(|_| {
+            std::thread::sleep(Duration::from_millis(10));
+            inner.exists(&[1], BUFFERED).unwrap()
+        });
+        assert!(flushed);
+    }
+
+    #[test]
+    fn drop_of_the_storage_flushes_the_buffer() {
+        // Given
+        let (inner, store) = buffered(usize::MAX);
+        store.put(&[1], BUFFERED, value(1)).unwrap();
+        store.delete(&[2], BUFFERED).unwrap();
+        inner.put(&[2], BUFFERED, value(2)).unwrap();
+
+        // When
+        drop(store);
+
+        // Then
+        assert_eq!(inner.get(&[1], BUFFERED).unwrap(), Some(value(1)));
+        assert_eq!(inner.get(&[2], BUFFERED).unwrap(), None);
+    }
+}
@@ -0,0 +1,848 @@
+//! The read-through cache of values of the inner storage, see [`CachedStorage`].
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Result as DatabaseResult,
+    },
+    state::{
+        column_iter::ColumnIter,
+        notifying::StorageEvent,
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        EngineStats,
+        IterDirection,
+        TransactableStorage,
+        WriteDurability,
+        WritePressure,
+    },
+};
+use fuel_core_metrics::storage_metrics::{
+    storage_metrics,
+    DatabaseLabels,
+};
+use fuel_core_storage::{
+    iter::{
+        BoxedIter,
+        IterConfig,
+        IteratorableStore,
+        PageIter,
+    },
+    kv_store::{
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Result as StorageResult,
+};
+use prometheus_client::metrics::counter::Counter;
+use std::{
+    collections::{
+        BTreeMap,
+        HashMap,
+    },
+    path::Path,
+    sync::Mutex,
+};
+use tokio::sync::broadcast;
+
+/// The key of the cached entry: the index of the column and the key of the entry.
+type CacheKey = (usize, Vec<u8>);
+
+/// The statistics of the [`CachedStorage`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of reads served by the cache.
+    pub hits: u64,
+    /// The number of reads that went to the inner storage.
+    pub misses: u64,
+    /// The number of cached entries.
+    pub entries: usize,
+    /// The size of cached keys and values in bytes.
+    pub size: usize,
+}
+
+#[derive(Debug)]
+struct CachedEntry {
+    /// The `None` caches the absence of the entry.
+    value: Option<Value>,
+    /// The time of the last use, the key of the entry in the [`Lru::order`].
+    used: u64,
+}
+
+impl CachedEntry {
+    fn size(key: &[u8], value: &Option<Value>) -> usize {
+        key.len()
+            .saturating_add(value.as_ref().map_or(0, |value| value.len()))
+    }
+}
+
+/// The least recently used entries bounded by their size in bytes.
+#[derive(Debug, Default)]
+struct Lru {
+    entries: HashMap<CacheKey, CachedEntry>,
+    /// Keys of entries by the time of their last use, the oldest first.
+    order: BTreeMap<u64, CacheKey>,
+    clock: u64,
+    size: usize,
+    capacity: usize,
+    /// Increments on each invalidation, so the read that started before the write
+    /// doesn't cache the value that the write replaced.
+    generation: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl Lru {
+    fn tick(&mut self) -> u64 {
+        self.clock = self.clock.wrapping_add(1);
+        self.clock
+    }
+
+    fn get(&mut self, key: &CacheKey) -> Option<Option<Value>> {
+        let used = self.tick();
+        let Some(entry) = self.entries.get_mut(key) else {
+            self.misses = self.misses.saturating_add(1);
+            return None
+        };
+        self.hits = self.hits.saturating_add(1);
+        let key = self
+            .order
+            .remove(&entry.used)
+            .expect("The entry is ordered");
+        entry.used = used;
+        self.order.insert(used, key);
+        Some(entry.value.clone())
+    }
+
+    /// Caches the `value` read by the read that started at the `generation`.
+    fn insert(&mut self, key: CacheKey, value: Option<Value>, generation: u64) {
+        let size = CachedEntry::size(&key.1, &value);
+        if generation != self.generation || size > self.capacity {
+            return
+        }
+        self.remove(&key);
+        while self.size.saturating_add(size) > self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break
+            };
+            self.remove_entry(&oldest);
+        }
+        let used = self.tick();
+        self.size = self.size.saturating_add(size);
+        self.order.insert(used, key.clone());
+        self.entries.insert(key, CachedEntry { value, used });
+    }
+
+    fn remove_entry(&mut self, key: &CacheKey) -> Option<CachedEntry> {
+        let entry = self.entries.remove(key)?;
+        self.size = self
+            .size
+            .saturating_sub(CachedEntry::size(&key.1, &entry.value));
+        Some(entry)
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.remove_entry(key) {
+            self.order.remove(&entry.used);
+        }
+    }
+
+    fn invalidate<'a>(&mut self, keys: impl Iterator<Item = &'a CacheKey>) {
+        self.generation = self.generation.wrapping_add(1);
+        for key in keys {
+            self.remove(key);
+        }
+    }
+
+    /// Removes entries of the `column` with the `prefix`.
+    fn invalidate_prefix(&mut self, column: usize, prefix: &[u8]) {
+        let keys = self
+            .entries
+            .keys()
+            .filter(|(entry_column, key)| {
+                *entry_column == column && key.starts_with(prefix)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        self.invalidate(keys.iter());
+    }
+}
+
+/// The storage that caches values read from the inner storage in the LRU bounded
+/// by the size of keys and values. Absent entries are cached too, so repeated
+/// checks of missing keys don't reach the inner storage.
+///
+/// Every write through the storage invalidates entries of its keys after the inner
+/// storage applies it, so the read after the write never returns the old value.
+/// Writes into the inner storage that bypass the cache aren't seen by it, so the cache
+/// must be the outermost layer that writes go through, e.g. below the
+/// [`MemoryTransactionView`](crate::state::in_memory::transaction::MemoryTransactionView)
+/// whose commits it invalidates. Iterators read the inner storage directly.
+#[derive(Debug)]
+pub struct CachedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    inner: DataSourceInner<Description::Column>,
+    lru: Mutex<Lru>,
+    hits: Counter,
+    misses: Counter,
+}
+
+impl<Description> CachedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Wraps the `inner` storage with the cache of up to `capacity` bytes.
+    pub fn new<D>(inner: D, capacity: usize) -> Self
+    where
+        D: Into<DataSource<Description>>,
+    {
+        let labels = DatabaseLabels {
+            database: Description::name(),
+        };
+        let metrics = storage_metrics();
+        Self {
+            inner: inner.into().0,
+            lru: Mutex::new(Lru {
+                capacity,
+                ..Default::default()
+            }),
+            hits: metrics.cache_hits.get_or_create(&labels).clone(),
+            misses: metrics.cache_misses.get_or_create(&labels).clone(),
+        }
+    }
+
+    /// Returns the statistics of the cache.
+    pub fn stats(&self) -> CacheStats {
+        let lru = self.lru();
+        CacheStats {
+            hits: lru.hits,
+            misses: lru.misses,
+            entries: lru.entries.len(),
+            size: lru.size,
+        }
+    }
+
+    fn lru(&self) -> std::sync::MutexGuard<'_, Lru> {
+        self.lru.lock().expect("poisoned")
+    }
+
+    fn cache_key(key: &[u8], column: Description::Column) -> CacheKey {
+        (column.as_usize(), key.to_vec())
+    }
+
+    /// Returns the cached value of the `key`, or the generation of the cache to insert
+    /// the value read from the inner storage.
+    fn lookup(&self, key: &CacheKey) -> Result<Option<Value>, u64> {
+        let mut lru = self.lru();
+        match lru.get(key) {
+            Some(value) => {
+                self.hits.inc();
+                Ok(value)
+            }
+            None => {
+                self.misses.inc();
+                Err(lru.generation)
+            }
+        }
+    }
+
+    /// Runs the `write` and invalidates the `key` after it, even if the write fails.
+    fn write_key<T>(
+        &self,
+        key: &[u8],
+        column: Description::Column,
+        write: impl FnOnce() -> StorageResult<T>,
+    ) -> StorageResult<T> {
+        let result = write();
+        self.lru()
+            .invalidate(core::iter::once(&Self::cache_key(key, column)));
+        result
+    }
+
+    fn invalidate_column(&self, column: Description::Column, prefix: Option<&[u8]>) {
+        self.lru()
+            .invalidate_prefix(column.as_usize(), prefix.unwrap_or_default());
+    }
+}
+
+impl<Description> KeyValueStore for CachedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        self.write_key(key, column, || self.inner.put(key, column, value))
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        self.write_key(key, column, || self.inner.replace(key, column, value))
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        self.write_key(key, column, || self.inner.write(key, column, buf))
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.write_key(key, column, || self.inner.take(key, column))
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        self.write_key(key, column, || self.inner.delete(key, column))
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        self.write_key(key, column, || self.inner.merge(key, column, operand))
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        match self.lru().entries.get(&Self::cache_key(key, column)) {
+            Some(entry) => Ok(entry.value.is_some()),
+            None => self.inner.exists(key, column),
+        }
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        match self.lru().entries.get(&Self::cache_key(key, column)) {
+            Some(entry) => Ok(entry.value.as_ref().map(|value| value.len())),
+            None => self.inner.size_of_value(key, column),
+        }
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        let cache_key = Self::cache_key(key, column);
+        let generation = match self.lookup(&cache_key) {
+            Ok(value) => return Ok(value),
+            Err(generation) => generation,
+        };
+        let value = self.inner.get(key, column)?;
+        self.lru().insert(cache_key, value.clone(), generation);
+        Ok(value)
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        Ok(self.get(key, column)?.map(|value| f(&value)).is_some())
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        let mut values = Vec::with_capacity(keys.len());
+        let mut missed = Vec::new();
+        let mut generation = None;
+        for (index, key) in keys.iter().enumerate() {
+            match self.lookup(&Self::cache_key(key, column)) {
+                Ok(value) => values.push(value),
+                Err(current) => {
+                    // The first miss is the earliest generation, so it covers all misses.
+                    generation.get_or_insert(current);
+                    values.push(None);
+                    missed.push(index);
+                }
+            }
+        }
+        let Some(generation) = generation else {
+            return Ok(values)
+        };
+
+        let missed_keys = missed
+            .iter()
+            .map(|index| keys[*index].clone())
+            .collect::<Vec<_>>();
+        let fetched = self.inner.multi_get(&missed_keys, column)?;
+        let mut lru = self.lru();
+        for (index, value) in missed.into_iter().zip(fetched) {
+            lru.insert(
+                Self::cache_key(&keys[index], column),
+                value.clone(),
+                generation,
+            );
+            values[index] = value;
+        }
+        Ok(values)
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        self.write_key(key, column, || {
+            self.inner.compare_and_swap(key, column, expected, new)
+        })
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        self.write_key(key, column, || self.inner.update(key, column, f))
+    }
+}
+
+impl<Description> IteratorableStore for CachedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.inner.iter_all(column, prefix, start, direction)
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.inner.iter_range(column, prefix, start, end, direction)
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.inner.iter_keys(column, prefix, start, direction)
+    }
+
+    fn has_any(&self, column: Self::Column, prefix: &[u8]) -> StorageResult<bool> {
+        self.inner.has_any(column, prefix)
+    }
+
+    fn iter(&self, column: Self::Column, config: IterConfig) -> PageIter {
+        self.inner.iter(column, config)
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        let result = self.inner.delete_all(column, prefix);
+        self.invalidate_column(column, prefix);
+        result
+    }
+}
+
+impl<Description> BatchOperations for CachedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for CachedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        let mut written = Vec::new();
+        let result = self.inner.batch_write_with(
+            &mut entries
+                .inspect(|(key, column, _)| written.push(Self::cache_key(key, *column))),
+            durability,
+        );
+        self.lru().invalidate(written.iter());
+        result
+    }
+
+    fn iter_column(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> ColumnIter<'_> {
+        self.inner.iter_column(column, prefix, start, direction)
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        self.inner.flush()
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        self.inner.column_stats(column)
+    }
+
+    fn engine_stats(&self) -> DatabaseResult<EngineStats> {
+        self.inner.engine_stats()
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        self.inner.write_pressure()
+    }
+
+    fn size_of_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> DatabaseResult<u64> {
+        self.inner.size_of_range(column, start, end)
+    }
+
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        self.inner.checkpoint(path)
+    }
+
+    fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()> {
+        self.inner.backup_to(backup_dir)
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        self.inner.compact(column, range)
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        let result = self.inner.clear_column(column);
+        self.invalidate_column(column, None);
+        result
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        // The snapshot is frozen, so it doesn't need the invalidation of the cache.
+        self.inner.snapshot()
+    }
+
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        self.inner.subscribe(column, prefix)
+    }
+
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.inner.replay_change_log(from_seq, into)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.inner.truncate_change_log(seq)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        self.inner.freeze_columns(columns)
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        self.inner.unfreeze_columns(columns)
+    }
+
+    fn begin_shutdown(&self) {
+        self.inner.begin_shutdown()
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        self.inner.finish_shutdown()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::on_chain::OnChain,
+        state::{
+            in_memory::{
+                memory_store::MemoryStore,
+                transaction::MemoryTransactionView,
+            },
+            test_helpers::over_memory_store,
+        },
+    };
+    use fuel_core_storage::column::Column;
+    use std::sync::Arc;
+
+    fn cached(
+        capacity: usize,
+    ) -> (Arc<MemoryStore<OnChain>>, Arc<CachedStorage<OnChain>>) {
+        over_memory_store(|inner| Arc::new(CachedStorage::new(inner, capacity)))
+    }
+
+    fn value(byte: u8, len: usize) -> Value {
+        Arc::new(vec![byte; len])
+    }
+
+    #[test]
+    fn repeated_reads_are_served_by_the_cache() {
+        // Given
+        let (inner, store) = cached(1024);
+        inner.put(&[1], Column::Coins, value(1, 10)).unwrap();
+
+        // When
+        let first = store.get(&[1], Column::Coins).unwrap();
+        // The change that bypasses the cache shows that the cached value is returned.
+        inner.put(&[1], Column::Coins, value(2, 10)).unwrap();
+        let second = store.get(&[1], Column::Coins).unwrap();
+        let missing = [
+            store.get(&[2], Column::Coins).unwrap(),
+            store.get(&[2], Column::Coins).unwrap(),
+        ];
+
+        // Then
+        assert_eq!(first, Some(value(1, 10)));
+        assert_eq!(second, first);
+        assert_eq!(missing, [None, None]);
+        let stats = store.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 2));
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.size, 12);
+    }
+
+    #[test]
+    fn writes_invalidate_cached_values() {
+        // Given
+        let (_, store) = cached(1024);
+        store.put(&[1], Column::Coins, value(1, 10)).unwrap();
+        store.put(&[2], Column::Coins, value(1, 10)).unwrap();
+        store
+            .multi_get(&[vec![1], vec![2], vec![3]], Column::Coins)
+            .unwrap();
+
+        // When
+        store.put(&[1], Column::Coins, value(2, 10)).unwrap();
+        store
+            .batch_write(
+                &mut vec![
+                    (vec![2], Column::Coins, WriteOperation::Remove),
+                    (vec![3], Column::Coins, WriteOperation::Insert(value(3, 1))),
+                ]
+                .into_iter(),
+            )
+            .unwrap();
+
+        // Then
+        assert_eq!(
+            store
+                .multi_get(&[vec![1], vec![2], vec![3]], Column::Coins)
+                .unwrap(),
+            vec![Some(value(2, 10)), None, Some(value(3, 1))]
+        );
+    }
+
+    #[test]
+    fn commits_of_transaction_view_invalidate_cached_values() {
+        // Given
+        let (_, store) = cached(1024);
+        store.put(&[1], Column::Coins, value(1, 10)).unwrap();
+        assert_eq!(store.get(&[1], Column::Coins).unwrap(), Some(value(1, 10)));
+        let view = MemoryTransactionView::<OnChain>::new(store.clone());
+        view.put(&[1], Column::Coins, value(2, 10)).unwrap();
+
+        // When
+        view.commit().unwrap();
+
+        // Then
+        assert_eq!(store.get(&[1], Column::Coins).unwrap(), Some(value(2, 10)));
+    }
+
+    #[test]
+    fn coarse_removals_invalidate_the_column() {
+        // Given
+        let (_, store) = cached(1024);
+        for key in [[1, 1], [1, 2], [2, 1]] {
+            store.put(&key, Column::Coins, value(1, 1)).unwrap();
+            store.put(&key, Column::Metadata, value(1, 1)).unwrap();
+            store.get(&key, Column::Coins).unwrap();
+            store.get(&key, Column::Metadata).unwrap();
+        }
+
+        // When
+        store.delete_all(Column::Coins, Some(&[1])).unwrap();
+        store.clear_column(Column::Metadata).unwrap();
+
+        // Then
+        assert_eq!(store.get(&[1, 1], Column::Coins).unwrap(), None);
+        assert_eq!(
+            store.get(&[2, 1], Column::Coins).unwrap(),
+            Some(value(1, 1))
+        );
+        assert!(!store.exists(&[2, 1], Column::Metadata).unwrap());
+    }
+
+    #[test]
+    fn least_recently_used_entries_are_evicted_by_size() {
+        // Given
+        let (inner, store) = cached(25);
+        for key in 1..=3u8 {
+            inner.put(&[key], Column::Coins, value(key, 9)).unwrap();
+        }
+        store.get(&[1], Column::Coins).unwrap();
+        store.get(&[2], Column::Coins).unwrap();
+        store.get(&[1], Column::Coins).unwrap();
+
+        // When
+        store.get(&[3], Column::Coins).unwrap();
+
+        // Then
+        let stats = store.stats();
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.size, 20);
+        let misses = stats.misses;
+        store.get(&[1], Column::Coins).unwrap();
+        assert_eq!(store.stats().misses, misses);
+        store.get(&[2], Column::Coins).unwrap();
+        assert_eq!(store.stats().misses, misses + 1);
+    }
+
+    #[test]
+    fn concurrent_reads_never_see_values_older_than_the_last_write() {
+        // Given
+        const WRITERS: u8 = 4;
+        const VERSIONS: u8 = 200;
+        let (inner, store) = cached(64);
+        let keys = (0..WRITERS).map(|key| vec![key]).collect::<Vec<_>>();
+
+        // When
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                let store = store.clone();
+                let keys = keys.clone();
+                scope.spawn(move || {
+                    for _ in 0..2_000 {
+                        store.multi_get(&keys, Column::Coins).unwrap();
+                        for key in keys.iter() {
+                            store.get(key, Column::Coins).unwrap();
+                        }
+                    }
+                });
+            }
+            for key in keys.iter() {
+                let store = store.clone();
+                scope.spawn(move || {
+                    for version in 0..VERSIONS {
+                        if version % 2 == 0 {
+                            store.put(key, Column::Coins, value(version, 1)).unwrap();
+                        } else {
+                            store
+                                .batch_write(
+                                    &mut vec![(
+                                        key.clone(),
+                                        Column::Coins,
+                                        WriteOperation::Insert(value(version, 1)),
+                                    )]
+                                    .into_iter(),
+                                )
+                                .unwrap();
+                        }
+                        // Then
+                        assert_eq!(
+                            store.get(key, Column::Coins).unwrap(),
+                            Some(value(version, 1))
+                        );
+                    }
+                });
+            }
+        });
+
+        // Then
+        for key in keys.iter() {
+            assert_eq!(
+                store.get(key, Column::Coins).unwrap(),
+                inner.get(key, Column::Coins).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn concurrent_updates_do_not_lose_increments() {
+        // Given
+        let (inner, store) = cached(1024);
+        let barrier = std::sync::Barrier::new(8);
+
+        // When
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    barrier.wait();
+                    for _ in 0..100 {
+                        store
+                            .update(&[0xA], Column::Coins, &mut |current| {
+                                let current = current.map_or(0, |current| {
+                                    u64::from_be_bytes(current.try_into().unwrap())
+                                });
+                                Some(current.saturating_add(1).to_be_bytes().to_vec())
+                            })
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        // Then
+        let stored = store.get(&[0xA], Column::Coins).unwrap().unwrap();
+        assert_eq!(stored.as_slice(), 800u64.to_be_bytes());
+        assert_eq!(inner.get(&[0xA], Column::Coins).unwrap(), Some(stored));
+    }
+}
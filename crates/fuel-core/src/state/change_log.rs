@@ -0,0 +1,1165 @@
+//! The storage wrapper that records committed writes into the change log, so another
+//! database is brought up to date by replaying the log, see [`ChangeLogStorage::replay`].
+//!
+//! Each successful write of the inner storage appends one [`ChangeLogEntry`] with the next
+//! sequence number. The commit of the transaction is one batch, so it is one entry,
+//! and the replay applies the same batches in the same order. Columns are recorded by their
+//! [`StorageColumn::id`], so the log stays readable by other versions of the node.
+//!
+//! The log is kept in the column of the inner storage or in the append-only file,
+//! see [`ChangeLogTarget`]. The entry in the column is written in the same batch as the
+//! changes, so the log never misses the committed write. The entry of the file is appended
+//! after the write, so the crash in between loses the entry of the last write.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::{
+        notifying::StorageEvent,
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        EngineStats,
+        IterDirection,
+        ReadView,
+        TransactableStorage,
+        WriteDurability,
+        WritePressure,
+    },
+};
+use fuel_core_storage::{
+    codec::{
+        postcard::Postcard,
+        Decode,
+        Encode,
+    },
+    iter::{
+        BoxedIter,
+        IteratorableStore,
+    },
+    kv_store::{
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Error as StorageError,
+    Result as StorageResult,
+};
+use std::{
+    fs::{
+        File,
+        OpenOptions,
+    },
+    io::{
+        BufRead,
+        BufReader,
+        BufWriter,
+        Read,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::{
+        Arc,
+        Mutex,
+        MutexGuard,
+    },
+};
+use tokio::sync::broadcast;
+
+/// The write of the `key` of the column with the `column` id.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ChangeLogOp {
+    /// The [`StorageColumn::id`] of the column.
+    pub column: u32,
+    /// The key of the entry.
+    pub key: Vec<u8>,
+    /// The write of the entry.
+    pub op: WriteOperation,
+}
+
+/// The writes of one committed batch.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ChangeLogEntry {
+    /// The sequence number of the batch. Sequence numbers start from zero and have no gaps.
+    pub seq: u64,
+    /// The writes of the batch in the order of the batch.
+    pub ops: Vec<ChangeLogOp>,
+}
+
+/// The place where the change log is kept.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChangeLogTarget<Column> {
+    /// Entries are stored in the column of the inner storage by the big-endian sequence
+    /// number. Writes into the column itself are not recorded.
+    Column(Column),
+    /// Entries are appended to the file. Each record is the big-endian `u32` length
+    /// followed by the entry encoded by the [`Postcard`].
+    File(PathBuf),
+}
+
+/// The configuration of the change log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChangeLogConfig<Column> {
+    /// The place where entries are kept.
+    pub target: ChangeLogTarget<Column>,
+    /// The number of the latest entries kept by the log, or `None` to keep all entries.
+    /// The column drops older entries on each write. The file is rewritten once it holds
+    /// twice the retention, so the file doesn't grow without bounds.
+    pub retention: Option<u64>,
+}
+
+#[derive(Debug)]
+struct LogState {
+    /// The sequence number of the oldest kept entry.
+    first_seq: u64,
+    /// The sequence number of the next entry.
+    next_seq: u64,
+    /// The append handle of the [`ChangeLogTarget::File`].
+    file: Option<File>,
+}
+
+/// The storage that delegates to the inner storage and records successful writes
+/// into the change log. The failed write records nothing.
+#[derive(Debug)]
+pub struct ChangeLogStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    inner: DataSourceInner<Description::Column>,
+    target: ChangeLogTarget<Description::Column>,
+    retention: Option<u64>,
+    /// Writes are serialized with their entries, so entries follow the order of writes.
+    log: Mutex<LogState>,
+}
+
+fn io_error(err: std::io::Error) -> StorageError {
+    StorageError::Other(anyhow::anyhow!("Unable to access the change log: {err}"))
+}
+
+fn decode(bytes: &[u8]) -> StorageResult<ChangeLogEntry> {
+    Postcard::decode(bytes).map_err(|e| {
+        StorageError::Other(anyhow::anyhow!("The change log entry is corrupted: {e}"))
+    })
+}
+
+fn seq_of_key(key: &[u8]) -> StorageResult<u64> {
+    let bytes = key.try_into().map_err(|_| {
+        StorageError::Other(anyhow::anyhow!(
+            "The change log key {} is not the sequence number",
+            hex::encode(key)
+        ))
+    })?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+/// Reads the next record of the file, or `None` at the end of the file.
+fn read_record(reader: &mut impl BufRead) -> StorageResult<Option<Vec<u8>>> {
+    if reader.fill_buf().map_err(io_error)?.is_empty() {
+        return Ok(None)
+    }
+    let mut len = [0; 4];
+    reader.read_exact(&mut len).map_err(io_error)?;
+    let len = u32::from_be_bytes(len);
+    // The buffer grows with the read bytes, so the torn length doesn't allocate at once.
+    let mut bytes = vec![];
+    reader
+        .take(u64::from(len))
+        .read_to_end(&mut bytes)
+        .map_err(io_error)?;
+    if bytes.len() != len as usize {
+        return Err(io_error(std::io::ErrorKind::UnexpectedEof.into()))
+    }
+    Ok(Some(bytes))
+}
+
+fn write_record(writer: &mut impl Write, entry: &ChangeLogEntry) -> StorageResult<()> {
+    let bytes = Postcard::encode(entry);
+    let len = u32::try_from(bytes.len()).map_err(|_| {
+        StorageError::Other(anyhow::anyhow!(
+            "The change log entry of {} bytes is too large",
+            bytes.len()
+        ))
+    })?;
+    writer.write_all(&len.to_be_bytes()).map_err(io_error)?;
+    writer.write_all(&bytes).map_err(io_error)
+}
+
+impl<Description> ChangeLogStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Wraps the `inner` storage and continues the change log of the `config`
+    /// after the latest kept entry.
+    ///
+    /// The torn record at the end of the file, left by the crash during the append,
+    /// is removed.
+    pub fn new<D>(
+        inner: D,
+        config: ChangeLogConfig<Description::Column>,
+    ) -> StorageResult<Self>
+    where
+        D: Into<DataSource<Description>>,
+    {
+        let inner = inner.into().0;
+        let state = match &config.target {
+            ChangeLogTarget::Column(column) => {
+                let first = inner
+                    .iter_keys(*column, None, None, IterDirection::Forward)
+                    .next()
+                    .transpose()?;
+                let last = inner
+                    .iter_keys(*column, None, None, IterDirection::Reverse)
+                    .next()
+                    .transpose()?;
+                LogState {
+                    first_seq: first.as_deref().map(seq_of_key).transpose()?.unwrap_or(0),
+                    next_seq: last
+                        .as_deref()
+                        .map(seq_of_key)
+                        .transpose()?
+                        .map_or(0, |seq| seq.saturating_add(1)),
+                    file: None,
+                }
+            }
+            ChangeLogTarget::File(path) => Self::open_file(path)?,
+        };
+        Ok(Self {
+            inner,
+            target: config.target,
+            retention: config.retention,
+            log: Mutex::new(state),
+        })
+    }
+
+    fn open_file(path: &Path) -> StorageResult<LogState> {
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)
+            .map_err(io_error)?;
+        let mut reader = BufReader::new(&file);
+        let mut bounds = None;
+        let mut valid_len = 0u64;
+        loop {
+            match read_record(&mut reader) {
+                Ok(Some(bytes)) => {
+                    let seq = decode(&bytes)?.seq;
+                    let (first, _) = bounds.unwrap_or((seq, seq));
+                    bounds = Some((first, seq));
+                    valid_len = valid_len
+                        .saturating_add(4)
+                        .saturating_add(bytes.len() as u64);
+                }
+                Ok(None) => break,
+                Err(_) => {
+                    tracing::warn!(
+                        "Removing the torn record at the end of the change log {}",
+                        path.display()
+                    );
+                    file.set_len(valid_len).map_err(io_error)?;
+                    break
+                }
+            }
+        }
+        let (first_seq, last_seq) =
+            bounds.map_or((0, None), |(first, last)| (first, Some(last)));
+        Ok(LogState {
+            first_seq,
+            next_seq: last_seq.map_or(0, |seq| seq.saturating_add(1)),
+            file: Some(file),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, LogState> {
+        self.log.lock().expect("poisoned")
+    }
+
+    fn is_log_column(&self, column: Description::Column) -> bool {
+        matches!(&self.target, ChangeLogTarget::Column(log) if *log == column)
+    }
+
+    /// Returns the sequence number of the next entry. The replica that replayed
+    /// the log up to this number is up to date.
+    pub fn next_seq(&self) -> u64 {
+        self.lock().next_seq
+    }
+
+    /// Writes the `ops` into the inner storage and records them as the next entry.
+    fn append(
+        &self,
+        log: &mut LogState,
+        ops: Vec<(Vec<u8>, Description::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        let entry = ChangeLogEntry {
+            seq: log.next_seq,
+            ops: ops
+                .iter()
+                .filter(|(_, column, _)| !self.is_log_column(*column))
+                .map(|(key, column, op)| ChangeLogOp {
+                    column: column.id(),
+                    key: key.clone(),
+                    op: op.clone(),
+                })
+                .collect(),
+        };
+        let recorded = !entry.ops.is_empty();
+        match &self.target {
+            ChangeLogTarget::Column(column) => {
+                let record = recorded.then(|| {
+                    let value = Postcard::encode(&entry).into_owned();
+                    (
+                        entry.seq.to_be_bytes().to_vec(),
+                        *column,
+                        WriteOperation::Insert(Arc::new(value)),
+                    )
+                });
+                self.inner
+                    .batch_write_with(&mut ops.into_iter().chain(record), durability)?;
+            }
+            ChangeLogTarget::File(_) => {
+                self.inner
+                    .batch_write_with(&mut ops.into_iter(), durability)?;
+                if recorded {
+                    let file = log
+                        .file
+                        .as_mut()
+                        .expect("The file of the change log is open");
+                    // The record is written by one call, so concurrent readers observe
+                    // either the whole record or the torn tail.
+                    let mut record = vec![];
+                    write_record(&mut record, &entry)?;
+                    file.write_all(&record).map_err(io_error)?;
+                    if durability == WriteDurability::Sync {
+                        file.sync_data().map_err(io_error)?;
+                    }
+                }
+            }
+        }
+        if recorded {
+            log.next_seq = log.next_seq.saturating_add(1);
+            self.apply_retention(log)?;
+        }
+        Ok(())
+    }
+
+    fn apply_retention(&self, log: &mut LogState) -> StorageResult<()> {
+        let Some(retention) = self.retention else {
+            return Ok(())
+        };
+        let retention = retention.max(1);
+        let kept = log.next_seq.saturating_sub(log.first_seq);
+        let limit = match self.target {
+            ChangeLogTarget::Column(_) => retention,
+            ChangeLogTarget::File(_) => retention.saturating_mul(2),
+        };
+        if kept > limit {
+            self.truncate(log, log.next_seq.saturating_sub(retention))?;
+        }
+        Ok(())
+    }
+
+    /// Removes entries before the `seq`. The latest entry is always kept,
+    /// so the sequence continues after the restart.
+    pub fn truncate_before(&self, seq: u64) -> StorageResult<()> {
+        let mut log = self.lock();
+        self.truncate(&mut log, seq)
+    }
+
+    fn truncate(&self, log: &mut LogState, seq: u64) -> StorageResult<()> {
+        let seq = seq.min(log.next_seq.saturating_sub(1));
+        if seq <= log.first_seq {
+            return Ok(())
+        }
+        match &self.target {
+            ChangeLogTarget::Column(column) => {
+                self.inner
+                    .batch_write(&mut (log.first_seq..seq).map(|seq| {
+                        (seq.to_be_bytes().to_vec(), *column, WriteOperation::Remove)
+                    }))?;
+            }
+            ChangeLogTarget::File(path) => {
+                let tmp = path.with_extension("truncated");
+                {
+                    let mut writer =
+                        BufWriter::new(File::create(&tmp).map_err(io_error)?);
+                    let mut reader = BufReader::new(File::open(path).map_err(io_error)?);
+                    while let Some(bytes) = read_record(&mut reader)? {
+                        let entry = decode(&bytes)?;
+                        if entry.seq >= seq {
+                            write_record(&mut writer, &entry)?;
+                        }
+                    }
+                    let file =
+                        writer.into_inner().map_err(|e| io_error(e.into_error()))?;
+                    file.sync_all().map_err(io_error)?;
+                }
+                std::fs::rename(&tmp, path).map_err(io_error)?;
+                log.file = Some(
+                    OpenOptions::new()
+                        .read(true)
+                        .append(true)
+                        .open(path)
+                        .map_err(io_error)?,
+                );
+            }
+        }
+        log.first_seq = seq;
+        Ok(())
+    }
+
+    /// Returns kept entries starting from the `seq`.
+    fn entries(
+        &self,
+        seq: u64,
+    ) -> StorageResult<Box<dyn Iterator<Item = StorageResult<ChangeLogEntry>> + '_>> {
+        match &self.target {
+            ChangeLogTarget::Column(column) => Ok(Box::new(
+                self.inner
+                    .iter_all(
+                        *column,
+                        None,
+                        Some(&seq.to_be_bytes()),
+                        IterDirection::Forward,
+                    )
+                    .map(|item| decode(&item?.1)),
+            )),
+            ChangeLogTarget::File(path) => {
+                let mut reader = BufReader::new(File::open(path).map_err(io_error)?);
+                Ok(Box::new(
+                    std::iter::from_fn(move || read_record(&mut reader).transpose())
+                        .map(|bytes| decode(&bytes?))
+                        .filter(
+                            move |entry| !matches!(entry, Ok(entry) if entry.seq < seq),
+                        ),
+                ))
+            }
+        }
+    }
+
+    /// Applies entries of the log starting from the `from_seq` to the `into` storage,
+    /// one batch per entry. Returns the sequence number of the next entry,
+    /// so the next replay continues from it.
+    ///
+    /// The replay fails if the entry `from_seq` is already truncated, because
+    /// the `into` storage would miss its writes. Writes committed during the replay
+    /// are left for the next replay.
+    pub fn replay(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Description::Column>,
+    ) -> StorageResult<u64> {
+        let (first_seq, next_seq) = {
+            let log = self.lock();
+            (log.first_seq, log.next_seq)
+        };
+        if from_seq >= next_seq {
+            return Ok(from_seq)
+        }
+        if from_seq < first_seq {
+            return Err(anyhow::anyhow!(
+                "The change log starts at the entry {first_seq}, the entry {from_seq} is truncated"
+            )
+            .into())
+        }
+
+        let mut expected = from_seq;
+        for entry in self.entries(from_seq)? {
+            let entry = entry?;
+            if entry.seq >= next_seq {
+                break
+            }
+            if entry.seq != expected {
+                return Err(anyhow::anyhow!(
+                    "The entry {expected} of the change log is truncated"
+                )
+                .into())
+            }
+            let ops = entry
+                .ops
+                .into_iter()
+                .map(
+                    |ChangeLogOp {
+                         column: id,
+                         key,
+                         op,
+                     }| {
+                        let column = enum_iterator::all::<Description::Column>()
+                            .find(|column| column.id() == id)
+                            .ok_or_else(|| {
+                                StorageError::Other(anyhow::anyhow!(
+                                "The change log entry {} writes the unknown column {id}",
+                                entry.seq
+                            ))
+                            })?;
+                        Ok((key, column, op))
+                    },
+                )
+                .collect::<StorageResult<Vec<_>>>()?;
+            into.batch_write(&mut ops.into_iter())?;
+            expected = expected.saturating_add(1);
+        }
+        if expected != next_seq {
+            return Err(anyhow::anyhow!(
+                "The entry {expected} of the change log is truncated"
+            )
+            .into())
+        }
+        Ok(next_seq)
+    }
+
+    /// Removes all entries of the `column` with the `prefix` as one recorded batch.
+    fn remove_all(
+        &self,
+        column: Description::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        let mut log = self.lock();
+        let keys = self
+            .inner
+            .iter_keys(column, prefix, None, IterDirection::Forward)
+            .collect::<StorageResult<Vec<_>>>()?;
+        let removed = keys.len();
+        self.append(
+            &mut log,
+            keys.into_iter()
+                .map(|key| (key, column, WriteOperation::Remove))
+                .collect(),
+            WriteDurability::Buffered,
+        )?;
+        Ok(removed)
+    }
+}
+
+impl<Description> KeyValueStore for ChangeLogStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        self.append(
+            &mut self.lock(),
+            vec![(key.to_vec(), column, WriteOperation::Insert(value))],
+            WriteDurability::Buffered,
+        )
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        let mut log = self.lock();
+        let previous = self.inner.get(key, column)?;
+        self.append(
+            &mut log,
+            vec![(key.to_vec(), column, WriteOperation::Insert(value))],
+            WriteDurability::Buffered,
+        )?;
+        Ok(previous)
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        self.put(key, column, Arc::new(buf.to_vec()))?;
+        Ok(buf.len())
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        let mut log = self.lock();
+        let previous = self.inner.get(key, column)?;
+        self.append(
+            &mut log,
+            vec![(key.to_vec(), column, WriteOperation::Remove)],
+            WriteDurability::Buffered,
+        )?;
+        Ok(previous)
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        self.append(
+            &mut self.lock(),
+            vec![(key.to_vec(), column, WriteOperation::Remove)],
+            WriteDurability::Buffered,
+        )
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        self.append(
+            &mut self.lock(),
+            vec![(key.to_vec(), column, WriteOperation::Merge(operand))],
+            WriteDurability::Buffered,
+        )
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        self.inner.exists(key, column)
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        self.inner.size_of_value(key, column)
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.inner.get(key, column)
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        self.inner.get_with(key, column, f)
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        self.inner.multi_get(keys, column)
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        // All writes go through the lock, so the comparison and the write are atomic.
+        let mut log = self.lock();
+        let current = self.inner.get(key, column)?;
+        if current.as_ref().map(|value| value.as_slice()) != expected {
+            return Ok(Err(current))
+        }
+        self.append(
+            &mut log,
+            vec![(key.to_vec(), column, WriteOperation::Insert(new))],
+            WriteDurability::Buffered,
+        )?;
+        Ok(Ok(()))
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        // All writes go through the lock, so the read and the write are atomic.
+        let mut log = self.lock();
+        let current = self.inner.get(key, column)?;
+        let current = current.as_deref().map(Vec::as_slice);
+        let new = f(current);
+        let outcome = UpdateOutcome::of(current, new.as_deref());
+        let op = match new {
+            _ if !outcome.is_changed() => return Ok(outcome),
+            Some(new) => WriteOperation::Insert(Arc::new(new)),
+            None => WriteOperation::Remove,
+        };
+        self.append(
+            &mut log,
+            vec![(key.to_vec(), column, op)],
+            WriteDurability::Buffered,
+        )?;
+        Ok(outcome)
+    }
+
+    fn read(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &mut [u8],
+    ) -> StorageResult<Option<usize>> {
+        self.inner.read(key, column, buf)
+    }
+}
+
+impl<Description> IteratorableStore for ChangeLogStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.inner.iter_all(column, prefix, start, direction)
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.inner.iter_range(column, prefix, start, end, direction)
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.inner.iter_keys(column, prefix, start, direction)
+    }
+
+    fn has_any(&self, column: Self::Column, prefix: &[u8]) -> StorageResult<bool> {
+        self.inner.has_any(column, prefix)
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        self.remove_all(column, prefix)
+    }
+}
+
+impl<Description> BatchOperations for ChangeLogStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for ChangeLogStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        self.append(&mut self.lock(), entries.collect(), durability)
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        self.inner.flush()?;
+        if let Some(file) = &self.lock().file {
+            file.sync_data()
+                .map_err(|e| DatabaseError::Other(anyhow::anyhow!(io_error(e))))?;
+        }
+        Ok(())
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        self.inner.column_stats(column)
+    }
+
+    fn engine_stats(&self) -> DatabaseResult<EngineStats> {
+        self.inner.engine_stats()
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        self.inner.write_pressure()
+    }
+
+    fn size_of_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> DatabaseResult<u64> {
+        self.inner.size_of_range(column, start, end)
+    }
+
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        self.inner.checkpoint(path)
+    }
+
+    fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()> {
+        self.inner.backup_to(backup_dir)
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        self.inner.compact(column, range)
+    }
+
+    /// The removal of entries is recorded as the batch of removals of all keys,
+    /// so the replay clears the column of the replica too.
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        self.remove_all(column, None).map_err(DatabaseError::from)?;
+        Ok(())
+    }
+
+    // `multi_delete` and `delete_range` keep the default expansion into removals of
+    // the batch, so each removed key is logged and replayed.
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        self.inner.snapshot()
+    }
+
+    fn read_snapshot(&self) -> DatabaseResult<Box<dyn ReadView<Column = Self::Column>>>
+    where
+        Self::Column: 'static,
+    {
+        self.inner.read_snapshot()
+    }
+
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        self.inner.subscribe(column, prefix)
+    }
+
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.replay(from_seq, into).map_err(DatabaseError::from)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.truncate_before(seq).map_err(DatabaseError::from)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        self.inner.freeze_columns(columns)
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        self.inner.unfreeze_columns(columns)
+    }
+
+    fn begin_shutdown(&self) {
+        self.inner.begin_shutdown()
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        // Syncs the file of the change log, which the inner storage doesn't know.
+        self.flush()?;
+        self.inner.finish_shutdown()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{
+            database_description::on_chain::OnChain,
+            Database,
+        },
+        state::{
+            faulty::FaultyStorage,
+            in_memory::{
+                memory_store::MemoryStore,
+                transaction::MemoryTransactionView,
+            },
+        },
+    };
+    use fuel_core_storage::column::Column;
+
+    type Columns = Vec<(Column, Vec<(Vec<u8>, Value)>)>;
+
+    fn targets(dir: &tempfile::TempDir) -> Vec<ChangeLogTarget<Column>> {
+        vec![
+            ChangeLogTarget::Column(Column::ChangeLog),
+            ChangeLogTarget::File(dir.path().join("change.log")),
+        ]
+    }
+
+    fn logged(
+        inner: Arc<MemoryStore<OnChain>>,
+        target: ChangeLogTarget<Column>,
+        retention: Option<u64>,
+    ) -> Arc<ChangeLogStorage<OnChain>> {
+        Arc::new(
+            ChangeLogStorage::new(inner, ChangeLogConfig { target, retention }).unwrap(),
+        )
+    }
+
+    fn put(storage: &ChangeLogStorage<OnChain>, key: u8) {
+        storage
+            .put(&[key], Column::Coins, Arc::new(vec![key]))
+            .unwrap();
+    }
+
+    fn columns(storage: &dyn TransactableStorage<Column = Column>) -> Columns {
+        enum_iterator::all::<Column>()
+            .filter(|column| *column != Column::ChangeLog)
+            .map(|column| {
+                let entries = storage
+                    .iter_all(column, None, None, IterDirection::Forward)
+                    .map(|item| item.unwrap())
+                    .collect();
+                (column, entries)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn replayed_log_reproduces_the_database() {
+        let dir = tempfile::tempdir().unwrap();
+        for target in targets(&dir) {
+            // Given
+            let a = Arc::new(MemoryStore::<OnChain>::default());
+            let storage = logged(a.clone(), target, None);
+            for i in 0..20u8 {
+                let transaction = MemoryTransactionView::<OnChain>::new(storage.clone());
+                transaction
+                    .put(&[i], Column::Coins, Arc::new(vec![i]))
+                    .unwrap();
+                transaction
+                    .put(&[i, i], Column::Messages, Arc::new(vec![i; 3]))
+                    .unwrap();
+                if i % 3 == 0 {
+                    transaction.delete(&[i / 2], Column::Coins).unwrap();
+                }
+                transaction.commit().unwrap();
+            }
+            storage
+                .replace(&[1], Column::Coins, Arc::new(vec![9]))
+                .unwrap();
+            storage.take(&[2], Column::Coins).unwrap();
+            storage
+                .compare_and_swap(&[5], Column::Coins, Some(&[5]), Arc::new(vec![7]))
+                .unwrap()
+                .unwrap();
+            storage.delete_all(Column::Messages, Some(&[4])).unwrap();
+            storage
+                .put(&[0], Column::Metadata, Arc::new(vec![1]))
+                .unwrap();
+            storage.clear_column(Column::Metadata).unwrap();
+            let b = MemoryStore::<OnChain>::default();
+
+            // When
+            let next = storage.replay(0, &b).unwrap();
+
+            // Then
+            assert_eq!(next, storage.next_seq());
+            assert_eq!(columns(&b), columns(a.as_ref()));
+            assert!(!columns(&b)[Column::Coins.as_usize()].1.is_empty());
+        }
+    }
+
+    #[test]
+    fn multi_delete_is_replayed_as_removals() {
+        let dir = tempfile::tempdir().unwrap();
+        for target in targets(&dir) {
+            // Given
+            let a = Arc::new(MemoryStore::<OnChain>::default());
+            let storage = logged(a.clone(), target, None);
+            let b = MemoryStore::<OnChain>::default();
+            for key in 1..=3 {
+                put(&storage, key);
+            }
+
+            // When
+            let existing = storage
+                .multi_delete(Column::Coins, &[vec![1], vec![3], vec![9]])
+                .unwrap();
+            storage.replay(0, &b).unwrap();
+
+            // Then
+            assert_eq!(existing, 2);
+            assert_eq!(
+                columns(&b),
+                columns(a.as_ref()),
+                "The replica doesn't see the removals"
+            );
+            assert_eq!(b.get(&[2], Column::Coins).unwrap(), Some(Arc::new(vec![2])));
+        }
+    }
+
+    #[test]
+    fn replay_continues_from_the_returned_sequence() {
+        let dir = tempfile::tempdir().unwrap();
+        for target in targets(&dir) {
+            // Given
+            let a = Arc::new(MemoryStore::<OnChain>::default());
+            let storage = logged(a.clone(), target, None);
+            let b = MemoryStore::<OnChain>::default();
+            put(&storage, 1);
+            let next = storage.replay(0, &b).unwrap();
+
+            // When
+            put(&storage, 2);
+            storage.delete(&[1], Column::Coins).unwrap();
+            let next = storage.replay(next, &b).unwrap();
+
+            // Then
+            assert_eq!(next, 3);
+            assert_eq!(storage.replay(next, &b).unwrap(), 3);
+            assert_eq!(columns(&b), columns(a.as_ref()));
+        }
+    }
+
+    #[test]
+    fn failed_writes_are_not_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        for target in targets(&dir) {
+            // Given
+            let faulty = Arc::new(FaultyStorage::<OnChain>::new(Arc::new(
+                MemoryStore::default(),
+            )));
+            let storage = Arc::new(
+                ChangeLogStorage::new(
+                    faulty.clone(),
+                    ChangeLogConfig {
+                        target,
+                        retention: None,
+                    },
+                )
+                .unwrap(),
+            );
+
+            // When
+            faulty.fail_batch_write();
+            let transaction = MemoryTransactionView::<OnChain>::new(storage.clone());
+            transaction
+                .put(&[1], Column::Coins, Arc::new(vec![1]))
+                .unwrap();
+            let result = transaction.commit();
+
+            // Then
+            assert!(result.is_err());
+            assert_eq!(storage.next_seq(), 0);
+        }
+    }
+
+    #[test]
+    fn retention_truncates_old_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        for target in targets(&dir) {
+            // Given
+            let storage = logged(Arc::default(), target, Some(2));
+
+            // When
+            for i in 0..10u8 {
+                put(&storage, i);
+            }
+
+            // Then
+            let b = MemoryStore::<OnChain>::default();
+            assert!(storage.replay(0, &b).is_err());
+            assert_eq!(storage.replay(8, &b).unwrap(), 10);
+            assert_eq!(
+                columns(&b)[Column::Coins.as_usize()].1,
+                vec![(vec![8], Arc::new(vec![8])), (vec![9], Arc::new(vec![9]))]
+            );
+        }
+    }
+
+    #[test]
+    fn truncation_keeps_the_latest_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        for target in targets(&dir) {
+            // Given
+            let inner = Arc::new(MemoryStore::<OnChain>::default());
+            let storage = logged(inner.clone(), target.clone(), None);
+            for i in 0..3u8 {
+                put(&storage, i);
+            }
+
+            // When
+            storage.truncate_before(u64::MAX).unwrap();
+            drop(storage);
+            let reopened = logged(inner, target, None);
+            put(&reopened, 3);
+
+            // Then
+            let b = MemoryStore::<OnChain>::default();
+            assert!(reopened.replay(1, &b).is_err());
+            assert_eq!(reopened.replay(2, &b).unwrap(), 4);
+        }
+    }
+
+    #[test]
+    fn file_log_drops_the_torn_record_on_open() {
+        // Given
+        let dir = tempfile::tempdir().unwrap();
+        let target = ChangeLogTarget::File(dir.path().join("change.log"));
+        let inner = Arc::new(MemoryStore::<OnChain>::default());
+        let storage = logged(inner.clone(), target.clone(), None);
+        put(&storage, 1);
+        put(&storage, 2);
+        drop(storage);
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("change.log"))
+            .unwrap();
+        file.write_all(&[0, 0, 0, 9, 1]).unwrap();
+
+        // When
+        let reopened = logged(inner, target, None);
+        put(&reopened, 3);
+
+        // Then
+        let b = MemoryStore::<OnChain>::default();
+        assert_eq!(reopened.replay(0, &b).unwrap(), 3);
+        assert_eq!(columns(&b)[Column::Coins.as_usize()].1.len(), 3);
+    }
+
+    #[test]
+    fn only_database_with_change_log_replays() {
+        // Given
+        let plain = Database::<OnChain>::in_memory();
+        let logged = Database::<OnChain>::in_memory()
+            .with_change_log(ChangeLogConfig {
+                target: ChangeLogTarget::Column(Column::ChangeLog),
+                retention: None,
+            })
+            .unwrap();
+        let replica = Database::<OnChain>::in_memory();
+
+        // Then
+        assert!(plain.replay_change_log(0, &replica).is_err());
+        assert_eq!(logged.replay_change_log(0, &replica).unwrap(), 0);
+    }
+}
@@ -0,0 +1,924 @@
+//! The checksums of values, see [`ChecksummedStorage`].
+//!
+//! The block checksums of the RocksDB don't cover the in-memory backend, the exported
+//! state, or values truncated by our own writes. The [`ChecksummedStorage`] stores each
+//! value followed by the trailer of the CRC32 of the column, the key and the value,
+//! and the [`MARKER`] byte. The trailer is verified on every read and stripped before
+//! the value is returned, and the mismatch fails with [`DatabaseError::Corrupted`].
+//! The key is a part of the checksum, so the value moved to another key fails too.
+//!
+//! The trailer adds [`TRAILER_SIZE`] bytes to each value. Measured on encoded values,
+//! it is 15.6% of 32 bytes of the contract slot, 7.2% of 69 bytes of the coin, and 2.3%
+//! of 221 bytes of the minimal script transaction. The RocksDB compresses values in blocks, so the overhead
+//! on the disk is smaller for compressible values, but checksums don't compress.
+//!
+//! The metadata column isn't covered, so the mode can be checked before the values
+//! are read. It holds the [`ChecksumMode`] record written on the first open with
+//! checksums, and the database with the record can't be opened without checksums.
+//! Columns with the merge operator aren't covered either, because the backend merges
+//! stored values.
+//!
+//! The database with values written before checksums were enabled is opened in the
+//! [`ChecksumMode::Migrating`] mode, where values without the valid trailer are read
+//! as is. Rewritten values get the trailer, and [`ChecksummedStorage::migrate`]
+//! rewrites the rest and switches the database to the [`ChecksumMode::Strict`] mode.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        metadata::{
+            get_metadata,
+            set_metadata,
+            ValueChecksums,
+        },
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::{
+        column_iter::ColumnIter,
+        notifying::StorageEvent,
+        update_encoded,
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        EngineStats,
+        IterDirection,
+        TransactableStorage,
+        WriteDurability,
+        WritePressure,
+    },
+};
+use fuel_core_storage::{
+    iter::{
+        BoxedIter,
+        IntoBoxedIter,
+        IterConfig,
+        IteratorableStore,
+        PageIter,
+    },
+    kv_store::{
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Result as StorageResult,
+};
+use std::{
+    path::Path,
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Arc,
+    },
+};
+use tokio::sync::broadcast;
+
+/// The last byte of the value with the checksum.
+pub const MARKER: u8 = 0xCC;
+
+/// The size of the checksum and the marker appended to the value.
+pub const TRAILER_SIZE: usize = 5;
+
+/// The number of entries rewritten by [`ChecksummedStorage::migrate`] per scan.
+const MIGRATION_CHUNK: usize = 1024;
+
+/// The mode of checksums of the database, recorded in the metadata column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ChecksumMode {
+    /// All values have checksums, and the value without the valid trailer is corrupted.
+    Strict,
+    /// Values written before checksums were enabled are read as is.
+    Migrating,
+}
+
+fn checksum<Column: StorageColumn>(column: Column, key: &[u8], value: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&column.id().to_be_bytes());
+    hasher.update(key);
+    hasher.update(value);
+    hasher.finalize()
+}
+
+/// Returns the length of the value in the `stored` value if it has the valid trailer.
+fn verified_len<Column: StorageColumn>(
+    column: Column,
+    key: &[u8],
+    stored: &[u8],
+) -> Option<usize> {
+    let len = stored.len().checked_sub(TRAILER_SIZE)?;
+    let (value, trailer) = stored.split_at(len);
+    let valid = trailer[4] == MARKER
+        && trailer[..4] == checksum(column, key, value).to_le_bytes();
+    valid.then_some(len)
+}
+
+/// Fails if the `store` has values with checksums, so it must be opened
+/// with the [`ChecksummedStorage`].
+pub fn ensure_disabled<Description>(
+    store: &(impl KeyValueStore<Column = Description::Column> + ?Sized),
+) -> DatabaseResult<()>
+where
+    Description: DatabaseDescription,
+{
+    let mode = get_metadata::<ValueChecksums, Description>(store)
+        .map_err(DatabaseError::from)?;
+    match mode {
+        Some(mode) => Err(anyhow::anyhow!(
+            "The `{}` database stores values with checksums in the {mode:?} mode, \
+            it can't be opened without checksums",
+            Description::name()
+        )
+        .into()),
+        None => Ok(()),
+    }
+}
+
+/// The storage that appends checksums to values of all columns except the metadata
+/// column and columns with the merge operator, and verifies them on reads.
+#[derive(Debug)]
+pub struct ChecksummedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    inner: DataSourceInner<Description::Column>,
+    /// `true` in the [`ChecksumMode::Strict`] mode. It is shared with snapshots,
+    /// so they stop accepting legacy values after the migration too.
+    strict: Arc<AtomicBool>,
+}
+
+impl<Description> ChecksummedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Wraps the `inner` storage with checksums of values. The mode is taken from
+    /// the metadata column. Without the record, the empty storage is [`ChecksumMode::Strict`],
+    /// and the storage with values is [`ChecksumMode::Migrating`].
+    pub fn new<D>(inner: D) -> DatabaseResult<Self>
+    where
+        D: Into<DataSource<Description>>,
+    {
+        let inner = inner.into().0;
+        let mode = get_metadata::<ValueChecksums, Description>(inner.as_ref())
+            .map_err(DatabaseError::from)?;
+        let mode = match mode {
+            Some(mode) => mode,
+            None => {
+                let has_entries = enum_iterator::all::<Description::Column>()
+                    .filter(|column| Self::is_covered(*column))
+                    .any(|column| !matches!(inner.has_any(column, &[]), Ok(false)));
+                let mode = match has_entries {
+                    true => {
+                        tracing::warn!(
+                            "The `{}` database has values without checksums, \
+                            they are verified after the migration",
+                            Description::name()
+                        );
+                        ChecksumMode::Migrating
+                    }
+                    false => ChecksumMode::Strict,
+                };
+                set_metadata::<ValueChecksums, Description>(inner.as_ref(), &mode)
+                    .map_err(DatabaseError::from)?;
+                mode
+            }
+        };
+        Ok(Self {
+            inner,
+            strict: Arc::new(AtomicBool::new(mode == ChecksumMode::Strict)),
+        })
+    }
+
+    /// Returns the mode of checksums.
+    pub fn mode(&self) -> ChecksumMode {
+        match self.strict.load(Ordering::Acquire) {
+            true => ChecksumMode::Strict,
+            false => ChecksumMode::Migrating,
+        }
+    }
+
+    /// Appends checksums to values without them and switches the storage
+    /// to the [`ChecksumMode::Strict`] mode. Returns the number of rewritten values.
+    pub fn migrate(&self) -> DatabaseResult<usize> {
+        let mut migrated = 0usize;
+        for column in enum_iterator::all::<Description::Column>()
+            .filter(|column| Self::is_covered(*column))
+        {
+            let mut start: Option<Vec<u8>> = None;
+            loop {
+                let entries = self
+                    .inner
+                    .iter_all(column, None, start.as_deref(), IterDirection::Forward)
+                    .filter(|item| match (item, &start) {
+                        (Ok((key, _)), Some(start)) => key != start,
+                        _ => true,
+                    })
+                    .take(MIGRATION_CHUNK)
+                    .collect::<StorageResult<Vec<_>>>()
+                    .map_err(DatabaseError::from)?;
+                let Some((last, _)) = entries.last() else {
+                    break
+                };
+                start = Some(last.clone());
+                for (key, stored) in entries {
+                    if verified_len(column, &key, &stored).is_some() {
+                        continue
+                    }
+                    let sealed = Self::seal(column, &key, &stored);
+                    // The value rewritten since the scan already has the checksum.
+                    let swapped = self
+                        .inner
+                        .compare_and_swap(&key, column, Some(&stored), sealed)
+                        .map_err(DatabaseError::from)?;
+                    if swapped.is_ok() {
+                        migrated = migrated.saturating_add(1);
+                    }
+                }
+            }
+        }
+        set_metadata::<ValueChecksums, Description>(
+            self.inner.as_ref(),
+            &ChecksumMode::Strict,
+        )
+        .map_err(DatabaseError::from)?;
+        self.strict.store(true, Ordering::Release);
+        Ok(migrated)
+    }
+
+    fn is_covered(column: Description::Column) -> bool {
+        column != Description::metadata_column() && column.merge_operator().is_none()
+    }
+
+    fn seal(column: Description::Column, key: &[u8], value: &[u8]) -> Value {
+        let mut stored = Vec::with_capacity(value.len().saturating_add(TRAILER_SIZE));
+        stored.extend_from_slice(value);
+        stored.extend_from_slice(&checksum(column, key, value).to_le_bytes());
+        stored.push(MARKER);
+        Arc::new(stored)
+    }
+
+    /// Returns the value to store for the `value` of the `column`.
+    fn seal_value(column: Description::Column, key: &[u8], value: Value) -> Value {
+        match Self::is_covered(column) {
+            true => Self::seal(column, key, &value),
+            false => value,
+        }
+    }
+
+    /// Returns the length of the value in the `stored` value of the covered `column`.
+    fn value_len(
+        &self,
+        column: Description::Column,
+        key: &[u8],
+        stored: &[u8],
+    ) -> StorageResult<usize> {
+        match verified_len(column, key, stored) {
+            Some(len) => Ok(len),
+            None if self.strict.load(Ordering::Acquire) => {
+                Err(DatabaseError::Corrupted {
+                    column: column.name(),
+                    key: key.to_vec(),
+                }
+                .into())
+            }
+            None => Ok(stored.len()),
+        }
+    }
+
+    /// Returns the value of the `column` for the `stored` value.
+    fn unseal(
+        &self,
+        column: Description::Column,
+        key: &[u8],
+        stored: Value,
+    ) -> StorageResult<Value> {
+        if !Self::is_covered(column) {
+            return Ok(stored)
+        }
+        let len = self.value_len(column, key, &stored)?;
+        if len == stored.len() {
+            return Ok(stored)
+        }
+        // The stored value is usually not shared, so the trailer is cut without the copy.
+        let value = match Arc::try_unwrap(stored) {
+            Ok(mut value) => {
+                value.truncate(len);
+                value
+            }
+            Err(stored) => stored[..len].to_vec(),
+        };
+        Ok(Arc::new(value))
+    }
+
+    fn open(
+        &self,
+        column: Description::Column,
+        key: &[u8],
+        stored: Option<Value>,
+    ) -> StorageResult<Option<Value>> {
+        stored
+            .map(|stored| self.unseal(column, key, stored))
+            .transpose()
+    }
+
+    /// Verifies and strips checksums of values of the `iter` over the `column`.
+    fn verified<'a>(
+        &'a self,
+        column: Description::Column,
+        iter: BoxedIter<'a, KVItem>,
+    ) -> BoxedIter<'a, KVItem> {
+        if !Self::is_covered(column) {
+            return iter
+        }
+        iter.map(move |item| {
+            let (key, stored) = item?;
+            let value = self.unseal(column, &key, stored)?;
+            Ok((key, value))
+        })
+        .into_boxed()
+    }
+}
+
+impl<Description> KeyValueStore for ChecksummedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        self.inner
+            .put(key, column, Self::seal_value(column, key, value))
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        let old =
+            self.inner
+                .replace(key, column, Self::seal_value(column, key, value))?;
+        self.open(column, key, old)
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        if !Self::is_covered(column) {
+            return self.inner.write(key, column, buf)
+        }
+        self.inner.put(key, column, Self::seal(column, key, buf))?;
+        Ok(buf.len())
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        let old = self.inner.take(key, column)?;
+        self.open(column, key, old)
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        self.inner.delete(key, column)
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        // Covered columns don't have the merge operator, so the inner storage rejects it.
+        self.inner.merge(key, column, operand)
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        self.inner.exists(key, column)
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        if !Self::is_covered(column) {
+            return self.inner.size_of_value(key, column)
+        }
+        // The checksum is verified, so the corrupted value doesn't report its size.
+        let mut size = Ok(0);
+        let found = self.inner.get_with(key, column, &mut |stored| {
+            size = self.value_len(column, key, stored);
+        })?;
+        found.then_some(size).transpose()
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        let stored = self.inner.get(key, column)?;
+        self.open(column, key, stored)
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        if !Self::is_covered(column) {
+            return self.inner.get_with(key, column, f)
+        }
+        let mut verified = Ok(());
+        let found = self.inner.get_with(key, column, &mut |stored| {
+            verified = self
+                .value_len(column, key, stored)
+                .map(|len| f(&stored[..len]));
+        })?;
+        verified?;
+        Ok(found)
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        let values = self.inner.multi_get(keys, column)?;
+        keys.iter()
+            .zip(values)
+            .map(|(key, stored)| self.open(column, key, stored))
+            .collect()
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        if !Self::is_covered(column) {
+            return self.inner.compare_and_swap(key, column, expected, new)
+        }
+        // The swap compares stored values, so the expected value is replaced
+        // by the stored one that it matches.
+        let stored = self.inner.get(key, column)?;
+        let current = self.open(column, key, stored.clone())?;
+        if current.as_deref().map(Vec::as_slice) != expected {
+            return Ok(Err(current))
+        }
+        match self.inner.compare_and_swap(
+            key,
+            column,
+            stored.as_deref().map(Vec::as_slice),
+            Self::seal(column, key, &new),
+        )? {
+            Ok(()) => Ok(Ok(())),
+            Err(stored) => Ok(Err(self.open(column, key, stored)?)),
+        }
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        if !Self::is_covered(column) {
+            return self.inner.update(key, column, f)
+        }
+        update_encoded(
+            self.inner.as_ref(),
+            key,
+            column,
+            f,
+            &|stored| {
+                let len = self.value_len(column, key, stored)?;
+                Ok(Some(stored[..len].to_vec()))
+            },
+            &|value| Ok(Self::seal(column, key, &value).to_vec()),
+        )
+    }
+}
+
+impl<Description> IteratorableStore for ChecksummedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        let iter = self.inner.iter_all(column, prefix, start, direction);
+        self.verified(column, iter)
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        let iter = self.inner.iter_range(column, prefix, start, end, direction);
+        self.verified(column, iter)
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.inner.iter_keys(column, prefix, start, direction)
+    }
+
+    fn has_any(&self, column: Self::Column, prefix: &[u8]) -> StorageResult<bool> {
+        self.inner.has_any(column, prefix)
+    }
+
+    fn iter(&self, column: Self::Column, config: IterConfig) -> PageIter {
+        let iter = self.inner.iter(column, config);
+        match Self::is_covered(column) {
+            true => PageIter::new(self.verified(column, iter.into_boxed())),
+            false => iter,
+        }
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        self.inner.delete_all(column, prefix)
+    }
+}
+
+impl<Description> BatchOperations for ChecksummedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for ChecksummedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        let mut entries = entries.map(|(key, column, op)| match op {
+            WriteOperation::Insert(value) => {
+                let value = Self::seal_value(column, &key, value);
+                (key, column, WriteOperation::Insert(value))
+            }
+            op => (key, column, op),
+        });
+        self.inner.batch_write_with(&mut entries, durability)
+    }
+
+    fn iter_column(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> ColumnIter<'_> {
+        match Self::is_covered(column) {
+            true => ColumnIter::boxed(self.iter_all(column, prefix, start, direction)),
+            false => self.inner.iter_column(column, prefix, start, direction),
+        }
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        self.inner.flush()
+    }
+
+    /// Returns the statistics of stored values, i.e. sizes of values with checksums.
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        self.inner.column_stats(column)
+    }
+
+    fn engine_stats(&self) -> DatabaseResult<EngineStats> {
+        self.inner.engine_stats()
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        self.inner.write_pressure()
+    }
+
+    fn size_of_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> DatabaseResult<u64> {
+        self.inner.size_of_range(column, start, end)
+    }
+
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        self.inner.checkpoint(path)
+    }
+
+    fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()> {
+        self.inner.backup_to(backup_dir)
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        self.inner.compact(column, range)
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        self.inner.clear_column(column)
+    }
+
+    fn multi_delete(
+        &self,
+        column: Self::Column,
+        keys: &[Vec<u8>],
+    ) -> DatabaseResult<usize> {
+        self.inner.multi_delete(column, keys)
+    }
+
+    fn delete_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: &[u8],
+    ) -> DatabaseResult<()> {
+        self.inner.delete_range(column, start, end)
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        // The snapshot stores values with checksums, so it is wrapped too.
+        Ok(Arc::new(Self {
+            inner: self.inner.snapshot()?,
+            strict: self.strict.clone(),
+        }))
+    }
+
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        self.inner.subscribe(column, prefix)
+    }
+
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.inner.replay_change_log(from_seq, into)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.inner.truncate_change_log(seq)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        self.inner.freeze_columns(columns)
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        self.inner.unfreeze_columns(columns)
+    }
+
+    fn begin_shutdown(&self) {
+        self.inner.begin_shutdown()
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        self.inner.finish_shutdown()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::{
+            on_chain::OnChain,
+            test::MergeDatabase,
+        },
+        state::{
+            in_memory::memory_store::MemoryStore,
+            test_helpers::over_memory_store,
+        },
+    };
+    use fuel_core_storage::column::Column;
+
+    fn value(seed: u8) -> Value {
+        Arc::new(vec![seed; 32])
+    }
+
+    fn storage() -> (Arc<MemoryStore<OnChain>>, ChecksummedStorage<OnChain>) {
+        over_memory_store(|inner| ChecksummedStorage::new(inner).unwrap())
+    }
+
+    fn is_corrupted(result: impl core::fmt::Debug, key: u8) -> bool {
+        let expected = DatabaseError::Corrupted {
+            column: Column::ContractsState.name(),
+            key: vec![key],
+        };
+        format!("{result:?}").contains(&format!("{expected:?}"))
+    }
+
+    /// Flips the first byte of the value stored at the `key` of the `column`.
+    fn flip_byte(inner: &MemoryStore<OnChain>, key: &[u8], column: Column) {
+        let mut stored = inner.get(key, column).unwrap().unwrap().to_vec();
+        stored[0] ^= 0xff;
+        inner.put(key, column, Arc::new(stored)).unwrap();
+    }
+
+    #[test]
+    fn values_round_trip_with_the_trailer() {
+        // Given
+        let (inner, storage) = storage();
+
+        // When
+        storage.put(&[1], Column::ContractsState, value(1)).unwrap();
+        storage
+            .batch_write(
+                &mut vec![(
+                    vec![2],
+                    Column::ContractsState,
+                    WriteOperation::Insert(value(2)),
+                )]
+                .into_iter(),
+            )
+            .unwrap();
+
+        // Then
+        assert_eq!(storage.mode(), ChecksumMode::Strict);
+        assert_eq!(
+            storage.get(&[1], Column::ContractsState).unwrap(),
+            Some(value(1))
+        );
+        assert_eq!(
+            storage.size_of_value(&[2], Column::ContractsState).unwrap(),
+            Some(32)
+        );
+        let stored = inner.get(&[1], Column::ContractsState).unwrap().unwrap();
+        assert_eq!(stored.len(), 32 + TRAILER_SIZE);
+        assert_eq!(stored.last(), Some(&MARKER));
+        let values = storage
+            .iter_all(Column::ContractsState, None, None, IterDirection::Forward)
+            .collect::<StorageResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(values, vec![(vec![1], value(1)), (vec![2], value(2))]);
+    }
+
+    #[test]
+    fn flipped_byte_fails_reads_with_the_column_and_the_key() {
+        // Given
+        let (inner, storage) = storage();
+        storage.put(&[1], Column::ContractsState, value(1)).unwrap();
+        storage.put(&[2], Column::ContractsState, value(2)).unwrap();
+        flip_byte(&inner, &[2], Column::ContractsState);
+
+        // When
+        let get = storage.get(&[2], Column::ContractsState);
+        let iter = storage
+            .iter_all(Column::ContractsState, None, None, IterDirection::Forward)
+            .collect::<Vec<_>>();
+
+        // Then
+        assert!(is_corrupted(get, 2));
+        assert!(matches!(&iter[0], Ok((key, _)) if key == &[1]));
+        assert!(is_corrupted(&iter[1], 2));
+        assert!(is_corrupted(
+            storage.get_with(&[2], Column::ContractsState, &mut |_| {}),
+            2
+        ));
+    }
+
+    #[test]
+    fn truncated_value_and_value_moved_to_another_key_are_corrupted() {
+        // Given
+        let (inner, storage) = storage();
+        storage.put(&[1], Column::ContractsState, value(1)).unwrap();
+        let stored = inner.get(&[1], Column::ContractsState).unwrap().unwrap();
+        inner
+            .put(&[2], Column::ContractsState, stored.clone())
+            .unwrap();
+        inner
+            .put(
+                &[3],
+                Column::ContractsState,
+                Arc::new(stored[..30].to_vec()),
+            )
+            .unwrap();
+
+        // When
+        let moved = storage.get(&[2], Column::ContractsState);
+        let truncated = storage.get(&[3], Column::ContractsState);
+
+        // Then
+        assert!(is_corrupted(moved, 2));
+        assert!(is_corrupted(truncated, 3));
+    }
+
+    #[test]
+    fn legacy_values_are_readable_until_the_migration() {
+        // Given
+        let inner = Arc::new(MemoryStore::<OnChain>::default());
+        inner.put(&[1], Column::ContractsState, value(1)).unwrap();
+        inner.put(&[2], Column::ContractsState, value(2)).unwrap();
+        let storage = ChecksummedStorage::<OnChain>::new(inner.clone()).unwrap();
+        storage.put(&[3], Column::ContractsState, value(3)).unwrap();
+
+        // When
+        let legacy = storage.get(&[1], Column::ContractsState).unwrap();
+        let migrated = storage.migrate().unwrap();
+
+        // Then
+        assert_eq!(legacy, Some(value(1)));
+        assert_eq!(migrated, 2);
+        assert_eq!(storage.mode(), ChecksumMode::Strict);
+        let values = storage
+            .iter_all(Column::ContractsState, None, None, IterDirection::Forward)
+            .collect::<StorageResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                (vec![1], value(1)),
+                (vec![2], value(2)),
+                (vec![3], value(3))
+            ]
+        );
+        let reopened = ChecksummedStorage::<OnChain>::new(inner.clone()).unwrap();
+        assert_eq!(reopened.mode(), ChecksumMode::Strict);
+        inner.put(&[4], Column::ContractsState, value(4)).unwrap();
+        assert!(reopened.get(&[4], Column::ContractsState).is_err());
+    }
+
+    #[test]
+    fn database_with_checksums_cant_be_opened_without_them() {
+        // Given
+        let (inner, storage) = storage();
+        let plain = MemoryStore::<OnChain>::default();
+
+        // When
+        let with_checksums = ensure_disabled::<OnChain>(inner.as_ref());
+        let without_checksums = ensure_disabled::<OnChain>(&plain);
+
+        // Then
+        assert!(with_checksums.is_err());
+        assert!(without_checksums.is_ok());
+        drop(storage);
+    }
+
+    #[test]
+    fn metadata_and_merge_columns_are_not_covered() {
+        // Given
+        let inner = Arc::new(MemoryStore::<MergeDatabase>::default());
+        let storage = ChecksummedStorage::<MergeDatabase>::new(inner.clone()).unwrap();
+        let counters = crate::database::database_description::test::MergeColumn::Counters;
+
+        // When
+        storage
+            .merge(&[1], counters, Arc::new(1u64.to_be_bytes().to_vec()))
+            .unwrap();
+
+        // Then
+        assert_eq!(
+            storage.get(&[1], counters).unwrap(),
+            inner.get(&[1], counters).unwrap()
+        );
+        assert_eq!(
+            get_metadata::<ValueChecksums, MergeDatabase>(inner.as_ref()).unwrap(),
+            Some(ChecksumMode::Strict)
+        );
+    }
+}
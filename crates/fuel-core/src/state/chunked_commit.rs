@@ -0,0 +1,226 @@
+//! The commit of huge write sets in several batches with bounded memory.
+//!
+//! Chunks are applied in order, but the commit is not atomic anymore. The journal entry
+//! in the metadata column marks the commit in progress, so the interrupted commit
+//! is detectable by [`interrupted_chunked_commit`] after a crash. The journal is updated
+//! in the same batch as each chunk and is removed by the batch of the last chunk.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        metadata::{
+            get_metadata,
+            metadata_write,
+            ChunkedCommitJournal,
+        },
+    },
+    state::TransactableStorage,
+};
+use fuel_core_storage::{
+    kv_store::{
+        KeyValueStore,
+        WriteOperation,
+    },
+    Result as StorageResult,
+};
+use std::iter::Peekable;
+
+/// The limits of one chunk. The chunk ends when any of the limits is reached,
+/// but it always contains at least one entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkLimits {
+    /// The maximum number of entries in the chunk.
+    pub max_entries: usize,
+    /// The maximum total size of keys and values of the chunk in bytes.
+    pub max_bytes: usize,
+}
+
+impl Default for ChunkLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: 100_000,
+            max_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// Writes the `entries` into the `storage` by chunks within the `limits`. The `entries`
+/// are consumed lazily, so only one chunk is in memory at a time.
+/// Returns the number of written chunks.
+///
+/// If a chunk fails, the previous chunks stay applied and the journal remains
+/// in the storage until the next successful chunked commit.
+pub fn chunked_batch_write<Description>(
+    storage: &dyn TransactableStorage<Column = Description::Column>,
+    entries: impl Iterator<Item = (Vec<u8>, Description::Column, WriteOperation)>,
+    limits: ChunkLimits,
+) -> StorageResult<u64>
+where
+    Description: DatabaseDescription,
+{
+    let mut entries = entries.peekable();
+    let mut chunks = 0u64;
+    while entries.peek().is_some() {
+        let mut chunk = next_chunk(&mut entries, limits);
+        chunks = chunks.saturating_add(1);
+        let journal = entries.peek().is_some().then_some(&chunks);
+        chunk.push(metadata_write::<ChunkedCommitJournal, Description>(journal));
+        storage.batch_write(&mut chunk.into_iter())?;
+    }
+    Ok(chunks)
+}
+
+/// Returns the number of chunks applied by the interrupted chunked commit,
+/// or `None` if there is no interrupted commit.
+pub fn interrupted_chunked_commit<Description>(
+    storage: &(impl KeyValueStore<Column = Description::Column> + ?Sized),
+) -> StorageResult<Option<u64>>
+where
+    Description: DatabaseDescription,
+{
+    get_metadata::<ChunkedCommitJournal, Description>(storage)
+}
+
+fn next_chunk<Column, I>(
+    entries: &mut Peekable<I>,
+    limits: ChunkLimits,
+) -> Vec<(Vec<u8>, Column, WriteOperation)>
+where
+    I: Iterator<Item = (Vec<u8>, Column, WriteOperation)>,
+{
+    let mut chunk = vec![];
+    let mut bytes = 0usize;
+    while let Some(entry) = entries.peek() {
+        let size = entry_size(entry);
+        if !chunk.is_empty()
+            && (chunk.len() >= limits.max_entries
+                || bytes.saturating_add(size) > limits.max_bytes)
+        {
+            break
+        }
+        bytes = bytes.saturating_add(size);
+        chunk.extend(entries.next());
+    }
+    chunk
+}
+
+fn entry_size<Column>((key, _, op): &(Vec<u8>, Column, WriteOperation)) -> usize {
+    let value = match op {
+        WriteOperation::Insert(value) | WriteOperation::Merge(value) => value.len(),
+        WriteOperation::Remove => 0,
+    };
+    key.len().saturating_add(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::on_chain::OnChain,
+        state::{
+            faulty::FaultyStorage,
+            in_memory::{
+                memory_store::MemoryStore,
+                transaction::MemoryTransactionView,
+            },
+            IterDirection,
+        },
+    };
+    use fuel_core_storage::column::Column;
+    use std::sync::Arc;
+
+    fn insert(key: u8) -> (Vec<u8>, Column, WriteOperation) {
+        (
+            vec![key],
+            Column::Coins,
+            WriteOperation::Insert(Arc::new(vec![key; 10])),
+        )
+    }
+
+    #[test]
+    fn chunks_respect_limits_and_keep_the_order() {
+        // Given
+        let store = Arc::new(MemoryStore::<OnChain>::default());
+        let faulty = FaultyStorage::<OnChain>::new(store.clone());
+        let entries = (0..10)
+            .map(insert)
+            .chain([(vec![0], Column::Coins, WriteOperation::Remove), insert(1)]);
+        let limits = ChunkLimits {
+            max_entries: 3,
+            max_bytes: usize::MAX,
+        };
+
+        // When
+        let chunks = chunked_batch_write::<OnChain>(&faulty, entries, limits).unwrap();
+
+        // Then
+        assert_eq!(chunks, 4);
+        assert_eq!(faulty.operations(), 4);
+        let keys: Vec<_> = store
+            .iter_all(Column::Coins, None, None, IterDirection::Forward)
+            .map(|item| item.unwrap().0[0])
+            .collect();
+        assert_eq!(keys, (1..10).collect::<Vec<_>>());
+        assert_eq!(
+            interrupted_chunked_commit::<OnChain>(store.as_ref()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn chunks_are_limited_by_bytes() {
+        let store = MemoryStore::<OnChain>::default();
+        // Each entry is 11 bytes, so two entries fit into the chunk.
+        let limits = ChunkLimits {
+            max_entries: usize::MAX,
+            max_bytes: 25,
+        };
+
+        let chunks =
+            chunked_batch_write::<OnChain>(&store, (0..5).map(insert), limits).unwrap();
+
+        assert_eq!(chunks, 3);
+    }
+
+    #[test]
+    fn crash_between_chunks_is_detected() {
+        // Given
+        let store = Arc::new(MemoryStore::<OnChain>::default());
+        let faulty = Arc::new(FaultyStorage::<OnChain>::new(store.clone()));
+        let view = MemoryTransactionView::<OnChain>::new(faulty.clone());
+        for key in 0..10 {
+            let (key, column, _) = insert(key);
+            view.put(&key, column, Arc::new(vec![1])).unwrap();
+        }
+        let limits = ChunkLimits {
+            max_entries: 4,
+            max_bytes: usize::MAX,
+        };
+        faulty.fail_after(2);
+
+        // When
+        let result = view.commit_chunked(limits);
+
+        // Then
+        assert!(result.is_err());
+        assert_eq!(
+            interrupted_chunked_commit::<OnChain>(store.as_ref()).unwrap(),
+            Some(2)
+        );
+        assert_eq!(
+            store
+                .iter_all(Column::Coins, None, None, IterDirection::Forward)
+                .count(),
+            8
+        );
+
+        // The next chunked commit clears the journal.
+        faulty.reset();
+        view.put(&[0xFF], Column::Coins, Arc::new(vec![1])).unwrap();
+        view.commit_chunked(limits).unwrap();
+        assert_eq!(
+            interrupted_chunked_commit::<OnChain>(store.as_ref()).unwrap(),
+            None
+        );
+    }
+}
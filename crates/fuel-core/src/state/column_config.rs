@@ -0,0 +1,416 @@
+//! The tuning of columns of the RocksDB. Columns have different access patterns,
+//! so each column may use its own compression, block size, caching, and write buffers.
+
+use crate::{
+    database::database_description::DatabaseDescription,
+    state::open_mode::OpenMode,
+};
+use fuel_core_storage::kv_store::StorageColumn;
+use std::{
+    collections::HashMap,
+    time::Duration,
+};
+
+/// The compression of the blocks of the column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Lz4,
+    Zstd,
+}
+
+/// The priority of the column in the shared block cache.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CachePriority {
+    /// Data, index, and filter blocks are evicted by the cache in the same way.
+    Low,
+    /// Index and filter blocks of the first level are pinned in the cache and never evicted.
+    High,
+}
+
+/// The configuration of the column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColumnConfig {
+    pub compression: Compression,
+    /// The size of the data block in bytes.
+    pub block_size: usize,
+    /// The priority in the shared block cache. The `None` means the column doesn't use the cache.
+    /// The cache is used only if the database is opened with the capacity.
+    pub block_cache: Option<CachePriority>,
+    /// The size of the single memtable in bytes.
+    pub write_buffer_size: usize,
+    /// The maximum number of memtables, including the one that accepts writes.
+    pub max_write_buffer_number: i32,
+    /// The time after which entries of the column expire. Values of the column are tagged
+    /// with the time of the write by the [`TtlStorage`](crate::state::ttl::TtlStorage),
+    /// and the expired entries are removed by the compaction. Values written before
+    /// the TTL was enabled don't have the tag, so the TTL can't be enabled for a non-empty column.
+    pub ttl: Option<Duration>,
+    /// The size in bytes above which values of the column are compressed with the zstd
+    /// by the [`CompressedStorage`](crate::state::compressed::CompressedStorage).
+    /// Values written before the compression was enabled stay readable, but the compression
+    /// can't be disabled for the column with compressed values. Without the `compression`
+    /// feature, the database with the threshold fails to open.
+    pub compress_values_above: Option<usize>,
+}
+
+impl Default for ColumnConfig {
+    fn default() -> Self {
+        Self {
+            compression: Compression::Lz4,
+            block_size: 4 * 1024,
+            block_cache: Some(CachePriority::High),
+            write_buffer_size: 64 * 1024 * 1024,
+            max_write_buffer_number: 2,
+            ttl: None,
+            compress_values_above: None,
+        }
+    }
+}
+
+impl ColumnConfig {
+    /// The configuration of columns that are written once and rarely read.
+    /// Large blocks compress better and the column doesn't push hot blocks out of the cache.
+    pub fn write_once() -> Self {
+        Self {
+            compression: Compression::Zstd,
+            block_size: 16 * 1024,
+            block_cache: Some(CachePriority::Low),
+            ..Default::default()
+        }
+    }
+
+    /// The configuration of columns that are written often and almost never read.
+    pub fn write_only() -> Self {
+        Self {
+            block_cache: None,
+            ..Default::default()
+        }
+    }
+}
+
+/// The limits of resources of the RocksDB, shared by all columns of the database.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// The number of files kept open by the database, `None` keeps all files open.
+    /// Each column has its own files, so the unlimited database runs out of file
+    /// descriptors of the process with the low `ulimit -n`.
+    pub max_open_files: Option<u32>,
+    /// The number of background flushes and compactions, `None` uses the half of CPUs.
+    pub max_background_jobs: Option<u32>,
+    /// The size of the write-ahead log after which the oldest memtables are flushed.
+    pub max_total_wal_size: u64,
+    /// The size of SST files of the first level of the LSM tree.
+    pub target_file_size: u64,
+    /// The rate in bytes per second of writes of flushes and compactions, including
+    /// manual ones, so they don't starve reads and writes of the node. `None` is unlimited.
+    pub background_io_bytes_per_sec: Option<u64>,
+    /// The memory in bytes shared by the block cache and memtables of all columns, see
+    /// [`Self::write_buffer_budget`]. It replaces the capacity passed to the open, and
+    /// columns with the larger `write_buffer_size` use the whole write buffer budget.
+    /// `None` sizes caches by the capacity, and memtables by the configuration of columns.
+    pub memory_budget_bytes: Option<usize>,
+    /// The number of files on the first level of the column at which writes are slowed.
+    pub level0_slowdown_writes_trigger: u32,
+    /// The number of files on the first level of the column at which writes are stopped.
+    pub level0_stop_writes_trigger: u32,
+    /// The size of data pending for the compaction of the column at which writes are slowed.
+    pub soft_pending_compaction_bytes: u64,
+    /// The size of data pending for the compaction of the column at which writes are stopped.
+    pub hard_pending_compaction_bytes: u64,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            // The node opens three databases, so they fit into the default
+            // soft limit of 1024 file descriptors on Linux together with sockets.
+            max_open_files: Some(256),
+            max_background_jobs: None,
+            max_total_wal_size: 64 * 1024 * 1024,
+            target_file_size: 64 * 1024 * 1024,
+            background_io_bytes_per_sec: None,
+            memory_budget_bytes: None,
+            // Defaults of the RocksDB.
+            level0_slowdown_writes_trigger: 20,
+            level0_stop_writes_trigger: 36,
+            soft_pending_compaction_bytes: 64 * 1024 * 1024 * 1024,
+            hard_pending_compaction_bytes: 256 * 1024 * 1024 * 1024,
+        }
+    }
+}
+
+impl ResourceLimits {
+    /// Returns the part of the [`Self::memory_budget_bytes`] used by memtables,
+    /// the rest is used by the block cache.
+    pub fn write_buffer_budget(&self) -> Option<usize> {
+        self.memory_budget_bytes.map(|budget| budget / 4)
+    }
+
+    /// Returns the number of files that the database may keep open within the `soft_limit`
+    /// of file descriptors of the process. The half of the limit is left for other files
+    /// and sockets. `None` keeps all files open.
+    pub fn max_open_files_within(&self, soft_limit: Option<u64>) -> Option<u32> {
+        let Some(soft_limit) = soft_limit else {
+            return self.max_open_files
+        };
+        let available = u32::try_from(soft_limit / 2).unwrap_or(u32::MAX);
+        match self.max_open_files {
+            Some(max_open_files) if max_open_files <= available => Some(max_open_files),
+            _ => Some(available),
+        }
+    }
+}
+
+/// The overrides of the [`DatabaseDescription::column_config`] by the name of the database
+/// and the name of the column. Columns without the override use the default configuration,
+/// and overrides of unknown columns are ignored.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ColumnsConfig {
+    overrides: HashMap<(String, String), ColumnConfig>,
+    deny_unknown_column_families: bool,
+    drop_retired_columns_with_data: bool,
+    open_mode: OpenMode,
+    limits: ResourceLimits,
+    statistics: bool,
+    strict_keys: bool,
+    value_checksums: bool,
+}
+
+impl ColumnsConfig {
+    /// Overrides the configuration of the `column` of the `database`.
+    pub fn with_column(
+        mut self,
+        database: &str,
+        column: &str,
+        config: ColumnConfig,
+    ) -> Self {
+        self.overrides
+            .insert((database.to_string(), column.to_string()), config);
+        self
+    }
+
+    /// Refuses to open the database with column families unknown to the `Description`,
+    /// e.g. written by a newer version of the node. By default, unknown column families
+    /// are opened and left untouched.
+    pub fn deny_unknown_column_families(mut self, deny: bool) -> Self {
+        self.deny_unknown_column_families = deny;
+        self
+    }
+
+    /// Returns `true` if the database with unknown column families must not be opened.
+    pub fn denies_unknown_column_families(&self) -> bool {
+        self.deny_unknown_column_families
+    }
+
+    /// Drops column families of [retired](DatabaseDescription::retired_columns) columns
+    /// even if they still have data. By default, only empty column families are dropped,
+    /// and the database with the data in the retired column is not opened.
+    pub fn drop_retired_columns_with_data(mut self, drop: bool) -> Self {
+        self.drop_retired_columns_with_data = drop;
+        self
+    }
+
+    /// Returns `true` if the data of retired columns may be dropped.
+    pub fn drops_retired_columns_with_data(&self) -> bool {
+        self.drop_retired_columns_with_data
+    }
+
+    /// Sets the `mode` of opening the database when it is missing or already exists.
+    /// By default, the missing database is created.
+    pub fn with_open_mode(mut self, mode: OpenMode) -> Self {
+        self.open_mode = mode;
+        self
+    }
+
+    /// Returns the mode of opening the database.
+    pub fn open_mode(&self) -> OpenMode {
+        self.open_mode
+    }
+
+    /// Sets the `limits` of resources of the database.
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Returns the limits of resources of the database.
+    pub fn limits(&self) -> &ResourceLimits {
+        &self.limits
+    }
+
+    /// Enables the statistics of the RocksDB, e.g. counters of the block cache and stalls
+    /// of writes. The statistics slow down every operation a little, so they are disabled
+    /// by default. Properties of the database and columns are available anyway.
+    pub fn enable_statistics(mut self, enable: bool) -> Self {
+        self.statistics = enable;
+        self
+    }
+
+    /// Returns `true` if the statistics of the RocksDB are enabled.
+    pub fn statistics_enabled(&self) -> bool {
+        self.statistics
+    }
+
+    /// Rejects writes of keys that don't have the format of the column in release builds
+    /// too, see [`StrictKeysStorage`](crate::state::strict_keys::StrictKeysStorage).
+    /// By default, keys are checked only by debug assertions of tables.
+    pub fn enable_strict_keys(mut self, enable: bool) -> Self {
+        self.strict_keys = enable;
+        self
+    }
+
+    /// Returns `true` if keys of all writes are checked.
+    pub fn strict_keys_enabled(&self) -> bool {
+        self.strict_keys
+    }
+
+    /// Stores values with checksums verified on every read, see
+    /// [`ChecksummedStorage`](crate::state::checksummed::ChecksummedStorage).
+    /// The database with checksums can't be opened without them.
+    pub fn enable_value_checksums(mut self, enable: bool) -> Self {
+        self.value_checksums = enable;
+        self
+    }
+
+    /// Returns `true` if values are stored with checksums.
+    pub fn value_checksums_enabled(&self) -> bool {
+        self.value_checksums
+    }
+
+    /// Returns the configuration of the `column` of the `Description` database.
+    pub fn column<Description>(&self, column: Description::Column) -> ColumnConfig
+    where
+        Description: DatabaseDescription,
+    {
+        self.overrides
+            .get(&(Description::name().to_string(), column.name().to_string()))
+            .copied()
+            .unwrap_or_else(|| Description::column_config(&column))
+    }
+
+    /// Returns the columns of the `Description` database with the TTL.
+    pub fn ttl_columns<Description>(&self) -> Vec<(Description::Column, Duration)>
+    where
+        Description: DatabaseDescription,
+    {
+        enum_iterator::all::<Description::Column>()
+            .filter_map(|column| Some((column, self.column::<Description>(column).ttl?)))
+            .collect()
+    }
+
+    /// Returns the columns of the `Description` database with the compression of values
+    /// and their thresholds.
+    pub fn compressed_columns<Description>(&self) -> Vec<(Description::Column, usize)>
+    where
+        Description: DatabaseDescription,
+    {
+        enum_iterator::all::<Description::Column>()
+            .filter_map(|column| {
+                Some((
+                    column,
+                    self.column::<Description>(column).compress_values_above?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Returns the names of the overridden columns that the `Description` database doesn't have.
+    pub fn unknown_columns<Description>(&self) -> Vec<&str>
+    where
+        Description: DatabaseDescription,
+    {
+        self.overrides
+            .keys()
+            .filter(|(database, _)| database == Description::name())
+            .filter(|(_, name)| {
+                !enum_iterator::all::<Description::Column>()
+                    .any(|column| column.name() == name)
+            })
+            .map(|(_, name)| name.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::database_description::{
+        off_chain::OffChain,
+        on_chain::OnChain,
+    };
+    use fuel_core_storage::column::Column;
+
+    #[test]
+    fn overrides_apply_only_to_their_database_and_column() {
+        // Given
+        let custom = ColumnConfig {
+            compression: Compression::None,
+            ..Default::default()
+        };
+        let config = ColumnsConfig::default()
+            .with_column(OnChain::name(), Column::Metadata.name(), custom)
+            .with_column(OnChain::name(), "Unknown", custom)
+            .with_column("unknown", Column::Coins.name(), custom);
+
+        // Then
+        assert_eq!(config.column::<OnChain>(Column::Metadata), custom);
+        assert_eq!(
+            config.column::<OnChain>(Column::Coins),
+            OnChain::column_config(&Column::Coins)
+        );
+        assert_eq!(
+            config.column::<OffChain>(
+                crate::fuel_core_graphql_api::storage::Column::Metadata
+            ),
+            ColumnConfig::default()
+        );
+        assert_eq!(config.unknown_columns::<OnChain>(), vec!["Unknown"]);
+    }
+
+    #[test]
+    fn columns_have_built_in_defaults() {
+        assert_eq!(
+            OnChain::column_config(&Column::Transactions),
+            ColumnConfig::write_once()
+        );
+        assert_eq!(
+            OnChain::column_config(&Column::ContractsState),
+            ColumnConfig::default()
+        );
+        assert_eq!(
+            OnChain::column_config(&Column::UndoLog),
+            ColumnConfig::write_only()
+        );
+    }
+
+    #[test]
+    fn max_open_files_is_clamped_to_the_half_of_the_soft_limit() {
+        // Given
+        let limited = ResourceLimits::default();
+        let unlimited = ResourceLimits {
+            max_open_files: None,
+            ..Default::default()
+        };
+
+        // Then
+        assert_eq!(limited.max_open_files_within(Some(1024)), Some(256));
+        assert_eq!(limited.max_open_files_within(Some(256)), Some(128));
+        assert_eq!(limited.max_open_files_within(None), Some(256));
+        assert_eq!(unlimited.max_open_files_within(Some(1024)), Some(512));
+        assert_eq!(unlimited.max_open_files_within(None), None);
+    }
+
+    #[test]
+    fn quarter_of_the_memory_budget_is_used_by_memtables() {
+        // Given
+        let limits = ResourceLimits {
+            memory_budget_bytes: Some(64 * 1024 * 1024),
+            ..Default::default()
+        };
+
+        // Then
+        assert_eq!(limits.write_buffer_budget(), Some(16 * 1024 * 1024));
+        assert_eq!(ResourceLimits::default().write_buffer_budget(), None);
+    }
+}
@@ -0,0 +1,124 @@
+//! The iterator over the column that lends keys and values from its internal buffers
+//! instead of allocating them for each item. See [`ColumnIter`].
+
+use crate::state::in_memory::memory_store::MemoryColumnIter;
+use fuel_core_storage::{
+    iter::BoxedIter,
+    kv_store::{
+        KVItem,
+        Value,
+    },
+    Result as StorageResult,
+};
+
+#[cfg(feature = "rocksdb")]
+type RocksDBIter<'a> = crate::state::rocks_db_key_iterator::RocksDBColumnIter<
+    'a,
+    rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+>;
+
+/// The cursor over the sorted entries of the column. It is the building block of
+/// the [`ColumnIter`], and it allows merging several cursors without copying their items.
+pub trait RawColumnIter {
+    /// Moves the cursor to the next entry. The first call moves it to the first entry.
+    fn advance(&mut self) -> StorageResult<()>;
+
+    /// Returns the entry under the cursor, or `None` if the cursor is exhausted.
+    fn current(&self) -> Option<(&[u8], &[u8])>;
+}
+
+/// The iterator over the column that yields `(key, value)` borrowed from the iterator.
+/// The item is valid until the next call to [`ColumnIter::next`], so the iteration
+/// doesn't allocate for each item. Use [`ColumnIter::next`] in the `while let` loop.
+///
+/// The iterator is a concrete type over the backends, so it doesn't allocate on creation
+/// either, except for the backends that fall back to the boxed iterator.
+pub struct ColumnIter<'a> {
+    inner: Inner<'a>,
+    done: bool,
+}
+
+enum Inner<'a> {
+    #[cfg(feature = "rocksdb")]
+    RocksDb(RocksDBIter<'a>),
+    Memory(MemoryColumnIter),
+    /// The iterator of the layered storage, e.g. the transaction view.
+    Layered(Box<dyn RawColumnIter + 'a>),
+    /// The fallback to the boxed iterator. The current item is owned by the iterator.
+    Boxed {
+        iter: BoxedIter<'a, KVItem>,
+        current: Option<(Vec<u8>, Value)>,
+    },
+}
+
+impl<'a> ColumnIter<'a> {
+    #[cfg(feature = "rocksdb")]
+    pub(crate) fn rocks_db(iter: RocksDBIter<'a>) -> Self {
+        Self::new(Inner::RocksDb(iter))
+    }
+
+    pub(crate) fn memory(iter: MemoryColumnIter) -> Self {
+        Self::new(Inner::Memory(iter))
+    }
+
+    pub(crate) fn layered(iter: impl RawColumnIter + 'a) -> Self {
+        Self::new(Inner::Layered(Box::new(iter)))
+    }
+
+    /// Creates the iterator from the boxed iterator, e.g. of the storage
+    /// without its own implementation of the [`ColumnIter`].
+    pub fn boxed(iter: BoxedIter<'a, KVItem>) -> Self {
+        Self::new(Inner::Boxed {
+            iter,
+            current: None,
+        })
+    }
+
+    fn new(inner: Inner<'a>) -> Self {
+        Self { inner, done: false }
+    }
+
+    /// Returns the next entry. The error ends the iteration.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<StorageResult<(&[u8], &[u8])>> {
+        if self.done {
+            return None
+        }
+        if let Err(err) = self.advance() {
+            self.done = true;
+            return Some(Err(err))
+        }
+        if self.current().is_none() {
+            self.done = true;
+            return None
+        }
+        self.current().map(Ok)
+    }
+}
+
+impl<'a> RawColumnIter for ColumnIter<'a> {
+    fn advance(&mut self) -> StorageResult<()> {
+        match &mut self.inner {
+            #[cfg(feature = "rocksdb")]
+            Inner::RocksDb(iter) => iter.advance(),
+            Inner::Memory(iter) => iter.advance(),
+            Inner::Layered(iter) => iter.advance(),
+            Inner::Boxed { iter, current } => {
+                *current = iter.next().transpose()?;
+                Ok(())
+            }
+        }
+    }
+
+    fn current(&self) -> Option<(&[u8], &[u8])> {
+        match &self.inner {
+            #[cfg(feature = "rocksdb")]
+            Inner::RocksDb(iter) => iter.current(),
+            Inner::Memory(iter) => iter.current(),
+            Inner::Layered(iter) => iter.current(),
+            Inner::Boxed { current, .. } => current
+                .as_ref()
+                .map(|(key, value)| (key.as_slice(), value.as_slice())),
+        }
+    }
+}
@@ -0,0 +1,879 @@
+//! The compression of values of columns, see [`ColumnConfig::compress_values_above`].
+//!
+//! The [`CompressedStorage`] compresses values larger than the threshold of the column
+//! with the zstd on writes and decompresses them on reads, so it works the same way
+//! for all backends and the exported state. The compressed value starts with the
+//! [`COMPRESSED`] header followed by the zstd frame. Values without the header are read
+//! as is, so values written before the compression was enabled stay readable and are
+//! compressed when they are rewritten.
+//!
+//! [`ColumnConfig::compress_values_above`]: crate::state::column_config::ColumnConfig::compress_values_above
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Result as DatabaseResult,
+    },
+    state::{
+        column_config::ColumnsConfig,
+        column_iter::ColumnIter,
+        notifying::StorageEvent,
+        update_encoded,
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        EngineStats,
+        IterDirection,
+        TransactableStorage,
+        WriteDurability,
+        WritePressure,
+    },
+};
+use fuel_core_storage::{
+    iter::{
+        BoxedIter,
+        IntoBoxedIter,
+        IterConfig,
+        IteratorableStore,
+        PageIter,
+    },
+    kv_store::{
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Error as StorageError,
+    Result as StorageResult,
+};
+use std::{
+    path::Path,
+    sync::Arc,
+};
+use tokio::sync::broadcast;
+
+/// The header of the compressed value.
+pub const COMPRESSED: u8 = 0xC5;
+
+/// The magic number that starts each zstd frame.
+const ZSTD_MAGIC: [u8; 4] = 0xFD2F_B528u32.to_le_bytes();
+
+/// Returns `true` if the stored `value` is compressed. The header must be followed
+/// by the zstd frame, so the raw value is mistaken for the compressed one only if
+/// it starts with the same five bytes.
+fn is_compressed(value: &[u8]) -> bool {
+    value.first() == Some(&COMPRESSED) && value.get(1..5) == Some(&ZSTD_MAGIC[..])
+}
+
+/// Returns the value to store for the `value` of the column with the `threshold`.
+fn compress(value: Value, threshold: usize) -> StorageResult<Value> {
+    // The raw value that looks compressed is always compressed, so it is read back as is.
+    let looks_compressed = is_compressed(&value);
+    if value.len() <= threshold && !looks_compressed {
+        return Ok(value)
+    }
+    let frame = zstd::bulk::compress(&value, zstd::DEFAULT_COMPRESSION_LEVEL)
+        .map_err(|e| StorageError::Other(e.into()))?;
+    if frame.len() >= value.len() && !looks_compressed {
+        // The value doesn't compress, e.g. it is a hash, so it is cheaper to store it raw.
+        return Ok(value)
+    }
+    let mut compressed = Vec::with_capacity(frame.len().saturating_add(1));
+    compressed.push(COMPRESSED);
+    compressed.extend_from_slice(&frame);
+    Ok(Arc::new(compressed))
+}
+
+/// Returns the size of the zstd `frame` after the decompression, if the frame has it.
+fn content_size(frame: &[u8]) -> Option<usize> {
+    let size = zstd::zstd_safe::get_frame_content_size(frame).ok()??;
+    usize::try_from(size).ok()
+}
+
+fn decompress_frame<Column: StorageColumn>(
+    column: Column,
+    frame: &[u8],
+) -> StorageResult<Vec<u8>> {
+    match content_size(frame) {
+        Some(size) => zstd::bulk::decompress(frame, size),
+        None => zstd::decode_all(frame),
+    }
+    .map_err(|e| {
+        StorageError::Other(anyhow::anyhow!(
+            "Unable to decompress the value of the column `{}`: {e}",
+            column.name()
+        ))
+    })
+}
+
+/// Returns the value for the stored `value` of the `column`.
+fn decompress<Column: StorageColumn>(
+    column: Column,
+    value: Value,
+) -> StorageResult<Value> {
+    if !is_compressed(&value) {
+        return Ok(value)
+    }
+    decompress_frame(column, &value[1..]).map(Arc::new)
+}
+
+/// The storage that compresses values of columns with the compression threshold.
+/// Other columns are passed to the inner storage as is.
+///
+/// Only columns with the compression enabled are decompressed, so the compression
+/// of the column can't be disabled while it has compressed values.
+#[derive(Debug)]
+pub struct CompressedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    inner: DataSourceInner<Description::Column>,
+    /// The size above which values are compressed, of each column by its index.
+    thresholds: Arc<Vec<Option<usize>>>,
+}
+
+impl<Description> CompressedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Wraps the `inner` storage with the compression of columns from the `config`.
+    /// Columns with the merge operator can't be compressed.
+    pub fn new<D>(inner: D, config: &ColumnsConfig) -> DatabaseResult<Self>
+    where
+        D: Into<DataSource<Description>>,
+    {
+        let mut thresholds =
+            vec![None; enum_iterator::all::<Description::Column>().count()];
+        for (column, threshold) in config.compressed_columns::<Description>() {
+            if column.merge_operator().is_some() {
+                return Err(anyhow::anyhow!(
+                    "The column `{}` with the merge operator can't be compressed",
+                    column.name()
+                )
+                .into())
+            }
+            thresholds[column.as_usize()] = Some(threshold);
+        }
+        Ok(Self {
+            inner: inner.into().0,
+            thresholds: Arc::new(thresholds),
+        })
+    }
+
+    fn threshold(&self, column: Description::Column) -> Option<usize> {
+        self.thresholds.get(column.as_usize()).copied().flatten()
+    }
+
+    /// Returns the value of the `column` for the stored `value`.
+    fn read_value(
+        &self,
+        column: Description::Column,
+        value: Option<Value>,
+    ) -> StorageResult<Option<Value>> {
+        match (value, self.threshold(column)) {
+            (Some(value), Some(_)) => decompress(column, value).map(Some),
+            (value, _) => Ok(value),
+        }
+    }
+
+    /// Decompresses values of the `iter` over the `column`.
+    fn decompressed(
+        column: Description::Column,
+        iter: BoxedIter<KVItem>,
+    ) -> BoxedIter<KVItem> {
+        iter.map(move |item| {
+            let (key, value) = item?;
+            Ok((key, decompress(column, value)?))
+        })
+        .into_boxed()
+    }
+}
+
+impl<Description> KeyValueStore for CompressedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        match self.threshold(column) {
+            Some(threshold) => self.inner.put(key, column, compress(value, threshold)?),
+            None => self.inner.put(key, column, value),
+        }
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        match self.threshold(column) {
+            Some(threshold) => {
+                let old = self
+                    .inner
+                    .replace(key, column, compress(value, threshold)?)?;
+                self.read_value(column, old)
+            }
+            None => self.inner.replace(key, column, value),
+        }
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        match self.threshold(column) {
+            Some(threshold) => {
+                let value = compress(Arc::new(buf.to_vec()), threshold)?;
+                self.inner.put(key, column, value)?;
+                Ok(buf.len())
+            }
+            None => self.inner.write(key, column, buf),
+        }
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        let value = self.inner.take(key, column)?;
+        self.read_value(column, value)
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        self.inner.delete(key, column)
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        // Compressed columns don't have the merge operator, so the inner storage rejects it.
+        self.inner.merge(key, column, operand)
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        self.inner.exists(key, column)
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        if self.threshold(column).is_none() {
+            return self.inner.size_of_value(key, column)
+        }
+        // The zstd frame stores the size of the content, so the value isn't decompressed.
+        let mut size = Ok(0);
+        let found = self.inner.get_with(key, column, &mut |value| {
+            size = match is_compressed(value) {
+                true => content_size(&value[1..]).map_or_else(
+                    || decompress_frame(column, &value[1..]).map(|value| value.len()),
+                    Ok,
+                ),
+                false => Ok(value.len()),
+            };
+        })?;
+        found.then_some(size).transpose()
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        let value = self.inner.get(key, column)?;
+        self.read_value(column, value)
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        if self.threshold(column).is_none() {
+            return self.inner.get_with(key, column, f)
+        }
+        // Raw values are passed to the `f` without the copy.
+        let mut decompressed = Ok(());
+        let found = self.inner.get_with(key, column, &mut |value| {
+            if is_compressed(value) {
+                decompressed =
+                    decompress_frame(column, &value[1..]).map(|value| f(&value));
+            } else {
+                f(value)
+            }
+        })?;
+        decompressed?;
+        Ok(found)
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        let values = self.inner.multi_get(keys, column)?;
+        match self.threshold(column) {
+            Some(_) => values
+                .into_iter()
+                .map(|value| self.read_value(column, value))
+                .collect(),
+            None => Ok(values),
+        }
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        let Some(threshold) = self.threshold(column) else {
+            return self.inner.compare_and_swap(key, column, expected, new)
+        };
+        // The swap compares stored values, so the expected value is replaced
+        // by the stored one that it matches.
+        let stored = self.inner.get(key, column)?;
+        let current = self.read_value(column, stored.clone())?;
+        if current.as_deref().map(Vec::as_slice) != expected {
+            return Ok(Err(current))
+        }
+        match self.inner.compare_and_swap(
+            key,
+            column,
+            stored.as_deref().map(Vec::as_slice),
+            compress(new, threshold)?,
+        )? {
+            Ok(()) => Ok(Ok(())),
+            Err(stored) => Ok(Err(self.read_value(column, stored)?)),
+        }
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        let Some(threshold) = self.threshold(column) else {
+            return self.inner.update(key, column, f)
+        };
+        update_encoded(
+            self.inner.as_ref(),
+            key,
+            column,
+            f,
+            &|stored| Ok(Some(decompress(column, Arc::new(stored.to_vec()))?.to_vec())),
+            &|value| Ok(compress(Arc::new(value), threshold)?.to_vec()),
+        )
+    }
+}
+
+impl<Description> IteratorableStore for CompressedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        let iter = self.inner.iter_all(column, prefix, start, direction);
+        match self.threshold(column) {
+            Some(_) => Self::decompressed(column, iter),
+            None => iter,
+        }
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        let iter = self.inner.iter_range(column, prefix, start, end, direction);
+        match self.threshold(column) {
+            Some(_) => Self::decompressed(column, iter),
+            None => iter,
+        }
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.inner.iter_keys(column, prefix, start, direction)
+    }
+
+    fn has_any(&self, column: Self::Column, prefix: &[u8]) -> StorageResult<bool> {
+        self.inner.has_any(column, prefix)
+    }
+
+    fn iter(&self, column: Self::Column, config: IterConfig) -> PageIter {
+        let iter = self.inner.iter(column, config);
+        match self.threshold(column) {
+            Some(_) => PageIter::new(Self::decompressed(column, iter.into_boxed())),
+            None => iter,
+        }
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        self.inner.delete_all(column, prefix)
+    }
+}
+
+impl<Description> BatchOperations for CompressedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for CompressedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        // The error of the compression stops the batch before the inner storage applies it.
+        let entries = entries
+            .map(|(key, column, op)| match (op, self.threshold(column)) {
+                (WriteOperation::Insert(value), Some(threshold)) => {
+                    let value = compress(value, threshold)?;
+                    Ok((key, column, WriteOperation::Insert(value)))
+                }
+                (op, _) => Ok((key, column, op)),
+            })
+            .collect::<StorageResult<Vec<_>>>()?;
+        self.inner
+            .batch_write_with(&mut entries.into_iter(), durability)
+    }
+
+    fn iter_column(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> ColumnIter<'_> {
+        match self.threshold(column) {
+            Some(_) => ColumnIter::boxed(self.iter_all(column, prefix, start, direction)),
+            None => self.inner.iter_column(column, prefix, start, direction),
+        }
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        self.inner.flush()
+    }
+
+    /// Returns the statistics of stored values, i.e. sizes of compressed values.
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        self.inner.column_stats(column)
+    }
+
+    fn engine_stats(&self) -> DatabaseResult<EngineStats> {
+        self.inner.engine_stats()
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        self.inner.write_pressure()
+    }
+
+    fn size_of_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> DatabaseResult<u64> {
+        self.inner.size_of_range(column, start, end)
+    }
+
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        self.inner.checkpoint(path)
+    }
+
+    fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()> {
+        self.inner.backup_to(backup_dir)
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        self.inner.compact(column, range)
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        self.inner.clear_column(column)
+    }
+
+    fn multi_delete(
+        &self,
+        column: Self::Column,
+        keys: &[Vec<u8>],
+    ) -> DatabaseResult<usize> {
+        self.inner.multi_delete(column, keys)
+    }
+
+    fn delete_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: &[u8],
+    ) -> DatabaseResult<()> {
+        self.inner.delete_range(column, start, end)
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        // The snapshot stores compressed values, so it is wrapped too.
+        Ok(Arc::new(Self {
+            inner: self.inner.snapshot()?,
+            thresholds: self.thresholds.clone(),
+        }))
+    }
+
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        self.inner.subscribe(column, prefix)
+    }
+
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.inner.replay_change_log(from_seq, into)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.inner.truncate_change_log(seq)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        self.inner.freeze_columns(columns)
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        self.inner.unfreeze_columns(columns)
+    }
+
+    fn begin_shutdown(&self) {
+        self.inner.begin_shutdown()
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        self.inner.finish_shutdown()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::{
+            on_chain::OnChain,
+            test::MergeDatabase,
+        },
+        state::{
+            column_config::ColumnConfig,
+            in_memory::memory_store::MemoryStore,
+        },
+    };
+    use fuel_core_storage::column::Column;
+
+    const THRESHOLD: usize = 16;
+
+    fn compression_config() -> ColumnsConfig {
+        ColumnsConfig::default().with_column(
+            OnChain::name(),
+            Column::Transactions.name(),
+            ColumnConfig {
+                compress_values_above: Some(THRESHOLD),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Returns the storages of all backends, persistent ones are in the `dir`.
+    #[cfg_attr(
+        not(any(feature = "rocksdb", feature = "redb")),
+        allow(unused_variables)
+    )]
+    fn backends(dir: &Path) -> Vec<DataSource<OnChain>> {
+        vec![
+            Arc::new(MemoryStore::<OnChain>::default()).into(),
+            #[cfg(feature = "rocksdb")]
+            {
+                let db = crate::state::rocks_db::RocksDb::<OnChain>::default_open(
+                    dir.join("rocksdb"),
+                    None,
+                )
+                .unwrap();
+                Arc::new(db).into()
+            },
+            #[cfg(feature = "redb")]
+            {
+                let db = crate::state::redb_store::RedbStore::<OnChain>::open(
+                    dir.join("redb"),
+                    None,
+                )
+                .unwrap();
+                Arc::new(db).into()
+            },
+        ]
+    }
+
+    fn compressible(seed: u8) -> Value {
+        Arc::new(vec![seed; 1024])
+    }
+
+    #[test]
+    fn compressed_values_round_trip_on_all_backends() {
+        let tmp = tempfile::tempdir().unwrap();
+        for inner in backends(tmp.path()) {
+            // Given
+            let store =
+                CompressedStorage::<OnChain>::new(inner.clone(), &compression_config())
+                    .unwrap();
+            let small = Arc::new(vec![1; THRESHOLD]);
+
+            // When
+            store
+                .put(&[1], Column::Transactions, compressible(1))
+                .unwrap();
+            store
+                .put(&[2], Column::Transactions, small.clone())
+                .unwrap();
+            store.put(&[1], Column::Coins, compressible(1)).unwrap();
+
+            // Then
+            assert_eq!(
+                store.get(&[1], Column::Transactions).unwrap(),
+                Some(compressible(1))
+            );
+            assert_eq!(
+                store.get(&[2], Column::Transactions).unwrap(),
+                Some(small.clone())
+            );
+            let stored = inner.get(&[1], Column::Transactions).unwrap().unwrap();
+            assert_eq!(stored[0], COMPRESSED);
+            assert!(stored.len() < compressible(1).len());
+            assert_eq!(inner.get(&[2], Column::Transactions).unwrap(), Some(small));
+            assert_eq!(
+                inner.get(&[1], Column::Coins).unwrap(),
+                Some(compressible(1))
+            );
+        }
+    }
+
+    #[test]
+    fn values_written_before_the_compression_are_readable() {
+        // Given
+        let inner = Arc::new(MemoryStore::<OnChain>::default());
+        inner
+            .put(&[1], Column::Transactions, compressible(1))
+            .unwrap();
+        let store =
+            CompressedStorage::<OnChain>::new(inner.clone(), &compression_config())
+                .unwrap();
+
+        // When
+        let value = store.get(&[1], Column::Transactions).unwrap();
+        let size = store.size_of_value(&[1], Column::Transactions).unwrap();
+        store
+            .put(&[1], Column::Transactions, value.clone().unwrap())
+            .unwrap();
+
+        // Then
+        assert_eq!(value, Some(compressible(1)));
+        assert_eq!(size, Some(1024));
+        assert!(is_compressed(
+            &inner.get(&[1], Column::Transactions).unwrap().unwrap()
+        ));
+        assert_eq!(
+            store.get(&[1], Column::Transactions).unwrap(),
+            Some(compressible(1))
+        );
+    }
+
+    #[test]
+    fn iter_all_yields_decompressed_values_in_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        for inner in backends(tmp.path()) {
+            // Given
+            let store =
+                CompressedStorage::<OnChain>::new(inner, &compression_config()).unwrap();
+            for key in [3u8, 1, 2] {
+                store
+                    .put(&[key], Column::Transactions, compressible(key))
+                    .unwrap();
+            }
+            let expected = vec![
+                (vec![1], compressible(1)),
+                (vec![2], compressible(2)),
+                (vec![3], compressible(3)),
+            ];
+
+            // When
+            let forward = store
+                .iter_all(Column::Transactions, None, None, IterDirection::Forward)
+                .collect::<StorageResult<Vec<_>>>()
+                .unwrap();
+            let reverse = store
+                .iter_all(Column::Transactions, None, None, IterDirection::Reverse)
+                .collect::<StorageResult<Vec<_>>>()
+                .unwrap();
+
+            // Then
+            assert_eq!(forward, expected);
+            assert_eq!(reverse, expected.into_iter().rev().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn exists_delete_and_size_work_with_compressed_values() {
+        // Given
+        let store = CompressedStorage::<OnChain>::new(
+            Arc::new(MemoryStore::<OnChain>::default()),
+            &compression_config(),
+        )
+        .unwrap();
+        store
+            .put(&[1], Column::Transactions, compressible(1))
+            .unwrap();
+
+        // When
+        let exists = store.exists(&[1], Column::Transactions).unwrap();
+        let size = store.size_of_value(&[1], Column::Transactions).unwrap();
+        store.delete(&[1], Column::Transactions).unwrap();
+
+        // Then
+        assert!(exists);
+        assert_eq!(size, Some(1024));
+        assert!(!store.exists(&[1], Column::Transactions).unwrap());
+        assert_eq!(
+            store.size_of_value(&[1], Column::Transactions).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn compare_and_swap_and_batches_compare_decompressed_values() {
+        // Given
+        let inner = Arc::new(MemoryStore::<OnChain>::default());
+        let store =
+            CompressedStorage::<OnChain>::new(inner.clone(), &compression_config())
+                .unwrap();
+        store
+            .batch_write(
+                &mut vec![(
+                    vec![1],
+                    Column::Transactions,
+                    WriteOperation::Insert(compressible(1)),
+                )]
+                .into_iter(),
+            )
+            .unwrap();
+
+        // When
+        let mismatched = store
+            .compare_and_swap(&[1], Column::Transactions, None, compressible(2))
+            .unwrap();
+        let swapped = store
+            .compare_and_swap(
+                &[1],
+                Column::Transactions,
+                Some(&compressible(1)),
+                compressible(2),
+            )
+            .unwrap();
+
+        // Then
+        assert_eq!(mismatched, Err(Some(compressible(1))));
+        assert_eq!(swapped, Ok(()));
+        assert!(is_compressed(
+            &inner.get(&[1], Column::Transactions).unwrap().unwrap()
+        ));
+        assert_eq!(
+            store.take(&[1], Column::Transactions).unwrap(),
+            Some(compressible(2))
+        );
+    }
+
+    #[test]
+    fn raw_value_that_looks_compressed_round_trips() {
+        // Given
+        let store = CompressedStorage::<OnChain>::new(
+            Arc::new(MemoryStore::<OnChain>::default()),
+            &compression_config(),
+        )
+        .unwrap();
+        let mut value = vec![COMPRESSED];
+        value.extend_from_slice(&ZSTD_MAGIC);
+        let value = Arc::new(value);
+
+        // When
+        store
+            .put(&[1], Column::Transactions, value.clone())
+            .unwrap();
+
+        // Then
+        assert_eq!(store.get(&[1], Column::Transactions).unwrap(), Some(value));
+    }
+
+    #[test]
+    fn columns_with_merge_operator_cant_be_compressed() {
+        // Given
+        let config = ColumnsConfig::default().with_column(
+            MergeDatabase::name(),
+            crate::database::database_description::test::MergeColumn::Counters.name(),
+            ColumnConfig {
+                compress_values_above: Some(THRESHOLD),
+                ..Default::default()
+            },
+        );
+
+        // When
+        let result = CompressedStorage::<MergeDatabase>::new(
+            Arc::new(MemoryStore::<MergeDatabase>::default()),
+            &config,
+        );
+
+        // Then
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,432 @@
+//! Opaque cursors for the pagination of columns across the API boundary.
+//!
+//! The [`Cursor`] remembers the last seen key of the page together with the column
+//! and the direction of the iteration. Clients get it as the opaque string and pass it
+//! back for the next page. The string is protected by the checksum, and the decoded
+//! cursor is validated against the request, so the stale or tampered cursor fails with
+//! [`DatabaseError::InvalidCursor`] instead of yielding the page of another column
+//! or of another prefix.
+
+use crate::{
+    database::{
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::strict_keys::check_key,
+};
+use core::{
+    fmt,
+    ops::Bound,
+    str::FromStr,
+};
+use fuel_core_storage::{
+    iter::{
+        IterConfig,
+        IterDirection,
+        IteratorableStore,
+    },
+    kv_store::{
+        StorageColumn,
+        Value,
+    },
+    Result as StorageResult,
+};
+
+/// The version of the encoding of cursors.
+const VERSION: u8 = 0;
+/// The version, the id of the column, and the direction.
+const HEADER_SIZE: usize = 6;
+const CHECKSUM_SIZE: usize = 4;
+
+/// The position of the page in the column: the last key of the previous page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    column: u32,
+    direction: IterDirection,
+    key: Vec<u8>,
+}
+
+impl Cursor {
+    /// Creates the cursor after the `key` of the `column` iterated in the `direction`.
+    pub fn new<Column: StorageColumn>(
+        column: Column,
+        direction: IterDirection,
+        key: Vec<u8>,
+    ) -> Self {
+        Self {
+            column: column.id(),
+            direction,
+            key,
+        }
+    }
+
+    /// Returns the last seen key.
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// Returns the direction of the iteration.
+    pub fn direction(&self) -> IterDirection {
+        self.direction
+    }
+
+    /// Encodes the cursor into the URL-safe string.
+    pub fn encode(&self) -> String {
+        let mut bytes = Vec::with_capacity(
+            HEADER_SIZE
+                .saturating_add(self.key.len())
+                .saturating_add(CHECKSUM_SIZE),
+        );
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.column.to_be_bytes());
+        bytes.push(match self.direction {
+            IterDirection::Forward => 0,
+            IterDirection::Reverse => 1,
+        });
+        bytes.extend_from_slice(&self.key);
+        let checksum = crc32fast::hash(&bytes);
+        bytes.extend_from_slice(&checksum.to_be_bytes());
+        hex::encode(bytes)
+    }
+
+    /// Decodes the cursor from the string of [`Self::encode`].
+    /// It only checks the integrity of the cursor, see [`iter_from_cursor`]
+    /// for the validation against the request.
+    pub fn decode(encoded: &str) -> DatabaseResult<Self> {
+        let bytes = hex::decode(encoded)
+            .map_err(|_| DatabaseError::InvalidCursor("the cursor is malformed"))?;
+        let body_size = bytes
+            .len()
+            .checked_sub(CHECKSUM_SIZE)
+            .filter(|size| *size >= HEADER_SIZE)
+            .ok_or(DatabaseError::InvalidCursor("the cursor is malformed"))?;
+        let (body, checksum) = bytes.split_at(body_size);
+        if crc32fast::hash(body).to_be_bytes() != checksum {
+            return Err(DatabaseError::InvalidCursor(
+                "the checksum of the cursor doesn't match",
+            ))
+        }
+
+        let (header, key) = body.split_at(HEADER_SIZE);
+        if header[0] != VERSION {
+            return Err(DatabaseError::InvalidCursor(
+                "the version of the cursor is not supported",
+            ))
+        }
+        let column = u32::from_be_bytes(
+            header[1..5]
+                .try_into()
+                .expect("The header contains the column id"),
+        );
+        let direction = match header[5] {
+            0 => IterDirection::Forward,
+            1 => IterDirection::Reverse,
+            _ => return Err(DatabaseError::InvalidCursor("the cursor is malformed")),
+        };
+        Ok(Self {
+            column,
+            direction,
+            key: key.to_vec(),
+        })
+    }
+
+    /// Checks that the cursor continues the iteration of the `prefix` of the `column`
+    /// in the `direction`.
+    fn validate<Column: StorageColumn>(
+        &self,
+        column: Column,
+        prefix: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> DatabaseResult<()> {
+        if self.column != column.id() {
+            return Err(DatabaseError::InvalidCursor(
+                "the cursor belongs to another column",
+            ))
+        }
+        if self.direction != direction {
+            return Err(DatabaseError::InvalidCursor(
+                "the cursor belongs to the iteration in another direction",
+            ))
+        }
+        check_key(column, &self.key)?;
+        if prefix.is_some_and(|prefix| !self.key.starts_with(prefix)) {
+            return Err(DatabaseError::InvalidCursor(
+                "the cursor points outside of the prefix",
+            ))
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = DatabaseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::decode(s)
+    }
+}
+
+/// The page of entries returned by [`iter_from_cursor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    /// Entries of the page in the direction of the iteration.
+    pub entries: Vec<(Vec<u8>, Value)>,
+    /// The cursor of the next page, or `None` if the page is the last.
+    pub next: Option<Cursor>,
+}
+
+/// Returns the page of at most `limit` entries with the `prefix` of the `column`
+/// in the `direction`, starting after the `cursor`, or from the beginning without it.
+///
+/// The key of the cursor doesn't have to exist, so the cursor stays valid
+/// when its key is removed between pages.
+pub fn iter_from_cursor<S>(
+    store: &S,
+    column: S::Column,
+    prefix: Option<&[u8]>,
+    direction: IterDirection,
+    cursor: Option<&Cursor>,
+    limit: usize,
+) -> DatabaseResult<Page>
+where
+    S: IteratorableStore + ?Sized,
+{
+    let start = match cursor {
+        Some(cursor) => {
+            cursor.validate(column, prefix, direction)?;
+            Bound::Excluded(cursor.key())
+        }
+        None => Bound::Unbounded,
+    };
+    // One more entry tells whether the next page exists.
+    let mut iter = store.iter(
+        column,
+        IterConfig {
+            prefix,
+            start,
+            direction,
+            limit: Some(limit.saturating_add(1)),
+            ..Default::default()
+        },
+    );
+    let entries = iter
+        .by_ref()
+        .take(limit)
+        .collect::<StorageResult<Vec<_>>>()?;
+    let next = match iter.next().transpose()? {
+        Some(_) => entries
+            .last()
+            .map(|(key, _)| Cursor::new(column, direction, key.clone())),
+        None => None,
+    };
+    Ok(Page { entries, next })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::on_chain::OnChain,
+        state::in_memory::memory_store::MemoryStore,
+    };
+    use fuel_core_storage::{
+        column::Column,
+        kv_store::KeyValueStore,
+    };
+    use std::sync::Arc;
+
+    /// Two owners with three coins each, keyed by the owner and the index of the coin.
+    fn store() -> MemoryStore<OnChain> {
+        let store = MemoryStore::default();
+        for owner in [1u8, 2] {
+            for index in 0..3u8 {
+                store
+                    .put(
+                        &[owner, index],
+                        Column::Metadata,
+                        Arc::new(vec![owner, index]),
+                    )
+                    .unwrap();
+            }
+        }
+        store
+    }
+
+    fn keys(page: &Page) -> Vec<Vec<u8>> {
+        page.entries.iter().map(|(key, _)| key.clone()).collect()
+    }
+
+    /// Sends the cursor through the client, like the API does.
+    fn round_trip(cursor: &Cursor) -> Cursor {
+        cursor.to_string().parse().unwrap()
+    }
+
+    #[test]
+    fn pages_cover_the_prefix_in_both_directions() {
+        // Given
+        let store = store();
+
+        for (direction, expected) in [
+            (
+                IterDirection::Forward,
+                vec![vec![1, 0], vec![1, 1], vec![1, 2]],
+            ),
+            (
+                IterDirection::Reverse,
+                vec![vec![1, 2], vec![1, 1], vec![1, 0]],
+            ),
+        ] {
+            // When
+            let first = iter_from_cursor(
+                &store,
+                Column::Metadata,
+                Some(&[1]),
+                direction,
+                None,
+                2,
+            )
+            .unwrap();
+            let cursor = round_trip(first.next.as_ref().unwrap());
+            let second = iter_from_cursor(
+                &store,
+                Column::Metadata,
+                Some(&[1]),
+                direction,
+                Some(&cursor),
+                2,
+            )
+            .unwrap();
+
+            // Then
+            assert_eq!(cursor.direction(), direction);
+            assert_eq!(
+                [keys(&first), keys(&second)].concat(),
+                expected,
+                "{direction:?}"
+            );
+            assert_eq!(second.next, None);
+        }
+    }
+
+    #[test]
+    fn cursor_stays_valid_after_its_key_is_deleted() {
+        // Given
+        let store = store();
+        let first = iter_from_cursor(
+            &store,
+            Column::Metadata,
+            Some(&[1]),
+            IterDirection::Forward,
+            None,
+            1,
+        )
+        .unwrap();
+        let cursor = round_trip(first.next.as_ref().unwrap());
+        store.delete(cursor.key(), Column::Metadata).unwrap();
+
+        // When
+        let second = iter_from_cursor(
+            &store,
+            Column::Metadata,
+            Some(&[1]),
+            IterDirection::Forward,
+            Some(&cursor),
+            5,
+        )
+        .unwrap();
+
+        // Then
+        assert_eq!(keys(&second), vec![vec![1, 1], vec![1, 2]]);
+        assert_eq!(second.next, None);
+    }
+
+    #[test]
+    fn tampered_cursors_are_rejected() {
+        // Given
+        let encoded =
+            Cursor::new(Column::Metadata, IterDirection::Forward, vec![1, 0]).to_string();
+        let mut flipped = encoded.clone().into_bytes();
+        let last = flipped.len() - 1;
+        flipped[last] = if flipped[last] == b'0' { b'1' } else { b'0' };
+        let flipped = String::from_utf8(flipped).unwrap();
+
+        // When
+        let results = [
+            flipped.as_str(),
+            &encoded[..encoded.len() - 2],
+            "not a cursor",
+            "",
+        ]
+        .map(Cursor::decode);
+
+        // Then
+        for result in results {
+            assert!(
+                matches!(result, Err(DatabaseError::InvalidCursor(_))),
+                "{result:?}"
+            );
+        }
+        assert!(Cursor::decode(&encoded).is_ok());
+    }
+
+    #[test]
+    fn cursors_of_other_requests_are_rejected() {
+        // Given
+        let store = store();
+        let another_owner =
+            Cursor::new(Column::Metadata, IterDirection::Forward, vec![2, 0]);
+        let another_column =
+            Cursor::new(Column::Coins, IterDirection::Forward, vec![1, 0]);
+        let another_direction =
+            Cursor::new(Column::Metadata, IterDirection::Reverse, vec![1, 0]);
+
+        // When
+        let results = [another_owner, another_column, another_direction].map(|cursor| {
+            iter_from_cursor(
+                &store,
+                Column::Metadata,
+                Some(&[1]),
+                IterDirection::Forward,
+                Some(&round_trip(&cursor)),
+                5,
+            )
+        });
+
+        // Then
+        for result in results {
+            assert!(
+                matches!(result, Err(DatabaseError::InvalidCursor(_))),
+                "{result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn cursor_with_the_key_of_the_wrong_size_is_rejected() {
+        // Given
+        let store = MemoryStore::<OnChain>::default();
+        let cursor = Cursor::new(Column::Coins, IterDirection::Forward, vec![1, 2, 3]);
+
+        // When
+        let result = iter_from_cursor(
+            &store,
+            Column::Coins,
+            None,
+            IterDirection::Forward,
+            Some(&round_trip(&cursor)),
+            5,
+        );
+
+        // Then
+        assert!(
+            matches!(result, Err(DatabaseError::InvalidKey { .. })),
+            "{result:?}"
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! The structural diff of two stores, e.g. to find the divergence of two nodes
+//! instead of comparing only state roots. Columns are walked lazily with ordered
+//! cursors over both stores, so huge columns don't need to fit in memory.
+
+use crate::{
+    database::{
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::{
+        IterDirection,
+        TransactableStorage,
+    },
+};
+use core::cmp::Ordering;
+use fuel_core_storage::{
+    iter::{
+        BoxedIter,
+        IntoBoxedIter,
+    },
+    kv_store::{
+        BatchOperations,
+        StorageColumn,
+        Value,
+        WriteOperation,
+    },
+};
+use itertools::{
+    EitherOrBoth,
+    Itertools,
+};
+
+/// The maximum number of entries of the diff applied by one batch.
+pub const APPLY_BATCH_SIZE: usize = 10_000;
+
+/// The difference of one entry between the store `a` and the store `b`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffEntry<Column> {
+    /// The key exists only in the store `a`.
+    OnlyInA(Column, Vec<u8>, Value),
+    /// The key exists only in the store `b`.
+    OnlyInB(Column, Vec<u8>, Value),
+    /// The key exists in both stores with different values, `a` value goes first.
+    Changed(Column, Vec<u8>, Value, Value),
+}
+
+impl<Column> DiffEntry<Column>
+where
+    Column: StorageColumn,
+{
+    /// Returns the column of the entry.
+    pub fn column(&self) -> Column {
+        match self {
+            Self::OnlyInA(column, ..)
+            | Self::OnlyInB(column, ..)
+            | Self::Changed(column, ..) => *column,
+        }
+    }
+
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &[u8] {
+        match self {
+            Self::OnlyInA(_, key, _)
+            | Self::OnlyInB(_, key, _)
+            | Self::Changed(_, key, ..) => key,
+        }
+    }
+
+    /// Converts the entry into the operation that makes the store `a` equal to the store `b`.
+    pub fn into_write_operation(self) -> (Vec<u8>, Column, WriteOperation) {
+        match self {
+            Self::OnlyInA(column, key, _) => (key, column, WriteOperation::Remove),
+            Self::OnlyInB(column, key, value) | Self::Changed(column, key, _, value) => {
+                (key, column, WriteOperation::Insert(value))
+            }
+        }
+    }
+}
+
+/// Returns the differences between the store `a` and the store `b` in the `columns`,
+/// ordered by the column and then by the key. The iteration stops after the first error.
+pub fn diff<'a, Column>(
+    a: &'a dyn TransactableStorage<Column = Column>,
+    b: &'a dyn TransactableStorage<Column = Column>,
+    columns: &'a [Column],
+) -> BoxedIter<'a, DatabaseResult<DiffEntry<Column>>>
+where
+    Column: StorageColumn + 'a,
+{
+    columns
+        .iter()
+        .flat_map(move |column| diff_column(a, b, *column))
+        .scan(false, |failed, entry| {
+            if *failed {
+                return None
+            }
+            *failed = entry.is_err();
+            Some(entry)
+        })
+        .into_boxed()
+}
+
+fn diff_column<'a, Column>(
+    a: &'a dyn TransactableStorage<Column = Column>,
+    b: &'a dyn TransactableStorage<Column = Column>,
+    column: Column,
+) -> impl Iterator<Item = DatabaseResult<DiffEntry<Column>>> + 'a
+where
+    Column: StorageColumn + 'a,
+{
+    let a = a.iter_all(column, None, None, IterDirection::Forward);
+    let b = b.iter_all(column, None, None, IterDirection::Forward);
+    a.merge_join_by(b, |a, b| match (a, b) {
+        (Ok((a, _)), Ok((b, _))) => a.cmp(b),
+        // Errors are yielded as soon as they are met.
+        (Err(_), _) => Ordering::Less,
+        (_, Err(_)) => Ordering::Greater,
+    })
+    .filter_map(move |item| {
+        let entry = match item {
+            EitherOrBoth::Left(a) => {
+                a.map(|(key, value)| DiffEntry::OnlyInA(column, key, value))
+            }
+            EitherOrBoth::Right(b) => {
+                b.map(|(key, value)| DiffEntry::OnlyInB(column, key, value))
+            }
+            EitherOrBoth::Both(Ok((key, a)), Ok((_, b))) => {
+                if a == b {
+                    return None
+                }
+                Ok(DiffEntry::Changed(column, key, a, b))
+            }
+            EitherOrBoth::Both(Err(err), _) | EitherOrBoth::Both(_, Err(err)) => Err(err),
+        };
+        Some(entry.map_err(DatabaseError::from))
+    })
+}
+
+/// Applies the `diff` of the store `into` and another store, so the store `into`,
+/// used as the store `a` of the diff, becomes equal to the store `b` in diffed columns.
+/// Keys only in the store `a` are removed, while the rest take the value of the store `b`.
+/// The diff is applied by batches of [`APPLY_BATCH_SIZE`] entries, so the error leaves
+/// the store partially reconciled. Returns the number of applied entries.
+pub fn apply_diff<Column>(
+    into: &dyn BatchOperations<Column = Column>,
+    diff: impl Iterator<Item = DatabaseResult<DiffEntry<Column>>>,
+) -> DatabaseResult<usize>
+where
+    Column: StorageColumn,
+{
+    let mut applied = 0usize;
+    for chunk in &diff.chunks(APPLY_BATCH_SIZE) {
+        let operations = chunk
+            .map_ok(DiffEntry::into_write_operation)
+            .collect::<DatabaseResult<Vec<_>>>()?;
+        let len = operations.len();
+        into.batch_write(&mut operations.into_iter())
+            .map_err(DatabaseError::from)?;
+        applied = applied.saturating_add(len);
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::on_chain::OnChain,
+        state::in_memory::memory_store::MemoryStore,
+    };
+    use fuel_core_storage::{
+        column::Column,
+        kv_store::KeyValueStore,
+    };
+    use std::sync::Arc;
+
+    fn store(entries: &[(u8, u8)]) -> MemoryStore<OnChain> {
+        let store = MemoryStore::<OnChain>::default();
+        for (key, value) in entries {
+            store
+                .put(&[*key], Column::Coins, Arc::new(vec![*value]))
+                .unwrap();
+        }
+        store
+    }
+
+    fn entries(store: &MemoryStore<OnChain>) -> Vec<(Vec<u8>, Value)> {
+        store
+            .iter_all(Column::Coins, None, None, IterDirection::Forward)
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn diff_finds_inserts_deletes_and_changes_at_ends_and_middle() {
+        // Given
+        let a = store(&[(1, 1), (3, 3), (5, 5), (7, 7), (9, 9)]);
+        let b = store(&[(0, 0), (3, 3), (4, 4), (5, 50), (7, 7), (9, 90), (10, 10)]);
+
+        // When
+        let diff = diff(&a, &b, &[Column::Metadata, Column::Coins])
+            .collect::<DatabaseResult<Vec<_>>>()
+            .unwrap();
+
+        // Then
+        let value = |value: u8| Arc::new(vec![value]);
+        assert_eq!(
+            diff,
+            vec![
+                DiffEntry::OnlyInB(Column::Coins, vec![0], value(0)),
+                DiffEntry::OnlyInA(Column::Coins, vec![1], value(1)),
+                DiffEntry::OnlyInB(Column::Coins, vec![4], value(4)),
+                DiffEntry::Changed(Column::Coins, vec![5], value(5), value(50)),
+                DiffEntry::Changed(Column::Coins, vec![9], value(9), value(90)),
+                DiffEntry::OnlyInB(Column::Coins, vec![10], value(10)),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_of_identical_stores_is_empty() {
+        // Given
+        let a = store(&[(1, 1), (2, 2), (3, 3)]);
+        let b = store(&[(1, 1), (2, 2), (3, 3)]);
+
+        // When
+        let diff = diff(&a, &b, &[Column::Coins]).count();
+
+        // Then
+        assert_eq!(diff, 0);
+    }
+
+    #[test]
+    fn apply_diff_reconciles_a_toward_b() {
+        // Given
+        let a = store(&[(1, 1), (3, 3), (5, 5), (9, 9)]);
+        let b = store(&[(0, 0), (3, 3), (5, 50), (10, 10)]);
+        let entries_of_diff = diff(&a, &b, &[Column::Coins])
+            .collect::<DatabaseResult<Vec<_>>>()
+            .unwrap();
+
+        // When
+        let applied = apply_diff(&a, entries_of_diff.into_iter().map(Ok)).unwrap();
+
+        // Then
+        assert_eq!(applied, 5);
+        assert_eq!(entries(&a), entries(&b));
+        assert_eq!(diff(&a, &b, &[Column::Coins]).count(), 0);
+    }
+}
@@ -0,0 +1,890 @@
+//! The differential tests of the storage backends. Random sequences of operations
+//! are applied to every backend and to the model of the storage, and the results
+//! of each operation must be identical, including the order of iterated entries.
+//! The boundaries of the iteration start are also checked by the fixed [`start_matrix`].
+//!
+//! The number of cases is bounded to keep CI fast. Set `PROPTEST_CASES` to run more
+//! cases locally, for example, `PROPTEST_CASES=10000 cargo test differential`.
+
+use crate::{
+    database::database_description::{
+        on_chain::OnChain,
+        test::{
+            MergeColumn,
+            MergeDatabase,
+        },
+    },
+    state::{
+        hybrid::HybridStore,
+        in_memory::{
+            memory_store::MemoryStore,
+            transaction::MemoryTransactionView,
+        },
+        workload::{
+            BlockShape,
+            WorkloadGenerator,
+        },
+        DataSource,
+        TransactableStorage,
+    },
+};
+use fuel_core_storage::{
+    column::Column,
+    iter::{
+        is_start_within_prefix,
+        IterConfig,
+        IterDirection,
+    },
+    kv_store::{
+        BatchOperations,
+        KeyValueStore,
+        StorageColumn,
+        WriteOperation,
+    },
+};
+use proptest::{
+    prelude::*,
+    test_runner::TestCaseError,
+};
+use std::{
+    collections::BTreeMap,
+    ops::Bound,
+    sync::Arc,
+};
+
+const DEFAULT_CASES: u32 = 64;
+
+type Key = Vec<u8>;
+type Entries = BTreeMap<(MergeColumn, Key), Vec<u8>>;
+type Changes = BTreeMap<(MergeColumn, Key), Option<Vec<u8>>>;
+
+#[derive(Debug, Clone)]
+enum Action {
+    Put {
+        column: MergeColumn,
+        key: Key,
+        value: Vec<u8>,
+    },
+    Delete {
+        column: MergeColumn,
+        key: Key,
+    },
+    Get {
+        column: MergeColumn,
+        key: Key,
+    },
+    Exists {
+        column: MergeColumn,
+        key: Key,
+    },
+    Iter {
+        column: MergeColumn,
+        prefix: Option<Key>,
+        start: Option<Key>,
+        direction: IterDirection,
+    },
+    /// The `None` value removes the entry.
+    BatchWrite(Vec<(Key, MergeColumn, Option<Vec<u8>>)>),
+    /// Opens the transaction on top of the current one.
+    Begin,
+    /// Commits the innermost transaction, if any.
+    Commit,
+    /// Drops the innermost transaction, if any.
+    Abort,
+}
+
+/// The result of the action observed by the caller.
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    Done,
+    Value(Option<Vec<u8>>),
+    Exists(bool),
+    Entries(Vec<(Key, Vec<u8>)>),
+}
+
+fn column() -> impl Strategy<Value = MergeColumn> {
+    prop_oneof![Just(MergeColumn::Metadata), Just(MergeColumn::Logs)]
+}
+
+/// Keys use the small alphabet, so operations often hit the same keys and prefixes.
+/// The alphabet includes the boundary bytes, and keys may be empty.
+fn key() -> impl Strategy<Value = Key> {
+    prop::collection::vec(prop_oneof![Just(0u8), Just(1), Just(2), Just(0xFF)], 0..=3)
+}
+
+fn value() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(any::<u8>(), 0..3)
+}
+
+fn direction() -> impl Strategy<Value = IterDirection> {
+    prop_oneof![Just(IterDirection::Forward), Just(IterDirection::Reverse)]
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    prop_oneof![
+        4 => (column(), key(), value())
+            .prop_map(|(column, key, value)| Action::Put { column, key, value }),
+        2 => (column(), key()).prop_map(|(column, key)| Action::Delete { column, key }),
+        1 => (column(), key()).prop_map(|(column, key)| Action::Get { column, key }),
+        1 => (column(), key()).prop_map(|(column, key)| Action::Exists { column, key }),
+        3 => (
+            column(),
+            prop::option::of(key()),
+            prop::option::of(key()),
+            direction()
+        )
+            .prop_map(|(column, prefix, start, direction)| Action::Iter {
+                column,
+                prefix,
+                start,
+                direction,
+            }),
+        2 => prop::collection::vec((key(), column(), prop::option::of(value())), 0..5)
+            .prop_map(Action::BatchWrite),
+        1 => Just(Action::Begin),
+        1 => Just(Action::Commit),
+        1 => Just(Action::Abort),
+    ]
+}
+
+fn actions() -> impl Strategy<Value = Vec<Action>> {
+    prop::collection::vec(action(), 1..64)
+}
+
+fn config() -> ProptestConfig {
+    let cases = std::env::var("PROPTEST_CASES")
+        .ok()
+        .and_then(|cases| cases.parse().ok())
+        .unwrap_or(DEFAULT_CASES);
+    ProptestConfig::with_cases(cases)
+}
+
+/// The reference model of the storage with nested transactions.
+#[derive(Default)]
+struct Model {
+    entries: Entries,
+    transactions: Vec<Changes>,
+}
+
+impl Model {
+    fn get(&self, column: MergeColumn, key: &[u8]) -> Option<Vec<u8>> {
+        let entry = (column, key.to_vec());
+        for changes in self.transactions.iter().rev() {
+            if let Some(value) = changes.get(&entry) {
+                return value.clone()
+            }
+        }
+        self.entries.get(&entry).cloned()
+    }
+
+    fn write(&mut self, column: MergeColumn, key: Key, value: Option<Vec<u8>>) {
+        match self.transactions.last_mut() {
+            Some(changes) => {
+                changes.insert((column, key), value);
+            }
+            None => match value {
+                Some(value) => {
+                    self.entries.insert((column, key), value);
+                }
+                None => {
+                    self.entries.remove(&(column, key));
+                }
+            },
+        }
+    }
+
+    fn iter(
+        &self,
+        column: MergeColumn,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> Vec<(Key, Vec<u8>)> {
+        let mut visible = self
+            .entries
+            .iter()
+            .filter(|((entry_column, _), _)| *entry_column == column)
+            .map(|((_, key), value)| (key.clone(), value.clone()))
+            .collect::<BTreeMap<_, _>>();
+        for changes in self.transactions.iter() {
+            for ((entry_column, key), value) in changes {
+                if *entry_column != column {
+                    continue
+                }
+                match value {
+                    Some(value) => visible.insert(key.clone(), value.clone()),
+                    None => visible.remove(key),
+                };
+            }
+        }
+
+        if !is_start_within_prefix(prefix, start) {
+            return vec![]
+        }
+        let entries = visible.into_iter().filter(|(key, _)| {
+            let in_prefix = prefix.map_or(true, |prefix| key.starts_with(prefix));
+            let after_start = start.map_or(true, |start| match direction {
+                IterDirection::Forward => key.as_slice() >= start,
+                IterDirection::Reverse => key.as_slice() <= start,
+            });
+            in_prefix && after_start
+        });
+        match direction {
+            IterDirection::Forward => entries.collect(),
+            IterDirection::Reverse => entries.rev().collect(),
+        }
+    }
+
+    fn apply(&mut self, action: &Action) -> Outcome {
+        match action.clone() {
+            Action::Put { column, key, value } => {
+                self.write(column, key, Some(value));
+                Outcome::Done
+            }
+            Action::Delete { column, key } => {
+                self.write(column, key, None);
+                Outcome::Done
+            }
+            Action::Get { column, key } => Outcome::Value(self.get(column, &key)),
+            Action::Exists { column, key } => {
+                Outcome::Exists(self.get(column, &key).is_some())
+            }
+            Action::Iter {
+                column,
+                prefix,
+                start,
+                direction,
+            } => Outcome::Entries(self.iter(
+                column,
+                prefix.as_deref(),
+                start.as_deref(),
+                direction,
+            )),
+            Action::BatchWrite(batch) => {
+                for (key, column, value) in batch {
+                    self.write(column, key, value);
+                }
+                Outcome::Done
+            }
+            Action::Begin => {
+                self.transactions.push(Changes::new());
+                Outcome::Done
+            }
+            Action::Commit => {
+                if let Some(changes) = self.transactions.pop() {
+                    for ((column, key), value) in changes {
+                        self.write(column, key, value);
+                    }
+                }
+                Outcome::Done
+            }
+            Action::Abort => {
+                self.transactions.pop();
+                Outcome::Done
+            }
+        }
+    }
+}
+
+/// The backend under the test with the stack of open transactions over it.
+struct Subject {
+    storage: DataSource<MergeDatabase>,
+    transactions: Vec<Arc<MemoryTransactionView<MergeDatabase>>>,
+}
+
+impl Subject {
+    fn new(storage: impl Into<DataSource<MergeDatabase>>) -> Self {
+        Self {
+            storage: storage.into(),
+            transactions: vec![],
+        }
+    }
+
+    fn current(&self) -> DataSource<MergeDatabase> {
+        match self.transactions.last() {
+            Some(transaction) => transaction.clone().into(),
+            None => self.storage.clone(),
+        }
+    }
+
+    fn apply(&mut self, action: &Action) -> Result<Outcome, String> {
+        let current = self.current();
+        let outcome = match action.clone() {
+            Action::Put { column, key, value } => current
+                .put(&key, column, Arc::new(value))
+                .map(|_| Outcome::Done),
+            Action::Delete { column, key } => {
+                current.delete(&key, column).map(|_| Outcome::Done)
+            }
+            Action::Get { column, key } => current
+                .get(&key, column)
+                .map(|value| Outcome::Value(value.map(|value| value.to_vec()))),
+            Action::Exists { column, key } => {
+                current.exists(&key, column).map(Outcome::Exists)
+            }
+            Action::Iter {
+                column,
+                prefix,
+                start,
+                direction,
+            } => {
+                let entries = current
+                    .iter_all(column, prefix.as_deref(), start.as_deref(), direction)
+                    .map(|item| item.map(|(key, value)| (key, value.to_vec())))
+                    .collect::<Result<Vec<_>, _>>();
+                // The lending iterator must yield the same entries as the boxed one.
+                let mut lent = vec![];
+                let mut iter = current.iter_column(
+                    column,
+                    prefix.as_deref(),
+                    start.as_deref(),
+                    direction,
+                );
+                while let Some(Ok((key, value))) = iter.next() {
+                    lent.push((key.to_vec(), value.to_vec()));
+                }
+                if let Ok(entries) = &entries {
+                    if entries != &lent {
+                        return Err(format!(
+                            "The lending iterator yields {lent:?} instead of {entries:?}"
+                        ))
+                    }
+                }
+                entries.map(Outcome::Entries)
+            }
+            Action::BatchWrite(batch) => current
+                .batch_write(&mut batch.into_iter().map(|(key, column, value)| {
+                    let operation = match value {
+                        Some(value) => WriteOperation::Insert(Arc::new(value)),
+                        None => WriteOperation::Remove,
+                    };
+                    (key, column, operation)
+                }))
+                .map(|_| Outcome::Done),
+            Action::Begin => {
+                self.transactions
+                    .push(Arc::new(MemoryTransactionView::new(current)));
+                Ok(Outcome::Done)
+            }
+            Action::Commit => match self.transactions.pop() {
+                Some(transaction) => transaction.commit().map(|_| Outcome::Done),
+                None => Ok(Outcome::Done),
+            },
+            Action::Abort => {
+                self.transactions.pop();
+                Ok(Outcome::Done)
+            }
+        };
+        outcome.map_err(|error| format!("{error:?}"))
+    }
+}
+
+/// Applies the `actions` to the `storage` and to the model and compares the results.
+fn check(
+    storage: impl Into<DataSource<MergeDatabase>>,
+    actions: &[Action],
+) -> Result<(), TestCaseError> {
+    let mut model = Model::default();
+    let mut subject = Subject::new(storage);
+
+    for (step, action) in actions.iter().enumerate() {
+        let expected = model.apply(action);
+        let actual = subject.apply(action);
+        prop_assert_eq!(
+            actual,
+            Ok(expected),
+            "The step {} diverged: {:?}",
+            step,
+            action
+        );
+    }
+
+    // Everything committed into the storage must be visible after transactions are dropped.
+    model.transactions.clear();
+    subject.transactions.clear();
+    for column in [MergeColumn::Metadata, MergeColumn::Logs] {
+        for direction in [IterDirection::Forward, IterDirection::Reverse] {
+            let action = Action::Iter {
+                column,
+                prefix: None,
+                start: None,
+                direction,
+            };
+            prop_assert_eq!(subject.apply(&action), Ok(model.apply(&action)));
+        }
+    }
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(config())]
+
+    #[test]
+    fn memory_store_matches_the_model(actions in actions()) {
+        check(Arc::new(MemoryStore::<MergeDatabase>::default()), &actions)?;
+    }
+
+    #[test]
+    fn transaction_view_matches_the_model(actions in actions()) {
+        let store = Arc::new(MemoryStore::<MergeDatabase>::default());
+        let view = Arc::new(MemoryTransactionView::new(store));
+        check(view, &actions)?;
+    }
+
+    #[test]
+    fn hybrid_store_matches_the_model(actions in actions()) {
+        let disk = Arc::new(MemoryStore::<MergeDatabase>::default());
+        let store = HybridStore::new([MergeColumn::Logs].into_iter().collect(), disk);
+        check(Arc::new(store), &actions)?;
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn rocksdb_matches_the_model(actions in actions()) {
+        use crate::state::rocks_db::RocksDb;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let db = RocksDb::<MergeDatabase>::default_open(tmp.path(), None).unwrap();
+        check(Arc::new(db), &actions)?;
+    }
+
+    #[cfg(feature = "redb")]
+    #[test]
+    fn redb_matches_the_model(actions in actions()) {
+        use crate::state::redb_store::RedbStore;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let db = RedbStore::<MergeDatabase>::open(tmp.path(), None).unwrap();
+        check(Arc::new(db), &actions)?;
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn hybrid_store_over_rocksdb_matches_the_model(actions in actions()) {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = HybridStore::<MergeDatabase>::open(
+            tmp.path(),
+            [MergeColumn::Logs].into_iter().collect(),
+            None,
+        )
+        .unwrap();
+        check(Arc::new(store), &actions)?;
+    }
+}
+
+/// Returns the key of the [`start_matrix`]. The `[byte; 32]` part has the length of
+/// the prefix extractor of the `ContractsState`, so the RocksDB uses the prefix seek for it.
+fn matrix_key(byte: u8, suffix: &[u8]) -> Key {
+    let mut key = vec![byte; 32];
+    key.extend_from_slice(suffix);
+    key
+}
+
+/// The cases of the iteration over the keys `[0; 32] ++ [9]`, `[1; 32] ++ [0]`,
+/// `[1; 32] ++ [2]`, `[1; 32] ++ [4]`, and `[2; 32] ++ [0]` with the prefix `[1; 32]`.
+/// Each case is `(prefix, start, direction, expected keys)`.
+#[allow(clippy::type_complexity)]
+fn start_matrix() -> Vec<(Option<Key>, Bound<Key>, IterDirection, Vec<Key>)> {
+    use Bound::{
+        Excluded,
+        Included,
+        Unbounded,
+    };
+    use IterDirection::{
+        Forward,
+        Reverse,
+    };
+
+    let prefix = Some(matrix_key(1, &[]));
+    let below = matrix_key(0, &[9]);
+    let first = matrix_key(1, &[0]);
+    let middle = matrix_key(1, &[2]);
+    let last = matrix_key(1, &[4]);
+    let above = matrix_key(2, &[0]);
+    let missing = matrix_key(1, &[3]);
+    let all = vec![first.clone(), middle.clone(), last.clone()];
+    let all_reversed = vec![last.clone(), middle.clone(), first.clone()];
+
+    vec![
+        // The `start` doesn't exist.
+        (
+            prefix.clone(),
+            Included(missing.clone()),
+            Forward,
+            vec![last.clone()],
+        ),
+        (
+            prefix.clone(),
+            Included(missing.clone()),
+            Reverse,
+            vec![middle.clone(), first.clone()],
+        ),
+        (
+            None,
+            Included(missing.clone()),
+            Forward,
+            vec![last.clone(), above.clone()],
+        ),
+        (
+            None,
+            Included(missing.clone()),
+            Reverse,
+            vec![middle.clone(), first.clone(), below.clone()],
+        ),
+        // The `start` is outside of the prefix on both sides.
+        (
+            prefix.clone(),
+            Included(matrix_key(0, &[])),
+            Forward,
+            vec![],
+        ),
+        (
+            prefix.clone(),
+            Included(matrix_key(0, &[])),
+            Reverse,
+            vec![],
+        ),
+        (
+            prefix.clone(),
+            Included(matrix_key(2, &[])),
+            Forward,
+            vec![],
+        ),
+        (
+            prefix.clone(),
+            Included(matrix_key(2, &[])),
+            Reverse,
+            vec![],
+        ),
+        (
+            prefix.clone(),
+            Excluded(matrix_key(0, &[])),
+            Forward,
+            vec![],
+        ),
+        (
+            prefix.clone(),
+            Excluded(matrix_key(2, &[])),
+            Reverse,
+            vec![],
+        ),
+        // The `start` is the first or the last key of the prefix.
+        (
+            prefix.clone(),
+            Included(first.clone()),
+            Forward,
+            all.clone(),
+        ),
+        (
+            prefix.clone(),
+            Included(first.clone()),
+            Reverse,
+            vec![first.clone()],
+        ),
+        (
+            prefix.clone(),
+            Included(last.clone()),
+            Forward,
+            vec![last.clone()],
+        ),
+        (
+            prefix.clone(),
+            Included(last.clone()),
+            Reverse,
+            all_reversed.clone(),
+        ),
+        // The `start` is before the first or after the last key of the prefix.
+        (
+            prefix.clone(),
+            Included(matrix_key(1, &[])),
+            Forward,
+            all.clone(),
+        ),
+        (
+            prefix.clone(),
+            Included(matrix_key(1, &[])),
+            Reverse,
+            vec![],
+        ),
+        (
+            prefix.clone(),
+            Included(matrix_key(1, &[0xFF])),
+            Forward,
+            vec![],
+        ),
+        (
+            prefix.clone(),
+            Included(matrix_key(1, &[0xFF])),
+            Reverse,
+            all_reversed.clone(),
+        ),
+        // Without the `start`.
+        (prefix.clone(), Unbounded, Forward, all.clone()),
+        (prefix.clone(), Unbounded, Reverse, all_reversed.clone()),
+        // The excluded `start` exists.
+        (
+            prefix.clone(),
+            Excluded(middle.clone()),
+            Forward,
+            vec![last.clone()],
+        ),
+        (
+            prefix.clone(),
+            Excluded(middle.clone()),
+            Reverse,
+            vec![first.clone()],
+        ),
+        (
+            prefix.clone(),
+            Excluded(first.clone()),
+            Forward,
+            vec![middle.clone(), last.clone()],
+        ),
+        (prefix.clone(), Excluded(first.clone()), Reverse, vec![]),
+        (prefix.clone(), Excluded(last.clone()), Forward, vec![]),
+        (
+            prefix.clone(),
+            Excluded(last.clone()),
+            Reverse,
+            vec![middle.clone(), first.clone()],
+        ),
+        (None, Excluded(first.clone()), Reverse, vec![below.clone()]),
+        (None, Excluded(last.clone()), Forward, vec![above]),
+        // The excluded `start` doesn't exist.
+        (
+            prefix.clone(),
+            Excluded(missing.clone()),
+            Forward,
+            vec![last],
+        ),
+        (
+            prefix.clone(),
+            Excluded(missing.clone()),
+            Reverse,
+            vec![middle.clone(), first.clone()],
+        ),
+        (None, Excluded(missing), Reverse, vec![middle, first, below]),
+        (prefix.clone(), Excluded(matrix_key(1, &[])), Forward, all),
+        (
+            prefix,
+            Excluded(matrix_key(1, &[0xFF])),
+            Reverse,
+            all_reversed,
+        ),
+    ]
+}
+
+fn matrix_entries(
+    keys: impl IntoIterator<Item = Key>,
+) -> impl Iterator<Item = (Key, Column, WriteOperation)> {
+    keys.into_iter().flat_map(|key| {
+        [Column::Metadata, Column::ContractsState].map(|column| {
+            (
+                key.clone(),
+                column,
+                WriteOperation::Insert(Arc::new(key.clone())),
+            )
+        })
+    })
+}
+
+/// Fills the `storage` with the keys of the [`start_matrix`].
+fn fill_start_matrix(storage: &dyn TransactableStorage<Column = Column>) {
+    let keys = [(0, 9), (1, 0), (1, 2), (1, 4), (2, 0)]
+        .map(|(byte, suffix)| matrix_key(byte, &[suffix]));
+    storage.batch_write(&mut matrix_entries(keys)).unwrap();
+}
+
+/// Checks that all iterators of the `storage` follow the [`start_matrix`].
+/// The iterators without the [`IterConfig`] can't exclude the `start`.
+fn check_start_matrix(storage: &dyn TransactableStorage<Column = Column>) {
+    for column in [Column::Metadata, Column::ContractsState] {
+        for (prefix, start, direction, expected) in start_matrix() {
+            let prefix = prefix.as_deref();
+            let start = match &start {
+                Bound::Included(start) => Bound::Included(start.as_slice()),
+                Bound::Excluded(start) => Bound::Excluded(start.as_slice()),
+                Bound::Unbounded => Bound::Unbounded,
+            };
+            let case = format!("{column:?} {prefix:?} {start:?} {direction:?}");
+
+            let config = IterConfig {
+                prefix,
+                start,
+                direction,
+                limit: None,
+                ..Default::default()
+            };
+            let entries = storage
+                .iter(column, config)
+                .map(|item| item.unwrap().0)
+                .collect::<Vec<_>>();
+            assert_eq!(entries, expected, "{case}");
+
+            let start = match start {
+                Bound::Included(start) => Some(start),
+                Bound::Unbounded => None,
+                Bound::Excluded(_) => continue,
+            };
+
+            let entries = storage
+                .iter_all(column, prefix, start, direction)
+                .map(|item| item.unwrap().0)
+                .collect::<Vec<_>>();
+            assert_eq!(entries, expected, "{case}");
+
+            let keys = storage
+                .iter_keys(column, prefix, start, direction)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(keys, expected, "{case}");
+
+            let mut lent = vec![];
+            let mut iter = storage.iter_column(column, prefix, start, direction);
+            while let Some(item) = iter.next() {
+                lent.push(item.unwrap().0.to_vec());
+            }
+            assert_eq!(lent, expected, "{case}");
+        }
+    }
+}
+
+#[test]
+fn memory_store_follows_the_start_matrix() {
+    let store = MemoryStore::<OnChain>::default();
+    fill_start_matrix(&store);
+    check_start_matrix(&store);
+}
+
+#[test]
+fn transaction_view_follows_the_start_matrix() {
+    // Given
+    let store = Arc::new(MemoryStore::<OnChain>::default());
+    fill_start_matrix(store.as_ref());
+    let missing = matrix_key(1, &[3]);
+    let middle = matrix_key(1, &[2]);
+    store
+        .batch_write(&mut matrix_entries([missing.clone()]))
+        .unwrap();
+    let view = MemoryTransactionView::new(store.clone());
+    // The `start` of the cursor is removed by the view, the same as between pages.
+    for (key, column, _) in matrix_entries([missing]) {
+        view.delete(&key, column).unwrap();
+    }
+    // The same key is in both layers, and the key is only in the view.
+    for (key, column, _) in matrix_entries([middle.clone()]) {
+        store.delete(&key, column).unwrap();
+        view.put(&key, column, Arc::new(key.clone())).unwrap();
+    }
+
+    // Then
+    check_start_matrix(&view);
+}
+
+#[test]
+fn hybrid_store_follows_the_start_matrix() {
+    let disk = Arc::new(MemoryStore::<OnChain>::default());
+    let store = HybridStore::new([Column::Metadata].into_iter().collect(), disk);
+    fill_start_matrix(&store);
+    check_start_matrix(&store);
+}
+
+#[cfg(feature = "rocksdb")]
+#[test]
+fn rocksdb_follows_the_start_matrix() {
+    use crate::state::rocks_db::RocksDb;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let db = RocksDb::<OnChain>::default_open(tmp.path(), None).unwrap();
+    fill_start_matrix(&db);
+    check_start_matrix(&db);
+}
+
+#[cfg(feature = "redb")]
+#[test]
+fn redb_follows_the_start_matrix() {
+    use crate::state::redb_store::RedbStore;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let db = RedbStore::<OnChain>::open(tmp.path(), None).unwrap();
+    fill_start_matrix(&db);
+    check_start_matrix(&db);
+}
+
+/// Commits generated blocks to the `storage` through transaction views
+/// and checks that changed columns match the model of the blocks.
+fn check_generated_blocks(storage: DataSource<OnChain>) {
+    let mut generator = WorkloadGenerator::new(0xB10C);
+    let state = generator.contract_state(4, 50);
+    let mut unspent = vec![];
+    let mut model = BTreeMap::new();
+    let shape = BlockShape {
+        transaction_size: 256,
+        ..Default::default()
+    };
+    for _ in 0..5 {
+        let block = generator.block(&mut unspent, &state, shape);
+        let view = MemoryTransactionView::new(storage.clone());
+        for (key, column, operation) in block {
+            let entry = (column.id(), key.clone());
+            match operation {
+                WriteOperation::Insert(value) => {
+                    model.insert(entry, value.to_vec());
+                    view.put(&key, column, value).unwrap();
+                }
+                WriteOperation::Remove => {
+                    model.remove(&entry);
+                    view.delete(&key, column).unwrap();
+                }
+                WriteOperation::Merge(_) => unreachable!("Blocks don't merge"),
+            }
+        }
+        view.commit().unwrap();
+    }
+
+    let storage: &dyn TransactableStorage<Column = Column> = storage.as_ref();
+    let entries = [Column::Coins, Column::ContractsState, Column::Transactions]
+        .into_iter()
+        .flat_map(|column| {
+            storage
+                .iter_all(column, None, None, IterDirection::Forward)
+                .map(move |item| {
+                    let (key, value) = item.unwrap();
+                    ((column.id(), key), value.to_vec())
+                })
+        })
+        .collect::<BTreeMap<_, _>>();
+    assert_eq!(entries, model);
+}
+
+#[test]
+fn memory_store_commits_generated_blocks() {
+    check_generated_blocks(Arc::new(MemoryStore::<OnChain>::default()).into());
+}
+
+#[test]
+fn hybrid_store_commits_generated_blocks() {
+    let disk = Arc::new(MemoryStore::<OnChain>::default());
+    let store = HybridStore::new([Column::Coins].into_iter().collect(), disk);
+    check_generated_blocks(Arc::new(store).into());
+}
+
+#[cfg(feature = "rocksdb")]
+#[test]
+fn rocksdb_commits_generated_blocks() {
+    use crate::state::rocks_db::RocksDb;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let db = RocksDb::<OnChain>::default_open(tmp.path(), None).unwrap();
+    check_generated_blocks(Arc::new(db).into());
+}
+
+#[cfg(feature = "redb")]
+#[test]
+fn redb_commits_generated_blocks() {
+    use crate::state::redb_store::RedbStore;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let db = RedbStore::<OnChain>::open(tmp.path(), None).unwrap();
+    check_generated_blocks(Arc::new(db).into());
+}
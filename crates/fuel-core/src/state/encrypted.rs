@@ -0,0 +1,901 @@
+//! The encryption of values at rest, see [`EncryptedStorage`].
+//!
+//! Values are encrypted with the XChaCha20-Poly1305 and stored as the random nonce
+//! followed by the ciphertext. The column and the key of the entry are authenticated
+//! with the value, so the value moved to another key doesn't decrypt.
+//!
+//! Keys are stored in plaintext, so the backend still orders them, and prefix iteration
+//! and seeks work as before. The trade-off is that keys leak the structure of the state:
+//! which contracts, owners, and heights have entries, but not the values of entries.
+//!
+//! The metadata column holds versions and heights of the database, so it isn't encrypted,
+//! and the version of the database can be checked without the key. It also holds
+//! the [`EncryptionKeyCheck`] record, so opening with the wrong key fails instead of
+//! failing on the first read.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        metadata::{
+            get_metadata,
+            set_metadata,
+            EncryptionKeyCheck,
+            MetadataEntry,
+        },
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::{
+        column_iter::ColumnIter,
+        notifying::StorageEvent,
+        update_encoded,
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        EngineStats,
+        IterDirection,
+        TransactableStorage,
+        WriteDurability,
+        WritePressure,
+    },
+};
+use chacha20poly1305::{
+    aead::{
+        Aead,
+        KeyInit,
+        Payload,
+    },
+    XChaCha20Poly1305,
+    XNonce,
+};
+use fuel_core_storage::{
+    iter::{
+        BoxedIter,
+        IntoBoxedIter,
+        IterConfig,
+        IteratorableStore,
+        PageIter,
+    },
+    kv_store::{
+        merge_value,
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Result as StorageResult,
+};
+use rand::RngCore;
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    path::Path,
+    sync::{
+        Arc,
+        Mutex,
+    },
+};
+use tokio::sync::broadcast;
+
+/// The plaintext of the [`EncryptionKeyCheck`] record.
+const KEY_CHECK_PLAINTEXT: &[u8] = b"fuel-core encrypted database";
+
+/// The size of the nonce stored before the ciphertext.
+const NONCE_SIZE: usize = 24;
+
+/// The size of the authentication tag appended to the ciphertext.
+const TAG_SIZE: usize = 16;
+
+/// The storage that encrypts values of all columns except the metadata column
+/// with the key provided at construction.
+pub struct EncryptedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    inner: DataSourceInner<Description::Column>,
+    cipher: Arc<XChaCha20Poly1305>,
+    /// Merges and swaps read the value before writing, so writes are serialized
+    /// to keep them atomic.
+    write_lock: Mutex<()>,
+}
+
+impl<Description> Debug for EncryptedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EncryptedStorage")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Description> EncryptedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Wraps the `inner` storage with the encryption by the `key`. Fails with
+    /// [`DatabaseError::WrongEncryptionKey`] if the storage is encrypted with another key,
+    /// or if it has unencrypted entries.
+    pub fn new<D>(inner: D, key: &[u8; 32]) -> DatabaseResult<Self>
+    where
+        D: Into<DataSource<Description>>,
+    {
+        let storage = Self {
+            inner: inner.into().0,
+            cipher: Arc::new(XChaCha20Poly1305::new(key.into())),
+            write_lock: Mutex::new(()),
+        };
+        storage.check_key()?;
+        Ok(storage)
+    }
+
+    fn check_key(&self) -> DatabaseResult<()> {
+        let metadata = Description::metadata_column();
+        let key = MetadataEntry::EncryptionKeyCheck.key();
+        let record = get_metadata::<EncryptionKeyCheck, Description>(self.inner.as_ref())
+            .map_err(DatabaseError::from)?;
+        if let Some(record) = record {
+            return match self.decrypt(metadata, key, &record) {
+                Ok(plaintext) if plaintext == KEY_CHECK_PLAINTEXT => Ok(()),
+                _ => Err(DatabaseError::WrongEncryptionKey),
+            }
+        }
+
+        let has_entries = enum_iterator::all::<Description::Column>()
+            .filter(|column| *column != metadata)
+            .any(|column| !matches!(self.inner.has_any(column, &[]), Ok(false)));
+        if has_entries {
+            return Err(DatabaseError::WrongEncryptionKey)
+        }
+        let record = self.encrypt(metadata, key, KEY_CHECK_PLAINTEXT)?;
+        set_metadata::<EncryptionKeyCheck, Description>(self.inner.as_ref(), &record)
+            .map_err(DatabaseError::from)
+    }
+
+    fn is_encrypted(column: Description::Column) -> bool {
+        column != Description::metadata_column()
+    }
+
+    fn aad(column: Description::Column, key: &[u8]) -> Vec<u8> {
+        let mut aad = Vec::with_capacity(key.len().saturating_add(4));
+        aad.extend_from_slice(&column.id().to_be_bytes());
+        aad.extend_from_slice(key);
+        aad
+    }
+
+    fn encrypt(
+        &self,
+        column: Description::Column,
+        key: &[u8],
+        value: &[u8],
+    ) -> DatabaseResult<Value> {
+        let mut nonce = [0u8; NONCE_SIZE];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let aad = Self::aad(column, key);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: value,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "Unable to encrypt the value of the column `{}`",
+                    column.name()
+                )
+            })?;
+        let mut stored = Vec::with_capacity(NONCE_SIZE.saturating_add(ciphertext.len()));
+        stored.extend_from_slice(&nonce);
+        stored.extend_from_slice(&ciphertext);
+        Ok(Arc::new(stored))
+    }
+
+    fn decrypt(
+        &self,
+        column: Description::Column,
+        key: &[u8],
+        stored: &[u8],
+    ) -> DatabaseResult<Vec<u8>> {
+        let error = || DatabaseError::Decryption {
+            column: column.name(),
+        };
+        if stored.len() < NONCE_SIZE.saturating_add(TAG_SIZE) {
+            return Err(error())
+        }
+        let (nonce, ciphertext) = stored.split_at(NONCE_SIZE);
+        let aad = Self::aad(column, key);
+        self.cipher
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| error())
+    }
+
+    /// Returns the value to store for the `value` of the `column`.
+    fn seal(
+        &self,
+        column: Description::Column,
+        key: &[u8],
+        value: Value,
+    ) -> StorageResult<Value> {
+        match Self::is_encrypted(column) {
+            true => Ok(self.encrypt(column, key, &value)?),
+            false => Ok(value),
+        }
+    }
+
+    /// Returns the value of the `column` for the `stored` value.
+    fn open(
+        &self,
+        column: Description::Column,
+        key: &[u8],
+        stored: Option<Value>,
+    ) -> StorageResult<Option<Value>> {
+        match stored {
+            Some(stored) if Self::is_encrypted(column) => {
+                Ok(Some(Arc::new(self.decrypt(column, key, &stored)?)))
+            }
+            stored => Ok(stored),
+        }
+    }
+
+    /// Decrypts values of the `iter` over the `column`.
+    fn decrypted<'a>(
+        &'a self,
+        column: Description::Column,
+        iter: BoxedIter<'a, KVItem>,
+    ) -> BoxedIter<'a, KVItem> {
+        if !Self::is_encrypted(column) {
+            return iter
+        }
+        iter.map(move |item| {
+            let (key, stored) = item?;
+            let value = self.decrypt(column, &key, &stored)?;
+            Ok((key, Arc::new(value)))
+        })
+        .into_boxed()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ()> {
+        self.write_lock.lock().expect("poisoned")
+    }
+}
+
+impl<Description> KeyValueStore for EncryptedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        let value = self.seal(column, key, value)?;
+        let _guard = self.lock();
+        self.inner.put(key, column, value)
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        let value = self.seal(column, key, value)?;
+        let old = {
+            let _guard = self.lock();
+            self.inner.replace(key, column, value)?
+        };
+        self.open(column, key, old)
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        self.put(key, column, Arc::new(buf.to_vec()))?;
+        Ok(buf.len())
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        let old = {
+            let _guard = self.lock();
+            self.inner.take(key, column)?
+        };
+        self.open(column, key, old)
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        let _guard = self.lock();
+        self.inner.delete(key, column)
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        if !Self::is_encrypted(column) {
+            let _guard = self.lock();
+            return self.inner.merge(key, column, operand)
+        }
+        // The backend can't merge ciphertexts, so the merge is resolved here.
+        let _guard = self.lock();
+        let existing = self.open(column, key, self.inner.get(key, column)?)?;
+        let value =
+            merge_value(column, existing.as_deref().map(Vec::as_slice), &operand)?;
+        let value = self.seal(column, key, value)?;
+        self.inner.put(key, column, value)
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        self.inner.exists(key, column)
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        let size = self.inner.size_of_value(key, column)?;
+        match Self::is_encrypted(column) {
+            true => Ok(size.map(|size| size.saturating_sub(NONCE_SIZE + TAG_SIZE))),
+            false => Ok(size),
+        }
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        let stored = self.inner.get(key, column)?;
+        self.open(column, key, stored)
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        if !Self::is_encrypted(column) {
+            return self.inner.get_with(key, column, f)
+        }
+        let mut decrypted = Ok(());
+        let found = self.inner.get_with(key, column, &mut |stored| {
+            decrypted = self.decrypt(column, key, stored).map(|value| f(&value));
+        })?;
+        decrypted?;
+        Ok(found)
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        let values = self.inner.multi_get(keys, column)?;
+        keys.iter()
+            .zip(values)
+            .map(|(key, stored)| self.open(column, key, stored))
+            .collect()
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        if !Self::is_encrypted(column) {
+            let _guard = self.lock();
+            return self.inner.compare_and_swap(key, column, expected, new)
+        }
+        // Ciphertexts of the same value differ, so decrypted values are compared.
+        let new = self.seal(column, key, new)?;
+        let _guard = self.lock();
+        let current = self.open(column, key, self.inner.get(key, column)?)?;
+        if current.as_deref().map(Vec::as_slice) != expected {
+            return Ok(Err(current))
+        }
+        self.inner.put(key, column, new)?;
+        Ok(Ok(()))
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        let _guard = self.lock();
+        if !Self::is_encrypted(column) {
+            return self.inner.update(key, column, f)
+        }
+        // Ciphertexts of the same value differ, so decrypted values are compared.
+        update_encoded(
+            self.inner.as_ref(),
+            key,
+            column,
+            f,
+            &|stored| Ok(Some(self.decrypt(column, key, stored)?)),
+            &|value| Ok(self.encrypt(column, key, &value)?.to_vec()),
+        )
+    }
+}
+
+impl<Description> IteratorableStore for EncryptedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        let iter = self.inner.iter_all(column, prefix, start, direction);
+        self.decrypted(column, iter)
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        let iter = self.inner.iter_range(column, prefix, start, end, direction);
+        self.decrypted(column, iter)
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.inner.iter_keys(column, prefix, start, direction)
+    }
+
+    fn has_any(&self, column: Self::Column, prefix: &[u8]) -> StorageResult<bool> {
+        self.inner.has_any(column, prefix)
+    }
+
+    fn iter(&self, column: Self::Column, config: IterConfig) -> PageIter {
+        let iter = self.inner.iter(column, config);
+        match Self::is_encrypted(column) {
+            true => PageIter::new(self.decrypted(column, iter.into_boxed())),
+            false => iter,
+        }
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        let _guard = self.lock();
+        self.inner.delete_all(column, prefix)
+    }
+}
+
+impl<Description> BatchOperations for EncryptedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for EncryptedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        let _guard = self.lock();
+        // Merges of encrypted columns are resolved into inserts. The batch may merge
+        // the key written earlier in the same batch, so its latest values are tracked.
+        let mut latest = HashMap::<(usize, Vec<u8>), Option<Value>>::new();
+        let mut sealed = Vec::new();
+        for (key, column, op) in entries {
+            if !Self::is_encrypted(column) {
+                sealed.push((key, column, op));
+                continue
+            }
+            let value = match op {
+                WriteOperation::Insert(value) => Some(value),
+                WriteOperation::Remove => None,
+                WriteOperation::Merge(operand) => {
+                    let existing = match latest.get(&(column.as_usize(), key.clone())) {
+                        Some(existing) => existing.clone(),
+                        None => self.open(column, &key, self.inner.get(&key, column)?)?,
+                    };
+                    Some(merge_value(
+                        column,
+                        existing.as_deref().map(Vec::as_slice),
+                        &operand,
+                    )?)
+                }
+            };
+            let op = match &value {
+                Some(value) => WriteOperation::Insert(self.encrypt(column, &key, value)?),
+                None => WriteOperation::Remove,
+            };
+            if column.merge_operator().is_some() {
+                latest.insert((column.as_usize(), key.clone()), value);
+            }
+            sealed.push((key, column, op));
+        }
+        self.inner
+            .batch_write_with(&mut sealed.into_iter(), durability)
+    }
+
+    fn iter_column(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> ColumnIter<'_> {
+        match Self::is_encrypted(column) {
+            true => ColumnIter::boxed(self.iter_all(column, prefix, start, direction)),
+            false => self.inner.iter_column(column, prefix, start, direction),
+        }
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        self.inner.flush()
+    }
+
+    /// Returns the statistics of stored values, i.e. sizes of encrypted values.
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        self.inner.column_stats(column)
+    }
+
+    fn engine_stats(&self) -> DatabaseResult<EngineStats> {
+        self.inner.engine_stats()
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        self.inner.write_pressure()
+    }
+
+    fn size_of_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> DatabaseResult<u64> {
+        self.inner.size_of_range(column, start, end)
+    }
+
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        self.inner.checkpoint(path)
+    }
+
+    fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()> {
+        self.inner.backup_to(backup_dir)
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        self.inner.compact(column, range)
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        let _guard = self.lock();
+        self.inner.clear_column(column)
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        // The snapshot stores encrypted values, so it is wrapped too.
+        Ok(Arc::new(Self {
+            inner: self.inner.snapshot()?,
+            cipher: self.cipher.clone(),
+            write_lock: Mutex::new(()),
+        }))
+    }
+
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        self.inner.subscribe(column, prefix)
+    }
+
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.inner.replay_change_log(from_seq, into)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.inner.truncate_change_log(seq)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        self.inner.freeze_columns(columns)
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        self.inner.unfreeze_columns(columns)
+    }
+
+    fn begin_shutdown(&self) {
+        self.inner.begin_shutdown()
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        self.inner.finish_shutdown()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::{
+            on_chain::OnChain,
+            test::{
+                apply_merge_operations,
+                merge_base,
+                merge_operations,
+                merged_state,
+                MergeColumn,
+                MergeDatabase,
+            },
+        },
+        state::{
+            in_memory::{
+                memory_store::MemoryStore,
+                transaction::MemoryTransactionView,
+            },
+            test_helpers::over_memory_store,
+            TransactableStorage,
+        },
+    };
+    use fuel_core_storage::column::Column;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    fn encrypted() -> (Arc<MemoryStore<OnChain>>, EncryptedStorage<OnChain>) {
+        over_memory_store(|inner| EncryptedStorage::new(inner, &KEY).unwrap())
+    }
+
+    #[test]
+    fn values_round_trip_and_are_stored_encrypted() {
+        // Given
+        let (inner, store) = encrypted();
+        let value = Arc::new(b"secret value".to_vec());
+
+        // When
+        store.put(&[1], Column::Coins, value.clone()).unwrap();
+
+        // Then
+        assert_eq!(store.get(&[1], Column::Coins).unwrap(), Some(value.clone()));
+        assert_eq!(store.size_of_value(&[1], Column::Coins).unwrap(), Some(12));
+        let stored = inner.get(&[1], Column::Coins).unwrap().unwrap();
+        assert_ne!(stored, value);
+        assert!(!stored
+            .windows(value.len())
+            .any(|window| window == value.as_slice()));
+        assert_eq!(
+            store.take(&[1], Column::Coins).unwrap(),
+            Some(value.clone())
+        );
+        assert!(!store.exists(&[1], Column::Coins).unwrap());
+    }
+
+    #[test]
+    fn opening_with_wrong_key_fails() {
+        // Given
+        let (inner, store) = encrypted();
+        store.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+
+        // When
+        let result = EncryptedStorage::<OnChain>::new(inner.clone(), &[8; 32]);
+
+        // Then
+        assert!(matches!(result, Err(DatabaseError::WrongEncryptionKey)));
+        assert!(EncryptedStorage::<OnChain>::new(inner, &KEY).is_ok());
+    }
+
+    #[test]
+    fn opening_unencrypted_database_fails() {
+        // Given
+        let inner = Arc::new(MemoryStore::<OnChain>::default());
+        inner.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+
+        // When
+        let result = EncryptedStorage::<OnChain>::new(inner, &KEY);
+
+        // Then
+        assert!(matches!(result, Err(DatabaseError::WrongEncryptionKey)));
+    }
+
+    #[test]
+    fn corrupted_value_fails_with_the_column_name() {
+        // Given
+        let (inner, store) = encrypted();
+        store.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+        // The value moved to another key doesn't decrypt.
+        let stored = inner.get(&[1], Column::Coins).unwrap().unwrap();
+        inner.put(&[2], Column::Coins, stored).unwrap();
+
+        // When
+        let result = store.get(&[2], Column::Coins);
+
+        // Then
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains(Column::Coins.name()), "{err}");
+    }
+
+    #[test]
+    fn iter_all_through_transaction_view_matches_plain_store() {
+        // Given
+        let plain = Arc::new(MemoryStore::<OnChain>::default());
+        let (_, encrypted) = encrypted();
+        let encrypted = Arc::new(encrypted);
+        for key in [[1, 2], [1, 1], [2, 1], [1, 3]] {
+            let value = Arc::new(key.to_vec());
+            plain
+                .put(&key, Column::ContractsState, value.clone())
+                .unwrap();
+            encrypted.put(&key, Column::ContractsState, value).unwrap();
+        }
+        let plain_view = MemoryTransactionView::<OnChain>::new(plain);
+        let encrypted_view = MemoryTransactionView::<OnChain>::new(encrypted);
+        for view in [&plain_view, &encrypted_view] {
+            view.put(&[1, 0], Column::ContractsState, Arc::new(vec![0]))
+                .unwrap();
+            view.delete(&[1, 2], Column::ContractsState).unwrap();
+        }
+
+        // When
+        let iters = [
+            (Some(&[1u8][..]), None, IterDirection::Forward),
+            (
+                Some(&[1u8][..]),
+                Some(&[1u8, 1][..]),
+                IterDirection::Forward,
+            ),
+            (Some(&[1u8][..]), None, IterDirection::Reverse),
+            (None, Some(&[1u8, 3][..]), IterDirection::Reverse),
+        ];
+
+        // Then
+        for (prefix, start, direction) in iters {
+            let plain = plain_view
+                .iter_all(Column::ContractsState, prefix, start, direction)
+                .collect::<StorageResult<Vec<_>>>()
+                .unwrap();
+            let encrypted = encrypted_view
+                .iter_all(Column::ContractsState, prefix, start, direction)
+                .collect::<StorageResult<Vec<_>>>()
+                .unwrap();
+            assert_eq!(plain, encrypted);
+        }
+    }
+
+    #[test]
+    fn merges_are_resolved_over_decrypted_values() {
+        for batched in [true, false] {
+            // Given
+            let store = EncryptedStorage::<MergeDatabase>::new(
+                Arc::new(MemoryStore::<MergeDatabase>::default()),
+                &KEY,
+            )
+            .unwrap();
+            store.batch_write(&mut merge_base().into_iter()).unwrap();
+
+            // When
+            if batched {
+                store
+                    .batch_write(&mut merge_operations().into_iter())
+                    .unwrap();
+            } else {
+                apply_merge_operations(&store, merge_operations());
+            }
+
+            // Then
+            for (column, key, value) in merged_state() {
+                assert_eq!(store.get(&key, column).unwrap(), Some(value));
+            }
+            assert_eq!(store.get(&[3], MergeColumn::Counters).unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn compare_and_swap_compares_decrypted_values() {
+        // Given
+        let (_, store) = encrypted();
+        store.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+
+        // When
+        let mismatched = store
+            .compare_and_swap(&[1], Column::Coins, Some(&[2]), Arc::new(vec![3]))
+            .unwrap();
+        let swapped = store
+            .compare_and_swap(&[1], Column::Coins, Some(&[1]), Arc::new(vec![3]))
+            .unwrap();
+
+        // Then
+        assert_eq!(mismatched, Err(Some(Arc::new(vec![1]))));
+        assert_eq!(swapped, Ok(()));
+        assert_eq!(
+            store.get(&[1], Column::Coins).unwrap(),
+            Some(Arc::new(vec![3]))
+        );
+        let snapshot = TransactableStorage::snapshot(&store).unwrap();
+        assert_eq!(
+            snapshot.get(&[1], Column::Coins).unwrap(),
+            Some(Arc::new(vec![3]))
+        );
+    }
+
+    #[test]
+    fn concurrent_updates_do_not_lose_increments() {
+        // Given
+        let (inner, store) = encrypted();
+        let barrier = std::sync::Barrier::new(8);
+
+        // When
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    barrier.wait();
+                    for _ in 0..100 {
+                        store
+                            .update(&[0xA], Column::Coins, &mut |current| {
+                                let current = current.map_or(0, |current| {
+                                    u64::from_be_bytes(current.try_into().unwrap())
+                                });
+                                Some(current.saturating_add(1).to_be_bytes().to_vec())
+                            })
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        // Then
+        let stored = store.get(&[0xA], Column::Coins).unwrap().unwrap();
+        assert_eq!(stored.as_slice(), 800u64.to_be_bytes());
+        let raw = inner.get(&[0xA], Column::Coins).unwrap().unwrap();
+        assert_ne!(raw.as_slice(), 800u64.to_be_bytes());
+    }
+}
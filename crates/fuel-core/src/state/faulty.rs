@@ -0,0 +1,719 @@
+//! The storage wrapper that injects faults into operations of the inner storage.
+//! It is used to test the handling of storage errors.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::{
+        notifying::StorageEvent,
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        IterDirection,
+        TransactableStorage,
+        WriteDurability,
+        WritePressure,
+    },
+};
+use fuel_core_storage::{
+    iter::{
+        BoxedIter,
+        IntoBoxedIter,
+        IteratorableStore,
+    },
+    kv_store::{
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Error as StorageError,
+    Result as StorageResult,
+};
+use std::{
+    collections::HashSet,
+    path::Path,
+    sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        Mutex,
+    },
+    time::Duration,
+};
+use tokio::sync::broadcast;
+
+/// The kind of the storage operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Op {
+    /// Reads of the value: `get`, `get_with`, `multi_get`, `read`, and `size_of_value`.
+    Get,
+    /// The check of the existence of the value.
+    Exists,
+    /// Writes of the value: `put`, `replace`, `write`, `merge`, and `compare_and_swap`.
+    Put,
+    /// Removals of values: `delete`, `take`, and `delete_all`.
+    Delete,
+    /// Iterations over the column.
+    Iter,
+    /// The write of the batch.
+    BatchWrite,
+}
+
+#[derive(Debug, Default)]
+struct Faults {
+    /// The number of the operation from which all operations fail.
+    fail_from: Option<usize>,
+    /// The pairs of the column id and the operation that fail.
+    fail_on: HashSet<(u32, Op)>,
+    fail_batch_write: bool,
+    /// The number of items yielded by iterators before the failure.
+    fail_iter_after: Option<usize>,
+    latency: Option<Duration>,
+}
+
+/// The storage that delegates to the inner storage but fails operations as programmed.
+/// Faults can be programmed while the storage is shared, for example, as the [`DataSource`].
+#[derive(Debug)]
+pub struct FaultyStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    inner: DataSourceInner<Description::Column>,
+    faults: Mutex<Faults>,
+    /// The number of operations performed since the creation.
+    operations: AtomicUsize,
+}
+
+impl<Description> FaultyStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Wraps the `inner` storage without faults.
+    pub fn new<D>(inner: D) -> Self
+    where
+        D: Into<DataSource<Description>>,
+    {
+        Self {
+            inner: inner.into().0,
+            faults: Default::default(),
+            operations: AtomicUsize::new(0),
+        }
+    }
+
+    /// The next `n` operations succeed, and all operations after them fail.
+    pub fn fail_after(&self, n: usize) {
+        let from = self.operations.load(Ordering::SeqCst).saturating_add(n);
+        self.faults().fail_from = Some(from);
+    }
+
+    /// Operations `op` over the `column` fail.
+    pub fn fail_on(&self, column: Description::Column, op: Op) {
+        self.faults().fail_on.insert((column.id(), op));
+    }
+
+    /// All batch writes fail, while other operations succeed.
+    pub fn fail_batch_write(&self) {
+        self.faults().fail_batch_write = true;
+    }
+
+    /// Iterators yield `k` items, and then fail.
+    pub fn fail_iter_after(&self, k: usize) {
+        self.faults().fail_iter_after = Some(k);
+    }
+
+    /// Each operation is delayed by the `latency`.
+    pub fn set_latency(&self, latency: Duration) {
+        self.faults().latency = Some(latency);
+    }
+
+    /// Removes all programmed faults and latency.
+    pub fn reset(&self) {
+        *self.faults() = Default::default();
+    }
+
+    /// Returns the number of operations performed since the creation.
+    pub fn operations(&self) -> usize {
+        self.operations.load(Ordering::SeqCst)
+    }
+
+    fn faults(&self) -> std::sync::MutexGuard<'_, Faults> {
+        self.faults.lock().expect("poisoned")
+    }
+
+    fn check(&self, column: Option<Description::Column>, op: Op) -> StorageResult<()> {
+        let operation = self.operations.fetch_add(1, Ordering::SeqCst);
+        let (latency, failed) = {
+            let faults = self.faults();
+            let failed = faults.fail_from.is_some_and(|from| operation >= from)
+                || (op == Op::BatchWrite && faults.fail_batch_write)
+                || column
+                    .is_some_and(|column| faults.fail_on.contains(&(column.id(), op)));
+            (faults.latency, failed)
+        };
+
+        if let Some(latency) = latency {
+            std::thread::sleep(latency);
+        }
+
+        if failed {
+            return Err(fault(column, op))
+        }
+        Ok(())
+    }
+
+    fn faulty_iter<'a, T>(
+        &'a self,
+        column: Description::Column,
+        iter: impl FnOnce() -> BoxedIter<'a, StorageResult<T>>,
+    ) -> BoxedIter<'a, StorageResult<T>>
+    where
+        T: 'a,
+    {
+        if let Err(err) = self.check(Some(column), Op::Iter) {
+            return core::iter::once(Err(err)).into_boxed()
+        }
+
+        match self.faults().fail_iter_after {
+            None => iter(),
+            Some(k) => iter()
+                .enumerate()
+                .map(move |(i, item)| {
+                    if i >= k {
+                        Err(fault(Some(column), Op::Iter))
+                    } else {
+                        item
+                    }
+                })
+                .into_boxed(),
+        }
+    }
+}
+
+/// Injected faults are reported as I/O failures of the backend.
+fn fault<Column: StorageColumn>(column: Option<Column>, op: Op) -> StorageError {
+    let error = match column {
+        Some(column) => {
+            anyhow::anyhow!("Injected fault of {op:?} on the column `{}`", column.name())
+        }
+        None => anyhow::anyhow!("Injected fault of {op:?}"),
+    };
+    DatabaseError::Io(error).into()
+}
+
+impl<Description> KeyValueStore for FaultyStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        self.check(Some(column), Op::Put)?;
+        self.inner.put(key, column, value)
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        self.check(Some(column), Op::Put)?;
+        self.inner.replace(key, column, value)
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        self.check(Some(column), Op::Put)?;
+        self.inner.write(key, column, buf)
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.check(Some(column), Op::Delete)?;
+        self.inner.take(key, column)
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        self.check(Some(column), Op::Delete)?;
+        self.inner.delete(key, column)
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        self.check(Some(column), Op::Put)?;
+        self.inner.merge(key, column, operand)
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        self.check(Some(column), Op::Exists)?;
+        self.inner.exists(key, column)
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        self.check(Some(column), Op::Get)?;
+        self.inner.size_of_value(key, column)
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.check(Some(column), Op::Get)?;
+        self.inner.get(key, column)
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        self.check(Some(column), Op::Get)?;
+        self.inner.get_with(key, column, f)
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        self.check(Some(column), Op::Get)?;
+        self.inner.multi_get(keys, column)
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        self.check(Some(column), Op::Put)?;
+        self.inner.compare_and_swap(key, column, expected, new)
+    }
+
+    fn get_or_insert_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        init: &mut dyn FnMut() -> Vec<u8>,
+    ) -> StorageResult<Value> {
+        self.check(Some(column), Op::Put)?;
+        self.inner.get_or_insert_with(key, column, init)
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        self.check(Some(column), Op::Put)?;
+        self.inner.update(key, column, f)
+    }
+
+    fn read(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &mut [u8],
+    ) -> StorageResult<Option<usize>> {
+        self.check(Some(column), Op::Get)?;
+        self.inner.read(key, column, buf)
+    }
+}
+
+impl<Description> IteratorableStore for FaultyStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.faulty_iter(column, || {
+            self.inner.iter_all(column, prefix, start, direction)
+        })
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.faulty_iter(column, || {
+            self.inner.iter_range(column, prefix, start, end, direction)
+        })
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.faulty_iter(column, || {
+            self.inner.iter_keys(column, prefix, start, direction)
+        })
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        self.check(Some(column), Op::Delete)?;
+        self.inner.delete_all(column, prefix)
+    }
+}
+
+impl<Description> BatchOperations for FaultyStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for FaultyStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        let entries: Vec<_> = entries.collect();
+        self.check(None, Op::BatchWrite)?;
+        {
+            let faults = self.faults();
+            if let Some((_, column, _)) = entries.iter().find(|(_, column, _)| {
+                faults.fail_on.contains(&(column.id(), Op::BatchWrite))
+            }) {
+                return Err(fault(Some(*column), Op::BatchWrite))
+            }
+        }
+        self.inner
+            .batch_write_with(&mut entries.into_iter(), durability)
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        self.inner.flush()
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        self.inner.column_stats(column)
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        self.inner.write_pressure()
+    }
+
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        self.inner.checkpoint(path)
+    }
+
+    fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()> {
+        self.inner.backup_to(backup_dir)
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        self.inner.compact(column, range)
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        self.inner.clear_column(column)
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        self.inner.snapshot()
+    }
+
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        self.inner.subscribe(column, prefix)
+    }
+
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.inner.replay_change_log(from_seq, into)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.inner.truncate_change_log(seq)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        self.inner.freeze_columns(columns)
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        self.inner.unfreeze_columns(columns)
+    }
+
+    fn begin_shutdown(&self) {
+        self.inner.begin_shutdown()
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        self.inner.finish_shutdown()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{
+            database_description::on_chain::OnChain,
+            Database,
+        },
+        state::{
+            in_memory::{
+                memory_store::MemoryStore,
+                transaction::MemoryTransactionView,
+            },
+            test_helpers::over_memory_store,
+        },
+    };
+    use fuel_core_storage::{
+        column::Column,
+        tables::{
+            ContractsAssets,
+            ContractsInfo,
+            ContractsLatestUtxo,
+            ContractsRawCode,
+        },
+        transactional::Transaction,
+        ContractsAssetKey,
+        StorageAsMut,
+    };
+    use fuel_core_types::{
+        entities::contract::ContractUtxoInfo,
+        fuel_tx::Contract,
+        fuel_types::{
+            AssetId,
+            ContractId,
+        },
+    };
+    use std::sync::Arc;
+
+    fn faulty_store() -> (Arc<MemoryStore>, Arc<FaultyStorage<OnChain>>) {
+        over_memory_store(|inner| Arc::new(FaultyStorage::new(inner)))
+    }
+
+    #[test]
+    fn fail_after_fails_operations_after_the_nth() {
+        // Given
+        let (_, faulty) = faulty_store();
+        faulty.fail_after(2);
+
+        // When
+        let first = faulty.put(&[1], Column::Metadata, Arc::new(vec![1]));
+        let second = faulty.get(&[1], Column::Metadata);
+        let third = faulty.exists(&[1], Column::Metadata);
+
+        // Then
+        assert!(first.is_ok());
+        assert!(second.is_ok());
+        assert!(third.is_err());
+        faulty.reset();
+        assert!(faulty.exists(&[1], Column::Metadata).unwrap());
+    }
+
+    #[test]
+    fn fail_on_fails_only_the_operation_of_the_column() {
+        // Given
+        let (_, faulty) = faulty_store();
+        faulty.fail_on(Column::Coins, Op::Put);
+
+        // Then
+        assert!(faulty.put(&[1], Column::Coins, Arc::new(vec![1])).is_err());
+        assert!(faulty.get(&[1], Column::Coins).is_ok());
+        assert!(faulty
+            .put(&[1], Column::Metadata, Arc::new(vec![1]))
+            .is_ok());
+    }
+
+    #[test]
+    fn failed_commit_of_transaction_view_keeps_its_changes() {
+        // Given
+        let (store, faulty) = faulty_store();
+        let view = MemoryTransactionView::<OnChain>::new(faulty.clone());
+        view.put(&[1], Column::Metadata, Arc::new(vec![1])).unwrap();
+        view.delete(&[2], Column::Coins).unwrap();
+        faulty.fail_batch_write();
+
+        // When
+        let result = view.commit();
+
+        // Then
+        assert!(result.is_err());
+        assert!(!store.exists(&[1], Column::Metadata).unwrap());
+        assert_eq!(view.pending_writes().unwrap().len(), 2);
+        faulty.reset();
+        view.commit().unwrap();
+        assert!(store.exists(&[1], Column::Metadata).unwrap());
+    }
+
+    #[test]
+    fn failed_commit_of_database_transaction_leaves_database_unchanged() {
+        // Given
+        let (store, faulty) = faulty_store();
+        let database = Database::<OnChain>::new(faulty.clone());
+        let mut transaction = database.transaction();
+        let contract_id = ContractId::from([1; 32]);
+        transaction
+            .as_mut()
+            .storage_as_mut::<ContractsRawCode>()
+            .insert(&contract_id, Contract::from(vec![1, 2, 3]).as_ref())
+            .unwrap();
+        faulty.fail_on(Column::ContractsRawCode, Op::BatchWrite);
+
+        // When
+        let result = transaction.commit();
+
+        // Then
+        assert!(result.is_err());
+        assert!(store
+            .iter_all(Column::ContractsRawCode, None, None, IterDirection::Forward)
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn iterator_consumers_propagate_iteration_errors() {
+        // Given
+        let (_, faulty) = faulty_store();
+        let mut database = Database::<OnChain>::new(faulty.clone());
+        let contract_id = ContractId::from([1; 32]);
+        database
+            .storage_as_mut::<ContractsRawCode>()
+            .insert(&contract_id, Contract::from(vec![1]).as_ref())
+            .unwrap();
+        database
+            .storage_as_mut::<ContractsInfo>()
+            .insert(&contract_id, &(Default::default(), Default::default()))
+            .unwrap();
+        database
+            .storage_as_mut::<ContractsLatestUtxo>()
+            .insert(&contract_id, &ContractUtxoInfo::default())
+            .unwrap();
+        for i in 0..3 {
+            database
+                .storage_as_mut::<ContractsAssets>()
+                .insert(
+                    &ContractsAssetKey::new(&contract_id, &AssetId::from([i; 32])),
+                    &10,
+                )
+                .unwrap();
+        }
+        assert!(database.get_contract_config_by_id(contract_id).is_ok());
+
+        // When
+        faulty.fail_iter_after(1);
+        let config = database.get_contract_config_by_id(contract_id);
+        let balances = database
+            .contract_balances(contract_id, None, None)
+            .collect::<StorageResult<Vec<_>>>();
+
+        // Then
+        assert!(config.is_err());
+        assert!(balances.is_err());
+    }
+
+    #[test]
+    fn reads_propagate_errors_instead_of_panicking() {
+        // Given
+        let (_, faulty) = faulty_store();
+        let database = Database::<OnChain>::new(faulty.clone());
+        faulty.fail_on(Column::ContractsRawCode, Op::Get);
+
+        // When
+        let result = database.get_contract_config_by_id(ContractId::from([1; 32]));
+
+        // Then
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn latency_delays_operations() {
+        // Given
+        let (_, faulty) = faulty_store();
+        faulty.set_latency(Duration::from_millis(20));
+
+        // When
+        let start = std::time::Instant::now();
+        faulty.get(&[1], Column::Metadata).unwrap();
+
+        // Then
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn injected_io_failure_keeps_its_variant_through_commit() {
+        // Given
+        let (_, faulty) = faulty_store();
+        let database = Database::<OnChain>::new(faulty.clone());
+        let mut transaction = database.transaction();
+        transaction
+            .as_mut()
+            .storage_as_mut::<ContractsRawCode>()
+            .insert(&ContractId::from([1; 32]), Contract::from(vec![1]).as_ref())
+            .unwrap();
+        faulty.fail_batch_write();
+
+        // When
+        let result = transaction.commit();
+
+        // Then
+        assert!(matches!(
+            result.map_err(DatabaseError::from),
+            Err(DatabaseError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,617 @@
+//! The guard that freezes columns against writes, see [`GuardedStorage`].
+//!
+//! The snapshot export, the regenesis, and the integrity verification read columns
+//! over a long time and assume that they don't change. The [`GuardedStorage`] rejects
+//! writes to frozen columns with the [`DatabaseError::ColumnFrozen`], so the stray write
+//! of another service fails loudly instead of invalidating the result. Columns are frozen
+//! by the [`FreezeGuard`] of the [`crate::database::Database::freeze`] until it is dropped.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::{
+        column_iter::ColumnIter,
+        notifying::StorageEvent,
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        EngineStats,
+        IterDirection,
+        TransactableStorage,
+        WriteDurability,
+        WritePressure,
+    },
+};
+use fuel_core_storage::{
+    iter::{
+        BoxedIter,
+        IterConfig,
+        IteratorableStore,
+        PageIter,
+    },
+    kv_store::{
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Result as StorageResult,
+};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{
+        Arc,
+        RwLock,
+        RwLockReadGuard,
+    },
+};
+use tokio::sync::broadcast;
+
+/// The number of active freezes of each frozen column by the id of the column.
+type Freezes = HashMap<u32, usize>;
+
+/// The storage that rejects writes to frozen columns and passes everything else
+/// to the inner storage. Reads and iterations of frozen columns are unaffected.
+///
+/// Writes hold the shared lock of freezes until the inner storage applies them,
+/// so the write that passed the check lands before the freeze of its column returns.
+/// The guard should be the outermost wrapper, so writes buffered by other wrappers
+/// are checked before they are accepted.
+#[derive(Debug)]
+pub struct GuardedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    inner: DataSourceInner<Description::Column>,
+    freezes: Arc<RwLock<Freezes>>,
+}
+
+impl<Description> GuardedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Wraps the `inner` storage without frozen columns.
+    pub fn new<D>(inner: D) -> Self
+    where
+        D: Into<DataSource<Description>>,
+    {
+        Self {
+            inner: inner.into().0,
+            freezes: Default::default(),
+        }
+    }
+
+    /// Returns `true` if the `column` is frozen.
+    pub fn is_frozen(&self, column: Description::Column) -> bool {
+        self.freezes
+            .read()
+            .expect("poisoned")
+            .contains_key(&column.id())
+    }
+
+    /// Fails if any of the `columns` is frozen, otherwise returns the lock
+    /// that keeps them unfrozen until it is dropped.
+    fn check(
+        &self,
+        columns: impl IntoIterator<Item = Description::Column>,
+    ) -> DatabaseResult<RwLockReadGuard<'_, Freezes>> {
+        let freezes = self.freezes.read().expect("poisoned");
+        for column in columns {
+            if freezes.contains_key(&column.id()) {
+                return Err(DatabaseError::ColumnFrozen(column.name()))
+            }
+        }
+        Ok(freezes)
+    }
+}
+
+impl<Description> KeyValueStore for GuardedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        let _unfrozen = self.check([column])?;
+        self.inner.put(key, column, value)
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        let _unfrozen = self.check([column])?;
+        self.inner.replace(key, column, value)
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        let _unfrozen = self.check([column])?;
+        self.inner.write(key, column, buf)
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        let _unfrozen = self.check([column])?;
+        self.inner.take(key, column)
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        let _unfrozen = self.check([column])?;
+        self.inner.delete(key, column)
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        let _unfrozen = self.check([column])?;
+        self.inner.merge(key, column, operand)
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        self.inner.exists(key, column)
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        self.inner.size_of_value(key, column)
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.inner.get(key, column)
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        self.inner.get_with(key, column, f)
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        self.inner.multi_get(keys, column)
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        let _unfrozen = self.check([column])?;
+        self.inner.compare_and_swap(key, column, expected, new)
+    }
+
+    fn get_or_insert_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        init: &mut dyn FnMut() -> Vec<u8>,
+    ) -> StorageResult<Value> {
+        let _unfrozen = self.check([column])?;
+        self.inner.get_or_insert_with(key, column, init)
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        let _unfrozen = self.check([column])?;
+        self.inner.update(key, column, f)
+    }
+}
+
+impl<Description> IteratorableStore for GuardedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.inner.iter_all(column, prefix, start, direction)
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.inner.iter_range(column, prefix, start, end, direction)
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.inner.iter_keys(column, prefix, start, direction)
+    }
+
+    fn has_any(&self, column: Self::Column, prefix: &[u8]) -> StorageResult<bool> {
+        self.inner.has_any(column, prefix)
+    }
+
+    fn iter(&self, column: Self::Column, config: IterConfig) -> PageIter {
+        self.inner.iter(column, config)
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        let _unfrozen = self.check([column])?;
+        self.inner.delete_all(column, prefix)
+    }
+}
+
+impl<Description> BatchOperations for GuardedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for GuardedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        // The batch touching any frozen column is rejected before anything is applied.
+        let entries = entries.collect::<Vec<_>>();
+        let _unfrozen = self.check(entries.iter().map(|(_, column, _)| *column))?;
+        self.inner
+            .batch_write_with(&mut entries.into_iter(), durability)
+    }
+
+    fn iter_column(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> ColumnIter<'_> {
+        self.inner.iter_column(column, prefix, start, direction)
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        self.inner.flush()
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        self.inner.column_stats(column)
+    }
+
+    fn engine_stats(&self) -> DatabaseResult<EngineStats> {
+        self.inner.engine_stats()
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        self.inner.write_pressure()
+    }
+
+    fn size_of_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> DatabaseResult<u64> {
+        self.inner.size_of_range(column, start, end)
+    }
+
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        self.inner.checkpoint(path)
+    }
+
+    fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()> {
+        self.inner.backup_to(backup_dir)
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        self.inner.compact(column, range)
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        let _unfrozen = self.check([column])?;
+        self.inner.clear_column(column)
+    }
+
+    fn multi_delete(
+        &self,
+        column: Self::Column,
+        keys: &[Vec<u8>],
+    ) -> DatabaseResult<usize> {
+        let _unfrozen = self.check([column])?;
+        self.inner.multi_delete(column, keys)
+    }
+
+    fn delete_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: &[u8],
+    ) -> DatabaseResult<()> {
+        let _unfrozen = self.check([column])?;
+        self.inner.delete_range(column, start, end)
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        // The snapshot shares freezes, so it rejects writes to frozen columns too.
+        Ok(Arc::new(Self {
+            inner: self.inner.snapshot()?,
+            freezes: self.freezes.clone(),
+        }))
+    }
+
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        self.inner.subscribe(column, prefix)
+    }
+
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.inner.replay_change_log(from_seq, into)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.inner.truncate_change_log(seq)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        // Waits for writes that passed the check before the freeze.
+        let mut freezes = self.freezes.write().expect("poisoned");
+        for column in columns {
+            let count = freezes.entry(column.id()).or_default();
+            *count = count.saturating_add(1);
+        }
+        Ok(())
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        let mut freezes = self.freezes.write().expect("poisoned");
+        for column in columns {
+            if let Some(count) = freezes.get_mut(&column.id()) {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    freezes.remove(&column.id());
+                }
+            }
+        }
+    }
+
+    fn begin_shutdown(&self) {
+        self.inner.begin_shutdown()
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        self.inner.finish_shutdown()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// The freeze of columns of the storage, returned by the
+/// [`crate::database::Database::freeze`]. Columns are unfrozen when it is dropped,
+/// unless another guard still freezes them.
+#[must_use = "Columns are unfrozen when the guard is dropped"]
+#[derive(Debug)]
+pub struct FreezeGuard<Description>
+where
+    Description: DatabaseDescription,
+{
+    storage: DataSource<Description>,
+    columns: Vec<Description::Column>,
+}
+
+impl<Description> FreezeGuard<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Freezes the `columns` of the `storage` until the guard is dropped.
+    pub fn new(
+        storage: DataSource<Description>,
+        columns: Vec<Description::Column>,
+    ) -> DatabaseResult<Self> {
+        storage.freeze_columns(&columns)?;
+        Ok(Self { storage, columns })
+    }
+
+    /// Returns the frozen columns.
+    pub fn columns(&self) -> &[Description::Column] {
+        &self.columns
+    }
+}
+
+impl<Description> Drop for FreezeGuard<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn drop(&mut self) {
+        self.storage.unfreeze_columns(&self.columns);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{
+            database_description::on_chain::OnChain,
+            Database,
+        },
+        state::test_helpers::over_memory_store,
+    };
+    use fuel_core_storage::column::Column;
+
+    fn guarded() -> (Database<OnChain>, Arc<GuardedStorage<OnChain>>) {
+        let (_, storage) =
+            over_memory_store(|inner| Arc::new(GuardedStorage::new(inner)));
+        (Database::new(storage.clone()), storage)
+    }
+
+    fn is_frozen(result: impl core::fmt::Debug, column: Column) -> bool {
+        format!("{result:?}").contains(&format!("ColumnFrozen({:?})", column.name()))
+    }
+
+    fn value(byte: u8) -> Value {
+        Arc::new(vec![byte])
+    }
+
+    #[test]
+    fn writes_to_frozen_columns_are_rejected_and_reads_pass() {
+        // Given
+        let (database, storage) = guarded();
+        storage.put(&[1], Column::Coins, value(1)).unwrap();
+        let _guard = database.freeze(&[Column::Coins]).unwrap();
+
+        // When
+        let put = storage.put(&[2], Column::Coins, value(2));
+        let deleted = storage.delete(&[1], Column::Coins);
+        let deleted_all = storage.delete_all(Column::Coins, None);
+        let cleared = storage.clear_column(Column::Coins);
+        let deleted_range = storage.delete_range(Column::Coins, &[0], &[9]);
+        let deleted_many = storage.multi_delete(Column::Coins, &[vec![1]]);
+        let unfrozen = storage.put(&[2], Column::Messages, value(2));
+
+        // Then
+        assert!(is_frozen(put, Column::Coins));
+        assert!(is_frozen(deleted, Column::Coins));
+        assert!(is_frozen(deleted_all, Column::Coins));
+        assert!(is_frozen(cleared, Column::Coins));
+        assert!(is_frozen(deleted_range, Column::Coins));
+        assert!(is_frozen(deleted_many, Column::Coins));
+        assert!(unfrozen.is_ok());
+        assert_eq!(storage.get(&[1], Column::Coins).unwrap(), Some(value(1)));
+        assert_eq!(
+            storage
+                .iter_keys(Column::Coins, None, None, IterDirection::Forward)
+                .collect::<StorageResult<Vec<_>>>()
+                .unwrap(),
+            vec![vec![1]]
+        );
+    }
+
+    #[test]
+    fn batch_with_the_frozen_column_applies_nothing() {
+        // Given
+        let (database, storage) = guarded();
+        let _guard = database.freeze(&[Column::Coins]).unwrap();
+
+        // When
+        let result = storage.batch_write(
+            &mut [
+                (vec![1], Column::Messages, WriteOperation::Insert(value(1))),
+                (vec![1], Column::Coins, WriteOperation::Insert(value(1))),
+                (vec![2], Column::Messages, WriteOperation::Insert(value(2))),
+            ]
+            .into_iter(),
+        );
+
+        // Then
+        assert!(is_frozen(result, Column::Coins));
+        assert_eq!(storage.get(&[1], Column::Messages).unwrap(), None);
+        assert_eq!(storage.get(&[2], Column::Messages).unwrap(), None);
+        assert_eq!(storage.get(&[1], Column::Coins).unwrap(), None);
+    }
+
+    #[test]
+    fn overlapping_freezes_compose_and_the_drop_restores_writes() {
+        // Given
+        let (database, storage) = guarded();
+        let outer = database.freeze(&[Column::Coins, Column::Messages]).unwrap();
+        let inner = database.freeze(&[Column::Coins]).unwrap();
+
+        // When
+        drop(outer);
+        let messages = storage.put(&[1], Column::Messages, value(1));
+        let coins_while_frozen = storage.put(&[1], Column::Coins, value(1));
+        drop(inner);
+        let coins = storage.put(&[1], Column::Coins, value(1));
+
+        // Then
+        assert!(messages.is_ok());
+        assert!(is_frozen(coins_while_frozen, Column::Coins));
+        assert!(coins.is_ok());
+        assert!(!storage.is_frozen(Column::Coins));
+        assert_eq!(storage.get(&[1], Column::Coins).unwrap(), Some(value(1)));
+    }
+
+    #[test]
+    fn only_the_database_with_the_guard_can_be_frozen() {
+        // Given
+        let unguarded = Database::<OnChain>::in_memory();
+        let guarded = Database::<OnChain>::in_memory()
+            .with_metrics()
+            .with_column_guard();
+
+        // When
+        let unsupported = unguarded.freeze(&[Column::Coins]);
+        let frozen = guarded.freeze(&[Column::Coins]);
+
+        // Then
+        assert!(matches!(
+            unsupported,
+            Err(DatabaseError::Unsupported("column freezing"))
+        ));
+        assert_eq!(frozen.unwrap().columns(), &[Column::Coins]);
+    }
+}
@@ -0,0 +1,616 @@
+use crate::{
+    database::database_description::{
+        on_chain::OnChain,
+        DatabaseDescription,
+    },
+    state::{
+        BatchOperations,
+        DataSource,
+        IterDirection,
+        ReadView,
+    },
+};
+use fuel_core_storage::{
+    codec::height::BlockHeightKey,
+    iter::{
+        BoxedIter,
+        IntoBoxedIter,
+        IteratorableStore,
+    },
+    kv_store::{
+        merge_value,
+        KVItem,
+        KeyValueStore,
+        StorageColumn,
+        Value,
+        WriteOperation,
+    },
+    Error as StorageError,
+    Result as StorageResult,
+};
+use itertools::Itertools;
+use std::{
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    sync::{
+        atomic::{
+            AtomicU32,
+            Ordering,
+        },
+        Arc,
+    },
+};
+
+/// The size of the big-endian height suffix of the versioned key.
+const HEIGHT_SIZE: usize = BlockHeightKey::LEN;
+/// The first byte of the versioned value that marks the removal of the value.
+const REMOVED: u8 = 0;
+/// The first byte of the versioned value that marks the insertion of the value.
+const INSERTED: u8 = 1;
+
+/// The adapter over the data source that keeps all versions of values in the versioned columns.
+/// Each write into the versioned column adds a new version of the value at the current height,
+/// while the removal adds a tombstone version. Writes into other columns are passed through.
+///
+/// Callers read and write the adapter as any other storage and see the latest versions.
+/// The [`HistoricalView`] returned by [`Self::at_height`] sees versions as of the height.
+///
+/// Keys of the versioned columns must have a fixed size within the column because the
+/// versioned key is the key with the big-endian height suffix.
+#[derive(Debug)]
+pub struct VersionedStorage<Description = OnChain>
+where
+    Description: DatabaseDescription,
+{
+    data_source: DataSource<Description>,
+    versioned_columns: Arc<HashSet<u32>>,
+    height: AtomicU32,
+}
+
+impl<Description> VersionedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    pub fn new<D>(
+        data_source: D,
+        versioned_columns: impl IntoIterator<Item = Description::Column>,
+        height: u32,
+    ) -> Self
+    where
+        D: Into<DataSource<Description>>,
+    {
+        Self {
+            data_source: data_source.into(),
+            versioned_columns: Arc::new(
+                versioned_columns.into_iter().map(|c| c.id()).collect(),
+            ),
+            height: AtomicU32::new(height),
+        }
+    }
+
+    /// Returns the height used for new versions of values.
+    pub fn height(&self) -> u32 {
+        self.height.load(Ordering::Acquire)
+    }
+
+    /// Sets the height used for new versions of values.
+    /// The height should not decrease; otherwise, new versions are hidden by the existing ones.
+    pub fn set_height(&self, height: u32) {
+        self.height.store(height, Ordering::Release)
+    }
+
+    /// Returns the view of the storage as of the `height`.
+    pub fn at_height(&self, height: u32) -> HistoricalView<Description> {
+        HistoricalView {
+            data_source: self.data_source.clone(),
+            versioned_columns: self.versioned_columns.clone(),
+            height,
+        }
+    }
+
+    fn is_versioned(&self, column: Description::Column) -> bool {
+        self.versioned_columns.contains(&column.id())
+    }
+}
+
+impl<Description> KeyValueStore for VersionedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        if !self.is_versioned(column) {
+            return self.data_source.put(key, column, value)
+        }
+        self.write(key, column, value.as_slice()).map(|_| ())
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        if !self.is_versioned(column) {
+            return self.data_source.write(key, column, buf)
+        }
+        self.data_source.write(
+            &versioned_key(key, self.height()),
+            column,
+            &encode_value(Some(buf)),
+        )?;
+        Ok(buf.len())
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        if !self.is_versioned(column) {
+            return self.data_source.delete(key, column)
+        }
+        self.data_source
+            .write(
+                &versioned_key(key, self.height()),
+                column,
+                &encode_value(None),
+            )
+            .map(|_| ())
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        if !self.is_versioned(column) {
+            return self.data_source.merge(key, column, operand)
+        }
+        // The new version contains the merged value.
+        let existing = self.get(key, column)?;
+        let value =
+            merge_value(column, existing.as_deref().map(Vec::as_slice), &operand)?;
+        self.put(key, column, value)
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        if !self.is_versioned(column) {
+            return self.data_source.get(key, column)
+        }
+        get_at(&self.data_source, key, column, u32::MAX)
+    }
+}
+
+impl<Description> IteratorableStore for VersionedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        if !self.is_versioned(column) {
+            return self.data_source.iter_all(column, prefix, start, direction)
+        }
+        iter_at(
+            &self.data_source,
+            column,
+            prefix,
+            start,
+            direction,
+            u32::MAX,
+        )
+    }
+}
+
+impl<Description> BatchOperations for VersionedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        // All versions of the batch are created at the same height.
+        let height = self.height();
+        // The merged values of versioned columns are stored as new versions,
+        // so merges are resolved against the latest versions and earlier entries of the batch.
+        let mut merged = HashMap::<(u32, Vec<u8>), Option<Value>>::new();
+        let mut versioned = vec![];
+        for (key, column, op) in entries {
+            if !self.is_versioned(column) {
+                versioned.push((key, column, op));
+                continue
+            }
+            let value = match op {
+                WriteOperation::Insert(value) => Some(value),
+                WriteOperation::Remove => None,
+                WriteOperation::Merge(operand) => {
+                    let existing = match merged.get(&(column.id(), key.clone())) {
+                        Some(value) => value.clone(),
+                        None => self.get(&key, column)?,
+                    };
+                    Some(merge_value(
+                        column,
+                        existing.as_deref().map(Vec::as_slice),
+                        &operand,
+                    )?)
+                }
+            };
+            let encoded = encode_value(value.as_deref().map(Vec::as_slice));
+            merged.insert((column.id(), key.clone()), value);
+            versioned.push((
+                versioned_key(&key, height),
+                column,
+                WriteOperation::Insert(Arc::new(encoded)),
+            ));
+        }
+        self.data_source.batch_write(&mut versioned.into_iter())
+    }
+}
+
+/// The read-only view of the [`VersionedStorage`] as of the height.
+/// Versioned columns return the newest versions at or below the height,
+/// while other columns return the latest values.
+#[derive(Debug, Clone)]
+pub struct HistoricalView<Description = OnChain>
+where
+    Description: DatabaseDescription,
+{
+    data_source: DataSource<Description>,
+    versioned_columns: Arc<HashSet<u32>>,
+    height: u32,
+}
+
+impl<Description> HistoricalView<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Returns the height of the view.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn is_versioned(&self, column: Description::Column) -> bool {
+        self.versioned_columns.contains(&column.id())
+    }
+}
+
+impl<Description> ReadView for HistoricalView<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        if !self.is_versioned(column) {
+            return self.data_source.get(key, column)
+        }
+        get_at(&self.data_source, key, column, self.height)
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        Ok(self.get(key, column)?.is_some())
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        keys.iter().map(|key| self.get(key, column)).collect()
+    }
+
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        if !self.is_versioned(column) {
+            return self.data_source.iter_all(column, prefix, start, direction)
+        }
+        iter_at(
+            &self.data_source,
+            column,
+            prefix,
+            start,
+            direction,
+            self.height,
+        )
+    }
+}
+
+fn versioned_key(key: &[u8], height: u32) -> Vec<u8> {
+    let mut versioned = Vec::with_capacity(key.len().saturating_add(HEIGHT_SIZE));
+    versioned.extend_from_slice(key);
+    versioned.extend_from_slice(BlockHeightKey::from(height).as_ref());
+    versioned
+}
+
+fn split_versioned_key(versioned: &[u8]) -> StorageResult<(&[u8], u32)> {
+    let key_size = versioned.len().checked_sub(HEIGHT_SIZE).ok_or_else(|| {
+        StorageError::Other(anyhow::anyhow!(
+            "The versioned key {versioned:?} doesn't have the height suffix"
+        ))
+    })?;
+    let (key, height) = versioned.split_at(key_size);
+    let height =
+        BlockHeightKey::try_from(height).expect("The suffix has the size of the height");
+    Ok((key, height.into()))
+}
+
+fn encode_value(value: Option<&[u8]>) -> Vec<u8> {
+    match value {
+        Some(value) => {
+            let mut encoded = Vec::with_capacity(value.len().saturating_add(1));
+            encoded.push(INSERTED);
+            encoded.extend_from_slice(value);
+            encoded
+        }
+        None => vec![REMOVED],
+    }
+}
+
+fn decode_value(value: &[u8]) -> StorageResult<Option<Value>> {
+    match value.split_first() {
+        Some((&INSERTED, value)) => Ok(Some(Arc::new(value.to_vec()))),
+        Some((&REMOVED, [])) => Ok(None),
+        _ => Err(StorageError::Other(anyhow::anyhow!(
+            "The versioned value {value:?} has invalid encoding"
+        ))),
+    }
+}
+
+/// Returns the newest version of the value at or below the `height`.
+fn get_at<Description>(
+    data_source: &DataSource<Description>,
+    key: &[u8],
+    column: Description::Column,
+    height: u32,
+) -> StorageResult<Option<Value>>
+where
+    Description: DatabaseDescription,
+{
+    // The reverse seek from the `height` finds the newest version at or below it.
+    let newest = data_source
+        .iter_all(
+            column,
+            Some(key),
+            Some(&versioned_key(key, height)),
+            IterDirection::Reverse,
+        )
+        .next()
+        .transpose()?;
+    match newest {
+        Some((_, value)) => decode_value(&value),
+        None => Ok(None),
+    }
+}
+
+/// Iterates over the newest versions of values at or below the `height`, skipping removed values.
+fn iter_at<'a, Description>(
+    data_source: &'a DataSource<Description>,
+    column: Description::Column,
+    prefix: Option<&[u8]>,
+    start: Option<&[u8]>,
+    direction: IterDirection,
+    height: u32,
+) -> BoxedIter<'a, KVItem>
+where
+    Description: DatabaseDescription,
+{
+    // Versions of the `start` key begin after it in the forward direction
+    // and end at its maximum version in the reverse direction.
+    let start = start.map(|start| match direction {
+        IterDirection::Forward => start.to_vec(),
+        IterDirection::Reverse => versioned_key(start, u32::MAX),
+    });
+
+    data_source
+        .iter_all(column, prefix, start.as_deref(), direction)
+        .peekable()
+        .batching(move |iter| loop {
+            let (versioned, value) = match iter.next()? {
+                Ok(item) => item,
+                Err(err) => return Some(Err(err)),
+            };
+            let (key, version) = match split_versioned_key(&versioned) {
+                Ok((key, version)) => (key.to_vec(), version),
+                Err(err) => return Some(Err(err)),
+            };
+
+            // Versions of the same key are adjacent, so we pick the newest one at or below the `height`.
+            let mut newest = (version <= height).then_some((version, value));
+            while let Some(Ok((versioned, value))) = iter.next_if(|item| {
+                matches!(item, Ok((next, _)) if next.len() == versioned.len() && next.starts_with(&key))
+            }) {
+                let (_, version) = match split_versioned_key(&versioned) {
+                    Ok(split) => split,
+                    Err(err) => return Some(Err(err)),
+                };
+                let is_newer = newest
+                    .as_ref()
+                    .map_or(true, |(newest, _)| version > *newest);
+                if version <= height && is_newer {
+                    newest = Some((version, value));
+                }
+            }
+
+            match newest.map(|(_, value)| decode_value(&value)) {
+                Some(Ok(Some(value))) => return Some(Ok((key, value))),
+                Some(Err(err)) => return Some(Err(err)),
+                // The key is removed or doesn't exist at the `height`.
+                Some(Ok(None)) | None => continue,
+            }
+        })
+        .into_boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::in_memory::memory_store::MemoryStore;
+    use fuel_core_storage::column::Column;
+
+    const VERSIONED: Column = Column::ContractsState;
+
+    fn value(v: u8) -> Value {
+        Arc::new(vec![v])
+    }
+
+    /// Height 1: a = 1, b = 1
+    /// Height 2: a = 2, b removed, c = 2
+    /// Height 3: a removed, b = 3
+    /// Height 4: d = 4, c = 4 (via batch)
+    fn versioned_storage() -> VersionedStorage {
+        let storage =
+            VersionedStorage::new(Arc::new(MemoryStore::default()), [VERSIONED], 1);
+        storage.put(b"a", VERSIONED, value(1)).unwrap();
+        storage.put(b"b", VERSIONED, value(1)).unwrap();
+        storage.set_height(2);
+        storage.put(b"a", VERSIONED, value(2)).unwrap();
+        storage.delete(b"b", VERSIONED).unwrap();
+        storage.put(b"c", VERSIONED, value(2)).unwrap();
+        storage.set_height(3);
+        storage.delete(b"a", VERSIONED).unwrap();
+        storage.put(b"b", VERSIONED, value(3)).unwrap();
+        storage.set_height(4);
+        storage
+            .batch_write(
+                &mut [
+                    (b"d".to_vec(), VERSIONED, WriteOperation::Insert(value(4))),
+                    (b"c".to_vec(), VERSIONED, WriteOperation::Insert(value(4))),
+                ]
+                .into_iter(),
+            )
+            .unwrap();
+        storage
+    }
+
+    fn items(
+        view: &HistoricalView,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> Vec<(u8, u8)> {
+        view.iter_all(VERSIONED, None, start, direction)
+            .map_ok(|(k, v)| (k[0], v[0]))
+            .try_collect()
+            .unwrap()
+    }
+
+    #[test]
+    fn get_returns_newest_version_at_height() {
+        // setup
+        let storage = versioned_storage();
+
+        // verify
+        let at = |height: u32, key: &[u8]| {
+            storage
+                .at_height(height)
+                .get(key, VERSIONED)
+                .unwrap()
+                .map(|v| v[0])
+        };
+        assert_eq!(at(0, b"a"), None);
+        assert_eq!(at(1, b"a"), Some(1));
+        assert_eq!(at(2, b"a"), Some(2));
+        assert_eq!(at(3, b"a"), None);
+        assert_eq!(at(1, b"b"), Some(1));
+        assert_eq!(at(2, b"b"), None);
+        assert_eq!(at(3, b"b"), Some(3));
+        assert_eq!(at(3, b"c"), Some(2));
+        assert_eq!(at(4, b"c"), Some(4));
+        assert_eq!(at(u32::MAX, b"d"), Some(4));
+    }
+
+    #[test]
+    fn latest_reads_see_newest_versions() {
+        // setup
+        let storage = versioned_storage();
+
+        // verify
+        assert_eq!(storage.get(b"a", VERSIONED).unwrap(), None);
+        assert_eq!(storage.get(b"b", VERSIONED).unwrap(), Some(value(3)));
+        assert!(storage.exists(b"c", VERSIONED).unwrap());
+        let latest: Vec<_> = storage
+            .iter_all(VERSIONED, None, None, IterDirection::Forward)
+            .map_ok(|(k, v)| (k[0], v[0]))
+            .try_collect()
+            .unwrap();
+        assert_eq!(latest, vec![(b'b', 3), (b'c', 4), (b'd', 4)]);
+    }
+
+    #[test]
+    fn iter_all_deduplicates_versions_at_height() {
+        // setup
+        let storage = versioned_storage();
+
+        // verify
+        let expected = [
+            (0, vec![]),
+            (1, vec![(b'a', 1), (b'b', 1)]),
+            (2, vec![(b'a', 2), (b'c', 2)]),
+            (3, vec![(b'b', 3), (b'c', 2)]),
+            (4, vec![(b'b', 3), (b'c', 4), (b'd', 4)]),
+        ];
+        for (height, expected) in expected {
+            let view = storage.at_height(height);
+            assert_eq!(
+                items(&view, None, IterDirection::Forward),
+                expected,
+                "forward at {height}"
+            );
+            assert_eq!(
+                items(&view, None, IterDirection::Reverse),
+                expected.into_iter().rev().collect::<Vec<_>>(),
+                "reverse at {height}"
+            );
+        }
+    }
+
+    #[test]
+    fn iter_all_respects_start_at_height() {
+        // setup
+        let storage = versioned_storage();
+        let view = storage.at_height(2);
+
+        // verify
+        assert_eq!(
+            items(&view, Some(b"b"), IterDirection::Forward),
+            vec![(b'c', 2)]
+        );
+        assert_eq!(
+            items(&view, Some(b"c"), IterDirection::Reverse),
+            vec![(b'c', 2), (b'a', 2)]
+        );
+        assert_eq!(
+            items(&storage.at_height(3), Some(b"b"), IterDirection::Reverse),
+            vec![(b'b', 3)]
+        );
+    }
+
+    #[test]
+    fn not_versioned_columns_are_passed_through() {
+        // setup
+        let store = Arc::new(MemoryStore::<OnChain>::default());
+        let storage = VersionedStorage::new(store.clone(), [VERSIONED], 1);
+
+        // test
+        storage.put(b"a", Column::Metadata, value(1)).unwrap();
+        storage.set_height(2);
+        storage.put(b"a", Column::Metadata, value(2)).unwrap();
+
+        // verify
+        assert_eq!(store.get(b"a", Column::Metadata).unwrap(), Some(value(2)));
+        assert_eq!(
+            storage.at_height(1).get(b"a", Column::Metadata).unwrap(),
+            Some(value(2))
+        );
+    }
+}
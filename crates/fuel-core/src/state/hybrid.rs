@@ -0,0 +1,758 @@
+//! The hybrid store keeps the configured columns in memory and the rest of the columns
+//! in the disk store, like RocksDB. The routing is invisible for users of the store.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::{
+        column_iter::ColumnIter,
+        in_memory::{
+            memory_store::MemoryStore,
+            transaction::WriteEntry,
+        },
+        notifying::StorageEvent,
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        IterDirection,
+        TransactableStorage,
+        WriteDurability,
+        WritePressure,
+    },
+};
+use fuel_core_storage::{
+    iter::{
+        BoxedIter,
+        IterConfig,
+        IteratorableStore,
+        PageIter,
+    },
+    kv_store::{
+        merge_value,
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Result as StorageResult,
+};
+use std::{
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    hash::Hash,
+    path::Path,
+    sync::{
+        Arc,
+        Mutex,
+    },
+};
+use tokio::sync::broadcast;
+
+#[derive(Debug)]
+pub struct HybridStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    memory: MemoryStore<Description>,
+    disk: DataSourceInner<Description::Column>,
+    /// The flag for each column whether it is stored in memory.
+    in_memory: Vec<bool>,
+    /// Serializes writes, so the batch staged for the memory side remains valid
+    /// until it is applied, and snapshots see either none or all of the batch.
+    write_lock: Mutex<()>,
+}
+
+impl<Description> HybridStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Creates the store that keeps the `memory_columns` in memory and the rest in the `disk`.
+    pub fn new<D>(memory_columns: HashSet<Description::Column>, disk: D) -> Self
+    where
+        Description::Column: Hash + Eq,
+        D: Into<DataSource<Description>>,
+    {
+        let disk: DataSource<Description> = disk.into();
+        Self {
+            memory: MemoryStore::default(),
+            disk: disk.0,
+            in_memory: enum_iterator::all::<Description::Column>()
+                .map(|column| memory_columns.contains(&column))
+                .collect(),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// Opens the RocksDB at the `path` for the columns that are not the `memory_columns`.
+    #[cfg(feature = "rocksdb")]
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        memory_columns: HashSet<Description::Column>,
+        capacity: Option<usize>,
+    ) -> DatabaseResult<Self>
+    where
+        Description::Column: Hash + Eq,
+    {
+        let disk_columns = enum_iterator::all::<Description::Column>()
+            .filter(|column| !memory_columns.contains(column))
+            .collect();
+        let disk =
+            super::rocks_db::RocksDb::<Description>::open(path, disk_columns, capacity)?;
+        Ok(Self::new(memory_columns, Arc::new(disk)))
+    }
+
+    fn is_in_memory(&self, column: Description::Column) -> bool {
+        self.in_memory[column.as_usize()]
+    }
+
+    fn tier(
+        &self,
+        column: Description::Column,
+    ) -> &dyn TransactableStorage<Column = Description::Column> {
+        if self.is_in_memory(column) {
+            &self.memory
+        } else {
+            self.disk.as_ref()
+        }
+    }
+
+    /// Resolves merges of the memory side of the batch, so applying of the staged batch can't fail.
+    fn stage(
+        &self,
+        entries: Vec<WriteEntry<Description::Column>>,
+    ) -> StorageResult<Vec<WriteEntry<Description::Column>>> {
+        let mut staged = HashMap::<(usize, Vec<u8>), Option<Value>>::new();
+        entries
+            .into_iter()
+            .map(|(key, column, op)| {
+                let value = match op {
+                    WriteOperation::Insert(value) => Some(value),
+                    WriteOperation::Remove => None,
+                    WriteOperation::Merge(operand) => {
+                        let existing = match staged.get(&(column.as_usize(), key.clone()))
+                        {
+                            Some(value) => value.clone(),
+                            None => self.memory.get(&key, column)?,
+                        };
+                        Some(merge_value(
+                            column,
+                            existing.as_deref().map(Vec::as_slice),
+                            &operand,
+                        )?)
+                    }
+                };
+                staged.insert((column.as_usize(), key.clone()), value.clone());
+                let op = match value {
+                    Some(value) => WriteOperation::Insert(value),
+                    None => WriteOperation::Remove,
+                };
+                Ok((key, column, op))
+            })
+            .collect()
+    }
+}
+
+impl<Description> KeyValueStore for HybridStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.tier(column).put(key, column, value)
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.tier(column).replace(key, column, value)
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.tier(column).write(key, column, buf)
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.tier(column).take(key, column)
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.tier(column).delete(key, column)
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.tier(column).merge(key, column, operand)
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        self.tier(column).exists(key, column)
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        self.tier(column).size_of_value(key, column)
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.tier(column).get(key, column)
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        self.tier(column).get_with(key, column, f)
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        self.tier(column).multi_get(keys, column)
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.tier(column)
+            .compare_and_swap(key, column, expected, new)
+    }
+
+    fn get_or_insert_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        init: &mut dyn FnMut() -> Vec<u8>,
+    ) -> StorageResult<Value> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.tier(column).get_or_insert_with(key, column, init)
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.tier(column).update(key, column, f)
+    }
+
+    fn read(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &mut [u8],
+    ) -> StorageResult<Option<usize>> {
+        self.tier(column).read(key, column, buf)
+    }
+}
+
+impl<Description> IteratorableStore for HybridStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.tier(column).iter_all(column, prefix, start, direction)
+    }
+
+    fn iter(&self, column: Self::Column, config: IterConfig) -> PageIter {
+        self.tier(column).iter(column, config)
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.tier(column)
+            .iter_range(column, prefix, start, end, direction)
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.tier(column)
+            .iter_keys(column, prefix, start, direction)
+    }
+
+    fn has_any(&self, column: Self::Column, prefix: &[u8]) -> StorageResult<bool> {
+        self.tier(column).has_any(column, prefix)
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.tier(column).delete_all(column, prefix)
+    }
+}
+
+impl<Description> BatchOperations for HybridStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Writes the batch spanning both tiers atomically:
+    /// 1. The memory side is staged: merges are resolved, so only inserts and removals remain.
+    ///    If staging fails, nothing is written.
+    /// 2. The disk side is committed as one batch. If it fails, nothing is written.
+    /// 3. The staged memory side is applied. It can't fail, because the memory store
+    ///    has no capacity limit and all merges are already resolved.
+    ///
+    /// If the process crashes between the steps, the disk side is persisted alone, but the memory
+    /// side doesn't survive the restart in any case, so it is the same as a crash after the batch.
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for HybridStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_column(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> ColumnIter<'_> {
+        self.tier(column)
+            .iter_column(column, prefix, start, direction)
+    }
+
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        let (memory, disk): (Vec<_>, Vec<_>) =
+            entries.partition(|(_, column, _)| self.is_in_memory(*column));
+
+        let memory = self.stage(memory)?;
+        if !disk.is_empty() {
+            self.disk
+                .batch_write_with(&mut disk.into_iter(), durability)?;
+        }
+        self.memory.batch_write(&mut memory.into_iter())
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.disk.flush()?;
+        self.memory.flush()
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        self.tier(column).column_stats(column)
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        self.memory.write_pressure().max(self.disk.write_pressure())
+    }
+
+    fn checkpoint(&self, _: &Path) -> DatabaseResult<()> {
+        // The checkpoint of the disk side alone would lose the memory columns.
+        Err(DatabaseError::Unsupported("checkpoint"))
+    }
+
+    fn backup_to(&self, _: &Path) -> DatabaseResult<()> {
+        Err(DatabaseError::Unsupported("backup"))
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        match column {
+            Some(column) => self.tier(column).compact(Some(column), range),
+            None => self.disk.compact(None, range),
+        }
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.tier(column).clear_column(column)
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        Ok(Arc::new(Self {
+            memory: self.memory.consistent_copy(),
+            disk: self.disk.snapshot()?,
+            in_memory: self.in_memory.clone(),
+            write_lock: Mutex::new(()),
+        }))
+    }
+
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        self.tier(column).subscribe(column, prefix)
+    }
+
+    // Memory columns are written into the memory store directly, so the change log
+    // of the disk store covers only disk columns.
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.disk.replay_change_log(from_seq, into)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.disk.truncate_change_log(seq)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        // Writes of memory columns don't pass the guard of the disk store.
+        if columns.iter().any(|column| self.is_in_memory(*column)) {
+            return Err(DatabaseError::Unsupported("column freezing"))
+        }
+        self.disk.freeze_columns(columns)
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        self.disk.unfreeze_columns(columns)
+    }
+
+    fn begin_shutdown(&self) {
+        self.disk.begin_shutdown();
+        self.memory.begin_shutdown()
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.disk.finish_shutdown()?;
+        self.memory.finish_shutdown()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{
+            database_description::{
+                on_chain::OnChain,
+                test::*,
+            },
+            Database,
+        },
+        state::in_memory::transaction::MemoryTransactionView,
+    };
+    use fuel_core_storage::{
+        column::Column,
+        tables::{
+            Coins,
+            ContractsRawCode,
+        },
+        StorageAsMut,
+        StorageAsRef,
+    };
+    use fuel_core_types::{
+        entities::coins::coin::CompressedCoin,
+        fuel_tx::UtxoId,
+        fuel_types::ContractId,
+        fuel_vm::Contract,
+    };
+
+    fn hybrid<D>(
+        memory_columns: &[D::Column],
+        disk: MemoryStore<D>,
+    ) -> (HybridStore<D>, Arc<MemoryStore<D>>)
+    where
+        D: DatabaseDescription,
+        D::Column: Hash + Eq,
+    {
+        let disk = Arc::new(disk);
+        let store =
+            HybridStore::new(memory_columns.iter().copied().collect(), disk.clone());
+        (store, disk)
+    }
+
+    #[test]
+    fn columns_are_routed_to_their_tiers() {
+        // Given
+        let (store, disk) =
+            hybrid::<OnChain>(&[Column::Metadata], MemoryStore::default());
+
+        // When
+        store
+            .put(&[1], Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+        store.put(&[2], Column::Coins, Arc::new(vec![2])).unwrap();
+
+        // Then
+        assert_eq!(
+            store.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+        assert_eq!(
+            store.get(&[2], Column::Coins).unwrap(),
+            Some(Arc::new(vec![2]))
+        );
+        assert!(!disk.exists(&[1], Column::Metadata).unwrap());
+        assert!(disk.exists(&[2], Column::Coins).unwrap());
+    }
+
+    #[test]
+    fn mixed_batches_behave_like_a_single_store() {
+        for memory_columns in [
+            vec![],
+            vec![MergeColumn::Counters],
+            vec![MergeColumn::Logs, MergeColumn::Metadata],
+            enum_iterator::all::<MergeColumn>().collect(),
+        ] {
+            // Given
+            let (by_methods, _) =
+                hybrid::<MergeDatabase>(&memory_columns, MemoryStore::default());
+            let (by_batch, _) =
+                hybrid::<MergeDatabase>(&memory_columns, MemoryStore::default());
+            let (by_transaction, _) =
+                hybrid::<MergeDatabase>(&memory_columns, MemoryStore::default());
+            let by_transaction = Arc::new(by_transaction);
+
+            // When
+            apply_merge_operations(&by_methods, merge_base());
+            apply_merge_operations(&by_methods, merge_operations());
+            by_batch.batch_write(&mut merge_base().into_iter()).unwrap();
+            by_batch
+                .batch_write(&mut merge_operations().into_iter())
+                .unwrap();
+            by_transaction
+                .batch_write(&mut merge_base().into_iter())
+                .unwrap();
+            let view = MemoryTransactionView::<MergeDatabase>::new(DataSource::new(
+                by_transaction.clone(),
+            ));
+            apply_merge_operations(&view, merge_operations());
+            view.commit().unwrap();
+
+            // Then
+            assert_eq!(merge_entries(&by_methods), merged_state());
+            assert_eq!(merge_entries(&by_batch), merged_state());
+            assert_eq!(merge_entries(by_transaction.as_ref()), merged_state());
+        }
+    }
+
+    #[test]
+    fn failed_disk_batch_leaves_the_memory_side_unapplied() {
+        // Given
+        let (store, _) =
+            hybrid::<OnChain>(&[Column::Metadata], MemoryStore::with_capacity_limit(10));
+
+        // When
+        let result = store.batch_write(
+            &mut [
+                (
+                    vec![1],
+                    Column::Metadata,
+                    WriteOperation::Insert(Arc::new(vec![1])),
+                ),
+                (
+                    vec![2],
+                    Column::Coins,
+                    WriteOperation::Insert(Arc::new(vec![0; 10])),
+                ),
+            ]
+            .into_iter(),
+        );
+
+        // Then
+        assert!(result.is_err());
+        assert!(!store.exists(&[1], Column::Metadata).unwrap());
+        assert!(!store.exists(&[2], Column::Coins).unwrap());
+    }
+
+    #[test]
+    fn failed_memory_merge_leaves_the_disk_side_unapplied() {
+        // Given
+        let (store, disk) =
+            hybrid::<MergeDatabase>(&[MergeColumn::Metadata], MemoryStore::default());
+
+        // When
+        let result = store.batch_write(
+            &mut [
+                (
+                    vec![1],
+                    MergeColumn::Logs,
+                    WriteOperation::Merge(Arc::new(vec![1])),
+                ),
+                (
+                    vec![1],
+                    MergeColumn::Metadata,
+                    WriteOperation::Merge(Arc::new(vec![1])),
+                ),
+            ]
+            .into_iter(),
+        );
+
+        // Then
+        assert!(result.is_err());
+        assert!(merge_entries(&store).is_empty());
+        assert!(merge_entries(disk.as_ref()).is_empty());
+    }
+
+    #[test]
+    fn snapshot_is_consistent_across_tiers() {
+        // Given
+        let (store, _) = hybrid::<OnChain>(&[Column::Metadata], MemoryStore::default());
+        let batch = |value: u8| {
+            [Column::Metadata, Column::Coins].map(|column| {
+                (
+                    vec![0],
+                    column,
+                    WriteOperation::Insert(Arc::new(vec![value])),
+                )
+            })
+        };
+        store.batch_write(&mut batch(1).into_iter()).unwrap();
+
+        // When
+        let snapshot = store.snapshot().unwrap();
+        store.batch_write(&mut batch(2).into_iter()).unwrap();
+
+        // Then
+        for column in [Column::Metadata, Column::Coins] {
+            assert_eq!(snapshot.get(&[0], column).unwrap(), Some(Arc::new(vec![1])));
+            assert_eq!(store.get(&[0], column).unwrap(), Some(Arc::new(vec![2])));
+        }
+    }
+
+    #[test]
+    fn tables_work_over_both_tiers() {
+        // Given
+        let (store, disk) =
+            hybrid::<OnChain>(&[Column::ContractsRawCode], MemoryStore::default());
+        let mut database = Database::<OnChain>::new(DataSource::new(Arc::new(store)));
+        let utxo_ids = (0..10u8)
+            .map(|i| UtxoId::new([i; 32].into(), i))
+            .collect::<Vec<_>>();
+        let contract_id = ContractId::from([1; 32]);
+        let contract = Contract::from(vec![1, 2, 3]);
+
+        // When
+        for utxo_id in utxo_ids.iter() {
+            database
+                .storage_as_mut::<Coins>()
+                .insert(utxo_id, &CompressedCoin::default())
+                .unwrap();
+        }
+        database
+            .storage_as_mut::<ContractsRawCode>()
+            .insert(&contract_id, contract.as_ref())
+            .unwrap();
+        database
+            .storage_as_mut::<Coins>()
+            .remove(&utxo_ids[0])
+            .unwrap();
+
+        // Then
+        let coins = database
+            .iter_all::<Coins>(None)
+            .map(|entry| entry.unwrap().0)
+            .collect::<Vec<_>>();
+        assert_eq!(coins, utxo_ids[1..].to_vec());
+        assert_eq!(
+            database
+                .storage::<ContractsRawCode>()
+                .get(&contract_id)
+                .unwrap()
+                .unwrap()
+                .into_owned(),
+            contract
+        );
+        assert!(disk
+            .iter_all(Column::ContractsRawCode, None, None, IterDirection::Forward)
+            .next()
+            .is_none());
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn hybrid_over_rocksdb_behaves_like_a_single_store() {
+        // Given
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let store = HybridStore::<MergeDatabase>::open(
+            tmp_dir.path(),
+            [MergeColumn::Counters].into_iter().collect(),
+            None,
+        )
+        .unwrap();
+
+        // When
+        store.batch_write(&mut merge_base().into_iter()).unwrap();
+        apply_merge_operations(&store, merge_operations());
+
+        // Then
+        assert_eq!(merge_entries(&store), merged_state());
+    }
+}
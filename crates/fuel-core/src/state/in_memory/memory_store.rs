@@ -1,60 +1,118 @@
 use crate::{
     database::{
         database_description::{
+            column_slots,
+            debug_assert_key_size,
             on_chain::OnChain,
             DatabaseDescription,
         },
+        Error as DatabaseError,
         Result as DatabaseResult,
     },
     state::{
+        column_iter::{
+            ColumnIter,
+            RawColumnIter,
+        },
         BatchOperations,
+        ColumnStats,
+        DataSourceInner,
         IterDirection,
+        StallReason,
         TransactableStorage,
+        WritePressure,
     },
 };
 use fuel_core_storage::{
     iter::{
+        is_start_within_prefix,
         BoxedIter,
         IntoBoxedIter,
+        IterConfig,
         IteratorableStore,
+        PageIter,
     },
     kv_store::{
+        merge_value,
         KVItem,
+        KeyItem,
         KeyValueStore,
         StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
         Value,
+        WriteOperation,
     },
     Result as StorageResult,
 };
 use std::{
-    collections::BTreeMap,
+    collections::{
+        BTreeMap,
+        HashMap,
+        VecDeque,
+    },
     fmt::Debug,
+    ops::Bound,
+    path::Path,
     sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
         Arc,
-        Mutex,
+        RwLock,
     },
 };
 
+/// The entries of the column of the [`MemoryStore`].
+type ColumnMap = BTreeMap<Vec<u8>, Value>;
+
 #[derive(Debug)]
 pub struct MemoryStore<Description = OnChain>
 where
     Description: DatabaseDescription,
 {
-    // TODO: Remove `Mutex`.
-    inner: Vec<Mutex<BTreeMap<Vec<u8>, Value>>>,
+    /// Each column is behind its own lock, so readers don't block each other.
+    /// Iterators share the version of the column at their creation, and the write
+    /// copies the column only if some iterator or snapshot still holds that version.
+    inner: Vec<RwLock<Arc<ColumnMap>>>,
+    /// The sum of lengths of keys and values of each column.
+    /// The usage of the column is updated under its write lock.
+    usage: Vec<AtomicUsize>,
+    /// The sum of the usage of all columns.
+    total_usage: AtomicUsize,
+    /// The limit of the `total_usage` in bytes, if any.
+    capacity: Option<usize>,
     _marker: core::marker::PhantomData<Description>,
 }
 
+/// The memory occupied by keys and values of the [`MemoryStore`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryUsage<Column> {
+    /// The total number of bytes.
+    pub total: usize,
+    /// The number of bytes occupied by each column.
+    pub columns: Vec<(Column, usize)>,
+}
+
+fn entry_size(key: &[u8], value: Option<&Value>) -> usize {
+    value.map_or(0, |value| key.len().saturating_add(value.len()))
+}
+
 impl<Description> Default for MemoryStore<Description>
 where
     Description: DatabaseDescription,
 {
     fn default() -> Self {
-        use strum::EnumCount;
         Self {
-            inner: (0..Description::Column::COUNT)
-                .map(|_| Mutex::new(BTreeMap::new()))
+            inner: (0..column_slots::<Description>())
+                .map(|_| RwLock::new(Arc::default()))
                 .collect(),
+            usage: (0..column_slots::<Description>())
+                .map(|_| AtomicUsize::new(0))
+                .collect(),
+            total_usage: AtomicUsize::new(0),
+            capacity: None,
             _marker: Default::default(),
         }
     }
@@ -64,69 +122,456 @@ impl<Description> MemoryStore<Description>
 where
     Description: DatabaseDescription,
 {
+    /// Returns the copy of the store. Locks of all columns are held together,
+    /// so the copy is consistent across columns. The copy shares versions of columns
+    /// with the store, so each column is copied only on the first write into it.
+    pub(crate) fn consistent_copy(&self) -> Self {
+        let locks: Vec<_> = self
+            .inner
+            .iter()
+            .map(|column| column.read().expect("poisoned"))
+            .collect();
+        Self {
+            inner: locks
+                .iter()
+                .map(|map| RwLock::new(Arc::clone(map)))
+                .collect(),
+            usage: self
+                .usage
+                .iter()
+                .map(|usage| AtomicUsize::new(usage.load(Ordering::Relaxed)))
+                .collect(),
+            total_usage: AtomicUsize::new(self.total_usage.load(Ordering::Relaxed)),
+            capacity: self.capacity,
+            _marker: Default::default(),
+        }
+    }
+
+    /// Creates the store that rejects writes growing the sum of lengths
+    /// of its keys and values beyond the `capacity` in bytes.
+    pub fn with_capacity_limit(capacity: usize) -> Self {
+        Self {
+            capacity: Some(capacity),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the sum of lengths of keys and values of the store.
+    pub fn memory_usage(&self) -> MemoryUsage<Description::Column> {
+        MemoryUsage {
+            total: self.total_usage.load(Ordering::Relaxed),
+            columns: enum_iterator::all::<Description::Column>()
+                .map(|column| {
+                    (
+                        column,
+                        self.usage[column.as_usize()].load(Ordering::Relaxed),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Accounts the change of entries from the `old` to the `new` size in bytes
+    /// for each `(column, old, new)`. Fails without changes if the growth of the store
+    /// exceeds the capacity. The caller must hold write locks of the columns.
+    fn resize(&self, sizes: &[(usize, usize, usize)]) -> DatabaseResult<()> {
+        let (old, new) = sizes.iter().fold((0usize, 0usize), |(old, new), size| {
+            (old.saturating_add(size.1), new.saturating_add(size.2))
+        });
+
+        if new > old {
+            let required = new.saturating_sub(old);
+            self.total_usage
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |usage| {
+                    usage.checked_add(required).filter(|grown| {
+                        self.capacity.map_or(true, |capacity| *grown <= capacity)
+                    })
+                })
+                .map_err(|usage| DatabaseError::OutOfCapacity {
+                    capacity: self.capacity.unwrap_or(usize::MAX),
+                    usage,
+                    required,
+                })?;
+        } else {
+            self.total_usage
+                .fetch_sub(old.saturating_sub(new), Ordering::Relaxed);
+        }
+
+        for (column, old, new) in sizes {
+            let usage = &self.usage[*column];
+            if new > old {
+                usage.fetch_add(new.saturating_sub(*old), Ordering::Relaxed);
+            } else {
+                usage.fetch_sub(old.saturating_sub(*new), Ordering::Relaxed);
+            }
+        }
+        Ok(())
+    }
+
+    fn insert_entry(
+        &self,
+        map: &mut ColumnMap,
+        column: Description::Column,
+        key: &[u8],
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        debug_assert_key_size::<Description>(&column, key);
+        let old = entry_size(key, map.get(key));
+        self.resize(&[(column.as_usize(), old, entry_size(key, Some(&value)))])?;
+        Ok(map.insert(key.to_vec(), value))
+    }
+
+    fn remove_entry(
+        &self,
+        map: &mut ColumnMap,
+        column: usize,
+        key: &[u8],
+    ) -> StorageResult<Option<Value>> {
+        let removed = map.remove(key);
+        self.resize(&[(column, entry_size(key, removed.as_ref()), 0)])?;
+        Ok(removed)
+    }
+
+    /// Iterates lazily over the range of the `column`. The iterator holds the version
+    /// of the column at its creation, so writes that happen during the iteration are
+    /// never visible to it. Entries of the version are read in chunks, and the next
+    /// chunk is sought after the last read key.
+    ///
+    /// While the iterator is alive, the first write into the column copies the map
+    /// of the column, and the writes share the copy until the next iterator is created.
+    /// The copy clones keys, but values are shared.
     pub fn iter_all(
         &self,
         column: Description::Column,
         prefix: Option<&[u8]>,
         start: Option<&[u8]>,
         direction: IterDirection,
-    ) -> impl Iterator<Item = KVItem> {
-        let lock = self.inner[column.as_usize()].lock().expect("poisoned");
+    ) -> impl Iterator<Item = KVItem> + '_ {
+        self.iter(
+            column,
+            IterConfig {
+                prefix,
+                start: included(start),
+                direction,
+                limit: None,
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn iter_range(
+        &self,
+        column: Description::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> impl Iterator<Item = KVItem> + '_ {
+        self.iter_with(column, prefix, start, end, direction, clone_entry)
+    }
 
-        fn clone<K: Clone, V: Clone>(kv: (&K, &V)) -> (K, V) {
-            (kv.0.clone(), kv.1.clone())
+    /// Iterates in the same way as [`Self::iter_all`] with the `config`. The limit
+    /// of the `config` bounds the reads, so entries beyond it are never cloned.
+    /// The excluded start is the excluded bound of the range, so it is never read.
+    pub fn iter(
+        &self,
+        column: Description::Column,
+        config: IterConfig,
+    ) -> impl Iterator<Item = KVItem> + '_ {
+        let range = self.range_cursor(
+            column,
+            config.prefix,
+            config.start,
+            None,
+            config.direction,
+        );
+        Cursor {
+            range: RangeCursor {
+                remaining: config.limit,
+                ..range
+            },
+            chunk: VecDeque::new(),
+            extract: clone_entry,
         }
+    }
 
-        let collection: Vec<_> = match (prefix, start) {
-            (None, None) => {
-                if direction == IterDirection::Forward {
-                    lock.iter().map(clone).collect()
-                } else {
-                    lock.iter().rev().map(clone).collect()
-                }
-            }
-            (Some(prefix), None) => {
-                if direction == IterDirection::Forward {
-                    lock.range(prefix.to_vec()..)
-                        .take_while(|(key, _)| key.starts_with(prefix))
-                        .map(clone)
-                        .collect()
-                } else {
-                    let mut vec: Vec<_> = lock
-                        .range(prefix.to_vec()..)
-                        .into_boxed()
-                        .take_while(|(key, _)| key.starts_with(prefix))
-                        .map(clone)
-                        .collect();
-
-                    vec.reverse();
-                    vec
-                }
-            }
-            (None, Some(start)) => {
-                if direction == IterDirection::Forward {
-                    lock.range(start.to_vec()..).map(clone).collect()
-                } else {
-                    lock.range(..=start.to_vec()).rev().map(clone).collect()
-                }
+    pub fn iter_keys(
+        &self,
+        column: Description::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> impl Iterator<Item = KeyItem> + '_ {
+        self.iter_with(column, prefix, start, None, direction, |(key, _)| {
+            key.clone()
+        })
+    }
+
+    fn iter_with<'a, T, F>(
+        &'a self,
+        column: Description::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+        extract: F,
+    ) -> impl Iterator<Item = StorageResult<T>> + 'a
+    where
+        T: 'a,
+        F: Fn((&Vec<u8>, &Value)) -> T + 'a,
+    {
+        Cursor {
+            range: self.range_cursor(column, prefix, included(start), end, direction),
+            chunk: VecDeque::new(),
+            extract,
+        }
+    }
+
+    /// Returns the iterator over the `column` that lends its items, see [`ColumnIter`].
+    /// Keys of the chunk are copied into the buffer reused by the next chunks,
+    /// and values are shared with the store.
+    pub fn iter_column(
+        &self,
+        column: Description::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> MemoryColumnIter {
+        MemoryColumnIter {
+            range: self.range_cursor(column, prefix, included(start), None, direction),
+            keys: vec![],
+            chunk: VecDeque::new(),
+            current: None,
+        }
+    }
+
+    fn range_cursor(
+        &self,
+        column: Description::Column,
+        prefix: Option<&[u8]>,
+        start: Bound<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> RangeCursor {
+        // The `end` is exclusive in the direction of the iteration.
+        let (lower, upper) = match direction {
+            IterDirection::Forward => (
+                match start {
+                    Bound::Unbounded => prefix.map_or(Bound::Unbounded, owned_included),
+                    start => owned(start),
+                },
+                end.map_or(Bound::Unbounded, owned_excluded),
+            ),
+            IterDirection::Reverse => {
+                (end.map_or(Bound::Unbounded, owned_excluded), owned(start))
             }
-            (Some(prefix), Some(start)) => {
-                if direction == IterDirection::Forward {
-                    lock.range(start.to_vec()..)
-                        .take_while(|(key, _)| key.starts_with(prefix))
-                        .map(clone)
-                        .collect()
-                } else {
-                    lock.range(..=start.to_vec())
-                        .rev()
-                        .take_while(|(key, _)| key.starts_with(prefix))
-                        .map(clone)
-                        .collect()
+        };
+        let start_key = match start {
+            Bound::Included(start) | Bound::Excluded(start) => Some(start),
+            Bound::Unbounded => None,
+        };
+
+        RangeCursor {
+            map: self.inner[column.as_usize()]
+                .read()
+                .expect("poisoned")
+                .clone(),
+            prefix: prefix.map(<[u8]>::to_vec),
+            lower,
+            upper,
+            direction,
+            // Without the `start`, the reverse iteration begins after the prefix.
+            skip_after_prefix: direction == IterDirection::Reverse
+                && start == Bound::Unbounded,
+            chunk_size: 1,
+            remaining: None,
+            exhausted: !is_start_within_prefix(prefix, start_key),
+        }
+    }
+}
+
+/// The maximum number of entries read at once by the [`RangeCursor`].
+const MAX_CHUNK_SIZE: usize = 1024;
+
+/// The position of the lazy iteration over the version of the column of the [`MemoryStore`].
+/// The size of the chunk doubles after each read up to the [`MAX_CHUNK_SIZE`],
+/// so short iterations clone only a few entries beyond the yielded ones.
+struct RangeCursor {
+    map: Arc<ColumnMap>,
+    prefix: Option<Vec<u8>>,
+    lower: Bound<Vec<u8>>,
+    upper: Bound<Vec<u8>>,
+    direction: IterDirection,
+    skip_after_prefix: bool,
+    chunk_size: usize,
+    /// The number of entries left to read, if the iteration is limited.
+    remaining: Option<usize>,
+    exhausted: bool,
+}
+
+impl RangeCursor {
+    /// Visits the entries of the next chunk.
+    fn read_chunk(&mut self, mut visit: impl FnMut((&Vec<u8>, &Value))) {
+        if self.exhausted {
+            return
+        }
+        if range_is_empty(&self.lower, &self.upper) {
+            self.exhausted = true;
+            return
+        }
+
+        let range = self
+            .map
+            .range::<[u8], _>((as_slice(&self.lower), as_slice(&self.upper)));
+        let entries: Box<dyn Iterator<Item = (&Vec<u8>, &Value)>> = match self.direction {
+            IterDirection::Forward => Box::new(range),
+            IterDirection::Reverse => Box::new(range.rev()),
+        };
+
+        let prefix = self.prefix.as_deref();
+        let has_prefix =
+            |key: &[u8]| prefix.map_or(true, |prefix| key.starts_with(prefix));
+        let skip = core::mem::take(&mut self.skip_after_prefix);
+        let entries = entries
+            .skip_while(|(key, _)| skip && !has_prefix(key))
+            .take_while(|(key, _)| has_prefix(key));
+        let chunk_size = self
+            .remaining
+            .map_or(self.chunk_size, |remaining| remaining.min(self.chunk_size));
+
+        let mut last = None;
+        let mut read = 0usize;
+        for entry in entries.take(chunk_size) {
+            visit(entry);
+            last = Some(entry.0);
+            read = read.saturating_add(1);
+        }
+
+        self.remaining = self
+            .remaining
+            .map(|remaining| remaining.saturating_sub(read));
+        match last {
+            Some(last) if read == chunk_size && self.remaining != Some(0) => {
+                let last = Bound::Excluded(last.clone());
+                match self.direction {
+                    IterDirection::Forward => self.lower = last,
+                    IterDirection::Reverse => self.upper = last,
                 }
+                self.chunk_size = self.chunk_size.saturating_mul(2).min(MAX_CHUNK_SIZE);
             }
-        };
+            _ => self.exhausted = true,
+        }
+    }
+}
+
+/// The lazy iterator over the column of the [`MemoryStore`].
+struct Cursor<T, F> {
+    range: RangeCursor,
+    chunk: VecDeque<T>,
+    extract: F,
+}
+
+impl<T, F> Iterator for Cursor<T, F>
+where
+    F: Fn((&Vec<u8>, &Value)) -> T,
+{
+    type Item = StorageResult<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.chunk.is_empty() {
+            let Self {
+                range,
+                chunk,
+                extract,
+            } = self;
+            range.read_chunk(|entry| chunk.push_back(extract(entry)));
+        }
+        self.chunk.pop_front().map(Ok)
+    }
+}
+
+/// The lending iterator over the column of the [`MemoryStore`], see [`MemoryStore::iter_column`].
+pub struct MemoryColumnIter {
+    range: RangeCursor,
+    /// The keys of the chunk one after another.
+    keys: Vec<u8>,
+    /// The end of the key in the `keys` and the value of each entry of the chunk.
+    chunk: VecDeque<(usize, Value)>,
+    /// The range of the key in the `keys` and the value of the current entry.
+    current: Option<(usize, usize, Value)>,
+}
+
+impl RawColumnIter for MemoryColumnIter {
+    fn advance(&mut self) -> StorageResult<()> {
+        let start = self.current.take().map_or(0, |(_, end, _)| end);
+        if self.chunk.is_empty() {
+            let Self {
+                range, keys, chunk, ..
+            } = self;
+            keys.clear();
+            range.read_chunk(|(key, value)| {
+                keys.extend_from_slice(key);
+                chunk.push_back((keys.len(), value.clone()));
+            });
+            self.current = self.chunk.pop_front().map(|(end, value)| (0, end, value));
+        } else {
+            self.current = self
+                .chunk
+                .pop_front()
+                .map(|(end, value)| (start, end, value));
+        }
+        Ok(())
+    }
+
+    fn current(&self) -> Option<(&[u8], &[u8])> {
+        self.current
+            .as_ref()
+            .map(|(start, end, value)| (&self.keys[*start..*end], value.as_slice()))
+    }
+}
+
+fn clone_entry<K: Clone, V: Clone>(kv: (&K, &V)) -> (K, V) {
+    (kv.0.clone(), kv.1.clone())
+}
+
+fn included(key: Option<&[u8]>) -> Bound<&[u8]> {
+    key.map_or(Bound::Unbounded, Bound::Included)
+}
+
+fn owned_included(key: &[u8]) -> Bound<Vec<u8>> {
+    Bound::Included(key.to_vec())
+}
+
+fn owned_excluded(key: &[u8]) -> Bound<Vec<u8>> {
+    Bound::Excluded(key.to_vec())
+}
+
+fn owned(bound: Bound<&[u8]>) -> Bound<Vec<u8>> {
+    match bound {
+        Bound::Included(key) => owned_included(key),
+        Bound::Excluded(key) => owned_excluded(key),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+fn as_slice(bound: &Bound<Vec<u8>>) -> Bound<&[u8]> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.as_slice()),
+        Bound::Excluded(key) => Bound::Excluded(key.as_slice()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
 
-        collection.into_iter().map(Ok)
+/// `BTreeMap::range` panics if the lower bound is greater than the upper,
+/// or if they are equal and both excluded.
+fn range_is_empty(lower: &Bound<Vec<u8>>, upper: &Bound<Vec<u8>>) -> bool {
+    match (lower, upper) {
+        (Bound::Included(lower), Bound::Included(upper)) => lower > upper,
+        (
+            Bound::Included(lower) | Bound::Excluded(lower),
+            Bound::Included(upper) | Bound::Excluded(upper),
+        ) => lower >= upper,
+        _ => false,
     }
 }
 
@@ -136,16 +581,19 @@ where
 {
     type Column = Description::Column;
 
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        self.replace(key, column, value).map(|_| ())
+    }
+
     fn replace(
         &self,
         key: &[u8],
         column: Self::Column,
         value: Value,
     ) -> StorageResult<Option<Value>> {
-        Ok(self.inner[column.as_usize()]
-            .lock()
-            .expect("poisoned")
-            .insert(key.to_vec(), value))
+        let index = column.as_usize();
+        let mut lock = self.inner[index].write().expect("poisoned");
+        self.insert_entry(Arc::make_mut(&mut lock), column, key, value)
     }
 
     fn write(
@@ -155,37 +603,142 @@ where
         buf: &[u8],
     ) -> StorageResult<usize> {
         let len = buf.len();
-        self.inner[column.as_usize()]
-            .lock()
-            .expect("poisoned")
-            .insert(key.to_vec(), Arc::new(buf.to_vec()));
+        self.put(key, column, Arc::new(buf.to_vec()))?;
         Ok(len)
     }
 
     fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
-        Ok(self.inner[column.as_usize()]
-            .lock()
-            .expect("poisoned")
-            .remove(&key.to_vec()))
+        let index = column.as_usize();
+        let mut lock = self.inner[index].write().expect("poisoned");
+        // The missing key doesn't copy the version held by iterators.
+        if !lock.contains_key(key) {
+            return Ok(None)
+        }
+        self.remove_entry(Arc::make_mut(&mut lock), index, key)
     }
 
     fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
         self.take(key, column).map(|_| ())
     }
 
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        let index = column.as_usize();
+        let mut lock = self.inner[index].write().expect("poisoned");
+        let existing = lock.get(key).map(|value| value.as_slice());
+        let value = merge_value(column, existing, &operand)?;
+        self.insert_entry(Arc::make_mut(&mut lock), column, key, value)?;
+        Ok(())
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        Ok(self.inner[column.as_usize()]
+            .read()
+            .expect("poisoned")
+            .contains_key(key))
+    }
+
     fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
         Ok(self.inner[column.as_usize()]
-            .lock()
+            .read()
             .expect("poisoned")
             .get(&key.to_vec())
             .cloned())
     }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        let index = column.as_usize();
+        let mut lock = self.inner[index].write().expect("poisoned");
+        let current = lock.get(key);
+        if current.map(|value| value.as_slice()) != expected {
+            return Ok(Err(current.cloned()))
+        }
+        self.insert_entry(Arc::make_mut(&mut lock), column, key, new)?;
+        Ok(Ok(()))
+    }
+
+    fn get_or_insert_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        init: &mut dyn FnMut() -> Vec<u8>,
+    ) -> StorageResult<Value> {
+        let index = column.as_usize();
+        let mut lock = self.inner[index].write().expect("poisoned");
+        if let Some(value) = lock.get(key) {
+            return Ok(value.clone())
+        }
+        let value = Value::new(init());
+        self.insert_entry(Arc::make_mut(&mut lock), column, key, value.clone())?;
+        Ok(value)
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        let index = column.as_usize();
+        let mut lock = self.inner[index].write().expect("poisoned");
+        let current = lock.get(key).map(|value| value.as_slice());
+        let new = f(current);
+        let outcome = UpdateOutcome::of(current, new.as_deref());
+        // The unchanged value doesn't copy the version held by iterators.
+        match new {
+            _ if !outcome.is_changed() => {}
+            Some(new) => {
+                self.insert_entry(Arc::make_mut(&mut lock), column, key, Arc::new(new))?;
+            }
+            None => {
+                self.remove_entry(Arc::make_mut(&mut lock), index, key)?;
+            }
+        }
+        Ok(outcome)
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        let lock = self.inner[column.as_usize()].read().expect("poisoned");
+        Ok(keys.iter().map(|key| lock.get(key).cloned()).collect())
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        Ok(self.inner[column.as_usize()]
+            .read()
+            .expect("poisoned")
+            .get(key)
+            .map(|value| f(value.as_ref()))
+            .is_some())
+    }
 }
 
 impl<Description> IteratorableStore for MemoryStore<Description>
 where
     Description: DatabaseDescription,
 {
+    fn iter(&self, column: Self::Column, config: IterConfig) -> PageIter {
+        PageIter::new(self.iter(column, config).into_boxed())
+    }
+
     fn iter_all(
         &self,
         column: Self::Column,
@@ -195,11 +748,141 @@ where
     ) -> BoxedIter<KVItem> {
         self.iter_all(column, prefix, start, direction).into_boxed()
     }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.iter_range(column, prefix, start, end, direction)
+            .into_boxed()
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.iter_keys(column, prefix, start, direction)
+            .into_boxed()
+    }
+
+    fn has_any(&self, column: Self::Column, prefix: &[u8]) -> StorageResult<bool> {
+        Ok(self.inner[column.as_usize()]
+            .read()
+            .expect("poisoned")
+            .range::<[u8], _>((Bound::Included(prefix), Bound::Unbounded))
+            .next()
+            .is_some_and(|(key, _)| key.starts_with(prefix)))
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        let index = column.as_usize();
+        let mut lock = self.inner[index].write().expect("poisoned");
+
+        match prefix.filter(|prefix| !prefix.is_empty()) {
+            None => {
+                let removed = lock.len();
+                let usage = self.usage[index].load(Ordering::Relaxed);
+                self.resize(&[(index, usage, 0)])?;
+                // Iterators keep the old version, so it isn't copied to be cleared.
+                *lock = Arc::default();
+                Ok(removed)
+            }
+            Some(prefix) => {
+                let keys: Vec<_> = lock
+                    .range::<[u8], _>((Bound::Included(prefix), Bound::Unbounded))
+                    .take_while(|(key, _)| key.starts_with(prefix))
+                    .map(|(key, _)| key.clone())
+                    .collect();
+                let map = Arc::make_mut(&mut lock);
+                for key in keys.iter() {
+                    self.remove_entry(map, index, key)?;
+                }
+                Ok(keys.len())
+            }
+        }
+    }
 }
 
-impl<Description> BatchOperations for MemoryStore<Description> where
-    Description: DatabaseDescription
+impl<Description> BatchOperations for MemoryStore<Description>
+where
+    Description: DatabaseDescription,
 {
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        // Stage the whole batch before applying it.
+        let entries: Vec<_> = entries.collect();
+
+        // Hold locks of all columns, so readers see either none or all of the batch.
+        // Locks are always acquired in the order of columns, so it can't deadlock.
+        let mut columns: Vec<_> = self
+            .inner
+            .iter()
+            .map(|column| column.write().expect("poisoned"))
+            .collect();
+
+        // Resolve merges before applying the batch, so the failed merge doesn't leave it applied partially.
+        let mut resolved = HashMap::<(usize, Vec<u8>), Option<Value>>::new();
+        let mut writes = Vec::with_capacity(entries.len());
+        for (key, column, op) in entries {
+            let index = column.as_usize();
+            if !matches!(op, WriteOperation::Remove) {
+                debug_assert_key_size::<Description>(&column, &key);
+            }
+            let value = match op {
+                WriteOperation::Insert(value) => Some(value),
+                WriteOperation::Remove => None,
+                WriteOperation::Merge(operand) => {
+                    let existing = match resolved.get(&(index, key.clone())) {
+                        Some(value) => value.clone(),
+                        None => columns[index].get(&key).cloned(),
+                    };
+                    Some(merge_value(
+                        column,
+                        existing.as_deref().map(Vec::as_slice),
+                        &operand,
+                    )?)
+                }
+            };
+            resolved.insert((index, key.clone()), value.clone());
+            writes.push((index, key, value));
+        }
+
+        // Account the whole batch at once, so it is rejected entirely if it exceeds the capacity.
+        let sizes: Vec<_> = resolved
+            .iter()
+            .map(|((index, key), value)| {
+                let old = entry_size(key, columns[*index].get(key));
+                (*index, old, entry_size(key, value.as_ref()))
+            })
+            .collect();
+        self.resize(&sizes)?;
+
+        for (index, key, value) in writes {
+            let column = Arc::make_mut(&mut columns[index]);
+            match value {
+                Some(value) => {
+                    column.insert(key, value);
+                }
+                None => {
+                    column.remove(&key);
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<Description> TransactableStorage for MemoryStore<Description>
@@ -207,24 +890,141 @@ where
     Description: DatabaseDescription,
 {
     fn flush(&self) -> DatabaseResult<()> {
-        for lock in self.inner.iter() {
-            lock.lock().expect("poisoned").clear();
-        }
+        // The store isn't durable, so there is nothing to flush.
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use fuel_core_storage::column::Column;
-    use std::sync::Arc;
+    fn iter_column(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> ColumnIter<'_> {
+        ColumnIter::memory(self.iter_column(column, prefix, start, direction))
+    }
 
-    #[test]
-    fn can_use_unit_value() {
-        let key = vec![0x00];
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        let lock = self.inner[column.as_usize()].read().expect("poisoned");
+        Ok(ColumnStats::from_entries(
+            lock.iter()
+                .map(|(key, value)| (key.as_slice(), value.as_slice())),
+        ))
+    }
 
-        let db = MemoryStore::<OnChain>::default();
+    /// Writes are reported as slowed in the last fifth of the capacity, and as stalled
+    /// at the capacity, because the store rejects writes growing it further.
+    fn write_pressure(&self) -> WritePressure {
+        let Some(capacity) = self.capacity else {
+            return WritePressure::Ok
+        };
+        let usage = self.total_usage.load(Ordering::Relaxed);
+        if usage >= capacity {
+            WritePressure::Stalled(StallReason::MemoryCap)
+        } else if usage >= capacity.saturating_sub(capacity / 5) {
+            WritePressure::Slowed(StallReason::MemoryCap)
+        } else {
+            WritePressure::Ok
+        }
+    }
+
+    fn checkpoint(&self, _: &Path) -> DatabaseResult<()> {
+        Err(DatabaseError::Unsupported("checkpoint"))
+    }
+
+    fn backup_to(&self, _: &Path) -> DatabaseResult<()> {
+        Err(DatabaseError::Unsupported("backup"))
+    }
+
+    fn compact(
+        &self,
+        _: Option<Self::Column>,
+        _: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        // Removed values don't occupy the space, so there is nothing to compact.
+        Ok(())
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        let index = column.as_usize();
+        let mut lock = self.inner[index].write().expect("poisoned");
+        let usage = self.usage[index].load(Ordering::Relaxed);
+        self.resize(&[(index, usage, 0)])?;
+        let entries = core::mem::take(&mut *lock);
+        drop(lock);
+        // Entries of the large column are freed without blocking other operations.
+        drop(entries);
+        Ok(())
+    }
+
+    fn multi_delete(
+        &self,
+        column: Self::Column,
+        keys: &[Vec<u8>],
+    ) -> DatabaseResult<usize> {
+        let index = column.as_usize();
+        let mut lock = self.inner[index].write().expect("poisoned");
+        let map = Arc::make_mut(&mut lock);
+        let mut existing = 0usize;
+        for key in keys {
+            // The repeated key is already removed, so it isn't counted twice.
+            let removed = self
+                .remove_entry(map, index, key)
+                .map_err(DatabaseError::from)?;
+            existing = existing.saturating_add(usize::from(removed.is_some()));
+        }
+        Ok(existing)
+    }
+
+    fn delete_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: &[u8],
+    ) -> DatabaseResult<()> {
+        if start >= end {
+            return Ok(())
+        }
+        let index = column.as_usize();
+        let mut lock = self.inner[index].write().expect("poisoned");
+        let map = Arc::make_mut(&mut lock);
+        let mut removed = map.split_off(start);
+        let mut after = removed.split_off(end);
+        map.append(&mut after);
+        let size = removed.iter().fold(0usize, |size, (key, value)| {
+            size.saturating_add(entry_size(key, Some(value)))
+        });
+        self.resize(&[(index, size, 0)])?;
+        drop(lock);
+        drop(removed);
+        Ok(())
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        Ok(Arc::new(self.consistent_copy()))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{
+        in_memory::transaction::MemoryTransactionView,
+        DataSource,
+        IngestStats,
+    };
+    use fuel_core_storage::column::Column;
+    use std::sync::Arc;
+
+    #[test]
+    fn can_use_unit_value() {
+        let key = vec![0x00];
+
+        let db = MemoryStore::<OnChain>::default();
         let expected = Arc::new(vec![]);
         db.put(&key.to_vec(), Column::Metadata, expected.clone())
             .unwrap();
@@ -292,4 +1092,1181 @@ mod tests {
 
         assert!(!db.exists(&key, Column::Metadata).unwrap());
     }
+
+    #[test]
+    fn batch_write_with_stats_counts_removals_of_missing_keys() {
+        // Given
+        let db = MemoryStore::<OnChain>::default();
+        db.put(&[1], Column::Metadata, Arc::new(vec![0])).unwrap();
+        let entries = [
+            (
+                vec![2],
+                Column::Metadata,
+                WriteOperation::Insert(Arc::new(vec![2; 4])),
+            ),
+            (vec![1], Column::Metadata, WriteOperation::Remove),
+            (vec![3], Column::Metadata, WriteOperation::Remove),
+        ];
+
+        // When
+        let stats = db.batch_write_with_stats(&mut entries.into_iter()).unwrap();
+
+        // Then
+        assert_eq!(
+            stats,
+            fuel_core_storage::kv_store::BatchWriteResult {
+                inserted: 1,
+                removed: 2,
+                bytes_written: 7,
+            }
+        );
+        assert_eq!(db.get(&[1], Column::Metadata).unwrap(), None);
+    }
+
+    #[test]
+    fn batch_write_applies_operations_in_order() {
+        // Given
+        let db = MemoryStore::<OnChain>::default();
+        db.put(&[2], Column::Metadata, Arc::new(vec![0])).unwrap();
+        let insert = |key: u8, value: u8| {
+            (
+                vec![key],
+                Column::Metadata,
+                WriteOperation::Insert(Arc::new(vec![value])),
+            )
+        };
+        let remove = |key: u8| (vec![key], Column::Metadata, WriteOperation::Remove);
+
+        // When
+        db.batch_write(
+            &mut [
+                insert(1, 1),
+                remove(1),
+                remove(2),
+                insert(2, 2),
+                insert(3, 1),
+                insert(3, 3),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+
+        // Then
+        assert_eq!(db.get(&[1], Column::Metadata).unwrap(), None);
+        assert_eq!(
+            db.get(&[2], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![2]))
+        );
+        assert_eq!(
+            db.get(&[3], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![3]))
+        );
+    }
+
+    #[test]
+    fn batch_write_applies_all_operations() {
+        let db = MemoryStore::<OnChain>::default();
+        db.put(&[1], Column::Metadata, Arc::new(vec![1])).unwrap();
+
+        db.batch_write(
+            &mut [
+                (vec![1], Column::Metadata, WriteOperation::Remove),
+                (
+                    vec![2],
+                    Column::Metadata,
+                    WriteOperation::Insert(Arc::new(vec![2])),
+                ),
+                (
+                    vec![3],
+                    Column::Coins,
+                    WriteOperation::Insert(Arc::new(vec![3])),
+                ),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+
+        assert_eq!(db.get(&[1], Column::Metadata).unwrap(), None);
+        assert_eq!(
+            db.get(&[2], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![2]))
+        );
+        assert_eq!(
+            db.get(&[3], Column::Coins).unwrap(),
+            Some(Arc::new(vec![3]))
+        );
+    }
+
+    #[test]
+    fn column_stats_counts_entries() {
+        let db = MemoryStore::<OnChain>::default();
+        db.put(&[1], Column::Metadata, Arc::new(vec![1, 2]))
+            .unwrap();
+        db.put(&[2, 3], Column::Metadata, Arc::new(vec![3]))
+            .unwrap();
+
+        assert_eq!(
+            db.column_stats(Column::Metadata).unwrap(),
+            ColumnStats { keys: 2, size: 6 }
+        );
+        assert_eq!(
+            db.column_stats(Column::Coins).unwrap(),
+            ColumnStats::default()
+        );
+    }
+
+    #[test]
+    fn size_of_range_sums_entries_exactly() {
+        // Given
+        let db = MemoryStore::<OnChain>::default();
+        for key in [[0, 1], [1, 0], [1, 0xFF], [2, 0], [0xFF, 0xFF]] {
+            db.put(&key, Column::Coins, Arc::new(vec![0; 8])).unwrap();
+        }
+
+        // Then
+        assert_eq!(
+            db.size_of_range(Column::Coins, &[1], Some(&[2])).unwrap(),
+            20
+        );
+        assert_eq!(
+            db.size_of_range(Column::Coins, &[1, 0xFF], None).unwrap(),
+            30
+        );
+        assert_eq!(db.size_of_prefix(Column::Coins, &[1]).unwrap(), 20);
+        assert_eq!(db.size_of_prefix(Column::Coins, &[0xFF]).unwrap(), 10);
+        assert_eq!(db.size_of_prefix(Column::Coins, &[]).unwrap(), 50);
+        assert_eq!(db.size_of_prefix(Column::Coins, &[3]).unwrap(), 0);
+    }
+
+    #[test]
+    fn flush_keeps_values() {
+        let db = MemoryStore::<OnChain>::default();
+        db.put(&[1], Column::Metadata, Arc::new(vec![1])).unwrap();
+
+        db.flush().unwrap();
+
+        assert_eq!(
+            db.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+    }
+
+    #[test]
+    fn compact_keeps_values() {
+        let db = MemoryStore::<OnChain>::default();
+        db.put(&[1], Column::Metadata, Arc::new(vec![1])).unwrap();
+
+        db.compact(None, None).unwrap();
+        db.compact(Some(Column::Metadata), Some((vec![0], vec![2])))
+            .unwrap();
+
+        assert_eq!(
+            db.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+    }
+
+    #[test]
+    fn delete_all_removes_only_prefixed_keys() {
+        let db = MemoryStore::<OnChain>::default();
+        for key in [[0, 1], [1, 0], [1, 2], [2, 0]] {
+            db.put(&key, Column::Metadata, Arc::new(vec![1])).unwrap();
+        }
+
+        assert_eq!(db.delete_all(Column::Metadata, Some(&[1])).unwrap(), 2);
+        assert_eq!(db.delete_all(Column::Metadata, Some(&[1])).unwrap(), 0);
+        assert!(db.exists(&[0, 1], Column::Metadata).unwrap());
+        assert!(db.exists(&[2, 0], Column::Metadata).unwrap());
+        assert_eq!(db.delete_all(Column::Metadata, None).unwrap(), 2);
+        assert_eq!(db.column_stats(Column::Metadata).unwrap().keys, 0);
+    }
+
+    #[test]
+    fn compare_and_swap_has_exactly_one_winner() {
+        let db = MemoryStore::<OnChain>::default();
+        let db = Arc::new(db);
+        let key = vec![0xA];
+
+        let winners: usize = (0..8u8)
+            .map(|i| {
+                let db = db.clone();
+                let key = key.clone();
+                std::thread::spawn(move || {
+                    db.compare_and_swap(&key, Column::Metadata, None, Arc::new(vec![i]))
+                        .unwrap()
+                        .is_ok()
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| usize::from(handle.join().unwrap()))
+            .sum();
+
+        assert_eq!(winners, 1);
+        let winner = db.get(&key, Column::Metadata).unwrap().unwrap();
+        assert_eq!(
+            db.compare_and_swap(&key, Column::Metadata, None, Arc::new(vec![0xFF]))
+                .unwrap(),
+            Err(Some(winner.clone()))
+        );
+        assert_eq!(
+            db.compare_and_swap(
+                &key,
+                Column::Metadata,
+                Some(winner.as_slice()),
+                Arc::new(vec![0xFF])
+            )
+            .unwrap(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn read_snapshot_is_not_affected_by_later_writes() {
+        // setup
+        let store = MemoryStore::<OnChain>::default();
+        store
+            .put(&[1], Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+        store
+            .put(&[2], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+
+        // test
+        let snapshot = store.read_snapshot().unwrap();
+        store.delete(&[1], Column::Metadata).unwrap();
+        store
+            .put(&[3], Column::Metadata, Arc::new(vec![3]))
+            .unwrap();
+
+        // verify
+        assert_eq!(
+            snapshot.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+        assert!(!snapshot.exists(&[3], Column::Metadata).unwrap());
+        assert_eq!(
+            snapshot
+                .multi_get(&[vec![1], vec![3]], Column::Metadata)
+                .unwrap(),
+            vec![Some(Arc::new(vec![1])), None]
+        );
+        let keys: Vec<_> = snapshot
+            .iter_all(Column::Metadata, None, None, IterDirection::Forward)
+            .map(|item| item.unwrap().0)
+            .collect();
+        assert_eq!(keys, vec![vec![1], vec![2]]);
+    }
+
+    #[test]
+    fn merges_via_methods_and_batch_write_give_the_same_state() {
+        use crate::database::database_description::test::*;
+
+        // setup
+        let by_methods = MemoryStore::<MergeDatabase>::default();
+        let by_batch = MemoryStore::<MergeDatabase>::default();
+        // test
+        apply_merge_operations(&by_methods, merge_base());
+        apply_merge_operations(&by_methods, merge_operations());
+        by_batch.batch_write(&mut merge_base().into_iter()).unwrap();
+        by_batch
+            .batch_write(&mut merge_operations().into_iter())
+            .unwrap();
+        // verify
+        assert_eq!(merge_entries(&by_methods), merged_state());
+        assert_eq!(merge_entries(&by_batch), merged_state());
+    }
+
+    #[test]
+    fn failed_merge_leaves_the_batch_unapplied() {
+        use crate::database::database_description::test::*;
+
+        // setup
+        let store = MemoryStore::<MergeDatabase>::default();
+        store.batch_write(&mut merge_base().into_iter()).unwrap();
+        let mut batch = merge_operations();
+        batch.push((
+            vec![1],
+            MergeColumn::Metadata,
+            WriteOperation::Merge(Arc::new(vec![1])),
+        ));
+        // test
+        let result = store.batch_write(&mut batch.into_iter());
+        let direct = store.merge(&[1], MergeColumn::Counters, Arc::new(vec![1, 2]));
+        // verify
+        assert!(result.is_err());
+        assert!(direct.is_err());
+        let expected = MemoryStore::<MergeDatabase>::default();
+        expected.batch_write(&mut merge_base().into_iter()).unwrap();
+        assert_eq!(merge_entries(&store), merge_entries(&expected));
+    }
+
+    #[test]
+    fn concurrent_readers_and_writers_see_consistent_batches() {
+        const READERS: usize = 8;
+        const WRITERS: usize = 4;
+        const ITERATIONS: u64 = 200;
+        const KEYS: u8 = 16;
+        let columns = [Column::Metadata, Column::Coins];
+
+        // setup
+        let store = Arc::new(MemoryStore::<OnChain>::default());
+        let batch = move |value: u64| {
+            let value = Arc::new(value.to_be_bytes().to_vec());
+            columns
+                .into_iter()
+                .flat_map(|column| (0..KEYS).map(move |key| (vec![key], column)))
+                .map(|(key, column)| (key, column, WriteOperation::Insert(value.clone())))
+                .collect::<Vec<_>>()
+        };
+        store.batch_write(&mut batch(0).into_iter()).unwrap();
+
+        // test
+        let (done, finished) = std::sync::mpsc::channel();
+        let writers = (0..WRITERS as u64).map(|writer| {
+            let store = store.clone();
+            std::thread::spawn(move || {
+                for i in 0..ITERATIONS {
+                    let value = i * WRITERS as u64 + writer;
+                    store.batch_write(&mut batch(value).into_iter()).unwrap();
+                }
+            })
+        });
+        let readers = (0..READERS).map(|_| {
+            let store = store.clone();
+            std::thread::spawn(move || {
+                for _ in 0..ITERATIONS {
+                    // Each batch overwrites all keys, so any read must see a single batch.
+                    let keys = (0..KEYS).map(|key| vec![key]).collect::<Vec<_>>();
+                    let values = store.multi_get(&keys, Column::Metadata).unwrap();
+                    assert!(values.iter().all(|value| value == &values[0]));
+                    assert_eq!(
+                        store
+                            .iter_all(
+                                Column::Metadata,
+                                None,
+                                None,
+                                IterDirection::Forward
+                            )
+                            .count(),
+                        KEYS as usize
+                    );
+
+                    let copy = store.consistent_copy();
+                    let in_columns = columns
+                        .into_iter()
+                        .flat_map(|column| {
+                            copy.iter_all(column, None, None, IterDirection::Forward)
+                        })
+                        .map(|entry| entry.unwrap().1)
+                        .collect::<Vec<_>>();
+                    assert!(in_columns.iter().all(|value| value == &in_columns[0]));
+                }
+            })
+        });
+        let threads = writers.chain(readers).collect::<Vec<_>>();
+        std::thread::spawn(move || {
+            let results = threads
+                .into_iter()
+                .map(|thread| thread.join())
+                .collect::<Vec<_>>();
+            done.send(results).unwrap();
+        });
+
+        // verify
+        let results = finished
+            .recv_timeout(std::time::Duration::from_secs(60))
+            .expect("Readers and writers are deadlocked");
+        assert!(results.iter().all(Result::is_ok));
+        let last = (0..WRITERS as u64)
+            .map(|writer| (ITERATIONS - 1) * WRITERS as u64 + writer)
+            .collect::<Vec<_>>();
+        let value = store.get(&[0], Column::Metadata).unwrap().unwrap();
+        let value = u64::from_be_bytes(value.as_slice().try_into().unwrap());
+        assert!(last.contains(&value));
+    }
+
+    #[test]
+    fn iter_all_clones_only_the_entries_it_reads() {
+        // setup
+        let store = MemoryStore::<OnChain>::default();
+        {
+            let mut lock = store.inner[Column::Coins.as_usize()].write().unwrap();
+            let column = Arc::make_mut(&mut lock);
+            for i in 0..1_000_000u32 {
+                column.insert(i.to_be_bytes().to_vec(), Arc::new(vec![]));
+            }
+        }
+        // test
+        let mut iter = store.iter_all(Column::Coins, None, None, IterDirection::Forward);
+        let first = iter
+            .by_ref()
+            .take(10)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        // verify
+        assert_eq!(first.len(), 10);
+        let cloned = store.inner[Column::Coins.as_usize()]
+            .read()
+            .unwrap()
+            .values()
+            .filter(|value| Arc::strong_count(value) > 1)
+            .count();
+        assert!(cloned < 16, "{cloned} values are cloned");
+    }
+
+    fn page(
+        store: &MemoryStore<OnChain>,
+        prefix: Option<&[u8]>,
+        start: Bound<&[u8]>,
+        direction: IterDirection,
+        limit: Option<usize>,
+    ) -> (Vec<Vec<u8>>, Option<Vec<u8>>) {
+        let config = IterConfig {
+            prefix,
+            start,
+            direction,
+            limit,
+            ..Default::default()
+        };
+        let mut iter = IteratorableStore::iter(store, Column::Coins, config);
+        let keys = iter
+            .by_ref()
+            .map(|item| item.unwrap().0)
+            .collect::<Vec<_>>();
+        (keys, iter.last_key().map(<[u8]>::to_vec))
+    }
+
+    #[test]
+    fn iter_with_limit_on_the_prefix_boundary_yields_the_whole_prefix() {
+        // setup
+        let store = MemoryStore::<OnChain>::default();
+        for key in [[1, 0], [1, 1], [1, 2], [2, 0], [2, 1]] {
+            store.put(&key, Column::Coins, Arc::new(vec![])).unwrap();
+        }
+        // test
+        let (keys, last_key) = page(
+            &store,
+            Some(&[1]),
+            Bound::Unbounded,
+            IterDirection::Forward,
+            Some(3),
+        );
+        // verify
+        assert_eq!(keys, vec![vec![1, 0], vec![1, 1], vec![1, 2]]);
+        assert_eq!(last_key, Some(vec![1, 2]));
+
+        // The next page starts after the last key and is empty.
+        let (keys, last_key) = page(
+            &store,
+            Some(&[1]),
+            Bound::Excluded(&[1, 2]),
+            IterDirection::Forward,
+            Some(3),
+        );
+        assert!(keys.is_empty());
+        assert_eq!(last_key, None);
+    }
+
+    #[test]
+    fn pages_with_excluded_start_cover_the_column_in_both_directions() {
+        // setup
+        let store = MemoryStore::<OnChain>::default();
+        for key in 0..7u8 {
+            store.put(&[key], Column::Coins, Arc::new(vec![])).unwrap();
+        }
+        for direction in [IterDirection::Forward, IterDirection::Reverse] {
+            // test
+            let mut keys = vec![];
+            let mut cursor = None;
+            loop {
+                let start = cursor.as_deref().map_or(Bound::Unbounded, Bound::Excluded);
+                let (page, last_key) = page(&store, None, start, direction, Some(3));
+                keys.extend(page.into_iter().map(|key| key[0]));
+                let Some(last_key) = last_key else { break };
+                cursor = Some(last_key);
+            }
+            // verify
+            let mut expected = (0..7u8).collect::<Vec<_>>();
+            if direction == IterDirection::Reverse {
+                expected.reverse();
+            }
+            assert_eq!(keys, expected, "{direction:?}");
+        }
+    }
+
+    #[test]
+    fn excluded_start_that_does_not_exist_is_the_same_as_included() {
+        // setup
+        let store = MemoryStore::<OnChain>::default();
+        for key in [[1, 0], [1, 2], [1, 4]] {
+            store.put(&key, Column::Coins, Arc::new(vec![])).unwrap();
+        }
+        for direction in [IterDirection::Forward, IterDirection::Reverse] {
+            // test
+            let (excluded, _) = page(
+                &store,
+                Some(&[1]),
+                Bound::Excluded(&[1, 3]),
+                direction,
+                None,
+            );
+            let (included, _) = page(
+                &store,
+                Some(&[1]),
+                Bound::Included(&[1, 3]),
+                direction,
+                None,
+            );
+            // verify
+            assert_eq!(excluded, included, "{direction:?}");
+        }
+
+        // The cursor key is removed between pages in the reverse direction.
+        store.delete(&[1, 2], Column::Coins).unwrap();
+        let (keys, _) = page(
+            &store,
+            Some(&[1]),
+            Bound::Excluded(&[1, 2]),
+            IterDirection::Reverse,
+            None,
+        );
+        assert_eq!(keys, vec![vec![1, 0]]);
+    }
+
+    #[test]
+    fn iter_with_zero_limit_yields_nothing() {
+        // setup
+        let store = MemoryStore::<OnChain>::default();
+        store.put(&[1], Column::Coins, Arc::new(vec![])).unwrap();
+        // test
+        let (keys, last_key) = page(
+            &store,
+            None,
+            Bound::Unbounded,
+            IterDirection::Forward,
+            Some(0),
+        );
+        // verify
+        assert!(keys.is_empty());
+        assert_eq!(last_key, None);
+    }
+
+    #[test]
+    fn iter_with_limit_clones_only_the_entries_of_the_page() {
+        // setup
+        let store = MemoryStore::<OnChain>::default();
+        {
+            let mut lock = store.inner[Column::Coins.as_usize()].write().unwrap();
+            let column = Arc::make_mut(&mut lock);
+            for i in 0..1000u32 {
+                column.insert(i.to_be_bytes().to_vec(), Arc::new(vec![]));
+            }
+        }
+        let config = IterConfig {
+            limit: Some(3),
+            ..Default::default()
+        };
+        // test
+        let mut iter = IteratorableStore::iter(&store, Column::Coins, config);
+        let page = iter.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        // verify
+        assert_eq!(page.len(), 3);
+        let cloned = store.inner[Column::Coins.as_usize()]
+            .read()
+            .unwrap()
+            .values()
+            .filter(|value| Arc::strong_count(value) > 1)
+            .count();
+        assert_eq!(cloned, 3);
+    }
+
+    #[test]
+    fn iter_all_over_many_chunks_matches_the_filtered_range() {
+        // setup
+        let store = MemoryStore::<OnChain>::default();
+        let keys = (0..5000u16)
+            .map(|i| i.to_be_bytes().to_vec())
+            .collect::<Vec<_>>();
+        for key in keys.iter() {
+            store
+                .put(key, Column::Coins, Arc::new(key.clone()))
+                .unwrap();
+        }
+        let collect = |prefix: Option<&[u8]>, start: Option<&[u8]>, direction| {
+            store
+                .iter_keys(Column::Coins, prefix, start, direction)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        };
+        // test
+        let forward = collect(Some(&[0x0A]), None, IterDirection::Forward);
+        let reverse = collect(Some(&[0x0A]), None, IterDirection::Reverse);
+        let reverse_from = collect(None, Some(&[0x0A, 0x10]), IterDirection::Reverse);
+        // verify
+        let with_prefix = keys
+            .iter()
+            .filter(|key| key[0] == 0x0A)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(forward, with_prefix);
+        assert_eq!(reverse, with_prefix.into_iter().rev().collect::<Vec<_>>());
+        assert_eq!(
+            reverse_from,
+            keys.iter()
+                .filter(|key| key.as_slice() <= [0x0A, 0x10].as_slice())
+                .rev()
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn iter_all_does_not_see_writes_after_its_creation() {
+        // setup
+        let store = MemoryStore::<OnChain>::default();
+        for i in [1u8, 3] {
+            store
+                .put(&[i], Column::Metadata, Arc::new(vec![i]))
+                .unwrap();
+        }
+        let mut iter =
+            store.iter_keys(Column::Metadata, None, None, IterDirection::Forward);
+        // test
+        let first = iter.next().unwrap().unwrap();
+        store
+            .put(&[0], Column::Metadata, Arc::new(vec![0]))
+            .unwrap();
+        store
+            .put(&[2], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+        let rest = iter.collect::<Result<Vec<_>, _>>().unwrap();
+        // verify
+        assert_eq!(first, vec![1]);
+        assert_eq!(rest, vec![vec![3]]);
+    }
+
+    #[test]
+    fn iter_all_with_start_outside_of_prefix_is_empty() {
+        // setup
+        let store = MemoryStore::<OnChain>::default();
+        for key in [[1, 0], [2, 0]] {
+            store
+                .put(&key, Column::Metadata, Arc::new(vec![1]))
+                .unwrap();
+        }
+        // test
+        let iter = |start: &[u8], direction| {
+            store
+                .iter_keys(Column::Metadata, Some(&[2]), Some(start), direction)
+                .count()
+        };
+        // verify
+        assert_eq!(iter(&[], IterDirection::Forward), 0);
+        assert_eq!(iter(&[3], IterDirection::Reverse), 0);
+        assert_eq!(iter(&[2], IterDirection::Forward), 1);
+    }
+
+    fn usage_of(db: &MemoryStore<OnChain>, column: Column) -> usize {
+        db.memory_usage()
+            .columns
+            .into_iter()
+            .find(|(c, _)| *c == column)
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn memory_usage_accounts_the_delta_of_overwrites() {
+        let db = MemoryStore::<OnChain>::default();
+
+        db.put(&[1, 2, 3], Column::Metadata, Arc::new(vec![0; 10]))
+            .unwrap();
+        assert_eq!(usage_of(&db, Column::Metadata), 13);
+
+        db.put(&[1, 2, 3], Column::Metadata, Arc::new(vec![0; 4]))
+            .unwrap();
+        assert_eq!(usage_of(&db, Column::Metadata), 7);
+
+        db.write(&[1], Column::Coins, &[0; 9]).unwrap();
+        db.batch_write(
+            &mut [
+                (vec![1, 2, 3], Column::Metadata, WriteOperation::Remove),
+                (
+                    vec![4],
+                    Column::Metadata,
+                    WriteOperation::Insert(Arc::new(vec![0; 2])),
+                ),
+                (
+                    vec![4],
+                    Column::Metadata,
+                    WriteOperation::Insert(Arc::new(vec![0; 5])),
+                ),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+        assert_eq!(usage_of(&db, Column::Metadata), 6);
+        assert_eq!(usage_of(&db, Column::Coins), 10);
+        assert_eq!(db.memory_usage().total, 16);
+
+        db.take(&[4], Column::Metadata).unwrap();
+        db.delete_all(Column::Coins, None).unwrap();
+        assert_eq!(db.memory_usage().total, 0);
+        assert!(db
+            .memory_usage()
+            .columns
+            .iter()
+            .all(|(_, usage)| *usage == 0));
+    }
+
+    #[test]
+    fn capacity_rejects_the_batch_crossing_it() {
+        // setup
+        let db = MemoryStore::<OnChain>::with_capacity_limit(100);
+        db.put(&[0], Column::Metadata, Arc::new(vec![0; 59]))
+            .unwrap();
+        let batch = |sizes: &[usize]| {
+            (1u8..)
+                .zip(sizes)
+                .map(|(i, size)| {
+                    (
+                        vec![i],
+                        Column::Coins,
+                        WriteOperation::Insert(Arc::new(vec![0; *size])),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // test
+        let crossing = db.batch_write(&mut batch(&[19, 19, 1]).into_iter());
+        let put = db.put(&[0], Column::Metadata, Arc::new(vec![0; 100]));
+
+        // verify
+        let out_of_capacity =
+            fuel_core_storage::Error::from(DatabaseError::OutOfCapacity {
+                capacity: 100,
+                usage: 60,
+                required: 42,
+            });
+        assert_eq!(
+            format!("{:?}", crossing.unwrap_err()),
+            format!("{out_of_capacity:?}")
+        );
+        assert!(put.is_err());
+        assert_eq!(db.memory_usage().total, 60);
+        assert!(!db.exists(&[1], Column::Coins).unwrap());
+        // The batch that fits exactly into the capacity is accepted.
+        db.batch_write(&mut batch(&[19, 19]).into_iter()).unwrap();
+        assert_eq!(db.memory_usage().total, 100);
+        // The overwrite that shrinks the store is accepted at the capacity.
+        db.put(&[0], Column::Metadata, Arc::new(vec![0; 1]))
+            .unwrap();
+        assert_eq!(db.memory_usage().total, 42);
+    }
+
+    #[test]
+    fn clear_column_removes_only_its_entries_and_keeps_open_iterators() {
+        // Given
+        let db = MemoryStore::<OnChain>::default();
+        for i in 0..10u8 {
+            db.put(&[i], Column::Coins, Arc::new(vec![i])).unwrap();
+        }
+        db.put(&[1], Column::Metadata, Arc::new(vec![1])).unwrap();
+        let mut iter = db.iter_all(Column::Coins, None, None, IterDirection::Forward);
+        assert_eq!(iter.next().unwrap().unwrap().0, vec![0]);
+
+        // When
+        db.clear_column(Column::Coins).unwrap();
+
+        // Then
+        // The open iterator holds the version of the column at its creation.
+        assert_eq!(
+            iter.map(|item| item.unwrap().0).collect::<Vec<_>>(),
+            (1..10u8).map(|i| vec![i]).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            db.iter_all(Column::Coins, None, None, IterDirection::Forward)
+                .count(),
+            0
+        );
+        assert_eq!(usage_of(&db, Column::Coins), 0);
+        assert_eq!(db.memory_usage().total, 2);
+        assert_eq!(
+            db.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+        db.put(&[1], Column::Coins, Arc::new(vec![2])).unwrap();
+        assert_eq!(
+            db.get(&[1], Column::Coins).unwrap(),
+            Some(Arc::new(vec![2]))
+        );
+    }
+
+    #[test]
+    fn as_any_downcasts_to_the_storage_itself() {
+        // Given
+        let store = Arc::new(MemoryStore::<OnChain>::default());
+        let wrapped: DataSource<OnChain> =
+            Arc::new(MemoryTransactionView::<OnChain>::new(store.clone())).into();
+        let store: DataSource<OnChain> = store.into();
+
+        // When
+        let store_as_memory = store.as_any().downcast_ref::<MemoryStore<OnChain>>();
+        let wrapped_as_memory = wrapped.as_any().downcast_ref::<MemoryStore<OnChain>>();
+
+        // Then
+        assert!(store_as_memory.is_some());
+        assert!(wrapped_as_memory.is_none());
+        assert!(wrapped
+            .as_any()
+            .downcast_ref::<MemoryTransactionView<OnChain>>()
+            .is_some());
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn as_rocks_of_the_memory_store_is_none() {
+        // Given
+        let store: DataSource<OnChain> =
+            Arc::new(MemoryStore::<OnChain>::default()).into();
+
+        // When
+        let rocks = store.as_rocks::<OnChain>();
+
+        // Then
+        assert!(rocks.is_none());
+    }
+
+    #[test]
+    fn multi_delete_counts_only_existing_keys() {
+        // Given
+        let db = MemoryStore::<OnChain>::default();
+        for key in [1u8, 3] {
+            db.put(&[key], Column::Coins, Arc::new(vec![key])).unwrap();
+        }
+        db.put(&[2], Column::Coins, Arc::new(vec![2])).unwrap();
+
+        // When
+        let existing = db
+            .multi_delete(Column::Coins, &[vec![1], vec![3], vec![4]])
+            .unwrap();
+
+        // Then
+        assert_eq!(existing, 2);
+        assert_eq!(
+            db.iter_all(Column::Coins, None, None, IterDirection::Forward)
+                .map(|item| item.unwrap().0)
+                .collect::<Vec<_>>(),
+            vec![vec![2]]
+        );
+        assert_eq!(db.memory_usage().total, 2);
+    }
+
+    #[test]
+    fn multi_delete_counts_repeated_key_once() {
+        // Given
+        let db = MemoryStore::<OnChain>::default();
+        db.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+
+        // When
+        let existing = db.multi_delete(Column::Coins, &[vec![1], vec![1]]).unwrap();
+
+        // Then
+        assert_eq!(existing, 1);
+        assert!(!db.exists(&[1], Column::Coins).unwrap());
+    }
+
+    #[test]
+    fn delete_range_removes_keys_from_start_to_end_exclusive() {
+        // Given
+        let store = MemoryStore::<OnChain>::default();
+        for key in 1..=5u8 {
+            store
+                .put(&[key], Column::Coins, Arc::new(vec![key]))
+                .unwrap();
+        }
+        store
+            .put(&[3], Column::Metadata, Arc::new(vec![3]))
+            .unwrap();
+
+        // When
+        store.delete_range(Column::Coins, &[2], &[4]).unwrap();
+        store.delete_range(Column::Coins, &[5], &[5]).unwrap();
+
+        // Then
+        let keys: Vec<_> = store
+            .iter_all(Column::Coins, None, None, IterDirection::Forward)
+            .map(|item| item.unwrap().0)
+            .collect();
+        assert_eq!(keys, vec![vec![1], vec![4], vec![5]]);
+        assert!(store.exists(&[3], Column::Metadata).unwrap());
+        assert_eq!(store.memory_usage().total, 8);
+    }
+
+    #[test]
+    fn write_pressure_follows_the_usage_of_the_capacity() {
+        // Given
+        let db = MemoryStore::<OnChain>::with_capacity_limit(100);
+        let unlimited = MemoryStore::<OnChain>::default();
+        unlimited
+            .put(&[0], Column::Metadata, Arc::new(vec![0; 1024]))
+            .unwrap();
+
+        // When
+        let mut pressures = vec![db.write_pressure()];
+        for size in [59, 84, 99, 9] {
+            db.put(&[0], Column::Metadata, Arc::new(vec![0; size]))
+                .unwrap();
+            pressures.push(db.write_pressure());
+        }
+
+        // Then
+        assert_eq!(
+            pressures,
+            vec![
+                WritePressure::Ok,
+                WritePressure::Ok,
+                WritePressure::Slowed(StallReason::MemoryCap),
+                WritePressure::Stalled(StallReason::MemoryCap),
+                WritePressure::Ok,
+            ]
+        );
+        assert_eq!(unlimited.write_pressure(), WritePressure::Ok);
+    }
+
+    #[test]
+    fn ingest_sorted_falls_back_to_the_batch_write() {
+        // Given
+        let db = MemoryStore::<OnChain>::default();
+        db.put(&[5], Column::Coins, Arc::new(vec![0])).unwrap();
+        let entries = (0..10u8).map(|i| (vec![i], vec![i; 3]));
+
+        // When
+        let stats = db.ingest_sorted(Column::Coins, &mut entries.into_iter());
+
+        // Then
+        assert_eq!(
+            stats.unwrap(),
+            IngestStats {
+                entries: 10,
+                bytes: 40,
+                files: 0,
+            }
+        );
+        assert_eq!(
+            db.get(&[5], Column::Coins).unwrap(),
+            Some(Arc::new(vec![5; 3]))
+        );
+        assert_eq!(
+            db.iter_all(Column::Coins, None, None, IterDirection::Forward)
+                .count(),
+            10
+        );
+    }
+
+    #[test]
+    fn unsorted_ingest_writes_nothing() {
+        // Given
+        let db = MemoryStore::<OnChain>::default();
+        let unsorted = vec![(vec![1], vec![1]), (vec![3], vec![3]), (vec![2], vec![2])];
+        let duplicated = vec![(vec![1], vec![1]), (vec![1], vec![2])];
+
+        // When
+        let unsorted = db.ingest_sorted(Column::Coins, &mut unsorted.into_iter());
+        let duplicated = db.ingest_sorted(Column::Coins, &mut duplicated.into_iter());
+
+        // Then
+        assert!(matches!(
+            unsorted,
+            Err(DatabaseError::UnsortedIngest { column, ref key })
+                if column == Column::Coins.name() && key == &[2]
+        ));
+        assert!(matches!(
+            duplicated,
+            Err(DatabaseError::UnsortedIngest { ref key, .. }) if key == &[1]
+        ));
+        assert!(db
+            .iter_all(Column::Coins, None, None, IterDirection::Forward)
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn get_or_insert_with_initializes_once_across_racing_callers() {
+        // Given
+        let db = MemoryStore::<OnChain>::default();
+        let db = Arc::new(db);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(std::sync::Barrier::new(16));
+
+        // When
+        let values = (0..16u8)
+            .map(|i| {
+                let db = db.clone();
+                let calls = calls.clone();
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    db.get_or_insert_with(&[0xA], Column::Metadata, &mut || {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        vec![i]
+                    })
+                    .unwrap()
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
+
+        // Then
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        let stored = db.get(&[0xA], Column::Metadata).unwrap().unwrap();
+        assert!(values.iter().all(|value| *value == stored));
+    }
+
+    #[test]
+    fn concurrent_updates_do_not_lose_increments() {
+        // Given
+        let db = Arc::new(MemoryStore::<OnChain>::default());
+        let barrier = Arc::new(std::sync::Barrier::new(16));
+
+        // When
+        let handles = (0..16)
+            .map(|_| {
+                let db = db.clone();
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    for _ in 0..100 {
+                        db.update(&[0xA], Column::Metadata, &mut |current| {
+                            let current = current.map_or(0, |current| {
+                                u64::from_be_bytes(current.try_into().unwrap())
+                            });
+                            Some(current.saturating_add(1).to_be_bytes().to_vec())
+                        })
+                        .unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        // Then
+        let stored = db.get(&[0xA], Column::Metadata).unwrap().unwrap();
+        assert_eq!(stored.as_slice(), 1600u64.to_be_bytes());
+    }
+
+    #[test]
+    fn update_to_none_removes_the_value() {
+        // Given
+        let db = MemoryStore::<OnChain>::default();
+        db.put(&[0xA], Column::Metadata, Arc::new(vec![1])).unwrap();
+
+        // When
+        let removed = db.update(&[0xA], Column::Metadata, &mut |_| None).unwrap();
+        let missing = db.update(&[0xB], Column::Metadata, &mut |_| None).unwrap();
+
+        // Then
+        assert_eq!(removed, UpdateOutcome::Removed);
+        assert_eq!(missing, UpdateOutcome::Unchanged);
+        assert!(!db.exists(&[0xA], Column::Metadata).unwrap());
+    }
+
+    #[test]
+    fn iterators_see_the_state_at_their_creation_under_concurrent_writes() {
+        // Given
+        const KEYS: u16 = 2000;
+        let (start, end) = (KEYS / 4, KEYS / 4 * 3);
+        let store = Arc::new(MemoryStore::<OnChain>::default());
+        for i in (0..KEYS).step_by(2) {
+            store
+                .put(&i.to_be_bytes(), Column::Coins, Arc::new(vec![0]))
+                .unwrap();
+        }
+        // Reads of the expected state and creations of iterators exclude writes.
+        let writes = Arc::new(std::sync::Mutex::new(()));
+        let stop = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let writer = std::thread::spawn({
+            let (store, writes, stop) = (store.clone(), writes.clone(), stop.clone());
+            move || {
+                let mut round = 0u8;
+                while !stop.load(Ordering::Relaxed) {
+                    round = round.wrapping_add(1);
+                    // Keys inside and outside of the range are inserted and removed.
+                    for i in 0..KEYS {
+                        let guard = writes.lock().unwrap();
+                        let key = i.to_be_bytes();
+                        if (i ^ u16::from(round)) % 3 == 0 {
+                            store.delete(&key, Column::Coins).unwrap();
+                        } else {
+                            store
+                                .put(&key, Column::Coins, Arc::new(vec![round]))
+                                .unwrap();
+                        }
+                        drop(guard);
+                        std::thread::yield_now();
+                    }
+                }
+            }
+        });
+
+        // When
+        let mut results = vec![];
+        for _ in 0..10 {
+            let (expected, iter, mut column_iter) = {
+                let _guard = writes.lock().unwrap();
+                let expected = (start..end)
+                    .filter_map(|i| {
+                        let key = i.to_be_bytes().to_vec();
+                        let value = store.get(&key, Column::Coins).unwrap()?;
+                        Some((key, value))
+                    })
+                    .collect::<Vec<_>>();
+                let iter = store.iter_range(
+                    Column::Coins,
+                    None,
+                    Some(&start.to_be_bytes()),
+                    Some(&end.to_be_bytes()),
+                    IterDirection::Forward,
+                );
+                let column_iter = store.iter_column(
+                    Column::Coins,
+                    None,
+                    Some(&start.to_be_bytes()),
+                    IterDirection::Forward,
+                );
+                (expected, iter, column_iter)
+            };
+            let entries = iter
+                .map(|item| {
+                    std::thread::yield_now();
+                    item.unwrap()
+                })
+                .collect::<Vec<_>>();
+            let mut column_entries = vec![];
+            column_iter.advance().unwrap();
+            while let Some((key, value)) = column_iter.current() {
+                if key >= end.to_be_bytes().as_slice() {
+                    break
+                }
+                column_entries.push((key.to_vec(), Arc::new(value.to_vec())));
+                std::thread::yield_now();
+                column_iter.advance().unwrap();
+            }
+            results.push((expected, entries, column_entries));
+        }
+        stop.store(true, Ordering::Relaxed);
+        writer.join().unwrap();
+
+        // Then
+        for (expected, entries, column_entries) in results {
+            assert_eq!(entries, expected);
+            assert_eq!(column_entries, expected);
+        }
+    }
 }
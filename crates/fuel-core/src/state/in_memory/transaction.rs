@@ -1,32 +1,61 @@
 use crate::{
     database::{
         database_description::{
+            column_slots,
             on_chain::OnChain,
             DatabaseDescription,
         },
+        Error as DatabaseError,
         Result as DatabaseResult,
     },
     state::{
-        in_memory::memory_store::MemoryStore,
+        chunked_commit::{
+            chunked_batch_write,
+            ChunkLimits,
+        },
+        column_iter::{
+            ColumnIter,
+            RawColumnIter,
+        },
+        distinct_keys,
+        in_memory::memory_store::{
+            MemoryColumnIter,
+            MemoryStore,
+        },
+        read_context,
         BatchOperations,
+        ColumnStats,
         DataSource,
+        DataSourceInner,
         IterDirection,
         TransactableStorage,
+        WriteDurability,
+        WritePressure,
     },
 };
+use fuel_core_database::LimitKind;
 use fuel_core_storage::{
     iter::{
+        is_start_within_prefix,
         BoxedIter,
         IntoBoxedIter,
+        IterConfig,
         IteratorableStore,
+        PageIter,
     },
     kv_store::{
+        merge_value,
+        BatchWriteResult,
         KVItem,
+        KeyItem,
         KeyValueStore,
         StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
         Value,
         WriteOperation,
     },
+    Error as StorageError,
     Result as StorageResult,
 };
 use itertools::{
@@ -35,15 +64,177 @@ use itertools::{
 };
 use std::{
     cmp::Ordering,
-    collections::HashMap,
+    collections::{
+        BTreeMap,
+        BTreeSet,
+        HashMap,
+    },
     fmt::Debug,
-    ops::DerefMut,
+    ops::Bound,
+    path::Path,
     sync::{
+        atomic::{
+            self,
+            AtomicU64,
+        },
         Arc,
         Mutex,
     },
 };
 
+/// The write of the `key` in the column with the operation.
+pub type WriteEntry<Column> = (Vec<u8>, Column, WriteOperation);
+
+/// The values of the data source observed by reads of the column.
+type ReadSet = Mutex<HashMap<Vec<u8>, Option<Value>>>;
+
+/// The removal of the key hidden by the range tombstone.
+static RANGE_REMOVED: WriteOperation = WriteOperation::Remove;
+
+/// The pending changes of the column.
+#[derive(Debug, Default, Clone)]
+struct Changes {
+    /// Changes of keys ordered by the key, so the view emits its writes in the stable order.
+    /// The map collapses changes of the same key, e.g. insert then remove.
+    points: BTreeMap<Vec<u8>, WriteOperation>,
+    /// Range tombstones `[start, end)` that hide keys of the data source without listing
+    /// them, where the `None` end is the end of the column. The range drops point changes
+    /// inside it when it is added, so point changes inside ranges are always newer and
+    /// take precedence over them.
+    ranges: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+    /// The usage of point changes.
+    usage: WriteUsage,
+}
+
+impl Changes {
+    /// Returns the change of the `key`, the removal if only the range tombstone covers it.
+    fn get(&self, key: &[u8]) -> Option<&WriteOperation> {
+        self.points.get(key).or_else(|| {
+            self.ranges
+                .iter()
+                .any(|(start, end)| {
+                    start.as_slice() <= key
+                        && !matches!(end, Some(end) if key >= end.as_slice())
+                })
+                .then_some(&RANGE_REMOVED)
+        })
+    }
+
+    fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    fn insert(&mut self, key: Vec<u8>, change: WriteOperation) {
+        self.usage = self
+            .usage
+            .saturating_sub(self.replaced_usage(&key))
+            .saturating_add(WriteUsage::of(&change));
+        self.points.insert(key, change);
+    }
+
+    /// Returns the usage of the point change of the `key` that the new change replaces.
+    fn replaced_usage(&self, key: &[u8]) -> WriteUsage {
+        self.points.get(key).map(WriteUsage::of).unwrap_or_default()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.points.is_empty() && self.ranges.is_empty()
+    }
+
+    /// Adds the range tombstone `[start, end)` over point changes inside it.
+    /// The `None` end removes everything from the `start` to the end of the column.
+    fn remove_range(&mut self, start: &[u8], end: Option<&[u8]>) {
+        let inside = self.points.split_off(start);
+        let inside = match end {
+            Some(end) => {
+                let mut inside = inside;
+                let mut after = inside.split_off(end);
+                self.points.append(&mut after);
+                inside
+            }
+            None => inside,
+        };
+        for change in inside.values() {
+            self.usage = self.usage.saturating_sub(WriteUsage::of(change));
+        }
+        self.ranges.push((start.to_vec(), end.map(<[u8]>::to_vec)));
+    }
+}
+
+/// The size of changes staged by the [`MemoryTransactionView`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WriteUsage {
+    /// The number of staged keys, including removed keys.
+    /// Range tombstones of [`TransactableStorage::delete_range`] aren't counted.
+    pub keys: usize,
+    /// The total size of staged values in bytes. Removals don't have values.
+    pub bytes: usize,
+}
+
+impl WriteUsage {
+    /// Returns the usage of the single change.
+    fn of(change: &WriteOperation) -> Self {
+        let bytes = match change {
+            WriteOperation::Insert(value) | WriteOperation::Merge(value) => value.len(),
+            WriteOperation::Remove => 0,
+        };
+        Self { keys: 1, bytes }
+    }
+
+    fn saturating_add(self, other: Self) -> Self {
+        Self {
+            keys: self.keys.saturating_add(other.keys),
+            bytes: self.bytes.saturating_add(other.bytes),
+        }
+    }
+
+    fn saturating_sub(self, other: Self) -> Self {
+        Self {
+            keys: self.keys.saturating_sub(other.keys),
+            bytes: self.bytes.saturating_sub(other.bytes),
+        }
+    }
+}
+
+/// Limits of changes staged by the [`MemoryTransactionView`], see
+/// [`MemoryTransactionView::with_limits`]. `None` means there is no limit.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WriteLimits {
+    /// The maximum number of staged keys, see [`WriteUsage::keys`].
+    pub max_keys: Option<usize>,
+    /// The maximum total size of staged values, see [`WriteUsage::bytes`].
+    pub max_bytes: Option<usize>,
+    /// The maximum size of the single value.
+    pub max_value_size: Option<usize>,
+}
+
+impl WriteLimits {
+    fn check(
+        kind: LimitKind,
+        limit: Option<usize>,
+        attempted: usize,
+    ) -> DatabaseResult<()> {
+        match limit {
+            Some(limit) if attempted > limit => Err(DatabaseError::LimitExceeded {
+                kind,
+                limit,
+                attempted,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_value(&self, change: &WriteOperation) -> DatabaseResult<()> {
+        let size = WriteUsage::of(change).bytes;
+        Self::check(LimitKind::ValueSize, self.max_value_size, size)
+    }
+
+    fn check_usage(&self, usage: WriteUsage) -> DatabaseResult<()> {
+        Self::check(LimitKind::Keys, self.max_keys, usage.keys)?;
+        Self::check(LimitKind::Bytes, self.max_bytes, usage.bytes)
+    }
+}
+
 #[derive(Debug)]
 pub struct MemoryTransactionView<Description = OnChain>
 where
@@ -51,8 +242,19 @@ where
 {
     view_layer: MemoryStore<Description>,
     // TODO: Remove `Mutex`.
-    // use hashmap to collapse changes (e.g. insert then remove the same key)
-    changes: Vec<Mutex<HashMap<Vec<u8>, WriteOperation>>>,
+    /// Iterators share the changes of the column at their creation, and the write
+    /// copies them only if some iterator still holds them, like the `view_layer`.
+    changes: Vec<Mutex<Arc<Changes>>>,
+    /// The values of the data source observed by reads of each column,
+    /// or `None` if the view doesn't detect conflicts.
+    reads: Option<Vec<ReadSet>>,
+    /// The number of writes into the data source when the view started to read or last
+    /// committed, see [`crate::state::CommitSequence`]. If no write happened since then,
+    /// the commit is valid without re-reading the observed values.
+    seen_commits: AtomicU64,
+    limits: WriteLimits,
+    /// The usage of all columns. It is locked after changes of columns.
+    usage: Mutex<WriteUsage>,
     data_source: DataSource<Description>,
 }
 
@@ -64,29 +266,704 @@ where
     where
         D: Into<DataSource<Description>>,
     {
-        use strum::EnumCount;
         Self {
             view_layer: MemoryStore::default(),
-            changes: (0..Description::Column::COUNT)
-                .map(|_| Mutex::new(HashMap::new()))
+            changes: (0..column_slots::<Description>())
+                .map(|_| Mutex::new(Arc::default()))
                 .collect(),
+            reads: None,
+            seen_commits: AtomicU64::default(),
+            limits: WriteLimits::default(),
+            usage: Mutex::default(),
             data_source: source.into(),
         }
     }
 
+    /// Sets `limits` of changes staged by the view. The write beyond them fails
+    /// immediately with [`DatabaseError::LimitExceeded`] and doesn't change the view.
+    /// The replaced change of the key is not counted twice.
+    pub fn with_limits(self, limits: WriteLimits) -> Self {
+        Self { limits, ..self }
+    }
+
+    /// Returns the size of changes staged by the view, e.g. for the metering of executors.
+    pub fn usage(&self) -> WriteUsage {
+        *self.usage.lock().expect("poisoned lock")
+    }
+
+    /// Inserts the `change` of the `key` into `changes` of the column,
+    /// if it keeps the view within its limits.
+    fn insert_change(
+        &self,
+        changes: &mut Arc<Changes>,
+        key: &[u8],
+        change: WriteOperation,
+    ) -> DatabaseResult<()> {
+        self.limits.check_value(&change)?;
+        let mut usage = self.usage.lock().expect("poisoned lock");
+        let attempted = usage
+            .saturating_sub(changes.replaced_usage(key))
+            .saturating_add(WriteUsage::of(&change));
+        self.limits.check_usage(attempted)?;
+        *usage = attempted;
+        Arc::make_mut(changes).insert(key.to_vec(), change);
+        Ok(())
+    }
+
+    /// Modifies `changes` of the column by the `f` and accounts the difference of their usage.
+    fn modify_changes<R>(
+        &self,
+        changes: &mut Arc<Changes>,
+        f: impl FnOnce(&mut Changes) -> R,
+    ) -> R {
+        let before = changes.usage;
+        let result = f(Arc::make_mut(changes));
+        let mut usage = self.usage.lock().expect("poisoned lock");
+        *usage = usage.saturating_sub(before).saturating_add(changes.usage);
+        result
+    }
+
+    /// Creates the view that detects conflicts with concurrent commits into the data source.
+    ///
+    /// The view records values of the data source observed by its reads, and the commit
+    /// fails with [`DatabaseError::Conflict`] if any of them changed since the read.
+    /// Iterators record the entries they yield, but not the absence of entries between
+    /// them. The validation and the write hold the lock of the data source, so no write
+    /// through the data source or its clones sneaks in between them. Writes that bypass
+    /// the data source, e.g. into the storage converted into another data source,
+    /// aren't seen by the validation.
+    pub fn with_conflict_detection<D>(source: D) -> Self
+    where
+        D: Into<DataSource<Description>>,
+    {
+        let view = Self::new(source);
+        let seen_commits = view.data_source.commits().current();
+        Self {
+            reads: Some(
+                (0..column_slots::<Description>())
+                    .map(|_| Mutex::new(HashMap::new()))
+                    .collect(),
+            ),
+            seen_commits: AtomicU64::new(seen_commits),
+            ..view
+        }
+    }
+
+    /// Commits the changes into the data source. If the commit fails,
+    /// the changes are kept, unless they were overwritten during the commit.
     pub fn commit(&self) -> StorageResult<()> {
-        let mut iter = self
+        self.commit_with(WriteDurability::Buffered)
+    }
+
+    /// Commits the changes into the data source with the `durability`.
+    /// Works in the same way as [`Self::commit`].
+    pub fn commit_with(&self, durability: WriteDurability) -> StorageResult<()> {
+        self.validated_write(|| self.write_changes(durability))
+    }
+
+    /// Works like [`Self::commit_with`], but returns the [`DatabaseError::Conflict`]
+    /// as it is, so the caller can match it and retry the transaction.
+    /// Errors of the data source are wrapped into the [`DatabaseError::Other`].
+    pub fn try_commit_with(&self, durability: WriteDurability) -> DatabaseResult<()> {
+        self.validated_write(|| {
+            self.write_changes(durability).map_err(DatabaseError::from)
+        })
+    }
+
+    /// Writes the changes into the data source after the validation of reads.
+    fn write_changes(&self, durability: WriteDurability) -> StorageResult<()> {
+        let writes = self.collect_writes(core::mem::take)?;
+        let mut stats = BatchWriteResult::default();
+        for (key, _, op) in &writes {
+            stats.record(key, op);
+        }
+        let result = self
+            .data_source
+            .batch_write_with(&mut writes.clone().into_iter(), durability);
+        if result.is_ok() {
+            tracing::debug!(
+                database = Description::name(),
+                inserted = stats.inserted,
+                removed = stats.removed,
+                bytes_written = stats.bytes_written,
+                "Committed the transaction"
+            );
+            self.clear_reads();
+        } else {
+            for (key, column, op) in writes {
+                let mut changes = self.changes[column.as_usize()]
+                    .lock()
+                    .expect("poisoned lock");
+                if !changes.points.contains_key(&key) {
+                    self.modify_changes(&mut changes, |changes| changes.insert(key, op));
+                }
+            }
+        }
+        result
+    }
+
+    /// Commits the changes into the data source by chunks within the `limits`, see
+    /// [`chunked_batch_write`]. The changes are taken from the view column by column,
+    /// so they are never copied into one batch.
+    ///
+    /// Unlike [`Self::commit`], the commit isn't atomic. If a chunk fails, the changes
+    /// of the view are dropped, while the previous chunks stay in the data source.
+    pub fn commit_chunked(&self, limits: ChunkLimits) -> StorageResult<u64> {
+        self.validated_write(|| self.write_chunks(limits))
+    }
+
+    /// Writes the changes into the data source by chunks after the validation of reads.
+    fn write_chunks(&self, limits: ChunkLimits) -> StorageResult<u64> {
+        // Range tombstones become removals before the first chunk is written,
+        // so the failed expansion leaves the view as it is.
+        for (column_map, column) in self
+            .changes
+            .iter()
+            .zip(enum_iterator::all::<Description::Column>())
+        {
+            let mut lock = column_map.lock().expect("poisoned lock");
+            let removed = self.range_removed_keys(&lock, column)?;
+            self.modify_changes(&mut lock, |changes| {
+                for key in removed {
+                    changes.insert(key, WriteOperation::Remove);
+                }
+                changes.ranges.clear();
+            });
+        }
+        self.clear_reads();
+        let writes = self
             .changes
             .iter()
             .zip(enum_iterator::all::<Description::Column>())
             .flat_map(|(column_map, column)| {
-                let mut map = column_map.lock().expect("poisoned lock");
-                let changes = core::mem::take(map.deref_mut());
-
-                changes.into_iter().map(move |t| (t.0, column, t.1))
+                let changes = self
+                    .modify_changes(
+                        &mut column_map.lock().expect("poisoned lock"),
+                        core::mem::take,
+                    )
+                    .points;
+                changes.into_iter().map(move |(key, op)| (key, column, op))
             });
+        chunked_batch_write::<Description>(&**self.data_source, writes, limits)
+    }
+
+    /// Returns the changes that [`Self::commit`] will apply to the data source,
+    /// without committing them. Writes are ordered by the index of the column and
+    /// then by the key, and each key has only one write, with the last change winning.
+    /// The commit hands writes to the data source in the same order.
+    ///
+    /// Range tombstones are expanded into removals of the keys of the data source inside
+    /// them, so it fails if the data source can't be iterated.
+    pub fn pending_writes(&self) -> StorageResult<Vec<WriteEntry<Description::Column>>> {
+        self.collect_writes(|map| map.clone())
+    }
+
+    /// Returns the writes that revert the pending changes of the view,
+    /// i.e., restore the current values of the data source.
+    pub fn undo_writes(&self) -> StorageResult<Vec<WriteEntry<Description::Column>>> {
+        self.pending_writes()?
+            .into_iter()
+            .map(|(key, column, _)| {
+                let undo = match self.data_source.get(&key, column)? {
+                    Some(value) => WriteOperation::Insert(value),
+                    None => WriteOperation::Remove,
+                };
+                Ok((key, column, undo))
+            })
+            .collect()
+    }
+
+    /// Runs the `write` of the changes as the write of the data source. If the view
+    /// detects conflicts, no other write of the data source runs between the validation
+    /// of reads and the `write`.
+    fn validated_write<T, E>(&self, write: impl FnOnce() -> Result<T, E>) -> Result<T, E>
+    where
+        E: From<DatabaseError>,
+    {
+        let commits = self.data_source.commits();
+        if self.reads.is_none() {
+            return commits.write(write)
+        }
+        let commit = commits.exclusive();
+        self.validate_reads(commit.current())?;
+        let (result, seq) = commit.write(write);
+        if result.is_ok() {
+            // Only the write of the view itself is counted since the validation.
+            self.seen_commits.store(seq, atomic::Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// Checks that values observed by reads of the view are still in the data source,
+    /// where `seq` is the number of writes of the data source done so far.
+    fn validate_reads(&self, seq: u64) -> DatabaseResult<()> {
+        let Some(reads) = &self.reads else {
+            return Ok(())
+        };
+        if self.seen_commits.load(atomic::Ordering::Relaxed) == seq {
+            return Ok(())
+        }
+        for (column_reads, column) in reads
+            .iter()
+            .zip(enum_iterator::all::<Description::Column>())
+        {
+            for (key, observed) in column_reads.lock().expect("poisoned lock").iter() {
+                let current = self
+                    .data_source
+                    .get(key, column)
+                    .map_err(DatabaseError::from)?;
+                if current != *observed {
+                    return Err(DatabaseError::Conflict {
+                        column: column.name(),
+                        key: key.clone(),
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Forgets observed values after the commit, because the view's own writes change them.
+    fn clear_reads(&self) {
+        for column_reads in self.reads.iter().flatten() {
+            column_reads.lock().expect("poisoned lock").clear();
+        }
+    }
+
+    /// Records the `value` of the data source observed by the read of the `key`.
+    /// The first observation is kept, because the commit must see the same value.
+    fn record_read(
+        &self,
+        key: &[u8],
+        column: Description::Column,
+        value: &Option<Value>,
+    ) {
+        if let Some(reads) = &self.reads {
+            reads[column.as_usize()]
+                .lock()
+                .expect("poisoned lock")
+                .entry(key.to_vec())
+                .or_insert_with(|| value.clone());
+        }
+    }
+
+    /// Reads the `key` from the data source and records the observed value.
+    fn read_source(
+        &self,
+        key: &[u8],
+        column: Description::Column,
+    ) -> StorageResult<Option<Value>> {
+        let value = self.data_source.get(key, column)?;
+        self.record_read(key, column, &value);
+        Ok(value)
+    }
+
+    /// Records the point read of the `key` into the installed read context, see
+    /// [`read_context`]. The read is served by the view if it has the change of the key.
+    fn record_view_read(&self, key: &[u8], column: Description::Column) {
+        read_context::record(column, |counters| {
+            let overlay = self.changes[column.as_usize()]
+                .lock()
+                .expect("poisoned lock")
+                .contains_key(key);
+            counters.view_read(overlay)
+        })
+    }
+
+    /// Returns the columns modified by the view.
+    pub fn changed_columns(&self) -> BTreeSet<Description::Column> {
+        self.changes
+            .iter()
+            .zip(enum_iterator::all::<Description::Column>())
+            .filter(|(column_map, _)| {
+                !column_map.lock().expect("poisoned lock").is_empty()
+            })
+            .map(|(_, column)| column)
+            .collect()
+    }
+
+    /// Returns the current value of the `key`, taking the `changes` of the `column` into account.
+    fn current_value(
+        &self,
+        changes: &Changes,
+        key: &[u8],
+        column: Description::Column,
+    ) -> StorageResult<Option<Value>> {
+        match changes.get(key) {
+            Some(WriteOperation::Merge(operand)) => {
+                self.resolve_merge(key, column, operand).map(Some)
+            }
+            Some(_) => self.view_layer.get(key, column),
+            None => self.read_source(key, column),
+        }
+    }
+
+    /// Returns the pending merge operand of the `key`, if the view only merges into it.
+    fn pending_merge(&self, key: &[u8], column: Description::Column) -> Option<Value> {
+        match self.changes[column.as_usize()]
+            .lock()
+            .expect("poisoned lock")
+            .get(key)
+        {
+            Some(WriteOperation::Merge(operand)) => Some(operand.clone()),
+            _ => None,
+        }
+    }
+
+    /// Applies the pending merge `operand` to the value of the data source.
+    fn resolve_merge(
+        &self,
+        key: &[u8],
+        column: Description::Column,
+        operand: &[u8],
+    ) -> StorageResult<Value> {
+        let existing = self.read_source(key, column)?;
+        merge_value(column, existing.as_deref().map(Vec::as_slice), operand)
+    }
+
+    /// Returns the change of the key after merging the `operand` into its current `change`.
+    /// The merge into the value unknown to the view stays pending until it is read or committed.
+    fn merged_change(
+        column: Description::Column,
+        change: Option<&WriteOperation>,
+        operand: Value,
+    ) -> StorageResult<WriteOperation> {
+        let change = match change {
+            None => {
+                // Validate the operand before deferring the merge.
+                merge_value(column, None, &operand)?;
+                WriteOperation::Merge(operand)
+            }
+            // Merge operators are associative, so pending operands are combined.
+            Some(WriteOperation::Merge(pending)) => {
+                WriteOperation::Merge(merge_value(column, Some(pending), &operand)?)
+            }
+            Some(WriteOperation::Insert(value)) => {
+                WriteOperation::Insert(merge_value(column, Some(value), &operand)?)
+            }
+            Some(WriteOperation::Remove) => {
+                WriteOperation::Insert(merge_value(column, None, &operand)?)
+            }
+        };
+        Ok(change)
+    }
+
+    /// Stages the `change` of the `key` in the view layer.
+    fn stage(
+        &self,
+        key: &[u8],
+        column: Description::Column,
+        change: &WriteOperation,
+    ) -> StorageResult<()> {
+        match change {
+            WriteOperation::Insert(value) => {
+                self.view_layer.put(key, column, value.clone())
+            }
+            WriteOperation::Remove => self.view_layer.delete(key, column),
+            // The pending operand keeps the key visible to iterators of the view layer.
+            WriteOperation::Merge(operand) => {
+                self.view_layer.put(key, column, operand.clone())
+            }
+        }
+    }
+
+    /// Returns keys of the data source hidden by range tombstones of the `changes`
+    /// and not changed by the view, in the order of keys.
+    fn range_removed_keys(
+        &self,
+        changes: &Changes,
+        column: Description::Column,
+    ) -> StorageResult<BTreeSet<Vec<u8>>> {
+        let mut keys = BTreeSet::new();
+        for (start, end) in &changes.ranges {
+            for key in self.data_source.iter_keys(
+                column,
+                None,
+                Some(start),
+                IterDirection::Forward,
+            ) {
+                let key = key?;
+                if end.as_ref().is_some_and(|end| key >= *end) {
+                    break
+                }
+                if !changes.points.contains_key(&key) {
+                    keys.insert(key);
+                }
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Collects the changes of all columns, extracted by the `extract`, into a sorted list of writes.
+    /// The locks of all columns are held together, so the result is a consistent snapshot of the view.
+    /// Range tombstones are expanded before the extraction, so the failed expansion keeps changes.
+    fn collect_writes(
+        &self,
+        extract: impl Fn(&mut Changes) -> Changes,
+    ) -> StorageResult<Vec<WriteEntry<Description::Column>>> {
+        let mut locks: Vec<_> = self
+            .changes
+            .iter()
+            .map(|column_map| column_map.lock().expect("poisoned lock"))
+            .collect();
+        let range_removed = locks
+            .iter()
+            .zip(enum_iterator::all::<Description::Column>())
+            .map(|(map, column)| self.range_removed_keys(map, column))
+            .collect::<StorageResult<Vec<_>>>()?;
+
+        Ok(locks
+            .iter_mut()
+            .zip(enum_iterator::all::<Description::Column>())
+            .zip(range_removed)
+            .flat_map(|((map, column), range_removed)| {
+                let mut points = self.modify_changes(map, &extract).points;
+                points.extend(
+                    range_removed
+                        .into_iter()
+                        .map(|key| (key, WriteOperation::Remove)),
+                );
+                points.into_iter().map(move |(key, op)| (key, column, op))
+            })
+            .collect())
+    }
+
+    /// Creates iterators of both layers by the `layers` under the lock of changes
+    /// of the `column`, and returns them with these changes. Writes into the view hold
+    /// the lock until both the changes and the view layer are updated, so the iteration
+    /// sees the state of the view at its creation.
+    fn capture_layers<V, S>(
+        &self,
+        column: Description::Column,
+        layers: impl FnOnce() -> (V, S),
+    ) -> (Arc<Changes>, V, S) {
+        let changes = self.changes[column.as_usize()]
+            .lock()
+            .expect("poisoned lock");
+        let (view, data_source) = layers();
+        (Arc::clone(&changes), view, data_source)
+    }
+
+    /// Merges two sorted iterators (our current view overlay + backing data source)
+    /// while also filtering deleted entries.
+    /// Merges entries of the layers and replaces the pending merge operands
+    /// with the merged values. The operand is merged into the value yielded by
+    /// the data source, so it is never read after the creation of the iterator.
+    fn merge_entries<'a>(
+        &'a self,
+        column: Description::Column,
+        direction: IterDirection,
+        changes: Arc<Changes>,
+        view: BoxedIter<'a, KVItem>,
+        data_source: BoxedIter<'a, KVItem>,
+    ) -> BoxedIter<'a, KVItem> {
+        let data_source = match self.reads {
+            Some(_) => data_source
+                .inspect(move |item| {
+                    if let Ok((key, value)) = item {
+                        self.record_read(key, column, &Some(value.clone()));
+                    }
+                })
+                .into_boxed(),
+            None => data_source,
+        };
+        self.merge_layers(
+            direction,
+            changes,
+            view,
+            data_source,
+            |(key, _)| key,
+            move |changes, (key, value), source| {
+                let Some(WriteOperation::Merge(operand)) = changes.get(&key) else {
+                    return Ok((key, value))
+                };
+                // Both layers iterate over the same range,
+                // so the key is missing in the data source if it didn't yield it.
+                let existing = match source {
+                    Some((_, existing)) => Some(existing),
+                    None => {
+                        self.record_read(&key, column, &None);
+                        None
+                    }
+                };
+                let value =
+                    merge_value(column, existing.as_deref().map(Vec::as_slice), operand)?;
+                Ok((key, value))
+            },
+        )
+    }
+
+    /// Merges layers with the `changes` captured at the creation of iterators.
+    /// The `resolve` receives the entry of the view and the entry of the data source
+    /// with the same key, if any.
+    fn merge_layers<'a, T>(
+        &'a self,
+        direction: IterDirection,
+        changes: Arc<Changes>,
+        view: BoxedIter<'a, StorageResult<T>>,
+        data_source: BoxedIter<'a, StorageResult<T>>,
+        key: fn(&T) -> &Vec<u8>,
+        resolve: impl Fn(&Changes, T, Option<T>) -> StorageResult<T> + 'a,
+    ) -> BoxedIter<'a, StorageResult<T>>
+    where
+        T: 'a,
+    {
+        let removed = Arc::clone(&changes);
+        view.merge_join_by(data_source, move |i, j| {
+            if let (Ok(i), Ok(j)) = (i, j) {
+                if IterDirection::Forward == direction {
+                    key(i).cmp(key(j))
+                } else {
+                    key(j).cmp(key(i))
+                }
+            } else {
+                // prioritize errors from db result first
+                if j.is_err() {
+                    Ordering::Greater
+                } else {
+                    Ordering::Less
+                }
+            }
+        })
+        .map(move |either_both| {
+            match either_both {
+                // in the case of overlap, choose the left-side (our view overlay)
+                EitherOrBoth::Both(Ok(v), Ok(s)) => resolve(&changes, v, Some(s)),
+                EitherOrBoth::Left(Ok(v)) => resolve(&changes, v, None),
+                EitherOrBoth::Both(v, _)
+                | EitherOrBoth::Left(v)
+                | EitherOrBoth::Right(v) => v,
+            }
+        })
+        // filter entries which have been deleted over the course of this transaction
+        .filter(move |item| {
+            if let Ok(item) = item {
+                !matches!(removed.get(key(item)), Some(WriteOperation::Remove))
+            } else {
+                // ensure errors are propagated
+                true
+            }
+        })
+        .into_boxed()
+    }
+}
+
+/// The layers of the [`ViewColumnIter`] that hold the current entry.
+#[derive(Debug, Clone, Copy)]
+enum Layer {
+    View,
+    DataSource,
+    Both,
+}
+
+/// The lending iterator over the column of the [`MemoryTransactionView`], see [`ColumnIter`].
+/// It merges the view layer with the data source in the same way as the `merge_layers`.
+struct ViewColumnIter<'a, Description>
+where
+    Description: DatabaseDescription,
+{
+    /// The changes of the column at the creation of the iterator.
+    changes: Arc<Changes>,
+    column: Description::Column,
+    direction: IterDirection,
+    view_layer: MemoryColumnIter,
+    data_source: ColumnIter<'a>,
+    started: bool,
+    current: Option<Layer>,
+    /// The value of the current entry with the resolved pending merge.
+    merged: Option<Value>,
+}
+
+impl<'a, Description> ViewColumnIter<'a, Description>
+where
+    Description: DatabaseDescription,
+{
+    fn is_removed(&self, key: &[u8]) -> bool {
+        matches!(self.changes.get(key), Some(WriteOperation::Remove))
+    }
+
+    fn select_layer(&self) -> Option<Layer> {
+        match (self.view_layer.current(), self.data_source.current()) {
+            (None, None) => None,
+            (Some(_), None) => Some(Layer::View),
+            (None, Some(_)) => Some(Layer::DataSource),
+            (Some((view_key, _)), Some((source_key, _))) => {
+                let ordering = match self.direction {
+                    IterDirection::Forward => view_key.cmp(source_key),
+                    IterDirection::Reverse => source_key.cmp(view_key),
+                };
+                Some(match ordering {
+                    Ordering::Less => Layer::View,
+                    Ordering::Greater => Layer::DataSource,
+                    Ordering::Equal => Layer::Both,
+                })
+            }
+        }
+    }
+}
+
+impl<'a, Description> RawColumnIter for ViewColumnIter<'a, Description>
+where
+    Description: DatabaseDescription,
+{
+    fn advance(&mut self) -> StorageResult<()> {
+        match (self.started, self.current) {
+            (false, _) => {
+                self.started = true;
+                self.view_layer.advance()?;
+                self.data_source.advance()?;
+            }
+            (true, Some(Layer::View)) => self.view_layer.advance()?,
+            (true, Some(Layer::DataSource)) => self.data_source.advance()?,
+            (true, Some(Layer::Both)) => {
+                self.view_layer.advance()?;
+                self.data_source.advance()?;
+            }
+            (true, None) => return Ok(()),
+        }
+
+        loop {
+            self.current = self.select_layer();
+            // The removed keys are absent in the view layer,
+            // so only keys of the data source are hidden by tombstones.
+            let removed = matches!(self.current, Some(Layer::DataSource))
+                && self
+                    .data_source
+                    .current()
+                    .map_or(false, |(key, _)| self.is_removed(key));
+            if !removed {
+                break
+            }
+            self.data_source.advance()?;
+        }
+
+        // Replace the pending merge operand with the merged value.
+        // The operand is merged into the current entry of the data source.
+        self.merged = None;
+        if let Some(layer @ (Layer::View | Layer::Both)) = self.current {
+            if let Some((key, _)) = self.view_layer.current() {
+                if let Some(WriteOperation::Merge(operand)) = self.changes.get(key) {
+                    let existing = match layer {
+                        Layer::Both => self.data_source.current().map(|(_, value)| value),
+                        _ => None,
+                    };
+                    self.merged = Some(merge_value(self.column, existing, operand)?);
+                }
+            }
+        }
+        Ok(())
+    }
 
-        self.data_source.batch_write(&mut iter)
+    fn current(&self) -> Option<(&[u8], &[u8])> {
+        let (key, value) = match self.current? {
+            Layer::View | Layer::Both => self.view_layer.current()?,
+            Layer::DataSource => self.data_source.current()?,
+        };
+        let value = self
+            .merged
+            .as_ref()
+            .map_or(value, |merged| merged.as_slice());
+        Some((key, value))
     }
 }
 
@@ -96,24 +973,30 @@ where
 {
     type Column = Description::Column;
 
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        // The change and the view layer share the allocation of the value.
+        // The lock is held until the view layer is written, so iterators see both or none.
+        let mut changes = self.changes[column.as_usize()]
+            .lock()
+            .expect("poisoned lock");
+        self.insert_change(&mut changes, key, WriteOperation::Insert(value.clone()))?;
+        self.view_layer.put(key, column, value)
+    }
+
     fn replace(
         &self,
         key: &[u8],
         column: Self::Column,
         value: Value,
     ) -> StorageResult<Option<Value>> {
-        let key_vec = key.to_vec();
-        let contained_key = self.changes[column.as_usize()]
+        // Hold the lock until the end to make the operation atomic.
+        let mut changes = self.changes[column.as_usize()]
             .lock()
-            .expect("poisoned lock")
-            .insert(key_vec, WriteOperation::Insert(value.clone()))
-            .is_some();
-        let res = self.view_layer.replace(key, column, value);
-        if contained_key {
-            res
-        } else {
-            self.data_source.get(key, column)
-        }
+            .expect("poisoned lock");
+        let old_value = self.current_value(&changes, key, column)?;
+        self.insert_change(&mut changes, key, WriteOperation::Insert(value.clone()))?;
+        self.view_layer.put(key, column, value)?;
+        Ok(old_value)
     }
 
     fn write(
@@ -122,51 +1005,84 @@ where
         column: Self::Column,
         buf: &[u8],
     ) -> StorageResult<usize> {
-        let k = key.to_vec();
-        self.changes[column.as_usize()]
+        let mut changes = self.changes[column.as_usize()]
             .lock()
-            .expect("poisoned lock")
-            .insert(k, WriteOperation::Insert(Arc::new(buf.to_vec())));
+            .expect("poisoned lock");
+        self.insert_change(
+            &mut changes,
+            key,
+            WriteOperation::Insert(Arc::new(buf.to_vec())),
+        )?;
         self.view_layer.write(key, column, buf)
     }
 
     fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
-        let k = key.to_vec();
-        let contained_key = {
-            let mut lock = self.changes[column.as_usize()]
-                .lock()
-                .expect("poisoned lock");
-            lock.insert(k, WriteOperation::Remove).is_some()
-        };
-        let res = self.view_layer.take(key, column);
-        if contained_key {
-            res
-        } else {
-            self.data_source.get(key, column)
-        }
+        // Hold the lock until the end to make the operation atomic.
+        let mut changes = self.changes[column.as_usize()]
+            .lock()
+            .expect("poisoned lock");
+        // The key may only exist in the data source, so the tombstone hides it.
+        let old_value = self.current_value(&changes, key, column)?;
+        self.insert_change(&mut changes, key, WriteOperation::Remove)?;
+        self.view_layer.delete(key, column)?;
+        Ok(old_value)
     }
 
     fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
-        let k = key.to_vec();
-        self.changes[column.as_usize()]
+        let mut changes = self.changes[column.as_usize()]
             .lock()
-            .expect("poisoned lock")
-            .insert(k, WriteOperation::Remove);
+            .expect("poisoned lock");
+        self.insert_change(&mut changes, key, WriteOperation::Remove)?;
         self.view_layer.delete(key, column)
     }
 
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        let mut changes = self.changes[column.as_usize()]
+            .lock()
+            .expect("poisoned lock");
+        let change = Self::merged_change(column, changes.get(key), operand)?;
+        self.insert_change(&mut changes, key, change.clone())?;
+        self.stage(key, column, &change)
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        self.record_view_read(key, column);
+        let changes = self.changes[column.as_usize()]
+            .lock()
+            .expect("poisoned lock");
+        match changes.get(key) {
+            // The merge always produces the value.
+            Some(WriteOperation::Merge(_)) => Ok(true),
+            // The removed key is absent in the view layer, so the tombstone hides the data source.
+            Some(_) => self.view_layer.exists(key, column),
+            None if self.reads.is_some() => Ok(self.read_source(key, column)?.is_some()),
+            None => self.data_source.exists(key, column),
+        }
+    }
+
     fn size_of_value(
         &self,
         key: &[u8],
         column: Self::Column,
     ) -> StorageResult<Option<usize>> {
+        self.record_view_read(key, column);
+        if let Some(operand) = self.pending_merge(key, column) {
+            return Ok(Some(self.resolve_merge(key, column, &operand)?.len()))
+        }
         // try to fetch data from View layer if any changes to the key
         if self.changes[column.as_usize()]
             .lock()
             .expect("poisoned lock")
-            .contains_key(&key.to_vec())
+            .contains_key(key)
         {
             self.view_layer.size_of_value(key, column)
+        } else if self.reads.is_some() {
+            Ok(self.read_source(key, column)?.map(|value| value.len()))
         } else {
             // fall-through to original data source
             // Note: The getting size from original database may be more performant than from `get`
@@ -175,44 +1091,202 @@ where
     }
 
     fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
-        // try to fetch data from View layer if any changes to the key
-        if self.changes[column.as_usize()]
+        self.record_view_read(key, column);
+        let changes = self.changes[column.as_usize()]
             .lock()
-            .expect("poisoned lock")
-            .contains_key(&key.to_vec())
-        {
-            self.view_layer.get(key, column)
-        } else {
-            // fall-through to original data source
-            self.data_source.get(key, column)
-        }
+            .expect("poisoned lock");
+        // try to fetch data from View layer if any changes to the key,
+        // otherwise fall-through to original data source
+        self.current_value(&changes, key, column)
     }
 
-    fn read(
+    fn compare_and_swap(
         &self,
         key: &[u8],
         column: Self::Column,
-        buf: &mut [u8],
-    ) -> StorageResult<Option<usize>> {
-        // try to fetch data from View layer if any changes to the key
-        if self.changes[column.as_usize()]
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        // Hold the lock until the end to make the operation atomic.
+        let mut changes = self.changes[column.as_usize()]
             .lock()
-            .expect("poisoned lock")
-            .contains_key(&key.to_vec())
-        {
-            self.view_layer.read(key, column, buf)
-        } else {
-            // fall-through to original data source
-            // Note: The read from original database may be more performant than from `get`
-            self.data_source.read(key, column, buf)
+            .expect("poisoned lock");
+        let current = self.current_value(&changes, key, column)?;
+        if current.as_deref().map(Vec::as_slice) != expected {
+            return Ok(Err(current))
         }
+        self.insert_change(&mut changes, key, WriteOperation::Insert(new.clone()))?;
+        self.view_layer.put(key, column, new)?;
+        Ok(Ok(()))
     }
-}
 
-impl<Description> IteratorableStore for MemoryTransactionView<Description>
-where
-    Description: DatabaseDescription,
-{
+    fn get_or_insert_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        init: &mut dyn FnMut() -> Vec<u8>,
+    ) -> StorageResult<Value> {
+        // Hold the lock until the end to make the operation atomic.
+        let mut changes = self.changes[column.as_usize()]
+            .lock()
+            .expect("poisoned lock");
+        if let Some(value) = self.current_value(&changes, key, column)? {
+            return Ok(value)
+        }
+        let value = Value::new(init());
+        self.insert_change(&mut changes, key, WriteOperation::Insert(value.clone()))?;
+        self.view_layer.put(key, column, value.clone())?;
+        Ok(value)
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        // Hold the lock until the end to make the operation atomic.
+        let mut changes = self.changes[column.as_usize()]
+            .lock()
+            .expect("poisoned lock");
+        let current = self.current_value(&changes, key, column)?;
+        let current = current.as_deref().map(Vec::as_slice);
+        let new = f(current);
+        let outcome = UpdateOutcome::of(current, new.as_deref());
+        match new {
+            _ if !outcome.is_changed() => {}
+            Some(new) => {
+                let value = Value::new(new);
+                self.insert_change(
+                    &mut changes,
+                    key,
+                    WriteOperation::Insert(value.clone()),
+                )?;
+                self.view_layer.put(key, column, value)?;
+            }
+            None => {
+                self.insert_change(&mut changes, key, WriteOperation::Remove)?;
+                self.view_layer.delete(key, column)?;
+            }
+        }
+        Ok(outcome)
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        for key in keys {
+            self.record_view_read(key, column);
+        }
+        let mut values = Vec::with_capacity(keys.len());
+        let mut missed_indexes = vec![];
+        let mut missed_keys = vec![];
+        {
+            let changes = self.changes[column.as_usize()]
+                .lock()
+                .expect("poisoned lock");
+            for (index, key) in keys.iter().enumerate() {
+                // try to fetch data from View layer if any changes to the key
+                if changes.contains_key(key) {
+                    values.push(self.current_value(&changes, key, column)?);
+                } else {
+                    values.push(None);
+                    missed_indexes.push(index);
+                    missed_keys.push(key.clone());
+                }
+            }
+        }
+
+        if !missed_keys.is_empty() {
+            // fall-through to original data source for the keys without changes
+            let missed_values = self.data_source.multi_get(&missed_keys, column)?;
+            for (index, value) in missed_indexes.into_iter().zip(missed_values) {
+                self.record_read(&keys[index], column, &value);
+                values[index] = value;
+            }
+        }
+
+        Ok(values)
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        self.record_view_read(key, column);
+        if let Some(operand) = self.pending_merge(key, column) {
+            f(self.resolve_merge(key, column, &operand)?.as_slice());
+            return Ok(true)
+        }
+        // try to fetch data from View layer if any changes to the key
+        if self.changes[column.as_usize()]
+            .lock()
+            .expect("poisoned lock")
+            .contains_key(key)
+        {
+            self.view_layer.get_with(key, column, f)
+        } else if self.reads.is_some() {
+            Ok(self
+                .read_source(key, column)?
+                .map(|value| f(&value))
+                .is_some())
+        } else {
+            // fall-through to original data source
+            self.data_source.get_with(key, column, f)
+        }
+    }
+
+    fn read(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &mut [u8],
+    ) -> StorageResult<Option<usize>> {
+        self.record_view_read(key, column);
+        if let Some(operand) = self.pending_merge(key, column) {
+            let value = self.resolve_merge(key, column, &operand)?;
+            if value.len() != buf.len() {
+                return Err(StorageError::Other(anyhow::anyhow!(
+                    "Buffer size is not equal to the value size"
+                )))
+            }
+            buf.copy_from_slice(value.as_slice());
+            return Ok(Some(value.len()))
+        }
+        // try to fetch data from View layer if any changes to the key
+        if self.changes[column.as_usize()]
+            .lock()
+            .expect("poisoned lock")
+            .contains_key(key)
+        {
+            self.view_layer.read(key, column, buf)
+        } else if self.reads.is_some() {
+            let Some(value) = self.read_source(key, column)? else {
+                return Ok(None)
+            };
+            if value.len() != buf.len() {
+                return Err(StorageError::Other(anyhow::anyhow!(
+                    "Buffer size is not equal to the value size"
+                )))
+            }
+            buf.copy_from_slice(value.as_slice());
+            Ok(Some(value.len()))
+        } else {
+            // fall-through to original data source
+            // Note: The read from original database may be more performant than from `get`
+            self.data_source.read(key, column, buf)
+        }
+    }
+}
+
+impl<Description> IteratorableStore for MemoryTransactionView<Description>
+where
+    Description: DatabaseDescription,
+{
     fn iter_all(
         &self,
         column: Self::Column,
@@ -220,59 +1294,189 @@ where
         start: Option<&[u8]>,
         direction: IterDirection,
     ) -> BoxedIter<KVItem> {
-        // iterate over inmemory + db while also filtering deleted entries
-        self.view_layer
-                // iter_all returns items in sorted order
+        self.iter_range(column, prefix, start, None, direction)
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        // Both layers yield nothing, but the data source may be slow to find it out.
+        if !is_start_within_prefix(prefix, start) {
+            return core::iter::empty().into_boxed()
+        }
+        let (changes, view, data_source) = self.capture_layers(column, || {
+            (
+                // iter_range returns items in sorted order
+                self.view_layer
+                    .iter_range(column, prefix, start, end, direction)
+                    .into_boxed(),
+                self.data_source
+                    .iter_range(column, prefix, start, end, direction),
+            )
+        });
+        self.merge_entries(column, direction, changes, view, data_source)
+    }
+
+    /// Both layers seek past the excluded start, and the limit is applied after the merge,
+    /// because entries of the data source may be removed by the view.
+    fn iter(&self, column: Self::Column, config: IterConfig) -> PageIter {
+        if config.limit == Some(0)
+            || !is_start_within_prefix(config.prefix, config.start_key())
+        {
+            return PageIter::new(core::iter::empty().into_boxed())
+        }
+        let layer_config = IterConfig {
+            limit: None,
+            ..config
+        };
+        let (changes, view, data_source) = self.capture_layers(column, || {
+            (
+                self.view_layer.iter(column, layer_config).into_boxed(),
+                self.data_source.iter(column, layer_config).into_boxed(),
+            )
+        });
+        let iter =
+            self.merge_entries(column, config.direction, changes, view, data_source);
+        match config.limit {
+            Some(limit) => PageIter::new(iter.take(limit).into_boxed()),
+            None => PageIter::new(iter),
+        }
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        if !is_start_within_prefix(prefix, start) {
+            return core::iter::empty().into_boxed()
+        }
+        if self.reads.is_some() {
+            // Keys of the data source are recorded with their values.
+            return self
                 .iter_all(column, prefix, start, direction)
-                // Merge two sorted iterators (our current view overlay + backing data source)
-                .merge_join_by(
-                    self.data_source.iter_all(column, prefix, start, direction),
-                    move |i, j| {
-                        if let (Ok(i), Ok(j)) = (i, j) {
-                            if IterDirection::Forward == direction {
-                                i.0.cmp(&j.0)
-                            } else {
-                                j.0.cmp(&i.0)
-                            }
-                        } else {
-                            // prioritize errors from db result first
-                            if j.is_err() {
-                                Ordering::Greater
-                            } else {
-                                Ordering::Less
-                            }
-                        }
-                    },
-                )
-                .map(|either_both| {
-                    match either_both {
-                        // in the case of overlap, choose the left-side (our view overlay)
-                        EitherOrBoth::Both(v, _)
-                        | EitherOrBoth::Left(v)
-                        | EitherOrBoth::Right(v) => v,
-                    }
-                })
-                // filter entries which have been deleted over the course of this transaction
-                .filter(move |item| {
-                    if let Ok((key, _)) = item {
-                        !matches!(
-                            self.changes[column.as_usize()]
-                                .lock()
-                                .expect("poisoned")
-                                .get(key),
-                            Some(WriteOperation::Remove)
-                        )
-                    } else {
-                        // ensure errors are propagated
-                        true
-                    }
-                }).into_boxed()
+                .map(|item| item.map(|(key, _)| key))
+                .into_boxed()
+        }
+        let (changes, view, data_source) = self.capture_layers(column, || {
+            (
+                self.view_layer
+                    .iter_keys(column, prefix, start, direction)
+                    .into_boxed(),
+                self.data_source.iter_keys(column, prefix, start, direction),
+            )
+        });
+        self.merge_layers(
+            direction,
+            changes,
+            view,
+            data_source,
+            |key| key,
+            |_, key, _| Ok(key),
+        )
+    }
+
+    /// Inserts and merges of the view under the `prefix` are enough, otherwise
+    /// the keys of the data source are checked until one of them isn't removed by the view.
+    fn has_any(&self, column: Self::Column, prefix: &[u8]) -> StorageResult<bool> {
+        if self.reads.is_some() {
+            // The found key of the data source is recorded with its value.
+            let prefix = Some(prefix).filter(|prefix| !prefix.is_empty());
+            return Ok(self
+                .iter_all(column, prefix, None, IterDirection::Forward)
+                .next()
+                .transpose()?
+                .is_some())
+        }
+        let changes = self.changes[column.as_usize()]
+            .lock()
+            .expect("poisoned lock");
+        let written = changes
+            .points
+            .range::<[u8], _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .any(|(_, change)| !matches!(change, WriteOperation::Remove));
+        if written {
+            return Ok(true)
+        }
+        let source_prefix = Some(prefix).filter(|prefix| !prefix.is_empty());
+        for key in self.data_source.iter_keys(
+            column,
+            source_prefix,
+            None,
+            IterDirection::Forward,
+        ) {
+            if !changes.contains_key(&key?) {
+                return Ok(true)
+            }
+        }
+        Ok(false)
     }
 }
 
-impl<Description> BatchOperations for MemoryTransactionView<Description> where
-    Description: DatabaseDescription
+impl<Description> BatchOperations for MemoryTransactionView<Description>
+where
+    Description: DatabaseDescription,
 {
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        // Stage the whole batch before applying it, e.g. when a child view is committed.
+        let entries: Vec<_> = entries.collect();
+
+        // Hold locks of all columns, so readers see either none or all of the batch.
+        let mut changes: Vec<_> = self
+            .changes
+            .iter()
+            .map(|column| column.lock().expect("poisoned lock"))
+            .collect();
+
+        // Resolve merges before applying the batch, so the failed merge doesn't leave it applied partially.
+        let mut staged = HashMap::<(usize, Vec<u8>), WriteOperation>::new();
+        let mut writes = Vec::with_capacity(entries.len());
+        for (key, column, op) in entries {
+            let index = column.as_usize();
+            let change = match op {
+                WriteOperation::Merge(operand) => {
+                    let current = staged
+                        .get(&(index, key.clone()))
+                        .or_else(|| changes[index].get(&key));
+                    Self::merged_change(column, current, operand)?
+                }
+                op => op,
+            };
+            self.limits.check_value(&change)?;
+            staged.insert((index, key.clone()), change.clone());
+            writes.push((key, column, change));
+        }
+
+        // Only the last change of the key is staged, so limits apply to it.
+        let attempted =
+            staged
+                .iter()
+                .fold(self.usage(), |usage, ((index, key), change)| {
+                    usage
+                        .saturating_sub(changes[*index].replaced_usage(key))
+                        .saturating_add(WriteUsage::of(change))
+                });
+        self.limits.check_usage(attempted)?;
+
+        for (key, column, change) in writes {
+            self.stage(&key, column, &change)?;
+            self.modify_changes(&mut changes[column.as_usize()], |changes| {
+                changes.insert(key, change)
+            });
+        }
+        Ok(())
+    }
 }
 
 impl<Description> TransactableStorage for MemoryTransactionView<Description>
@@ -280,12 +1484,206 @@ where
     Description: DatabaseDescription,
 {
     fn flush(&self) -> DatabaseResult<()> {
-        for lock in self.changes.iter() {
-            lock.lock().expect("poisoned lock").clear();
-        }
-        self.view_layer.flush()?;
+        // Uncommitted changes are not durable, only the data source can be flushed.
         self.data_source.flush()
     }
+
+    fn iter_column(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> ColumnIter<'_> {
+        if !is_start_within_prefix(prefix, start) {
+            return ColumnIter::boxed(core::iter::empty().into_boxed())
+        }
+        if self.reads.is_some() {
+            // The lending iterator doesn't own entries to record them.
+            return ColumnIter::boxed(self.iter_all(column, prefix, start, direction))
+        }
+        let (changes, view_layer, data_source) = self.capture_layers(column, || {
+            (
+                self.view_layer
+                    .iter_column(column, prefix, start, direction),
+                self.data_source
+                    .iter_column(column, prefix, start, direction),
+            )
+        });
+        ColumnIter::layered(ViewColumnIter::<Description> {
+            changes,
+            column,
+            direction,
+            view_layer,
+            data_source,
+            started: false,
+            current: None,
+            merged: None,
+        })
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        // The view merges changes with the data source, so we count the result of the merge.
+        let mut stats = ColumnStats::default();
+        for item in self.iter_all(column, None, None, IterDirection::Forward) {
+            let (key, value) = item.map_err(DatabaseError::from)?;
+            stats = stats.merge(ColumnStats::from_entries(core::iter::once((
+                key.as_slice(),
+                value.as_slice(),
+            ))));
+        }
+        Ok(stats)
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        // Changes of the view are written into the data source on the commit.
+        self.data_source.write_pressure()
+    }
+
+    fn checkpoint(&self, _: &Path) -> DatabaseResult<()> {
+        Err(DatabaseError::Unsupported("checkpoint"))
+    }
+
+    fn backup_to(&self, _: &Path) -> DatabaseResult<()> {
+        Err(DatabaseError::Unsupported("backup"))
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        // Uncommitted changes are in memory, only the data source can be compacted.
+        self.data_source.compact(column, range)
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        let mut changes = self.changes[column.as_usize()]
+            .lock()
+            .expect("poisoned lock");
+        // Like `delete_range`, the tombstone of the whole column hides keys of the data
+        // source until the commit, and dropping the view leaves the data source intact.
+        self.modify_changes(&mut changes, |changes| changes.remove_range(&[], None));
+        self.view_layer.clear_column(column)
+    }
+
+    fn multi_delete(
+        &self,
+        column: Self::Column,
+        keys: &[Vec<u8>],
+    ) -> DatabaseResult<usize> {
+        let keys = distinct_keys(keys);
+        // Hold the lock until the end to make the operation atomic.
+        let mut changes = self.changes[column.as_usize()]
+            .lock()
+            .expect("poisoned lock");
+        let mut existing = 0usize;
+        let mut unchanged = vec![];
+        for key in keys.iter() {
+            let exists = match changes.get(key) {
+                // The merge always produces the value.
+                Some(WriteOperation::Merge(_)) => true,
+                Some(_) => self
+                    .view_layer
+                    .exists(key, column)
+                    .map_err(DatabaseError::from)?,
+                None => {
+                    unchanged.push(key.clone());
+                    false
+                }
+            };
+            existing = existing.saturating_add(usize::from(exists));
+        }
+        // Keys without changes are read from the data source at once.
+        let values = self
+            .data_source
+            .multi_get(&unchanged, column)
+            .map_err(DatabaseError::from)?;
+        for (key, value) in unchanged.iter().zip(values) {
+            self.record_read(key, column, &value);
+            existing = existing.saturating_add(usize::from(value.is_some()));
+        }
+
+        let attempted = keys.iter().fold(self.usage(), |usage, key| {
+            usage
+                .saturating_sub(changes.replaced_usage(key))
+                .saturating_add(WriteUsage::of(&WriteOperation::Remove))
+        });
+        self.limits.check_usage(attempted)?;
+
+        // Keys may only exist in the data source, so tombstones hide them.
+        self.view_layer.multi_delete(column, &keys)?;
+        self.modify_changes(&mut changes, |changes| {
+            for key in keys {
+                changes.insert(key, WriteOperation::Remove);
+            }
+        });
+        Ok(existing)
+    }
+
+    fn delete_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: &[u8],
+    ) -> DatabaseResult<()> {
+        if start >= end {
+            return Ok(())
+        }
+        let mut changes = self.changes[column.as_usize()]
+            .lock()
+            .expect("poisoned lock");
+        // Keys of the data source stay where they are until the commit,
+        // only the tombstone of the range hides them.
+        self.modify_changes(&mut changes, |changes| {
+            changes.remove_range(start, Some(end))
+        });
+        self.view_layer.delete_range(column, start, end)
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        // Follow the lock order of the writes: changes, view layer, data source.
+        let changes: Vec<_> = self
+            .changes
+            .iter()
+            .map(|column| column.lock().expect("poisoned lock"))
+            .collect();
+        let view_layer = self.view_layer.consistent_copy();
+        let data_source = DataSource::new(self.data_source.snapshot()?);
+        // The copy of the data source has its own sequence of writes, so the reads
+        // are known to be valid only if nothing was written since they were observed.
+        let unchanged = self.seen_commits.load(atomic::Ordering::Relaxed)
+            == self.data_source.commits().current();
+        let seen_commits = if unchanged {
+            data_source.commits().current()
+        } else {
+            u64::MAX
+        };
+
+        Ok(Arc::new(Self {
+            view_layer,
+            changes: changes
+                .iter()
+                .map(|map| Mutex::new((*map).clone()))
+                .collect(),
+            reads: self.reads.as_ref().map(|reads| {
+                reads
+                    .iter()
+                    .map(|column_reads| {
+                        Mutex::new(column_reads.lock().expect("poisoned lock").clone())
+                    })
+                    .collect()
+            }),
+            seen_commits: AtomicU64::new(seen_commits),
+            limits: self.limits,
+            usage: Mutex::new(self.usage()),
+            data_source,
+        }))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
 }
 
 #[cfg(test)]
@@ -343,6 +1741,78 @@ mod tests {
         assert_eq!(original, Some(expected))
     }
 
+    #[test]
+    fn get_with_borrows_from_view_and_data_store() {
+        // setup
+        let store = Arc::new(MemoryStore::default());
+        store
+            .put(&[0xA], Column::Metadata, Arc::new(vec![1, 2, 3]))
+            .unwrap();
+        store
+            .put(&[0xB], Column::Metadata, Arc::new(vec![4, 5, 6]))
+            .unwrap();
+        let view = MemoryTransactionView::new(store);
+        view.put(&[0xC], Column::Metadata, Arc::new(vec![7, 8, 9]))
+            .unwrap();
+        view.delete(&[0xB], Column::Metadata).unwrap();
+
+        let get_with = |key: &[u8]| {
+            let mut value = None;
+            let found = view
+                .get_with(key, Column::Metadata, &mut |bytes| {
+                    value = Some(bytes.to_vec())
+                })
+                .unwrap();
+            assert_eq!(found, value.is_some());
+            value
+        };
+
+        // test & verify
+        assert_eq!(get_with(&[0xA]), Some(vec![1, 2, 3]));
+        assert_eq!(get_with(&[0xB]), None);
+        assert_eq!(get_with(&[0xC]), Some(vec![7, 8, 9]));
+        assert_eq!(get_with(&[0xD]), None);
+    }
+
+    #[test]
+    fn multi_get_returns_values_in_the_order_of_keys() {
+        // setup
+        let store = Arc::new(MemoryStore::default());
+        store
+            .put(&[1], Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+        store
+            .put(&[2], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+        store
+            .put(&[3], Column::Metadata, Arc::new(vec![3]))
+            .unwrap();
+        let view = MemoryTransactionView::new(store);
+        view.delete(&[2], Column::Metadata).unwrap();
+        view.put(&[3], Column::Metadata, Arc::new(vec![33]))
+            .unwrap();
+        view.put(&[4], Column::Metadata, Arc::new(vec![4])).unwrap();
+        // test
+        let ret = view
+            .multi_get(
+                &[vec![4], vec![5], vec![3], vec![2], vec![1], vec![2]],
+                Column::Metadata,
+            )
+            .unwrap();
+        // verify
+        assert_eq!(
+            ret,
+            vec![
+                Some(Arc::new(vec![4])),
+                None,
+                Some(Arc::new(vec![33])),
+                None,
+                Some(Arc::new(vec![1])),
+                None,
+            ]
+        )
+    }
+
     #[test]
     fn can_insert_value_into_view() {
         // setup
@@ -409,6 +1879,76 @@ mod tests {
         assert_eq!(get, None)
     }
 
+    #[test]
+    fn take_returns_none_when_already_deleted_in_view() {
+        // setup
+        let store = Arc::new(MemoryStore::default());
+        let key = vec![0xA, 0xB, 0xC];
+        store
+            .put(&key, Column::Metadata, Arc::new(vec![1, 2, 3]))
+            .unwrap();
+        let view = MemoryTransactionView::new(store.clone());
+        view.delete(&key, Column::Metadata).unwrap();
+        // test
+        let ret = view.take(&key, Column::Metadata).unwrap();
+        view.commit().unwrap();
+        // verify
+        assert_eq!(ret, None);
+        assert_eq!(store.get(&key, Column::Metadata).unwrap(), None)
+    }
+
+    #[test]
+    fn take_hides_data_source_value_until_commit() {
+        // setup
+        let store = Arc::new(MemoryStore::default());
+        let key = vec![0xA, 0xB, 0xC];
+        let expected = Arc::new(vec![1, 2, 3]);
+        store.put(&key, Column::Metadata, expected.clone()).unwrap();
+        let view = MemoryTransactionView::new(store.clone());
+        // test
+        let ret = view.take(&key, Column::Metadata).unwrap();
+        // verify
+        assert_eq!(ret, Some(expected.clone()));
+        assert!(!view.exists(&key, Column::Metadata).unwrap());
+        assert_eq!(store.get(&key, Column::Metadata).unwrap(), Some(expected));
+        view.commit().unwrap();
+        assert_eq!(store.get(&key, Column::Metadata).unwrap(), None)
+    }
+
+    #[test]
+    fn compare_and_swap_respects_view_changes() {
+        // setup
+        let store = Arc::new(MemoryStore::default());
+        let key = vec![0xA, 0xB, 0xC];
+        store
+            .put(&key, Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+        let view = MemoryTransactionView::new(store.clone());
+        // test
+        let absent = view
+            .compare_and_swap(&key, Column::Metadata, None, Arc::new(vec![2]))
+            .unwrap();
+        let swapped = view
+            .compare_and_swap(&key, Column::Metadata, Some(&[1]), Arc::new(vec![2]))
+            .unwrap();
+        view.delete(&key, Column::Metadata).unwrap();
+        let inserted = view
+            .compare_and_swap(&key, Column::Metadata, None, Arc::new(vec![3]))
+            .unwrap();
+        // verify
+        assert_eq!(absent, Err(Some(Arc::new(vec![1]))));
+        assert_eq!(swapped, Ok(()));
+        assert_eq!(inserted, Ok(()));
+        assert_eq!(
+            view.get(&key, Column::Metadata).unwrap(),
+            Some(Arc::new(vec![3]))
+        );
+        assert_eq!(
+            store.get(&key, Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+    }
+
     #[test]
     fn exists_checks_view_values() {
         // setup
@@ -488,23 +2028,194 @@ mod tests {
     }
 
     #[test]
-    fn iter_all_is_sorted_across_source_and_view() {
+    fn pending_writes_are_sorted_and_not_committed() {
         // setup
         let store = Arc::new(MemoryStore::default());
-        (0..10).step_by(2).for_each(|i| {
-            store
-                .put(&[i], Column::Metadata, Arc::new(vec![1]))
-                .unwrap();
-        });
+        store.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+        let view = MemoryTransactionView::new(store.clone());
+        view.put(&[3], Column::Metadata, Arc::new(vec![3])).unwrap();
+        view.put(&[2], Column::Coins, Arc::new(vec![2])).unwrap();
+        view.delete(&[1], Column::Coins).unwrap();
+        view.put(&[0], Column::Metadata, Arc::new(vec![0])).unwrap();
 
-        let view = MemoryTransactionView::new(store);
         // test
-        (0..10).step_by(3).for_each(|i| {
-            view.put(&[i], Column::Metadata, Arc::new(vec![2])).unwrap();
-        });
+        let writes = view.pending_writes().unwrap();
 
-        let ret: Vec<_> = view
-            .iter_all(Column::Metadata, None, None, IterDirection::Forward)
+        // verify
+        assert_eq!(
+            writes,
+            vec![
+                (vec![1], Column::Coins, WriteOperation::Remove),
+                (
+                    vec![2],
+                    Column::Coins,
+                    WriteOperation::Insert(Arc::new(vec![2]))
+                ),
+                (
+                    vec![0],
+                    Column::Metadata,
+                    WriteOperation::Insert(Arc::new(vec![0]))
+                ),
+                (
+                    vec![3],
+                    Column::Metadata,
+                    WriteOperation::Insert(Arc::new(vec![3]))
+                ),
+            ]
+        );
+        assert_eq!(
+            view.changed_columns(),
+            BTreeSet::from([Column::Metadata, Column::Coins])
+        );
+        assert_eq!(
+            store.get(&[1], Column::Coins).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+    }
+
+    #[test]
+    fn pending_writes_match_commit() {
+        // setup
+        let populate = |store: &MemoryStore| {
+            for i in 0..5u8 {
+                store
+                    .put(&[i], Column::Metadata, Arc::new(vec![i]))
+                    .unwrap();
+            }
+        };
+        let committed = Arc::new(MemoryStore::default());
+        let replayed = MemoryStore::default();
+        populate(&committed);
+        populate(&replayed);
+
+        let view = MemoryTransactionView::new(committed.clone());
+        view.put(&[7], Column::Metadata, Arc::new(vec![7])).unwrap();
+        view.put(&[2], Column::Metadata, Arc::new(vec![0xB]))
+            .unwrap();
+        view.delete(&[3], Column::Metadata).unwrap();
+        view.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+
+        // test
+        let writes = view.pending_writes().unwrap();
+        replayed.batch_write(&mut writes.into_iter()).unwrap();
+        view.commit().unwrap();
+
+        // verify
+        assert!(view.pending_writes().unwrap().is_empty());
+        assert!(view.changed_columns().is_empty());
+        for column in [Column::Metadata, Column::Coins] {
+            let committed: Vec<_> = committed
+                .iter_all(column, None, None, IterDirection::Forward)
+                .try_collect()
+                .unwrap();
+            let replayed: Vec<_> = replayed
+                .iter_all(column, None, None, IterDirection::Forward)
+                .try_collect()
+                .unwrap();
+            assert_eq!(committed, replayed);
+        }
+    }
+
+    #[test]
+    fn commits_of_the_same_transaction_emit_the_same_writes() {
+        use crate::state::notifying::NotifyingStorage;
+
+        let run = || {
+            // setup
+            let storage = Arc::new(NotifyingStorage::<OnChain>::new(
+                Arc::new(MemoryStore::default()),
+                1024,
+            ));
+            let mut receiver = storage.subscribe(Column::Coins, vec![]).unwrap();
+            let view = MemoryTransactionView::new(storage.clone());
+            for i in 0..64u8 {
+                let key = [i.wrapping_mul(37), i];
+                view.put(&key, Column::Coins, Arc::new(vec![i])).unwrap();
+                view.put(&key, Column::Metadata, Arc::new(vec![i])).unwrap();
+                if i % 3 == 0 {
+                    view.delete(&key, Column::Coins).unwrap();
+                }
+            }
+
+            // test
+            let writes = view.pending_writes().unwrap();
+            view.commit().unwrap();
+            let mut committed = vec![];
+            while let Ok(event) = receiver.try_recv() {
+                committed.push(event.key);
+            }
+            (writes, committed)
+        };
+
+        // verify
+        let (writes, committed) = run();
+        let coins: Vec<_> = writes
+            .iter()
+            .filter(|(_, column, _)| *column == Column::Coins)
+            .map(|(key, _, _)| key.clone())
+            .collect();
+        assert_eq!(committed, coins);
+        assert!(writes
+            .windows(2)
+            .all(|pair| (pair[0].1.as_usize(), &pair[0].0)
+                < (pair[1].1.as_usize(), &pair[1].0)));
+        for _ in 0..50 {
+            assert_eq!(run(), (writes.clone(), committed.clone()));
+        }
+    }
+
+    #[test]
+    fn writes_to_the_same_key_collapse_to_the_last_one() {
+        // setup
+        let store = Arc::new(MemoryStore::default());
+        let view = MemoryTransactionView::new(store.clone());
+        view.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+        view.delete(&[1], Column::Coins).unwrap();
+        view.put(&[1], Column::Coins, Arc::new(vec![3])).unwrap();
+        view.put(&[2], Column::Coins, Arc::new(vec![2])).unwrap();
+        view.delete(&[2], Column::Coins).unwrap();
+
+        // test
+        let writes = view.pending_writes().unwrap();
+        view.commit().unwrap();
+
+        // verify
+        assert_eq!(
+            writes,
+            vec![
+                (
+                    vec![1],
+                    Column::Coins,
+                    WriteOperation::Insert(Arc::new(vec![3]))
+                ),
+                (vec![2], Column::Coins, WriteOperation::Remove),
+            ]
+        );
+        assert_eq!(
+            store.get(&[1], Column::Coins).unwrap(),
+            Some(Arc::new(vec![3]))
+        );
+        assert_eq!(store.get(&[2], Column::Coins).unwrap(), None);
+    }
+
+    #[test]
+    fn iter_all_is_sorted_across_source_and_view() {
+        // setup
+        let store = Arc::new(MemoryStore::default());
+        (0..10).step_by(2).for_each(|i| {
+            store
+                .put(&[i], Column::Metadata, Arc::new(vec![1]))
+                .unwrap();
+        });
+
+        let view = MemoryTransactionView::new(store);
+        // test
+        (0..10).step_by(3).for_each(|i| {
+            view.put(&[i], Column::Metadata, Arc::new(vec![2])).unwrap();
+        });
+
+        let ret: Vec<_> = view
+            .iter_all(Column::Metadata, None, None, IterDirection::Forward)
             .map_ok(|(k, _)| k[0])
             .try_collect()
             .unwrap();
@@ -589,120 +2300,1664 @@ mod tests {
         assert_eq!(ret, vec![2, 4, 8])
     }
 
-    #[test]
-    fn can_use_unit_value() {
-        let key = vec![0x00];
-
+    fn prefixed_view() -> MemoryTransactionView {
         let store = Arc::new(MemoryStore::default());
-        let db = MemoryTransactionView::new(store.clone());
-        let expected = Arc::new(vec![]);
-        db.put(&key, Column::Metadata, expected.clone()).unwrap();
-
-        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), expected);
-
-        assert!(db.exists(&key, Column::Metadata).unwrap());
+        for key in [[0, 9], [1, 0], [1, 2], [1, 4], [1, 6], [2, 0]] {
+            store
+                .put(&key, Column::Metadata, Arc::new(vec![0xA]))
+                .unwrap();
+        }
+        let view = MemoryTransactionView::new(store);
+        view.delete(&[1, 2], Column::Metadata).unwrap();
+        view.put(&[1, 3], Column::Metadata, Arc::new(vec![0xB]))
+            .unwrap();
+        view.put(&[1, 4], Column::Metadata, Arc::new(vec![0xB]))
+            .unwrap();
+        view.put(&[1, 5], Column::Metadata, Arc::new(vec![0xB]))
+            .unwrap();
+        view
+    }
 
-        assert_eq!(
-            db.iter_all(Column::Metadata, None, None, IterDirection::Forward)
-                .collect::<Result<Vec<_>, _>>()
-                .unwrap(),
-            vec![(key.clone(), expected.clone())]
-        );
+    fn prefixed_items(
+        view: &MemoryTransactionView,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> Vec<(Vec<u8>, u8)> {
+        view.iter_all(Column::Metadata, Some(&[1]), start, direction)
+            .map_ok(|(k, v)| (k, v[0]))
+            .try_collect()
+            .unwrap()
+    }
 
-        assert_eq!(db.take(&key, Column::Metadata).unwrap().unwrap(), expected);
+    #[test]
+    fn iter_all_with_prefix_skips_keys_deleted_in_view() {
+        // setup
+        let view = prefixed_view();
 
-        assert!(!db.exists(&key, Column::Metadata).unwrap());
+        // test
+        let forward = prefixed_items(&view, None, IterDirection::Forward);
+        let reverse = prefixed_items(&view, None, IterDirection::Reverse);
 
-        db.commit().unwrap();
+        // verify
+        let expected = vec![
+            (vec![1, 0], 0xA),
+            (vec![1, 3], 0xB),
+            (vec![1, 4], 0xB),
+            (vec![1, 5], 0xB),
+            (vec![1, 6], 0xA),
+        ];
+        assert_eq!(forward, expected);
+        assert_eq!(reverse, expected.into_iter().rev().collect::<Vec<_>>());
+    }
 
-        assert!(!store.exists(&key, Column::Metadata).unwrap());
+    #[test]
+    fn iter_all_with_start_at_deleted_key_begins_after_it() {
+        // setup
+        let view = prefixed_view();
 
-        let store = Arc::new(MemoryStore::default());
-        let db = MemoryTransactionView::new(store.clone());
-        db.put(&key, Column::Metadata, expected.clone()).unwrap();
-        db.commit().unwrap();
+        // test
+        let forward = prefixed_items(&view, Some(&[1, 2]), IterDirection::Forward);
+        let reverse = prefixed_items(&view, Some(&[1, 2]), IterDirection::Reverse);
 
+        // verify
         assert_eq!(
-            store.get(&key, Column::Metadata).unwrap().unwrap(),
-            expected
+            forward,
+            vec![
+                (vec![1, 3], 0xB),
+                (vec![1, 4], 0xB),
+                (vec![1, 5], 0xB),
+                (vec![1, 6], 0xA)
+            ]
         );
+        assert_eq!(reverse, vec![(vec![1, 0], 0xA)]);
     }
 
     #[test]
-    fn can_use_unit_key() {
-        let key: Vec<u8> = Vec::with_capacity(0);
+    fn iter_all_reverse_with_start_in_the_middle_of_view_keys() {
+        // setup
+        let view = prefixed_view();
+
+        // test
+        let reverse = prefixed_items(&view, Some(&[1, 4]), IterDirection::Reverse);
+
+        // verify
+        assert_eq!(
+            reverse,
+            vec![(vec![1, 4], 0xB), (vec![1, 3], 0xB), (vec![1, 0], 0xA)]
+        );
+    }
 
+    fn range_view() -> MemoryTransactionView {
         let store = Arc::new(MemoryStore::default());
-        let db = MemoryTransactionView::new(store.clone());
-        let expected = Arc::new(vec![]);
-        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+        (0..10).step_by(2).for_each(|i| {
+            store
+                .put(&[i], Column::Metadata, Arc::new(vec![1]))
+                .unwrap();
+        });
 
-        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), expected);
+        let view = MemoryTransactionView::new(store);
+        (0..10).step_by(3).for_each(|i| {
+            view.put(&[i], Column::Metadata, Arc::new(vec![2])).unwrap();
+        });
+        view.delete(&[4], Column::Metadata).unwrap();
+        view
+    }
 
-        assert!(db.exists(&key, Column::Metadata).unwrap());
+    fn range_keys(
+        view: &MemoryTransactionView,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> Vec<Vec<u8>> {
+        view.iter_range(Column::Metadata, prefix, start, end, direction)
+            .map_ok(|(k, _)| k)
+            .try_collect()
+            .unwrap()
+    }
 
-        assert_eq!(
-            db.iter_all(Column::Metadata, None, None, IterDirection::Forward)
-                .collect::<Result<Vec<_>, _>>()
-                .unwrap(),
-            vec![(key.clone(), expected.clone())]
-        );
+    #[test]
+    fn iter_range_stops_before_end_across_source_and_view() {
+        // setup
+        let view = range_view();
 
-        assert_eq!(db.take(&key, Column::Metadata).unwrap().unwrap(), expected);
+        // test
+        let forward =
+            range_keys(&view, None, Some(&[2]), Some(&[8]), IterDirection::Forward);
+        let unbounded = range_keys(&view, None, None, Some(&[3]), IterDirection::Forward);
 
-        assert!(!db.exists(&key, Column::Metadata).unwrap());
+        // verify
+        assert_eq!(forward, vec![vec![2], vec![3], vec![6]]);
+        assert_eq!(unbounded, vec![vec![0], vec![2]]);
+    }
 
-        db.commit().unwrap();
+    #[test]
+    fn iter_range_reverse_uses_end_as_lower_bound() {
+        // setup
+        let view = range_view();
 
-        assert!(!store.exists(&key, Column::Metadata).unwrap());
+        // test
+        let reverse =
+            range_keys(&view, None, Some(&[8]), Some(&[2]), IterDirection::Reverse);
+        let unbounded = range_keys(&view, None, None, Some(&[6]), IterDirection::Reverse);
+        let empty =
+            range_keys(&view, None, Some(&[2]), Some(&[8]), IterDirection::Reverse);
 
+        // verify
+        assert_eq!(reverse, vec![vec![8], vec![6], vec![3]]);
+        assert_eq!(unbounded, vec![vec![9], vec![8]]);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn iter_range_stays_within_prefix() {
+        // setup
         let store = Arc::new(MemoryStore::default());
-        let db = MemoryTransactionView::new(store.clone());
-        db.put(&key, Column::Metadata, expected.clone()).unwrap();
-        db.commit().unwrap();
+        for key in [[0, 1], [1, 0], [1, 2], [1, 4], [2, 0]] {
+            store
+                .put(&key, Column::Metadata, Arc::new(vec![1]))
+                .unwrap();
+        }
+        let view = MemoryTransactionView::new(store);
+        view.put(&[1, 3], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+        view.put(&[2, 1], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+
+        // test
+        let prefix: &[u8] = &[1];
+        let forward = range_keys(
+            &view,
+            Some(prefix),
+            None,
+            Some(&[3]),
+            IterDirection::Forward,
+        );
+        let forward_bounded = range_keys(
+            &view,
+            Some(prefix),
+            None,
+            Some(&[1, 3]),
+            IterDirection::Forward,
+        );
+        let reverse = range_keys(
+            &view,
+            Some(prefix),
+            None,
+            Some(&[0]),
+            IterDirection::Reverse,
+        );
+        let reverse_bounded = range_keys(
+            &view,
+            Some(prefix),
+            Some(&[1, 4]),
+            Some(&[1, 2]),
+            IterDirection::Reverse,
+        );
 
+        // verify
         assert_eq!(
-            store.get(&key, Column::Metadata).unwrap().unwrap(),
-            expected
+            forward,
+            vec![vec![1, 0], vec![1, 2], vec![1, 3], vec![1, 4]]
+        );
+        assert_eq!(forward_bounded, vec![vec![1, 0], vec![1, 2]]);
+        assert_eq!(
+            reverse,
+            vec![vec![1, 4], vec![1, 3], vec![1, 2], vec![1, 0]]
         );
+        assert_eq!(reverse_bounded, vec![vec![1, 4], vec![1, 3]]);
     }
 
     #[test]
-    fn can_use_unit_key_and_value() {
-        let key: Vec<u8> = Vec::with_capacity(0);
+    fn iter_keys_merges_view_and_hides_deleted_keys() {
+        // setup
+        let view = range_view();
 
-        let store = Arc::new(MemoryStore::default());
-        let db = MemoryTransactionView::new(store.clone());
-        let expected = Arc::new(vec![]);
-        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+        // test
+        let forward: Vec<_> = view
+            .iter_keys(Column::Metadata, None, None, IterDirection::Forward)
+            .map_ok(|k| k[0])
+            .try_collect()
+            .unwrap();
+        let reverse: Vec<_> = view
+            .iter_keys(Column::Metadata, None, Some(&[6]), IterDirection::Reverse)
+            .map_ok(|k| k[0])
+            .try_collect()
+            .unwrap();
 
-        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), expected);
+        // verify
+        assert_eq!(forward, vec![0, 2, 3, 6, 8, 9]);
+        assert_eq!(reverse, vec![6, 3, 2, 0]);
+    }
 
-        assert!(db.exists(&key, Column::Metadata).unwrap());
+    #[test]
+    fn column_stats_respects_view_changes() {
+        // setup
+        let view = range_view();
 
-        assert_eq!(
-            db.iter_all(Column::Metadata, None, None, IterDirection::Forward)
-                .collect::<Result<Vec<_>, _>>()
-                .unwrap(),
-            vec![(key.clone(), expected.clone())]
-        );
+        // test
+        let stats = view.column_stats(Column::Metadata).unwrap();
 
-        assert_eq!(db.take(&key, Column::Metadata).unwrap().unwrap(), expected);
+        // verify
+        assert_eq!(stats, ColumnStats { keys: 6, size: 12 });
+    }
 
-        assert!(!db.exists(&key, Column::Metadata).unwrap());
+    #[test]
+    fn delete_all_hides_data_source_keys_after_commit() {
+        // setup
+        let store = Arc::new(MemoryStore::default());
+        for key in [[0, 1], [1, 0], [1, 2], [2, 0]] {
+            store
+                .put(&key, Column::Metadata, Arc::new(vec![1]))
+                .unwrap();
+        }
+        let view = MemoryTransactionView::new(store.clone());
+        view.put(&[1, 1], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+        view.delete(&[1, 2], Column::Metadata).unwrap();
 
-        db.commit().unwrap();
+        // test
+        let removed = view.delete_all(Column::Metadata, Some(&[1])).unwrap();
 
-        assert!(!store.exists(&key, Column::Metadata).unwrap());
+        // verify
+        assert_eq!(removed, 2);
+        let keys: Vec<_> = view
+            .iter_keys(Column::Metadata, None, None, IterDirection::Forward)
+            .try_collect()
+            .unwrap();
+        assert_eq!(keys, vec![vec![0, 1], vec![2, 0]]);
+        view.commit().unwrap();
+        let keys: Vec<_> = store
+            .iter_keys(Column::Metadata, None, None, IterDirection::Forward)
+            .try_collect()
+            .unwrap();
+        assert_eq!(keys, vec![vec![0, 1], vec![2, 0]]);
+    }
 
-        let store = Arc::new(MemoryStore::default());
-        let db = MemoryTransactionView::new(store.clone());
-        db.put(&key, Column::Metadata, expected.clone()).unwrap();
-        db.commit().unwrap();
+    #[test]
+    fn delete_all_with_empty_prefix_clears_column() {
+        // setup
+        let view = range_view();
+
+        // test
+        let removed = view.delete_all(Column::Metadata, Some(&[])).unwrap();
 
+        // verify
+        assert_eq!(removed, 6);
         assert_eq!(
-            store.get(&key, Column::Metadata).unwrap().unwrap(),
-            expected
+            view.iter_all(Column::Metadata, None, None, IterDirection::Forward)
+                .count(),
+            0
+        );
+    }
+
+    fn nested_views() -> (
+        Arc<MemoryStore>,
+        Arc<MemoryTransactionView>,
+        MemoryTransactionView,
+    ) {
+        let store = Arc::new(MemoryStore::default());
+        (0..10).step_by(2).for_each(|i| {
+            store
+                .put(&[i], Column::Metadata, Arc::new(vec![0]))
+                .unwrap();
+        });
+        let parent = Arc::new(MemoryTransactionView::new(store.clone()));
+        (0..10).step_by(3).for_each(|i| {
+            parent
+                .put(&[i], Column::Metadata, Arc::new(vec![1]))
+                .unwrap();
+        });
+        parent.delete(&[4], Column::Metadata).unwrap();
+        let child = MemoryTransactionView::new(parent.clone());
+        child
+            .put(&[4], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+        child
+            .put(&[7], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+        child.delete(&[3], Column::Metadata).unwrap();
+        (store, parent, child)
+    }
+
+    #[test]
+    fn nested_view_reads_parent_changes() {
+        // setup
+        let (_, _parent, child) = nested_views();
+
+        // verify
+        assert_eq!(
+            child.get(&[0], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+        assert_eq!(
+            child.get(&[2], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![0]))
+        );
+        assert_eq!(
+            child.get(&[4], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![2]))
+        );
+        assert_eq!(child.get(&[3], Column::Metadata).unwrap(), None);
+    }
+
+    #[test]
+    fn nested_view_iter_all_merges_all_layers() {
+        // setup
+        let (_, _parent, child) = nested_views();
+
+        // test
+        let forward: Vec<_> = child
+            .iter_all(Column::Metadata, None, None, IterDirection::Forward)
+            .map_ok(|(k, v)| (k[0], v[0]))
+            .try_collect()
+            .unwrap();
+        let reverse: Vec<_> = child
+            .iter_all(Column::Metadata, None, None, IterDirection::Reverse)
+            .map_ok(|(k, v)| (k[0], v[0]))
+            .try_collect()
+            .unwrap();
+
+        // verify
+        let expected = vec![(0, 1), (2, 0), (4, 2), (6, 1), (7, 2), (8, 0), (9, 1)];
+        assert_eq!(forward, expected);
+        assert_eq!(reverse, expected.into_iter().rev().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn nested_view_iter_column_lends_the_same_entries_as_iter_all() {
+        // setup
+        let (_, _parent, child) = nested_views();
+
+        for direction in [IterDirection::Forward, IterDirection::Reverse] {
+            for (prefix, start) in [(None, None), (Some([4].as_slice()), None)] {
+                // test
+                let mut lent = vec![];
+                let mut iter =
+                    child.iter_column(Column::Metadata, prefix, start, direction);
+                while let Some(item) = iter.next() {
+                    let (key, value) = item.unwrap();
+                    lent.push((key.to_vec(), value.to_vec()));
+                }
+
+                // verify
+                let expected: Vec<_> = child
+                    .iter_all(Column::Metadata, prefix, start, direction)
+                    .map_ok(|(key, value)| (key, value.to_vec()))
+                    .try_collect()
+                    .unwrap();
+                assert_eq!(lent, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn nested_view_commit_folds_changes_into_parent_only() {
+        // setup
+        let (store, parent, child) = nested_views();
+
+        // test
+        child.commit().unwrap();
+
+        // verify
+        let parent_keys: Vec<_> = parent
+            .iter_all(Column::Metadata, None, None, IterDirection::Forward)
+            .map_ok(|(k, v)| (k[0], v[0]))
+            .try_collect()
+            .unwrap();
+        assert_eq!(
+            parent_keys,
+            vec![(0, 1), (2, 0), (4, 2), (6, 1), (7, 2), (8, 0), (9, 1)]
+        );
+        assert_eq!(store.get(&[7], Column::Metadata).unwrap(), None);
+        assert_eq!(
+            store.get(&[4], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![0]))
+        );
+    }
+
+    #[test]
+    fn nested_view_drop_discards_changes() {
+        // setup
+        let (_, parent, child) = nested_views();
+
+        // test
+        drop(child);
+
+        // verify
+        assert_eq!(parent.get(&[4], Column::Metadata).unwrap(), None);
+        assert_eq!(parent.get(&[7], Column::Metadata).unwrap(), None);
+        assert_eq!(
+            parent.get(&[3], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+    }
+
+    #[test]
+    fn can_use_unit_value() {
+        let key = vec![0x00];
+
+        let store = Arc::new(MemoryStore::default());
+        let db = MemoryTransactionView::new(store.clone());
+        let expected = Arc::new(vec![]);
+        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+
+        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), expected);
+
+        assert!(db.exists(&key, Column::Metadata).unwrap());
+
+        assert_eq!(
+            db.iter_all(Column::Metadata, None, None, IterDirection::Forward)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![(key.clone(), expected.clone())]
+        );
+
+        assert_eq!(db.take(&key, Column::Metadata).unwrap().unwrap(), expected);
+
+        assert!(!db.exists(&key, Column::Metadata).unwrap());
+
+        db.commit().unwrap();
+
+        assert!(!store.exists(&key, Column::Metadata).unwrap());
+
+        let store = Arc::new(MemoryStore::default());
+        let db = MemoryTransactionView::new(store.clone());
+        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+        db.commit().unwrap();
+
+        assert_eq!(
+            store.get(&key, Column::Metadata).unwrap().unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn can_use_unit_key() {
+        let key: Vec<u8> = Vec::with_capacity(0);
+
+        let store = Arc::new(MemoryStore::default());
+        let db = MemoryTransactionView::new(store.clone());
+        let expected = Arc::new(vec![]);
+        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+
+        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), expected);
+
+        assert!(db.exists(&key, Column::Metadata).unwrap());
+
+        assert_eq!(
+            db.iter_all(Column::Metadata, None, None, IterDirection::Forward)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![(key.clone(), expected.clone())]
+        );
+
+        assert_eq!(db.take(&key, Column::Metadata).unwrap().unwrap(), expected);
+
+        assert!(!db.exists(&key, Column::Metadata).unwrap());
+
+        db.commit().unwrap();
+
+        assert!(!store.exists(&key, Column::Metadata).unwrap());
+
+        let store = Arc::new(MemoryStore::default());
+        let db = MemoryTransactionView::new(store.clone());
+        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+        db.commit().unwrap();
+
+        assert_eq!(
+            store.get(&key, Column::Metadata).unwrap().unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn can_use_unit_key_and_value() {
+        let key: Vec<u8> = Vec::with_capacity(0);
+
+        let store = Arc::new(MemoryStore::default());
+        let db = MemoryTransactionView::new(store.clone());
+        let expected = Arc::new(vec![]);
+        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+
+        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), expected);
+
+        assert!(db.exists(&key, Column::Metadata).unwrap());
+
+        assert_eq!(
+            db.iter_all(Column::Metadata, None, None, IterDirection::Forward)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![(key.clone(), expected.clone())]
+        );
+
+        assert_eq!(db.take(&key, Column::Metadata).unwrap().unwrap(), expected);
+
+        assert!(!db.exists(&key, Column::Metadata).unwrap());
+
+        db.commit().unwrap();
+
+        assert!(!store.exists(&key, Column::Metadata).unwrap());
+
+        let store = Arc::new(MemoryStore::default());
+        let db = MemoryTransactionView::new(store.clone());
+        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+        db.commit().unwrap();
+
+        assert_eq!(
+            store.get(&key, Column::Metadata).unwrap().unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn read_snapshot_is_not_affected_by_later_writes() {
+        // setup
+        let store = Arc::new(MemoryStore::default());
+        store
+            .put(&[1], Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+        store
+            .put(&[2], Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+        let view = MemoryTransactionView::new(store.clone());
+        view.put(&[2], Column::Metadata, Arc::new(vec![2])).unwrap();
+
+        // test
+        let snapshot = view.read_snapshot().unwrap();
+        view.delete(&[1], Column::Metadata).unwrap();
+        view.put(&[3], Column::Metadata, Arc::new(vec![3])).unwrap();
+        store
+            .put(&[4], Column::Metadata, Arc::new(vec![4]))
+            .unwrap();
+
+        // verify
+        assert_eq!(
+            snapshot.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+        assert!(!snapshot.exists(&[3], Column::Metadata).unwrap());
+        let items: Vec<_> = snapshot
+            .iter_all(Column::Metadata, None, None, IterDirection::Forward)
+            .map_ok(|(k, v)| (k[0], v[0]))
+            .try_collect()
+            .unwrap();
+        assert_eq!(items, vec![(1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn commit_shares_the_allocation_of_the_staged_value() {
+        // setup
+        let store = Arc::new(MemoryStore::default());
+        let view = MemoryTransactionView::new(store.clone());
+        let key = vec![0xA, 0xB, 0xC];
+        let expected = Arc::new(vec![1, 2, 3]);
+        view.put(&key, Column::Metadata, expected.clone()).unwrap();
+        let staged = view.get(&key, Column::Metadata).unwrap().unwrap();
+        // test
+        view.commit().unwrap();
+        let ret = store.get(&key, Column::Metadata).unwrap().unwrap();
+        // verify
+        assert!(Arc::ptr_eq(&staged, &expected));
+        assert!(Arc::ptr_eq(&ret, &expected));
+    }
+
+    #[test]
+    fn commit_applies_the_last_write_of_the_key() {
+        // setup
+        let store = Arc::new(MemoryStore::default());
+        store
+            .put(&[1], Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+        store
+            .put(&[2], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+        let view = MemoryTransactionView::new(store.clone());
+        // test
+        view.put(&[1], Column::Metadata, vec![10].into()).unwrap();
+        view.write(&[1], Column::Metadata, &[11]).unwrap();
+        view.delete(&[2], Column::Metadata).unwrap();
+        view.put(&[2], Column::Metadata, vec![20].into()).unwrap();
+        view.put(&[3], Column::Metadata, vec![30].into()).unwrap();
+        view.delete(&[3], Column::Metadata).unwrap();
+        view.commit().unwrap();
+        // verify
+        assert_eq!(
+            store.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![11]))
+        );
+        assert_eq!(
+            store.get(&[2], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![20]))
+        );
+        assert_eq!(store.get(&[3], Column::Metadata).unwrap(), None);
+    }
+
+    #[test]
+    fn nested_view_commit_shares_the_allocation_of_the_staged_value() {
+        // setup
+        let store = Arc::new(MemoryStore::default());
+        let outer = Arc::new(MemoryTransactionView::new(store.clone()));
+        let inner = MemoryTransactionView::new(outer.clone());
+        let expected = Arc::new(vec![1, 2, 3]);
+        inner.put(&[1], Column::Metadata, expected.clone()).unwrap();
+        // test
+        inner.commit().unwrap();
+        outer.commit().unwrap();
+        // verify
+        let ret = store.get(&[1], Column::Metadata).unwrap().unwrap();
+        assert!(Arc::ptr_eq(&ret, &expected));
+    }
+
+    #[test]
+    fn commit_resolves_deferred_merges_against_the_data_source() {
+        use crate::database::database_description::test::*;
+
+        // setup
+        let store = Arc::new(MemoryStore::<MergeDatabase>::default());
+        store.batch_write(&mut merge_base().into_iter()).unwrap();
+        let view = super::MemoryTransactionView::new(store.clone());
+        apply_merge_operations(&view, merge_operations());
+        // test
+        view.commit().unwrap();
+        // verify
+        assert_eq!(merge_entries(store.as_ref()), merged_state());
+    }
+
+    #[test]
+    fn reads_before_commit_see_pending_merges() {
+        use crate::database::database_description::test::*;
+
+        // setup
+        let store = Arc::new(MemoryStore::<MergeDatabase>::default());
+        store.batch_write(&mut merge_base().into_iter()).unwrap();
+        let view = super::MemoryTransactionView::new(store.clone());
+        apply_merge_operations(&view, merge_operations());
+        // test
+        let entries = merge_entries(&view);
+        let counter = view.get(&[4], MergeColumn::Counters).unwrap();
+        // verify
+        assert_eq!(entries, merged_state());
+        assert_eq!(counter, Some(Arc::new(u64::MAX.to_be_bytes().to_vec())));
+        assert!(view.exists(&[2], MergeColumn::Counters).unwrap());
+        assert!(!view.exists(&[3], MergeColumn::Counters).unwrap());
+        view.commit().unwrap();
+        assert_eq!(merge_entries(store.as_ref()), merged_state());
+    }
+
+    #[test]
+    fn iter_column_resolves_pending_merges() {
+        use crate::database::database_description::test::*;
+
+        // setup
+        let store = Arc::new(MemoryStore::<MergeDatabase>::default());
+        store.batch_write(&mut merge_base().into_iter()).unwrap();
+        let view = super::MemoryTransactionView::new(store);
+        apply_merge_operations(&view, merge_operations());
+
+        // test
+        let mut entries = vec![];
+        for column in enum_iterator::all::<MergeColumn>() {
+            let mut iter = view.iter_column(column, None, None, IterDirection::Forward);
+            while let Some(item) = iter.next() {
+                let (key, value) = item.unwrap();
+                entries.push((column, key.to_vec(), Arc::new(value.to_vec())));
+            }
+        }
+
+        // verify
+        assert_eq!(entries, merged_state());
+    }
+
+    #[test]
+    fn nested_views_merge_like_the_data_source() {
+        use crate::database::database_description::test::*;
+
+        // setup
+        let store = Arc::new(MemoryStore::<MergeDatabase>::default());
+        store.batch_write(&mut merge_base().into_iter()).unwrap();
+        let parent = Arc::new(super::MemoryTransactionView::new(store.clone()));
+        let child = super::MemoryTransactionView::new(parent.clone());
+        let mut operations = merge_operations();
+        let second_half = operations.split_off(operations.len() / 2);
+        apply_merge_operations(parent.as_ref(), operations);
+        apply_merge_operations(&child, second_half);
+        // test
+        child.commit().unwrap();
+        parent.commit().unwrap();
+        // verify
+        let reference = MemoryStore::<MergeDatabase>::default();
+        apply_merge_operations(&reference, merge_base());
+        apply_merge_operations(&reference, merge_operations());
+        assert_eq!(merge_entries(store.as_ref()), merge_entries(&reference));
+        assert_eq!(merge_entries(store.as_ref()), merged_state());
+    }
+
+    #[test]
+    fn commit_over_the_capacity_leaves_the_store_unchanged() {
+        // setup
+        let store = Arc::new(MemoryStore::with_capacity_limit(50));
+        store
+            .put(&[0], Column::Metadata, Arc::new(vec![0; 9]))
+            .unwrap();
+        let view = MemoryTransactionView::new(store.clone());
+        view.delete(&[0], Column::Metadata).unwrap();
+        for i in 1..=6 {
+            view.put(&[i], Column::Metadata, Arc::new(vec![0; 9]))
+                .unwrap();
+        }
+        // test
+        let result = view.commit();
+        // verify
+        assert!(result.is_err());
+        assert_eq!(store.memory_usage().total, 10);
+        assert!(store.exists(&[0], Column::Metadata).unwrap());
+        assert!(!store.exists(&[1], Column::Metadata).unwrap());
+    }
+
+    #[test]
+    fn only_the_first_of_overlapping_transactions_commits() {
+        // Given
+        let store = Arc::new(MemoryStore::default());
+        let source = DataSource::from(store.clone());
+        store
+            .put(&[1], Column::Metadata, Arc::new(vec![0]))
+            .unwrap();
+        let first = MemoryTransactionView::with_conflict_detection(source.clone());
+        let second = MemoryTransactionView::with_conflict_detection(source.clone());
+        let increment = |view: &MemoryTransactionView| {
+            let value = view.get(&[1], Column::Metadata).unwrap().unwrap();
+            view.put(&[1], Column::Metadata, Arc::new(vec![value[0] + 1]))
+                .unwrap();
+        };
+
+        // When
+        increment(&first);
+        increment(&second);
+        let first_result = first.commit();
+        let second_result = second.commit();
+
+        // Then
+        assert!(first_result.is_ok());
+        let conflict = StorageError::from(DatabaseError::Conflict {
+            column: Column::Metadata.name(),
+            key: vec![1],
+        });
+        assert_eq!(
+            format!("{:?}", second_result.unwrap_err()),
+            format!("{conflict:?}")
+        );
+        assert_eq!(
+            store.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+        // The conflicted view keeps its changes.
+        assert_eq!(second.pending_writes().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn reads_of_iterators_are_validated() {
+        // Given
+        let store = Arc::new(MemoryStore::default());
+        let source = DataSource::from(store.clone());
+        store
+            .put(&[1], Column::Metadata, Arc::new(vec![0]))
+            .unwrap();
+        let view = MemoryTransactionView::with_conflict_detection(source.clone());
+        let keys = view
+            .iter_keys(Column::Metadata, None, None, IterDirection::Forward)
+            .collect::<StorageResult<Vec<_>>>()
+            .unwrap();
+        view.put(&[2], Column::Metadata, Arc::new(keys.concat()))
+            .unwrap();
+
+        // When
+        source.delete(&[1], Column::Metadata).unwrap();
+        let result = view.commit();
+
+        // Then
+        assert!(result.is_err());
+        assert!(!store.exists(&[2], Column::Metadata).unwrap());
+    }
+
+    #[test]
+    fn commits_of_views_without_conflict_detection_are_validated_against() {
+        // Given
+        let store = Arc::new(MemoryStore::default());
+        let source = DataSource::from(store.clone());
+        source
+            .put(&[1], Column::Metadata, Arc::new(vec![0]))
+            .unwrap();
+        let checked = MemoryTransactionView::with_conflict_detection(source.clone());
+        let value = checked.get(&[1], Column::Metadata).unwrap().unwrap();
+        checked
+            .put(&[1], Column::Metadata, Arc::new(vec![value[0] + 1]))
+            .unwrap();
+        let unchecked = MemoryTransactionView::new(source.clone());
+        unchecked
+            .put(&[1], Column::Metadata, Arc::new(vec![10]))
+            .unwrap();
+        let seq = source.commits().current();
+
+        // When
+        unchecked.commit().unwrap();
+        let result = checked.commit();
+
+        // Then
+        // The conflicted commit writes nothing, so it isn't counted.
+        assert_eq!(source.commits().current(), seq + 1);
+        assert!(result.is_err());
+        assert_eq!(
+            store.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![10]))
+        );
+    }
+
+    #[test]
+    fn non_overlapping_transactions_both_commit() {
+        // Given
+        let store = Arc::new(MemoryStore::default());
+        let source = DataSource::from(store.clone());
+        store
+            .put(&[1], Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+        store
+            .put(&[2], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+        let first = MemoryTransactionView::with_conflict_detection(source.clone());
+        let second = MemoryTransactionView::with_conflict_detection(source.clone());
+
+        // When
+        let value = first.get(&[1], Column::Metadata).unwrap().unwrap();
+        first
+            .put(&[1], Column::Metadata, Arc::new(vec![value[0] + 10]))
+            .unwrap();
+        let value = second.get(&[2], Column::Metadata).unwrap().unwrap();
+        second
+            .put(&[2], Column::Metadata, Arc::new(vec![value[0] + 10]))
+            .unwrap();
+        // Blind writes of the same key don't conflict.
+        first
+            .put(&[3], Column::Metadata, Arc::new(vec![3]))
+            .unwrap();
+        second
+            .put(&[3], Column::Metadata, Arc::new(vec![4]))
+            .unwrap();
+
+        // Then
+        first.commit().unwrap();
+        second.commit().unwrap();
+        assert_eq!(
+            store.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![11]))
+        );
+        assert_eq!(
+            store.get(&[2], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![12]))
+        );
+        // The view may commit again after its own commit changed what it read.
+        first
+            .put(&[1], Column::Metadata, Arc::new(vec![0]))
+            .unwrap();
+        first.commit().unwrap();
+    }
+
+    #[test]
+    fn concurrent_increments_never_lose_updates() {
+        // Given
+        let store = Arc::new(MemoryStore::default());
+        let source = DataSource::from(store.clone());
+        store
+            .put(
+                &[1],
+                Column::Metadata,
+                Arc::new(0u64.to_be_bytes().to_vec()),
+            )
+            .unwrap();
+
+        // When
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..25 {
+                        loop {
+                            let view = MemoryTransactionView::with_conflict_detection(
+                                source.clone(),
+                            );
+                            let value =
+                                view.get(&[1], Column::Metadata).unwrap().unwrap();
+                            let counter =
+                                u64::from_be_bytes(value.as_slice().try_into().unwrap());
+                            view.put(
+                                &[1],
+                                Column::Metadata,
+                                Arc::new((counter + 1).to_be_bytes().to_vec()),
+                            )
+                            .unwrap();
+                            if view.commit().is_ok() {
+                                break
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        // Then
+        assert_eq!(
+            store.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(100u64.to_be_bytes().to_vec()))
+        );
+    }
+
+    #[test]
+    fn multi_delete_hides_keys_of_the_view_and_the_data_source() {
+        // Given
+        let store = Arc::new(MemoryStore::default());
+        store.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+        store.put(&[2], Column::Coins, Arc::new(vec![2])).unwrap();
+        let view = MemoryTransactionView::new(store.clone());
+        view.put(&[3], Column::Coins, Arc::new(vec![3])).unwrap();
+        view.delete(&[2], Column::Coins).unwrap();
+
+        // When
+        let existing = view
+            .multi_delete(Column::Coins, &[vec![1], vec![2], vec![3], vec![4]])
+            .unwrap();
+
+        // Then
+        assert_eq!(existing, 2);
+        assert_eq!(
+            view.iter_all(Column::Coins, None, None, IterDirection::Forward)
+                .count(),
+            0
+        );
+        assert!(store.exists(&[1], Column::Coins).unwrap());
+        view.commit().unwrap();
+        assert_eq!(
+            store
+                .iter_all(Column::Coins, None, None, IterDirection::Forward)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn multi_delete_counts_repeated_key_once() {
+        // Given
+        let store = Arc::new(MemoryStore::default());
+        store.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+        let view = MemoryTransactionView::new(store);
+
+        // When
+        let existing = view
+            .multi_delete(Column::Coins, &[vec![1], vec![1], vec![5], vec![5]])
+            .unwrap();
+
+        // Then
+        assert_eq!(existing, 1);
+        assert_eq!(
+            view.pending_writes().unwrap().len(),
+            2,
+            "Each distinct key has one tombstone"
+        );
+    }
+
+    /// Returns the view over the store with keys from 1 to 5 of the column.
+    fn range_delete_view() -> (Arc<MemoryStore>, MemoryTransactionView) {
+        let store = Arc::new(MemoryStore::default());
+        for key in 1..=5u8 {
+            store
+                .put(&[key], Column::Coins, Arc::new(vec![key]))
+                .unwrap();
+        }
+        let view = MemoryTransactionView::new(store.clone());
+        (store, view)
+    }
+
+    fn view_keys(view: &MemoryTransactionView) -> Vec<u8> {
+        let keys: Vec<_> = view
+            .iter_all(Column::Coins, None, None, IterDirection::Forward)
+            .map_ok(|(key, _)| key[0])
+            .try_collect()
+            .unwrap();
+        let mut lent = vec![];
+        let mut iter =
+            view.iter_column(Column::Coins, None, None, IterDirection::Forward);
+        while let Some(item) = iter.next() {
+            lent.push(item.unwrap().0[0]);
+        }
+        assert_eq!(keys, lent, "Both iterators see the same keys");
+        keys
+    }
+
+    fn store_keys(store: &MemoryStore) -> Vec<u8> {
+        store
+            .iter_all(Column::Coins, None, None, IterDirection::Forward)
+            .map_ok(|(key, _)| key[0])
+            .try_collect()
+            .unwrap()
+    }
+
+    #[test]
+    fn delete_range_hides_data_source_keys_until_commit() {
+        // Given
+        let (store, view) = range_delete_view();
+
+        // When
+        view.delete_range(Column::Coins, &[2], &[4]).unwrap();
+
+        // Then
+        assert_eq!(view.get(&[2], Column::Coins).unwrap(), None);
+        assert_eq!(
+            view.get(&[4], Column::Coins).unwrap(),
+            Some(Arc::new(vec![4]))
+        );
+        assert!(!view.exists(&[3], Column::Coins).unwrap());
+        assert_eq!(view_keys(&view), vec![1, 4, 5]);
+        assert_eq!(store_keys(&store), vec![1, 2, 3, 4, 5]);
+
+        view.commit().unwrap();
+        assert_eq!(store_keys(&store), vec![1, 4, 5]);
+    }
+
+    #[test]
+    fn clear_column_of_the_dropped_view_keeps_the_data_source() {
+        // Given
+        let (store, view) = range_delete_view();
+
+        // When
+        view.clear_column(Column::Coins).unwrap();
+        let keys_of_the_view = view_keys(&view);
+        drop(view);
+
+        // Then
+        assert!(keys_of_the_view.is_empty());
+        assert_eq!(store_keys(&store), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn clear_column_is_applied_on_commit_under_later_writes() {
+        // Given
+        let (store, view) = range_delete_view();
+        view.clear_column(Column::Coins).unwrap();
+
+        // When
+        view.put(&[3], Column::Coins, Arc::new(vec![30])).unwrap();
+
+        // Then
+        assert_eq!(view_keys(&view), vec![3]);
+        assert_eq!(store_keys(&store), vec![1, 2, 3, 4, 5]);
+        view.commit().unwrap();
+        assert_eq!(store_keys(&store), vec![3]);
+        assert_eq!(
+            store.get(&[3], Column::Coins).unwrap(),
+            Some(Arc::new(vec![30]))
+        );
+    }
+
+    #[test]
+    fn insert_after_delete_range_is_visible_before_and_after_commit() {
+        // Given
+        let (store, view) = range_delete_view();
+        view.delete_range(Column::Coins, &[2], &[5]).unwrap();
+
+        // When
+        view.put(&[3], Column::Coins, Arc::new(vec![30])).unwrap();
+
+        // Then
+        assert_eq!(
+            view.get(&[3], Column::Coins).unwrap(),
+            Some(Arc::new(vec![30]))
+        );
+        assert_eq!(view_keys(&view), vec![1, 3, 5]);
+
+        view.commit().unwrap();
+        assert_eq!(store_keys(&store), vec![1, 3, 5]);
+        assert_eq!(
+            store.get(&[3], Column::Coins).unwrap(),
+            Some(Arc::new(vec![30]))
+        );
+    }
+
+    #[test]
+    fn delete_range_after_insert_removes_it_before_and_after_commit() {
+        // Given
+        let (store, view) = range_delete_view();
+        view.put(&[3], Column::Coins, Arc::new(vec![30])).unwrap();
+        view.put(&[6], Column::Coins, Arc::new(vec![6])).unwrap();
+
+        // When
+        view.delete_range(Column::Coins, &[2], &[5]).unwrap();
+
+        // Then
+        assert_eq!(view.get(&[3], Column::Coins).unwrap(), None);
+        assert_eq!(view_keys(&view), vec![1, 5, 6]);
+
+        view.commit().unwrap();
+        assert_eq!(store_keys(&store), vec![1, 5, 6]);
+    }
+
+    #[test]
+    fn delete_range_is_expanded_into_removals_of_existing_keys() {
+        // Given
+        let (_, view) = range_delete_view();
+        view.put(&[3], Column::Coins, Arc::new(vec![30])).unwrap();
+        view.delete_range(Column::Coins, &[2], &[4]).unwrap();
+        view.delete_range(Column::Coins, &[3], &[9]).unwrap();
+        view.put(&[4], Column::Coins, Arc::new(vec![40])).unwrap();
+
+        // When
+        let writes = view.pending_writes().unwrap();
+
+        // Then
+        assert_eq!(
+            writes,
+            vec![
+                (vec![2], Column::Coins, WriteOperation::Remove),
+                (vec![3], Column::Coins, WriteOperation::Remove),
+                (
+                    vec![4],
+                    Column::Coins,
+                    WriteOperation::Insert(Arc::new(vec![40]))
+                ),
+                (vec![5], Column::Coins, WriteOperation::Remove),
+            ]
+        );
+    }
+
+    #[test]
+    fn delete_range_is_visible_to_the_parent_after_the_nested_commit() {
+        // Given
+        let (store, parent) = range_delete_view();
+        let parent = Arc::new(parent);
+        let child = MemoryTransactionView::new(parent.clone());
+        child.delete_range(Column::Coins, &[1], &[4]).unwrap();
+        child.put(&[2], Column::Coins, Arc::new(vec![20])).unwrap();
+
+        // When
+        child.commit().unwrap();
+
+        // Then
+        assert_eq!(view_keys(&parent), vec![2, 4, 5]);
+        assert_eq!(store_keys(&store), vec![1, 2, 3, 4, 5]);
+        parent.commit().unwrap();
+        assert_eq!(store_keys(&store), vec![2, 4, 5]);
+    }
+
+    #[test]
+    fn commit_chunked_applies_delete_range() {
+        // Given
+        let (store, view) = range_delete_view();
+        view.delete_range(Column::Coins, &[2], &[4]).unwrap();
+        view.put(&[3], Column::Coins, Arc::new(vec![30])).unwrap();
+
+        // When
+        view.commit_chunked(ChunkLimits::default()).unwrap();
+
+        // Then
+        assert_eq!(store_keys(&store), vec![1, 3, 4, 5]);
+        assert!(view.changed_columns().is_empty());
+    }
+
+    #[test]
+    fn get_or_insert_with_checks_view_changes_first() {
+        // Given
+        let store = Arc::new(MemoryStore::default());
+        store
+            .put(&[1], Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+        store
+            .put(&[2], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+        let view = MemoryTransactionView::new(store.clone());
+        view.put(&[1], Column::Metadata, Arc::new(vec![10]))
+            .unwrap();
+        view.delete(&[2], Column::Metadata).unwrap();
+        let mut calls = 0;
+        let mut init = || {
+            calls += 1;
+            vec![20]
+        };
+
+        // When
+        let changed = view
+            .get_or_insert_with(&[1], Column::Metadata, &mut init)
+            .unwrap();
+        let removed = view
+            .get_or_insert_with(&[2], Column::Metadata, &mut init)
+            .unwrap();
+
+        // Then
+        assert_eq!(calls, 1);
+        assert_eq!((changed.to_vec(), removed.to_vec()), (vec![10], vec![20]));
+        assert_eq!(
+            view.get(&[2], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![20]))
+        );
+        assert_eq!(
+            store.get(&[2], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![2]))
+        );
+    }
+
+    #[test]
+    fn update_applies_to_the_value_of_the_view() {
+        // Given
+        let store = Arc::new(MemoryStore::default());
+        store
+            .put(&[1], Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+        let view = MemoryTransactionView::new(store.clone());
+        view.put(&[1], Column::Metadata, Arc::new(vec![10]))
+            .unwrap();
+
+        // When
+        let mut seen = None;
+        let outcome = view
+            .update(&[1], Column::Metadata, &mut |current| {
+                seen = current.map(<[u8]>::to_vec);
+                Some(vec![11])
+            })
+            .unwrap();
+
+        // Then
+        assert_eq!(outcome, UpdateOutcome::Updated);
+        assert_eq!(seen, Some(vec![10]));
+        assert_eq!(
+            view.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![11]))
+        );
+        assert_eq!(
+            store.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+    }
+
+    #[test]
+    fn unchanged_update_does_not_record_a_write() {
+        // Given
+        let store = Arc::new(MemoryStore::default());
+        store
+            .put(&[1], Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+        let view = MemoryTransactionView::new(store);
+
+        // When
+        let same = view
+            .update(&[1], Column::Metadata, &mut |current| {
+                current.map(<[u8]>::to_vec)
+            })
+            .unwrap();
+        let missing = view.update(&[2], Column::Metadata, &mut |_| None).unwrap();
+
+        // Then
+        assert_eq!(
+            (same, missing),
+            (UpdateOutcome::Unchanged, UpdateOutcome::Unchanged)
+        );
+        assert!(view.pending_writes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn iterators_see_the_state_at_their_creation_under_concurrent_writes() {
+        // Given
+        const KEYS: u16 = 2000;
+        let (start, end) = (KEYS / 4, KEYS / 4 * 3);
+        let store = Arc::new(MemoryStore::default());
+        for i in (0..KEYS).step_by(2) {
+            store
+                .put(&i.to_be_bytes(), Column::Coins, Arc::new(vec![0]))
+                .unwrap();
+        }
+        let view = Arc::new(MemoryTransactionView::new(store.clone()));
+        // Reads of the expected state and creations of iterators exclude writes.
+        let writes = Arc::new(Mutex::new(()));
+        let stop = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let writer = std::thread::spawn({
+            let (store, view, writes, stop) =
+                (store.clone(), view.clone(), writes.clone(), stop.clone());
+            move || {
+                let mut round = 0u8;
+                while !stop.load(std::sync::atomic::Ordering::Relaxed) {
+                    round = round.wrapping_add(1);
+                    // Keys inside and outside of the range are inserted and removed
+                    // in both the view and the data source.
+                    for i in 0..KEYS {
+                        let guard = writes.lock().unwrap();
+                        let key = i.to_be_bytes();
+                        let layer: &dyn KeyValueStore<Column = Column> = if i % 2 == 0 {
+                            store.as_ref()
+                        } else {
+                            view.as_ref()
+                        };
+                        if (i ^ u16::from(round)) % 3 == 0 {
+                            layer.delete(&key, Column::Coins).unwrap();
+                        } else {
+                            layer
+                                .put(&key, Column::Coins, Arc::new(vec![round]))
+                                .unwrap();
+                        }
+                        drop(guard);
+                        std::thread::yield_now();
+                    }
+                }
+            }
+        });
+
+        // When
+        let mut results = vec![];
+        for _ in 0..10 {
+            let (expected, iter, mut column_iter) = {
+                let _guard = writes.lock().unwrap();
+                let expected = (start..end)
+                    .filter_map(|i| {
+                        let key = i.to_be_bytes().to_vec();
+                        let value = view.get(&key, Column::Coins).unwrap()?;
+                        Some((key, value))
+                    })
+                    .collect::<Vec<_>>();
+                let iter = view.iter_range(
+                    Column::Coins,
+                    None,
+                    Some(&start.to_be_bytes()),
+                    Some(&end.to_be_bytes()),
+                    IterDirection::Forward,
+                );
+                let column_iter = view.iter_column(
+                    Column::Coins,
+                    None,
+                    Some(&start.to_be_bytes()),
+                    IterDirection::Forward,
+                );
+                (expected, iter, column_iter)
+            };
+            let entries = iter
+                .map(|item| {
+                    std::thread::yield_now();
+                    item.unwrap()
+                })
+                .collect::<Vec<_>>();
+            let mut column_entries = vec![];
+            while let Some(item) = column_iter.next() {
+                let (key, value) = item.unwrap();
+                if key >= end.to_be_bytes().as_slice() {
+                    break
+                }
+                column_entries.push((key.to_vec(), Arc::new(value.to_vec())));
+                std::thread::yield_now();
+            }
+            results.push((expected, entries, column_entries));
+        }
+        stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        writer.join().unwrap();
+
+        // Then
+        for (expected, entries, column_entries) in results {
+            assert_eq!(entries, expected);
+            assert_eq!(column_entries, expected);
+        }
+    }
+
+    #[test]
+    fn has_any_is_false_when_the_view_removes_all_keys_of_the_prefix() {
+        // Given
+        let store = Arc::new(MemoryStore::<OnChain>::default());
+        for key in [[1, 0], [1, 1], [1, 2], [2, 0]] {
+            store.put(&key, Column::Metadata, Arc::new(vec![])).unwrap();
+        }
+        let view = MemoryTransactionView::new(store.clone());
+
+        // When
+        view.delete(&[1, 0], Column::Metadata).unwrap();
+        view.delete_range(Column::Metadata, &[1, 1], &[1, 3])
+            .unwrap();
+
+        // Then
+        assert!(!view.has_any(Column::Metadata, &[1]).unwrap());
+        assert!(view.has_any(Column::Metadata, &[2]).unwrap());
+        assert!(store.has_any(Column::Metadata, &[1]).unwrap());
+    }
+
+    #[test]
+    fn has_any_is_true_for_the_insert_only_in_the_view() {
+        // Given
+        let store = Arc::new(MemoryStore::<OnChain>::default());
+        store
+            .put(&[1, 0], Column::Metadata, Arc::new(vec![]))
+            .unwrap();
+        let view = MemoryTransactionView::new(store.clone());
+        let before = view.has_any(Column::Metadata, &[3]).unwrap();
+
+        // When
+        view.put(&[3, 0], Column::Metadata, Arc::new(vec![]))
+            .unwrap();
+
+        // Then
+        assert!(!before);
+        assert!(view.has_any(Column::Metadata, &[3]).unwrap());
+        assert!(!store.has_any(Column::Metadata, &[3]).unwrap());
+    }
+
+    #[test]
+    fn has_any_with_the_empty_prefix_checks_the_column_is_non_empty() {
+        // Given
+        let store = Arc::new(MemoryStore::<OnChain>::default());
+        store.put(&[1], Column::Metadata, Arc::new(vec![])).unwrap();
+        let view = MemoryTransactionView::new(store);
+
+        // When
+        let with_source_key = view.has_any(Column::Metadata, &[]).unwrap();
+        view.delete(&[1], Column::Metadata).unwrap();
+        let removed = view.has_any(Column::Metadata, &[]).unwrap();
+        view.put(&[2], Column::Metadata, Arc::new(vec![])).unwrap();
+        let inserted = view.has_any(Column::Metadata, &[]).unwrap();
+
+        // Then
+        assert!(with_source_key);
+        assert!(!removed);
+        assert!(inserted);
+        assert!(!view.has_any(Column::Coins, &[]).unwrap());
+    }
+
+    #[test]
+    fn has_any_of_the_view_with_conflict_detection_respects_removals() {
+        // Given
+        let store = Arc::new(MemoryStore::<OnChain>::default());
+        store
+            .put(&[1, 0], Column::Metadata, Arc::new(vec![]))
+            .unwrap();
+        let view = MemoryTransactionView::with_conflict_detection(store);
+
+        // When
+        view.delete(&[1, 0], Column::Metadata).unwrap();
+
+        // Then
+        assert!(!view.has_any(Column::Metadata, &[1]).unwrap());
+    }
+
+    fn limited_view(limits: WriteLimits) -> MemoryTransactionView {
+        MemoryTransactionView::new(Arc::new(MemoryStore::default())).with_limits(limits)
+    }
+
+    fn limit_exceeded(result: StorageResult<()>) -> Option<(LimitKind, usize, usize)> {
+        match result.map_err(DatabaseError::from) {
+            Err(DatabaseError::LimitExceeded {
+                kind,
+                limit,
+                attempted,
+            }) => Some((kind, limit, attempted)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn writes_beyond_the_limit_of_keys_fail_immediately() {
+        // Given
+        let view = limited_view(WriteLimits {
+            max_keys: Some(2),
+            ..Default::default()
+        });
+        view.put(&[1], Column::Metadata, Arc::new(vec![1])).unwrap();
+        view.delete(&[2], Column::Coins).unwrap();
+
+        // When
+        let put = view.put(&[3], Column::Metadata, Arc::new(vec![3]));
+        let delete = view.delete(&[3], Column::Metadata);
+        let overwrite = view.put(&[2], Column::Coins, Arc::new(vec![2]));
+
+        // Then
+        assert_eq!(limit_exceeded(put), Some((LimitKind::Keys, 2, 3)));
+        assert_eq!(limit_exceeded(delete), Some((LimitKind::Keys, 2, 3)));
+        overwrite.unwrap();
+        assert_eq!(view.get(&[3], Column::Metadata).unwrap(), None);
+        assert_eq!(view.usage(), WriteUsage { keys: 2, bytes: 2 });
+    }
+
+    #[test]
+    fn overwrites_and_deletes_are_not_counted_twice_in_staged_bytes() {
+        // Given
+        let view = limited_view(WriteLimits {
+            max_bytes: Some(10),
+            ..Default::default()
+        });
+        view.put(&[1], Column::Metadata, Arc::new(vec![1; 6]))
+            .unwrap();
+
+        // When
+        view.put(&[1], Column::Metadata, Arc::new(vec![1; 8]))
+            .unwrap();
+        let over = view.put(&[2], Column::Metadata, Arc::new(vec![2; 3]));
+        view.delete(&[1], Column::Metadata).unwrap();
+        let after_delete = view.put(&[2], Column::Metadata, Arc::new(vec![2; 10]));
+
+        // Then
+        assert_eq!(limit_exceeded(over), Some((LimitKind::Bytes, 10, 11)));
+        after_delete.unwrap();
+        assert_eq!(view.usage(), WriteUsage { keys: 2, bytes: 10 });
+    }
+
+    #[test]
+    fn values_larger_than_the_limit_are_rejected() {
+        // Given
+        let view = limited_view(WriteLimits {
+            max_value_size: Some(4),
+            ..Default::default()
+        });
+
+        // When
+        let put = view.put(&[1], Column::Metadata, Arc::new(vec![1; 5]));
+        let batch = view.batch_write(
+            &mut [
+                (
+                    vec![2],
+                    Column::Metadata,
+                    WriteOperation::Insert(Arc::new(vec![2])),
+                ),
+                (
+                    vec![3],
+                    Column::Metadata,
+                    WriteOperation::Insert(Arc::new(vec![3; 5])),
+                ),
+            ]
+            .into_iter(),
+        );
+        let fits = view.put(&[4], Column::Metadata, Arc::new(vec![4; 4]));
+
+        // Then
+        assert_eq!(limit_exceeded(put), Some((LimitKind::ValueSize, 4, 5)));
+        assert_eq!(limit_exceeded(batch), Some((LimitKind::ValueSize, 4, 5)));
+        fits.unwrap();
+        assert_eq!(
+            view.pending_writes().unwrap(),
+            vec![(
+                vec![4],
+                Column::Metadata,
+                WriteOperation::Insert(Arc::new(vec![4; 4]))
+            )]
+        );
+    }
+
+    #[test]
+    fn batch_beyond_the_limit_is_not_applied_partially() {
+        // Given
+        let view = limited_view(WriteLimits {
+            max_keys: Some(2),
+            ..Default::default()
+        });
+        view.put(&[1], Column::Metadata, Arc::new(vec![1])).unwrap();
+        let batch = |keys: &[u8]| {
+            keys.iter()
+                .map(|key| (vec![*key], Column::Metadata, WriteOperation::Remove))
+                .collect::<Vec<_>>()
+        };
+
+        // When
+        let over = view.batch_write(&mut batch(&[1, 2, 3]).into_iter());
+        let fits = view.batch_write(&mut batch(&[1, 2, 2]).into_iter());
+
+        // Then
+        assert_eq!(limit_exceeded(over), Some((LimitKind::Keys, 2, 3)));
+        fits.unwrap();
+        assert_eq!(view.usage(), WriteUsage { keys: 2, bytes: 0 });
+    }
+
+    #[test]
+    fn commit_releases_the_usage_of_the_view() {
+        // Given
+        let store = Arc::new(MemoryStore::default());
+        let view = MemoryTransactionView::new(store.clone()).with_limits(WriteLimits {
+            max_keys: Some(1),
+            ..Default::default()
+        });
+        view.put(&[1], Column::Metadata, Arc::new(vec![1])).unwrap();
+
+        // When
+        view.commit().unwrap();
+
+        // Then
+        assert_eq!(view.usage(), WriteUsage::default());
+        view.put(&[2], Column::Metadata, Arc::new(vec![2])).unwrap();
+        assert_eq!(
+            store.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+    }
+
+    #[test]
+    fn unlimited_view_behaves_like_the_view_without_limits() {
+        // Given
+        let writes = |view: &MemoryTransactionView| {
+            for i in 0..1_000u32 {
+                view.put(&i.to_be_bytes(), Column::Metadata, Arc::new(vec![0; 1024]))
+                    .unwrap();
+            }
+            view.delete_range(
+                Column::Metadata,
+                &10u32.to_be_bytes(),
+                &20u32.to_be_bytes(),
+            )
+            .unwrap();
+            view.multi_delete(Column::Metadata, &[30u32.to_be_bytes().to_vec()])
+                .unwrap();
+            view.pending_writes().unwrap()
+        };
+        let plain = MemoryTransactionView::new(Arc::new(MemoryStore::default()));
+        let unlimited = limited_view(WriteLimits::default());
+
+        // When
+        let plain_writes = writes(&plain);
+        let unlimited_writes = writes(&unlimited);
+
+        // Then
+        assert_eq!(plain_writes, unlimited_writes);
+        assert_eq!(
+            unlimited.usage(),
+            WriteUsage {
+                keys: 990,
+                bytes: 989 * 1024
+            }
         );
+        unlimited.commit().unwrap();
     }
 }
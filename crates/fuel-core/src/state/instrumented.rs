@@ -0,0 +1,903 @@
+//! The storage wrapper that records the metrics of operations of the inner storage.
+//! The storage is wrapped only when metrics are enabled, so disabled metrics cost nothing.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Result as DatabaseResult,
+    },
+    state::{
+        notifying::StorageEvent,
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        EngineStats,
+        IngestStats,
+        IterDirection,
+        ReadView,
+        TransactableStorage,
+        WriteDurability,
+        WritePressure,
+    },
+};
+use fuel_core_metrics::storage_metrics::{
+    storage_metrics,
+    ColumnLabels,
+    DatabaseLabels,
+    OperationLabels,
+};
+use fuel_core_storage::{
+    iter::{
+        BoxedIter,
+        IntoBoxedIter,
+        IteratorableStore,
+    },
+    kv_store::{
+        BatchWriteResult,
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Result as StorageResult,
+};
+use prometheus_client::metrics::{
+    counter::Counter,
+    histogram::Histogram,
+};
+use std::{
+    path::Path,
+    sync::Mutex,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+use tokio::sync::broadcast;
+
+/// The engine statistics are recorded on batch writes at most once per interval,
+/// because their collection reads properties of all columns.
+const ENGINE_STATS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The counter and the latency histogram of the operation over the column.
+#[derive(Debug)]
+struct OperationMetrics {
+    count: Counter,
+    duration: Histogram,
+}
+
+impl OperationMetrics {
+    fn new(
+        database: &'static str,
+        column: &'static str,
+        operation: &'static str,
+    ) -> Self {
+        let labels = OperationLabels {
+            database,
+            column,
+            operation,
+        };
+        let metrics = storage_metrics();
+        Self {
+            count: metrics.operations.get_or_create(&labels).clone(),
+            duration: metrics.operation_duration.get_or_create(&labels).clone(),
+        }
+    }
+
+    fn record<T>(&self, f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let result = f();
+        self.count.inc();
+        self.duration.observe(started.elapsed().as_secs_f64());
+        result
+    }
+}
+
+/// The metrics of the column. Handles are resolved once, so operations don't look up labels.
+#[derive(Debug)]
+struct ColumnMetrics {
+    get: OperationMetrics,
+    put: OperationMetrics,
+    delete: OperationMetrics,
+    exists: OperationMetrics,
+    iter: OperationMetrics,
+    iter_first_item_duration: Histogram,
+    iter_items: Histogram,
+}
+
+impl ColumnMetrics {
+    fn new(database: &'static str, column: &'static str) -> Self {
+        let labels = ColumnLabels { database, column };
+        let metrics = storage_metrics();
+        Self {
+            get: OperationMetrics::new(database, column, "get"),
+            put: OperationMetrics::new(database, column, "put"),
+            delete: OperationMetrics::new(database, column, "delete"),
+            exists: OperationMetrics::new(database, column, "exists"),
+            iter: OperationMetrics::new(database, column, "iter"),
+            iter_first_item_duration: metrics
+                .iter_first_item_duration
+                .get_or_create(&labels)
+                .clone(),
+            iter_items: metrics.iter_items.get_or_create(&labels).clone(),
+        }
+    }
+}
+
+/// The storage that delegates to the inner storage and records the metrics of operations.
+/// Operations are grouped the same way as by the [`FaultyStorage`](super::faulty::FaultyStorage):
+/// all reads of the value are `get`, all writes of the value are `put`, and removals are `delete`.
+/// The iterator records the time to the first item, and the number of items with the total
+/// duration when it is dropped.
+#[derive(Debug)]
+pub struct InstrumentedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    inner: DataSourceInner<Description::Column>,
+    /// The metrics of columns indexed by the [`StorageColumn::as_usize`].
+    columns: Vec<ColumnMetrics>,
+    batch_write_entries: Histogram,
+    batch_write_duration: Histogram,
+    batch_write_bytes: Histogram,
+    /// The time when the engine statistics were recorded the last time.
+    engine_stats_recorded: Mutex<Option<Instant>>,
+}
+
+impl<Description> InstrumentedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Wraps the `inner` storage. Metrics are labeled with the name of the database.
+    pub fn new<D>(inner: D) -> Self
+    where
+        D: Into<DataSource<Description>>,
+    {
+        let database = Description::name();
+        let mut columns = enum_iterator::all::<Description::Column>().collect::<Vec<_>>();
+        columns.sort_by_key(|column| column.as_usize());
+        let labels = DatabaseLabels { database };
+        let metrics = storage_metrics();
+
+        Self {
+            inner: inner.into().0,
+            columns: columns
+                .into_iter()
+                .map(|column| ColumnMetrics::new(database, column.name()))
+                .collect(),
+            batch_write_entries: metrics
+                .batch_write_entries
+                .get_or_create(&labels)
+                .clone(),
+            batch_write_duration: metrics
+                .batch_write_duration
+                .get_or_create(&labels)
+                .clone(),
+            batch_write_bytes: metrics.batch_write_bytes.get_or_create(&labels).clone(),
+            engine_stats_recorded: Mutex::new(None),
+        }
+    }
+
+    /// Records the engine statistics if they weren't recorded during the last interval.
+    fn maybe_record_engine_stats(&self) {
+        {
+            let mut recorded = self
+                .engine_stats_recorded
+                .lock()
+                .expect("poisoned lock on the time of engine statistics");
+            if recorded.is_some_and(|at| at.elapsed() < ENGINE_STATS_INTERVAL) {
+                return
+            }
+            *recorded = Some(Instant::now());
+        }
+        if let Err(err) = self.engine_stats() {
+            tracing::debug!(
+                "Unable to record the engine statistics of the `{}` database: {:?}",
+                Description::name(),
+                err
+            );
+        }
+    }
+
+    fn record_engine_stats(stats: &EngineStats) {
+        let gauge = |value: u64| i64::try_from(value).unwrap_or(i64::MAX);
+        let labels = DatabaseLabels {
+            database: Description::name(),
+        };
+        let metrics = storage_metrics();
+        for (family, value) in [
+            (&metrics.engine_block_cache_hits, stats.block_cache_hits),
+            (&metrics.engine_block_cache_misses, stats.block_cache_misses),
+            (&metrics.engine_stall_micros, stats.stall_micros),
+            (&metrics.engine_block_cache_usage, stats.block_cache_usage),
+            (&metrics.engine_memtable_usage, stats.memtable_usage),
+            (&metrics.engine_memory_budget, stats.memory_budget),
+            (
+                &metrics.engine_running_compactions,
+                stats.running_compactions,
+            ),
+            (
+                &metrics.engine_writes_stopped,
+                u64::from(stats.writes_stopped),
+            ),
+        ] {
+            family.get_or_create(&labels).set(gauge(value));
+        }
+        for column in &stats.columns {
+            let labels = ColumnLabels {
+                database: Description::name(),
+                column: column.column,
+            };
+            for (family, value) in [
+                (
+                    &metrics.engine_pending_compaction_bytes,
+                    column.pending_compaction_bytes,
+                ),
+                (
+                    &metrics.engine_immutable_memtables,
+                    column.immutable_memtables,
+                ),
+                (&metrics.engine_level0_files, column.level0_files),
+            ] {
+                family.get_or_create(&labels).set(gauge(value));
+            }
+        }
+    }
+
+    fn column(&self, column: Description::Column) -> &ColumnMetrics {
+        &self.columns[column.as_usize()]
+    }
+
+    fn instrumented_iter<'a, T>(
+        &'a self,
+        column: Description::Column,
+        iter: impl FnOnce() -> BoxedIter<'a, StorageResult<T>>,
+    ) -> BoxedIter<'a, StorageResult<T>>
+    where
+        T: 'a,
+    {
+        InstrumentedIter {
+            metrics: self.column(column),
+            created: Instant::now(),
+            items: 0,
+            first_item_recorded: false,
+            inner: iter(),
+        }
+        .into_boxed()
+    }
+}
+
+struct InstrumentedIter<'a, I> {
+    metrics: &'a ColumnMetrics,
+    created: Instant,
+    items: u64,
+    first_item_recorded: bool,
+    inner: I,
+}
+
+impl<'a, I> Iterator for InstrumentedIter<'a, I>
+where
+    I: Iterator,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next();
+        if item.is_some() {
+            if !self.first_item_recorded {
+                self.first_item_recorded = true;
+                self.metrics
+                    .iter_first_item_duration
+                    .observe(self.created.elapsed().as_secs_f64());
+            }
+            self.items = self.items.saturating_add(1);
+        }
+        item
+    }
+}
+
+impl<'a, I> Drop for InstrumentedIter<'a, I> {
+    fn drop(&mut self) {
+        let iter = &self.metrics.iter;
+        iter.count.inc();
+        iter.duration.observe(self.created.elapsed().as_secs_f64());
+        self.metrics.iter_items.observe(self.items as f64);
+    }
+}
+
+impl<Description> KeyValueStore for InstrumentedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        self.column(column)
+            .put
+            .record(|| self.inner.put(key, column, value))
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        self.column(column)
+            .put
+            .record(|| self.inner.replace(key, column, value))
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        self.column(column)
+            .put
+            .record(|| self.inner.write(key, column, buf))
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.column(column)
+            .delete
+            .record(|| self.inner.take(key, column))
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        self.column(column)
+            .delete
+            .record(|| self.inner.delete(key, column))
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        self.column(column)
+            .put
+            .record(|| self.inner.merge(key, column, operand))
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        self.column(column)
+            .exists
+            .record(|| self.inner.exists(key, column))
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        self.column(column)
+            .get
+            .record(|| self.inner.size_of_value(key, column))
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.column(column)
+            .get
+            .record(|| self.inner.get(key, column))
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        self.column(column)
+            .get
+            .record(|| self.inner.get_with(key, column, f))
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        self.column(column)
+            .get
+            .record(|| self.inner.multi_get(keys, column))
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        self.column(column)
+            .put
+            .record(|| self.inner.compare_and_swap(key, column, expected, new))
+    }
+
+    fn get_or_insert_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        init: &mut dyn FnMut() -> Vec<u8>,
+    ) -> StorageResult<Value> {
+        self.column(column)
+            .put
+            .record(|| self.inner.get_or_insert_with(key, column, init))
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        self.column(column)
+            .put
+            .record(|| self.inner.update(key, column, f))
+    }
+
+    fn read(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &mut [u8],
+    ) -> StorageResult<Option<usize>> {
+        self.column(column)
+            .get
+            .record(|| self.inner.read(key, column, buf))
+    }
+}
+
+impl<Description> IteratorableStore for InstrumentedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.instrumented_iter(column, || {
+            self.inner.iter_all(column, prefix, start, direction)
+        })
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.instrumented_iter(column, || {
+            self.inner.iter_range(column, prefix, start, end, direction)
+        })
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.instrumented_iter(column, || {
+            self.inner.iter_keys(column, prefix, start, direction)
+        })
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        self.column(column)
+            .delete
+            .record(|| self.inner.delete_all(column, prefix))
+    }
+}
+
+impl<Description> BatchOperations for InstrumentedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for InstrumentedStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        let started = Instant::now();
+        let mut stats = BatchWriteResult::default();
+        let result = self.inner.batch_write_with(
+            &mut entries.inspect(|(key, _, op)| stats.record(key, op)),
+            durability,
+        );
+        self.batch_write_duration
+            .observe(started.elapsed().as_secs_f64());
+        self.batch_write_entries
+            .observe(stats.inserted.saturating_add(stats.removed) as f64);
+        self.batch_write_bytes.observe(stats.bytes_written as f64);
+        self.maybe_record_engine_stats();
+        result
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        self.inner.flush()
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        self.inner.column_stats(column)
+    }
+
+    fn engine_stats(&self) -> DatabaseResult<EngineStats> {
+        let stats = self.inner.engine_stats()?;
+        Self::record_engine_stats(&stats);
+        Ok(stats)
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        self.inner.write_pressure()
+    }
+
+    fn size_of_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> DatabaseResult<u64> {
+        self.inner.size_of_range(column, start, end)
+    }
+
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        self.inner.checkpoint(path)
+    }
+
+    fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()> {
+        self.inner.backup_to(backup_dir)
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        self.inner.compact(column, range)
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        self.inner.clear_column(column)
+    }
+
+    fn multi_delete(
+        &self,
+        column: Self::Column,
+        keys: &[Vec<u8>],
+    ) -> DatabaseResult<usize> {
+        self.column(column)
+            .delete
+            .record(|| self.inner.multi_delete(column, keys))
+    }
+
+    fn delete_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: &[u8],
+    ) -> DatabaseResult<()> {
+        self.column(column)
+            .delete
+            .record(|| self.inner.delete_range(column, start, end))
+    }
+
+    fn ingest_sorted(
+        &self,
+        column: Self::Column,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<IngestStats> {
+        self.inner.ingest_sorted(column, entries)
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        self.inner.snapshot()
+    }
+
+    fn read_snapshot(&self) -> DatabaseResult<Box<dyn ReadView<Column = Self::Column>>>
+    where
+        Self::Column: 'static,
+    {
+        self.inner.read_snapshot()
+    }
+
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        self.inner.subscribe(column, prefix)
+    }
+
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.inner.replay_change_log(from_seq, into)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.inner.truncate_change_log(seq)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        self.inner.freeze_columns(columns)
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        self.inner.unfreeze_columns(columns)
+    }
+
+    fn begin_shutdown(&self) {
+        self.inner.begin_shutdown()
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        self.inner.finish_shutdown()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{
+            database_description::{
+                on_chain::OnChain,
+                test::{
+                    MergeColumn,
+                    MergeDatabase,
+                },
+            },
+            Database,
+        },
+        state::in_memory::{
+            memory_store::MemoryStore,
+            transaction::MemoryTransactionView,
+        },
+    };
+    use fuel_core_storage::{
+        column::Column,
+        tables::ContractsRawCode,
+        transactional::Transaction,
+        StorageAsMut,
+        StorageAsRef,
+    };
+    use fuel_core_types::{
+        fuel_tx::Contract,
+        fuel_types::ContractId,
+    };
+    use prometheus_client::encoding::text::encode;
+    use std::sync::Arc;
+
+    fn scrape() -> String {
+        let mut encoded = String::new();
+        encode(&mut encoded, &storage_metrics().registry).unwrap();
+        encoded
+    }
+
+    fn assert_metric(scraped: &str, metric: &str, value: &str) {
+        let line = format!("{metric} {value}");
+        assert!(
+            scraped.lines().any(|scraped| scraped == line),
+            "The `{line}` is not found in:\n{scraped}"
+        );
+    }
+
+    #[test]
+    fn scripted_workload_is_recorded_per_column_and_operation() {
+        // Given
+        let store = InstrumentedStorage::<MergeDatabase>::new(Arc::new(MemoryStore::<
+            MergeDatabase,
+        >::default(
+        )));
+
+        // When
+        for i in 0..3u8 {
+            store
+                .put(&[i], MergeColumn::Logs, Arc::new(vec![i]))
+                .unwrap();
+        }
+        store.get(&[0], MergeColumn::Logs).unwrap();
+        store.get(&[7], MergeColumn::Metadata).unwrap();
+        store.exists(&[1], MergeColumn::Logs).unwrap();
+        store.delete(&[2], MergeColumn::Logs).unwrap();
+        let items = store
+            .iter_all(MergeColumn::Logs, None, None, IterDirection::Forward)
+            .count();
+        store
+            .batch_write(&mut (0..4u8).map(|i| {
+                (
+                    vec![i],
+                    MergeColumn::Metadata,
+                    WriteOperation::Insert(Arc::new(vec![i])),
+                )
+            }))
+            .unwrap();
+
+        // Then
+        assert_eq!(items, 2);
+        let scraped = scrape();
+        let logs = r#"database="merge",column="Logs""#;
+        for (operation, count) in [("put", 3), ("get", 1), ("exists", 1), ("delete", 1)] {
+            let labels = format!(r#"{{{logs},operation="{operation}"}}"#);
+            assert_metric(
+                &scraped,
+                &format!("storage_operations_total{labels}"),
+                &count.to_string(),
+            );
+            assert_metric(
+                &scraped,
+                &format!("storage_operation_duration_s_count{labels}"),
+                &count.to_string(),
+            );
+        }
+        assert_metric(
+            &scraped,
+            r#"storage_operations_total{database="merge",column="Metadata",operation="get"}"#,
+            "1",
+        );
+        assert_metric(
+            &scraped,
+            &format!(r#"storage_operations_total{{{logs},operation="iter"}}"#),
+            "1",
+        );
+        assert_metric(
+            &scraped,
+            &format!("storage_iter_items_sum{{{logs}}}"),
+            "2.0",
+        );
+        assert_metric(
+            &scraped,
+            &format!("storage_iter_first_item_duration_s_count{{{logs}}}"),
+            "1",
+        );
+        assert_metric(
+            &scraped,
+            r#"storage_batch_write_entries_sum{database="merge"}"#,
+            "4.0",
+        );
+        assert_metric(
+            &scraped,
+            r#"storage_batch_write_duration_s_count{database="merge"}"#,
+            "1",
+        );
+    }
+
+    #[test]
+    fn transactions_over_instrumented_storage_are_forwarded() {
+        // Given
+        let store = Arc::new(MemoryStore::<OnChain>::default());
+        let database = Database::new(store.clone()).with_metrics();
+        let contract_id = ContractId::from([1; 32]);
+        let code = vec![1, 2, 3];
+
+        // When
+        let mut transaction = database.transaction();
+        transaction
+            .as_mut()
+            .storage_as_mut::<ContractsRawCode>()
+            .insert(&contract_id, Contract::from(code.clone()).as_ref())
+            .unwrap();
+        transaction.commit().unwrap();
+
+        // Then
+        let stored = database
+            .storage::<ContractsRawCode>()
+            .get(&contract_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.as_ref().as_ref(), code.as_slice());
+        assert!(store
+            .get(contract_id.as_ref(), Column::ContractsRawCode)
+            .unwrap()
+            .is_some());
+        let view = MemoryTransactionView::<OnChain>::new(Arc::new(
+            InstrumentedStorage::<OnChain>::new(store),
+        ));
+        assert!(view
+            .exists(contract_id.as_ref(), Column::ContractsRawCode)
+            .unwrap());
+    }
+
+    #[test]
+    fn engine_stats_are_recorded_as_gauges() {
+        use crate::{
+            database::database_description::relayer::Relayer,
+            state::ColumnEngineStats,
+        };
+
+        // Given
+        // The relayer database isn't instrumented by other tests, so its gauges don't race.
+        let stats = EngineStats {
+            block_cache_hits: 5,
+            block_cache_misses: 2,
+            stall_micros: 10,
+            block_cache_usage: 1024,
+            memtable_usage: 512,
+            memory_budget: 4096,
+            running_compactions: 1,
+            writes_stopped: true,
+            columns: vec![ColumnEngineStats {
+                column: "Events",
+                pending_compaction_bytes: 4096,
+                immutable_memtables: 2,
+                level0_files: 3,
+            }],
+        };
+
+        // When
+        InstrumentedStorage::<Relayer>::record_engine_stats(&stats);
+
+        // Then
+        let scraped = scrape();
+        let database = r#"{database="relayer"}"#;
+        for (metric, value) in [
+            ("storage_engine_block_cache_hits", "5"),
+            ("storage_engine_block_cache_misses", "2"),
+            ("storage_engine_stall_micros", "10"),
+            ("storage_engine_block_cache_usage_bytes", "1024"),
+            ("storage_engine_memtable_usage_bytes", "512"),
+            ("storage_engine_memory_budget_bytes", "4096"),
+            ("storage_engine_running_compactions", "1"),
+            ("storage_engine_writes_stopped", "1"),
+        ] {
+            assert_metric(&scraped, &format!("{metric}{database}"), value);
+        }
+        let column = r#"{database="relayer",column="Events"}"#;
+        for (metric, value) in [
+            ("storage_engine_pending_compaction_bytes", "4096"),
+            ("storage_engine_immutable_memtables", "2"),
+            ("storage_engine_level0_files", "3"),
+        ] {
+            assert_metric(&scraped, &format!("{metric}{column}"), value);
+        }
+    }
+
+    #[test]
+    fn in_memory_database_has_empty_engine_stats() {
+        // Given
+        let database = Database::<OnChain>::in_memory().with_metrics();
+
+        // When
+        let stats = database.engine_stats().unwrap();
+
+        // Then
+        assert_eq!(stats, EngineStats::default());
+    }
+}
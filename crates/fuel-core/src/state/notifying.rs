@@ -0,0 +1,777 @@
+//! The storage wrapper that notifies subscribers about committed changes of keys,
+//! so services inside the node react to changes instead of polling the storage.
+//!
+//! Writes of the transaction stay in the [`MemoryTransactionView`] until the commit,
+//! so subscribers are notified only about committed changes, in the order of commits.
+//! Each subscriber has its own bounded channel. The slow subscriber doesn't block commits:
+//! it loses the oldest events and receives [`broadcast::error::RecvError::Lagged`]
+//! with the number of lost events.
+//!
+//! [`MemoryTransactionView`]: crate::state::in_memory::transaction::MemoryTransactionView
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::{
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        EngineStats,
+        IterDirection,
+        ReadView,
+        TransactableStorage,
+        WriteDurability,
+        WritePressure,
+    },
+};
+use fuel_core_storage::{
+    iter::{
+        BoxedIter,
+        IteratorableStore,
+    },
+    kv_store::{
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Result as StorageResult,
+};
+use std::{
+    path::Path,
+    sync::{
+        Mutex,
+        RwLock,
+    },
+};
+use tokio::sync::broadcast;
+
+/// The kind of the committed change of the key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageEventKind {
+    /// The value of the key is inserted, updated, or merged.
+    Upsert,
+    /// The key is removed. The removal is notified even if the key didn't exist,
+    /// because the batch of writes doesn't know it.
+    Delete,
+}
+
+/// The committed change of the key of the column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageEvent<Column> {
+    pub column: Column,
+    pub key: Vec<u8>,
+    pub kind: StorageEventKind,
+}
+
+/// The subscription to changes of keys of the column with the prefix.
+#[derive(Debug)]
+struct Subscription<Column> {
+    column: Column,
+    prefix: Vec<u8>,
+    sender: broadcast::Sender<StorageEvent<Column>>,
+}
+
+/// The storage that delegates to the inner storage and notifies subscribers about
+/// successful writes. The failed write notifies nobody.
+#[derive(Debug)]
+pub struct NotifyingStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    inner: DataSourceInner<Description::Column>,
+    /// The number of events buffered for each subscriber.
+    capacity: usize,
+    subscriptions: RwLock<Vec<Subscription<Description::Column>>>,
+    /// Writes are serialized with their notifications, so events follow the order of writes.
+    write_lock: Mutex<()>,
+}
+
+impl<Description> NotifyingStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Wraps the `inner` storage. Each subscriber buffers up to `capacity` events.
+    pub fn new<D>(inner: D, capacity: usize) -> Self
+    where
+        D: Into<DataSource<Description>>,
+    {
+        Self {
+            inner: inner.into().0,
+            capacity: capacity.max(1),
+            subscriptions: RwLock::new(vec![]),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    fn subscriptions(
+        &self,
+    ) -> std::sync::RwLockReadGuard<'_, Vec<Subscription<Description::Column>>> {
+        self.subscriptions.read().expect("poisoned")
+    }
+
+    /// Returns the prefixes subscribed to changes of the `column`.
+    fn prefixes(&self, column: Description::Column) -> Vec<Vec<u8>> {
+        self.subscriptions()
+            .iter()
+            .filter(|subscription| subscription.column == column)
+            .map(|subscription| subscription.prefix.clone())
+            .collect()
+    }
+
+    fn is_subscribed(&self, column: Description::Column, key: &[u8]) -> bool {
+        self.subscriptions().iter().any(|subscription| {
+            subscription.column == column && key.starts_with(&subscription.prefix)
+        })
+    }
+
+    /// Sends `events` to matching subscribers and drops subscriptions without receivers.
+    fn notify(&self, events: Vec<StorageEvent<Description::Column>>) {
+        if events.is_empty() {
+            return
+        }
+        let mut closed = false;
+        for subscription in self.subscriptions().iter() {
+            for event in &events {
+                if event.column == subscription.column
+                    && event.key.starts_with(&subscription.prefix)
+                    && subscription.sender.send(event.clone()).is_err()
+                {
+                    closed = true;
+                    break
+                }
+            }
+        }
+        if closed {
+            self.subscriptions
+                .write()
+                .expect("poisoned")
+                .retain(|subscription| subscription.sender.receiver_count() > 0);
+        }
+    }
+
+    /// Performs the `write` of the `key` and notifies about it if the write succeeds.
+    fn notified<T>(
+        &self,
+        column: Description::Column,
+        key: &[u8],
+        kind: StorageEventKind,
+        write: impl FnOnce() -> StorageResult<T>,
+    ) -> StorageResult<T> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        let result = write()?;
+        if self.is_subscribed(column, key) {
+            self.notify(vec![StorageEvent {
+                column,
+                key: key.to_vec(),
+                kind,
+            }]);
+        }
+        Ok(result)
+    }
+
+    /// Returns the subscribed keys of the `column` with the `prefix`,
+    /// so their removal by the range can be notified.
+    fn subscribed_keys(
+        &self,
+        column: Description::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<Vec<Vec<u8>>> {
+        let mut keys = vec![];
+        for subscribed in self.prefixes(column) {
+            // The narrower of two prefixes selects keys that match both.
+            let prefix = match prefix {
+                Some(prefix) if prefix.starts_with(&subscribed) => prefix,
+                Some(prefix) if !subscribed.starts_with(prefix) => continue,
+                _ => subscribed.as_slice(),
+            };
+            for key in
+                self.inner
+                    .iter_keys(column, Some(prefix), None, IterDirection::Forward)
+            {
+                keys.push(key?);
+            }
+        }
+        keys.sort();
+        keys.dedup();
+        Ok(keys)
+    }
+
+    fn removed(
+        column: Description::Column,
+        keys: Vec<Vec<u8>>,
+    ) -> Vec<StorageEvent<Description::Column>> {
+        keys.into_iter()
+            .map(|key| StorageEvent {
+                column,
+                key,
+                kind: StorageEventKind::Delete,
+            })
+            .collect()
+    }
+}
+
+impl<Description> KeyValueStore for NotifyingStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        self.notified(column, key, StorageEventKind::Upsert, || {
+            self.inner.put(key, column, value)
+        })
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        self.notified(column, key, StorageEventKind::Upsert, || {
+            self.inner.replace(key, column, value)
+        })
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        self.notified(column, key, StorageEventKind::Upsert, || {
+            self.inner.write(key, column, buf)
+        })
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.notified(column, key, StorageEventKind::Delete, || {
+            self.inner.take(key, column)
+        })
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        self.notified(column, key, StorageEventKind::Delete, || {
+            self.inner.delete(key, column)
+        })
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        self.notified(column, key, StorageEventKind::Upsert, || {
+            self.inner.merge(key, column, operand)
+        })
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        self.inner.exists(key, column)
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        self.inner.size_of_value(key, column)
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.inner.get(key, column)
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        self.inner.get_with(key, column, f)
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        self.inner.multi_get(keys, column)
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        let result = self.inner.compare_and_swap(key, column, expected, new)?;
+        if result.is_ok() && self.is_subscribed(column, key) {
+            self.notify(vec![StorageEvent {
+                column,
+                key: key.to_vec(),
+                kind: StorageEventKind::Upsert,
+            }]);
+        }
+        Ok(result)
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        let outcome = self.inner.update(key, column, f)?;
+        let kind = match outcome {
+            UpdateOutcome::Unchanged => None,
+            UpdateOutcome::Inserted | UpdateOutcome::Updated => {
+                Some(StorageEventKind::Upsert)
+            }
+            UpdateOutcome::Removed => Some(StorageEventKind::Delete),
+        };
+        if let Some(kind) = kind {
+            if self.is_subscribed(column, key) {
+                self.notify(vec![StorageEvent {
+                    column,
+                    key: key.to_vec(),
+                    kind,
+                }]);
+            }
+        }
+        Ok(outcome)
+    }
+
+    fn read(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &mut [u8],
+    ) -> StorageResult<Option<usize>> {
+        self.inner.read(key, column, buf)
+    }
+}
+
+impl<Description> IteratorableStore for NotifyingStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.inner.iter_all(column, prefix, start, direction)
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.inner.iter_range(column, prefix, start, end, direction)
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.inner.iter_keys(column, prefix, start, direction)
+    }
+
+    fn has_any(&self, column: Self::Column, prefix: &[u8]) -> StorageResult<bool> {
+        self.inner.has_any(column, prefix)
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        let keys = self.subscribed_keys(column, prefix)?;
+        let deleted = self.inner.delete_all(column, prefix)?;
+        self.notify(Self::removed(column, keys));
+        Ok(deleted)
+    }
+}
+
+impl<Description> BatchOperations for NotifyingStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for NotifyingStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        let mut events = vec![];
+        self.inner.batch_write_with(
+            &mut entries.inspect(|(key, column, op)| {
+                if self.is_subscribed(*column, key) {
+                    let kind = match op {
+                        WriteOperation::Insert(_) | WriteOperation::Merge(_) => {
+                            StorageEventKind::Upsert
+                        }
+                        WriteOperation::Remove => StorageEventKind::Delete,
+                    };
+                    events.push(StorageEvent {
+                        column: *column,
+                        key: key.clone(),
+                        kind,
+                    });
+                }
+            }),
+            durability,
+        )?;
+        self.notify(events);
+        Ok(())
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        self.inner.flush()
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        self.inner.column_stats(column)
+    }
+
+    fn engine_stats(&self) -> DatabaseResult<EngineStats> {
+        self.inner.engine_stats()
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        self.inner.write_pressure()
+    }
+
+    fn size_of_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> DatabaseResult<u64> {
+        self.inner.size_of_range(column, start, end)
+    }
+
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        self.inner.checkpoint(path)
+    }
+
+    fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()> {
+        self.inner.backup_to(backup_dir)
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        self.inner.compact(column, range)
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        let keys = self
+            .subscribed_keys(column, None)
+            .map_err(DatabaseError::from)?;
+        self.inner.clear_column(column)?;
+        self.notify(Self::removed(column, keys));
+        Ok(())
+    }
+
+    // `multi_delete` and `delete_range` keep the default expansion into removals of
+    // the batch, so subscribers are notified of each removed key.
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        self.inner.snapshot()
+    }
+
+    fn read_snapshot(&self) -> DatabaseResult<Box<dyn ReadView<Column = Self::Column>>>
+    where
+        Self::Column: 'static,
+    {
+        self.inner.read_snapshot()
+    }
+
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        let (sender, receiver) = broadcast::channel(self.capacity);
+        self.subscriptions
+            .write()
+            .expect("poisoned")
+            .push(Subscription {
+                column,
+                prefix,
+                sender,
+            });
+        Ok(receiver)
+    }
+
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.inner.replay_change_log(from_seq, into)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.inner.truncate_change_log(seq)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        self.inner.freeze_columns(columns)
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        self.inner.unfreeze_columns(columns)
+    }
+
+    fn begin_shutdown(&self) {
+        self.inner.begin_shutdown()
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        self.inner.finish_shutdown()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{
+            database_description::on_chain::OnChain,
+            Database,
+        },
+        state::{
+            faulty::FaultyStorage,
+            in_memory::{
+                memory_store::MemoryStore,
+                transaction::MemoryTransactionView,
+            },
+            test_helpers::over_memory_store,
+        },
+    };
+    use fuel_core_storage::column::Column;
+    use std::sync::Arc;
+    use tokio::sync::broadcast::error::TryRecvError;
+
+    fn notifying() -> Arc<NotifyingStorage<OnChain>> {
+        notifying_with_capacity(16)
+    }
+
+    fn notifying_with_capacity(capacity: usize) -> Arc<NotifyingStorage<OnChain>> {
+        let (_, storage) =
+            over_memory_store(|inner| Arc::new(NotifyingStorage::new(inner, capacity)));
+        storage
+    }
+
+    fn event(key: &[u8], kind: StorageEventKind) -> StorageEvent<Column> {
+        StorageEvent {
+            column: Column::Coins,
+            key: key.to_vec(),
+            kind,
+        }
+    }
+
+    fn received(
+        receiver: &mut broadcast::Receiver<StorageEvent<Column>>,
+    ) -> Vec<StorageEvent<Column>> {
+        let mut events = vec![];
+        while let Ok(event) = receiver.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+
+    #[test]
+    fn subscribers_receive_committed_changes_under_their_prefix() {
+        // Given
+        let storage = notifying();
+        let mut receiver = storage.subscribe(Column::Coins, vec![1]).unwrap();
+        let transaction = MemoryTransactionView::<OnChain>::new(storage.clone());
+        transaction
+            .put(&[1, 0], Column::Coins, Arc::new(vec![1]))
+            .unwrap();
+        transaction
+            .put(&[2, 0], Column::Coins, Arc::new(vec![2]))
+            .unwrap();
+        transaction
+            .put(&[1, 0], Column::Messages, Arc::new(vec![3]))
+            .unwrap();
+        transaction
+            .put(&[1, 1], Column::Coins, Arc::new(vec![4]))
+            .unwrap();
+        let before_commit = received(&mut receiver);
+
+        // When
+        transaction.commit().unwrap();
+        let removal = MemoryTransactionView::<OnChain>::new(storage.clone());
+        removal.delete(&[1, 1], Column::Coins).unwrap();
+        removal.delete(&[2, 0], Column::Coins).unwrap();
+        removal.commit().unwrap();
+        storage
+            .put(&[1, 2], Column::Coins, Arc::new(vec![5]))
+            .unwrap();
+
+        // Then
+        assert!(before_commit.is_empty());
+        assert_eq!(
+            received(&mut receiver),
+            vec![
+                event(&[1, 0], StorageEventKind::Upsert),
+                event(&[1, 1], StorageEventKind::Upsert),
+                event(&[1, 1], StorageEventKind::Delete),
+                event(&[1, 2], StorageEventKind::Upsert),
+            ]
+        );
+    }
+
+    #[test]
+    fn updates_notify_only_about_changes() {
+        // Given
+        let storage = notifying();
+        let mut receiver = storage.subscribe(Column::Coins, vec![]).unwrap();
+        storage.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+        received(&mut receiver);
+
+        // When
+        storage
+            .update(&[1], Column::Coins, &mut |current| {
+                current.map(<[u8]>::to_vec)
+            })
+            .unwrap();
+        storage
+            .update(&[1], Column::Coins, &mut |_| Some(vec![2]))
+            .unwrap();
+        storage.update(&[1], Column::Coins, &mut |_| None).unwrap();
+
+        // Then
+        assert_eq!(
+            received(&mut receiver),
+            vec![
+                event(&[1], StorageEventKind::Upsert),
+                event(&[1], StorageEventKind::Delete),
+            ]
+        );
+    }
+
+    #[test]
+    fn aborted_and_failed_commits_notify_nobody() {
+        // Given
+        let faulty = Arc::new(FaultyStorage::<OnChain>::new(Arc::new(
+            MemoryStore::default(),
+        )));
+        let storage = Arc::new(NotifyingStorage::new(faulty.clone(), 16));
+        let mut receiver = storage.subscribe(Column::Coins, vec![]).unwrap();
+
+        // When
+        let aborted = MemoryTransactionView::<OnChain>::new(storage.clone());
+        aborted.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+        drop(aborted);
+        faulty.fail_batch_write();
+        let failed = MemoryTransactionView::<OnChain>::new(storage.clone());
+        failed.put(&[2], Column::Coins, Arc::new(vec![2])).unwrap();
+        let result = failed.commit();
+
+        // Then
+        assert!(result.is_err());
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn slow_subscriber_lags_without_blocking_commits() {
+        // Given
+        let storage = notifying_with_capacity(2);
+        let mut slow = storage.subscribe(Column::Coins, vec![]).unwrap();
+
+        // When
+        for i in 0..5u8 {
+            storage.put(&[i], Column::Coins, Arc::new(vec![i])).unwrap();
+        }
+
+        // Then
+        assert_eq!(slow.try_recv(), Err(TryRecvError::Lagged(3)));
+        assert_eq!(
+            received(&mut slow),
+            vec![
+                event(&[3], StorageEventKind::Upsert),
+                event(&[4], StorageEventKind::Upsert),
+            ]
+        );
+    }
+
+    #[test]
+    fn clear_notifies_removal_of_subscribed_keys() {
+        // Given
+        let storage = notifying();
+        for key in [[1, 0], [1, 1], [2, 0]] {
+            storage.put(&key, Column::Coins, Arc::new(vec![])).unwrap();
+        }
+        let mut receiver = storage.subscribe(Column::Coins, vec![1]).unwrap();
+
+        // When
+        storage.clear_column(Column::Coins).unwrap();
+
+        // Then
+        assert_eq!(
+            received(&mut receiver),
+            vec![
+                event(&[1, 0], StorageEventKind::Delete),
+                event(&[1, 1], StorageEventKind::Delete),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_database_with_notifications_accepts_subscriptions() {
+        // Given
+        let plain = Database::<OnChain>::in_memory();
+        let notifying = Database::<OnChain>::in_memory().with_notifications(16);
+
+        // Then
+        assert!(plain.subscribe(Column::Coins, vec![]).is_err());
+        assert!(notifying.subscribe(Column::Coins, vec![]).is_ok());
+    }
+}
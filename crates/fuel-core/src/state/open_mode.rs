@@ -0,0 +1,121 @@
+//! The policy of opening the database at the path, see [`OpenMode`].
+
+use crate::database::{
+    database_description::DatabaseDescription,
+    Result as DatabaseResult,
+};
+use clap::ValueEnum;
+use std::path::Path;
+use strum_macros::{
+    Display,
+    EnumString,
+    EnumVariantNames,
+};
+
+/// What to do when the database is missing or already exists at the opened path.
+///
+/// The database is recognized by the files of the backend, not by the existence
+/// of the directory. The wrong path, e.g. the typo in the mount point, otherwise
+/// creates the empty database silently, and the node syncs from scratch.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Display,
+    Eq,
+    PartialEq,
+    EnumString,
+    EnumVariantNames,
+    ValueEnum,
+)]
+#[strum(serialize_all = "kebab_case")]
+pub enum OpenMode {
+    /// Opens the existing database or creates the empty one.
+    #[default]
+    CreateIfMissing,
+    /// Opens only the existing database, e.g. after the explicit initialization.
+    MustExist,
+    /// Creates only the new database, e.g. for the explicit initialization.
+    MustNotExist,
+}
+
+impl OpenMode {
+    /// Returns `true` if the missing database may be created.
+    pub fn creates_missing(self) -> bool {
+        self != Self::MustExist
+    }
+
+    /// Checks that the mode allows opening the `Description` database at the `path`,
+    /// where the database `exists` or not.
+    pub fn check<Description>(self, path: &Path, exists: bool) -> DatabaseResult<()>
+    where
+        Description: DatabaseDescription,
+    {
+        match (self, exists) {
+            (Self::MustExist, false) => Err(anyhow::anyhow!(
+                "The `{}` database is not found at {}, the directory is missing \
+                or contains no database",
+                Description::name(),
+                path.display()
+            )
+            .into()),
+            (Self::MustNotExist, true) => Err(anyhow::anyhow!(
+                "The `{}` database already exists at {}",
+                Description::name(),
+                path.display()
+            )
+            .into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::database_description::on_chain::OnChain;
+    use std::str::FromStr;
+
+    #[test]
+    fn modes_are_parsed_from_kebab_case() {
+        // Given
+        let names = ["create-if-missing", "must-exist", "must-not-exist"];
+
+        // When
+        let modes = names.map(|name| <OpenMode as FromStr>::from_str(name).unwrap());
+
+        // Then
+        assert_eq!(
+            modes,
+            [
+                OpenMode::CreateIfMissing,
+                OpenMode::MustExist,
+                OpenMode::MustNotExist
+            ]
+        );
+        assert_eq!(OpenMode::default(), OpenMode::CreateIfMissing);
+    }
+
+    #[test]
+    fn check_refuses_only_the_unexpected_state() {
+        let path = Path::new("/mnt/typo/db");
+        for (mode, exists, allowed) in [
+            (OpenMode::CreateIfMissing, false, true),
+            (OpenMode::CreateIfMissing, true, true),
+            (OpenMode::MustExist, false, false),
+            (OpenMode::MustExist, true, true),
+            (OpenMode::MustNotExist, false, true),
+            (OpenMode::MustNotExist, true, false),
+        ] {
+            // When
+            let result = mode.check::<OnChain>(path, exists);
+
+            // Then
+            assert_eq!(result.is_ok(), allowed, "{mode} with exists={exists}");
+            if let Err(err) = result {
+                assert!(format!("{err:?}").contains("/mnt/typo/db"));
+            }
+        }
+    }
+}
@@ -0,0 +1,557 @@
+//! The copy-on-write storage over the base storage, see [`OverlayStorage`].
+//!
+//! The simulation and the dry run execute transactions against the current state,
+//! but their changes must never reach the database, so the whole executor is pointed
+//! at the overlay and the overlay is dropped or reset afterwards.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::{
+        column_iter::ColumnIter,
+        in_memory::transaction::{
+            MemoryTransactionView,
+            WriteEntry,
+        },
+        notifying::StorageEvent,
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        IterDirection,
+        TransactableStorage,
+        WriteDurability,
+    },
+};
+use fuel_core_storage::{
+    iter::{
+        BoxedIter,
+        IterConfig,
+        IteratorableStore,
+        PageIter,
+    },
+    kv_store::{
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Result as StorageResult,
+};
+use std::path::Path;
+use tokio::sync::broadcast;
+
+/// The storage that reads from the `base` storage and keeps all writes in memory.
+/// The base storage is never modified: writes, removals, range removals and clears of
+/// columns only hide entries of the base inside the overlay, and the overlay has
+/// nothing to flush or compact.
+///
+/// The overlay reads the base as it is, so commits into the base after the creation of
+/// the overlay are visible through it unless the overlay changed the same keys.
+/// Create it over the [`TransactableStorage::snapshot`] to read the frozen state.
+#[derive(Debug)]
+pub struct OverlayStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    base: DataSource<Description>,
+    overlay: MemoryTransactionView<Description>,
+}
+
+impl<Description> OverlayStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Creates the empty overlay over the `base` storage.
+    pub fn new<D>(base: D) -> Self
+    where
+        D: Into<DataSource<Description>>,
+    {
+        let base = base.into();
+        Self {
+            overlay: MemoryTransactionView::new(base.clone()),
+            base,
+        }
+    }
+
+    /// Returns the writes that turn the base into the state of the overlay,
+    /// in the same order as [`MemoryTransactionView::pending_writes`].
+    pub fn into_changes(self) -> StorageResult<Vec<WriteEntry<Description::Column>>> {
+        self.overlay.pending_writes()
+    }
+
+    /// Discards all changes of the overlay, so it reads the base as it is.
+    pub fn reset(&mut self) {
+        self.overlay = MemoryTransactionView::new(self.base.clone());
+    }
+}
+
+impl<Description> KeyValueStore for OverlayStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        self.overlay.put(key, column, value)
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        self.overlay.replace(key, column, value)
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        self.overlay.write(key, column, buf)
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.overlay.take(key, column)
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        self.overlay.delete(key, column)
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        self.overlay.merge(key, column, operand)
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        self.overlay.exists(key, column)
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        self.overlay.size_of_value(key, column)
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.overlay.get(key, column)
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        self.overlay.get_with(key, column, f)
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        self.overlay.multi_get(keys, column)
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        self.overlay.compare_and_swap(key, column, expected, new)
+    }
+
+    fn get_or_insert_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        init: &mut dyn FnMut() -> Vec<u8>,
+    ) -> StorageResult<Value> {
+        self.overlay.get_or_insert_with(key, column, init)
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        self.overlay.update(key, column, f)
+    }
+}
+
+impl<Description> IteratorableStore for OverlayStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.overlay.iter_all(column, prefix, start, direction)
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.overlay
+            .iter_range(column, prefix, start, end, direction)
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.overlay.iter_keys(column, prefix, start, direction)
+    }
+
+    fn has_any(&self, column: Self::Column, prefix: &[u8]) -> StorageResult<bool> {
+        self.overlay.has_any(column, prefix)
+    }
+
+    fn iter(&self, column: Self::Column, config: IterConfig) -> PageIter {
+        self.overlay.iter(column, config)
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        self.overlay.delete_all(column, prefix)
+    }
+}
+
+impl<Description> BatchOperations for OverlayStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.overlay.batch_write(entries)
+    }
+}
+
+impl<Description> TransactableStorage for OverlayStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        _: WriteDurability,
+    ) -> StorageResult<()> {
+        // Writes of the overlay are never durable.
+        self.overlay.batch_write(entries)
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        Ok(())
+    }
+
+    fn iter_column(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> ColumnIter<'_> {
+        self.overlay.iter_column(column, prefix, start, direction)
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        self.overlay.column_stats(column)
+    }
+
+    fn checkpoint(&self, _: &Path) -> DatabaseResult<()> {
+        Err(DatabaseError::Unsupported("checkpoint"))
+    }
+
+    fn backup_to(&self, _: &Path) -> DatabaseResult<()> {
+        Err(DatabaseError::Unsupported("backup"))
+    }
+
+    fn compact(
+        &self,
+        _: Option<Self::Column>,
+        _: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        // The compaction of the base would be the write into it.
+        Ok(())
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        // Unlike the transaction view, the overlay hides entries of the base column.
+        self.overlay
+            .delete_all(column, None)
+            .map_err(DatabaseError::from)?;
+        Ok(())
+    }
+
+    fn multi_delete(
+        &self,
+        column: Self::Column,
+        keys: &[Vec<u8>],
+    ) -> DatabaseResult<usize> {
+        self.overlay.multi_delete(column, keys)
+    }
+
+    fn delete_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: &[u8],
+    ) -> DatabaseResult<()> {
+        self.overlay.delete_range(column, start, end)
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        self.overlay.snapshot()
+    }
+
+    // Commits into the base are visible through the overlay, so they are observed and
+    // frozen in the base. Writes of the overlay itself never reach the base.
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        self.base.subscribe(column, prefix)
+    }
+
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.base.replay_change_log(from_seq, into)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.base.truncate_change_log(seq)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        self.base.freeze_columns(columns)
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        self.base.unfreeze_columns(columns)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{
+            database_description::on_chain::OnChain,
+            Database,
+        },
+        state::in_memory::memory_store::MemoryStore,
+    };
+    use fuel_core_storage::{
+        column::Column,
+        tables::Coins,
+        transactional::Transaction,
+        StorageAsMut,
+        StorageAsRef,
+    };
+    use fuel_core_types::{
+        entities::coins::coin::CompressedCoin,
+        fuel_tx::{
+            TxId,
+            UtxoId,
+        },
+    };
+    use std::sync::Arc;
+
+    /// Returns all entries of all columns of the `storage`.
+    fn dump(
+        storage: &dyn TransactableStorage<Column = Column>,
+    ) -> Vec<(Column, Vec<u8>, Vec<u8>)> {
+        enum_iterator::all::<Column>()
+            .flat_map(|column| {
+                storage
+                    .iter_all(column, None, None, IterDirection::Forward)
+                    .map(move |item| {
+                        let (key, value) = item.unwrap();
+                        (column, key, value.to_vec())
+                    })
+            })
+            .collect()
+    }
+
+    fn base() -> Arc<MemoryStore<OnChain>> {
+        let base = Arc::new(MemoryStore::<OnChain>::default());
+        for key in 1..=5u8 {
+            base.put(&[key], Column::Metadata, Arc::new(vec![key]))
+                .unwrap();
+        }
+        base.put(&[1], Column::ContractsState, Arc::new(vec![1]))
+            .unwrap();
+        base
+    }
+
+    #[test]
+    fn workload_against_the_overlay_leaves_the_base_untouched() {
+        // Given
+        let base = base();
+        let before = dump(base.as_ref());
+        let overlay = Arc::new(OverlayStorage::<OnChain>::new(base.clone()));
+        let mut database = Database::<OnChain>::new(overlay.clone());
+        let utxo_id = UtxoId::new(TxId::from([1; 32]), 0);
+
+        // When
+        database
+            .storage_as_mut::<Coins>()
+            .insert(&utxo_id, &CompressedCoin::default())
+            .unwrap();
+        let mut transaction = database.transaction();
+        transaction
+            .as_mut()
+            .storage_as_mut::<Coins>()
+            .remove(&utxo_id)
+            .unwrap();
+        transaction.commit().unwrap();
+        overlay
+            .put(&[6], Column::Metadata, Arc::new(vec![6]))
+            .unwrap();
+        overlay
+            .put(&[1], Column::Metadata, Arc::new(vec![10]))
+            .unwrap();
+        overlay.delete_range(Column::Metadata, &[2], &[4]).unwrap();
+        overlay.clear_column(Column::ContractsState).unwrap();
+        overlay.compact(None, None).unwrap();
+        overlay.flush().unwrap();
+
+        // Then
+        assert_eq!(dump(base.as_ref()), before);
+        assert!(!database.storage::<Coins>().contains_key(&utxo_id).unwrap());
+        assert_eq!(
+            dump(overlay.as_ref()),
+            vec![
+                (Column::Metadata, vec![1], vec![10]),
+                (Column::Metadata, vec![4], vec![4]),
+                (Column::Metadata, vec![5], vec![5]),
+                (Column::Metadata, vec![6], vec![6]),
+            ]
+        );
+    }
+
+    #[test]
+    fn overlay_over_the_overlay_hides_its_writes_from_the_lower_layers() {
+        // Given
+        let base = base();
+        let before = dump(base.as_ref());
+        let lower = Arc::new(OverlayStorage::<OnChain>::new(base.clone()));
+        lower
+            .put(&[6], Column::Metadata, Arc::new(vec![6]))
+            .unwrap();
+        let upper = OverlayStorage::<OnChain>::new(lower.clone());
+
+        // When
+        upper.delete(&[6], Column::Metadata).unwrap();
+        upper.delete(&[1], Column::Metadata).unwrap();
+        upper
+            .batch_write(&mut core::iter::once((
+                vec![7],
+                Column::Metadata,
+                WriteOperation::Insert(Arc::new(vec![7])),
+            )))
+            .unwrap();
+
+        // Then
+        let keys = |storage: &dyn TransactableStorage<Column = Column>| {
+            storage
+                .iter_keys(Column::Metadata, None, None, IterDirection::Forward)
+                .map(|key| key.unwrap()[0])
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&upper), vec![2, 3, 4, 5, 7]);
+        assert_eq!(keys(lower.as_ref()), vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(dump(base.as_ref()), before);
+    }
+
+    #[test]
+    fn into_changes_returns_the_writes_of_the_overlay() {
+        // Given
+        let overlay = OverlayStorage::<OnChain>::new(base());
+        overlay
+            .put(&[6], Column::Metadata, Arc::new(vec![6]))
+            .unwrap();
+        overlay.delete_range(Column::Metadata, &[4], &[9]).unwrap();
+        overlay
+            .put(&[5], Column::Metadata, Arc::new(vec![50]))
+            .unwrap();
+
+        // When
+        let changes = overlay.into_changes().unwrap();
+
+        // Then
+        assert_eq!(
+            changes,
+            vec![
+                (vec![4], Column::Metadata, WriteOperation::Remove),
+                (
+                    vec![5],
+                    Column::Metadata,
+                    WriteOperation::Insert(Arc::new(vec![50]))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reset_discards_the_changes() {
+        // Given
+        let base = base();
+        let mut overlay = OverlayStorage::<OnChain>::new(base.clone());
+        overlay.delete(&[1], Column::Metadata).unwrap();
+        overlay
+            .put(&[6], Column::Metadata, Arc::new(vec![6]))
+            .unwrap();
+
+        // When
+        overlay.reset();
+
+        // Then
+        assert_eq!(dump(&overlay), dump(base.as_ref()));
+    }
+}
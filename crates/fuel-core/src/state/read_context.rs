@@ -0,0 +1,413 @@
+//! Attribution of reads of the storage to requests for the tracing.
+//!
+//! The request installs the [`ReadContext`] with its span as the task-local value,
+//! and reads of the storage within the scope accumulate [`ReadCounters`] per column.
+//! When the last clone of the context is dropped, the counters are recorded as
+//! fields of the span and as debug events inside of it, one per column.
+//!
+//! Reads are counted where they enter the storage, i.e., by the outermost
+//! [`DataSource`](crate::state::DataSource) and iterators of the [`Database`],
+//! so the read forwarded by the inner layers is counted once.
+//! [`MemoryTransactionView`]s also count point reads served by their changes
+//! separately from reads forwarded to their parent.
+//!
+//! The attribution is compiled only with the `read-tracing` feature. Without it,
+//! the context does nothing, and reads don't touch the task-local value at all.
+//!
+//! [`Database`]: crate::database::Database
+//! [`MemoryTransactionView`]: crate::state::in_memory::transaction::MemoryTransactionView
+
+use fuel_core_storage::kv_store::StorageColumn;
+use std::{
+    collections::BTreeMap,
+    future::Future,
+};
+
+/// Counters of reads of the column.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReadCounters {
+    /// The number of point reads, including checks of the existence.
+    pub gets: u64,
+    /// The number of point reads that didn't find the value.
+    pub misses: u64,
+    /// The number of entries yielded by iterators.
+    pub iter_items: u64,
+    /// The number of bytes of values read by point reads and iterators.
+    pub bytes_read: u64,
+    /// The number of point reads that transaction views served from their changes.
+    pub overlay_hits: u64,
+    /// The number of point reads that transaction views forwarded to their parent.
+    pub parent_reads: u64,
+}
+
+impl ReadCounters {
+    /// Returns the sum of counters, e.g. of all columns.
+    pub fn saturating_add(self, other: Self) -> Self {
+        Self {
+            gets: self.gets.saturating_add(other.gets),
+            misses: self.misses.saturating_add(other.misses),
+            iter_items: self.iter_items.saturating_add(other.iter_items),
+            bytes_read: self.bytes_read.saturating_add(other.bytes_read),
+            overlay_hits: self.overlay_hits.saturating_add(other.overlay_hits),
+            parent_reads: self.parent_reads.saturating_add(other.parent_reads),
+        }
+    }
+
+    /// Counts the point read that found the value of `size` bytes, or nothing.
+    pub(crate) fn get(&mut self, size: Option<usize>) {
+        self.gets = self.gets.saturating_add(1);
+        match size {
+            Some(size) => {
+                self.bytes_read = self.bytes_read.saturating_add(size as u64);
+            }
+            None => self.misses = self.misses.saturating_add(1),
+        }
+    }
+
+    /// Counts the entry of `size` bytes yielded by the iterator.
+    pub(crate) fn iter_item(&mut self, size: usize) {
+        self.iter_items = self.iter_items.saturating_add(1);
+        self.bytes_read = self.bytes_read.saturating_add(size as u64);
+    }
+
+    /// Counts the point read of the transaction view, served by its changes or by its parent.
+    pub(crate) fn view_read(&mut self, overlay: bool) {
+        if overlay {
+            self.overlay_hits = self.overlay_hits.saturating_add(1);
+        } else {
+            self.parent_reads = self.parent_reads.saturating_add(1);
+        }
+    }
+}
+
+#[cfg(feature = "read-tracing")]
+mod enabled {
+    use super::ReadCounters;
+    use std::{
+        cell::Cell,
+        collections::BTreeMap,
+        sync::{
+            Arc,
+            Mutex,
+        },
+    };
+
+    tokio::task_local! {
+        pub(super) static CURRENT: ReadContextInner;
+    }
+
+    /// The context installed into the task.
+    #[derive(Debug)]
+    pub(super) struct ReadContextInner {
+        pub(super) shared: Arc<Shared>,
+        /// Whether the read that entered the storage is in progress,
+        /// so reads of inner layers aren't counted again.
+        pub(super) entered: Cell<bool>,
+    }
+
+    /// Counters shared by clones of the context.
+    #[derive(Debug)]
+    pub(super) struct Shared {
+        pub(super) span: tracing::Span,
+        pub(super) counters: Mutex<BTreeMap<&'static str, ReadCounters>>,
+    }
+
+    impl Shared {
+        pub(super) fn record(
+            &self,
+            column: &'static str,
+            f: impl FnOnce(&mut ReadCounters),
+        ) {
+            #[cfg(test)]
+            test_records::RECORDS
+                .with(|records| records.set(records.get().saturating_add(1)));
+            f(self
+                .counters
+                .lock()
+                .expect("poisoned lock")
+                .entry(column)
+                .or_default())
+        }
+    }
+
+    impl Drop for Shared {
+        fn drop(&mut self) {
+            let counters =
+                core::mem::take(self.counters.get_mut().expect("poisoned lock"));
+            let total = counters
+                .values()
+                .fold(ReadCounters::default(), |total, counters| {
+                    total.saturating_add(*counters)
+                });
+            self.span.record("storage_gets", total.gets);
+            self.span.record("storage_misses", total.misses);
+            self.span.record("storage_iter_items", total.iter_items);
+            self.span.record("storage_bytes_read", total.bytes_read);
+            self.span.record("storage_overlay_hits", total.overlay_hits);
+            self.span.record("storage_parent_reads", total.parent_reads);
+            for (column, counters) in counters.iter() {
+                tracing::debug!(
+                    parent: &self.span,
+                    column,
+                    gets = counters.gets,
+                    misses = counters.misses,
+                    iter_items = counters.iter_items,
+                    bytes_read = counters.bytes_read,
+                    overlay_hits = counters.overlay_hits,
+                    parent_reads = counters.parent_reads,
+                    "Storage reads of the column"
+                );
+            }
+        }
+    }
+
+    /// Restores the flag of the entered read even if the read panics.
+    pub(super) struct Entered<'a>(pub(super) &'a Cell<bool>);
+
+    impl Drop for Entered<'_> {
+        fn drop(&mut self) {
+            self.0.set(false);
+        }
+    }
+
+    #[cfg(test)]
+    pub(super) mod test_records {
+        use std::cell::Cell;
+
+        std::thread_local! {
+            /// The number of records into counters of contexts by the current thread.
+            pub static RECORDS: Cell<usize> = const { Cell::new(0) };
+        }
+    }
+}
+
+/// The context of the request that accumulates counters of its reads, see the module
+/// documentation. Clones share counters, so one of them can be installed into
+/// the task while another one reads counters later.
+///
+/// The span should declare `storage_gets`, `storage_misses`, `storage_iter_items`,
+/// `storage_bytes_read`, `storage_overlay_hits` and `storage_parent_reads` fields
+/// with [`tracing::field::Empty`] to receive totals of all columns.
+#[derive(Debug, Clone)]
+pub struct ReadContext {
+    #[cfg(feature = "read-tracing")]
+    shared: std::sync::Arc<enabled::Shared>,
+}
+
+impl ReadContext {
+    /// Creates the context that records counters into the `span`.
+    pub fn new(span: tracing::Span) -> Self {
+        #[cfg(feature = "read-tracing")]
+        {
+            Self {
+                shared: std::sync::Arc::new(enabled::Shared {
+                    span,
+                    counters: Default::default(),
+                }),
+            }
+        }
+        #[cfg(not(feature = "read-tracing"))]
+        {
+            let _ = span;
+            Self {}
+        }
+    }
+
+    /// Runs the `future` with the context installed into its task.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        #[cfg(feature = "read-tracing")]
+        {
+            enabled::CURRENT.scope(self.inner(), future).await
+        }
+        #[cfg(not(feature = "read-tracing"))]
+        {
+            future.await
+        }
+    }
+
+    /// Runs the `f` with the context installed, e.g. for reads in the blocking task.
+    pub fn sync_scope<R>(self, f: impl FnOnce() -> R) -> R {
+        #[cfg(feature = "read-tracing")]
+        {
+            enabled::CURRENT.sync_scope(self.inner(), f)
+        }
+        #[cfg(not(feature = "read-tracing"))]
+        {
+            f()
+        }
+    }
+
+    /// Returns counters of reads of each column so far.
+    pub fn counters(&self) -> BTreeMap<&'static str, ReadCounters> {
+        #[cfg(feature = "read-tracing")]
+        {
+            self.shared.counters.lock().expect("poisoned lock").clone()
+        }
+        #[cfg(not(feature = "read-tracing"))]
+        {
+            BTreeMap::new()
+        }
+    }
+
+    #[cfg(feature = "read-tracing")]
+    fn inner(self) -> enabled::ReadContextInner {
+        enabled::ReadContextInner {
+            shared: self.shared,
+            entered: Default::default(),
+        }
+    }
+}
+
+/// Performs the `read` that enters the storage and records its result by the `record`
+/// into counters of the `column` of the installed context. Reads of inner layers
+/// performed during the `read` aren't recorded.
+#[inline(always)]
+pub(crate) fn entering_read<C, T>(
+    column: C,
+    read: impl FnOnce() -> T,
+    record: impl FnOnce(&mut ReadCounters, &T),
+) -> T
+where
+    C: StorageColumn,
+{
+    #[cfg(feature = "read-tracing")]
+    {
+        let mut read = Some(read);
+        let result = enabled::CURRENT.try_with(|context| {
+            if context.entered.replace(true) {
+                return None
+            }
+            let _entered = enabled::Entered(&context.entered);
+            let result = (read.take().expect("The read is not performed yet"))();
+            context
+                .shared
+                .record(column.name(), |counters| record(counters, &result));
+            Some(result)
+        });
+        match (result, read) {
+            (Ok(Some(result)), _) => result,
+            (_, Some(read)) => read(),
+            (_, None) => unreachable!("The read is performed only once"),
+        }
+    }
+    #[cfg(not(feature = "read-tracing"))]
+    {
+        let _ = (column, record);
+        read()
+    }
+}
+
+/// Records counters of the `column` by the `f`, if the context is installed.
+#[inline(always)]
+pub(crate) fn record<C>(column: C, f: impl FnOnce(&mut ReadCounters))
+where
+    C: StorageColumn,
+{
+    #[cfg(feature = "read-tracing")]
+    {
+        let _ =
+            enabled::CURRENT.try_with(|context| context.shared.record(column.name(), f));
+    }
+    #[cfg(not(feature = "read-tracing"))]
+    {
+        let _ = (column, f);
+    }
+}
+
+#[cfg(all(test, feature = "read-tracing"))]
+mod tests {
+    use super::*;
+    use crate::database::{
+        database_description::on_chain::OnChain,
+        Database,
+    };
+    use fuel_core_storage::{
+        column::Column,
+        iter::IterDirection,
+        kv_store::KeyValueStore,
+    };
+    use std::sync::Arc;
+
+    /// Reads the parent database and its transaction with the staged change.
+    fn workload() {
+        let database = Database::<OnChain>::in_memory();
+        database
+            .data_source()
+            .put(&[1], Column::Coins, Arc::new(vec![1; 6]))
+            .unwrap();
+        let transaction = database.transaction();
+        let view: &Database = transaction.as_ref();
+        view.data_source()
+            .put(&[2], Column::Coins, Arc::new(vec![2; 3]))
+            .unwrap();
+
+        view.data_source().get(&[2], Column::Coins).unwrap();
+        view.data_source().get(&[1], Column::Coins).unwrap();
+        view.data_source().get(&[3], Column::Coins).unwrap();
+        database.data_source().get(&[1], Column::Coins).unwrap();
+        database
+            .iter_from_cursor(Column::Coins, None, IterDirection::Forward, None, 10)
+            .unwrap();
+    }
+
+    fn records() -> usize {
+        enabled::test_records::RECORDS.with(|records| records.get())
+    }
+
+    #[test]
+    fn installed_context_counts_reads_of_the_workload() {
+        // Given
+        let context = ReadContext::new(tracing::Span::none());
+
+        // When
+        context.clone().sync_scope(workload);
+
+        // Then
+        let counters = context.counters();
+        assert_eq!(
+            counters.get(Column::Coins.name()),
+            Some(&ReadCounters {
+                gets: 4,
+                misses: 1,
+                iter_items: 1,
+                bytes_read: 3 + 6 + 6 + 6,
+                overlay_hits: 1,
+                parent_reads: 2,
+            })
+        );
+        assert_eq!(counters.len(), 1, "{counters:?}");
+    }
+
+    #[tokio::test]
+    async fn context_counts_reads_across_await_points() {
+        // Given
+        let context = ReadContext::new(tracing::Span::none());
+
+        // When
+        context
+            .clone()
+            .scope(async {
+                workload();
+                tokio::task::yield_now().await;
+                workload();
+            })
+            .await;
+
+        // Then
+        assert_eq!(context.counters()[Column::Coins.name()].gets, 8);
+    }
+
+    #[test]
+    fn reads_without_the_context_record_nothing() {
+        // Given
+        let context = ReadContext::new(tracing::Span::none());
+        let before = records();
+
+        // When
+        workload();
+
+        // Then
+        assert_eq!(records(), before);
+        assert!(context.counters().is_empty());
+    }
+}
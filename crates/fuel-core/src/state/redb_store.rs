@@ -0,0 +1,949 @@
+//! The persistent storage on top of the [redb](https://docs.rs/redb) embedded database.
+//! It is the pure Rust alternative to the RocksDB for small nodes and targets
+//! where the RocksDB is hard to build, e.g., ARM boards.
+//!
+//! Each column is the table of the single database file. Writes are copy-on-write
+//! transactions, so the batch is applied atomically, and readers see the last commit
+//! without blocking the writer. Writers are serialized by the redb.
+//!
+//! The redb doesn't compress values and rewrites the B-tree pages on the path to each
+//! modified key, so it is reasonable for databases up to tens of gigabytes: local and test
+//! networks, or nodes without the history of large columns like `ContractsState` and
+//! `Transactions`. The RocksDB is preferable for mainnet nodes, where the compression
+//! and the write throughput of the LSM-tree matter.
+
+use crate::{
+    database::{
+        database_description::{
+            debug_assert_key_size,
+            DatabaseDescription,
+        },
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::{
+        next_prefix,
+        open_mode::OpenMode,
+        BatchOperations,
+        ColumnStats,
+        DataSourceInner,
+        IterDirection,
+        TransactableStorage,
+        WriteDurability,
+    },
+};
+use fuel_core_metrics::core_metrics::database_metrics;
+use fuel_core_storage::{
+    iter::{
+        is_start_within_prefix,
+        BoxedIter,
+        IntoBoxedIter,
+        IteratorableStore,
+    },
+    kv_store::{
+        merge_value,
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Result as StorageResult,
+};
+use redb::{
+    Durability,
+    ReadOnlyTable,
+    ReadTransaction,
+    ReadableTable,
+    ReadableTableMetadata,
+    Table,
+    TableDefinition,
+    WriteTransaction,
+};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    ops::Bound,
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::Arc,
+};
+
+type RawTable<'txn> = Table<'txn, &'static [u8], &'static [u8]>;
+
+/// The storage in the redb file `<name>.redb` of the database in the directory.
+pub struct RedbStore<Description> {
+    db: Arc<redb::Database>,
+    /// The point in time of the snapshot. The store with the snapshot rejects writes.
+    snapshot: Option<Arc<ReadTransaction>>,
+    _marker: core::marker::PhantomData<Description>,
+}
+
+impl<Description> Debug for RedbStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RedbStore")
+            .field("db", &self.db)
+            .field("snapshot", &self.snapshot.is_some())
+            .finish()
+    }
+}
+
+fn other(err: impl Into<redb::Error>) -> DatabaseError {
+    DatabaseError::Other(anyhow::anyhow!(err.into()))
+}
+
+fn table<Column: StorageColumn>(
+    column: Column,
+) -> TableDefinition<'static, &'static [u8], &'static [u8]> {
+    TableDefinition::new(column.name())
+}
+
+impl<Description> RedbStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Returns the path of the database file in the `path` directory.
+    pub fn file_path(path: &Path) -> PathBuf {
+        path.join(format!("{}.redb", Description::name()))
+    }
+
+    /// Opens the database in the `path` directory or creates it if it is missing.
+    /// The `capacity` is the size of the page cache in bytes.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        capacity: Option<usize>,
+    ) -> DatabaseResult<Self> {
+        Self::open_with_mode(path, capacity, OpenMode::CreateIfMissing)
+    }
+
+    /// Opens the database in the `path` directory, the `mode` decides whether
+    /// the database may be missing or already exist.
+    pub fn open_with_mode<P: AsRef<Path>>(
+        path: P,
+        capacity: Option<usize>,
+        mode: OpenMode,
+    ) -> DatabaseResult<Self> {
+        let path = path.as_ref();
+        // The empty file isn't the database, e.g. left by the failed creation.
+        let exists = std::fs::metadata(Self::file_path(path))
+            .map(|metadata| metadata.is_file() && metadata.len() > 0)
+            .unwrap_or(false);
+        mode.check::<Description>(path, exists)?;
+        std::fs::create_dir_all(path).map_err(|e| DatabaseError::Other(e.into()))?;
+        let mut builder = redb::Builder::new();
+        if let Some(capacity) = capacity {
+            builder.set_cache_size(capacity);
+        }
+        let db = builder.create(Self::file_path(path)).map_err(other)?;
+
+        // Tables of all columns exist, so reads don't handle missing tables.
+        let transaction = db.begin_write().map_err(other)?;
+        for column in enum_iterator::all::<Description::Column>() {
+            transaction.open_table(table(column)).map_err(other)?;
+        }
+        transaction.commit().map_err(other)?;
+
+        Ok(Self {
+            db: Arc::new(db),
+            snapshot: None,
+            _marker: Default::default(),
+        })
+    }
+
+    fn read_table(
+        &self,
+        column: Description::Column,
+    ) -> DatabaseResult<ReadOnlyTable<&'static [u8], &'static [u8]>> {
+        let table = match &self.snapshot {
+            Some(snapshot) => snapshot.open_table(table(column)),
+            None => self
+                .db
+                .begin_read()
+                .map_err(other)?
+                .open_table(table(column)),
+        };
+        table.map_err(other)
+    }
+
+    fn write_transaction(
+        &self,
+        durability: WriteDurability,
+    ) -> DatabaseResult<WriteTransaction> {
+        if self.snapshot.is_some() {
+            return Err(DatabaseError::ReadOnly)
+        }
+        let mut transaction = self.db.begin_write().map_err(other)?;
+        transaction.set_durability(match durability {
+            // The commit is written without `fsync`, so it survives the crash
+            // of the process, but not of the machine, until the next durable commit.
+            WriteDurability::Buffered => Durability::Eventual,
+            WriteDurability::Sync => Durability::Immediate,
+        });
+        Ok(transaction)
+    }
+
+    /// Runs the `write` with the table of the `column` in its own transaction
+    /// and commits it if the `write` succeeds.
+    fn write_with<T>(
+        &self,
+        column: Description::Column,
+        write: impl FnOnce(&mut RawTable<'_>) -> StorageResult<T>,
+    ) -> StorageResult<T> {
+        let transaction = self.write_transaction(WriteDurability::Buffered)?;
+        let result = {
+            let mut table = transaction.open_table(table(column)).map_err(other)?;
+            write(&mut table)?
+        };
+        transaction.commit().map_err(other)?;
+        database_metrics().write_meter.inc();
+        Ok(result)
+    }
+
+    fn iter_store<T: 'static>(
+        &self,
+        column: Description::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+        extract: fn(&[u8], &[u8]) -> T,
+    ) -> BoxedIter<StorageResult<T>> {
+        if !is_start_within_prefix(prefix, start) {
+            return core::iter::empty().into_boxed()
+        }
+        let table = match self.read_table(column) {
+            Ok(table) => table,
+            Err(err) => return core::iter::once(Err(err.into())).into_boxed(),
+        };
+
+        let end = prefix.and_then(|prefix| next_prefix(prefix.to_vec()));
+        let (lower, upper) = match direction {
+            IterDirection::Forward => (
+                start.or(prefix).map_or(Bound::Unbounded, Bound::Included),
+                Bound::Unbounded,
+            ),
+            IterDirection::Reverse => (
+                prefix.map_or(Bound::Unbounded, Bound::Included),
+                match (start, end.as_deref()) {
+                    (Some(start), _) => Bound::Included(start),
+                    (None, Some(end)) => Bound::Excluded(end),
+                    (None, None) => Bound::Unbounded,
+                },
+            ),
+        };
+        let range = match table.range::<&[u8]>((lower, upper)) {
+            Ok(range) => range,
+            Err(err) => return core::iter::once(Err(other(err).into())).into_boxed(),
+        };
+        let entries: Box<dyn Iterator<Item = _>> = match direction {
+            IterDirection::Forward => Box::new(range),
+            IterDirection::Reverse => Box::new(range.rev()),
+        };
+
+        let prefix = prefix.map(<[u8]>::to_vec);
+        entries
+            .take_while(move |item| match (item, &prefix) {
+                (Ok((key, _)), Some(prefix)) => key.value().starts_with(prefix),
+                _ => true,
+            })
+            .map(move |item| {
+                let (key, value) = item.map_err(other)?;
+                Ok(extract(key.value(), value.value()))
+            })
+            .into_boxed()
+    }
+}
+
+impl<Description> KeyValueStore for RedbStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        self.replace(key, column, Arc::new(buf.to_vec()))?;
+        Ok(buf.len())
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        debug_assert_key_size::<Description>(&column, key);
+        database_metrics().bytes_written.observe(value.len() as f64);
+        self.write_with(column, |table| {
+            let previous = table.insert(key, value.as_slice()).map_err(other)?;
+            Ok(previous.map(|previous| Arc::new(previous.value().to_vec())))
+        })
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.write_with(column, |table| {
+            let previous = table.remove(key).map_err(other)?;
+            Ok(previous.map(|previous| Arc::new(previous.value().to_vec())))
+        })
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        self.take(key, column).map(|_| ())
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        debug_assert_key_size::<Description>(&column, key);
+        self.write_with(column, |table| {
+            let existing = table.get(key).map_err(other)?.map(|v| v.value().to_vec());
+            let value = merge_value(column, existing.as_deref(), &operand)?;
+            table.insert(key, value.as_slice()).map_err(other)?;
+            Ok(())
+        })
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        debug_assert_key_size::<Description>(&column, key);
+        // Writers are serialized, so nothing changes the value between the read and the write.
+        self.write_with(column, |table| {
+            let current = table
+                .get(key)
+                .map_err(other)?
+                .map(|current| current.value().to_vec());
+            if current.as_deref() != expected {
+                return Ok(Err(current.map(Arc::new)))
+            }
+            table.insert(key, new.as_slice()).map_err(other)?;
+            Ok(Ok(()))
+        })
+    }
+
+    fn get_or_insert_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        init: &mut dyn FnMut() -> Vec<u8>,
+    ) -> StorageResult<Value> {
+        debug_assert_key_size::<Description>(&column, key);
+        self.write_with(column, |table| {
+            let current = table
+                .get(key)
+                .map_err(other)?
+                .map(|current| current.value().to_vec());
+            if let Some(current) = current {
+                return Ok(Arc::new(current))
+            }
+            let value = init();
+            table.insert(key, value.as_slice()).map_err(other)?;
+            Ok(Arc::new(value))
+        })
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        debug_assert_key_size::<Description>(&column, key);
+        self.write_with(column, |table| {
+            let current = table
+                .get(key)
+                .map_err(other)?
+                .map(|current| current.value().to_vec());
+            let new = f(current.as_deref());
+            let outcome = UpdateOutcome::of(current.as_deref(), new.as_deref());
+            match new {
+                Some(value) if outcome.is_changed() => {
+                    table.insert(key, value.as_slice()).map_err(other)?;
+                }
+                None if outcome.is_changed() => {
+                    table.remove(key).map_err(other)?;
+                }
+                _ => {}
+            }
+            Ok(outcome)
+        })
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        database_metrics().read_meter.inc();
+        let value = self.read_table(column)?.get(key).map_err(other)?;
+        Ok(value.map(|value| value.value().len()))
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        let mut value = None;
+        self.get_with(key, column, &mut |bytes| {
+            value = Some(Arc::new(bytes.to_vec()))
+        })?;
+        Ok(value)
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        database_metrics().read_meter.inc();
+        let Some(value) = self.read_table(column)?.get(key).map_err(other)? else {
+            return Ok(false)
+        };
+        database_metrics()
+            .bytes_read
+            .observe(value.value().len() as f64);
+        f(value.value());
+        Ok(true)
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        database_metrics().read_meter.inc_by(keys.len() as u64);
+        // All keys are read from the same transaction, so values are consistent.
+        let table = self.read_table(column)?;
+        keys.iter()
+            .map(|key| {
+                let value = table.get(key.as_slice()).map_err(other)?;
+                Ok(value.map(|value| {
+                    database_metrics()
+                        .bytes_read
+                        .observe(value.value().len() as f64);
+                    Arc::new(value.value().to_vec())
+                }))
+            })
+            .collect()
+    }
+}
+
+impl<Description> IteratorableStore for RedbStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.iter_store(column, prefix, start, direction, |key, value| {
+            (key.to_vec(), Arc::new(value.to_vec()))
+        })
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.iter_store(column, prefix, start, direction, |key, _| key.to_vec())
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        let prefix = prefix.filter(|prefix| !prefix.is_empty());
+        self.write_with(column, |table| {
+            let keys = match prefix {
+                Some(prefix) => table
+                    .range::<&[u8]>(prefix..)
+                    .map_err(other)?
+                    .map(|item| item.map(|(key, _)| key.value().to_vec()))
+                    .take_while(|key| {
+                        key.as_ref().map_or(true, |key| key.starts_with(prefix))
+                    })
+                    .collect::<Result<Vec<_>, _>>(),
+                None => table
+                    .iter()
+                    .map_err(other)?
+                    .map(|item| item.map(|(key, _)| key.value().to_vec()))
+                    .collect::<Result<Vec<_>, _>>(),
+            }
+            .map_err(other)?;
+            for key in keys.iter() {
+                table.remove(key.as_slice()).map_err(other)?;
+            }
+            Ok(keys.len())
+        })
+    }
+}
+
+impl<Description> BatchOperations for RedbStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for RedbStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Writes the batch in one write transaction. The transaction is aborted
+    /// on the first error, so the failed batch changes nothing.
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        let transaction = self.write_transaction(durability)?;
+        let mut bytes_written = 0usize;
+        {
+            let mut tables = HashMap::<usize, RawTable<'_>>::new();
+            for (key, column, op) in entries {
+                if !matches!(op, WriteOperation::Remove) {
+                    debug_assert_key_size::<Description>(&column, &key);
+                }
+                let table = match tables.entry(column.as_usize()) {
+                    std::collections::hash_map::Entry::Occupied(entry) => {
+                        entry.into_mut()
+                    }
+                    std::collections::hash_map::Entry::Vacant(entry) => entry
+                        .insert(transaction.open_table(table(column)).map_err(other)?),
+                };
+                bytes_written = bytes_written.saturating_add(key.len());
+                match op {
+                    WriteOperation::Insert(value) => {
+                        bytes_written = bytes_written.saturating_add(value.len());
+                        table
+                            .insert(key.as_slice(), value.as_slice())
+                            .map_err(other)?;
+                    }
+                    WriteOperation::Remove => {
+                        table.remove(key.as_slice()).map_err(other)?;
+                    }
+                    WriteOperation::Merge(operand) => {
+                        let existing = table
+                            .get(key.as_slice())
+                            .map_err(other)?
+                            .map(|value| value.value().to_vec());
+                        let value = merge_value(column, existing.as_deref(), &operand)?;
+                        bytes_written = bytes_written.saturating_add(value.len());
+                        table
+                            .insert(key.as_slice(), value.as_slice())
+                            .map_err(other)?;
+                    }
+                }
+            }
+        }
+        transaction.commit().map_err(other)?;
+
+        database_metrics().write_meter.inc();
+        database_metrics()
+            .bytes_written
+            .observe(bytes_written as f64);
+        Ok(())
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        if self.snapshot.is_some() {
+            // Nothing to flush, the snapshot can't be modified.
+            return Ok(())
+        }
+        // The durable commit syncs the file with all previous commits.
+        let transaction = self.write_transaction(WriteDurability::Sync)?;
+        transaction.commit().map_err(other)
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        let table = self.read_table(column)?;
+        Ok(ColumnStats {
+            keys: table.len().map_err(other)?,
+            size: table.stats().map_err(other)?.stored_bytes(),
+        })
+    }
+
+    /// Copies entries of the current commit into the new database at the `path`,
+    /// so [`Self::open`] can open it. Writes don't wait for the copy.
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        let file = Self::file_path(path);
+        if file.exists() {
+            return Err(anyhow::anyhow!(
+                "The checkpoint {} already exists",
+                file.display()
+            )
+            .into())
+        }
+        let snapshot = self.snapshot()?;
+        let checkpoint = Self::open(path, None)?;
+        for column in enum_iterator::all::<Description::Column>() {
+            checkpoint
+                .batch_write_with(
+                    &mut snapshot
+                        .iter_all(column, None, None, IterDirection::Forward)
+                        .map_while(Result::ok)
+                        .map(|(key, value)| (key, column, WriteOperation::Insert(value))),
+                    WriteDurability::Buffered,
+                )
+                .map_err(DatabaseError::from)?;
+        }
+        // The checkpoint is complete only if all entries are copied.
+        for column in enum_iterator::all::<Description::Column>() {
+            if snapshot.column_stats(column)?.keys
+                != checkpoint.column_stats(column)?.keys
+            {
+                return Err(anyhow::anyhow!(
+                    "Unable to copy the column `{}` into the checkpoint",
+                    column.name()
+                )
+                .into())
+            }
+        }
+        checkpoint.flush()
+    }
+
+    fn backup_to(&self, _: &Path) -> DatabaseResult<()> {
+        Err(DatabaseError::Unsupported("backup"))
+    }
+
+    fn compact(
+        &self,
+        _: Option<Self::Column>,
+        _: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        // The redb reuses pages freed by commits, and the compaction of the file
+        // requires the exclusive access to the database, so there is nothing to do online.
+        Ok(())
+    }
+
+    /// Deletes the table of the `column` and creates it again. Iterators and snapshots
+    /// created before the clear read their own commit, so they see the old entries.
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        let transaction = self.write_transaction(WriteDurability::Buffered)?;
+        transaction.delete_table(table(column)).map_err(other)?;
+        transaction.open_table(table(column)).map_err(other)?;
+        transaction.commit().map_err(other)
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        // The snapshot of the snapshot shares the same point in time.
+        let snapshot = match &self.snapshot {
+            Some(snapshot) => snapshot.clone(),
+            None => Arc::new(self.db.begin_read().map_err(other)?),
+        };
+        Ok(Arc::new(Self {
+            db: self.db.clone(),
+            snapshot: Some(snapshot),
+            _marker: Default::default(),
+        }))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::database_description::on_chain::OnChain;
+    use fuel_core_storage::column::Column;
+    use tempfile::TempDir;
+
+    fn create_db() -> (RedbStore<OnChain>, TempDir) {
+        let tmp_dir = TempDir::new().unwrap();
+        (RedbStore::open(tmp_dir.path(), None).unwrap(), tmp_dir)
+    }
+
+    fn keys(db: &RedbStore<OnChain>, column: Column) -> Vec<Vec<u8>> {
+        db.iter_keys(column, None, None, IterDirection::Forward)
+            .collect::<StorageResult<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn can_put_and_read() {
+        // Given
+        let (db, _tmp) = create_db();
+        let value = Arc::new(vec![1, 2, 3]);
+
+        // When
+        db.put(&[0xA], Column::Metadata, value.clone()).unwrap();
+
+        // Then
+        assert_eq!(db.get(&[0xA], Column::Metadata).unwrap(), Some(value));
+        assert_eq!(db.get(&[0xA], Column::Coins).unwrap(), None);
+        assert_eq!(db.size_of_value(&[0xA], Column::Metadata).unwrap(), Some(3));
+    }
+
+    #[test]
+    fn replace_and_take_return_previous_values() {
+        // Given
+        let (db, _tmp) = create_db();
+        db.put(&[0xA], Column::Metadata, Arc::new(vec![1])).unwrap();
+
+        // When
+        let replaced = db
+            .replace(&[0xA], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+        let taken = db.take(&[0xA], Column::Metadata).unwrap();
+
+        // Then
+        assert_eq!(replaced, Some(Arc::new(vec![1])));
+        assert_eq!(taken, Some(Arc::new(vec![2])));
+        assert!(!db.exists(&[0xA], Column::Metadata).unwrap());
+    }
+
+    #[test]
+    fn compare_and_swap_writes_only_expected_values() {
+        // Given
+        let (db, _tmp) = create_db();
+
+        // When
+        let inserted = db
+            .compare_and_swap(&[0xA], Column::Metadata, None, Arc::new(vec![1]))
+            .unwrap();
+        let mismatched = db
+            .compare_and_swap(&[0xA], Column::Metadata, None, Arc::new(vec![2]))
+            .unwrap();
+
+        // Then
+        assert_eq!(inserted, Ok(()));
+        assert_eq!(mismatched, Err(Some(Arc::new(vec![1]))));
+        assert_eq!(
+            db.get(&[0xA], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+    }
+
+    #[test]
+    fn failed_batch_changes_nothing() {
+        // Given
+        let (db, _tmp) = create_db();
+        // The `Metadata` column has no merge operator, so the merge fails.
+        let mut batch = vec![
+            (
+                vec![1],
+                Column::Coins,
+                WriteOperation::Insert(Arc::new(vec![1])),
+            ),
+            (
+                vec![2],
+                Column::Metadata,
+                WriteOperation::Merge(Arc::new(vec![2])),
+            ),
+        ]
+        .into_iter();
+
+        // When
+        let result = db.batch_write(&mut batch);
+
+        // Then
+        assert!(result.is_err());
+        assert!(keys(&db, Column::Coins).is_empty());
+    }
+
+    #[test]
+    fn data_survives_reopen() {
+        // Given
+        let tmp_dir = TempDir::new().unwrap();
+        let db = RedbStore::<OnChain>::open(tmp_dir.path(), None).unwrap();
+        db.batch_write_with(
+            &mut [1u8, 2, 3]
+                .map(|key| {
+                    (
+                        vec![key],
+                        Column::Coins,
+                        WriteOperation::Insert(Arc::new(vec![key])),
+                    )
+                })
+                .into_iter(),
+            WriteDurability::Sync,
+        )
+        .unwrap();
+        db.delete(&[2], Column::Coins).unwrap();
+        db.flush().unwrap();
+        drop(db);
+
+        // When
+        let db = RedbStore::<OnChain>::open(tmp_dir.path(), None).unwrap();
+
+        // Then
+        assert_eq!(keys(&db, Column::Coins), vec![vec![1], vec![3]]);
+    }
+
+    #[test]
+    fn snapshot_doesnt_see_later_writes_and_rejects_writes() {
+        // Given
+        let (db, _tmp) = create_db();
+        db.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+        let snapshot = db.snapshot().unwrap();
+
+        // When
+        db.put(&[2], Column::Coins, Arc::new(vec![2])).unwrap();
+        db.clear_column(Column::Coins).unwrap();
+
+        // Then
+        assert_eq!(
+            snapshot
+                .iter_keys(Column::Coins, None, None, IterDirection::Forward)
+                .collect::<StorageResult<Vec<_>>>()
+                .unwrap(),
+            vec![vec![1]]
+        );
+        assert!(matches!(
+            snapshot
+                .put(&[3], Column::Coins, Arc::new(vec![]))
+                .map_err(DatabaseError::from),
+            Err(DatabaseError::ReadOnly)
+        ));
+        assert!(keys(&db, Column::Coins).is_empty());
+    }
+
+    #[test]
+    fn delete_all_removes_only_prefixed_keys() {
+        // Given
+        let (db, _tmp) = create_db();
+        for key in [[1, 0], [1, 1], [2, 0]] {
+            db.put(&key, Column::Coins, Arc::new(vec![])).unwrap();
+        }
+
+        // When
+        let removed = db.delete_all(Column::Coins, Some(&[1])).unwrap();
+
+        // Then
+        assert_eq!(removed, 2);
+        assert_eq!(keys(&db, Column::Coins), vec![vec![2, 0]]);
+    }
+
+    #[test]
+    fn checkpoint_opens_as_the_database() {
+        // Given
+        let (db, _tmp) = create_db();
+        db.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+        let checkpoint_dir = TempDir::new().unwrap();
+
+        // When
+        db.checkpoint(checkpoint_dir.path()).unwrap();
+        db.put(&[2], Column::Coins, Arc::new(vec![2])).unwrap();
+
+        // Then
+        let checkpoint = RedbStore::<OnChain>::open(checkpoint_dir.path(), None).unwrap();
+        assert_eq!(keys(&checkpoint, Column::Coins), vec![vec![1]]);
+        assert!(db.checkpoint(checkpoint_dir.path()).is_err());
+    }
+
+    #[test]
+    fn column_stats_counts_keys() {
+        // Given
+        let (db, _tmp) = create_db();
+        db.put(&[1], Column::Coins, Arc::new(vec![1; 10])).unwrap();
+        db.put(&[2], Column::Coins, Arc::new(vec![2; 10])).unwrap();
+
+        // When
+        let stats = db.column_stats(Column::Coins).unwrap();
+
+        // Then
+        assert_eq!(stats.keys, 2);
+        assert!(stats.size >= 22);
+    }
+
+    /// Returns the missing path, the empty directory, and the path of the database
+    /// with the entry.
+    fn paths_for_open_modes(tmp: &TempDir) -> [PathBuf; 3] {
+        let missing = tmp.path().join("missing");
+        let empty = tmp.path().join("empty");
+        std::fs::create_dir_all(&empty).unwrap();
+        let valid = tmp.path().join("valid");
+        let db = RedbStore::<OnChain>::open(&valid, None).unwrap();
+        db.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+        [missing, empty, valid]
+    }
+
+    fn open_in_mode(path: &Path, mode: OpenMode) -> DatabaseResult<RedbStore<OnChain>> {
+        RedbStore::open_with_mode(path, None, mode)
+    }
+
+    #[test]
+    fn create_if_missing_opens_any_path() {
+        // Given
+        let tmp = TempDir::new().unwrap();
+        let [missing, empty, valid] = paths_for_open_modes(&tmp);
+
+        // When
+        let results = [&missing, &empty, &valid]
+            .map(|path| open_in_mode(path, OpenMode::CreateIfMissing));
+
+        // Then
+        let [missing, empty, valid] = results.map(Result::unwrap);
+        assert!(keys(&missing, Column::Coins).is_empty());
+        assert!(keys(&empty, Column::Coins).is_empty());
+        assert_eq!(keys(&valid, Column::Coins), vec![vec![1]]);
+    }
+
+    #[test]
+    fn must_exist_refuses_missing_and_empty_paths() {
+        // Given
+        let tmp = TempDir::new().unwrap();
+        let [missing, empty, valid] = paths_for_open_modes(&tmp);
+
+        // When
+        let results = [&missing, &empty, &valid]
+            .map(|path| open_in_mode(path, OpenMode::MustExist));
+
+        // Then
+        let [missing_result, empty_result, valid_result] = results;
+        let err = format!("{:?}", missing_result.unwrap_err());
+        assert!(err.contains(&missing.display().to_string()), "{err}");
+        assert!(!missing.exists());
+        let err = format!("{:?}", empty_result.unwrap_err());
+        assert!(err.contains(&empty.display().to_string()), "{err}");
+        assert_eq!(keys(&valid_result.unwrap(), Column::Coins), vec![vec![1]]);
+    }
+
+    #[test]
+    fn must_not_exist_refuses_valid_database() {
+        // Given
+        let tmp = TempDir::new().unwrap();
+        let [missing, empty, valid] = paths_for_open_modes(&tmp);
+
+        // When
+        let results = [&missing, &empty, &valid]
+            .map(|path| open_in_mode(path, OpenMode::MustNotExist));
+
+        // Then
+        let [missing_result, empty_result, valid_result] = results;
+        assert!(missing_result.is_ok());
+        assert!(empty_result.is_ok());
+        let err = format!("{:?}", valid_result.unwrap_err());
+        assert!(err.contains(&valid.display().to_string()), "{err}");
+    }
+}
@@ -1,57 +1,131 @@
 use crate::{
     database::{
+        column_lifecycle,
         convert_to_rocksdb_direction,
-        database_description::DatabaseDescription,
+        database_description::{
+            debug_assert_key_size,
+            DatabaseDescription,
+            RetiredColumn,
+        },
         Error as DatabaseError,
         Result as DatabaseResult,
     },
     state::{
+        column_config::{
+            CachePriority,
+            ColumnsConfig,
+            Compression,
+            ResourceLimits,
+        },
+        column_iter::ColumnIter,
+        ensure_sorted,
+        next_prefix,
+        rocks_db_key_iterator::{
+            seek,
+            ExtractItem,
+            RocksDBColumnIter,
+            RocksDBKeyIterator,
+        },
+        ttl,
         BatchOperations,
+        ColumnEngineStats,
+        ColumnStats,
+        DataSourceInner,
+        EngineStats,
+        IngestStats,
         IterDirection,
+        StallReason,
         TransactableStorage,
+        WriteDurability,
+        WritePressure,
     },
 };
 use fuel_core_metrics::core_metrics::database_metrics;
 use fuel_core_storage::{
     iter::{
+        is_start_within_prefix,
         BoxedIter,
         IntoBoxedIter,
+        IterConfig,
+        IterOptions,
         IteratorableStore,
+        PageIter,
     },
     kv_store::{
+        merge_value,
         KVItem,
+        KeyItem,
         KeyValueStore,
         StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
         Value,
         WriteOperation,
     },
     Result as StorageResult,
 };
+use itertools::Itertools;
 use rand::RngCore;
 use rocksdb::{
+    backup::{
+        BackupEngine,
+        BackupEngineOptions,
+        RestoreOptions,
+    },
+    checkpoint::Checkpoint,
     BlockBasedOptions,
     BoundColumnFamily,
     Cache,
     ColumnFamilyDescriptor,
+    CompactionDecision,
+    DBAccess,
     DBCompressionType,
+    DBRawIteratorWithThreadMode,
     DBWithThreadMode,
+    Env,
+    ErrorKind,
+    IngestExternalFileOptions,
     IteratorMode,
+    MergeOperands,
     MultiThreaded,
     Options,
     ReadOptions,
     SliceTransform,
+    SnapshotWithThreadMode,
+    SstFileWriter,
     WriteBatch,
+    WriteOptions,
+    DEFAULT_COLUMN_FAMILY_NAME,
 };
 use std::{
     cmp,
+    collections::{
+        hash_map::DefaultHasher,
+        BTreeSet,
+        HashMap,
+    },
     env,
     fmt::Debug,
+    hash::{
+        Hash,
+        Hasher,
+    },
     iter,
+    ops::Bound,
     path::{
         Path,
         PathBuf,
     },
-    sync::Arc,
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Arc,
+        RwLock,
+        RwLockReadGuard,
+        RwLockWriteGuard,
+    },
 };
 
 type DB = DBWithThreadMode<MultiThreaded>;
@@ -91,9 +165,145 @@ impl Drop for ShallowTempDir {
     }
 }
 
+/// The snapshot of the database pinned until it is dropped.
+pub(crate) struct PinnedSnapshot {
+    snapshot: SnapshotWithThreadMode<'static, DB>,
+    // The snapshot borrows the `DB`, so the `DB` must outlive it.
+    // Fields are dropped in the order of declaration, so the `snapshot` is released first.
+    _db: Arc<DB>,
+}
+
+impl PinnedSnapshot {
+    fn new(db: Arc<DB>) -> Self {
+        let snapshot = db.snapshot();
+        // SAFETY: The `DB` is kept alive by the `Arc` stored in the same structure,
+        // and the snapshot is dropped before it.
+        let snapshot = unsafe {
+            core::mem::transmute::<
+                SnapshotWithThreadMode<'_, DB>,
+                SnapshotWithThreadMode<'static, DB>,
+            >(snapshot)
+        };
+        Self { snapshot, _db: db }
+    }
+}
+
+impl Debug for PinnedSnapshot {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PinnedSnapshot").finish_non_exhaustive()
+    }
+}
+
+/// The block cache shared by columns. It is kept to recreate columns with the same options.
+#[derive(Clone, Default)]
+struct BlockCache(Option<Cache>);
+
+impl Debug for BlockCache {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("BlockCache")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+/// The number of stripes of [`KeyLocks`].
+const KEY_LOCK_STRIPES: u64 = 64;
+
+/// Locks of keys striped by the hash of the column and the key, so operations on keys
+/// of different stripes don't wait for each other. Read-modify-write operations hold
+/// the stripe of their key exclusively, while other writes hold stripes of their keys
+/// shared, so they can't interleave with the read and the write of the key.
+#[derive(Debug)]
+struct KeyLocks(Box<[RwLock<()>]>);
+
+impl Default for KeyLocks {
+    fn default() -> Self {
+        Self((0..KEY_LOCK_STRIPES).map(|_| RwLock::new(())).collect())
+    }
+}
+
+impl KeyLocks {
+    fn stripe(column: u32, key: &[u8]) -> usize {
+        let mut hasher = DefaultHasher::new();
+        (column, key).hash(&mut hasher);
+        (hasher.finish() % KEY_LOCK_STRIPES) as usize
+    }
+
+    fn exclusive(&self, column: u32, key: &[u8]) -> RwLockWriteGuard<'_, ()> {
+        self.0[Self::stripe(column, key)].write().expect("poisoned")
+    }
+
+    fn shared(&self, column: u32, key: &[u8]) -> RwLockReadGuard<'_, ()> {
+        self.0[Self::stripe(column, key)].read().expect("poisoned")
+    }
+
+    /// Locks `stripes` in the increasing order, so writes of many keys never deadlock.
+    fn shared_many(&self, stripes: BTreeSet<usize>) -> Vec<RwLockReadGuard<'_, ()>> {
+        stripes
+            .into_iter()
+            .map(|stripe| self.0[stripe].read().expect("poisoned"))
+            .collect()
+    }
+
+    /// Locks all stripes, for writes of ranges of keys.
+    fn shared_all(&self) -> Vec<RwLockReadGuard<'_, ()>> {
+        self.0
+            .iter()
+            .map(|stripe| stripe.read().expect("poisoned"))
+            .collect()
+    }
+}
+
+/// The options of the database with the enabled statistics. The statistics are shared
+/// by the copy of the options, so they count operations of the opened database.
+#[derive(Clone, Default)]
+struct Statistics(Option<Arc<Options>>);
+
+impl Debug for Statistics {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("Statistics")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+/// The result of the [`RocksDb::repair`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairReport<Column> {
+    /// The number of SST files that remain in the database after the repair.
+    pub salvaged_files: usize,
+    /// The number of SST files moved into the `lost` directory by the repair.
+    pub dropped_files: usize,
+    /// The columns that were missing after the repair and were recreated empty.
+    pub recreated_columns: Vec<Column>,
+}
+
 #[derive(Debug)]
 pub struct RocksDb<Description> {
-    db: DB,
+    /// Reads go through the snapshot if the database is a snapshot of another one.
+    snapshot: Option<Arc<PinnedSnapshot>>,
+    db: Arc<DB>,
+    read_only: bool,
+    /// Writes hold the shared lock, while operations over whole columns(like the clear),
+    /// the bulk import and the shutdown hold the exclusive lock.
+    lock: RwLock<()>,
+    /// Read-modify-write operations(like `take`) hold the lock of their key exclusively,
+    /// while other writes hold locks of their keys shared, so they can't interleave.
+    key_locks: KeyLocks,
+    /// The configuration of columns used to open the database.
+    columns_config: Arc<ColumnsConfig>,
+    block_cache: BlockCache,
+    statistics: Statistics,
+    /// Lookups of column family handles hold the shared lock, while the clear of the column
+    /// holds the exclusive lock to recreate its column family, so lookups never miss it.
+    column_families: Arc<RwLock<()>>,
+    /// Set while the [`BulkImport`] is alive. Other writes are refused in this mode.
+    bulk_import: AtomicBool,
+    /// Set by the [`TransactableStorage::begin_shutdown`]. Writes are refused after it.
+    shutting_down: AtomicBool,
+    /// The column families on disk that are not columns of the `Description`, e.g. created
+    /// by a newer version of the node. They are opened, but never read or written.
+    unknown_column_families: Vec<String>,
     _marker: core::marker::PhantomData<Description>,
 }
 
@@ -116,41 +326,457 @@ where
         path: P,
         columns: Vec<Description::Column>,
         capacity: Option<usize>,
+    ) -> DatabaseResult<Self> {
+        Self::open_with_config(path, columns, capacity, &ColumnsConfig::default())
+    }
+
+    /// Opens the database with the `config` of columns. Columns missing in the `config`
+    /// use the [`DatabaseDescription::column_config`].
+    ///
+    /// The `columns` missing on disk are created. Column families on disk unknown to
+    /// the `Description` are opened and left untouched, unless the `config`
+    /// [denies](ColumnsConfig::deny_unknown_column_families) them. The missing
+    /// database is created unless the [open mode](ColumnsConfig::with_open_mode) refuses it.
+    pub fn open_with_config<P: AsRef<Path>>(
+        path: P,
+        columns: Vec<Description::Column>,
+        capacity: Option<usize>,
+        config: &ColumnsConfig,
     ) -> DatabaseResult<Self> {
         let path = path.as_ref().join(Description::name());
-        let mut block_opts = BlockBasedOptions::default();
-        // See https://github.com/facebook/rocksdb/blob/a1523efcdf2f0e8133b9a9f6e170a0dad49f928f/include/rocksdb/table.h#L246-L271 for details on what the format versions are/do.
-        block_opts.set_format_version(5);
+        let mode = config.open_mode();
+        mode.check::<Description>(&path, Self::exists_at(&path))?;
+        let (mut opts, block_cache) = Self::db_opts(capacity, config.limits());
+        opts.create_if_missing(mode.creates_missing());
+        if config.statistics_enabled() {
+            opts.enable_statistics();
+        }
+        let statistics =
+            Statistics(config.statistics_enabled().then(|| Arc::new(opts.clone())));
+        // The `columns` missing on disk are created with their descriptors.
+        opts.create_missing_column_families(true);
+        let unknown_columns = config.unknown_columns::<Description>();
+        if !unknown_columns.is_empty() {
+            tracing::warn!(
+                "The config of unknown columns {:?} of the `{}` database is ignored",
+                unknown_columns,
+                Description::name()
+            );
+        }
 
-        if let Some(capacity) = capacity {
-            // Set cache size 1/3 of the capacity as recommended by
-            // https://github.com/facebook/rocksdb/wiki/Setup-Options-and-Basic-Tuning#block-cache-size
-            let block_cache_size = capacity / 3;
-            let cache = Cache::new_lru_cache(block_cache_size);
-            block_opts.set_block_cache(&cache);
-            // "index and filter blocks will be stored in block cache, together with all other data blocks."
-            // See: https://github.com/facebook/rocksdb/wiki/Memory-usage-in-RocksDB#indexes-and-filter-blocks
-            block_opts.set_cache_index_and_filter_blocks(true);
-            // Don't evict L0 filter/index blocks from the cache
-            block_opts.set_pin_l0_filter_and_index_blocks_in_cache(true);
-        } else {
-            block_opts.disable_cache();
+        Self::ensure_retired_col_names()?;
+        // The database that doesn't exist yet has no column families.
+        let existing = DB::list_cf(&opts, &path).unwrap_or_default();
+        let retired = Description::retired_columns()
+            .iter()
+            .filter(|column| existing.contains(&Self::retired_col_name(column)))
+            .collect::<Vec<_>>();
+        let unknown_column_families = Self::unknown_column_families_of(&existing);
+        if !unknown_column_families.is_empty() {
+            if config.denies_unknown_column_families() {
+                return Err(anyhow::anyhow!(
+                    "The `{}` database has unknown column families {:?}",
+                    Description::name(),
+                    unknown_column_families
+                )
+                .into())
+            }
+            tracing::warn!(
+                "The unknown column families {:?} of the `{}` database are ignored",
+                unknown_column_families,
+                Description::name()
+            );
         }
-        block_opts.set_bloom_filter(10.0, true);
 
-        let cf_descriptors = columns.clone().into_iter().map(|i| {
-            ColumnFamilyDescriptor::new(Self::col_name(i), Self::cf_opts(i, &block_opts))
+        // RocksDB refuses to open the database without all of its column families,
+        // so the existing ones are opened in addition to the `columns`.
+        let known = enum_iterator::all::<Description::Column>()
+            .map(|column| (Self::col_name(column), column))
+            .collect::<HashMap<_, _>>();
+        let names = columns
+            .iter()
+            .map(|column| Self::col_name(*column))
+            .chain(existing)
+            .unique()
+            .collect::<Vec<_>>();
+        let cf_descriptors = names.into_iter().map(|name| {
+            let cf_opts = known
+                .get(&name)
+                .map(|column| Self::cf_opts(*column, block_cache.as_ref(), config))
+                .unwrap_or_default();
+            ColumnFamilyDescriptor::new(name, cf_opts)
+        });
+
+        let db = DB::open_cf_descriptors(&opts, &path, cf_descriptors)
+            .map_err(|err| {
+                tracing::error!("Couldn't open the database with an error: {}. \nThe corrupted database can be repaired with `RocksDb::repair`", err);
+                rocksdb_error(err)
+            })?;
+        let rocks_db = RocksDb {
+            snapshot: None,
+            db: Arc::new(db),
+            read_only: false,
+            lock: RwLock::new(()),
+            key_locks: Default::default(),
+            columns_config: Arc::new(config.clone()),
+            block_cache: BlockCache(block_cache),
+            statistics,
+            column_families: Default::default(),
+            bulk_import: AtomicBool::new(false),
+            shutting_down: AtomicBool::new(false),
+            unknown_column_families,
+            _marker: Default::default(),
+        };
+        if !retired.is_empty() {
+            // Ids are checked before the drop, so the reused id is rejected
+            // before it drops anything.
+            column_lifecycle::check_column_ids::<Description>(&rocks_db)?;
+        }
+        for column in retired {
+            Self::drop_retired_column(&rocks_db.db, column, config)?;
+        }
+        Ok(rocks_db)
+    }
+
+    /// Ensures that no retired column shares the name of the column family
+    /// with the column of the `Description`, so dropping it never drops the live column.
+    fn ensure_retired_col_names() -> DatabaseResult<()> {
+        for column in enum_iterator::all::<Description::Column>() {
+            let name = Self::col_name(column);
+            if let Some(retired) = Description::retired_columns()
+                .iter()
+                .find(|retired| Self::retired_col_name(retired) == name)
+            {
+                return Err(DatabaseError::ColumnIdReused {
+                    id: retired.id,
+                    column: column.name(),
+                    previous: retired.name.to_string(),
+                })
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the `path` contains the RocksDB, i.e. its `CURRENT` file
+    /// and the `MANIFEST` it points to. The empty directory isn't the database.
+    fn exists_at(path: &Path) -> bool {
+        std::fs::read_to_string(path.join("CURRENT"))
+            .map(|manifest| path.join(manifest.trim()).is_file())
+            .unwrap_or(false)
+    }
+
+    /// Returns the column families on disk that are not columns of the `Description`.
+    /// They are left untouched by the database.
+    pub fn unknown_column_families(&self) -> &[String] {
+        &self.unknown_column_families
+    }
+
+    fn unknown_column_families_of(existing: &[String]) -> Vec<String> {
+        existing
+            .iter()
+            .filter(|name| name.as_str() != DEFAULT_COLUMN_FAMILY_NAME)
+            .filter(|name| {
+                !Description::retired_columns()
+                    .iter()
+                    .any(|column| Self::retired_col_name(column) == **name)
+            })
+            .filter(|name| {
+                !enum_iterator::all::<Description::Column>()
+                    .any(|column| Self::col_name(column) == **name)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Drops the column family of the retired `column`. The column family with data is
+    /// dropped only if the [`ColumnsConfig::drop_retired_columns_with_data`] allows it.
+    fn drop_retired_column(
+        db: &DB,
+        column: &RetiredColumn,
+        config: &ColumnsConfig,
+    ) -> DatabaseResult<()> {
+        let name = Self::retired_col_name(column);
+        let has_data = {
+            let cf = db.cf_handle(&name).expect("The column family is opened");
+            let mut iter = db.raw_iterator_cf(&cf);
+            iter.seek_to_first();
+            iter.status().map_err(rocksdb_error)?;
+            iter.valid()
+        };
+        if has_data && !config.drops_retired_columns_with_data() {
+            return Err(DatabaseError::RetiredColumnNotEmpty {
+                id: column.id,
+                column: column.name,
+            })
+        }
+        db.drop_cf(&name).map_err(rocksdb_error)?;
+        tracing::info!(
+            "The retired column `{}` of the `{}` database is dropped",
+            column.name,
+            Description::name()
+        );
+        Ok(())
+    }
+
+    /// Opens the database in read-only mode. It is safe to open the database
+    /// used by another process in this mode. The database only sees the data
+    /// that was committed before the opening.
+    pub fn open_read_only<P: AsRef<Path>>(
+        path: P,
+        columns: Vec<Description::Column>,
+        capacity: Option<usize>,
+    ) -> DatabaseResult<Self> {
+        let path = path.as_ref().join(Description::name());
+        let (opts, block_cache) = Self::db_opts(capacity, &ResourceLimits::default());
+
+        let cf_descriptors = columns.into_iter().map(|i| {
+            ColumnFamilyDescriptor::new(
+                Self::col_name(i),
+                Self::cf_opts(i, block_cache.as_ref(), &ColumnsConfig::default()),
+            )
+        });
+
+        let db = DB::open_cf_descriptors_read_only(&opts, &path, cf_descriptors, false)
+            .map_err(rocksdb_error)?;
+        let rocks_db = RocksDb {
+            snapshot: None,
+            db: Arc::new(db),
+            read_only: true,
+            lock: RwLock::new(()),
+            key_locks: Default::default(),
+            columns_config: Default::default(),
+            block_cache: Default::default(),
+            statistics: Default::default(),
+            column_families: Default::default(),
+            bulk_import: AtomicBool::new(false),
+            shutting_down: AtomicBool::new(false),
+            unknown_column_families: vec![],
+            _marker: Default::default(),
+        };
+        Ok(rocks_db)
+    }
+
+    /// Opens the database as a secondary instance of the primary database at the `primary_path`.
+    /// The secondary instance rejects writes and sees the data that the primary had at
+    /// the opening or at the last [`Self::try_catch_up_with_primary`]. It is safe to
+    /// use while the primary is running in another process. The `secondary_path` stores
+    /// the own files of the secondary, like info logs.
+    ///
+    /// Only the `columns` are opened, so column families created by the primary later are ignored.
+    pub fn open_secondary<P: AsRef<Path>, S: AsRef<Path>>(
+        primary_path: P,
+        secondary_path: S,
+        columns: Vec<Description::Column>,
+    ) -> DatabaseResult<Self> {
+        let primary_path = primary_path.as_ref().join(Description::name());
+        let secondary_path = secondary_path.as_ref().join(Description::name());
+        let (mut opts, block_cache) = Self::db_opts(None, &ResourceLimits::default());
+        // The secondary instance requires all files to be kept open.
+        opts.set_max_open_files(-1);
+
+        let cf_descriptors = columns.into_iter().map(|i| {
+            ColumnFamilyDescriptor::new(
+                Self::col_name(i),
+                Self::cf_opts(i, block_cache.as_ref(), &ColumnsConfig::default()),
+            )
+        });
+
+        let db = DB::open_cf_descriptors_as_secondary(
+            &opts,
+            primary_path,
+            secondary_path,
+            cf_descriptors,
+        )
+        .map_err(rocksdb_error)?;
+        let rocks_db = RocksDb {
+            snapshot: None,
+            db: Arc::new(db),
+            read_only: true,
+            lock: RwLock::new(()),
+            key_locks: Default::default(),
+            columns_config: Default::default(),
+            block_cache: Default::default(),
+            statistics: Default::default(),
+            column_families: Default::default(),
+            bulk_import: AtomicBool::new(false),
+            shutting_down: AtomicBool::new(false),
+            unknown_column_families: vec![],
+            _marker: Default::default(),
+        };
+        Ok(rocks_db)
+    }
+
+    /// Catches up the secondary instance with the latest changes of the primary.
+    /// Returns an error if the database is not opened by [`Self::open_secondary`].
+    pub fn try_catch_up_with_primary(&self) -> DatabaseResult<()> {
+        self.db.try_catch_up_with_primary().map_err(rocksdb_error)
+    }
+
+    /// Returns the value of the RocksDB property of the whole database, like
+    /// [`rocksdb::properties::DBSTATS`], or `None` if the property is unknown.
+    pub fn property_value(
+        &self,
+        name: &std::ffi::CStr,
+    ) -> DatabaseResult<Option<String>> {
+        self.db.property_value(name).map_err(rocksdb_error)
+    }
+
+    /// Returns the integer value of the RocksDB property of the `column`, like
+    /// [`rocksdb::properties::ESTIMATE_NUM_KEYS`], or `None` if the property is unknown.
+    pub fn property_int_value(
+        &self,
+        column: Description::Column,
+        name: &std::ffi::CStr,
+    ) -> DatabaseResult<Option<u64>> {
+        self.db
+            .property_int_value_cf(&self.cf(column), name)
+            .map_err(rocksdb_error)
+    }
+
+    /// Restores the database at the `path` from the latest backup in the `backup_dir`.
+    /// The database at the `path` must be closed. The restoration refuses to overwrite
+    /// a non-empty database unless `force` is set.
+    pub fn restore_from<P: AsRef<Path>, B: AsRef<Path>>(
+        backup_dir: B,
+        path: P,
+        force: bool,
+    ) -> DatabaseResult<()> {
+        let path = path.as_ref().join(Description::name());
+        let is_empty = match std::fs::read_dir(&path) {
+            Ok(mut entries) => entries.next().is_none(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => true,
+            Err(e) => return Err(rocksdb_error(e)),
+        };
+        if !is_empty && !force {
+            return Err(anyhow::anyhow!(
+                "The database at {} is not empty, use `force` to overwrite it",
+                path.display()
+            )
+            .into())
+        }
+
+        let mut engine = Self::backup_engine(backup_dir.as_ref())?;
+        engine
+            .restore_from_latest_backup(&path, &path, &RestoreOptions::default())
+            .map_err(rocksdb_error)
+    }
+
+    /// Repairs the corrupted database at the `path`. The repair salvages as much data
+    /// as possible and moves unreadable files into the `lost` directory of the database.
+    /// The `columns` that are missing after the repair are recreated empty.
+    ///
+    /// The repair may lose data, so it is never run automatically. The caller may run it
+    /// when the opening fails with the corruption error. The database must be closed.
+    pub fn repair<P: AsRef<Path>>(
+        path: P,
+        columns: Vec<Description::Column>,
+    ) -> DatabaseResult<RepairReport<Description::Column>> {
+        let path = path.as_ref().join(Description::name());
+        let lost = path.join("lost");
+        let (opts, block_cache) = Self::db_opts(None, &ResourceLimits::default());
+
+        let lost_before = count_sst_files(&lost)?;
+        DB::repair(&opts, &path).map_err(rocksdb_error)?;
+        let salvaged_files = count_sst_files(&path)?;
+        let dropped_files = count_sst_files(&lost)?.saturating_sub(lost_before);
+
+        let existing = DB::list_cf(&opts, &path).map_err(rocksdb_error)?;
+        let cf_descriptors = existing.iter().map(|name| {
+            let cf_opts = columns
+                .iter()
+                .find(|column| Self::col_name(**column) == *name)
+                .map(|column| {
+                    Self::cf_opts(
+                        *column,
+                        block_cache.as_ref(),
+                        &ColumnsConfig::default(),
+                    )
+                })
+                .unwrap_or_default();
+            ColumnFamilyDescriptor::new(name, cf_opts)
         });
+        let db = DB::open_cf_descriptors(&opts, &path, cf_descriptors)
+            .map_err(rocksdb_error)?;
+
+        let recreated_columns = columns
+            .into_iter()
+            .filter(|column| !existing.contains(&Self::col_name(*column)))
+            .collect::<Vec<_>>();
+        for column in &recreated_columns {
+            db.create_cf(
+                Self::col_name(*column),
+                &Self::cf_opts(*column, block_cache.as_ref(), &ColumnsConfig::default()),
+            )
+            .map_err(rocksdb_error)?;
+        }
+
+        Ok(RepairReport {
+            salvaged_files,
+            dropped_files,
+            recreated_columns,
+        })
+    }
 
+    fn backup_engine(backup_dir: &Path) -> DatabaseResult<BackupEngine> {
+        let opts = BackupEngineOptions::new(backup_dir).map_err(rocksdb_error)?;
+        let env = Env::new().map_err(rocksdb_error)?;
+        BackupEngine::open(&opts, &env).map_err(rocksdb_error)
+    }
+
+    /// Returns the options of the database and the block cache shared by columns.
+    fn db_opts(
+        capacity: Option<usize>,
+        limits: &ResourceLimits,
+    ) -> (Options, Option<Cache>) {
         let mut opts = Options::default();
+        // The budget shares one block cache between all columns, and the write buffer
+        // manager created by the `db_write_buffer_size` flushes the largest memtables
+        // when memtables of all columns exceed their part of the budget.
+        let block_cache = match (limits.memory_budget_bytes, limits.write_buffer_budget())
+        {
+            (Some(budget), Some(write_buffer_budget)) => {
+                opts.set_db_write_buffer_size(write_buffer_budget);
+                Some(Cache::new_lru_cache(
+                    budget.saturating_sub(write_buffer_budget),
+                ))
+            }
+            // Set cache size 1/3 of the capacity as recommended by
+            // https://github.com/facebook/rocksdb/wiki/Setup-Options-and-Basic-Tuning#block-cache-size
+            _ => capacity.map(|capacity| Cache::new_lru_cache(capacity / 3)),
+        };
+
         opts.create_if_missing(true);
         opts.set_compression_type(DBCompressionType::Lz4);
-        // TODO: Make it customizable https://github.com/FuelLabs/fuel-core/issues/1666
-        opts.set_max_total_wal_size(64 * 1024 * 1024);
+        opts.set_max_total_wal_size(limits.max_total_wal_size);
         let cpu_number =
             i32::try_from(num_cpus::get()).expect("The number of CPU can't exceed `i32`");
         opts.increase_parallelism(cmp::max(1, cpu_number / 2));
-        if let Some(capacity) = capacity {
+        if let Some(jobs) = limits.max_background_jobs {
+            opts.set_max_background_jobs(i32::try_from(jobs).unwrap_or(i32::MAX));
+        }
+        if let Some(rate) = limits.background_io_bytes_per_sec {
+            // The refill period and the fairness are the defaults of the RocksDB.
+            opts.set_ratelimiter(i64::try_from(rate).unwrap_or(i64::MAX), 100_000, 10);
+        }
+        let soft_limit = open_files_limit();
+        let max_open_files = limits.max_open_files_within(soft_limit);
+        if max_open_files != limits.max_open_files {
+            tracing::warn!(
+                "The `max_open_files` {:?} of the `{}` database doesn't fit into the soft limit \
+                of file descriptors of the process {:?}, it is clamped to {:?}. \
+                Raise the limit with `ulimit -n` to keep more files open",
+                limits.max_open_files,
+                Description::name(),
+                soft_limit,
+                max_open_files
+            );
+        }
+        // `-1` keeps all files open.
+        opts.set_max_open_files(
+            max_open_files.map_or(-1, |files| i32::try_from(files).unwrap_or(i32::MAX)),
+        );
+        // The row cache doesn't fit into the budget, so it is used only without it.
+        if let (Some(capacity), None) = (capacity, limits.memory_budget_bytes) {
             // Set cache size 1/3 of the capacity. Another 1/3 is
             // used by block cache and the last 1 / 3 remains for other purposes:
             //
@@ -160,165 +786,165 @@ where
             opts.set_row_cache(&cache);
         }
 
-        let db = match DB::open_cf_descriptors(&opts, &path, cf_descriptors) {
-            Err(_) => {
-                // setup cfs
-                match DB::open_cf(&opts, &path, &[] as &[&str]) {
-                    Ok(db) => {
-                        for i in columns {
-                            let opts = Self::cf_opts(i, &block_opts);
-                            db.create_cf(Self::col_name(i), &opts)
-                                .map_err(|e| DatabaseError::Other(e.into()))?;
-                        }
-                        Ok(db)
-                    }
-                    Err(err) => {
-                        tracing::error!("Couldn't open the database with an error: {}. \nTrying to repair the database", err);
-                        DB::repair(&opts, &path)
-                            .map_err(|e| DatabaseError::Other(e.into()))?;
-
-                        let cf_descriptors = columns.clone().into_iter().map(|i| {
-                            ColumnFamilyDescriptor::new(
-                                Self::col_name(i),
-                                Self::cf_opts(i, &block_opts),
-                            )
-                        });
-                        DB::open_cf_descriptors(&opts, &path, cf_descriptors)
-                    }
-                }
-            }
-            ok => ok,
+        (opts, block_cache)
+    }
+
+    fn ensure_writable(&self) -> StorageResult<()> {
+        if self.read_only {
+            return Err(DatabaseError::ReadOnly.into())
         }
-        .map_err(|e| DatabaseError::Other(e.into()))?;
-        let rocks_db = RocksDb {
-            db,
-            _marker: Default::default(),
-        };
-        Ok(rocks_db)
+        if self.bulk_import.load(Ordering::Acquire) {
+            return Err(DatabaseError::BulkImportInProgress.into())
+        }
+        if self.shutting_down.load(Ordering::Acquire) {
+            return Err(DatabaseError::ShuttingDown.into())
+        }
+        Ok(())
     }
 
-    fn cf(&self, column: Description::Column) -> Arc<BoundColumnFamily> {
-        self.db
-            .cf_handle(&Self::col_name(column))
-            .expect("invalid column state")
+    /// Takes the shared lock of writes and checks under it that writes are allowed.
+    /// The bulk import and the shutdown set their flag before they wait for the exclusive
+    /// lock, so the write either sees the flag or completes before they proceed.
+    fn write_guard(&self) -> StorageResult<RwLockReadGuard<'_, ()>> {
+        let guard = self.lock.read().expect("poisoned");
+        self.ensure_writable()?;
+        Ok(guard)
     }
 
-    fn col_name(column: Description::Column) -> String {
-        format!("col-{}", column.as_usize())
+    /// Takes the exclusive lock of writes and checks under it that writes are allowed.
+    fn exclusive_write_guard(&self) -> StorageResult<RwLockWriteGuard<'_, ()>> {
+        let guard = self.lock.write().expect("poisoned");
+        self.ensure_writable()?;
+        Ok(guard)
     }
 
-    fn cf_opts(column: Description::Column, block_opts: &BlockBasedOptions) -> Options {
-        let mut opts = Options::default();
-        opts.create_if_missing(true);
-        opts.set_compression_type(DBCompressionType::Lz4);
-        opts.set_block_based_table_factory(block_opts);
+    /// Collects the entries into a single `WriteBatch` and writes it with the `write_options`.
+    /// The caller holds the lock of writes, so handles of column families are resolved
+    /// under it, and the batch can't write into the column family dropped by the clear.
+    fn write_batch(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Description::Column, WriteOperation)>,
+        write_options: &WriteOptions,
+    ) -> StorageResult<()> {
+        let mut batch = WriteBatch::default();
+        let mut stripes = BTreeSet::new();
 
-        // All double-keys should be configured here
-        if let Some(size) = Description::prefix(&column) {
-            opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(size))
+        for (key, column, op) in entries {
+            if !matches!(op, WriteOperation::Remove) {
+                debug_assert_key_size::<Description>(&column, &key);
+            }
+            stripes.insert(KeyLocks::stripe(column.id(), &key));
+            match op {
+                WriteOperation::Insert(value) => {
+                    batch.put_cf(&self.cf(column), key, value.as_ref());
+                }
+                WriteOperation::Remove => {
+                    batch.delete_cf(&self.cf(column), key);
+                }
+                WriteOperation::Merge(operand) => {
+                    // RocksDB applies merges lazily, so the operand is validated before the write.
+                    merge_value(column, None, &operand)?;
+                    batch.merge_cf(&self.cf(column), key, operand.as_ref());
+                }
+            }
         }
 
-        opts
-    }
+        database_metrics().write_meter.inc();
+        database_metrics()
+            .bytes_written
+            .observe(batch.size_in_bytes() as f64);
 
-    /// RocksDB prefix iteration doesn't support reverse order,
-    /// but seeking the start key and iterating in reverse order works.
-    /// So we can create a workaround. We need to find the next available
-    /// element and use it as an anchor for reverse iteration,
-    /// but skip the first element to jump on the previous prefix.
-    /// If we can't find the next element, we are at the end of the list,
-    /// so we can use `IteratorMode::End` to start reverse iteration.
-    fn reverse_prefix_iter(
-        &self,
-        prefix: &[u8],
-        column: Description::Column,
-    ) -> impl Iterator<Item = KVItem> + '_ {
-        let maybe_next_item = next_prefix(prefix.to_vec())
-            .and_then(|next_prefix| {
-                self.iter_all(
-                    column,
-                    Some(next_prefix.as_slice()),
-                    None,
-                    IterDirection::Forward,
-                )
-                .next()
-            })
-            .and_then(|res| res.ok());
+        let _keys = self.key_locks.shared_many(stripes);
+        self.db
+            .write_opt(batch, write_options)
+            .map_err(|e| rocksdb_error(e).into())
+    }
 
-        if let Some((next_start_key, _)) = maybe_next_item {
-            let iter_mode = IteratorMode::From(
-                next_start_key.as_slice(),
-                rocksdb::Direction::Reverse,
-            );
-            let prefix = prefix.to_vec();
-            self
-                ._iter_all(column, ReadOptions::default(), iter_mode)
-                // Skip the element under the `next_start_key` key.
-                .skip(1)
-                .take_while(move |item| {
-                    if let Ok((key, _)) = item {
-                        key.starts_with(prefix.as_slice())
-                    } else {
-                        true
-                    }
-                })
-                .into_boxed()
-        } else {
-            // No next item, so we can start backward iteration from the end.
-            let prefix = prefix.to_vec();
-            self._iter_all(column, ReadOptions::default(), IteratorMode::End)
-                .take_while(move |item| {
-                    if let Ok((key, _)) = item {
-                        key.starts_with(prefix.as_slice())
-                    } else {
-                        true
-                    }
-                })
-                .into_boxed()
+    /// Switches the database into the bulk import mode. Batches written through the returned
+    /// guard skip the WAL and use larger write buffers, so they are lost on a crash
+    /// until the guard flushes them into SST files. Other writes are refused while
+    /// the guard is alive, and the guard flushes the data even if it is dropped
+    /// without [`BulkImport::finalize_bulk_import`].
+    ///
+    /// The mode is suitable for the data that can be re-derived, like the initial sync.
+    pub fn bulk_import(&self) -> DatabaseResult<BulkImport<'_, Description>> {
+        if self.read_only {
+            return Err(DatabaseError::ReadOnly)
         }
+        if self
+            .bulk_import
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return Err(DatabaseError::BulkImportInProgress)
+        }
+        // Waits for writes that started before the mode was entered.
+        drop(self.lock.write().expect("poisoned"));
+
+        let guard = BulkImport {
+            db: self,
+            finalized: false,
+        };
+        let write_buffer_size = BULK_IMPORT_WRITE_BUFFER_SIZE.to_string();
+        guard.set_write_buffer_sizes(|_| write_buffer_size.clone())?;
+        Ok(guard)
     }
 
-    fn _iter_all(
+    /// Writes sorted `entries` of the `column` into SST files in the `dir`,
+    /// starting the next file when the current one reaches the target size.
+    fn write_sst_files(
         &self,
         column: Description::Column,
-        opts: ReadOptions,
-        iter_mode: IteratorMode,
-    ) -> impl Iterator<Item = KVItem> + '_ {
-        self.db
-            .iterator_cf_opt(&self.cf(column), opts, iter_mode)
-            .map(|item| {
-                item.map(|(key, value)| {
-                    let value_as_vec = Vec::from(value);
-                    let key_as_vec = Vec::from(key);
-
-                    database_metrics().read_meter.inc();
-                    database_metrics().bytes_read.observe(
-                        (key_as_vec.len().saturating_add(value_as_vec.len())) as f64,
-                    );
-
-                    (key_as_vec, Arc::new(value_as_vec))
-                })
-                .map_err(|e| DatabaseError::Other(e.into()).into())
-            })
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Vec<u8>)>,
+        dir: &Path,
+    ) -> DatabaseResult<(IngestStats, Vec<PathBuf>)> {
+        std::fs::create_dir_all(dir)?;
+        // Files use the options of the column family, e.g. its compression and prefix filter.
+        let opts =
+            Self::cf_opts(column, self.block_cache.0.as_ref(), &self.columns_config);
+        let target_file_size = self.columns_config.limits().target_file_size;
+        let mut stats = IngestStats::default();
+        let mut files = vec![];
+        let mut writer: Option<SstFileWriter> = None;
+        let mut previous: Option<Vec<u8>> = None;
+        for (key, value) in entries {
+            ensure_sorted(column, previous.as_deref(), &key)?;
+            debug_assert_key_size::<Description>(&column, &key);
+            let file = match &mut writer {
+                Some(file) => file,
+                None => {
+                    let path = dir.join(format!("{}.sst", files.len()));
+                    let file = SstFileWriter::create(&opts);
+                    file.open(&path).map_err(rocksdb_error)?;
+                    files.push(path);
+                    writer.insert(file)
+                }
+            };
+            file.put(&key, &value).map_err(rocksdb_error)?;
+            stats.record(&key, &value);
+            if file.file_size() >= target_file_size {
+                file.finish().map_err(rocksdb_error)?;
+                writer = None;
+            }
+            previous = Some(key);
+        }
+        if let Some(mut file) = writer {
+            file.finish().map_err(rocksdb_error)?;
+        }
+        Ok((stats, files))
     }
-}
-
-impl<Description> KeyValueStore for RocksDb<Description>
-where
-    Description: DatabaseDescription,
-{
-    type Column = Description::Column;
 
-    fn write(
+    fn _write(
         &self,
         key: &[u8],
-        column: Self::Column,
+        column: Description::Column,
         buf: &[u8],
     ) -> StorageResult<usize> {
+        debug_assert_key_size::<Description>(&column, key);
         let r = buf.len();
         self.db
             .put_cf(&self.cf(column), key, buf)
-            .map_err(|e| DatabaseError::Other(e.into()))?;
+            .map_err(rocksdb_error)?;
 
         database_metrics().write_meter.inc();
         database_metrics().bytes_written.observe(r as f64);
@@ -326,23 +952,535 @@ where
         Ok(r)
     }
 
-    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+    fn _delete(&self, key: &[u8], column: Description::Column) -> StorageResult<()> {
         self.db
             .delete_cf(&self.cf(column), key)
-            .map_err(|e| DatabaseError::Other(e.into()).into())
+            .map_err(|e| rocksdb_error(e).into())
     }
 
-    fn size_of_value(
-        &self,
-        key: &[u8],
-        column: Self::Column,
-    ) -> StorageResult<Option<usize>> {
-        database_metrics().read_meter.inc();
+    /// Returns the snapshot of the database, or the snapshot the database reads from.
+    fn pin_snapshot(&self) -> Arc<PinnedSnapshot> {
+        match &self.snapshot {
+            Some(snapshot) => snapshot.clone(),
+            None => Arc::new(PinnedSnapshot::new(self.db.clone())),
+        }
+    }
 
-        Ok(self
+    /// Returns the read options that read from the pinned snapshot, if any.
+    fn read_options(&self) -> ReadOptions {
+        let mut opts = ReadOptions::default();
+        if let Some(snapshot) = &self.snapshot {
+            opts.set_snapshot(&snapshot.snapshot);
+        }
+        opts
+    }
+
+    fn cf(&self, column: Description::Column) -> Arc<BoundColumnFamily> {
+        let _guard = self.column_families.read().expect("poisoned");
+        self.db
+            .cf_handle(&Self::col_name(column))
+            .expect("invalid column state")
+    }
+
+    fn col_name(column: Description::Column) -> String {
+        format!("col-{}", column.as_usize())
+    }
+
+    fn retired_col_name(column: &RetiredColumn) -> String {
+        format!("col-{}", column.id)
+    }
+
+    /// Returns the size of the memtable of the `column`. One memtable can't use more
+    /// than the whole write buffer budget.
+    fn write_buffer_size(column: Description::Column, config: &ColumnsConfig) -> usize {
+        let size = config.column::<Description>(column).write_buffer_size;
+        config
+            .limits()
+            .write_buffer_budget()
+            .map_or(size, |budget| size.min(budget))
+    }
+
+    fn cf_opts(
+        column: Description::Column,
+        block_cache: Option<&Cache>,
+        config: &ColumnsConfig,
+    ) -> Options {
+        let limits = config.limits();
+        let target_file_size = limits.target_file_size;
+        let write_buffer_size = Self::write_buffer_size(column, config);
+        let config = config.column::<Description>(column);
+
+        let mut block_opts = BlockBasedOptions::default();
+        // See https://github.com/facebook/rocksdb/blob/a1523efcdf2f0e8133b9a9f6e170a0dad49f928f/include/rocksdb/table.h#L246-L271 for details on what the format versions are/do.
+        block_opts.set_format_version(5);
+        block_opts.set_block_size(config.block_size);
+        match (block_cache, config.block_cache) {
+            (Some(cache), Some(priority)) => {
+                block_opts.set_block_cache(cache);
+                // "index and filter blocks will be stored in block cache, together with all other data blocks."
+                // See: https://github.com/facebook/rocksdb/wiki/Memory-usage-in-RocksDB#indexes-and-filter-blocks
+                block_opts.set_cache_index_and_filter_blocks(true);
+                // Don't evict L0 filter/index blocks of high priority columns from the cache
+                block_opts.set_pin_l0_filter_and_index_blocks_in_cache(
+                    priority == CachePriority::High,
+                );
+            }
+            _ => block_opts.disable_cache(),
+        }
+        block_opts.set_bloom_filter(10.0, true);
+
+        let mut opts = Options::default();
+        // All double-keys should be configured here
+        if let Some(size) = Description::prefix(&column) {
+            opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(size));
+            // The prefix bloom filter of the memtable skips memtables without the prefix.
+            opts.set_memtable_prefix_bloom_ratio(0.1);
+            // The table bloom filter contains prefixes for prefix seeks
+            // and whole keys for point lookups.
+            block_opts.set_whole_key_filtering(true);
+        }
+        opts.create_if_missing(true);
+        opts.set_compression_type(match config.compression {
+            Compression::None => DBCompressionType::None,
+            Compression::Lz4 => DBCompressionType::Lz4,
+            Compression::Zstd => DBCompressionType::Zstd,
+        });
+        opts.set_write_buffer_size(write_buffer_size);
+        opts.set_max_write_buffer_number(config.max_write_buffer_number);
+        opts.set_target_file_size_base(target_file_size);
+        opts.set_block_based_table_factory(&block_opts);
+        // The RocksDB raises triggers below the number of files that starts the compaction.
+        opts.set_level_zero_slowdown_writes_trigger(
+            i32::try_from(limits.level0_slowdown_writes_trigger).unwrap_or(i32::MAX),
+        );
+        opts.set_level_zero_stop_writes_trigger(
+            i32::try_from(limits.level0_stop_writes_trigger).unwrap_or(i32::MAX),
+        );
+        opts.set_soft_pending_compaction_bytes_limit(
+            usize::try_from(limits.soft_pending_compaction_bytes).unwrap_or(usize::MAX),
+        );
+        opts.set_hard_pending_compaction_bytes_limit(
+            usize::try_from(limits.hard_pending_compaction_bytes).unwrap_or(usize::MAX),
+        );
+
+        if let Some(operator) = column.merge_operator() {
+            opts.set_merge_operator_associative(
+                operator.name(),
+                move |_: &[u8], existing: Option<&[u8]>, operands: &MergeOperands| {
+                    operator.apply(existing, operands)
+                },
+            );
+        }
+        if let Some(ttl) = config.ttl {
+            // Reads hide expired entries, while the compaction reclaims their space.
+            opts.set_compaction_filter("ttl", move |_: u32, _: &[u8], value: &[u8]| {
+                if ttl::is_expired(value, ttl, ttl::now()) {
+                    CompactionDecision::Remove
+                } else {
+                    CompactionDecision::Keep
+                }
+            });
+        }
+
+        opts
+    }
+
+    fn _iter_store<T>(
+        &self,
+        column: Description::Column,
+        opts: ReadOptions,
+        iter_mode: IteratorMode,
+        snapshot: Arc<PinnedSnapshot>,
+    ) -> impl Iterator<Item = StorageResult<T::Item>> + '_
+    where
+        T: ExtractItem,
+    {
+        let iter = self.db.raw_iterator_cf_opt(&self.cf(column), opts);
+
+        RocksDBKeyIterator::<_, T>::new(iter, iter_mode, snapshot).map(|item| {
+            item.map(|item| {
+                database_metrics().read_meter.inc();
+                database_metrics().bytes_read.observe(T::size(&item) as f64);
+
+                item
+            })
+            .map_err(|e| rocksdb_error(e).into())
+        })
+    }
+
+    fn iter_store<T>(
+        &self,
+        column: Description::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<StorageResult<T::Item>>
+    where
+        T: ExtractItem,
+    {
+        let start = start.map_or(Bound::Unbounded, Bound::Included);
+        match self.iter_plan(column, prefix, start, end, direction) {
+            Some(plan) => self.iter_planned::<T>(column, plan),
+            None => iter::empty().into_boxed(),
+        }
+    }
+
+    fn iter_planned<T>(
+        &self,
+        column: Description::Column,
+        plan: IterPlan<'_>,
+    ) -> BoxedIter<StorageResult<T::Item>>
+    where
+        T: ExtractItem,
+    {
+        let iter = self._iter_store::<T>(column, plan.opts, plan.mode, plan.snapshot);
+        match plan.prefix {
+            Some(prefix) => iter
+                .take_while(move |item| {
+                    if let Ok(item) = item {
+                        T::starts_with(item, prefix.as_slice())
+                    } else {
+                        true
+                    }
+                })
+                .into_boxed(),
+            None => iter.into_boxed(),
+        }
+    }
+
+    /// Returns the plan of the iteration, or `None` if the iteration yields nothing.
+    fn iter_plan<'k>(
+        &self,
+        column: Description::Column,
+        prefix: Option<&'k [u8]>,
+        start: Bound<&'k [u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> Option<IterPlan<'k>> {
+        let (start, start_is_excluded) = match start {
+            Bound::Included(start) => (Some(start), false),
+            Bound::Excluded(start) => (Some(start), true),
+            Bound::Unbounded => (None, false),
+        };
+        if !is_start_within_prefix(prefix, start) {
+            return None
+        }
+
+        // The prefix seek uses bloom filters, but it is defined only for keys with the
+        // prefix of the column's prefix extractor. Iterations over shorter prefixes or
+        // the whole column use the total order.
+        let prefix_seek = matches!(
+            (prefix, Description::prefix(&column)),
+            (Some(prefix), Some(size)) if prefix.len() >= size
+        );
+
+        // The `prefix` and the `end` are respected by the RocksDB itself via the iterate
+        // bounds, so it doesn't read blocks past them. The prefix is the smallest key
+        // with it, and the upper bound of the prefix is exclusive.
+        let mut lower_bound = prefix.map(<[u8]>::to_vec);
+        let mut upper_bound = prefix.and_then(|prefix| next_prefix(prefix.to_vec()));
+        if let Some(end) = end {
+            match direction {
+                IterDirection::Forward => {
+                    upper_bound = Some(match upper_bound {
+                        Some(upper_bound) if upper_bound.as_slice() < end => upper_bound,
+                        _ => end.to_vec(),
+                    });
+                }
+                // The lower bound is inclusive, so we use the first key after the `end`.
+                IterDirection::Reverse => {
+                    let mut end = end.to_vec();
+                    end.push(0);
+                    lower_bound = Some(match lower_bound {
+                        Some(lower_bound) if lower_bound > end => lower_bound,
+                        _ => end,
+                    });
+                }
+            }
+        }
+        // The excluded `start` tightens the bound in the direction of the iteration,
+        // and the seek to the `start` is moved to the bound by the RocksDB.
+        if let Some(start) = start.filter(|_| start_is_excluded) {
+            match direction {
+                // The first key after the `start` is the `start` with the zero byte.
+                IterDirection::Forward => {
+                    let mut after_start = start.to_vec();
+                    after_start.push(0);
+                    lower_bound = Some(match lower_bound {
+                        Some(lower_bound) if lower_bound > after_start => lower_bound,
+                        _ => after_start,
+                    });
+                }
+                // The upper bound is exclusive, so it is the `start` itself.
+                IterDirection::Reverse => {
+                    upper_bound = Some(match upper_bound {
+                        Some(upper_bound) if upper_bound.as_slice() < start => {
+                            upper_bound
+                        }
+                        _ => start.to_vec(),
+                    });
+                }
+            }
+        }
+        // Each iteration reads from its own snapshot, so it never sees writes
+        // that happen after its creation, even if it is re-seeked.
+        let snapshot = self.pin_snapshot();
+        let read_options = |prefix_seek: bool| {
+            let mut opts = self.read_options();
+            opts.set_snapshot(&snapshot.snapshot);
+            opts.set_total_order_seek(!prefix_seek);
+            opts.set_prefix_same_as_start(prefix_seek);
+            if let Some(lower_bound) = lower_bound.clone() {
+                opts.set_iterate_lower_bound(lower_bound);
+            }
+            if let Some(upper_bound) = upper_bound.clone() {
+                opts.set_iterate_upper_bound(upper_bound);
+            }
+            opts
+        };
+
+        let plan = match (prefix, start) {
+            (None, None) => {
+                let mode =
+                    // if no start or prefix just start iterating over entire keyspace
+                    match direction {
+                        IterDirection::Forward => IteratorMode::Start,
+                        // end always iterates in reverse
+                        IterDirection::Reverse => IteratorMode::End,
+                    };
+                IterPlan {
+                    snapshot: snapshot.clone(),
+                    opts: read_options(false),
+                    mode,
+                    prefix: None,
+                }
+            }
+            (Some(prefix), None) => {
+                if direction == IterDirection::Reverse {
+                    // RocksDB prefix iteration doesn't support reverse order,
+                    // but iterating in reverse order from the end of the bounded range works.
+                    // The upper bound is the first key after all keys of the prefix,
+                    // so the iteration starts from the last key before it.
+                    //
+                    // The anchor isn't searched with the prefix seek, because the seek is undefined
+                    // when the column doesn't have the keys of the following prefix.
+                    IterPlan {
+                        snapshot: snapshot.clone(),
+                        opts: read_options(false),
+                        mode: IteratorMode::End,
+                        prefix: Some(prefix.to_vec()),
+                    }
+                } else {
+                    // start iterating in a certain direction within the keyspace
+                    IterPlan {
+                        snapshot: snapshot.clone(),
+                        opts: read_options(prefix_seek),
+                        mode: IteratorMode::From(
+                            prefix,
+                            convert_to_rocksdb_direction(direction),
+                        ),
+                        // The extractor may be shorter than the `prefix`.
+                        prefix: Some(prefix.to_vec()),
+                    }
+                }
+            }
+            (None, Some(start)) => {
+                // start iterating in a certain direction from the start key
+                IterPlan {
+                    snapshot: snapshot.clone(),
+                    opts: read_options(false),
+                    mode: IteratorMode::From(
+                        start,
+                        convert_to_rocksdb_direction(direction),
+                    ),
+                    prefix: None,
+                }
+            }
+            (Some(prefix), Some(start)) => {
+                // The forward seek finds the smallest key `>= start`, and the reverse seek
+                // finds the greatest key `<= start`, so the `start` doesn't need to exist.
+                // start iterating in a certain direction from the start key
+                // and end iterating when we've gone outside the prefix
+                IterPlan {
+                    snapshot: snapshot.clone(),
+                    opts: read_options(prefix_seek),
+                    mode: IteratorMode::From(
+                        start,
+                        convert_to_rocksdb_direction(direction),
+                    ),
+                    prefix: Some(prefix.to_vec()),
+                }
+            }
+        };
+        Some(plan)
+    }
+}
+
+/// The read options, the position where the iteration starts, and the prefix of the iteration.
+/// The iteration ends at the first key without the prefix.
+struct IterPlan<'k> {
+    /// The snapshot read by the `opts`. It must outlive the iterator.
+    snapshot: Arc<PinnedSnapshot>,
+    opts: ReadOptions,
+    mode: IteratorMode<'k>,
+    prefix: Option<Vec<u8>>,
+}
+
+impl<Description> KeyValueStore for RocksDb<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        let _guard = self.write_guard()?;
+        let _key = self.key_locks.shared(column.id(), key);
+        self._write(key, column, buf)
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        let _guard = self.write_guard()?;
+        let _key = self.key_locks.exclusive(column.id(), key);
+        let old_value = self.get(key, column)?;
+        self._write(key, column, value.as_ref())?;
+        Ok(old_value)
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        let _guard = self.write_guard()?;
+        let _key = self.key_locks.exclusive(column.id(), key);
+        let old_value = self.get(key, column)?;
+        if old_value.is_some() {
+            self._delete(key, column)?;
+        }
+        Ok(old_value)
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        let _guard = self.write_guard()?;
+        let _key = self.key_locks.exclusive(column.id(), key);
+        let current = self.get(key, column)?;
+        if current.as_deref().map(Vec::as_slice) != expected {
+            return Ok(Err(current))
+        }
+        self._write(key, column, new.as_ref())?;
+        Ok(Ok(()))
+    }
+
+    fn get_or_insert_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        init: &mut dyn FnMut() -> Vec<u8>,
+    ) -> StorageResult<Value> {
+        let _guard = self.write_guard()?;
+        let _key = self.key_locks.exclusive(column.id(), key);
+        if let Some(value) = self.get(key, column)? {
+            return Ok(value)
+        }
+        let value = Value::new(init());
+        self._write(key, column, value.as_ref())?;
+        Ok(value)
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        let _guard = self.write_guard()?;
+        let _key = self.key_locks.exclusive(column.id(), key);
+        database_metrics().read_meter.inc();
+        let current = self
+            .db
+            .get_pinned_cf_opt(&self.cf(column), key, &self.read_options())
+            .map_err(rocksdb_entry_error(column, key))?;
+        let new = f(current.as_deref());
+        let outcome = UpdateOutcome::of(current.as_deref(), new.as_deref());
+        drop(current);
+        match new {
+            Some(value) if outcome.is_changed() => {
+                self._write(key, column, &value)?;
+            }
+            None if outcome.is_changed() => self._delete(key, column)?,
+            _ => {}
+        }
+        Ok(outcome)
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        let _guard = self.write_guard()?;
+        let _key = self.key_locks.shared(column.id(), key);
+        self._delete(key, column)
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        // RocksDB applies merges lazily, so the operand is validated before the write.
+        merge_value(column, None, &operand)?;
+        let _guard = self.write_guard()?;
+        let _key = self.key_locks.shared(column.id(), key);
+        self.db
+            .merge_cf(&self.cf(column), key, operand.as_slice())
+            .map_err(rocksdb_error)?;
+
+        database_metrics().write_meter.inc();
+        database_metrics()
+            .bytes_written
+            .observe(operand.len() as f64);
+        Ok(())
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        database_metrics().read_meter.inc();
+
+        let cf = self.cf(column);
+        let opts = self.read_options();
+        // The bloom filter rejects most of the missing keys without reading data blocks.
+        if !self.db.key_may_exist_cf_opt(&cf, key, &opts) {
+            return Ok(false)
+        }
+        // The pinned value is not copied out of the block cache.
+        Ok(self
+            .db
+            .get_pinned_cf_opt(&cf, key, &opts)
+            .map_err(rocksdb_entry_error(column, key))?
+            .is_some())
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        database_metrics().read_meter.inc();
+
+        Ok(self
             .db
-            .get_pinned_cf(&self.cf(column), key)
-            .map_err(|e| DatabaseError::Other(e.into()))?
+            .get_pinned_cf_opt(&self.cf(column), key, &self.read_options())
+            .map_err(rocksdb_entry_error(column, key))?
             .map(|value| value.len()))
     }
 
@@ -351,8 +1489,8 @@ where
 
         let value = self
             .db
-            .get_cf(&self.cf(column), key)
-            .map_err(|e| DatabaseError::Other(e.into()))?;
+            .get_cf_opt(&self.cf(column), key, &self.read_options())
+            .map_err(rocksdb_entry_error(column, key))?;
 
         if let Some(value) = &value {
             database_metrics().bytes_read.observe(value.len() as f64);
@@ -361,6 +1499,49 @@ where
         Ok(value.map(Arc::new))
     }
 
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        database_metrics().read_meter.inc_by(keys.len() as u64);
+
+        self.db
+            .batched_multi_get_cf_opt(&self.cf(column), keys, false, &self.read_options())
+            .into_iter()
+            .zip(keys)
+            .map(|(value, key)| {
+                let value = value.map_err(rocksdb_entry_error(column, key))?;
+
+                Ok(value.map(|value| {
+                    database_metrics().bytes_read.observe(value.len() as f64);
+                    Arc::new(value.to_vec())
+                }))
+            })
+            .collect()
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        database_metrics().read_meter.inc();
+
+        let value = self
+            .db
+            .get_pinned_cf_opt(&self.cf(column), key, &self.read_options())
+            .map_err(rocksdb_entry_error(column, key))?;
+
+        if let Some(value) = &value {
+            database_metrics().bytes_read.observe(value.len() as f64);
+            f(value.as_ref());
+        }
+
+        Ok(value.is_some())
+    }
+
     fn read(
         &self,
         key: &[u8],
@@ -371,12 +1552,12 @@ where
 
         let r = self
             .db
-            .get_pinned_cf(&self.cf(column), key)
-            .map_err(|e| DatabaseError::Other(e.into()))?
+            .get_pinned_cf_opt(&self.cf(column), key, &self.read_options())
+            .map_err(rocksdb_entry_error(column, key))?
             .map(|value| {
                 let read = value.len();
                 std::io::Write::write_all(&mut buf, value.as_ref())
-                    .map_err(|e| DatabaseError::Other(anyhow::anyhow!(e)))?;
+                    .map_err(DatabaseError::from)?;
                 StorageResult::Ok(read)
             })
             .transpose()?;
@@ -400,286 +1581,3301 @@ where
         start: Option<&[u8]>,
         direction: IterDirection,
     ) -> BoxedIter<KVItem> {
-        match (prefix, start) {
-            (None, None) => {
-                let iter_mode =
-                    // if no start or prefix just start iterating over entire keyspace
-                    match direction {
-                        IterDirection::Forward => IteratorMode::Start,
-                        // end always iterates in reverse
-                        IterDirection::Reverse => IteratorMode::End,
-                    };
-                self._iter_all(column, ReadOptions::default(), iter_mode)
-                    .into_boxed()
-            }
-            (Some(prefix), None) => {
-                if direction == IterDirection::Reverse {
-                    self.reverse_prefix_iter(prefix, column).into_boxed()
-                } else {
-                    // start iterating in a certain direction within the keyspace
-                    let iter_mode = IteratorMode::From(
-                        prefix,
-                        convert_to_rocksdb_direction(direction),
-                    );
-                    let mut opts = ReadOptions::default();
-                    opts.set_prefix_same_as_start(true);
+        self.iter_range(column, prefix, start, None, direction)
+    }
+
+    /// The read-ahead of the iterator is sized by the `limit`, unless the options
+    /// set it explicitly, so small pages don't read more blocks than they need.
+    /// The excluded start is the bound of the iteration, so it is never read.
+    fn iter(&self, column: Self::Column, config: IterConfig) -> PageIter {
+        let plan = match config.limit {
+            Some(0) => None,
+            _ => self.iter_plan(
+                column,
+                config.prefix,
+                config.start,
+                None,
+                config.direction,
+            ),
+        };
+        let Some(mut plan) = plan else {
+            return PageIter::new(iter::empty().into_boxed())
+        };
+        let IterOptions {
+            fill_cache,
+            readahead_bytes,
+            pin_data,
+        } = config.options;
+        plan.opts.fill_cache(fill_cache);
+        plan.opts.set_pin_data(pin_data);
+        let page_read_ahead = config.limit.map(|limit| {
+            limit
+                .saturating_mul(READ_AHEAD_ENTRY_SIZE)
+                .min(MAX_PAGE_READ_AHEAD)
+        });
+        if let Some(read_ahead) = readahead_bytes.or(page_read_ahead) {
+            plan.opts.set_readahead_size(read_ahead);
+        }
+        let iter = match config.limit {
+            Some(limit) => self
+                .iter_planned::<KeyAndValue>(column, plan)
+                .take(limit)
+                .into_boxed(),
+            None => self.iter_planned::<KeyAndValue>(column, plan),
+        };
+        PageIter::new(iter)
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.iter_store::<KeyAndValue>(column, prefix, start, end, direction)
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.iter_store::<KeyOnly>(column, prefix, start, None, direction)
+    }
+
+    /// Seeks to the first key within the bounds of the prefix. Neither the key
+    /// nor the value is copied, and the read blocks aren't pinned.
+    fn has_any(&self, column: Self::Column, prefix: &[u8]) -> StorageResult<bool> {
+        let prefix = Some(prefix).filter(|prefix| !prefix.is_empty());
+        let Some(plan) = self.iter_plan(
+            column,
+            prefix,
+            Bound::Unbounded,
+            None,
+            IterDirection::Forward,
+        ) else {
+            return Ok(false)
+        };
+        let IterPlan {
+            snapshot,
+            mut opts,
+            mode,
+            ..
+        } = plan;
+        opts.set_pin_data(false);
+        let mut raw = self.db.raw_iterator_cf_opt(&self.cf(column), opts);
+        seek(&mut raw, mode);
+        let found = raw
+            .key()
+            .is_some_and(|key| prefix.map_or(true, |prefix| key.starts_with(prefix)));
+        raw.status().map_err(rocksdb_error)?;
+        drop(raw);
+        drop(snapshot);
+        database_metrics().read_meter.inc();
+        Ok(found)
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        // Hold the exclusive lock to return the exact number of removed entries.
+        let _guard = self.exclusive_write_guard()?;
+        let prefix = prefix.filter(|prefix| !prefix.is_empty());
+        let cf = self.cf(column);
+        let mut batch = WriteBatch::default();
+        let mut removed = 0usize;
+
+        let keys = self.iter_store::<KeyOnly>(
+            column,
+            prefix,
+            None,
+            None,
+            IterDirection::Forward,
+        );
+        match prefix.and_then(|prefix| Some((prefix, next_prefix(prefix.to_vec())?))) {
+            // The range deletion removes all keys of the prefix at once.
+            Some((prefix, end)) => {
+                for key in keys {
+                    key?;
+                    removed = removed.saturating_add(1);
+                }
+                batch.delete_range_cf(&cf, prefix, end.as_slice());
+            }
+            // The range can't be defined, so we remove keys one by one.
+            None => {
+                for key in keys {
+                    batch.delete_cf(&cf, key?);
+                    removed = removed.saturating_add(1);
+                }
+            }
+        }
+
+        database_metrics().write_meter.inc();
+        database_metrics()
+            .bytes_written
+            .observe(batch.size_in_bytes() as f64);
+
+        self.db.write(batch).map_err(rocksdb_error)?;
+        Ok(removed)
+    }
+}
+
+impl<Description> BatchOperations for RocksDb<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// The entries are collected into a single `WriteBatch` that is applied by one write
+    /// through the WAL. RocksDB applies the batch atomically, so after a crash, the database
+    /// is recovered either without or with all entries of the batch.
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for RocksDb<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_column(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> ColumnIter<'_> {
+        let start = start.map_or(Bound::Unbounded, Bound::Included);
+        let Some(plan) = self.iter_plan(column, prefix, start, None, direction) else {
+            return ColumnIter::boxed(iter::empty().into_boxed())
+        };
+        let raw = self.db.raw_iterator_cf_opt(&self.cf(column), plan.opts);
+        ColumnIter::rocks_db(RocksDBColumnIter::new(
+            raw,
+            plan.mode,
+            plan.prefix,
+            plan.snapshot,
+        ))
+    }
+
+    /// The [`WriteDurability::Sync`] syncs the WAL before the write returns.
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        let _guard = self.write_guard()?;
+        let mut write_options = WriteOptions::default();
+        write_options.set_sync(durability == WriteDurability::Sync);
+        self.write_batch(entries, &write_options)
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        if self.read_only {
+            // Nothing to flush, the database can't be modified.
+            return Ok(())
+        }
+        self.db
+            .flush_wal(true)
+            .map_err(|e| anyhow::anyhow!("Unable to flush WAL file: {}", e))?;
+        // The `DB::flush` flushes only the default column family.
+        for column in enum_iterator::all::<Description::Column>() {
+            self.db
+                .flush_cf(&self.cf(column))
+                .map_err(|e| anyhow::anyhow!("Unable to flush SST files: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        let property = |name: &std::ffi::CStr| {
+            self.property_int_value(column, name)
+                .map(Option::unwrap_or_default)
+        };
+
+        let keys = property(rocksdb::properties::ESTIMATE_NUM_KEYS)?;
+        let size = property(rocksdb::properties::TOTAL_SST_FILES_SIZE)?
+            .saturating_add(property(rocksdb::properties::CUR_SIZE_ALL_MEM_TABLES)?);
+        Ok(ColumnStats { keys, size })
+    }
+
+    /// The size of the range is approximated by the metadata of SST files, so values
+    /// that are not flushed from memtables yet are not counted. The binding of the RocksDB
+    /// doesn't expose `GetApproximateSizes`, so SST files inside the range count fully,
+    /// and the part of the file overlapping the range is interpolated by positions
+    /// of keys, assuming that keys are spread evenly. It works well for hashed keys.
+    fn size_of_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> DatabaseResult<u64> {
+        let name = Self::col_name(column);
+        let files = self.db.live_files().map_err(rocksdb_error)?;
+        Ok(files
+            .iter()
+            .filter(|file| file.column_family_name == name)
+            .filter_map(|file| {
+                overlapping_size(
+                    file.size as u64,
+                    file.start_key.as_deref()?,
+                    file.end_key.as_deref()?,
+                    start,
+                    end,
+                )
+            })
+            .fold(0u64, u64::saturating_add))
+    }
+
+    fn engine_stats(&self) -> DatabaseResult<EngineStats> {
+        let property = |name: &std::ffi::CStr| {
+            self.db
+                .property_int_value(name)
+                .map(Option::unwrap_or_default)
+                .map_err(rocksdb_error)
+        };
+        let mut memtable_usage = 0u64;
+        let columns = enum_iterator::all::<Description::Column>()
+            .map(|column| {
+                let cf = self.cf(column);
+                let property = |name: &std::ffi::CStr| {
+                    self.db
+                        .property_int_value_cf(&cf, name)
+                        .map(Option::unwrap_or_default)
+                        .map_err(rocksdb_error)
+                };
+                memtable_usage = memtable_usage.saturating_add(property(
+                    rocksdb::properties::CUR_SIZE_ALL_MEM_TABLES,
+                )?);
+                Ok(ColumnEngineStats {
+                    column: column.name(),
+                    pending_compaction_bytes: property(
+                        rocksdb::properties::ESTIMATE_PENDING_COMPACTION_BYTES,
+                    )?,
+                    immutable_memtables: property(
+                        rocksdb::properties::NUM_IMMUTABLE_MEM_TABLE,
+                    )?,
+                    level0_files: property(
+                        rocksdb::properties::num_files_at_level(0).as_c_str(),
+                    )?,
+                })
+            })
+            .collect::<DatabaseResult<Vec<_>>>()?;
+        // Tickers are only available in the text dump of the statistics.
+        let statistics = self
+            .statistics
+            .0
+            .as_ref()
+            .and_then(|opts| opts.get_statistics())
+            .unwrap_or_default();
+
+        Ok(EngineStats {
+            block_cache_hits: ticker_count(&statistics, "rocksdb.block.cache.hit"),
+            block_cache_misses: ticker_count(&statistics, "rocksdb.block.cache.miss"),
+            stall_micros: ticker_count(&statistics, "rocksdb.stall.micros"),
+            // The property reports the cache of the default column family,
+            // while columns use the shared block cache.
+            block_cache_usage: match &self.block_cache.0 {
+                Some(cache) => u64::try_from(cache.get_usage()).unwrap_or(u64::MAX),
+                None => property(rocksdb::properties::BLOCK_CACHE_USAGE)?,
+            },
+            memtable_usage,
+            memory_budget: self
+                .columns_config
+                .limits()
+                .memory_budget_bytes
+                .map_or(0, |budget| u64::try_from(budget).unwrap_or(u64::MAX)),
+            running_compactions: property(rocksdb::properties::NUM_RUNNING_COMPACTIONS)?,
+            writes_stopped: property(rocksdb::properties::IS_WRITE_STOPPED)? != 0,
+            columns,
+        })
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        // The pressure is advisory, so the failed read of the property means no pressure.
+        let property = |name: &std::ffi::CStr| {
+            self.db
+                .property_int_value(name)
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        };
+        let stopped = property(rocksdb::properties::IS_WRITE_STOPPED) != 0;
+        let delayed = property(rocksdb::properties::ACTUAL_DELAYED_WRITE_RATE) != 0;
+        if !stopped && !delayed {
+            return WritePressure::Ok
+        }
+        let reason = self.engine_stats().map_or(StallReason::Memtables, |stats| {
+            stall_reason(&stats, self.columns_config.limits(), stopped)
+        });
+        if stopped {
+            WritePressure::Stalled(reason)
+        } else {
+            WritePressure::Slowed(reason)
+        }
+    }
+
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        // RocksDB hard-links the SST files and copies the WAL up to the same sequence number,
+        // so the checkpoint is consistent even with concurrent writes.
+        // The checkpoint uses the same layout as the database, so `Self::open` can open it.
+        std::fs::create_dir_all(path)?;
+        Checkpoint::new(&self.db)
+            .and_then(|checkpoint| {
+                checkpoint.create_checkpoint(path.join(Description::name()))
+            })
+            .map_err(rocksdb_error)
+    }
+
+    fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()> {
+        let mut engine = Self::backup_engine(backup_dir)?;
+        // Flush memtables to include the latest writes without replaying the WAL on restore.
+        engine
+            .create_new_backup_flush(&self.db, !self.read_only)
+            .map_err(rocksdb_error)
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        if self.read_only {
+            // Nothing to compact, the database can't be modified.
+            return Ok(())
+        }
+        let columns = match column {
+            Some(column) => vec![column],
+            None => enum_iterator::all::<Description::Column>().collect(),
+        };
+        let (start, end) = match range {
+            Some((start, end)) => (Some(start), Some(end)),
+            None => (None, None),
+        };
+        // The RocksDB synchronizes the manual compaction with writes and
+        // background compactions, so the lock isn't required.
+        for column in columns {
+            self.db
+                .compact_range_cf(&self.cf(column), start.as_ref(), end.as_ref());
+        }
+        Ok(())
+    }
+
+    /// Drops the column family of the `column` and creates it again, which doesn't
+    /// touch entries one by one. Iterators created before the clear pin the dropped
+    /// column family, so they finish over the old entries. Snapshots taken before
+    /// the clear read the new column family, so they see the column empty.
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        // Waits for writes in progress and blocks new ones until the column is recreated.
+        let _guard = self.exclusive_write_guard()?;
+        let name = Self::col_name(column);
+        let opts =
+            Self::cf_opts(column, self.block_cache.0.as_ref(), &self.columns_config);
+
+        let _column_families = self.column_families.write().expect("poisoned");
+        self.db.drop_cf(&name).map_err(rocksdb_error)?;
+        // If the creation fails, the next opening of the database creates the column.
+        self.db.create_cf(&name, &opts).map_err(rocksdb_error)
+    }
+
+    fn delete_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: &[u8],
+    ) -> DatabaseResult<()> {
+        if start >= end {
+            return Ok(())
+        }
+        let _guard = self.write_guard()?;
+        // Keys of the range may be in any stripe.
+        let _keys = self.key_locks.shared_all();
+        // One range tombstone covers all keys, and compactions drop them later.
+        let mut batch = WriteBatch::default();
+        batch.delete_range_cf(&self.cf(column), start, end);
+        database_metrics().write_meter.inc();
+        self.db.write(batch).map_err(rocksdb_error)
+    }
+
+    /// Writes entries into SST files of the [`ResourceLimits::target_file_size`] in the
+    /// directory of the database, and moves files into the column family at once.
+    /// The ingest flushes the memtable if it overlaps files, so entries are visible
+    /// when the call returns, and the data is as durable as after the flush.
+    fn ingest_sorted(
+        &self,
+        column: Self::Column,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<IngestStats> {
+        // Fails before writing files, the ingest itself checks it again under the lock.
+        self.ensure_writable().map_err(DatabaseError::from)?;
+        let dir = self
+            .db
+            .path()
+            .join(format!("ingest-{}", rand::thread_rng().next_u64()));
+        let result =
+            self.write_sst_files(column, entries, &dir)
+                .and_then(|(mut stats, files)| {
+                    if files.is_empty() {
+                        return Ok(stats)
+                    }
+                    stats.files = files.len() as u64;
+                    let mut opts = IngestExternalFileOptions::default();
+                    opts.set_move_files(true);
+                    // The column family can't be dropped by the clear during the ingest.
+                    let _guard = self.write_guard()?;
+                    let _keys = self.key_locks.shared_all();
+                    self.db
+                        .ingest_external_file_cf_opts(&self.cf(column), &opts, files)
+                        .map_err(rocksdb_error)?;
+                    database_metrics().write_meter.inc();
+                    database_metrics().bytes_written.observe(stats.bytes as f64);
+                    Ok(stats)
+                });
+        // Moved files are already gone, the rest is left by the failure.
+        let _ = std::fs::remove_dir_all(&dir);
+        result
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        // The snapshot of the snapshot shares the same point in time.
+        let snapshot = match &self.snapshot {
+            Some(snapshot) => snapshot.clone(),
+            None => Arc::new(PinnedSnapshot::new(self.db.clone())),
+        };
+        Ok(Arc::new(RocksDb::<Description> {
+            snapshot: Some(snapshot),
+            db: self.db.clone(),
+            read_only: true,
+            lock: RwLock::new(()),
+            key_locks: Default::default(),
+            columns_config: self.columns_config.clone(),
+            block_cache: self.block_cache.clone(),
+            statistics: self.statistics.clone(),
+            column_families: self.column_families.clone(),
+            bulk_import: AtomicBool::new(false),
+            shutting_down: AtomicBool::new(false),
+            unknown_column_families: self.unknown_column_families.clone(),
+            _marker: Default::default(),
+        }))
+    }
+
+    fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::Release);
+        // Waits for writes that started before the shutdown.
+        drop(self.lock.write().expect("poisoned"));
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        if self.read_only {
+            // The snapshot shares the database with the writable instance,
+            // so only the writable instance stops the background work.
+            return Ok(())
+        }
+        let _guard = self.lock.write().expect("poisoned");
+        self.flush()?;
+        // Waits for running flushes and compactions and cancels scheduled ones.
+        self.db.cancel_all_background_work(true);
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// The expected size of one entry, used to size the read-ahead of the limited iteration.
+const READ_AHEAD_ENTRY_SIZE: usize = 256;
+
+/// The maximum read-ahead of the limited iteration.
+const MAX_PAGE_READ_AHEAD: usize = 256 * 1024;
+
+/// The size of the memtable of each column during the bulk import.
+const BULK_IMPORT_WRITE_BUFFER_SIZE: usize = 256 * 1024 * 1024;
+
+/// The guard of the bulk import mode of the [`RocksDb`] returned by [`RocksDb::bulk_import`].
+/// The mode ends when the guard is finalized or dropped, and in both cases,
+/// the imported data is flushed to the disk.
+#[derive(Debug)]
+pub struct BulkImport<'a, Description>
+where
+    Description: DatabaseDescription,
+{
+    db: &'a RocksDb<Description>,
+    finalized: bool,
+}
+
+impl<Description> BulkImport<'_, Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Writes the batch of the entries without the WAL.
+    pub fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Description::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        let _guard = self.db.lock.read().expect("poisoned");
+        let mut write_options = WriteOptions::default();
+        write_options.disable_wal(true);
+        self.db.write_batch(entries, &write_options)
+    }
+
+    /// Flushes the imported data into SST files and leaves the bulk import mode.
+    /// The data is durable when the call returns `Ok`.
+    pub fn finalize_bulk_import(mut self) -> DatabaseResult<()> {
+        self.finalized = true;
+        self.finish()
+    }
+
+    fn set_write_buffer_sizes(
+        &self,
+        size: impl Fn(Description::Column) -> String,
+    ) -> DatabaseResult<()> {
+        for column in enum_iterator::all::<Description::Column>() {
+            self.db
+                .db
+                .set_options_cf(
+                    &self.db.cf(column),
+                    &[("write_buffer_size", size(column).as_str())],
+                )
+                .map_err(rocksdb_error)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&self) -> DatabaseResult<()> {
+        // The mode is left even if the flush fails, the next flush
+        // of the database makes the data durable.
+        let result = self.flush_and_restore();
+        self.db.bulk_import.store(false, Ordering::Release);
+        result
+    }
+
+    fn flush_and_restore(&self) -> DatabaseResult<()> {
+        // The data was written without the WAL, so only memtables contain it.
+        for column in enum_iterator::all::<Description::Column>() {
+            self.db
+                .db
+                .flush_cf(&self.db.cf(column))
+                .map_err(rocksdb_error)?;
+        }
+        self.set_write_buffer_sizes(|column| {
+            RocksDb::<Description>::write_buffer_size(column, &self.db.columns_config)
+                .to_string()
+        })
+    }
+}
+
+impl<Description> Drop for BulkImport<'_, Description>
+where
+    Description: DatabaseDescription,
+{
+    fn drop(&mut self) {
+        if !self.finalized {
+            if let Err(err) = self.finish() {
+                tracing::error!("Unable to flush the data of the bulk import: {}", err);
+            }
+        }
+    }
+}
+
+struct KeyOnly;
+
+impl ExtractItem for KeyOnly {
+    type Item = Vec<u8>;
+
+    fn extract_item<D>(
+        raw_iterator: &DBRawIteratorWithThreadMode<D>,
+    ) -> Option<Self::Item>
+    where
+        D: DBAccess,
+    {
+        raw_iterator.key().map(|key| key.to_vec())
+    }
+
+    fn size(item: &Self::Item) -> u64 {
+        item.len() as u64
+    }
+
+    fn starts_with(item: &Self::Item, prefix: &[u8]) -> bool {
+        item.starts_with(prefix)
+    }
+}
+
+struct KeyAndValue;
+
+impl ExtractItem for KeyAndValue {
+    type Item = (Vec<u8>, Value);
+
+    fn extract_item<D>(
+        raw_iterator: &DBRawIteratorWithThreadMode<D>,
+    ) -> Option<Self::Item>
+    where
+        D: DBAccess,
+    {
+        raw_iterator
+            .item()
+            .map(|(key, value)| (key.to_vec(), Arc::new(value.to_vec())))
+    }
+
+    fn size(item: &Self::Item) -> u64 {
+        item.0.len().saturating_add(item.1.len()) as u64
+    }
+
+    fn starts_with(item: &Self::Item, prefix: &[u8]) -> bool {
+        item.0.starts_with(prefix)
+    }
+}
+
+/// Maps the error of the RocksDB to the variant of the [`DatabaseError`] by its kind,
+/// so callers can retry I/O failures, but not failures of the data.
+pub(crate) fn rocksdb_error(error: rocksdb::Error) -> DatabaseError {
+    match error.kind() {
+        ErrorKind::IOError
+        | ErrorKind::Busy
+        | ErrorKind::TimedOut
+        | ErrorKind::TryAgain
+        | ErrorKind::Incomplete => DatabaseError::Io(error.into()),
+        _ => DatabaseError::Backend(error.into_string()),
+    }
+}
+
+/// The same as [`rocksdb_error`], but reports the corruption of the entry at the `key`
+/// of the `column`.
+fn rocksdb_entry_error<Column: StorageColumn>(
+    column: Column,
+    key: &[u8],
+) -> impl FnOnce(rocksdb::Error) -> DatabaseError + '_ {
+    move |error| match error.kind() {
+        ErrorKind::Corruption => {
+            tracing::error!(
+                "The entry of the column `{}` at the key {key:?} is corrupted: {error}",
+                column.name()
+            );
+            DatabaseError::Corrupted {
+                column: column.name(),
+                key: key.to_vec(),
+            }
+        }
+        _ => rocksdb_error(error),
+    }
+}
+
+/// Returns the part of the `size` of the SST file with keys from the `first` to the `last`
+/// inclusive that overlaps the range of keys from the `start` inclusive to the `end`
+/// exclusive, or `None` if they don't overlap. Keys are assumed to be spread evenly
+/// between the `first` and the `last` key.
+fn overlapping_size(
+    size: u64,
+    first: &[u8],
+    last: &[u8],
+    start: &[u8],
+    end: Option<&[u8]>,
+) -> Option<u64> {
+    if last < start || end.is_some_and(|end| end <= first) {
+        return None
+    }
+    // Keys between the `first` and the `last` share their common prefix,
+    // so positions are compared by bytes after it.
+    let common = first
+        .iter()
+        .zip(last)
+        .take_while(|(first, last)| first == last)
+        .count();
+    let position = |key: &[u8]| key_position(key, common);
+    let from = position(cmp::max(first, start));
+    let to = match end {
+        Some(end) if end <= last => position(end),
+        _ => position(last),
+    };
+    let span = position(last).saturating_sub(position(first));
+    if span == 0 {
+        return Some(size)
+    }
+    let part = u128::from(size)
+        .saturating_mul(u128::from(to.saturating_sub(from)))
+        .checked_div(u128::from(span))
+        .unwrap_or_default();
+    Some(u64::try_from(part).unwrap_or(u64::MAX))
+}
+
+/// Returns 8 bytes of the `key` after the `common` prefix as the big-endian number.
+/// Shorter keys are padded with zeros.
+fn key_position(key: &[u8], common: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    for (byte, key_byte) in bytes.iter_mut().zip(key.iter().skip(common)) {
+        *byte = *key_byte;
+    }
+    u64::from_be_bytes(bytes)
+}
+
+/// Returns the count of the `ticker` from the text dump of the RocksDB statistics,
+/// where tickers are lines like `rocksdb.block.cache.hit COUNT : 42`.
+fn ticker_count(statistics: &str, ticker: &str) -> u64 {
+    statistics
+        .lines()
+        .find_map(|line| {
+            let (name, count) = line.split_once(" COUNT : ")?;
+            if name != ticker {
+                return None
+            }
+            count.trim().parse().ok()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the reason why the RocksDB slows writes, or stops them if `stopped`, by the
+/// first column over the trigger of the [`ResourceLimits`]. The RocksDB also delays writes
+/// while memtables wait for the flush, so the pressure without the exceeded trigger
+/// is attributed to memtables.
+fn stall_reason(
+    stats: &EngineStats,
+    limits: &ResourceLimits,
+    stopped: bool,
+) -> StallReason {
+    let (level0_files, pending_bytes) = if stopped {
+        (
+            limits.level0_stop_writes_trigger,
+            limits.hard_pending_compaction_bytes,
+        )
+    } else {
+        (
+            limits.level0_slowdown_writes_trigger,
+            limits.soft_pending_compaction_bytes,
+        )
+    };
+    if stats
+        .columns
+        .iter()
+        .any(|column| column.level0_files >= u64::from(level0_files))
+    {
+        StallReason::Level0Files
+    } else if stats
+        .columns
+        .iter()
+        .any(|column| column.pending_compaction_bytes >= pending_bytes)
+    {
+        StallReason::PendingCompactionBytes
+    } else {
+        StallReason::Memtables
+    }
+}
+
+/// Returns the soft limit of file descriptors of the process, `None` if it is unlimited.
+#[cfg(unix)]
+fn open_files_limit() -> Option<u64> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: `getrlimit` only writes into the `limit`.
+    let result = unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) };
+    if result != 0 || limit.rlim_cur == libc::RLIM_INFINITY {
+        return None
+    }
+    Some(limit.rlim_cur)
+}
+
+#[cfg(not(unix))]
+fn open_files_limit() -> Option<u64> {
+    None
+}
+
+/// Returns the number of SST files in the `dir` or zero if the `dir` doesn't exist.
+fn count_sst_files(dir: &Path) -> DatabaseResult<usize> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let mut count = 0usize;
+    for entry in entries {
+        let entry = entry?;
+        if entry
+            .path()
+            .extension()
+            .is_some_and(|extension| extension == "sst")
+        {
+            count = count.saturating_add(1);
+        }
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::on_chain::OnChain,
+        state::{
+            open_mode::OpenMode,
+            DataSource,
+        },
+    };
+    use fuel_core_storage::column::Column;
+    use itertools::Itertools;
+    use std::sync::atomic::AtomicUsize;
+    use tempfile::TempDir;
+
+    fn create_db() -> (RocksDb<OnChain>, TempDir) {
+        let tmp_dir = TempDir::new().unwrap();
+        (
+            RocksDb::default_open(tmp_dir.path(), None).unwrap(),
+            tmp_dir,
+        )
+    }
+
+    #[test]
+    fn can_put_and_read() {
+        let key = vec![0xA, 0xB, 0xC];
+
+        let (db, _tmp) = create_db();
+        let expected = Arc::new(vec![1, 2, 3]);
+        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+
+        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), expected)
+    }
+
+    #[test]
+    fn get_with_borrows_value() {
+        let key = vec![0xA, 0xB, 0xC];
+
+        let (db, _tmp) = create_db();
+        let expected = vec![1, 2, 3];
+        db.put(&key, Column::Metadata, Arc::new(expected.clone()))
+            .unwrap();
+
+        let mut value = None;
+        let found = db
+            .get_with(&key, Column::Metadata, &mut |bytes| {
+                value = Some(bytes.to_vec())
+            })
+            .unwrap();
+        assert!(found);
+        assert_eq!(value, Some(expected));
+
+        let found = db
+            .get_with(&[0xD], Column::Metadata, &mut |_| {
+                panic!("The value doesn't exist")
+            })
+            .unwrap();
+        assert!(!found);
+    }
+
+    #[test]
+    fn multi_get_returns_values_in_the_order_of_keys() {
+        let (db, _tmp) = create_db();
+        db.put(&[1], Column::Metadata, Arc::new(vec![1])).unwrap();
+        db.put(&[3], Column::Metadata, Arc::new(vec![3])).unwrap();
+
+        let values = db
+            .multi_get(&[vec![3], vec![2], vec![1]], Column::Metadata)
+            .unwrap();
+
+        assert_eq!(
+            values,
+            vec![Some(Arc::new(vec![3])), None, Some(Arc::new(vec![1]))]
+        );
+    }
+
+    #[test]
+    fn put_returns_previous_value() {
+        let key = vec![0xA, 0xB, 0xC];
+
+        let (db, _tmp) = create_db();
+        let expected = Arc::new(vec![1, 2, 3]);
+        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+        let prev = db
+            .replace(&key, Column::Metadata, Arc::new(vec![2, 4, 6]))
+            .unwrap();
+
+        assert_eq!(prev, Some(expected));
+    }
+
+    #[test]
+    fn delete_and_get() {
+        let key = vec![0xA, 0xB, 0xC];
+
+        let (db, _tmp) = create_db();
+        let expected = Arc::new(vec![1, 2, 3]);
+        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), expected);
+
+        db.delete(&key, Column::Metadata).unwrap();
+        assert_eq!(db.get(&key, Column::Metadata).unwrap(), None);
+    }
+
+    #[test]
+    fn key_exists() {
+        let key = vec![0xA, 0xB, 0xC];
+
+        let (db, _tmp) = create_db();
+        let expected = Arc::new(vec![1, 2, 3]);
+        db.put(&key, Column::Metadata, expected).unwrap();
+        assert!(db.exists(&key, Column::Metadata).unwrap());
+    }
+
+    #[test]
+    fn batch_write_inserts() {
+        let key = vec![0xA, 0xB, 0xC];
+        let value = Arc::new(vec![1, 2, 3]);
+
+        let (db, _tmp) = create_db();
+        let ops = vec![(
+            key.clone(),
+            Column::Metadata,
+            WriteOperation::Insert(value.clone()),
+        )];
+
+        db.batch_write(&mut ops.into_iter()).unwrap();
+        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), value)
+    }
+
+    #[test]
+    fn batch_write_with_stats_counts_removals_of_missing_keys() {
+        // Given
+        let (db, _tmp) = create_db();
+        db.put(&[1], Column::Metadata, Arc::new(vec![0])).unwrap();
+        let entries = [
+            (
+                vec![2],
+                Column::Metadata,
+                WriteOperation::Insert(Arc::new(vec![2; 4])),
+            ),
+            (vec![1], Column::Metadata, WriteOperation::Remove),
+            (vec![3], Column::Metadata, WriteOperation::Remove),
+        ];
+
+        // When
+        let stats = db.batch_write_with_stats(&mut entries.into_iter()).unwrap();
+
+        // Then
+        assert_eq!(
+            stats,
+            fuel_core_storage::kv_store::BatchWriteResult {
+                inserted: 1,
+                removed: 2,
+                bytes_written: 7,
+            }
+        );
+        assert_eq!(db.get(&[1], Column::Metadata).unwrap(), None);
+    }
+
+    #[test]
+    fn batch_write_applies_operations_in_order() {
+        // Given
+        let (db, _tmp) = create_db();
+        db.put(&[2], Column::Metadata, Arc::new(vec![0])).unwrap();
+        let insert = |key: u8, value: u8| {
+            (
+                vec![key],
+                Column::Metadata,
+                WriteOperation::Insert(Arc::new(vec![value])),
+            )
+        };
+        let remove = |key: u8| (vec![key], Column::Metadata, WriteOperation::Remove);
+
+        // When
+        db.batch_write(
+            &mut [
+                insert(1, 1),
+                remove(1),
+                remove(2),
+                insert(2, 2),
+                insert(3, 1),
+                insert(3, 3),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+
+        // Then
+        assert_eq!(db.get(&[1], Column::Metadata).unwrap(), None);
+        assert_eq!(
+            db.get(&[2], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![2]))
+        );
+        assert_eq!(
+            db.get(&[3], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![3]))
+        );
+    }
+
+    #[test]
+    fn batch_write_removes() {
+        let key = vec![0xA, 0xB, 0xC];
+        let value = Arc::new(vec![1, 2, 3]);
+
+        let (db, _tmp) = create_db();
+        db.put(&key, Column::Metadata, value).unwrap();
+
+        let ops = vec![(key.clone(), Column::Metadata, WriteOperation::Remove)];
+        db.batch_write(&mut ops.into_iter()).unwrap();
+
+        assert_eq!(db.get(&key, Column::Metadata).unwrap(), None);
+    }
+
+    #[test]
+    fn can_use_unit_value() {
+        let key = vec![0x00];
+
+        let (db, _tmp) = create_db();
+        let expected = Arc::new(vec![]);
+        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+
+        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), expected);
+
+        assert!(db.exists(&key, Column::Metadata).unwrap());
+
+        assert_eq!(
+            db.iter_all(Column::Metadata, None, None, IterDirection::Forward)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()[0],
+            (key.clone(), expected.clone())
+        );
+
+        assert_eq!(db.take(&key, Column::Metadata).unwrap().unwrap(), expected);
+
+        assert!(!db.exists(&key, Column::Metadata).unwrap());
+    }
+
+    #[test]
+    fn can_use_unit_key() {
+        let key: Vec<u8> = Vec::with_capacity(0);
+
+        let (db, _tmp) = create_db();
+        let expected = Arc::new(vec![1, 2, 3]);
+        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+
+        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), expected);
+
+        assert!(db.exists(&key, Column::Metadata).unwrap());
+
+        assert_eq!(
+            db.iter_all(Column::Metadata, None, None, IterDirection::Forward)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()[0],
+            (key.clone(), expected.clone())
+        );
+
+        assert_eq!(db.take(&key, Column::Metadata).unwrap().unwrap(), expected);
+
+        assert!(!db.exists(&key, Column::Metadata).unwrap());
+    }
+
+    #[test]
+    fn can_use_unit_key_and_value() {
+        let key: Vec<u8> = Vec::with_capacity(0);
+
+        let (db, _tmp) = create_db();
+        let expected = Arc::new(vec![]);
+        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+
+        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), expected);
+
+        assert!(db.exists(&key, Column::Metadata).unwrap());
+
+        assert_eq!(
+            db.iter_all(Column::Metadata, None, None, IterDirection::Forward)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()[0],
+            (key.clone(), expected.clone())
+        );
+
+        assert_eq!(db.take(&key, Column::Metadata).unwrap().unwrap(), expected);
+
+        assert!(!db.exists(&key, Column::Metadata).unwrap());
+    }
+
+    #[test]
+    fn iter_range_respects_end_and_prefix() {
+        let (db, _tmp) = create_db();
+        for key in [[0, 1], [1, 0], [1, 2], [1, 4], [2, 0]] {
+            db.put(&key, Column::Metadata, Arc::new(vec![1])).unwrap();
+        }
+        let keys = |prefix: Option<&[u8]>,
+                    start: Option<&[u8]>,
+                    end: Option<&[u8]>,
+                    direction| {
+            db.iter_range(Column::Metadata, prefix, start, end, direction)
+                .map(|item| item.map(|(key, _)| key))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        };
+
+        assert_eq!(
+            keys(None, Some(&[1, 0]), Some(&[2, 0]), IterDirection::Forward),
+            vec![vec![1, 0], vec![1, 2], vec![1, 4]]
+        );
+        assert_eq!(
+            keys(None, Some(&[2, 0]), Some(&[1, 0]), IterDirection::Reverse),
+            vec![vec![2, 0], vec![1, 4], vec![1, 2]]
+        );
+        assert!(
+            keys(None, Some(&[1, 0]), Some(&[2, 0]), IterDirection::Reverse).is_empty()
+        );
+        assert_eq!(
+            keys(Some(&[1]), None, Some(&[3]), IterDirection::Forward),
+            vec![vec![1, 0], vec![1, 2], vec![1, 4]]
+        );
+        assert_eq!(
+            keys(Some(&[1]), None, Some(&[1, 0]), IterDirection::Reverse),
+            vec![vec![1, 4], vec![1, 2]]
+        );
+        assert_eq!(
+            keys(
+                Some(&[1]),
+                Some(&[1, 4]),
+                Some(&[0]),
+                IterDirection::Reverse
+            ),
+            vec![vec![1, 4], vec![1, 2], vec![1, 0]]
+        );
+    }
+
+    #[test]
+    fn iter_keys_returns_only_keys() {
+        let (db, _tmp) = create_db();
+        for key in [[0, 1], [1, 0], [1, 2], [2, 0]] {
+            db.put(&key, Column::Metadata, Arc::new(vec![0xA; 1024]))
+                .unwrap();
+        }
+
+        let keys = db
+            .iter_keys(Column::Metadata, Some(&[1]), None, IterDirection::Reverse)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let all_keys = db
+            .iter_keys(Column::Metadata, None, None, IterDirection::Forward)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(keys, vec![vec![1, 2], vec![1, 0]]);
+        assert_eq!(
+            all_keys,
+            vec![vec![0, 1], vec![1, 0], vec![1, 2], vec![2, 0]]
+        );
+    }
+
+    #[test]
+    fn read_only_db_reads_committed_data() {
+        let (db, tmp) = create_db();
+        let key = vec![0xA, 0xB, 0xC];
+        let value = Arc::new(vec![1, 2, 3]);
+        db.put(&key, Column::Metadata, value.clone()).unwrap();
+
+        let read_only = RocksDb::<OnChain>::open_read_only(
+            tmp.path(),
+            enum_iterator::all::<Column>().collect(),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_only.get(&key, Column::Metadata).unwrap(),
+            Some(value.clone())
+        );
+        assert!(read_only.exists(&key, Column::Metadata).unwrap());
+        assert_eq!(
+            read_only
+                .iter_all(Column::Metadata, None, None, IterDirection::Forward)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![(key.clone(), value)]
+        );
+    }
+
+    #[test]
+    fn read_only_db_rejects_writes() {
+        let (db, tmp) = create_db();
+        let key = vec![0xA, 0xB, 0xC];
+        db.put(&key, Column::Metadata, Arc::new(vec![1])).unwrap();
+
+        let read_only = RocksDb::<OnChain>::open_read_only(
+            tmp.path(),
+            enum_iterator::all::<Column>().collect(),
+            None,
+        )
+        .unwrap();
+
+        let is_read_only = |result: StorageResult<()>| {
+            matches!(
+                result.map_err(DatabaseError::from),
+                Err(DatabaseError::ReadOnly)
+            )
+        };
+        assert!(is_read_only(read_only.put(
+            &key,
+            Column::Metadata,
+            Arc::new(vec![2])
+        )));
+        assert!(is_read_only(read_only.delete(&key, Column::Metadata)));
+        assert!(is_read_only(read_only.batch_write(
+            &mut [(key.clone(), Column::Metadata, WriteOperation::Remove)].into_iter()
+        )));
+        assert_eq!(
+            db.get(&key, Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+    }
+
+    #[test]
+    fn column_stats_counts_keys_and_size() {
+        let (db, _tmp) = create_db();
+        for key in [[0], [1], [2]] {
+            db.put(&key, Column::Metadata, Arc::new(vec![0xA; 1024]))
+                .unwrap();
+        }
+        db.flush().unwrap();
+
+        let stats = db.column_stats(Column::Metadata).unwrap();
+        let empty = db.column_stats(Column::Coins).unwrap();
+
+        assert_eq!(stats.keys, 3);
+        assert!(stats.size > 0);
+        assert_eq!(empty.keys, 0);
+    }
+
+    #[test]
+    fn delete_all_removes_only_prefixed_keys() {
+        let (db, _tmp) = create_db();
+        for key in [[0, 1], [1, 0], [1, 2], [2, 0], [0xFF, 0]] {
+            db.put(&key, Column::Metadata, Arc::new(vec![1])).unwrap();
+        }
+
+        assert_eq!(db.delete_all(Column::Metadata, Some(&[1])).unwrap(), 2);
+        assert_eq!(db.delete_all(Column::Metadata, Some(&[1])).unwrap(), 0);
+        assert_eq!(db.delete_all(Column::Metadata, Some(&[0xFF])).unwrap(), 1);
+        assert!(db.exists(&[0, 1], Column::Metadata).unwrap());
+        assert!(!db.exists(&[1, 0], Column::Metadata).unwrap());
+        assert_eq!(db.delete_all(Column::Metadata, Some(&[])).unwrap(), 2);
+        assert_eq!(
+            db.iter_all(Column::Metadata, None, None, IterDirection::Forward)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn prefix_iteration_stays_within_the_prefix() {
+        let (db, _tmp) = create_db();
+        let keys = [
+            vec![1],
+            vec![1, 0xFF],
+            vec![1, 0xFF, 0],
+            vec![2, 0],
+            vec![2, 1],
+        ];
+        for key in keys.iter() {
+            db.put(key, Column::Metadata, Arc::new(vec![1])).unwrap();
+        }
+        let iter = |prefix: &[u8], direction| {
+            db.iter_all(Column::Metadata, Some(prefix), None, direction)
+                .map(|item| item.unwrap().0)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            iter(&[1, 0xFF], IterDirection::Forward),
+            vec![vec![1, 0xFF], vec![1, 0xFF, 0]]
+        );
+        assert_eq!(
+            iter(&[1, 0xFF], IterDirection::Reverse),
+            vec![vec![1, 0xFF, 0], vec![1, 0xFF]]
+        );
+        assert_eq!(
+            iter(&[1], IterDirection::Reverse),
+            vec![vec![1, 0xFF, 0], vec![1, 0xFF], vec![1]]
+        );
+        assert_eq!(
+            db.delete_all(Column::Metadata, Some(&[1, 0xFF])).unwrap(),
+            2
+        );
+        assert_eq!(
+            iter(&[2], IterDirection::Forward),
+            vec![vec![2, 0], vec![2, 1]]
+        );
+    }
+
+    #[test]
+    fn prefix_seek_matches_the_total_order_iteration() {
+        // Given
+        let (db, _tmp) = create_db();
+        let column = Column::ContractsState;
+        let owner = |i: u8| vec![i; 32];
+        let key = |owner: u8, slot: u8| [vec![owner; 32], vec![slot; 32]].concat();
+        let mut keys = vec![];
+        for owner in [1, 3, 5, 0xFF] {
+            for slot in [0, 2, 4, 0xFF] {
+                keys.push(key(owner, slot));
+            }
+        }
+        // Half of the keys are in SST files and the other half in the memtable.
+        for key in keys.iter().step_by(2) {
+            db.put(key, column, Arc::new(vec![1])).unwrap();
+        }
+        db.db.flush_cf(&db.cf(column)).unwrap();
+        for key in keys.iter().skip(1).step_by(2) {
+            db.put(key, column, Arc::new(vec![1])).unwrap();
+        }
+        keys.sort();
+
+        let prefixes = [
+            None,
+            Some(vec![]),
+            Some(vec![3; 4]),
+            Some(owner(0)),
+            Some(owner(3)),
+            Some(owner(4)),
+            Some(owner(0xFF)),
+            Some(key(3, 2)[..40].to_vec()),
+        ];
+        let starts = [
+            None,
+            Some(vec![3; 4]),
+            Some(vec![3; 10]),
+            Some(owner(3)),
+            Some(key(3, 0)),
+            Some(key(3, 3)),
+            Some(key(3, 2)[..40].to_vec()),
+            Some(key(4, 0)),
+            Some(key(0xFF, 0xFF)),
+        ];
+
+        for prefix in prefixes.iter() {
+            for start in starts.iter() {
+                for direction in [IterDirection::Forward, IterDirection::Reverse] {
+                    // When
+                    let actual = db
+                        .iter_all(column, prefix.as_deref(), start.as_deref(), direction)
+                        .map(|item| item.unwrap().0)
+                        .collect::<Vec<_>>();
+
+                    // Then
+                    let prefix = prefix.as_deref().unwrap_or_default();
+                    let mut expected = keys
+                        .iter()
+                        .filter(|key| key.starts_with(prefix))
+                        .filter(|key| match (start, direction) {
+                            (None, _) => true,
+                            (Some(start), _) if !start.starts_with(prefix) => false,
+                            (Some(start), IterDirection::Forward) => key >= &start,
+                            (Some(start), IterDirection::Reverse) => key <= &start,
+                        })
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    if direction == IterDirection::Reverse {
+                        expected.reverse();
+                    }
+                    assert_eq!(
+                        actual, expected,
+                        "prefix: {prefix:?}, start: {start:?}, direction: {direction:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn compact_reclaims_space_of_deleted_values() {
+        // Given
+        let (db, _tmp) = create_db();
+        let column = Column::ContractsRawCode;
+        let keys = (0..1000u32).map(|i| i.to_be_bytes()).collect::<Vec<_>>();
+        for key in keys.iter() {
+            db.put(key, column, Arc::new(vec![7; 1024])).unwrap();
+        }
+        db.db.flush_cf(&db.cf(column)).unwrap();
+        for key in keys.iter() {
+            db.delete(key, column).unwrap();
+        }
+        db.db.flush_cf(&db.cf(column)).unwrap();
+        db.put(&[1], Column::Metadata, Arc::new(vec![1])).unwrap();
+        let live_data = || {
+            db.db
+                .property_int_value_cf(
+                    &db.cf(column),
+                    rocksdb::properties::ESTIMATE_LIVE_DATA_SIZE,
+                )
+                .unwrap()
+                .unwrap_or_default()
+        };
+        let size_before = db.column_stats(column).unwrap().size;
+        let live_data_before = live_data();
+
+        // When
+        db.compact(Some(column), None).unwrap();
+
+        // Then
+        let size_after = db.column_stats(column).unwrap().size;
+        assert!(
+            size_after < size_before,
+            "{size_after} is not less than {size_before}"
+        );
+        assert!(live_data() < live_data_before);
+        assert_eq!(
+            db.iter_all(column, None, None, IterDirection::Forward)
+                .count(),
+            0
+        );
+
+        // The compaction of all columns and ranges keeps values.
+        db.compact(None, Some((vec![0], vec![2]))).unwrap();
+        db.compact(None, None).unwrap();
+        assert_eq!(
+            db.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+    }
+
+    #[test]
+    fn iter_column_lends_the_same_entries_as_iter_all() {
+        // Given
+        let (db, _tmp) = create_db();
+        for key in [
+            vec![1],
+            vec![1, 0xFF],
+            vec![1, 0xFF, 0],
+            vec![2],
+            vec![0xFF],
+        ] {
+            db.put(&key, Column::Metadata, Arc::new(key.clone()))
+                .unwrap();
+        }
+        let prefixes: [Option<&[u8]>; 4] =
+            [None, Some(&[1]), Some(&[1, 0xFF]), Some(&[3])];
+        let starts: [Option<&[u8]>; 3] = [None, Some(&[1, 0xFF]), Some(&[2])];
+
+        for direction in [IterDirection::Forward, IterDirection::Reverse] {
+            for (prefix, start) in prefixes.into_iter().cartesian_product(starts) {
+                // When
+                let mut lent = vec![];
+                let mut iter = db.iter_column(Column::Metadata, prefix, start, direction);
+                while let Some(item) = iter.next() {
+                    let (key, value) = item.unwrap();
+                    lent.push((key.to_vec(), value.to_vec()));
+                }
+
+                // Then
+                let expected = db
+                    .iter_all(Column::Metadata, prefix, start, direction)
+                    .map(|item| {
+                        let (key, value) = item.unwrap();
+                        (key, value.to_vec())
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(lent, expected, "{prefix:?} {start:?} {direction:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn iter_with_limit_on_the_prefix_boundary_yields_the_whole_prefix() {
+        // Given
+        let (db, _tmp) = create_db();
+        for key in [[1, 0], [1, 1], [1, 2], [2, 0], [2, 1]] {
+            db.put(&key, Column::Metadata, Arc::new(vec![])).unwrap();
+        }
+        let config = IterConfig {
+            prefix: Some(&[1]),
+            limit: Some(3),
+            ..Default::default()
+        };
+
+        for direction in [IterDirection::Forward, IterDirection::Reverse] {
+            // When
+            let mut iter = db.iter(
+                Column::Metadata,
+                IterConfig {
+                    direction,
+                    ..config
+                },
+            );
+            let keys = iter
+                .by_ref()
+                .map(|item| item.unwrap().0[1])
+                .collect::<Vec<_>>();
+
+            // Then
+            let (expected, last_key) = match direction {
+                IterDirection::Forward => (vec![0, 1, 2], [1, 2]),
+                IterDirection::Reverse => (vec![2, 1, 0], [1, 0]),
+            };
+            assert_eq!(keys, expected);
+            assert_eq!(iter.last_key(), Some(last_key.as_slice()));
+        }
+    }
+
+    #[test]
+    fn has_any_seeks_to_the_first_key_of_the_prefix() {
+        // Given
+        let (db, _tmp) = create_db();
+        for key in [[1, 0], [1, 1], [3, 0]] {
+            db.put(&key, Column::Metadata, Arc::new(vec![])).unwrap();
+        }
+
+        // When
+        let found = [[1].as_slice(), &[2], &[3], &[1, 1], &[1, 2], &[]]
+            .map(|prefix| db.has_any(Column::Metadata, prefix).unwrap());
+
+        // Then
+        assert_eq!(found, [true, false, true, true, false, true]);
+        assert!(!db.has_any(Column::Coins, &[]).unwrap());
+    }
+
+    #[test]
+    fn iter_with_zero_limit_yields_nothing() {
+        // Given
+        let (db, _tmp) = create_db();
+        db.put(&[1], Column::Metadata, Arc::new(vec![])).unwrap();
+        let config = IterConfig {
+            limit: Some(0),
+            ..Default::default()
+        };
+
+        // When
+        let mut iter = db.iter(Column::Metadata, config);
+
+        // Then
+        assert!(iter.next().is_none());
+        assert_eq!(iter.last_key(), None);
+    }
+
+    #[test]
+    fn prefix_bounds_handle_0xff_suffixes() {
+        // Given
+        let (db, _tmp) = create_db();
+        let keys = [
+            vec![1, 0xFE, 0xFF],
+            vec![1, 0xFF],
+            vec![1, 0xFF, 0xFF],
+            vec![1, 0xFF, 0xFF, 0],
+            vec![2],
+            vec![2, 0],
+            vec![0xFF],
+            vec![0xFF, 0xFF],
+            vec![0xFF, 0xFF, 1],
+        ];
+        for key in keys.iter() {
+            db.put(key, Column::Metadata, Arc::new(vec![1])).unwrap();
+        }
+        let iter = |prefix: &[u8], start: Option<&[u8]>, direction| {
+            db.iter_all(Column::Metadata, Some(prefix), start, direction)
+                .map(|item| item.unwrap().0)
+                .collect::<Vec<_>>()
+        };
+
+        // Then
+        assert_eq!(next_prefix(vec![1, 0xFF, 0xFF]), Some(vec![2]));
+        assert_eq!(next_prefix(vec![0xFF, 0xFF]), None);
+        assert_eq!(
+            iter(&[1, 0xFF, 0xFF], None, IterDirection::Forward),
+            vec![vec![1, 0xFF, 0xFF], vec![1, 0xFF, 0xFF, 0]]
+        );
+        assert_eq!(
+            iter(&[1, 0xFF, 0xFF], None, IterDirection::Reverse),
+            vec![vec![1, 0xFF, 0xFF, 0], vec![1, 0xFF, 0xFF]]
+        );
+        assert_eq!(
+            iter(&[1, 0xFF], Some(&[1, 0xFF, 0xFF]), IterDirection::Reverse),
+            vec![vec![1, 0xFF, 0xFF], vec![1, 0xFF]]
+        );
+        assert_eq!(
+            iter(&[0xFF], None, IterDirection::Forward),
+            vec![vec![0xFF], vec![0xFF, 0xFF], vec![0xFF, 0xFF, 1]]
+        );
+        assert_eq!(
+            iter(&[0xFF, 0xFF], None, IterDirection::Reverse),
+            vec![vec![0xFF, 0xFF, 1], vec![0xFF, 0xFF]]
+        );
+        assert_eq!(
+            iter(
+                &[0xFF, 0xFF],
+                Some(&[0xFF, 0xFF, 0]),
+                IterDirection::Forward
+            ),
+            vec![vec![0xFF, 0xFF, 1]]
+        );
+        assert_eq!(
+            iter(&[0xFF, 0xFF, 0xFF], None, IterDirection::Forward),
+            Vec::<Vec<u8>>::new()
+        );
+        // The `end` and the prefix bounds are combined.
+        let range = |prefix: &[u8], end: &[u8], direction| {
+            db.iter_range(Column::Metadata, Some(prefix), None, Some(end), direction)
+                .map(|item| item.unwrap().0)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            range(&[1, 0xFF], &[1, 0xFF, 0xFF, 0], IterDirection::Forward),
+            vec![vec![1, 0xFF], vec![1, 0xFF, 0xFF]]
+        );
+        assert_eq!(
+            range(&[1, 0xFF], &[3], IterDirection::Forward),
+            vec![vec![1, 0xFF], vec![1, 0xFF, 0xFF], vec![1, 0xFF, 0xFF, 0]]
+        );
+        assert_eq!(
+            range(&[1, 0xFF], &[1, 0xFF], IterDirection::Reverse),
+            vec![vec![1, 0xFF, 0xFF, 0], vec![1, 0xFF, 0xFF]]
+        );
+        assert_eq!(
+            range(&[1, 0xFF], &[0], IterDirection::Reverse),
+            vec![vec![1, 0xFF, 0xFF, 0], vec![1, 0xFF, 0xFF], vec![1, 0xFF]]
+        );
+    }
+
+    #[test]
+    fn multi_delete_counts_only_existing_keys_once() {
+        // Given
+        let (db, _tmp) = create_db();
+        for key in 1..=3u8 {
+            db.put(&[key], Column::Metadata, Arc::new(vec![key]))
+                .unwrap();
+        }
+
+        // When
+        let existing = db
+            .multi_delete(Column::Metadata, &[vec![1], vec![3], vec![3], vec![4]])
+            .unwrap();
+
+        // Then
+        assert_eq!(existing, 2);
+        assert_eq!(
+            db.iter_all(Column::Metadata, None, None, IterDirection::Forward)
+                .map(|item| item.unwrap().0)
+                .collect::<Vec<_>>(),
+            vec![vec![2]]
+        );
+    }
+
+    #[test]
+    fn delete_range_removes_keys_from_start_to_end_exclusive() {
+        // Given
+        let (db, _tmp) = create_db();
+        for key in 1..=5u8 {
+            db.put(&[key], Column::Metadata, Arc::new(vec![key]))
+                .unwrap();
+        }
+
+        // When
+        db.delete_range(Column::Metadata, &[2], &[4]).unwrap();
+        db.put(&[3], Column::Metadata, Arc::new(vec![30])).unwrap();
+
+        // Then
+        assert_eq!(
+            db.iter_all(Column::Metadata, None, None, IterDirection::Forward)
+                .map(|item| item.unwrap().0)
+                .collect::<Vec<_>>(),
+            vec![vec![1], vec![3], vec![4], vec![5]]
+        );
+    }
+
+    #[test]
+    fn ingest_sorted_is_visible_without_the_flush() {
+        // Given
+        let tmp = TempDir::new().unwrap();
+        let limits = ResourceLimits {
+            target_file_size: 1024 * 1024,
+            ..Default::default()
+        };
+        let db = RocksDb::<OnChain>::open_with_config(
+            tmp.path(),
+            enum_iterator::all::<Column>().collect(),
+            None,
+            &ColumnsConfig::default().with_limits(limits),
+        )
+        .unwrap();
+        // The memtable overlaps ingested files, so the ingest flushes it first.
+        db.put(&7u32.to_be_bytes(), Column::Metadata, Arc::new(vec![0]))
+            .unwrap();
+        let count = 200_000u32;
+        let entries = (0..count).map(|i| (i.to_be_bytes().to_vec(), vec![1; 32]));
+
+        // When
+        let stats = db
+            .ingest_sorted(Column::Metadata, &mut entries.into_iter())
+            .unwrap();
+
+        // Then
+        assert_eq!(stats.entries, u64::from(count));
+        assert_eq!(stats.bytes, u64::from(count) * 36);
+        assert!(stats.files > 1, "{stats:?}");
+        assert_eq!(
+            db.get(&7u32.to_be_bytes(), Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1; 32]))
+        );
+        assert_eq!(
+            db.get(&(count - 1).to_be_bytes(), Column::Metadata)
+                .unwrap(),
+            Some(Arc::new(vec![1; 32]))
+        );
+        let keys = db
+            .iter_all(Column::Metadata, None, None, IterDirection::Forward)
+            .map(|item| u32::from_be_bytes(item.unwrap().0.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(keys, (0..count).collect::<Vec<_>>());
+        assert!(std::fs::read_dir(db.db.path()).unwrap().all(|entry| !entry
+            .unwrap()
+            .file_name()
+            .to_string_lossy()
+            .starts_with("ingest-")));
+    }
+
+    #[test]
+    fn unsorted_ingest_fails_without_writes() {
+        // Given
+        let (db, _tmp) = create_db();
+        let entries = vec![(vec![1], vec![1]), (vec![3], vec![3]), (vec![2], vec![2])];
+
+        // When
+        let result = db.ingest_sorted(Column::Metadata, &mut entries.into_iter());
+
+        // Then
+        assert!(matches!(
+            result,
+            Err(DatabaseError::UnsortedIngest { ref key, .. }) if key == &[2]
+        ));
+        assert!(db
+            .iter_all(Column::Metadata, None, None, IterDirection::Forward)
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn as_rocks_exposes_the_property_queries() {
+        // Given
+        let (db, _tmp) = create_db();
+        let db = DataSource::<OnChain>::from(Arc::new(db));
+        for key in 0..10u8 {
+            db.put(&[key], Column::Metadata, Arc::new(vec![key]))
+                .unwrap();
+        }
+        db.flush().unwrap();
+
+        // When
+        let rocks = db.as_rocks::<OnChain>().unwrap();
+        let keys = rocks
+            .property_int_value(Column::Metadata, rocksdb::properties::ESTIMATE_NUM_KEYS)
+            .unwrap();
+        let stats = rocks.property_value(rocksdb::properties::DBSTATS).unwrap();
+
+        // Then
+        assert_eq!(keys, Some(10));
+        assert!(stats.is_some());
+    }
+
+    #[test]
+    fn sync_commits_sync_the_wal() {
+        use crate::state::in_memory::transaction::MemoryTransactionView;
+
+        // Given
+        let (db, tmp) = create_db();
+        let db = DataSource::<OnChain>::from(Arc::new(db));
+        let wal_syncs = || {
+            let stats = db
+                .as_rocks::<OnChain>()
+                .unwrap()
+                .property_value(rocksdb::properties::DBSTATS)
+                .unwrap()
+                .unwrap();
+            // The line looks like "Cumulative WAL: 2 writes, 1 syncs, 2.00 writes per sync, ...".
+            let line = stats
+                .lines()
+                .find(|line| line.starts_with("Cumulative WAL:"))
+                .unwrap()
+                .to_string();
+            line.split(", ")
+                .find_map(|part| part.strip_suffix(" syncs"))
+                .unwrap()
+                .parse::<u64>()
+                .unwrap()
+        };
+        let commit = |key: u8, durability| {
+            let transaction = MemoryTransactionView::<OnChain>::new(db.clone());
+            transaction
+                .put(&[key], Column::Metadata, Arc::new(vec![key]))
+                .unwrap();
+            transaction.commit_with(durability).unwrap();
+        };
+
+        // When
+        commit(1, WriteDurability::Buffered);
+        let syncs_after_buffered = wal_syncs();
+        commit(2, WriteDurability::Sync);
+        let syncs_after_sync = wal_syncs();
+
+        // Then
+        assert_eq!(syncs_after_buffered, 0);
+        assert_eq!(syncs_after_sync, 1);
+        db.flush().unwrap();
+        drop(db);
+        let db = RocksDb::<OnChain>::default_open(tmp.path(), None).unwrap();
+        assert_eq!(
+            db.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+        assert_eq!(
+            db.get(&[2], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![2]))
+        );
+    }
+
+    #[test]
+    fn bulk_import_is_durable_after_finalize() {
+        // Given
+        let (db, tmp) = create_db();
+        let batches = 10u32;
+        let batch_size = 10_000u32;
+        let bulk = db.bulk_import().unwrap();
+
+        // When
+        for batch in 0..batches {
+            let mut entries = (0..batch_size).map(|i| {
+                let key = batch * batch_size + i;
+                (
+                    key.to_be_bytes().to_vec(),
+                    Column::ContractsRawCode,
+                    WriteOperation::Insert(Arc::new(vec![7; 32])),
+                )
+            });
+            bulk.batch_write(&mut entries).unwrap();
+        }
+        let put_during_bulk = db.put(&[1], Column::Metadata, Arc::new(vec![1]));
+        let second_bulk = db.bulk_import().map(|_| ());
+        bulk.finalize_bulk_import().unwrap();
+
+        // Then
+        assert!(put_during_bulk.is_err());
+        assert!(matches!(
+            second_bulk,
+            Err(DatabaseError::BulkImportInProgress)
+        ));
+        let stats = db
+            .db
+            .property_value(rocksdb::properties::DBSTATS)
+            .unwrap()
+            .unwrap();
+        assert!(
+            stats.contains("Cumulative WAL: 0 writes"),
+            "The bulk import used the WAL: {stats}"
+        );
+        db.put(&[1], Column::Metadata, Arc::new(vec![1])).unwrap();
+        drop(db);
+        let db = RocksDb::<OnChain>::default_open(tmp.path(), None).unwrap();
+        assert_eq!(
+            db.iter_all(Column::ContractsRawCode, None, None, IterDirection::Forward)
+                .count(),
+            usize::try_from(batches * batch_size).unwrap()
+        );
+        assert_eq!(
+            db.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+    }
+
+    #[test]
+    fn dropped_bulk_import_flushes_the_data() {
+        // Given
+        let (db, tmp) = create_db();
+        let bulk = db.bulk_import().unwrap();
+        bulk.batch_write(&mut core::iter::once((
+            vec![1],
+            Column::Metadata,
+            WriteOperation::Insert(Arc::new(vec![1])),
+        )))
+        .unwrap();
+
+        // When
+        drop(bulk);
+
+        // Then
+        db.put(&[2], Column::Metadata, Arc::new(vec![2])).unwrap();
+        drop(db);
+        let db = RocksDb::<OnChain>::default_open(tmp.path(), None).unwrap();
+        assert_eq!(
+            db.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+    }
+
+    #[test]
+    fn bulk_import_waits_for_writes_that_passed_the_check() {
+        // Given
+        let (db, _tmp) = create_db();
+        // The write in progress that passed the check before the bulk import started.
+        let guard = db.write_guard().unwrap();
+        let entered = AtomicBool::new(false);
+
+        // When
+        let entered_before_write = std::thread::scope(|scope| {
+            let import = scope.spawn(|| {
+                let bulk = db.bulk_import().unwrap();
+                entered.store(true, Ordering::Release);
+                bulk.finalize_bulk_import().unwrap();
+            });
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            let entered_before_write = entered.load(Ordering::Acquire);
+            db.write_batch(
+                &mut core::iter::once((
+                    vec![1],
+                    Column::Metadata,
+                    WriteOperation::Insert(Arc::new(vec![1])),
+                )),
+                &WriteOptions::default(),
+            )
+            .unwrap();
+            drop(guard);
+            import.join().unwrap();
+            entered_before_write
+        });
+
+        // Then
+        assert!(!entered_before_write);
+        assert_eq!(
+            db.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+    }
+
+    #[test]
+    fn read_modify_write_waits_only_for_the_stripe_of_its_key() {
+        // Given
+        let (db, _tmp) = create_db();
+        let column = Column::Metadata;
+        let busy = vec![1];
+        let busy_stripe = KeyLocks::stripe(column.id(), &busy);
+        let free = (2..=u8::MAX)
+            .map(|i| vec![i])
+            .find(|key| KeyLocks::stripe(column.id(), key) != busy_stripe)
+            .unwrap();
+        // The read-modify-write of the `busy` key in progress.
+        let guard = db.key_locks.exclusive(column.id(), &busy);
+        let written = AtomicBool::new(false);
+
+        // When
+        let (replaced, written_before_release) = std::thread::scope(|scope| {
+            let write = scope.spawn(|| {
+                db.put(&busy, column, Arc::new(vec![1])).unwrap();
+                written.store(true, Ordering::Release);
+            });
+            let replaced = db.replace(&free, column, Arc::new(vec![2])).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            let written_before_release = written.load(Ordering::Acquire);
+            drop(guard);
+            write.join().unwrap();
+            (replaced, written_before_release)
+        });
+
+        // Then
+        assert_eq!(replaced, None);
+        assert!(!written_before_release);
+        assert_eq!(db.get(&busy, column).unwrap(), Some(Arc::new(vec![1])));
+        assert_eq!(db.get(&free, column).unwrap(), Some(Arc::new(vec![2])));
+    }
+
+    #[test]
+    fn take_returns_value_and_removes_it() {
+        let (db, _tmp) = create_db();
+        let key = vec![0xA, 0xB, 0xC];
+        let expected = Arc::new(vec![1, 2, 3]);
+        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+
+        assert_eq!(db.take(&key, Column::Metadata).unwrap(), Some(expected));
+        assert_eq!(db.take(&key, Column::Metadata).unwrap(), None);
+        assert!(!db.exists(&key, Column::Metadata).unwrap());
+    }
+
+    #[test]
+    fn compare_and_swap_has_exactly_one_winner() {
+        let (db, _tmp) = create_db();
+        let db = Arc::new(db);
+        let key = vec![0xA];
+
+        let winners: usize = (0..8u8)
+            .map(|i| {
+                let db = db.clone();
+                let key = key.clone();
+                std::thread::spawn(move || {
+                    db.compare_and_swap(&key, Column::Metadata, None, Arc::new(vec![i]))
+                        .unwrap()
+                        .is_ok()
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| usize::from(handle.join().unwrap()))
+            .sum();
+
+        assert_eq!(winners, 1);
+        let winner = db.get(&key, Column::Metadata).unwrap().unwrap();
+        assert_eq!(
+            db.compare_and_swap(&key, Column::Metadata, None, Arc::new(vec![0xFF]))
+                .unwrap(),
+            Err(Some(winner.clone()))
+        );
+        assert_eq!(
+            db.compare_and_swap(
+                &key,
+                Column::Metadata,
+                Some(winner.as_slice()),
+                Arc::new(vec![0xFF])
+            )
+            .unwrap(),
+            Ok(())
+        );
+    }
+
+    fn reopen(db: RocksDb<OnChain>, tmp: &TempDir) -> RocksDb<OnChain> {
+        // Drop the database without flushing memtables, so the reopened one recovers from the WAL.
+        drop(db);
+        RocksDb::default_open(tmp.path(), None).unwrap()
+    }
+
+    fn commit_batch() -> Vec<(Vec<u8>, Column, WriteOperation)> {
+        (0..10u8)
+            .map(|i| {
+                (
+                    vec![i],
+                    Column::Metadata,
+                    WriteOperation::Insert(Arc::new(vec![i])),
+                )
+            })
+            .chain(iter::once((
+                vec![0xFF],
+                Column::Metadata,
+                WriteOperation::Remove,
+            )))
+            .collect()
+    }
+
+    #[test]
+    fn batch_write_is_recovered_after_reopen() {
+        let (db, tmp) = create_db();
+        db.put(&[0xFF], Column::Metadata, Arc::new(vec![0xFF]))
+            .unwrap();
+
+        db.batch_write(&mut commit_batch().into_iter()).unwrap();
+        let db = reopen(db, &tmp);
+
+        let entries: Vec<_> = db
+            .iter_all(Column::Metadata, None, None, IterDirection::Forward)
+            .map_ok(|(k, v)| (k[0], v[0]))
+            .try_collect()
+            .unwrap();
+        assert_eq!(entries, (0..10u8).map(|i| (i, i)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn interrupted_batch_write_leaves_db_unchanged_after_reopen() {
+        let (db, tmp) = create_db();
+        db.put(&[0xFF], Column::Metadata, Arc::new(vec![0xFF]))
+            .unwrap();
+
+        // The fault hook aborts the commit in the middle of the batch.
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut entries = commit_batch().into_iter().enumerate().map(|(i, entry)| {
+                if i == 5 {
+                    panic!("Injected crash in the middle of the commit");
+                }
+                entry
+            });
+            db.batch_write(&mut entries)
+        }));
+        assert!(result.is_err());
+        let db = reopen(db, &tmp);
+
+        let entries: Vec<_> = db
+            .iter_all(Column::Metadata, None, None, IterDirection::Forward)
+            .map_ok(|(k, v)| (k[0], v[0]))
+            .try_collect()
+            .unwrap();
+        assert_eq!(entries, vec![(0xFF, 0xFF)]);
+    }
+
+    #[test]
+    fn checkpoint_contains_only_pre_checkpoint_state() {
+        let (db, _tmp) = create_db();
+        let checkpoint_dir = TempDir::new().unwrap();
+        for i in 0..5u8 {
+            db.put(&[i], Column::Metadata, Arc::new(vec![i])).unwrap();
+        }
+
+        db.checkpoint(checkpoint_dir.path()).unwrap();
+        db.put(&[0], Column::Metadata, Arc::new(vec![0xFF]))
+            .unwrap();
+        db.put(&[5], Column::Metadata, Arc::new(vec![5])).unwrap();
+        db.delete(&[1], Column::Metadata).unwrap();
+
+        let checkpoint =
+            RocksDb::<OnChain>::default_open(checkpoint_dir.path(), None).unwrap();
+        let entries: Vec<_> = checkpoint
+            .iter_all(Column::Metadata, None, None, IterDirection::Forward)
+            .map_ok(|(k, v)| (k[0], v[0]))
+            .try_collect()
+            .unwrap();
+        assert_eq!(entries, (0..5u8).map(|i| (i, i)).collect::<Vec<_>>());
+    }
+
+    fn all_entries(db: &RocksDb<OnChain>) -> Vec<(Column, Vec<u8>, Value)> {
+        enum_iterator::all::<Column>()
+            .flat_map(|column| {
+                db.iter_all(column, None, None, IterDirection::Forward)
+                    .map_ok(move |(k, v)| (column, k, v))
+            })
+            .try_collect()
+            .unwrap()
+    }
+
+    #[test]
+    fn restore_from_backup_brings_back_all_columns() {
+        let (db, tmp) = create_db();
+        let backup_dir = TempDir::new().unwrap();
+        for column in enum_iterator::all::<Column>() {
+            db.put(&column.id().to_be_bytes(), column, Arc::new(vec![1]))
+                .unwrap();
+        }
+        db.backup_to(backup_dir.path()).unwrap();
+        // The second backup into the same directory is incremental.
+        db.put(&[0xFF], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+        db.backup_to(backup_dir.path()).unwrap();
+        let expected = all_entries(&db);
+
+        // Corrupt the live database after the backup.
+        for column in enum_iterator::all::<Column>() {
+            db.delete_all(column, None).unwrap();
+        }
+        db.put(&[0xEE], Column::Coins, Arc::new(vec![3])).unwrap();
+        drop(db);
+
+        assert!(
+            RocksDb::<OnChain>::restore_from(backup_dir.path(), tmp.path(), false)
+                .is_err()
+        );
+        RocksDb::<OnChain>::restore_from(backup_dir.path(), tmp.path(), true).unwrap();
+
+        let db = RocksDb::<OnChain>::default_open(tmp.path(), None).unwrap();
+        assert_eq!(all_entries(&db), expected);
+    }
+
+    #[test]
+    fn restore_from_backup_into_empty_dir_does_not_require_force() {
+        let (db, _tmp) = create_db();
+        let backup_dir = TempDir::new().unwrap();
+        let restored_dir = TempDir::new().unwrap();
+        db.put(&[0xA], Column::Metadata, Arc::new(vec![1])).unwrap();
+        db.backup_to(backup_dir.path()).unwrap();
+
+        RocksDb::<OnChain>::restore_from(backup_dir.path(), restored_dir.path(), false)
+            .unwrap();
+
+        let restored =
+            RocksDb::<OnChain>::default_open(restored_dir.path(), None).unwrap();
+        assert_eq!(all_entries(&restored), all_entries(&db));
+    }
+
+    #[test]
+    fn secondary_db_reads_data_after_catch_up() {
+        let (primary, primary_dir) = create_db();
+        let secondary_dir = TempDir::new().unwrap();
+        primary
+            .put(&[1], Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+        primary.flush().unwrap();
+
+        let secondary = RocksDb::<OnChain>::open_secondary(
+            primary_dir.path(),
+            secondary_dir.path(),
+            enum_iterator::all::<Column>().collect(),
+        )
+        .unwrap();
+        assert_eq!(
+            secondary.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+
+        primary
+            .put(&[2], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+        primary.delete(&[1], Column::Metadata).unwrap();
+        assert_eq!(secondary.get(&[2], Column::Metadata).unwrap(), None);
+
+        secondary.try_catch_up_with_primary().unwrap();
+        assert_eq!(
+            secondary
+                .iter_all(Column::Metadata, None, None, IterDirection::Forward)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![(vec![2], Arc::new(vec![2]))]
+        );
+        assert!(secondary
+            .put(&[3], Column::Metadata, Arc::new(vec![3]))
+            .is_err());
+    }
+
+    #[test]
+    fn secondary_db_ignores_column_families_created_by_primary() {
+        let primary_dir = TempDir::new().unwrap();
+        let secondary_dir = TempDir::new().unwrap();
+        let primary =
+            RocksDb::<OnChain>::open(primary_dir.path(), vec![Column::Metadata], None)
+                .unwrap();
+        primary
+            .put(&[1], Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+
+        let secondary = RocksDb::<OnChain>::open_secondary(
+            primary_dir.path(),
+            secondary_dir.path(),
+            vec![Column::Metadata],
+        )
+        .unwrap();
+
+        // Reopen the primary with all columns to create new column families.
+        drop(primary);
+        let primary = RocksDb::<OnChain>::default_open(primary_dir.path(), None).unwrap();
+        primary.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+        primary
+            .put(&[2], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+
+        secondary.try_catch_up_with_primary().unwrap();
+        assert_eq!(
+            secondary.get(&[2], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![2]))
+        );
+    }
+
+    #[test]
+    fn read_snapshot_is_not_affected_by_later_writes() {
+        let (db, _tmp) = create_db();
+        db.put(&[1], Column::Metadata, Arc::new(vec![1])).unwrap();
+        db.put(&[2], Column::Metadata, Arc::new(vec![2])).unwrap();
+
+        let snapshot = db.read_snapshot().unwrap();
+        db.delete(&[1], Column::Metadata).unwrap();
+        db.put(&[3], Column::Metadata, Arc::new(vec![3])).unwrap();
+
+        assert_eq!(
+            snapshot.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+        assert!(!snapshot.exists(&[3], Column::Metadata).unwrap());
+        assert_eq!(
+            snapshot
+                .multi_get(&[vec![1], vec![3]], Column::Metadata)
+                .unwrap(),
+            vec![Some(Arc::new(vec![1])), None]
+        );
+        assert_eq!(
+            snapshot
+                .iter_all(Column::Metadata, None, None, IterDirection::Forward)
+                .map_ok(|(k, _)| k)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![vec![1], vec![2]]
+        );
+        assert_eq!(db.get(&[1], Column::Metadata).unwrap(), None);
+    }
+
+    #[test]
+    fn repair_recovers_database_with_truncated_sst_file() {
+        let (db, tmp) = create_db();
+        for i in 0..10u8 {
+            db.put(&[i], Column::Coins, Arc::new(vec![i; 100])).unwrap();
+        }
+        db.db.flush_cf(&db.cf(Column::Coins)).unwrap();
+        for i in 0..10u8 {
+            db.put(&[i], Column::Messages, Arc::new(vec![i; 100]))
+                .unwrap();
+        }
+        db.db.flush_cf(&db.cf(Column::Messages)).unwrap();
+        drop(db);
+
+        // The SST file of the `Messages` is created last, so it has the highest number.
+        let path = tmp.path().join(OnChain::name());
+        let corrupted = std::fs::read_dir(&path)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "sst"))
+            .max()
+            .unwrap();
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&corrupted)
+            .unwrap();
+        let len = file.metadata().unwrap().len();
+        file.set_len(len / 2).unwrap();
+        drop(file);
+
+        let report = RocksDb::<OnChain>::repair(
+            tmp.path(),
+            enum_iterator::all::<Column>().collect(),
+        )
+        .unwrap();
+        assert_eq!(report.dropped_files, 1);
+        assert!(report.salvaged_files >= 1);
+
+        let db = RocksDb::<OnChain>::default_open(tmp.path(), None).unwrap();
+        for i in 0..10u8 {
+            assert_eq!(
+                db.get(&[i], Column::Coins).unwrap(),
+                Some(Arc::new(vec![i; 100]))
+            );
+        }
+        // All columns are available after the repair.
+        for column in enum_iterator::all::<Column>() {
+            db.iter_all(column, None, None, IterDirection::Forward)
+                .try_collect::<_, Vec<_>, _>()
+                .unwrap();
+        }
+    }
+
+    /// Opens the database at the `tmp` bypassing the [`RocksDb`], like a newer version
+    /// of the node or a fork would do, and adds the `extra` column family.
+    fn add_column_family_out_of_band(tmp: &TempDir, extra: &str, removed: Column) {
+        let path = tmp.path().join(OnChain::name());
+        let opts = Options::default();
+        let existing = DB::list_cf(&opts, &path).unwrap();
+        let db = DB::open_cf(&opts, &path, existing).unwrap();
+        db.create_cf(extra, &opts).unwrap();
+        db.put_cf(&db.cf_handle(extra).unwrap(), [1], [2]).unwrap();
+        db.drop_cf(&RocksDb::<OnChain>::col_name(removed)).unwrap();
+    }
+
+    #[test]
+    fn open_ignores_unknown_column_families_and_creates_missing_ones() {
+        // Given
+        let (db, tmp) = create_db();
+        db.put(&[1], Column::Metadata, Arc::new(vec![1])).unwrap();
+        drop(db);
+        add_column_family_out_of_band(&tmp, "col-extra", Column::Coins);
+
+        // When
+        let db = RocksDb::<OnChain>::default_open(tmp.path(), None).unwrap();
+
+        // Then
+        assert_eq!(db.unknown_column_families(), ["col-extra".to_string()]);
+        assert_eq!(
+            db.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+        db.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+        assert_eq!(
+            db.get(&[1], Column::Coins).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+        // The unknown column family is left untouched.
+        let extra = db.db.cf_handle("col-extra").unwrap();
+        assert_eq!(db.db.get_cf(&extra, [1]).unwrap(), Some(vec![2]));
+    }
+
+    #[test]
+    fn open_fails_with_unknown_column_families_if_denied() {
+        // Given
+        let (db, tmp) = create_db();
+        drop(db);
+        add_column_family_out_of_band(&tmp, "col-extra", Column::Coins);
+        let config = ColumnsConfig::default().deny_unknown_column_families(true);
+
+        // When
+        let result = RocksDb::<OnChain>::open_with_config(
+            tmp.path(),
+            enum_iterator::all::<Column>().collect(),
+            None,
+            &config,
+        );
+
+        // Then
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("col-extra"), "{err}");
+    }
+
+    fn open_in_mode(path: &Path, mode: OpenMode) -> DatabaseResult<RocksDb<OnChain>> {
+        RocksDb::<OnChain>::open_with_config(
+            path,
+            enum_iterator::all::<Column>().collect(),
+            None,
+            &ColumnsConfig::default().with_open_mode(mode),
+        )
+    }
+
+    /// Returns the missing path, the path with the empty directory of the database,
+    /// and the path of the database with the entry.
+    fn paths_for_open_modes(tmp: &TempDir) -> [PathBuf; 3] {
+        let missing = tmp.path().join("missing");
+        let empty = tmp.path().join("empty");
+        std::fs::create_dir_all(empty.join(OnChain::name())).unwrap();
+        let valid = tmp.path().join("valid");
+        let db = RocksDb::<OnChain>::default_open(&valid, None).unwrap();
+        db.put(&[1], Column::Metadata, Arc::new(vec![1])).unwrap();
+        [missing, empty, valid]
+    }
+
+    #[test]
+    fn engine_stats_are_readable_after_the_write_workload() {
+        // Given
+        let tmp = TempDir::new().unwrap();
+        let db = RocksDb::<OnChain>::open_with_config(
+            tmp.path(),
+            enum_iterator::all::<Column>().collect(),
+            Some(16 * 1024 * 1024),
+            &ColumnsConfig::default().enable_statistics(true),
+        )
+        .unwrap();
+        for key in 0..100u8 {
+            db.put(&[key], Column::Coins, Arc::new(vec![key; 1024]))
+                .unwrap();
+        }
+        db.flush().unwrap();
+        for key in 0..100u8 {
+            db.get(&[key], Column::Coins).unwrap();
+        }
+
+        // When
+        let stats = db.engine_stats().unwrap();
+
+        // Then
+        assert!(
+            stats
+                .block_cache_hits
+                .saturating_add(stats.block_cache_misses)
+                > 0,
+            "{stats:?}"
+        );
+        assert!(stats.block_cache_usage > 0, "{stats:?}");
+        assert!(!stats.writes_stopped);
+        assert_eq!(stats.columns.len(), enum_iterator::all::<Column>().count());
+        let coins = stats
+            .columns
+            .iter()
+            .find(|column| column.column == Column::Coins.name())
+            .unwrap();
+        assert!(coins.level0_files > 0, "{stats:?}");
+        assert_eq!(coins.immutable_memtables, 0);
+    }
+
+    #[test]
+    fn engine_stats_have_no_tickers_without_statistics() {
+        // Given
+        let (db, _tmp) = create_db();
+        db.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+        db.flush().unwrap();
+        db.get(&[1], Column::Coins).unwrap();
+
+        // When
+        let stats = db.engine_stats().unwrap();
+
+        // Then
+        assert_eq!(stats.block_cache_hits, 0);
+        assert_eq!(stats.block_cache_misses, 0);
+        assert_eq!(stats.stall_micros, 0);
+        assert!(!stats.columns.is_empty());
+    }
+
+    #[test]
+    fn size_of_range_approximates_the_size_after_the_flush() {
+        // Given
+        let (db, _tmp) = create_db();
+        let mut rng = rand::thread_rng();
+        let mut exact = [0u64; 2];
+        for owner in 0..2u8 {
+            for i in 0..=255u8 {
+                // Random values don't compress, so the size on disk is close to the exact one.
+                let mut value = vec![0; 1024];
+                rng.fill_bytes(&mut value);
+                db.put(&[owner, i], Column::Coins, Arc::new(value)).unwrap();
+                exact[owner as usize] = exact[owner as usize].saturating_add(1026);
+            }
+        }
+        db.flush().unwrap();
+
+        // When
+        let sizes =
+            [0u8, 1].map(|owner| db.size_of_prefix(Column::Coins, &[owner]).unwrap());
+        let total = db.size_of_range(Column::Coins, &[], None).unwrap();
+
+        // Then
+        for (size, exact) in sizes.into_iter().zip(exact) {
+            assert!(
+                size > exact / 2 && size < exact.saturating_mul(2),
+                "{size} isn't close to {exact}"
+            );
+        }
+        assert!(total >= sizes[0].saturating_add(sizes[1]) / 2, "{total}");
+        assert_eq!(db.size_of_prefix(Column::Coins, &[0xFF]).unwrap(), 0);
+        assert_eq!(db.size_of_prefix(Column::Messages, &[]).unwrap(), 0);
+    }
+
+    #[test]
+    fn overlapping_size_interpolates_the_part_of_the_file() {
+        // The file with keys from `[1, 0]` to `[1, 0xFF]`.
+        let file = |start: &[u8], end: Option<&[u8]>| {
+            overlapping_size(1000, &[1, 0], &[1, 0xFF], start, end)
+        };
+
+        assert_eq!(file(&[0], Some(&[3])), Some(1000));
+        assert_eq!(file(&[1], None), Some(1000));
+        assert_eq!(file(&[2], None), None);
+        assert_eq!(file(&[0], Some(&[1, 0])), None);
+        let half = file(&[1, 0x80], None).unwrap();
+        assert!((450..550).contains(&half), "{half}");
+        // The single key file counts fully.
+        assert_eq!(overlapping_size(10, &[5], &[5], &[5], Some(&[6])), Some(10));
+    }
+
+    #[test]
+    fn ticker_count_is_parsed_from_the_statistics_dump() {
+        // Given
+        let statistics = "rocksdb.block.cache.miss COUNT : 7\n\
+            rocksdb.block.cache.hit COUNT : 42\n\
+            rocksdb.db.get.micros P50 : 1.000000 P95 : 2.000000 COUNT : 3 SUM : 4\n";
+
+        // Then
+        assert_eq!(ticker_count(statistics, "rocksdb.block.cache.hit"), 42);
+        assert_eq!(ticker_count(statistics, "rocksdb.block.cache.miss"), 7);
+        assert_eq!(ticker_count(statistics, "rocksdb.db.get.micros"), 0);
+        assert_eq!(ticker_count(statistics, "rocksdb.stall.micros"), 0);
+    }
+
+    #[test]
+    fn stall_reason_is_the_first_exceeded_trigger() {
+        // Given
+        let limits = ResourceLimits {
+            level0_slowdown_writes_trigger: 4,
+            level0_stop_writes_trigger: 8,
+            soft_pending_compaction_bytes: 100,
+            hard_pending_compaction_bytes: 1000,
+            ..Default::default()
+        };
+        let stats = |level0_files, pending_compaction_bytes| EngineStats {
+            columns: vec![ColumnEngineStats {
+                column: Column::Coins.name(),
+                level0_files,
+                pending_compaction_bytes,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        // Then
+        assert_eq!(
+            stall_reason(&stats(4, 1000), &limits, false),
+            StallReason::Level0Files
+        );
+        assert_eq!(
+            stall_reason(&stats(4, 1000), &limits, true),
+            StallReason::PendingCompactionBytes
+        );
+        assert_eq!(
+            stall_reason(&stats(0, 100), &limits, false),
+            StallReason::PendingCompactionBytes
+        );
+        assert_eq!(
+            stall_reason(&stats(7, 999), &limits, true),
+            StallReason::Memtables
+        );
+    }
+
+    #[test]
+    fn tiny_level0_triggers_stall_writes_until_the_compaction_catches_up() {
+        use crate::state::column_config::ColumnConfig;
 
-                    self._iter_all(column, opts, iter_mode).into_boxed()
+        // Given
+        let tmp = TempDir::new().unwrap();
+        let limits = ResourceLimits {
+            level0_slowdown_writes_trigger: 1,
+            level0_stop_writes_trigger: 2,
+            max_background_jobs: Some(2),
+            ..Default::default()
+        };
+        let config = ColumnsConfig::default().with_limits(limits).with_column(
+            OnChain::name(),
+            Column::Coins.name(),
+            ColumnConfig {
+                compression: Compression::None,
+                write_buffer_size: 64 * 1024,
+                ..Default::default()
+            },
+        );
+        let db = RocksDb::<OnChain>::open_with_config(
+            tmp.path(),
+            enum_iterator::all::<Column>().collect(),
+            None,
+            &config,
+        )
+        .unwrap();
+        let initial = db.write_pressure();
+        let writing = std::sync::atomic::AtomicBool::new(true);
+
+        // When
+        let observed = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let mut rng = rand::thread_rng();
+                let mut key = 0u64;
+                while writing.load(std::sync::atomic::Ordering::Acquire) {
+                    let mut value = vec![0; 1024];
+                    rng.fill_bytes(&mut value);
+                    db.put(&key.to_be_bytes(), Column::Coins, Arc::new(value))
+                        .unwrap();
+                    key = key.wrapping_add(1);
                 }
-            }
-            (None, Some(start)) => {
-                // start iterating in a certain direction from the start key
-                let iter_mode =
-                    IteratorMode::From(start, convert_to_rocksdb_direction(direction));
-                self._iter_all(column, ReadOptions::default(), iter_mode)
-                    .into_boxed()
-            }
-            (Some(prefix), Some(start)) => {
-                // TODO: Maybe we want to allow the `start` to be without a `prefix` in the future.
-                // If the `start` doesn't have the same `prefix`, return nothing.
-                if !start.starts_with(prefix) {
-                    return iter::empty().into_boxed()
+            });
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(60);
+            let mut observed = vec![];
+            while std::time::Instant::now() < deadline {
+                let pressure = db.write_pressure();
+                if pressure != WritePressure::Ok {
+                    observed.push(pressure);
+                    if observed.len() >= 10 {
+                        break
+                    }
                 }
-
-                // start iterating in a certain direction from the start key
-                // and end iterating when we've gone outside the prefix
-                let prefix = prefix.to_vec();
-                let iter_mode =
-                    IteratorMode::From(start, convert_to_rocksdb_direction(direction));
-                self._iter_all(column, ReadOptions::default(), iter_mode)
-                    .take_while(move |item| {
-                        if let Ok((key, _)) = item {
-                            key.starts_with(prefix.as_slice())
-                        } else {
-                            true
-                        }
-                    })
-                    .into_boxed()
+                std::thread::sleep(std::time::Duration::from_micros(100));
             }
+            writing.store(false, std::sync::atomic::Ordering::Release);
+            observed
+        });
+        db.compact(Some(Column::Coins), None).unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(60);
+        while db.write_pressure() != WritePressure::Ok
+            && std::time::Instant::now() < deadline
+        {
+            std::thread::sleep(std::time::Duration::from_millis(10));
         }
+
+        // Then
+        assert_eq!(initial, WritePressure::Ok);
+        assert!(!observed.is_empty());
+        assert!(
+            observed.iter().any(|pressure| matches!(
+                pressure,
+                WritePressure::Slowed(StallReason::Level0Files)
+                    | WritePressure::Stalled(StallReason::Level0Files)
+            )),
+            "{observed:?}"
+        );
+        assert_eq!(db.write_pressure(), WritePressure::Ok);
     }
-}
 
-impl<Description> BatchOperations for RocksDb<Description>
-where
-    Description: DatabaseDescription,
-{
-    fn batch_write(
-        &self,
-        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
-    ) -> StorageResult<()> {
-        let mut batch = WriteBatch::default();
+    #[test]
+    fn tiny_memory_budget_bounds_the_block_cache_of_all_columns() {
+        // Given
+        let tmp = TempDir::new().unwrap();
+        let budget = 1024 * 1024;
+        let limits = ResourceLimits {
+            memory_budget_bytes: Some(budget),
+            ..Default::default()
+        };
+        let db = RocksDb::<OnChain>::open_with_config(
+            tmp.path(),
+            enum_iterator::all::<Column>().collect(),
+            Some(64 * 1024 * 1024),
+            &ColumnsConfig::default().with_limits(limits),
+        )
+        .unwrap();
+        let columns = [Column::Coins, Column::Messages, Column::ContractsState];
 
-        for (key, column, op) in entries {
-            match op {
-                WriteOperation::Insert(value) => {
-                    batch.put_cf(&self.cf(column), key, value.as_ref());
+        // When
+        let mut max_usage = 0;
+        for round in 0..4u16 {
+            for column in columns {
+                for key in 0..500u16 {
+                    let key = [round.to_be_bytes(), key.to_be_bytes()].concat();
+                    db.put(&key, column, Arc::new(vec![1; 512])).unwrap();
                 }
-                WriteOperation::Remove => {
-                    batch.delete_cf(&self.cf(column), key);
+            }
+            db.flush().unwrap();
+            for column in columns {
+                for key in 0..500u16 {
+                    let key = [round.to_be_bytes(), key.to_be_bytes()].concat();
+                    db.get(&key, column).unwrap();
                 }
             }
+            max_usage = max_usage.max(db.engine_stats().unwrap().block_cache_usage);
         }
 
-        database_metrics().write_meter.inc();
-        database_metrics()
-            .bytes_written
-            .observe(batch.size_in_bytes() as f64);
-
-        self.db
-            .write(batch)
-            .map_err(|e| DatabaseError::Other(e.into()).into())
+        // Then
+        let stats = db.engine_stats().unwrap();
+        assert_eq!(stats.memory_budget, budget as u64);
+        assert!(max_usage > 0, "{stats:?}");
+        assert!(max_usage <= (budget - budget / 4) as u64, "{stats:?}");
+        assert!(stats.memtable_usage <= (budget / 4) as u64, "{stats:?}");
+        for column in columns {
+            for key in 0..500u16 {
+                let key = [3u16.to_be_bytes(), key.to_be_bytes()].concat();
+                assert_eq!(db.get(&key, column).unwrap(), Some(Arc::new(vec![1; 512])));
+            }
+        }
     }
-}
 
-impl<Description> TransactableStorage for RocksDb<Description>
-where
-    Description: DatabaseDescription,
-{
-    fn flush(&self) -> DatabaseResult<()> {
-        self.db
-            .flush_wal(true)
-            .map_err(|e| anyhow::anyhow!("Unable to flush WAL file: {}", e))?;
-        self.db
-            .flush()
-            .map_err(|e| anyhow::anyhow!("Unable to flush SST files: {}", e))?;
-        Ok(())
-    }
-}
+    #[test]
+    fn tiny_max_open_files_keeps_the_workload_over_many_files_correct() {
+        // Given
+        let tmp = TempDir::new().unwrap();
+        let limits = ResourceLimits {
+            max_open_files: Some(1),
+            target_file_size: 64 * 1024,
+            ..Default::default()
+        };
+        let db = RocksDb::<OnChain>::open_with_config(
+            tmp.path(),
+            enum_iterator::all::<Column>().collect(),
+            None,
+            &ColumnsConfig::default().with_limits(limits),
+        )
+        .unwrap();
+        let columns = [Column::Coins, Column::Messages, Column::ContractsState];
+
+        // When
+        for round in 0..10u8 {
+            for column in columns {
+                for key in 0..100u8 {
+                    db.put(&[round, key], column, Arc::new(vec![round; 1024]))
+                        .unwrap();
+                }
+                db.db.flush_cf(&db.cf(column)).unwrap();
+            }
+        }
 
-/// The `None` means overflow, so there is not following prefix.
-fn next_prefix(mut prefix: Vec<u8>) -> Option<Vec<u8>> {
-    for byte in prefix.iter_mut().rev() {
-        if let Some(new_byte) = byte.checked_add(1) {
-            *byte = new_byte;
-            return Some(prefix)
+        // Then
+        let files = count_sst_files(&tmp.path().join(OnChain::name())).unwrap();
+        assert!(files > columns.len(), "{files}");
+        for column in columns {
+            let entries = db
+                .iter_all(column, None, None, IterDirection::Forward)
+                .collect::<StorageResult<Vec<_>>>()
+                .unwrap();
+            assert_eq!(entries.len(), 1000);
+            assert_eq!(
+                db.get(&[9, 99], column).unwrap(),
+                Some(Arc::new(vec![9; 1024]))
+            );
         }
     }
-    None
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::database::database_description::on_chain::OnChain;
-    use fuel_core_storage::column::Column;
-    use tempfile::TempDir;
+    #[test]
+    fn create_if_missing_opens_any_path() {
+        // Given
+        let tmp = TempDir::new().unwrap();
+        let [missing, empty, valid] = paths_for_open_modes(&tmp);
 
-    fn create_db() -> (RocksDb<OnChain>, TempDir) {
-        let tmp_dir = TempDir::new().unwrap();
-        (
-            RocksDb::default_open(tmp_dir.path(), None).unwrap(),
-            tmp_dir,
-        )
+        // When
+        let results = [&missing, &empty, &valid]
+            .map(|path| open_in_mode(path, OpenMode::CreateIfMissing));
+
+        // Then
+        let [missing, empty, valid] = results.map(Result::unwrap);
+        assert_eq!(missing.get(&[1], Column::Metadata).unwrap(), None);
+        assert_eq!(empty.get(&[1], Column::Metadata).unwrap(), None);
+        assert_eq!(
+            valid.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
     }
 
     #[test]
-    fn can_put_and_read() {
-        let key = vec![0xA, 0xB, 0xC];
+    fn must_exist_refuses_missing_and_empty_paths() {
+        // Given
+        let tmp = TempDir::new().unwrap();
+        let [missing, empty, valid] = paths_for_open_modes(&tmp);
 
-        let (db, _tmp) = create_db();
-        let expected = Arc::new(vec![1, 2, 3]);
-        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+        // When
+        let results = [&missing, &empty, &valid]
+            .map(|path| open_in_mode(path, OpenMode::MustExist));
 
-        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), expected)
+        // Then
+        let [missing_result, empty_result, valid_result] = results;
+        let err = format!("{:?}", missing_result.unwrap_err());
+        assert!(err.contains(&missing.display().to_string()), "{err}");
+        assert!(!missing.exists());
+        let err = format!("{:?}", empty_result.unwrap_err());
+        assert!(err.contains(&empty.display().to_string()), "{err}");
+        assert_eq!(
+            valid_result.unwrap().get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
     }
 
     #[test]
-    fn put_returns_previous_value() {
-        let key = vec![0xA, 0xB, 0xC];
+    fn must_not_exist_refuses_valid_database() {
+        // Given
+        let tmp = TempDir::new().unwrap();
+        let [missing, empty, valid] = paths_for_open_modes(&tmp);
 
-        let (db, _tmp) = create_db();
-        let expected = Arc::new(vec![1, 2, 3]);
-        db.put(&key, Column::Metadata, expected.clone()).unwrap();
-        let prev = db
-            .replace(&key, Column::Metadata, Arc::new(vec![2, 4, 6]))
-            .unwrap();
+        // When
+        let results = [&missing, &empty, &valid]
+            .map(|path| open_in_mode(path, OpenMode::MustNotExist));
 
-        assert_eq!(prev, Some(expected));
+        // Then
+        let [missing_result, empty_result, valid_result] = results;
+        assert!(missing_result.is_ok());
+        assert!(empty_result.is_ok());
+        let err = format!("{:?}", valid_result.unwrap_err());
+        assert!(err.contains(&valid.display().to_string()), "{err}");
     }
 
     #[test]
-    fn delete_and_get() {
-        let key = vec![0xA, 0xB, 0xC];
+    fn clear_column_keeps_open_iterators_over_the_old_entries() {
+        // Given
+        let (db, tmp) = create_db();
+        for i in 0..10u8 {
+            db.put(&[i], Column::Coins, Arc::new(vec![i])).unwrap();
+        }
+        db.db.flush_cf(&db.cf(Column::Coins)).unwrap();
+        db.put(&[1], Column::Metadata, Arc::new(vec![1])).unwrap();
+        let mut iter = db.iter_all(Column::Coins, None, None, IterDirection::Forward);
+        assert_eq!(iter.next().unwrap().unwrap().0, vec![0]);
 
-        let (db, _tmp) = create_db();
-        let expected = Arc::new(vec![1, 2, 3]);
-        db.put(&key, Column::Metadata, expected.clone()).unwrap();
-        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), expected);
+        // When
+        db.clear_column(Column::Coins).unwrap();
 
-        db.delete(&key, Column::Metadata).unwrap();
-        assert_eq!(db.get(&key, Column::Metadata).unwrap(), None);
+        // Then
+        let rest = iter.map(|item| item.unwrap().0[0]).collect::<Vec<_>>();
+        assert_eq!(rest, (1..10).collect::<Vec<_>>());
+        assert_eq!(
+            db.iter_all(Column::Coins, None, None, IterDirection::Forward)
+                .count(),
+            0
+        );
+        assert_eq!(
+            db.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+        db.put(&[1], Column::Coins, Arc::new(vec![2])).unwrap();
+        drop(db);
+        // The clear survives the reopening.
+        let db = RocksDb::<OnChain>::default_open(tmp.path(), None).unwrap();
+        assert_eq!(
+            db.iter_all(Column::Coins, None, None, IterDirection::Forward)
+                .map(|item| item.unwrap())
+                .collect::<Vec<_>>(),
+            vec![(vec![1], Arc::new(vec![2]))]
+        );
     }
 
     #[test]
-    fn key_exists() {
-        let key = vec![0xA, 0xB, 0xC];
-
+    fn clear_column_does_not_break_concurrent_reads_and_writes() {
         let (db, _tmp) = create_db();
-        let expected = Arc::new(vec![1, 2, 3]);
-        db.put(&key, Column::Metadata, expected).unwrap();
-        assert!(db.exists(&key, Column::Metadata).unwrap());
+        let done = AtomicBool::new(false);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                while !done.load(Ordering::Acquire) {
+                    db.get(&[1], Column::Coins).unwrap();
+                    db.iter_all(Column::Coins, None, None, IterDirection::Forward)
+                        .try_collect::<_, Vec<_>, _>()
+                        .unwrap();
+                }
+            });
+            scope.spawn(|| {
+                let mut i = 0u8;
+                while !done.load(Ordering::Acquire) {
+                    i = i.wrapping_add(1);
+                    db.put(&[i], Column::Coins, Arc::new(vec![i])).unwrap();
+                    db.batch_write(&mut iter::once((
+                        vec![i],
+                        Column::Coins,
+                        WriteOperation::Remove,
+                    )))
+                    .unwrap();
+                }
+            });
+
+            for _ in 0..20 {
+                db.clear_column(Column::Coins).unwrap();
+            }
+            done.store(true, Ordering::Release);
+        });
     }
 
     #[test]
-    fn batch_write_inserts() {
-        let key = vec![0xA, 0xB, 0xC];
-        let value = Arc::new(vec![1, 2, 3]);
-
+    fn exists_checks_memtable_and_sst_files() {
         let (db, _tmp) = create_db();
-        let ops = vec![(
-            key.clone(),
-            Column::Metadata,
-            WriteOperation::Insert(value.clone()),
-        )];
+        db.put(&[1], Column::ContractsState, Arc::new(vec![1; 1024]))
+            .unwrap();
+        db.put(&[2], Column::ContractsState, Arc::new(vec![2; 1024]))
+            .unwrap();
+        db.db.flush_cf(&db.cf(Column::ContractsState)).unwrap();
+        db.put(&[3], Column::ContractsState, Arc::new(vec![3; 1024]))
+            .unwrap();
+        // The tombstone in the memtable hides the value in the SST file.
+        db.delete(&[2], Column::ContractsState).unwrap();
 
-        db.batch_write(&mut ops.into_iter()).unwrap();
-        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), value)
+        assert!(db.exists(&[1], Column::ContractsState).unwrap());
+        assert!(!db.exists(&[2], Column::ContractsState).unwrap());
+        assert!(db.exists(&[3], Column::ContractsState).unwrap());
+        assert!(!db.exists(&[4], Column::ContractsState).unwrap());
     }
 
     #[test]
-    fn batch_write_removes() {
-        let key = vec![0xA, 0xB, 0xC];
-        let value = Arc::new(vec![1, 2, 3]);
+    fn merges_match_the_memory_store() {
+        use crate::{
+            database::database_description::test::*,
+            state::in_memory::memory_store::MemoryStore,
+        };
 
-        let (db, _tmp) = create_db();
-        db.put(&key, Column::Metadata, value).unwrap();
+        // Given
+        let tmp_dir = TempDir::new().unwrap();
+        let db = RocksDb::<MergeDatabase>::default_open(tmp_dir.path(), None).unwrap();
+        let memory = MemoryStore::<MergeDatabase>::default();
+        db.batch_write(&mut merge_base().into_iter()).unwrap();
+        memory.batch_write(&mut merge_base().into_iter()).unwrap();
 
-        let ops = vec![(key.clone(), Column::Metadata, WriteOperation::Remove)];
-        db.batch_write(&mut ops.into_iter()).unwrap();
+        // When
+        let mut operations = merge_operations();
+        let second_half = operations.split_off(operations.len() / 2);
+        db.batch_write(&mut operations.clone().into_iter()).unwrap();
+        apply_merge_operations(&db, second_half.clone());
+        memory.batch_write(&mut operations.into_iter()).unwrap();
+        apply_merge_operations(&memory, second_half);
 
-        assert_eq!(db.get(&key, Column::Metadata).unwrap(), None);
+        // Then
+        assert_eq!(merge_entries(&db), merge_entries(&memory));
+        assert_eq!(merge_entries(&db), merged_state());
+        assert!(db
+            .merge(&[1], MergeColumn::Metadata, Arc::new(vec![1]))
+            .is_err());
     }
 
     #[test]
-    fn can_use_unit_value() {
-        let key = vec![0x00];
-
-        let (db, _tmp) = create_db();
-        let expected = Arc::new(vec![]);
-        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+    fn columns_use_their_config() {
+        use crate::state::column_config::ColumnConfig;
 
-        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), expected);
+        // Given
+        let tmp_dir = TempDir::new().unwrap();
+        let custom = ColumnConfig {
+            compression: Compression::Zstd,
+            block_size: 16 * 1024,
+            block_cache: None,
+            write_buffer_size: 8 * 1024 * 1024,
+            max_write_buffer_number: 3,
+            ttl: None,
+            compress_values_above: None,
+        };
+        let config = ColumnsConfig::default().with_column(
+            OnChain::name(),
+            Column::Metadata.name(),
+            custom,
+        );
+        let columns = enum_iterator::all::<Column>().collect::<Vec<_>>();
+        let db = RocksDb::<OnChain>::open_with_config(
+            tmp_dir.path(),
+            columns.clone(),
+            Some(1024 * 1024),
+            &config,
+        )
+        .unwrap();
+        db.put(&[1], Column::Metadata, Arc::new(vec![1])).unwrap();
+        drop(db);
 
-        assert!(db.exists(&key, Column::Metadata).unwrap());
+        // When
+        let db = RocksDb::<OnChain>::open_with_config(
+            tmp_dir.path(),
+            columns,
+            Some(1024 * 1024),
+            &config,
+        )
+        .unwrap();
 
+        // Then
         assert_eq!(
-            db.iter_all(Column::Metadata, None, None, IterDirection::Forward)
-                .collect::<Result<Vec<_>, _>>()
-                .unwrap()[0],
-            (key.clone(), expected.clone())
+            db.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
         );
+        let options = std::fs::read_dir(tmp_dir.path().join(OnChain::name()))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                path.file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .starts_with("OPTIONS-")
+            })
+            .max()
+            .map(|path| std::fs::read_to_string(path).unwrap())
+            .unwrap();
+        let section = |kind: &str, column: Column| {
+            let header = format!("[{kind} \"{}\"]", RocksDb::<OnChain>::col_name(column));
+            options
+                .split("\n[")
+                .find(|section| format!("[{section}").starts_with(&header))
+                .unwrap()
+                .to_string()
+        };
+        let metadata = section("CFOptions", Column::Metadata);
+        assert!(metadata.contains("compression=kZSTD"));
+        assert!(metadata.contains("write_buffer_size=8388608"));
+        assert!(metadata.contains("max_write_buffer_number=3"));
+        let metadata_table = section("TableOptions/BlockBasedTable", Column::Metadata);
+        assert!(metadata_table.contains("block_size=16384"));
+        assert!(metadata_table.contains("no_block_cache=true"));
 
-        assert_eq!(db.take(&key, Column::Metadata).unwrap().unwrap(), expected);
-
-        assert!(!db.exists(&key, Column::Metadata).unwrap());
+        let coins = section("CFOptions", Column::Coins);
+        assert!(coins.contains("compression=kLZ4Compression"));
+        let coins_table = section("TableOptions/BlockBasedTable", Column::Coins);
+        assert!(coins_table.contains("block_size=4096"));
+        assert!(coins_table.contains("no_block_cache=false"));
+        assert!(coins_table.contains("pin_l0_filter_and_index_blocks_in_cache=true"));
     }
 
     #[test]
-    fn can_use_unit_key() {
-        let key: Vec<u8> = Vec::with_capacity(0);
-
+    fn get_or_insert_with_initializes_once_across_racing_callers() {
+        // Given
         let (db, _tmp) = create_db();
-        let expected = Arc::new(vec![1, 2, 3]);
-        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+        let db = Arc::new(db);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(std::sync::Barrier::new(16));
 
-        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), expected);
+        // When
+        let values = (0..16u8)
+            .map(|i| {
+                let db = db.clone();
+                let calls = calls.clone();
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    db.get_or_insert_with(&[0xA], Column::Metadata, &mut || {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        vec![i]
+                    })
+                    .unwrap()
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
 
-        assert!(db.exists(&key, Column::Metadata).unwrap());
+        // Then
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        let stored = db.get(&[0xA], Column::Metadata).unwrap().unwrap();
+        assert!(values.iter().all(|value| *value == stored));
+    }
 
-        assert_eq!(
-            db.iter_all(Column::Metadata, None, None, IterDirection::Forward)
-                .collect::<Result<Vec<_>, _>>()
-                .unwrap()[0],
-            (key.clone(), expected.clone())
-        );
+    #[test]
+    fn concurrent_updates_do_not_lose_increments() {
+        // Given
+        let (db, _tmp) = create_db();
+        let db = Arc::new(db);
+        let barrier = Arc::new(std::sync::Barrier::new(16));
 
-        assert_eq!(db.take(&key, Column::Metadata).unwrap().unwrap(), expected);
+        // When
+        let handles = (0..16)
+            .map(|_| {
+                let db = db.clone();
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    for _ in 0..100 {
+                        db.update(&[0xA], Column::Metadata, &mut |current| {
+                            let current = current.map_or(0, |current| {
+                                u64::from_be_bytes(current.try_into().unwrap())
+                            });
+                            Some(current.saturating_add(1).to_be_bytes().to_vec())
+                        })
+                        .unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
 
-        assert!(!db.exists(&key, Column::Metadata).unwrap());
+        // Then
+        let stored = db.get(&[0xA], Column::Metadata).unwrap().unwrap();
+        assert_eq!(stored.as_slice(), 1600u64.to_be_bytes());
+        let outcome = db.update(&[0xA], Column::Metadata, &mut |_| None).unwrap();
+        assert_eq!(outcome, UpdateOutcome::Removed);
+        assert!(!db.exists(&[0xA], Column::Metadata).unwrap());
     }
 
     #[test]
-    fn can_use_unit_key_and_value() {
-        let key: Vec<u8> = Vec::with_capacity(0);
+    fn iterators_see_the_state_at_their_creation_under_concurrent_writes() {
+        // Given
+        const KEYS: u16 = 2000;
+        let (start, end) = (KEYS / 4, KEYS / 4 * 3);
+        let (db, _tmp) = create_db();
+        let db = Arc::new(db);
+        for i in (0..KEYS).step_by(2) {
+            db.put(&i.to_be_bytes(), Column::Coins, Arc::new(vec![0]))
+                .unwrap();
+        }
+        // Reads of the expected state and creations of iterators exclude writes.
+        let writes = Arc::new(std::sync::Mutex::new(()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let writer = std::thread::spawn({
+            let (db, writes, stop) = (db.clone(), writes.clone(), stop.clone());
+            move || {
+                let mut round = 0u8;
+                while !stop.load(Ordering::Relaxed) {
+                    round = round.wrapping_add(1);
+                    // Keys inside and outside of the range are inserted and removed.
+                    for i in 0..KEYS {
+                        let guard = writes.lock().unwrap();
+                        let key = i.to_be_bytes();
+                        if (i ^ u16::from(round)) % 3 == 0 {
+                            db.delete(&key, Column::Coins).unwrap();
+                        } else {
+                            db.put(&key, Column::Coins, Arc::new(vec![round])).unwrap();
+                        }
+                        drop(guard);
+                        std::thread::yield_now();
+                    }
+                }
+            }
+        });
+
+        // When
+        let mut results = vec![];
+        for _ in 0..10 {
+            let (expected, iter, mut column_iter) = {
+                let _guard = writes.lock().unwrap();
+                let expected = (start..end)
+                    .filter_map(|i| {
+                        let key = i.to_be_bytes().to_vec();
+                        let value = db.get(&key, Column::Coins).unwrap()?;
+                        Some((key, value))
+                    })
+                    .collect::<Vec<_>>();
+                let iter = db.iter_range(
+                    Column::Coins,
+                    None,
+                    Some(&start.to_be_bytes()),
+                    Some(&end.to_be_bytes()),
+                    IterDirection::Forward,
+                );
+                let column_iter = db.iter_column(
+                    Column::Coins,
+                    None,
+                    Some(&start.to_be_bytes()),
+                    IterDirection::Forward,
+                );
+                (expected, iter, column_iter)
+            };
+            let entries = iter
+                .map(|item| {
+                    std::thread::yield_now();
+                    item.unwrap()
+                })
+                .collect::<Vec<_>>();
+            let mut column_entries = vec![];
+            while let Some(item) = column_iter.next() {
+                let (key, value) = item.unwrap();
+                if key >= end.to_be_bytes().as_slice() {
+                    break
+                }
+                column_entries.push((key.to_vec(), Arc::new(value.to_vec())));
+                std::thread::yield_now();
+            }
+            results.push((expected, entries, column_entries));
+        }
+        stop.store(true, Ordering::Relaxed);
+        writer.join().unwrap();
+
+        // Then
+        for (expected, entries, column_entries) in results {
+            assert_eq!(entries, expected);
+            assert_eq!(column_entries, expected);
+        }
+    }
 
+    #[test]
+    fn writes_during_begin_shutdown_either_complete_or_are_rejected() {
+        // Given
         let (db, _tmp) = create_db();
-        let expected = Arc::new(vec![]);
-        db.put(&key, Column::Metadata, expected.clone()).unwrap();
+        let writers = 4u8;
+        let started = std::sync::Barrier::new(writers as usize + 1);
 
-        assert_eq!(db.get(&key, Column::Metadata).unwrap().unwrap(), expected);
+        // When
+        let written = std::thread::scope(|scope| {
+            let handles = (0..writers)
+                .map(|writer| {
+                    let (db, started) = (&db, &started);
+                    scope.spawn(move || {
+                        started.wait();
+                        let mut written = vec![];
+                        for i in 0u32.. {
+                            let key = [[writer].as_slice(), &i.to_be_bytes()].concat();
+                            match db.put(&key, Column::Coins, Arc::new(vec![writer])) {
+                                Ok(()) => written.push(key),
+                                Err(err) => return (written, DatabaseError::from(err)),
+                            }
+                        }
+                        unreachable!("The shutdown rejects writes")
+                    })
+                })
+                .collect::<Vec<_>>();
+            started.wait();
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            db.begin_shutdown();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("The writer doesn't panic"))
+                .collect::<Vec<_>>()
+        });
 
-        assert!(db.exists(&key, Column::Metadata).unwrap());
+        // Then
+        let mut expected = vec![];
+        for (keys, err) in written {
+            assert!(matches!(err, DatabaseError::ShuttingDown), "{err:?}");
+            expected.extend(keys);
+        }
+        expected.sort();
+        let stored = db
+            .iter_keys(Column::Coins, None, None, IterDirection::Forward)
+            .collect::<StorageResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(stored, expected);
+        let after = db
+            .put(&[0xFF], Column::Coins, Arc::new(vec![]))
+            .map_err(DatabaseError::from);
+        assert!(matches!(after, Err(DatabaseError::ShuttingDown)), "{after:?}");
+    }
 
-        assert_eq!(
-            db.iter_all(Column::Metadata, None, None, IterDirection::Forward)
-                .collect::<Result<Vec<_>, _>>()
-                .unwrap()[0],
-            (key.clone(), expected.clone())
-        );
+    #[test]
+    fn begin_shutdown_waits_for_writes_that_passed_the_check() {
+        // Given
+        let (db, _tmp) = create_db();
+        // The write in progress that passed the check before the shutdown started.
+        let guard = db.write_guard().unwrap();
+        let drained = AtomicBool::new(false);
 
-        assert_eq!(db.take(&key, Column::Metadata).unwrap().unwrap(), expected);
+        // When
+        let drained_before_write = std::thread::scope(|scope| {
+            let shutdown = scope.spawn(|| {
+                db.begin_shutdown();
+                drained.store(true, Ordering::Release);
+            });
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            let drained_before_write = drained.load(Ordering::Acquire);
+            db.write_batch(
+                &mut core::iter::once((
+                    vec![1],
+                    Column::Coins,
+                    WriteOperation::Insert(Arc::new(vec![1])),
+                )),
+                &WriteOptions::default(),
+            )
+            .unwrap();
+            drop(guard);
+            shutdown.join().unwrap();
+            drained_before_write
+        });
 
-        assert!(!db.exists(&key, Column::Metadata).unwrap());
+        // Then
+        assert!(!drained_before_write);
+        assert_eq!(db.get(&[1], Column::Coins).unwrap(), Some(Arc::new(vec![1])));
+        let after = db
+            .put(&[2], Column::Coins, Arc::new(vec![2]))
+            .map_err(DatabaseError::from);
+        assert!(matches!(after, Err(DatabaseError::ShuttingDown)), "{after:?}");
     }
 }
@@ -0,0 +1,185 @@
+use crate::state::{
+    column_iter::RawColumnIter,
+    rocks_db::{
+        rocksdb_error,
+        PinnedSnapshot,
+    },
+    IterDirection,
+};
+use fuel_core_metrics::core_metrics::database_metrics;
+use fuel_core_storage::{
+    Error as StorageError,
+    Result as StorageResult,
+};
+use rocksdb::{
+    DBAccess,
+    DBRawIteratorWithThreadMode,
+    IteratorMode,
+};
+use std::sync::Arc;
+
+/// Reimplementation of `rocksdb::DBIteratorWithThreadMode` that allows
+/// extracting only the required part of the item(for example, only the key).
+pub struct RocksDBKeyIterator<'a, D: DBAccess, R> {
+    raw: DBRawIteratorWithThreadMode<'a, D>,
+    /// The snapshot read by the `raw` iterator. It is declared after the `raw`,
+    /// so it is released after the iterator.
+    _snapshot: Arc<PinnedSnapshot>,
+    direction: IterDirection,
+    done: bool,
+    _marker: core::marker::PhantomData<R>,
+}
+
+/// Extracts the item from the current position of the raw iterator.
+pub trait ExtractItem: 'static {
+    /// The item returned by the iterator.
+    type Item;
+
+    /// Extracts the item from the raw iterator.
+    /// Returns `None` if the iterator is not valid.
+    fn extract_item<D>(
+        raw_iterator: &DBRawIteratorWithThreadMode<D>,
+    ) -> Option<Self::Item>
+    where
+        D: DBAccess;
+
+    /// Returns the size of the item in bytes.
+    fn size(item: &Self::Item) -> u64;
+
+    /// Returns `true` if the key of the item starts with the `prefix`.
+    fn starts_with(item: &Self::Item, prefix: &[u8]) -> bool;
+}
+
+impl<'a, D: DBAccess, R> RocksDBKeyIterator<'a, D, R> {
+    pub(crate) fn new(
+        raw: DBRawIteratorWithThreadMode<'a, D>,
+        mode: IteratorMode,
+        snapshot: Arc<PinnedSnapshot>,
+    ) -> Self {
+        let mut iterator = RocksDBKeyIterator {
+            raw,
+            _snapshot: snapshot,
+            direction: IterDirection::Forward,
+            done: false,
+            _marker: Default::default(),
+        };
+        iterator.set_mode(mode);
+        iterator
+    }
+
+    fn set_mode(&mut self, mode: IteratorMode) {
+        self.done = false;
+        self.direction = seek(&mut self.raw, mode);
+    }
+}
+
+/// Moves the `raw` iterator to the beginning of the iteration in the `mode`
+/// and returns the direction of the iteration.
+pub(crate) fn seek<D: DBAccess>(
+    raw: &mut DBRawIteratorWithThreadMode<D>,
+    mode: IteratorMode,
+) -> IterDirection {
+    match mode {
+        IteratorMode::Start => {
+            raw.seek_to_first();
+            IterDirection::Forward
+        }
+        IteratorMode::End => {
+            raw.seek_to_last();
+            IterDirection::Reverse
+        }
+        IteratorMode::From(key, rocksdb::Direction::Forward) => {
+            raw.seek(key);
+            IterDirection::Forward
+        }
+        IteratorMode::From(key, rocksdb::Direction::Reverse) => {
+            raw.seek_for_prev(key);
+            IterDirection::Reverse
+        }
+    }
+}
+
+/// The lending iterator over the raw iterator, see
+/// [`ColumnIter`](crate::state::column_iter::ColumnIter).
+/// Keys and values are borrowed from the raw iterator without copying.
+pub struct RocksDBColumnIter<'a, D: DBAccess> {
+    raw: DBRawIteratorWithThreadMode<'a, D>,
+    /// The snapshot read by the `raw` iterator, released after it.
+    _snapshot: Arc<PinnedSnapshot>,
+    direction: IterDirection,
+    /// The iteration ends at the first key without the prefix.
+    prefix: Option<Vec<u8>>,
+    started: bool,
+}
+
+impl<'a, D: DBAccess> RocksDBColumnIter<'a, D> {
+    pub(crate) fn new(
+        mut raw: DBRawIteratorWithThreadMode<'a, D>,
+        mode: IteratorMode,
+        prefix: Option<Vec<u8>>,
+        snapshot: Arc<PinnedSnapshot>,
+    ) -> Self {
+        let direction = seek(&mut raw, mode);
+        Self {
+            raw,
+            _snapshot: snapshot,
+            direction,
+            prefix,
+            started: false,
+        }
+    }
+}
+
+impl<'a, D: DBAccess> RawColumnIter for RocksDBColumnIter<'a, D> {
+    fn advance(&mut self) -> StorageResult<()> {
+        // The raw iterator is already at the first entry after the seek.
+        if core::mem::replace(&mut self.started, true) && self.raw.valid() {
+            match self.direction {
+                IterDirection::Forward => self.raw.next(),
+                IterDirection::Reverse => self.raw.prev(),
+            }
+        }
+        if !self.raw.valid() {
+            self.raw
+                .status()
+                .map_err(|e| StorageError::from(rocksdb_error(e)))?;
+        } else if let Some((key, value)) = self.current() {
+            let size = key.len().saturating_add(value.len());
+            database_metrics().read_meter.inc();
+            database_metrics().bytes_read.observe(size as f64);
+        }
+        Ok(())
+    }
+
+    fn current(&self) -> Option<(&[u8], &[u8])> {
+        let key = self.raw.key()?;
+        if let Some(prefix) = &self.prefix {
+            if !key.starts_with(prefix) {
+                return None
+            }
+        }
+        Some((key, self.raw.value()?))
+    }
+}
+
+impl<'a, D: DBAccess, R> Iterator for RocksDBKeyIterator<'a, D, R>
+where
+    R: ExtractItem,
+{
+    type Item = Result<R::Item, rocksdb::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            None
+        } else if let Some(item) = R::extract_item(&self.raw) {
+            match self.direction {
+                IterDirection::Forward => self.raw.next(),
+                IterDirection::Reverse => self.raw.prev(),
+            }
+            Some(Ok(item))
+        } else {
+            self.done = true;
+            self.raw.status().err().map(Result::Err)
+        }
+    }
+}
@@ -0,0 +1,805 @@
+//! The storage wrapper that logs operations of the inner storage exceeding the thresholds.
+//! It helps to find the operations stalling the services that use the storage.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Result as DatabaseResult,
+    },
+    state::{
+        notifying::StorageEvent,
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        EngineStats,
+        IngestStats,
+        IterDirection,
+        ReadView,
+        TransactableStorage,
+        WriteDurability,
+        WritePressure,
+    },
+};
+use fuel_core_storage::{
+    iter::{
+        BoxedIter,
+        IntoBoxedIter,
+        IteratorableStore,
+    },
+    kv_store::{
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Result as StorageResult,
+};
+use std::{
+    path::Path,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+use tokio::sync::broadcast;
+
+/// The number of bytes of the key included into the log.
+const LOGGED_KEY_BYTES: usize = 16;
+
+/// The thresholds of slow storage operations. The zero threshold disables its check,
+/// and the storage isn't wrapped when all thresholds are zero.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SlowLogConfig {
+    /// The duration of the single value operation: `get`, `put`, `delete`, or `exists`.
+    pub operation_duration: Duration,
+    /// The time spent by the storage to yield items of the iterator.
+    pub iter_duration: Duration,
+    /// The number of items yielded by the iterator.
+    pub iter_items: usize,
+    /// The duration of the batch write.
+    pub batch_write_duration: Duration,
+    /// The number of entries in the batch write.
+    pub batch_write_entries: usize,
+}
+
+impl Default for SlowLogConfig {
+    fn default() -> Self {
+        Self {
+            operation_duration: Duration::from_millis(50),
+            iter_duration: Duration::from_millis(250),
+            iter_items: 100_000,
+            batch_write_duration: Duration::from_millis(250),
+            batch_write_entries: 100_000,
+        }
+    }
+}
+
+impl SlowLogConfig {
+    /// The configuration that doesn't log anything.
+    pub fn disabled() -> Self {
+        Self {
+            operation_duration: Duration::ZERO,
+            iter_duration: Duration::ZERO,
+            iter_items: 0,
+            batch_write_duration: Duration::ZERO,
+            batch_write_entries: 0,
+        }
+    }
+
+    /// Returns `true` if any of the thresholds is set.
+    pub fn is_enabled(&self) -> bool {
+        *self != Self::disabled()
+    }
+}
+
+/// Returns `true` if the `value` crosses the non-zero `threshold`.
+fn exceeds<T: PartialOrd + Default>(value: T, threshold: T) -> bool {
+    threshold != T::default() && value > threshold
+}
+
+/// Formats the beginning of the `key` as hex.
+fn key_prefix(key: &[u8]) -> String {
+    if key.len() > LOGGED_KEY_BYTES {
+        format!("{}...", hex::encode(&key[..LOGGED_KEY_BYTES]))
+    } else {
+        hex::encode(key)
+    }
+}
+
+/// The storage that delegates to the inner storage and logs the operations slower than
+/// the [`SlowLogConfig`] allows. Logs are `tracing` warnings with the column, the hex prefix
+/// of the key, the duration, and the number of items of the operation.
+#[derive(Debug)]
+pub struct SlowLogStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    inner: DataSourceInner<Description::Column>,
+    config: SlowLogConfig,
+}
+
+impl<Description> SlowLogStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Wraps the `inner` storage with the thresholds from the `config`.
+    pub fn new<D>(inner: D, config: SlowLogConfig) -> Self
+    where
+        D: Into<DataSource<Description>>,
+    {
+        Self {
+            inner: inner.into().0,
+            config,
+        }
+    }
+
+    fn timed<T>(
+        &self,
+        operation: &'static str,
+        column: Description::Column,
+        key: &[u8],
+        f: impl FnOnce() -> T,
+    ) -> T {
+        let started = Instant::now();
+        let result = f();
+        let duration = started.elapsed();
+        if exceeds(duration, self.config.operation_duration) {
+            tracing::warn!(
+                database = Description::name(),
+                operation,
+                column = column.name(),
+                key = %key_prefix(key),
+                ?duration,
+                "Slow storage operation"
+            );
+        }
+        result
+    }
+
+    fn timed_iter<'a, T>(
+        &'a self,
+        column: Description::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        iter: impl FnOnce() -> BoxedIter<'a, StorageResult<T>>,
+    ) -> BoxedIter<'a, StorageResult<T>>
+    where
+        T: 'a,
+    {
+        let started = Instant::now();
+        let inner = iter();
+        SlowLogIter {
+            database: Description::name(),
+            column: column.name(),
+            prefix: prefix.map(<[u8]>::to_vec),
+            start: start.map(<[u8]>::to_vec),
+            config: self.config,
+            spent: started.elapsed(),
+            items: 0,
+            inner,
+        }
+        .into_boxed()
+    }
+}
+
+/// The iterator that accumulates the time spent in the inner iterator,
+/// and logs it with the number of yielded items when it is dropped.
+struct SlowLogIter<I> {
+    database: &'static str,
+    column: &'static str,
+    prefix: Option<Vec<u8>>,
+    start: Option<Vec<u8>>,
+    config: SlowLogConfig,
+    spent: Duration,
+    items: usize,
+    inner: I,
+}
+
+impl<I> Iterator for SlowLogIter<I>
+where
+    I: Iterator,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let started = Instant::now();
+        let item = self.inner.next();
+        self.spent = self.spent.saturating_add(started.elapsed());
+        if item.is_some() {
+            self.items = self.items.saturating_add(1);
+        }
+        item
+    }
+}
+
+impl<I> Drop for SlowLogIter<I> {
+    fn drop(&mut self) {
+        if exceeds(self.spent, self.config.iter_duration)
+            || exceeds(self.items, self.config.iter_items)
+        {
+            tracing::warn!(
+                database = self.database,
+                operation = "iter",
+                column = self.column,
+                prefix = %self.prefix.as_deref().map(key_prefix).unwrap_or_default(),
+                start = %self.start.as_deref().map(key_prefix).unwrap_or_default(),
+                duration = ?self.spent,
+                items = self.items,
+                "Slow storage iteration"
+            );
+        }
+    }
+}
+
+impl<Description> KeyValueStore for SlowLogStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        self.timed("put", column, key, || self.inner.put(key, column, value))
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        self.timed("replace", column, key, || {
+            self.inner.replace(key, column, value)
+        })
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        self.timed("write", column, key, || self.inner.write(key, column, buf))
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.timed("take", column, key, || self.inner.take(key, column))
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        self.timed("delete", column, key, || self.inner.delete(key, column))
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        self.timed("merge", column, key, || {
+            self.inner.merge(key, column, operand)
+        })
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        self.timed("exists", column, key, || self.inner.exists(key, column))
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        self.timed("size_of_value", column, key, || {
+            self.inner.size_of_value(key, column)
+        })
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.timed("get", column, key, || self.inner.get(key, column))
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        self.timed("get_with", column, key, || {
+            self.inner.get_with(key, column, f)
+        })
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        let first = keys.first().map(Vec::as_slice).unwrap_or_default();
+        self.timed("multi_get", column, first, || {
+            self.inner.multi_get(keys, column)
+        })
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        self.timed("compare_and_swap", column, key, || {
+            self.inner.compare_and_swap(key, column, expected, new)
+        })
+    }
+
+    fn get_or_insert_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        init: &mut dyn FnMut() -> Vec<u8>,
+    ) -> StorageResult<Value> {
+        self.timed("get_or_insert_with", column, key, || {
+            self.inner.get_or_insert_with(key, column, init)
+        })
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        self.timed("update", column, key, || self.inner.update(key, column, f))
+    }
+
+    fn read(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &mut [u8],
+    ) -> StorageResult<Option<usize>> {
+        self.timed("read", column, key, || self.inner.read(key, column, buf))
+    }
+}
+
+impl<Description> IteratorableStore for SlowLogStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.timed_iter(column, prefix, start, || {
+            self.inner.iter_all(column, prefix, start, direction)
+        })
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.timed_iter(column, prefix, start, || {
+            self.inner.iter_range(column, prefix, start, end, direction)
+        })
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.timed_iter(column, prefix, start, || {
+            self.inner.iter_keys(column, prefix, start, direction)
+        })
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        self.timed("delete_all", column, prefix.unwrap_or_default(), || {
+            self.inner.delete_all(column, prefix)
+        })
+    }
+}
+
+impl<Description> BatchOperations for SlowLogStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for SlowLogStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        let started = Instant::now();
+        let mut size = 0usize;
+        let mut columns = vec![];
+        let result = self.inner.batch_write_with(
+            &mut entries.inspect(|(_, column, _)| {
+                size = size.saturating_add(1);
+                if !columns.contains(column) {
+                    columns.push(*column);
+                }
+            }),
+            durability,
+        );
+        let duration = started.elapsed();
+
+        if exceeds(duration, self.config.batch_write_duration)
+            || exceeds(size, self.config.batch_write_entries)
+        {
+            let columns = columns
+                .iter()
+                .map(|column| column.name())
+                .collect::<Vec<_>>()
+                .join(",");
+            tracing::warn!(
+                database = Description::name(),
+                operation = "batch_write",
+                %columns,
+                ?duration,
+                entries = size,
+                "Slow storage batch write"
+            );
+        }
+        result
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        self.inner.flush()
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        self.inner.column_stats(column)
+    }
+
+    fn engine_stats(&self) -> DatabaseResult<EngineStats> {
+        self.inner.engine_stats()
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        self.inner.write_pressure()
+    }
+
+    fn size_of_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> DatabaseResult<u64> {
+        self.inner.size_of_range(column, start, end)
+    }
+
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        self.inner.checkpoint(path)
+    }
+
+    fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()> {
+        self.inner.backup_to(backup_dir)
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        self.inner.compact(column, range)
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        self.inner.clear_column(column)
+    }
+
+    fn multi_delete(
+        &self,
+        column: Self::Column,
+        keys: &[Vec<u8>],
+    ) -> DatabaseResult<usize> {
+        let key = keys.first().map(Vec::as_slice).unwrap_or_default();
+        self.timed("multi_delete", column, key, || {
+            self.inner.multi_delete(column, keys)
+        })
+    }
+
+    fn delete_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: &[u8],
+    ) -> DatabaseResult<()> {
+        self.timed("delete_range", column, start, || {
+            self.inner.delete_range(column, start, end)
+        })
+    }
+
+    fn ingest_sorted(
+        &self,
+        column: Self::Column,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<IngestStats> {
+        self.inner.ingest_sorted(column, entries)
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        self.inner.snapshot()
+    }
+
+    fn read_snapshot(&self) -> DatabaseResult<Box<dyn ReadView<Column = Self::Column>>>
+    where
+        Self::Column: 'static,
+    {
+        self.inner.read_snapshot()
+    }
+
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        self.inner.subscribe(column, prefix)
+    }
+
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.inner.replay_change_log(from_seq, into)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.inner.truncate_change_log(seq)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        self.inner.freeze_columns(columns)
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        self.inner.unfreeze_columns(columns)
+    }
+
+    fn begin_shutdown(&self) {
+        self.inner.begin_shutdown()
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        self.inner.finish_shutdown()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::on_chain::OnChain,
+        state::{
+            faulty::FaultyStorage,
+            in_memory::memory_store::MemoryStore,
+        },
+    };
+    use fuel_core_storage::column::Column;
+    use std::{
+        collections::BTreeMap,
+        sync::{
+            Arc,
+            Mutex,
+        },
+    };
+    use tracing::{
+        field::{
+            Field,
+            Visit,
+        },
+        Event,
+        Subscriber,
+    };
+    use tracing_subscriber::{
+        layer::{
+            Context,
+            SubscriberExt,
+        },
+        Layer,
+    };
+
+    type Fields = BTreeMap<String, String>;
+
+    /// Collects the fields of all events.
+    #[derive(Clone, Default)]
+    struct Events(Arc<Mutex<Vec<Fields>>>);
+
+    struct FieldsVisitor<'a>(&'a mut Fields);
+
+    impl<'a> Visit for FieldsVisitor<'a> {
+        fn record_debug(&mut self, field: &Field, value: &dyn core::fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for Events {
+        fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+            let mut fields = Fields::new();
+            event.record(&mut FieldsVisitor(&mut fields));
+            self.0.lock().unwrap().push(fields);
+        }
+    }
+
+    /// Runs the `f` and returns the fields of events it emitted.
+    fn capture(f: impl FnOnce()) -> Vec<Fields> {
+        let events = Events::default();
+        let subscriber = tracing_subscriber::registry().with(events.clone());
+        tracing::subscriber::with_default(subscriber, f);
+        let events = events.0.lock().unwrap().clone();
+        events
+    }
+
+    fn slow_store(
+        config: SlowLogConfig,
+    ) -> (Arc<FaultyStorage<OnChain>>, SlowLogStorage<OnChain>) {
+        let faulty = Arc::new(FaultyStorage::new(Arc::new(MemoryStore::default())));
+        let store = SlowLogStorage::new(faulty.clone(), config);
+        (faulty, store)
+    }
+
+    fn thresholds(duration: Duration, items: usize) -> SlowLogConfig {
+        SlowLogConfig {
+            operation_duration: duration,
+            iter_duration: duration,
+            iter_items: items,
+            batch_write_duration: duration,
+            batch_write_entries: items,
+        }
+    }
+
+    #[test]
+    fn slow_operations_are_logged_with_column_and_key() {
+        // Given
+        let (faulty, store) = slow_store(thresholds(Duration::from_millis(5), 0));
+        let key = [0xAB; 20];
+        store
+            .put(&key, Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+        faulty.set_latency(Duration::from_millis(10));
+
+        // When
+        let events = capture(|| {
+            store.get(&key, Column::Metadata).unwrap();
+        });
+
+        // Then
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event["operation"], "get");
+        assert_eq!(event["column"], Column::Metadata.name());
+        assert_eq!(event["key"], format!("{}...", "ab".repeat(16)));
+        assert!(event.contains_key("duration"));
+    }
+
+    #[test]
+    fn fast_operations_are_not_logged() {
+        // Given
+        let (_, store) = slow_store(thresholds(Duration::from_secs(10), 10));
+
+        // When
+        let events = capture(|| {
+            store
+                .put(&[1], Column::Metadata, Arc::new(vec![1]))
+                .unwrap();
+            store.get(&[1], Column::Metadata).unwrap();
+            store
+                .iter_all(Column::Metadata, None, None, IterDirection::Forward)
+                .for_each(drop);
+        });
+
+        // Then
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn iterations_over_the_item_limit_are_logged() {
+        // Given
+        let (_, store) = slow_store(thresholds(Duration::ZERO, 2));
+        for i in 0..3u8 {
+            store
+                .put(&[1, i], Column::Metadata, Arc::new(vec![i]))
+                .unwrap();
+        }
+
+        // When
+        let events = capture(|| {
+            store
+                .iter_all(Column::Metadata, Some(&[1]), None, IterDirection::Forward)
+                .for_each(drop);
+        });
+
+        // Then
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event["operation"], "iter");
+        assert_eq!(event["column"], Column::Metadata.name());
+        assert_eq!(event["prefix"], "01");
+        assert_eq!(event["items"], "3");
+    }
+
+    #[test]
+    fn slow_batch_writes_are_logged_with_columns_and_entries() {
+        // Given
+        let (faulty, store) = slow_store(thresholds(Duration::from_millis(5), 0));
+        faulty.set_latency(Duration::from_millis(10));
+
+        // When
+        let events = capture(|| {
+            store
+                .batch_write(&mut [Column::Metadata, Column::Coins].into_iter().map(
+                    |column| (vec![1], column, WriteOperation::Insert(Arc::new(vec![]))),
+                ))
+                .unwrap();
+        });
+
+        // Then
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event["operation"], "batch_write");
+        assert_eq!(
+            event["columns"],
+            format!("{},{}", Column::Metadata.name(), Column::Coins.name())
+        );
+        assert_eq!(event["entries"], "2");
+    }
+
+    #[test]
+    fn zero_thresholds_disable_logging() {
+        // Given
+        let config = SlowLogConfig::disabled();
+        let (faulty, store) = slow_store(config);
+        faulty.set_latency(Duration::from_millis(10));
+
+        // When
+        let events = capture(|| {
+            store.get(&[1], Column::Metadata).unwrap();
+        });
+
+        // Then
+        assert!(!config.is_enabled());
+        assert!(SlowLogConfig::default().is_enabled());
+        assert!(events.is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+//! The dump of all entries of the store in the canonical order, e.g. to pin the contents
+//! of the storage before and after the operation as the golden fixture of the test.
+//!
+//! Entries are ordered by the id of the column, then by the key, so dumps of stores
+//! with the same contents are equal regardless of the backend. The dump is serializable,
+//! and its [`StoreDump::hash`] digests the contents into one value to compare.
+
+use crate::database::{
+    Error as DatabaseError,
+    Result as DatabaseResult,
+};
+use fuel_core_storage::{
+    iter::{
+        IterDirection,
+        IteratorableStore,
+    },
+    kv_store::{
+        BatchOperations,
+        StorageColumn,
+        WriteOperation,
+    },
+};
+use fuel_core_types::fuel_crypto::Hasher;
+use std::sync::Arc;
+
+/// The entry of the [`StoreDump`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DumpEntry {
+    /// The id of the column of the entry.
+    pub column: u32,
+    /// The key of the entry.
+    #[serde(with = "hex")]
+    pub key: Vec<u8>,
+    /// The value of the entry.
+    #[serde(with = "hex")]
+    pub value: Vec<u8>,
+}
+
+/// All entries of the store ordered by the id of the column, then by the key.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct StoreDump {
+    entries: Vec<DumpEntry>,
+}
+
+impl StoreDump {
+    /// Reads all entries of all columns of the `store`.
+    pub fn capture<S>(store: &S) -> DatabaseResult<Self>
+    where
+        S: IteratorableStore + ?Sized,
+        S::Column: enum_iterator::Sequence,
+    {
+        let mut columns = enum_iterator::all::<S::Column>().collect::<Vec<_>>();
+        columns.sort_by_key(|column| column.id());
+        let mut entries = vec![];
+        for column in columns {
+            for entry in store.iter_all(column, None, None, IterDirection::Forward) {
+                let (key, value) = entry?;
+                entries.push(DumpEntry {
+                    column: column.id(),
+                    key,
+                    value: value.as_ref().clone(),
+                });
+            }
+        }
+        Ok(Self { entries })
+    }
+
+    /// Returns entries of the dump in the canonical order.
+    pub fn entries(&self) -> &[DumpEntry] {
+        &self.entries
+    }
+
+    /// Writes all entries of the dump into the `target` by one batch. Entries of
+    /// the `target` missing in the dump are left untouched, so the `target` should
+    /// be empty to get the same contents.
+    pub fn restore_into<Column>(
+        &self,
+        target: &dyn BatchOperations<Column = Column>,
+    ) -> DatabaseResult<()>
+    where
+        Column: StorageColumn + enum_iterator::Sequence,
+    {
+        let batch = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let column = enum_iterator::all::<Column>()
+                    .find(|column| column.id() == entry.column)
+                    .ok_or_else(|| {
+                        DatabaseError::Other(anyhow::anyhow!(
+                            "The dump has entries of the unknown column {}",
+                            entry.column
+                        ))
+                    })?;
+                Ok((
+                    entry.key.clone(),
+                    column,
+                    WriteOperation::Insert(Arc::new(entry.value.clone())),
+                ))
+            })
+            .collect::<DatabaseResult<Vec<_>>>()?;
+        target.batch_write(&mut batch.into_iter())?;
+        Ok(())
+    }
+
+    /// Returns the SHA-256 digest of entries in the canonical order. Each entry is
+    /// hashed as the big-endian id of the column followed by the key and the value
+    /// prefixed with their big-endian `u64` lengths, so different entries never
+    /// produce the same input.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hasher = Hasher::default();
+        for entry in &self.entries {
+            hasher.input(entry.column.to_be_bytes());
+            hasher.input((entry.key.len() as u64).to_be_bytes());
+            hasher.input(&entry.key);
+            hasher.input((entry.value.len() as u64).to_be_bytes());
+            hasher.input(&entry.value);
+        }
+        hasher.digest().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::on_chain::OnChain,
+        state::{
+            in_memory::memory_store::MemoryStore,
+            TransactableStorage,
+        },
+    };
+    use fuel_core_storage::{
+        column::Column,
+        kv_store::KeyValueStore,
+    };
+
+    /// Fills columns in the order different from the order of their ids.
+    fn filled<S>(store: &S)
+    where
+        S: KeyValueStore<Column = Column> + ?Sized,
+    {
+        for (column, key, value) in [
+            (Column::Metadata, vec![2], vec![20]),
+            (Column::Coins, vec![3, 1], vec![31, 1]),
+            (Column::Coins, vec![1], vec![]),
+            (Column::ContractsRawCode, vec![9], vec![90; 100]),
+            (Column::Metadata, vec![1], vec![10]),
+        ] {
+            store.put(&key, column, Arc::new(value)).unwrap();
+        }
+    }
+
+    #[test]
+    fn dump_orders_entries_by_the_column_id_and_the_key() {
+        // Given
+        let store = MemoryStore::<OnChain>::default();
+        filled(&store);
+
+        // When
+        let dump = StoreDump::capture(&store).unwrap();
+
+        // Then
+        let order = dump
+            .entries()
+            .iter()
+            .map(|entry| (entry.column, entry.key.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            [
+                (Column::ContractsRawCode.id(), vec![9]),
+                (Column::Coins.id(), vec![1]),
+                (Column::Coins.id(), vec![3, 1]),
+                (Column::Metadata.id(), vec![1]),
+                (Column::Metadata.id(), vec![2]),
+            ]
+        );
+    }
+
+    #[test]
+    fn dump_restore_dump_is_a_fixed_point() {
+        // Given
+        let store = MemoryStore::<OnChain>::default();
+        filled(&store);
+        let dump = StoreDump::capture(&store).unwrap();
+
+        // When
+        let restored = MemoryStore::<OnChain>::default();
+        dump.restore_into::<Column>(&restored).unwrap();
+        let serialized = serde_json::to_string(&dump).unwrap();
+        let deserialized: StoreDump = serde_json::from_str(&serialized).unwrap();
+
+        // Then
+        let redump = restored.dump().unwrap();
+        assert_eq!(redump, dump);
+        assert_eq!(redump.hash(), dump.hash());
+        assert_eq!(deserialized, dump);
+    }
+
+    #[test]
+    fn hash_changes_when_any_single_value_changes() {
+        // Given
+        let store = MemoryStore::<OnChain>::default();
+        filled(&store);
+        let dump = StoreDump::capture(&store).unwrap();
+        let mut hashes = vec![dump.hash()];
+
+        // When
+        for entry in dump.entries() {
+            let changed = MemoryStore::<OnChain>::default();
+            dump.restore_into::<Column>(&changed).unwrap();
+            let column = enum_iterator::all::<Column>()
+                .find(|column| column.id() == entry.column)
+                .unwrap();
+            let mut value = entry.value.clone();
+            value.push(0);
+            changed.put(&entry.key, column, Arc::new(value)).unwrap();
+            hashes.push(changed.dump().unwrap().hash());
+        }
+
+        // Then
+        let distinct = hashes
+            .iter()
+            .collect::<std::collections::HashSet<_>>()
+            .len();
+        assert_eq!(distinct, hashes.len());
+    }
+
+    #[test]
+    fn hash_distinguishes_the_boundary_between_the_key_and_the_value() {
+        // Given
+        let entry = |key: Vec<u8>, value: Vec<u8>| StoreDump {
+            entries: vec![DumpEntry {
+                column: 0,
+                key,
+                value,
+            }],
+        };
+
+        // When
+        let a = entry(vec![1, 2], vec![3]);
+        let b = entry(vec![1], vec![2, 3]);
+
+        // Then
+        assert_ne!(a.hash(), b.hash());
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn dump_of_the_rocksdb_equals_the_dump_of_the_memory_store() {
+        use crate::state::rocks_db::RocksDb;
+
+        // Given
+        let tmp = tempfile::TempDir::new().unwrap();
+        let rocks = RocksDb::<OnChain>::default_open(tmp.path(), None).unwrap();
+        filled(&rocks);
+        let memory = MemoryStore::<OnChain>::default();
+        filled(&memory);
+
+        // When
+        let dump = StoreDump::capture(&rocks).unwrap();
+
+        // Then
+        assert_eq!(dump, StoreDump::capture(&memory).unwrap());
+    }
+}
@@ -0,0 +1,569 @@
+//! The strict validation of keys of columns, see [`StorageColumn::key_format`].
+//!
+//! Tables always encode keys of the expected format, and it is checked by debug
+//! assertions. The raw writes, e.g. imports and tools, may write the malformed key,
+//! and it stays unreadable by tables. The [`StrictKeysStorage`] rejects such writes
+//! with the [`DatabaseError::InvalidKey`] in release builds too, and reports malformed
+//! keys met by scans of columns as errors of items.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::{
+        column_iter::ColumnIter,
+        notifying::StorageEvent,
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        EngineStats,
+        IterDirection,
+        TransactableStorage,
+        WriteDurability,
+        WritePressure,
+    },
+};
+use fuel_core_storage::{
+    iter::{
+        BoxedIter,
+        IntoBoxedIter,
+        IterConfig,
+        IteratorableStore,
+        PageIter,
+    },
+    kv_store::{
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Result as StorageResult,
+};
+use std::{
+    path::Path,
+    sync::Arc,
+};
+use tokio::sync::broadcast;
+
+/// Checks that the `key` has the [`StorageColumn::key_format`] of the `column`.
+pub fn check_key<Column: StorageColumn>(
+    column: Column,
+    key: &[u8],
+) -> DatabaseResult<()> {
+    let expected = column.key_format();
+    if expected.matches(key) {
+        Ok(())
+    } else {
+        Err(DatabaseError::InvalidKey {
+            column: column.name(),
+            expected,
+            got: key.len(),
+        })
+    }
+}
+
+/// The storage that rejects writes of keys with the wrong format and reports
+/// entries with such keys met by [`IteratorableStore::iter_all`] as errors.
+/// Reads of single keys are passed to the inner storage as is.
+#[derive(Debug)]
+pub struct StrictKeysStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    inner: DataSourceInner<Description::Column>,
+}
+
+impl<Description> StrictKeysStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Wraps the `inner` storage with the validation of keys.
+    pub fn new<D>(inner: D) -> Self
+    where
+        D: Into<DataSource<Description>>,
+    {
+        Self {
+            inner: inner.into().0,
+        }
+    }
+
+    fn check(column: Description::Column, key: &[u8]) -> StorageResult<()> {
+        check_key(column, key).map_err(Into::into)
+    }
+}
+
+impl<Description> KeyValueStore for StrictKeysStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        Self::check(column, key)?;
+        self.inner.put(key, column, value)
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        Self::check(column, key)?;
+        self.inner.replace(key, column, value)
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        Self::check(column, key)?;
+        self.inner.write(key, column, buf)
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        Self::check(column, key)?;
+        self.inner.take(key, column)
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        Self::check(column, key)?;
+        self.inner.delete(key, column)
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        Self::check(column, key)?;
+        self.inner.merge(key, column, operand)
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        self.inner.exists(key, column)
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        self.inner.size_of_value(key, column)
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.inner.get(key, column)
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        self.inner.get_with(key, column, f)
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        self.inner.multi_get(keys, column)
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        Self::check(column, key)?;
+        self.inner.compare_and_swap(key, column, expected, new)
+    }
+
+    fn get_or_insert_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        init: &mut dyn FnMut() -> Vec<u8>,
+    ) -> StorageResult<Value> {
+        Self::check(column, key)?;
+        self.inner.get_or_insert_with(key, column, init)
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        Self::check(column, key)?;
+        self.inner.update(key, column, f)
+    }
+}
+
+impl<Description> IteratorableStore for StrictKeysStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.inner
+            .iter_all(column, prefix, start, direction)
+            .map(move |item| {
+                let (key, value) = item?;
+                Self::check(column, &key)?;
+                Ok((key, value))
+            })
+            .into_boxed()
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.inner.iter_range(column, prefix, start, end, direction)
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.inner.iter_keys(column, prefix, start, direction)
+    }
+
+    fn has_any(&self, column: Self::Column, prefix: &[u8]) -> StorageResult<bool> {
+        self.inner.has_any(column, prefix)
+    }
+
+    fn iter(&self, column: Self::Column, config: IterConfig) -> PageIter {
+        self.inner.iter(column, config)
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        self.inner.delete_all(column, prefix)
+    }
+}
+
+impl<Description> BatchOperations for StrictKeysStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for StrictKeysStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        // The malformed key stops the batch before the inner storage applies it.
+        let entries = entries
+            .map(|(key, column, op)| {
+                Self::check(column, &key)?;
+                Ok((key, column, op))
+            })
+            .collect::<StorageResult<Vec<_>>>()?;
+        self.inner
+            .batch_write_with(&mut entries.into_iter(), durability)
+    }
+
+    fn iter_column(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> ColumnIter<'_> {
+        ColumnIter::boxed(self.iter_all(column, prefix, start, direction))
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        self.inner.flush()
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        self.inner.column_stats(column)
+    }
+
+    fn engine_stats(&self) -> DatabaseResult<EngineStats> {
+        self.inner.engine_stats()
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        self.inner.write_pressure()
+    }
+
+    fn size_of_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> DatabaseResult<u64> {
+        self.inner.size_of_range(column, start, end)
+    }
+
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        self.inner.checkpoint(path)
+    }
+
+    fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()> {
+        self.inner.backup_to(backup_dir)
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        self.inner.compact(column, range)
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        self.inner.clear_column(column)
+    }
+
+    fn multi_delete(
+        &self,
+        column: Self::Column,
+        keys: &[Vec<u8>],
+    ) -> DatabaseResult<usize> {
+        // The malformed key stops the removal before the inner storage applies it.
+        for key in keys {
+            check_key(column, key)?;
+        }
+        self.inner.multi_delete(column, keys)
+    }
+
+    fn delete_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: &[u8],
+    ) -> DatabaseResult<()> {
+        check_key(column, start)?;
+        check_key(column, end)?;
+        self.inner.delete_range(column, start, end)
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        // Scans of the snapshot report malformed keys too.
+        Ok(Arc::new(Self {
+            inner: self.inner.snapshot()?,
+        }))
+    }
+
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        self.inner.subscribe(column, prefix)
+    }
+
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.inner.replay_change_log(from_seq, into)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.inner.truncate_change_log(seq)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        self.inner.freeze_columns(columns)
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        self.inner.unfreeze_columns(columns)
+    }
+
+    fn begin_shutdown(&self) {
+        self.inner.begin_shutdown()
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        self.inner.finish_shutdown()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::on_chain::OnChain,
+        state::{
+            in_memory::memory_store::MemoryStore,
+            test_helpers::over_memory_store,
+        },
+    };
+    use fuel_core_storage::{
+        column::Column,
+        kv_store::KeyFormat,
+    };
+
+    fn strict() -> (Arc<MemoryStore<OnChain>>, StrictKeysStorage<OnChain>) {
+        over_memory_store(StrictKeysStorage::new)
+    }
+
+    fn is_invalid_key(
+        result: impl core::fmt::Debug,
+        expected: KeyFormat,
+        got: usize,
+    ) -> bool {
+        let result = format!("{result:?}");
+        result.contains("InvalidKey")
+            && result.contains(&format!("expected: {expected:?}, got: {got} }}"))
+    }
+
+    #[test]
+    fn writes_of_fixed_keys_are_checked() {
+        // Given
+        let (inner, storage) = strict();
+        let value = Arc::new(vec![1]);
+
+        // When
+        let truncated = storage.put(&[0; 31], Column::Transactions, value.clone());
+        let extended = storage.put(&[0; 33], Column::Transactions, value.clone());
+        let deleted = storage.delete(&[0; 31], Column::Transactions);
+        let valid = storage.put(&[0; 32], Column::Transactions, value.clone());
+
+        // Then
+        assert!(is_invalid_key(truncated, KeyFormat::Fixed(32), 31));
+        assert!(is_invalid_key(extended, KeyFormat::Fixed(32), 33));
+        assert!(is_invalid_key(deleted, KeyFormat::Fixed(32), 31));
+        assert!(valid.is_ok());
+        assert_eq!(
+            inner
+                .iter_keys(Column::Transactions, None, None, IterDirection::Forward)
+                .collect::<StorageResult<Vec<_>>>()
+                .unwrap(),
+            vec![vec![0; 32]]
+        );
+    }
+
+    #[test]
+    fn writes_of_multi_keys_are_checked_at_the_boundaries() {
+        // Given
+        let (_, storage) = strict();
+        let value = Arc::new(vec![1]);
+        let expected = KeyFormat::MultiKey(32, 32);
+
+        for (size, valid) in [(32, false), (63, false), (64, true), (65, false)] {
+            // When
+            let result =
+                storage.put(&vec![0; size], Column::ContractsState, value.clone());
+
+            // Then
+            match valid {
+                true => assert!(result.is_ok(), "{size}"),
+                false => assert!(is_invalid_key(result, expected, size), "{size}"),
+            }
+        }
+    }
+
+    #[test]
+    fn variable_keys_are_not_checked() {
+        // Given
+        let (_, storage) = strict();
+
+        // Then
+        for size in [0, 1, 100] {
+            storage
+                .put(&vec![0; size], Column::Metadata, Arc::new(vec![1]))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn batch_with_the_malformed_key_is_not_applied() {
+        // Given
+        let (inner, storage) = strict();
+        let value = Arc::new(vec![1]);
+        let mut batch = vec![
+            (
+                vec![1; 4],
+                Column::FuelBlocks,
+                WriteOperation::Insert(value.clone()),
+            ),
+            (
+                vec![2; 3],
+                Column::FuelBlocks,
+                WriteOperation::Insert(value.clone()),
+            ),
+        ]
+        .into_iter();
+
+        // When
+        let result = storage.batch_write(&mut batch);
+
+        // Then
+        assert!(is_invalid_key(result, KeyFormat::Fixed(4), 3));
+        assert_eq!(inner.get(&[1; 4], Column::FuelBlocks).unwrap(), None);
+    }
+
+    #[test]
+    fn scans_report_malformed_keys_as_errors_of_items() {
+        // Given
+        let (inner, storage) = strict();
+        for key in [vec![1; 33], vec![2; 32], vec![3; 33]] {
+            inner.put(&key, Column::Coins, Arc::new(vec![1])).unwrap();
+        }
+
+        // When
+        let items: Vec<_> = storage
+            .iter_all(Column::Coins, None, None, IterDirection::Forward)
+            .collect();
+
+        // Then
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].as_ref().unwrap().0, vec![1; 33]);
+        assert!(is_invalid_key(&items[1], KeyFormat::Fixed(33), 32));
+        assert_eq!(items[2].as_ref().unwrap().0, vec![3; 33]);
+    }
+}
@@ -0,0 +1,598 @@
+//! The storage whose underlying storage is replaced at runtime, see [`SwappableStorage`].
+//!
+//! Services share the storage via the [`DataSource`], so the restore from the backup
+//! or the completed regenesis import would otherwise require the restart of the node
+//! for services to pick up the new storage.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Result as DatabaseResult,
+    },
+    state::{
+        notifying::StorageEvent,
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        EngineStats,
+        IngestStats,
+        IterDirection,
+        TransactableStorage,
+        WriteDurability,
+        WritePressure,
+    },
+};
+use core::fmt::Debug;
+use fuel_core_storage::{
+    iter::{
+        BoxedIter,
+        IntoBoxedIter,
+        IterConfig,
+        IteratorableStore,
+        PageIter,
+    },
+    kv_store::{
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Result as StorageResult,
+};
+use std::{
+    path::Path,
+    sync::RwLock,
+};
+use tokio::sync::broadcast;
+
+/// The storage that delegates every operation to the current storage, which is replaced
+/// by the [`SwappableStorage::swap`] without the restart of services sharing it.
+///
+/// Each operation runs against the storage that was current when it started, so the
+/// operation never observes parts of different storages. Iterators and snapshots created
+/// before the swap keep working against the old storage until they are dropped, and they
+/// keep the old storage alive. Transaction views of the [`crate::database::Database`]
+/// are created over the current storage too, see [`TransactableStorage::transaction_base`],
+/// so the view created before the swap reads and commits into the old storage.
+/// Subscriptions stay subscribed to the storage that was current when they were created.
+#[derive(Debug)]
+pub struct SwappableStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    current: RwLock<DataSourceInner<Description::Column>>,
+}
+
+impl<Description> SwappableStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Creates the storage that delegates to the `inner` storage until the swap.
+    pub fn new<D>(inner: D) -> Self
+    where
+        D: Into<DataSource<Description>>,
+    {
+        Self {
+            current: RwLock::new(inner.into().0),
+        }
+    }
+
+    /// Returns the current storage.
+    pub fn current(&self) -> DataSource<Description> {
+        DataSource::new(self.inner())
+    }
+
+    /// Replaces the current storage with the `new` one and returns the old storage.
+    /// Operations in flight finish against the old storage, while operations started
+    /// after the swap run against the `new` storage.
+    pub fn swap(&self, new: DataSource<Description>) -> DataSource<Description> {
+        let mut current = self.current.write().expect("poisoned");
+        DataSource::new(core::mem::replace(&mut *current, new.0))
+    }
+
+    fn inner(&self) -> DataSourceInner<Description::Column> {
+        self.current.read().expect("poisoned").clone()
+    }
+
+    /// Returns the iterator created by `f` over the current storage.
+    /// The iterator keeps the storage alive, so it outlives the swap.
+    fn pinned<'a, T, F>(&self, f: F) -> BoxedIter<'a, T>
+    where
+        T: 'a,
+        F: for<'s> FnOnce(&'s DataSourceInner<Description::Column>) -> BoxedIter<'s, T>,
+    {
+        let storage = self.inner();
+        let iter = f(&storage);
+        // SAFETY: The iterator borrows the storage behind the `Arc`, which doesn't move
+        // and is kept alive by the `PinnedIter` until the iterator is dropped.
+        let iter =
+            unsafe { core::mem::transmute::<BoxedIter<'_, T>, BoxedIter<'a, T>>(iter) };
+        PinnedIter {
+            iter,
+            _storage: storage,
+        }
+        .into_boxed()
+    }
+}
+
+/// The iterator with the storage it borrows.
+struct PinnedIter<'a, T, Column> {
+    iter: BoxedIter<'a, T>,
+    // The iterator borrows the storage, so the storage must outlive it.
+    // Fields are dropped in the order of declaration, so the `iter` is released first.
+    _storage: DataSourceInner<Column>,
+}
+
+impl<'a, T, Column> Iterator for PinnedIter<'a, T, Column> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+}
+
+impl<Description> KeyValueStore for SwappableStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        self.inner().put(key, column, value)
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        self.inner().replace(key, column, value)
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        self.inner().write(key, column, buf)
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.inner().take(key, column)
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        self.inner().delete(key, column)
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        self.inner().merge(key, column, operand)
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        self.inner().exists(key, column)
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        self.inner().size_of_value(key, column)
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        self.inner().get(key, column)
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        self.inner().get_with(key, column, f)
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        self.inner().multi_get(keys, column)
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        self.inner().compare_and_swap(key, column, expected, new)
+    }
+
+    fn get_or_insert_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        init: &mut dyn FnMut() -> Vec<u8>,
+    ) -> StorageResult<Value> {
+        self.inner().get_or_insert_with(key, column, init)
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        self.inner().update(key, column, f)
+    }
+}
+
+impl<Description> IteratorableStore for SwappableStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.pinned(|storage| storage.iter_all(column, prefix, start, direction))
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        self.pinned(|storage| storage.iter_range(column, prefix, start, end, direction))
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        self.pinned(|storage| storage.iter_keys(column, prefix, start, direction))
+    }
+
+    fn has_any(&self, column: Self::Column, prefix: &[u8]) -> StorageResult<bool> {
+        self.inner().has_any(column, prefix)
+    }
+
+    fn iter(&self, column: Self::Column, config: IterConfig) -> PageIter {
+        PageIter::new(self.pinned(|storage| storage.iter(column, config).into_boxed()))
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        self.inner().delete_all(column, prefix)
+    }
+}
+
+impl<Description> BatchOperations for SwappableStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for SwappableStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        self.inner().batch_write_with(entries, durability)
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        self.inner().flush()
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        self.inner().column_stats(column)
+    }
+
+    fn engine_stats(&self) -> DatabaseResult<EngineStats> {
+        self.inner().engine_stats()
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        self.inner().write_pressure()
+    }
+
+    fn size_of_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> DatabaseResult<u64> {
+        self.inner().size_of_range(column, start, end)
+    }
+
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        self.inner().checkpoint(path)
+    }
+
+    fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()> {
+        self.inner().backup_to(backup_dir)
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        self.inner().compact(column, range)
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        self.inner().clear_column(column)
+    }
+
+    fn multi_delete(
+        &self,
+        column: Self::Column,
+        keys: &[Vec<u8>],
+    ) -> DatabaseResult<usize> {
+        self.inner().multi_delete(column, keys)
+    }
+
+    fn delete_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: &[u8],
+    ) -> DatabaseResult<()> {
+        self.inner().delete_range(column, start, end)
+    }
+
+    fn ingest_sorted(
+        &self,
+        column: Self::Column,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<IngestStats> {
+        self.inner().ingest_sorted(column, entries)
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        self.inner().snapshot()
+    }
+
+    fn transaction_base(&self) -> Option<DataSourceInner<Self::Column>> {
+        Some(self.inner())
+    }
+
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        self.inner().subscribe(column, prefix)
+    }
+
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.inner().replay_change_log(from_seq, into)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.inner().truncate_change_log(seq)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        self.inner().freeze_columns(columns)
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        self.inner().unfreeze_columns(columns)
+    }
+
+    fn begin_shutdown(&self) {
+        self.inner().begin_shutdown()
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        self.inner().finish_shutdown()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{
+            database_description::on_chain::OnChain,
+            Database,
+        },
+        state::in_memory::memory_store::MemoryStore,
+    };
+    use fuel_core_storage::column::Column;
+    use std::sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Arc,
+    };
+
+    const KEYS: u8 = 100;
+
+    /// Returns the store with all keys set to the `value`.
+    fn store(value: u8) -> Arc<MemoryStore<OnChain>> {
+        let store = Arc::new(MemoryStore::<OnChain>::default());
+        store
+            .batch_write(&mut (0..KEYS).map(|key| {
+                (
+                    vec![key],
+                    Column::Coins,
+                    WriteOperation::Insert(Arc::new(vec![value])),
+                )
+            }))
+            .unwrap();
+        store
+    }
+
+    fn values(storage: &dyn TransactableStorage<Column = Column>) -> Vec<u8> {
+        storage
+            .iter_all(Column::Coins, None, None, IterDirection::Forward)
+            .map(|item| item.unwrap().1[0])
+            .collect()
+    }
+
+    #[test]
+    fn swap_redirects_new_operations_to_the_new_store() {
+        // Given
+        let swappable = SwappableStorage::<OnChain>::new(store(1));
+        let iter = swappable.iter_all(Column::Coins, None, None, IterDirection::Forward);
+
+        // When
+        let old = swappable.swap(store(2).into());
+
+        // Then
+        assert_eq!(values(old.as_ref()), vec![1; KEYS as usize]);
+        assert_eq!(values(&swappable), vec![2; KEYS as usize]);
+        assert_eq!(
+            swappable.get(&[0], Column::Coins).unwrap(),
+            Some(Arc::new(vec![2]))
+        );
+        // The iterator created before the swap keeps going over the old store.
+        let old_values = iter.map(|item| item.unwrap().1[0]).collect::<Vec<_>>();
+        assert_eq!(old_values, vec![1; KEYS as usize]);
+    }
+
+    #[test]
+    fn old_store_is_dropped_after_the_last_iterator() {
+        // Given
+        let old = store(1);
+        let weak = Arc::downgrade(&old);
+        let swappable = SwappableStorage::<OnChain>::new(old);
+        let mut iter =
+            swappable.iter_keys(Column::Coins, None, None, IterDirection::Forward);
+        let page = swappable.iter(Column::Coins, IterConfig::default());
+
+        // When
+        drop(swappable.swap(store(2).into()));
+
+        // Then
+        assert!(weak.upgrade().is_some());
+        assert_eq!(iter.next().unwrap().unwrap(), vec![0]);
+        drop(iter);
+        assert!(weak.upgrade().is_some());
+        assert_eq!(page.count(), KEYS as usize);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn transaction_created_before_the_swap_keeps_the_old_store() {
+        // Given
+        let old = store(1);
+        let swappable = Arc::new(SwappableStorage::<OnChain>::new(old.clone()));
+        let database = Database::<OnChain>::new(swappable.clone());
+        let mut transaction = database.transaction();
+
+        // When
+        swappable.swap(store(2).into());
+        transaction
+            .as_ref()
+            .data_source()
+            .put(&[0], Column::Coins, Arc::new(vec![3]))
+            .unwrap();
+        transaction.commit_with(WriteDurability::Buffered).unwrap();
+
+        // Then
+        assert_eq!(
+            old.get(&[0], Column::Coins).unwrap(),
+            Some(Arc::new(vec![3]))
+        );
+        assert_eq!(
+            swappable.get(&[0], Column::Coins).unwrap(),
+            Some(Arc::new(vec![2]))
+        );
+    }
+
+    #[test]
+    fn concurrent_operations_never_observe_a_torn_state() {
+        // Given
+        let swappable = Arc::new(SwappableStorage::<OnChain>::new(store(1)));
+        let stores = [store(1), store(2)];
+        let keys = (0..KEYS).map(|key| vec![key]).collect::<Vec<_>>();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        // When
+        let readers = (0..4)
+            .map(|_| {
+                let swappable = swappable.clone();
+                let keys = keys.clone();
+                let stop = stop.clone();
+                std::thread::spawn(move || {
+                    let mut operations = 0usize;
+                    loop {
+                        let scanned = values(swappable.as_ref());
+                        assert_eq!(scanned.len(), KEYS as usize);
+                        assert!(scanned.iter().all(|value| *value == scanned[0]));
+
+                        let got = swappable.multi_get(&keys, Column::Coins).unwrap();
+                        let first = got[0].clone().unwrap();
+                        assert!(got.iter().all(|value| value.as_ref() == Some(&first)));
+                        operations = operations.saturating_add(1);
+                        if stop.load(Ordering::SeqCst) {
+                            return operations
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for i in 0..200 {
+            swappable.swap(stores[i % 2].clone().into());
+            std::thread::sleep(std::time::Duration::from_micros(200));
+        }
+        stop.store(true, Ordering::SeqCst);
+
+        // Then
+        for reader in readers {
+            assert!(reader.join().unwrap() > 0);
+        }
+        assert_eq!(values(swappable.as_ref()), vec![2; KEYS as usize]);
+    }
+}
@@ -0,0 +1,16 @@
+//! Fixtures shared by tests of wrappers of the storage.
+
+use crate::{
+    database::database_description::on_chain::OnChain,
+    state::in_memory::memory_store::MemoryStore,
+};
+use std::sync::Arc;
+
+/// Creates the wrapper by the `wrap` over the empty in-memory store and returns both,
+/// so the test can look at what the wrapper wrote into the inner store.
+pub(crate) fn over_memory_store<W>(
+    wrap: impl FnOnce(Arc<MemoryStore<OnChain>>) -> W,
+) -> (Arc<MemoryStore<OnChain>>, W) {
+    let inner = Arc::new(MemoryStore::default());
+    (inner.clone(), wrap(inner))
+}
@@ -0,0 +1,512 @@
+//! The throttled iteration for background tasks, see [`ThrottledIter`].
+//!
+//! Background tasks like the pruning and the integrity verification read whole columns.
+//! Unthrottled, they saturate the block cache and the disk and slow down foreground
+//! queries. The [`Throttle`] keeps the iteration within the [`IterBudget`] by sleeping
+//! between items, and stops it promptly once the [`CancellationToken`] is cancelled.
+
+use crate::database::Error as DatabaseError;
+use fuel_core_storage::{
+    kv_store::Value,
+    Result as StorageResult,
+};
+use futures::{
+    Stream,
+    StreamExt,
+};
+use std::{
+    borrow::BorrowMut,
+    sync::{
+        Arc,
+        Condvar,
+        Mutex,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+use tokio::sync::Notify;
+
+/// The budget of the throttled iteration. `None` or zero doesn't limit the iteration.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IterBudget {
+    /// The maximum number of items per second.
+    pub items_per_sec: Option<u64>,
+    /// The maximum total size of items per second in bytes.
+    pub bytes_per_sec: Option<u64>,
+    /// The iteration yields to the scheduler after this number of items.
+    pub yield_every: Option<u64>,
+}
+
+/// The token that cancels the throttled iteration. Clones share the same state,
+/// so the token cancelled by one clone is cancelled for all of them.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    inner: Arc<TokenInner>,
+}
+
+#[derive(Debug, Default)]
+struct TokenInner {
+    cancelled: Mutex<bool>,
+    condvar: Condvar,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    /// Creates the token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the token and wakes up iterations waiting for the budget.
+    pub fn cancel(&self) {
+        *self.inner.cancelled.lock().expect("poisoned") = true;
+        self.inner.condvar.notify_all();
+        self.inner.notify.notify_waiters();
+    }
+
+    /// Returns `true` if the token is cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        *self.inner.cancelled.lock().expect("poisoned")
+    }
+
+    /// Blocks the current thread for the `timeout` or until the token is cancelled.
+    /// Returns `true` if the token is cancelled.
+    fn wait_timeout(&self, timeout: Duration) -> bool {
+        let cancelled = self.inner.cancelled.lock().expect("poisoned");
+        let (cancelled, _) = self
+            .inner
+            .condvar
+            .wait_timeout_while(cancelled, timeout, |cancelled| !*cancelled)
+            .expect("poisoned");
+        *cancelled
+    }
+
+    /// Waits until the token is cancelled.
+    async fn cancelled(&self) {
+        loop {
+            // The notification is registered before the check, so the cancellation
+            // between the check and the await isn't missed.
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return
+            }
+            notified.await;
+        }
+    }
+}
+
+/// The size of the item of the throttled iteration counted by the [`IterBudget::bytes_per_sec`].
+pub trait ItemSize {
+    /// Returns the size of the item in bytes.
+    fn item_size(&self) -> usize;
+}
+
+impl ItemSize for Vec<u8> {
+    fn item_size(&self) -> usize {
+        self.len()
+    }
+}
+
+impl ItemSize for (Vec<u8>, Value) {
+    fn item_size(&self) -> usize {
+        self.0.len().saturating_add(self.1.len())
+    }
+}
+
+/// The state of the throttled iteration. It can be shared by several iterations,
+/// e.g. by iterators over consecutive batches, so they stay within one budget.
+#[derive(Debug)]
+pub struct Throttle {
+    budget: IterBudget,
+    cancellation: CancellationToken,
+    started: Instant,
+    items: u64,
+    bytes: u64,
+}
+
+impl Throttle {
+    /// Creates the throttle within the `budget`.
+    pub fn new(budget: IterBudget) -> Self {
+        Self {
+            budget,
+            cancellation: CancellationToken::new(),
+            started: Instant::now(),
+            items: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Stops the iteration once the `token` is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Admits the item of `size` bytes: blocks the current thread until the item fits
+    /// into the budget and yields to the scheduler every [`IterBudget::yield_every`] items.
+    /// Fails with [`DatabaseError::Cancelled`] if the token is cancelled.
+    pub fn acquire(&mut self, size: usize) -> StorageResult<()> {
+        self.ensure_not_cancelled()?;
+        let delay = self.admit(size);
+        if !delay.is_zero() && self.cancellation.wait_timeout(delay) {
+            return Err(DatabaseError::Cancelled.into())
+        }
+        if self.should_yield() {
+            std::thread::yield_now();
+        }
+        Ok(())
+    }
+
+    /// The async version of the [`Throttle::acquire`]. It awaits
+    /// the timer of the `tokio` instead of blocking the thread.
+    pub async fn acquire_async(&mut self, size: usize) -> StorageResult<()> {
+        self.ensure_not_cancelled()?;
+        let delay = self.admit(size);
+        if !delay.is_zero() {
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = self.cancellation.cancelled() => {
+                    return Err(DatabaseError::Cancelled.into())
+                }
+            }
+        }
+        if self.should_yield() {
+            tokio::task::yield_now().await;
+        }
+        Ok(())
+    }
+
+    fn ensure_not_cancelled(&self) -> StorageResult<()> {
+        if self.cancellation.is_cancelled() {
+            return Err(DatabaseError::Cancelled.into())
+        }
+        Ok(())
+    }
+
+    /// Counts the item and returns the time left until it fits into the budget.
+    /// The budget is spent by previous items, so the first item is admitted at once.
+    fn admit(&mut self, size: usize) -> Duration {
+        let floor = duration_of(self.items, self.budget.items_per_sec)
+            .max(duration_of(self.bytes, self.budget.bytes_per_sec));
+        self.items = self.items.saturating_add(1);
+        self.bytes = self.bytes.saturating_add(size as u64);
+        floor.saturating_sub(self.started.elapsed())
+    }
+
+    fn should_yield(&self) -> bool {
+        self.budget
+            .yield_every
+            .and_then(|every| self.items.checked_rem(every))
+            .is_some_and(|rem| rem == 0)
+    }
+}
+
+/// Returns the time it takes to spend the `amount` at the `rate` per second.
+fn duration_of(amount: u64, rate: Option<u64>) -> Duration {
+    let nanos = rate
+        .and_then(|rate| {
+            u128::from(amount)
+                .saturating_mul(1_000_000_000)
+                .checked_div(u128::from(rate))
+        })
+        .unwrap_or(0);
+    Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+}
+
+/// The iterator adapter that keeps the iteration within the [`IterBudget`].
+/// Once the token is cancelled, the iterator yields [`DatabaseError::Cancelled`]
+/// and stops, so the caller doesn't treat the cancelled iteration as the finished one.
+pub struct ThrottledIter<I, T = Throttle> {
+    inner: I,
+    throttle: T,
+    done: bool,
+}
+
+impl<I> ThrottledIter<I> {
+    /// Throttles the `inner` iterator within the `budget`.
+    pub fn new(inner: I, budget: IterBudget) -> Self {
+        Self::with_throttle(inner, Throttle::new(budget))
+    }
+
+    /// Stops the iteration once the `token` is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.throttle = self.throttle.with_cancellation(token);
+        self
+    }
+}
+
+impl<I, T> ThrottledIter<I, T>
+where
+    T: BorrowMut<Throttle>,
+{
+    /// Throttles the `inner` iterator by the `throttle`, e.g. the one borrowed
+    /// from the caller to share the budget with other iterators.
+    pub fn with_throttle(inner: I, throttle: T) -> Self {
+        Self {
+            inner,
+            throttle,
+            done: false,
+        }
+    }
+}
+
+impl<I, T, Item> Iterator for ThrottledIter<I, T>
+where
+    I: Iterator<Item = StorageResult<Item>>,
+    T: BorrowMut<Throttle>,
+    Item: ItemSize,
+{
+    type Item = StorageResult<Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None
+        }
+        let Some(item) = self.inner.next() else {
+            self.done = true;
+            return None
+        };
+        let size = item.as_ref().map_or(0, ItemSize::item_size);
+        if let Err(err) = self.throttle.borrow_mut().acquire(size) {
+            self.done = true;
+            return Some(Err(err))
+        }
+        Some(item)
+    }
+}
+
+/// The stream version of the [`ThrottledIter`] for async contexts,
+/// e.g. over the [`crate::database::Database::iter_async`].
+pub fn throttled_stream<S, Item>(
+    stream: S,
+    throttle: Throttle,
+) -> impl Stream<Item = StorageResult<Item>>
+where
+    S: Stream<Item = StorageResult<Item>>,
+    Item: ItemSize,
+{
+    futures::stream::unfold(Some((Box::pin(stream), throttle)), |state| async move {
+        let (mut stream, mut throttle) = state?;
+        let item = stream.next().await?;
+        let size = item.as_ref().map_or(0, ItemSize::item_size);
+        match throttle.acquire_async(size).await {
+            Ok(()) => Some((item, Some((stream, throttle)))),
+            Err(err) => Some((Err(err), None)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::on_chain::OnChain,
+        state::{
+            in_memory::memory_store::MemoryStore,
+            IterDirection,
+        },
+    };
+    use fuel_core_storage::{
+        column::Column,
+        kv_store::KeyValueStore,
+    };
+
+    const ENTRIES: u32 = 2_000;
+
+    fn large_column() -> MemoryStore<OnChain> {
+        let store = MemoryStore::<OnChain>::default();
+        for key in 0..ENTRIES {
+            store
+                .put(&key.to_be_bytes(), Column::Coins, Arc::new(vec![0; 12]))
+                .unwrap();
+        }
+        store
+    }
+
+    fn scan(
+        store: &MemoryStore<OnChain>,
+    ) -> impl Iterator<Item = StorageResult<(Vec<u8>, Value)>> + '_ {
+        store.iter_all(Column::Coins, None, None, IterDirection::Forward)
+    }
+
+    fn is_cancelled<T>(item: &StorageResult<T>) -> bool {
+        matches!(item, Err(err) if format!("{err:?}").contains("Cancelled"))
+    }
+
+    #[test]
+    fn throttled_iter_takes_at_least_the_floor_time_of_items_budget() {
+        // Given
+        let store = large_column();
+        let budget = IterBudget {
+            items_per_sec: Some(5_000),
+            ..Default::default()
+        };
+        let started = Instant::now();
+
+        // When
+        let items = ThrottledIter::new(scan(&store), budget)
+            .collect::<StorageResult<Vec<_>>>()
+            .unwrap();
+
+        // Then
+        assert_eq!(items.len(), ENTRIES as usize);
+        let floor = Duration::from_secs_f64(f64::from(ENTRIES - 1) / 5_000.0);
+        assert!(started.elapsed() >= floor, "{:?}", started.elapsed());
+    }
+
+    #[test]
+    fn throttled_iter_takes_at_least_the_floor_time_of_bytes_budget() {
+        // Given
+        let store = large_column();
+        // Each item is 4 bytes of the key and 12 bytes of the value.
+        let budget = IterBudget {
+            bytes_per_sec: Some(80_000),
+            yield_every: Some(100),
+            ..Default::default()
+        };
+        let started = Instant::now();
+
+        // When
+        let items = ThrottledIter::new(scan(&store), budget).count();
+
+        // Then
+        assert_eq!(items, ENTRIES as usize);
+        let floor = Duration::from_secs_f64(f64::from((ENTRIES - 1) * 16) / 80_000.0);
+        assert!(started.elapsed() >= floor, "{:?}", started.elapsed());
+    }
+
+    #[test]
+    fn shared_throttle_keeps_consecutive_iterators_within_one_budget() {
+        // Given
+        let store = large_column();
+        let mut throttle = Throttle::new(IterBudget {
+            items_per_sec: Some(5_000),
+            ..Default::default()
+        });
+        let started = Instant::now();
+
+        // When
+        for _ in 0..2 {
+            let items = ThrottledIter::with_throttle(scan(&store), &mut throttle)
+                .take(500)
+                .count();
+            assert_eq!(items, 500);
+        }
+
+        // Then
+        let floor = Duration::from_secs_f64(999.0 / 5_000.0);
+        assert!(started.elapsed() >= floor, "{:?}", started.elapsed());
+    }
+
+    #[test]
+    fn throttled_iter_stops_promptly_on_cancellation() {
+        // Given
+        let store = large_column();
+        let token = CancellationToken::new();
+        // The whole scan would take 200 seconds.
+        let budget = IterBudget {
+            items_per_sec: Some(10),
+            ..Default::default()
+        };
+        let canceller = std::thread::spawn({
+            let token = token.clone();
+            move || {
+                std::thread::sleep(Duration::from_millis(250));
+                token.cancel();
+            }
+        });
+        let started = Instant::now();
+
+        // When
+        let items = ThrottledIter::new(scan(&store), budget)
+            .with_cancellation(token)
+            .collect::<Vec<_>>();
+
+        // Then
+        canceller.join().unwrap();
+        assert!(
+            started.elapsed() < Duration::from_secs(2),
+            "{:?}",
+            started.elapsed()
+        );
+        assert!(items.len() < 10, "{}", items.len());
+        let (last, rest) = items.split_last().unwrap();
+        assert!(is_cancelled(last));
+        assert!(rest.iter().all(Result::is_ok));
+    }
+
+    #[test]
+    fn unlimited_budget_doesnt_throttle() {
+        // Given
+        let store = large_column();
+
+        // When
+        let items = ThrottledIter::new(scan(&store), IterBudget::default())
+            .collect::<StorageResult<Vec<_>>>()
+            .unwrap();
+
+        // Then
+        assert_eq!(
+            items,
+            scan(&store).collect::<StorageResult<Vec<_>>>().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn throttled_stream_takes_at_least_the_floor_time_and_stops_on_cancellation() {
+        // Given
+        let store = large_column();
+        let budget = IterBudget {
+            items_per_sec: Some(2_000),
+            yield_every: Some(10),
+            ..Default::default()
+        };
+        let started = Instant::now();
+
+        // When
+        let throttled = throttled_stream(
+            futures::stream::iter(scan(&store).take(500)),
+            Throttle::new(budget),
+        )
+        .count()
+        .await;
+
+        // Then
+        assert_eq!(throttled, 500);
+        let floor = Duration::from_secs_f64(499.0 / 2_000.0);
+        assert!(started.elapsed() >= floor, "{:?}", started.elapsed());
+
+        // Given
+        let token = CancellationToken::new();
+        let slow = IterBudget {
+            items_per_sec: Some(10),
+            ..Default::default()
+        };
+        tokio::spawn({
+            let token = token.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(250)).await;
+                token.cancel();
+            }
+        });
+        let started = Instant::now();
+
+        // When
+        let throttled = throttled_stream(
+            futures::stream::iter(scan(&store)),
+            Throttle::new(slow).with_cancellation(token),
+        )
+        .collect::<Vec<_>>()
+        .await;
+
+        // Then
+        assert!(
+            started.elapsed() < Duration::from_secs(2),
+            "{:?}",
+            started.elapsed()
+        );
+        assert!(is_cancelled(throttled.last().unwrap()));
+    }
+}
@@ -0,0 +1,902 @@
+//! The tiered store keeps the recent entries of tiered columns in the hot store and
+//! moves the old entries into the cold store, e.g. the second RocksDB on the slower disk.
+//! Archive nodes keep the whole history, but rarely read it, so the cold store keeps
+//! the old history out of compactions and caches of the hot store.
+//!
+//! Reads of tiered columns check the hot store first and fall back to the cold store,
+//! while writes always go to the hot store. The [`TieredStore::demote_below`] moves
+//! entries below the height from the hot store into the cold store.
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Error as DatabaseError,
+        Result as DatabaseResult,
+    },
+    state::{
+        column_iter::ColumnIter,
+        notifying::StorageEvent,
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        IterDirection,
+        TransactableStorage,
+        WriteDurability,
+        WritePressure,
+    },
+};
+use core::cmp::Ordering;
+use fuel_core_storage::{
+    codec::height::BlockHeightKey,
+    iter::{
+        BoxedIter,
+        IntoBoxedIter,
+        IteratorableStore,
+    },
+    kv_store::{
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Result as StorageResult,
+};
+use itertools::{
+    EitherOrBoth,
+    Itertools,
+};
+use std::{
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::{
+        Arc,
+        Mutex,
+    },
+};
+use tokio::sync::broadcast;
+
+/// The maximum number of entries moved into the cold store by one batch.
+pub const DEMOTION_BATCH_SIZE: usize = 10_000;
+
+/// The configuration of the cold store.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TieringConfig<Column> {
+    /// The path of the cold store.
+    pub path: PathBuf,
+    /// The columns whose old entries are moved into the cold store.
+    pub columns: Vec<Column>,
+}
+
+#[derive(Debug)]
+pub struct TieredStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    hot: DataSourceInner<Description::Column>,
+    cold: DataSourceInner<Description::Column>,
+    /// The flag for each column whether its old entries are moved into the cold store.
+    tiered: Vec<bool>,
+    /// Serializes writes with batches of the demotion, so the demotion doesn't remove
+    /// the entry rewritten after it was copied into the cold store.
+    write_lock: Mutex<()>,
+}
+
+impl<Description> TieredStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Creates the store that moves old entries of the `columns` from the `hot` into the `cold`.
+    /// Columns with the merge operator can't be tiered, because the merge in the hot store
+    /// doesn't see the value in the cold store.
+    pub fn new<H, C>(
+        columns: &[Description::Column],
+        hot: H,
+        cold: C,
+    ) -> DatabaseResult<Self>
+    where
+        H: Into<DataSource<Description>>,
+        C: Into<DataSource<Description>>,
+    {
+        if let Some(column) = columns
+            .iter()
+            .find(|column| column.merge_operator().is_some())
+        {
+            return Err(anyhow::anyhow!(
+                "The column `{}` with the merge operator can't be tiered",
+                column.name()
+            )
+            .into())
+        }
+        Ok(Self {
+            hot: hot.into().0,
+            cold: cold.into().0,
+            tiered: enum_iterator::all::<Description::Column>()
+                .map(|column| columns.contains(&column))
+                .collect(),
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    /// Opens the hot RocksDB at the `path` and the cold RocksDB at the path of the `config`.
+    #[cfg(feature = "rocksdb")]
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        config: &TieringConfig<Description::Column>,
+        capacity: Option<usize>,
+    ) -> DatabaseResult<Self> {
+        use super::rocks_db::RocksDb;
+        let hot = RocksDb::<Description>::default_open(path, capacity)?;
+        let cold = RocksDb::<Description>::default_open(&config.path, capacity)?;
+        Self::new(&config.columns, Arc::new(hot), Arc::new(cold))
+    }
+
+    fn is_tiered(&self, column: Description::Column) -> bool {
+        self.tiered[column.as_usize()]
+    }
+
+    /// Moves entries of the `columns` below the `height` from the hot store into the cold store.
+    /// Returns the number of moved entries. Keys of the `columns` must be [`BlockHeightKey`]s.
+    ///
+    /// Entries are moved in batches of the [`DEMOTION_BATCH_SIZE`], so the demotion doesn't
+    /// block writes for long. Each batch is written into the cold store before it is removed
+    /// from the hot store, so reads find moved entries at any moment. The interrupted
+    /// demotion leaves entries in both stores, and the next demotion moves them again.
+    pub fn demote_below(
+        &self,
+        height: u32,
+        columns: &[Description::Column],
+    ) -> StorageResult<usize> {
+        if let Some(column) = columns.iter().find(|column| {
+            !self.is_tiered(**column)
+                || Description::key_size(column) != Some(BlockHeightKey::LEN)
+        }) {
+            return Err(anyhow::anyhow!(
+                "The column `{}` is not tiered or not keyed by the height",
+                column.name()
+            )
+            .into())
+        }
+
+        let horizon = BlockHeightKey::from(height);
+        let mut moved = 0usize;
+        for column in columns.iter().copied() {
+            loop {
+                let _guard = self.write_lock.lock().expect("poisoned");
+                let entries = self
+                    .hot
+                    .iter_all(column, None, None, IterDirection::Forward)
+                    .take_while(|item| {
+                        !matches!(item, Ok((key, _)) if key.as_slice() >= horizon.as_ref())
+                    })
+                    .take(DEMOTION_BATCH_SIZE)
+                    .collect::<StorageResult<Vec<_>>>()?;
+                if entries.is_empty() {
+                    break
+                }
+                let is_last_batch = entries.len() < DEMOTION_BATCH_SIZE;
+                moved = moved.saturating_add(entries.len());
+
+                let keys = entries
+                    .iter()
+                    .map(|(key, _)| key.clone())
+                    .collect::<Vec<_>>();
+                self.cold.batch_write(
+                    &mut entries
+                        .into_iter()
+                        .map(|(key, value)| (key, column, WriteOperation::Insert(value))),
+                )?;
+                self.hot.batch_write(
+                    &mut keys
+                        .into_iter()
+                        .map(|key| (key, column, WriteOperation::Remove)),
+                )?;
+                if is_last_batch {
+                    break
+                }
+            }
+        }
+        Ok(moved)
+    }
+}
+
+/// Merges iterators over both stores in the order of the `direction`.
+/// The key existing in both stores is yielded once, with the value of the `hot` store.
+pub(crate) fn merge_tiers<'a, T: 'a>(
+    hot: BoxedIter<'a, StorageResult<T>>,
+    cold: BoxedIter<'a, StorageResult<T>>,
+    direction: IterDirection,
+    key: fn(&T) -> &[u8],
+) -> BoxedIter<'a, StorageResult<T>> {
+    hot.merge_join_by(cold, move |hot, cold| match (hot, cold) {
+        (Ok(hot), Ok(cold)) => {
+            let ordering = key(hot).cmp(key(cold));
+            match direction {
+                IterDirection::Forward => ordering,
+                IterDirection::Reverse => ordering.reverse(),
+            }
+        }
+        // Errors are yielded as soon as they are met.
+        (Err(_), _) => Ordering::Less,
+        (_, Err(_)) => Ordering::Greater,
+    })
+    .map(|item| match item {
+        EitherOrBoth::Left(hot) | EitherOrBoth::Both(hot, _) => hot,
+        EitherOrBoth::Right(cold) => cold,
+    })
+    .into_boxed()
+}
+
+impl<Description> KeyValueStore for TieredStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.hot.put(key, column, value)
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        match self.hot.replace(key, column, value)? {
+            None if self.is_tiered(column) => self.cold.get(key, column),
+            old => Ok(old),
+        }
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.hot.write(key, column, buf)
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        let hot = self.hot.take(key, column)?;
+        if !self.is_tiered(column) {
+            return Ok(hot)
+        }
+        let cold = self.cold.take(key, column)?;
+        Ok(hot.or(cold))
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.hot.delete(key, column)?;
+        if self.is_tiered(column) {
+            self.cold.delete(key, column)?;
+        }
+        Ok(())
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        // Tiered columns don't have the merge operator, so the hot store rejects it.
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.hot.merge(key, column, operand)
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        Ok(self.hot.exists(key, column)?
+            || (self.is_tiered(column) && self.cold.exists(key, column)?))
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        match self.hot.size_of_value(key, column)? {
+            None if self.is_tiered(column) => self.cold.size_of_value(key, column),
+            size => Ok(size),
+        }
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        match self.hot.get(key, column)? {
+            None if self.is_tiered(column) => self.cold.get(key, column),
+            value => Ok(value),
+        }
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        Ok(self.hot.get_with(key, column, f)?
+            || (self.is_tiered(column) && self.cold.get_with(key, column, f)?))
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        let mut values = self.hot.multi_get(keys, column)?;
+        if !self.is_tiered(column) {
+            return Ok(values)
+        }
+        let missing = values
+            .iter()
+            .zip(keys)
+            .filter(|(value, _)| value.is_none())
+            .map(|(_, key)| key.clone())
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(values)
+        }
+        let mut cold = self.cold.multi_get(&missing, column)?.into_iter();
+        for value in values.iter_mut().filter(|value| value.is_none()) {
+            *value = cold.next().flatten();
+        }
+        Ok(values)
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        if !self.is_tiered(column) {
+            return self.hot.compare_and_swap(key, column, expected, new)
+        }
+        // Writes are serialized, so the value can't change between the comparison and the swap.
+        let current = self.get(key, column)?;
+        if current.as_deref().map(Vec::as_slice) != expected {
+            return Ok(Err(current))
+        }
+        self.hot.put(key, column, new)?;
+        Ok(Ok(()))
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        if !self.is_tiered(column) {
+            return self.hot.update(key, column, f)
+        }
+        // Writes are serialized, so the value can't change between the read and the write.
+        let current = self.get(key, column)?;
+        let current = current.as_deref().map(Vec::as_slice);
+        let new = f(current);
+        let outcome = UpdateOutcome::of(current, new.as_deref());
+        match new {
+            _ if !outcome.is_changed() => {}
+            Some(new) => self.hot.put(key, column, Arc::new(new))?,
+            None => {
+                self.hot.delete(key, column)?;
+                self.cold.delete(key, column)?;
+            }
+        }
+        Ok(outcome)
+    }
+
+    fn read(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &mut [u8],
+    ) -> StorageResult<Option<usize>> {
+        match self.hot.read(key, column, buf)? {
+            None if self.is_tiered(column) => self.cold.read(key, column, buf),
+            read => Ok(read),
+        }
+    }
+}
+
+impl<Description> IteratorableStore for TieredStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        let hot = self.hot.iter_all(column, prefix, start, direction);
+        if !self.is_tiered(column) {
+            return hot
+        }
+        let cold = self.cold.iter_all(column, prefix, start, direction);
+        merge_tiers(hot, cold, direction, |(key, _)| key.as_slice())
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        let hot = self.hot.iter_range(column, prefix, start, end, direction);
+        if !self.is_tiered(column) {
+            return hot
+        }
+        let cold = self.cold.iter_range(column, prefix, start, end, direction);
+        merge_tiers(hot, cold, direction, |(key, _)| key.as_slice())
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        let hot = self.hot.iter_keys(column, prefix, start, direction);
+        if !self.is_tiered(column) {
+            return hot
+        }
+        let cold = self.cold.iter_keys(column, prefix, start, direction);
+        merge_tiers(hot, cold, direction, Vec::as_slice)
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        let hot = self.hot.delete_all(column, prefix)?;
+        if !self.is_tiered(column) {
+            return Ok(hot)
+        }
+        let cold = self.cold.delete_all(column, prefix)?;
+        Ok(hot.saturating_add(cold))
+    }
+}
+
+impl<Description> BatchOperations for TieredStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for TieredStore<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_column(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> ColumnIter<'_> {
+        if self.is_tiered(column) {
+            ColumnIter::boxed(self.iter_all(column, prefix, start, direction))
+        } else {
+            self.hot.iter_column(column, prefix, start, direction)
+        }
+    }
+
+    /// Writes the batch into the hot store, and then removes the removed entries
+    /// of tiered columns from the cold store. If the process crashes between
+    /// the writes, the removed entry of the cold store is visible again.
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        let mut cold = vec![];
+        self.hot.batch_write_with(
+            &mut entries.inspect(|(key, column, op)| {
+                if matches!(op, WriteOperation::Remove) && self.is_tiered(*column) {
+                    cold.push((key.clone(), *column, WriteOperation::Remove));
+                }
+            }),
+            durability,
+        )?;
+        if !cold.is_empty() {
+            self.cold
+                .batch_write_with(&mut cold.into_iter(), durability)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.hot.flush()?;
+        self.cold.flush()
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        let hot = self.hot.column_stats(column)?;
+        if !self.is_tiered(column) {
+            return Ok(hot)
+        }
+        Ok(hot.merge(self.cold.column_stats(column)?))
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        // Removals of tiered columns write into both stores.
+        self.hot.write_pressure().max(self.cold.write_pressure())
+    }
+
+    fn checkpoint(&self, _: &Path) -> DatabaseResult<()> {
+        // The checkpoint of the hot store alone would lose the old entries.
+        Err(DatabaseError::Unsupported("checkpoint"))
+    }
+
+    fn backup_to(&self, _: &Path) -> DatabaseResult<()> {
+        Err(DatabaseError::Unsupported("backup"))
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        self.hot.compact(column, range.clone())?;
+        if column.map_or(true, |column| self.is_tiered(column)) {
+            self.cold.compact(column, range)?;
+        }
+        Ok(())
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.hot.clear_column(column)?;
+        if self.is_tiered(column) {
+            self.cold.clear_column(column)?;
+        }
+        Ok(())
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        // Batches of the demotion hold the lock, so snapshots see each entry in one of tiers.
+        let _guard = self.write_lock.lock().expect("poisoned");
+        Ok(Arc::new(Self {
+            hot: self.hot.snapshot()?,
+            cold: self.cold.snapshot()?,
+            tiered: self.tiered.clone(),
+            write_lock: Mutex::new(()),
+        }))
+    }
+
+    // All writes go through the hot store, so its change log, notifications and frozen
+    // columns cover the whole store. The demotion moves entries without changing them.
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        self.hot.subscribe(column, prefix)
+    }
+
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.hot.replay_change_log(from_seq, into)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.hot.truncate_change_log(seq)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        self.hot.freeze_columns(columns)
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        self.hot.unfreeze_columns(columns)
+    }
+
+    fn begin_shutdown(&self) {
+        self.hot.begin_shutdown();
+        self.cold.begin_shutdown()
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        let _guard = self.write_lock.lock().expect("poisoned");
+        self.hot.finish_shutdown()?;
+        self.cold.finish_shutdown()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::{
+            on_chain::OnChain,
+            test::*,
+        },
+        state::{
+            faulty::FaultyStorage,
+            guarded::GuardedStorage,
+            in_memory::memory_store::MemoryStore,
+        },
+    };
+    use fuel_core_storage::column::Column;
+
+    type Stores = (
+        TieredStore<OnChain>,
+        Arc<FaultyStorage<OnChain>>,
+        Arc<MemoryStore<OnChain>>,
+    );
+
+    fn tiered(heights: u32) -> Stores {
+        let hot = Arc::new(FaultyStorage::new(Arc::new(MemoryStore::default())));
+        let cold = Arc::new(MemoryStore::default());
+        let store =
+            TieredStore::new(&[Column::FuelBlocks], hot.clone(), cold.clone()).unwrap();
+        store
+            .batch_write(&mut (0..heights).map(|height| {
+                (
+                    BlockHeightKey::from(height).as_ref().to_vec(),
+                    Column::FuelBlocks,
+                    WriteOperation::Insert(Arc::new(height.to_be_bytes().to_vec())),
+                )
+            }))
+            .unwrap();
+        (store, hot, cold)
+    }
+
+    fn key(height: u32) -> Vec<u8> {
+        BlockHeightKey::from(height).as_ref().to_vec()
+    }
+
+    fn keys(iter: BoxedIter<KVItem>) -> Vec<Vec<u8>> {
+        iter.map(|item| item.unwrap().0).collect()
+    }
+
+    #[test]
+    fn demotion_moves_entries_below_the_height_into_the_cold_store() {
+        // Given
+        let (store, hot, cold) = tiered(10);
+
+        // When
+        let moved = store.demote_below(4, &[Column::FuelBlocks]).unwrap();
+
+        // Then
+        assert_eq!(moved, 4);
+        for height in 0..10 {
+            assert_eq!(
+                hot.exists(&key(height), Column::FuelBlocks).unwrap(),
+                height >= 4
+            );
+            assert_eq!(
+                cold.exists(&key(height), Column::FuelBlocks).unwrap(),
+                height < 4
+            );
+            assert_eq!(
+                store.get(&key(height), Column::FuelBlocks).unwrap(),
+                Some(Arc::new(height.to_be_bytes().to_vec()))
+            );
+        }
+        assert_eq!(store.demote_below(4, &[Column::FuelBlocks]).unwrap(), 0);
+    }
+
+    #[test]
+    fn columns_are_frozen_in_the_hot_store_through_wrappers() {
+        // Given
+        let guarded = Arc::new(GuardedStorage::new(Arc::new(MemoryStore::default())));
+        let hot = Arc::new(FaultyStorage::new(guarded));
+        let store = TieredStore::<OnChain>::new(
+            &[Column::FuelBlocks],
+            hot,
+            Arc::new(MemoryStore::default()),
+        )
+        .unwrap();
+
+        // When
+        store.freeze_columns(&[Column::FuelBlocks]).unwrap();
+        let frozen = store.put(&key(1), Column::FuelBlocks, Arc::new(vec![1]));
+        store.unfreeze_columns(&[Column::FuelBlocks]);
+        let unfrozen = store.put(&key(1), Column::FuelBlocks, Arc::new(vec![1]));
+
+        // Then
+        assert!(format!("{frozen:?}").contains("ColumnFrozen"));
+        assert!(unfrozen.is_ok());
+    }
+
+    #[test]
+    fn iteration_merges_tiers_in_order() {
+        // Given
+        let (store, _, _) = tiered(10);
+        store.demote_below(5, &[Column::FuelBlocks]).unwrap();
+
+        for direction in [IterDirection::Forward, IterDirection::Reverse] {
+            // When
+            let actual = keys(store.iter_all(Column::FuelBlocks, None, None, direction));
+
+            // Then
+            let mut expected = (0..10).map(key).collect::<Vec<_>>();
+            if direction == IterDirection::Reverse {
+                expected.reverse();
+            }
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn reads_see_entries_during_the_interrupted_demotion() {
+        // Given
+        let (store, hot, cold) = tiered(10);
+        store
+            .put(&key(1), Column::FuelBlocks, Arc::new(vec![42]))
+            .unwrap();
+
+        // When
+        hot.fail_batch_write();
+        assert!(store.demote_below(5, &[Column::FuelBlocks]).is_err());
+        hot.reset();
+
+        // Then
+        assert!(cold.exists(&key(1), Column::FuelBlocks).unwrap());
+        assert!(hot.exists(&key(1), Column::FuelBlocks).unwrap());
+        assert_eq!(
+            store.get(&key(1), Column::FuelBlocks).unwrap(),
+            Some(Arc::new(vec![42]))
+        );
+        let entries = store
+            .iter_all(Column::FuelBlocks, None, None, IterDirection::Forward)
+            .map(|item| item.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|(key, _)| key.clone())
+                .collect::<Vec<_>>(),
+            (0..10).map(key).collect::<Vec<_>>()
+        );
+        assert_eq!(entries[1].1, Arc::new(vec![42]));
+        assert_eq!(store.demote_below(5, &[Column::FuelBlocks]).unwrap(), 5);
+        assert!(!hot.exists(&key(1), Column::FuelBlocks).unwrap());
+    }
+
+    #[test]
+    fn reads_see_all_entries_while_demotion_runs() {
+        // Given
+        let heights = u32::try_from(3 * DEMOTION_BATCH_SIZE).unwrap();
+        let (store, _, _) = tiered(heights);
+        let samples = [0, 1, heights / 2, heights - 1].map(key);
+
+        // When
+        std::thread::scope(|scope| {
+            let demotion =
+                scope.spawn(|| store.demote_below(heights, &[Column::FuelBlocks]));
+            while !demotion.is_finished() {
+                // Then
+                for key in samples.iter() {
+                    assert!(store.exists(key, Column::FuelBlocks).unwrap());
+                }
+            }
+            assert_eq!(demotion.join().unwrap().unwrap(), heights as usize);
+        });
+        assert_eq!(
+            store
+                .iter_keys(Column::FuelBlocks, None, None, IterDirection::Forward)
+                .count(),
+            heights as usize
+        );
+    }
+
+    #[test]
+    fn removals_remove_entries_from_both_tiers() {
+        // Given
+        let (store, _, cold) = tiered(4);
+        store.demote_below(4, &[Column::FuelBlocks]).unwrap();
+
+        // When
+        store.delete(&key(0), Column::FuelBlocks).unwrap();
+        store
+            .batch_write(
+                &mut [(key(1), Column::FuelBlocks, WriteOperation::Remove)].into_iter(),
+            )
+            .unwrap();
+        let taken = store.take(&key(2), Column::FuelBlocks).unwrap();
+
+        // Then
+        assert_eq!(taken, Some(Arc::new(2u32.to_be_bytes().to_vec())));
+        assert_eq!(
+            keys(store.iter_all(Column::FuelBlocks, None, None, IterDirection::Forward)),
+            vec![key(3)]
+        );
+        assert_eq!(
+            keys(
+                cold.iter_all(Column::FuelBlocks, None, None, IterDirection::Forward)
+                    .into_boxed()
+            ),
+            vec![key(3)]
+        );
+    }
+
+    #[test]
+    fn demotion_rejects_columns_not_keyed_by_height() {
+        // Given
+        let store = TieredStore::<OnChain>::new(
+            &[Column::Coins],
+            Arc::new(MemoryStore::default()),
+            Arc::new(MemoryStore::default()),
+        )
+        .unwrap();
+
+        // Then
+        assert!(store.demote_below(1, &[Column::Coins]).is_err());
+        assert!(store.demote_below(1, &[Column::FuelBlocks]).is_err());
+        assert!(TieredStore::<MergeDatabase>::new(
+            &[MergeColumn::Counters],
+            Arc::new(MemoryStore::default()),
+            Arc::new(MemoryStore::default()),
+        )
+        .is_err());
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn tiered_rocksdb_keeps_demoted_entries_after_reopen() {
+        // Given
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let config = TieringConfig {
+            path: tmp_dir.path().join("cold"),
+            columns: vec![Column::FuelBlocks],
+        };
+        let store =
+            TieredStore::<OnChain>::open(tmp_dir.path().join("hot"), &config, None)
+                .unwrap();
+        store
+            .put(&key(1), Column::FuelBlocks, Arc::new(vec![1]))
+            .unwrap();
+        store.demote_below(2, &[Column::FuelBlocks]).unwrap();
+        drop(store);
+
+        // When
+        let store =
+            TieredStore::<OnChain>::open(tmp_dir.path().join("hot"), &config, None)
+                .unwrap();
+
+        // Then
+        assert_eq!(
+            store.get(&key(1), Column::FuelBlocks).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+    }
+}
@@ -0,0 +1,898 @@
+//! The expiration of entries of columns with the TTL, see [`ColumnConfig::ttl`].
+//!
+//! The [`TtlStorage`] appends the time of the write to values of columns with the TTL
+//! and hides expired entries from reads, so all backends return `None` for the expired
+//! entry even if it is still stored. Expired entries are removed by the compaction filter
+//! of the RocksDB or by [`TtlStorage::purge_expired`] for other backends.
+//!
+//! [`ColumnConfig::ttl`]: crate::state::column_config::ColumnConfig::ttl
+
+use crate::{
+    database::{
+        database_description::DatabaseDescription,
+        Result as DatabaseResult,
+    },
+    state::{
+        column_config::ColumnsConfig,
+        column_iter::ColumnIter,
+        notifying::StorageEvent,
+        update_encoded,
+        BatchOperations,
+        ColumnStats,
+        DataSource,
+        DataSourceInner,
+        EngineStats,
+        IterDirection,
+        TransactableStorage,
+        WriteDurability,
+        WritePressure,
+    },
+};
+use fuel_core_storage::{
+    iter::{
+        BoxedIter,
+        IntoBoxedIter,
+        IterConfig,
+        IteratorableStore,
+        PageIter,
+    },
+    kv_store::{
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+        StorageColumn,
+        UpdateFn,
+        UpdateOutcome,
+        Value,
+        WriteOperation,
+    },
+    Error as StorageError,
+    Result as StorageResult,
+};
+use std::{
+    path::Path,
+    sync::{
+        Arc,
+        RwLock,
+    },
+    time::{
+        Duration,
+        SystemTime,
+    },
+};
+use tokio::sync::broadcast;
+
+/// The size of the time of the write appended to values of columns with the TTL.
+const TIMESTAMP_SIZE: usize = 8;
+
+/// The maximum number of expired entries removed by one batch of the purge.
+const PURGE_BATCH_SIZE: usize = 10_000;
+
+/// Returns the current time in milliseconds since the Unix epoch.
+pub(crate) fn now() -> u64 {
+    let since_epoch = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    u64::try_from(since_epoch.as_millis()).unwrap_or(u64::MAX)
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Splits the stored `value` into the value and the time of the write.
+fn split(value: &[u8]) -> Option<(&[u8], u64)> {
+    let position = value.len().checked_sub(TIMESTAMP_SIZE)?;
+    let (value, timestamp) = value.split_at(position);
+    let timestamp = timestamp.try_into().ok().map(u64::from_be_bytes)?;
+    Some((value, timestamp))
+}
+
+/// Returns `true` if the stored `value` was written more than `ttl` ago.
+/// Values without the time of the write never expire.
+pub(crate) fn is_expired(value: &[u8], ttl: Duration, now: u64) -> bool {
+    split(value).is_some_and(|(_, written)| written.saturating_add(millis(ttl)) <= now)
+}
+
+fn tag(value: &[u8], now: u64) -> Value {
+    let mut tagged = Vec::with_capacity(value.len().saturating_add(TIMESTAMP_SIZE));
+    tagged.extend_from_slice(value);
+    tagged.extend_from_slice(&now.to_be_bytes());
+    Arc::new(tagged)
+}
+
+/// The storage that expires entries of columns with the TTL. Other columns
+/// are passed to the inner storage as is.
+#[derive(Debug)]
+pub struct TtlStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    inner: DataSourceInner<Description::Column>,
+    /// The TTL of each column by its index.
+    ttls: Arc<Vec<Option<Duration>>>,
+    /// Writes into columns with the TTL hold the shared lock, while the purge holds
+    /// the exclusive lock, so it doesn't remove the entry rewritten after the check.
+    purge_lock: RwLock<()>,
+}
+
+impl<Description> TtlStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    /// Wraps the `inner` storage with the TTL of columns from the `config`.
+    /// Columns with the merge operator can't have the TTL.
+    pub fn new<D>(inner: D, config: &ColumnsConfig) -> DatabaseResult<Self>
+    where
+        D: Into<DataSource<Description>>,
+    {
+        let mut ttls = vec![None; enum_iterator::all::<Description::Column>().count()];
+        for (column, ttl) in config.ttl_columns::<Description>() {
+            if column.merge_operator().is_some() {
+                return Err(anyhow::anyhow!(
+                    "The column `{}` with the merge operator can't have the TTL",
+                    column.name()
+                )
+                .into())
+            }
+            ttls[column.as_usize()] = Some(ttl);
+        }
+        Ok(Self {
+            inner: inner.into().0,
+            ttls: Arc::new(ttls),
+            purge_lock: RwLock::new(()),
+        })
+    }
+
+    /// Removes expired entries of all columns with the TTL.
+    /// Returns the number of removed entries.
+    pub fn purge_expired(&self) -> StorageResult<usize> {
+        let mut removed = 0usize;
+        for column in enum_iterator::all::<Description::Column>() {
+            let Some(ttl) = self.ttl(column) else {
+                continue
+            };
+            loop {
+                let _guard = self.purge_lock.write().expect("poisoned");
+                let now = now();
+                let expired = self
+                    .inner
+                    .iter_all(column, None, None, IterDirection::Forward)
+                    .filter(|item| {
+                        !matches!(item, Ok((_, value)) if !is_expired(value, ttl, now))
+                    })
+                    .take(PURGE_BATCH_SIZE)
+                    .map(|item| item.map(|(key, _)| (key, column, WriteOperation::Remove)))
+                    .collect::<StorageResult<Vec<_>>>()?;
+                let is_last_batch = expired.len() < PURGE_BATCH_SIZE;
+                removed = removed.saturating_add(expired.len());
+                self.inner.batch_write(&mut expired.into_iter())?;
+                if is_last_batch {
+                    break
+                }
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Spawns the thread that calls [`Self::purge_expired`] every `interval`.
+    /// The thread stops after the `storage` is dropped.
+    pub fn spawn_purge(storage: &Arc<Self>, interval: Duration) {
+        let storage = Arc::downgrade(storage);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            let Some(storage) = storage.upgrade() else {
+                break
+            };
+            if let Err(err) = storage.purge_expired() {
+                tracing::warn!(
+                    "Unable to purge expired entries of the `{}` database: {:?}",
+                    Description::name(),
+                    err
+                );
+            }
+        });
+    }
+
+    fn ttl(&self, column: Description::Column) -> Option<Duration> {
+        self.ttls.get(column.as_usize()).copied().flatten()
+    }
+
+    /// Strips the time of the write from the stored `value`, or returns `None`
+    /// if the value is expired.
+    fn untag(
+        column: Description::Column,
+        value: Value,
+        ttl: Duration,
+        now: u64,
+    ) -> StorageResult<Option<Value>> {
+        let Some((_, written)) = split(&value) else {
+            return Err(StorageError::Other(anyhow::anyhow!(
+                "The value of the column `{}` doesn't have the time of the write",
+                column.name()
+            )))
+        };
+        if written.saturating_add(millis(ttl)) <= now {
+            return Ok(None)
+        }
+        let mut value = Arc::try_unwrap(value).unwrap_or_else(|value| (*value).clone());
+        value.truncate(value.len().saturating_sub(TIMESTAMP_SIZE));
+        Ok(Some(Arc::new(value)))
+    }
+
+    /// Returns the stored `value` of the `column`, or `None` if it is expired.
+    fn read_value(
+        &self,
+        column: Description::Column,
+        value: Option<Value>,
+    ) -> StorageResult<Option<Value>> {
+        match (value, self.ttl(column)) {
+            (Some(value), Some(ttl)) => Self::untag(column, value, ttl, now()),
+            (value, _) => Ok(value),
+        }
+    }
+
+    /// Hides expired entries of the `iter` over the `column` with the `ttl`.
+    fn live_entries(
+        column: Description::Column,
+        ttl: Duration,
+        iter: BoxedIter<KVItem>,
+    ) -> BoxedIter<KVItem> {
+        let now = now();
+        iter.filter_map(move |item| match item {
+            Ok((key, value)) => Self::untag(column, value, ttl, now)
+                .map(|value| value.map(|value| (key, value)))
+                .transpose(),
+            Err(err) => Some(Err(err)),
+        })
+        .into_boxed()
+    }
+}
+
+impl<Description> KeyValueStore for TtlStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    type Column = Description::Column;
+
+    fn put(&self, key: &[u8], column: Self::Column, value: Value) -> StorageResult<()> {
+        match self.ttl(column) {
+            Some(_) => {
+                let _guard = self.purge_lock.read().expect("poisoned");
+                self.inner.put(key, column, tag(&value, now()))
+            }
+            None => self.inner.put(key, column, value),
+        }
+    }
+
+    fn replace(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        value: Value,
+    ) -> StorageResult<Option<Value>> {
+        match self.ttl(column) {
+            Some(_) => {
+                let _guard = self.purge_lock.read().expect("poisoned");
+                let old = self.inner.replace(key, column, tag(&value, now()))?;
+                self.read_value(column, old)
+            }
+            None => self.inner.replace(key, column, value),
+        }
+    }
+
+    fn write(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        buf: &[u8],
+    ) -> StorageResult<usize> {
+        match self.ttl(column) {
+            Some(_) => {
+                let _guard = self.purge_lock.read().expect("poisoned");
+                self.inner.put(key, column, tag(buf, now()))?;
+                Ok(buf.len())
+            }
+            None => self.inner.write(key, column, buf),
+        }
+    }
+
+    fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        let value = self.inner.take(key, column)?;
+        self.read_value(column, value)
+    }
+
+    fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+        self.inner.delete(key, column)
+    }
+
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        // Columns with the TTL don't have the merge operator, so the inner storage rejects it.
+        self.inner.merge(key, column, operand)
+    }
+
+    fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
+        match self.ttl(column) {
+            Some(_) => Ok(self.get(key, column)?.is_some()),
+            None => self.inner.exists(key, column),
+        }
+    }
+
+    fn size_of_value(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+    ) -> StorageResult<Option<usize>> {
+        match self.ttl(column) {
+            Some(_) => Ok(self.get(key, column)?.map(|value| value.len())),
+            None => self.inner.size_of_value(key, column),
+        }
+    }
+
+    fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+        let value = self.inner.get(key, column)?;
+        self.read_value(column, value)
+    }
+
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        match self.ttl(column) {
+            Some(_) => Ok(self.get(key, column)?.map(|value| f(&value)).is_some()),
+            None => self.inner.get_with(key, column, f),
+        }
+    }
+
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        let values = self.inner.multi_get(keys, column)?;
+        match self.ttl(column) {
+            Some(ttl) => {
+                let now = now();
+                values
+                    .into_iter()
+                    .map(|value| {
+                        value
+                            .map(|value| Self::untag(column, value, ttl, now))
+                            .transpose()
+                            .map(Option::flatten)
+                    })
+                    .collect()
+            }
+            None => Ok(values),
+        }
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        if self.ttl(column).is_none() {
+            return self.inner.compare_and_swap(key, column, expected, new)
+        }
+        let _guard = self.purge_lock.read().expect("poisoned");
+        // The swap compares stored values, so the expected value is replaced
+        // by the stored one that it matches.
+        let stored = self.inner.get(key, column)?;
+        let current = self.read_value(column, stored.clone())?;
+        if current.as_deref().map(Vec::as_slice) != expected {
+            return Ok(Err(current))
+        }
+        let stored = current.and(stored);
+        match self.inner.compare_and_swap(
+            key,
+            column,
+            stored.as_deref().map(Vec::as_slice),
+            tag(&new, now()),
+        )? {
+            Ok(()) => Ok(Ok(())),
+            Err(stored) => Ok(Err(self.read_value(column, stored)?)),
+        }
+    }
+
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        let Some(ttl) = self.ttl(column) else {
+            return self.inner.update(key, column, f)
+        };
+        let _guard = self.purge_lock.read().expect("poisoned");
+        let now = now();
+        // The expired value is absent for the `f`, and the new value gets a new time.
+        update_encoded(
+            self.inner.as_ref(),
+            key,
+            column,
+            f,
+            &|stored| {
+                let value = Self::untag(column, Arc::new(stored.to_vec()), ttl, now)?;
+                Ok(value.map(|value| value.to_vec()))
+            },
+            &|value| Ok(tag(&value, now).to_vec()),
+        )
+    }
+}
+
+impl<Description> IteratorableStore for TtlStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn iter_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        let iter = self.inner.iter_all(column, prefix, start, direction);
+        match self.ttl(column) {
+            Some(ttl) => Self::live_entries(column, ttl, iter),
+            None => iter,
+        }
+    }
+
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KVItem> {
+        let iter = self.inner.iter_range(column, prefix, start, end, direction);
+        match self.ttl(column) {
+            Some(ttl) => Self::live_entries(column, ttl, iter),
+            None => iter,
+        }
+    }
+
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<KeyItem> {
+        match self.ttl(column) {
+            Some(_) => self
+                .iter_all(column, prefix, start, direction)
+                .map(|item| item.map(|(key, _)| key))
+                .into_boxed(),
+            None => self.inner.iter_keys(column, prefix, start, direction),
+        }
+    }
+
+    fn has_any(&self, column: Self::Column, prefix: &[u8]) -> StorageResult<bool> {
+        match self.ttl(column) {
+            // Expired entries are hidden by the iteration.
+            Some(_) => {
+                let prefix = Some(prefix).filter(|prefix| !prefix.is_empty());
+                Ok(self
+                    .iter_keys(column, prefix, None, IterDirection::Forward)
+                    .next()
+                    .transpose()?
+                    .is_some())
+            }
+            None => self.inner.has_any(column, prefix),
+        }
+    }
+
+    fn iter(&self, column: Self::Column, config: IterConfig) -> PageIter {
+        let Some(ttl) = self.ttl(column) else {
+            return self.inner.iter(column, config)
+        };
+        // The limit applies to entries that are not expired.
+        let limit = config.limit;
+        let iter = self
+            .inner
+            .iter(
+                column,
+                IterConfig {
+                    limit: None,
+                    ..config
+                },
+            )
+            .into_boxed();
+        let iter = Self::live_entries(column, ttl, iter);
+        match limit {
+            Some(limit) => PageIter::new(iter.take(limit).into_boxed()),
+            None => PageIter::new(iter),
+        }
+    }
+
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        self.inner.delete_all(column, prefix)
+    }
+}
+
+impl<Description> BatchOperations for TtlStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<()> {
+        self.batch_write_with(entries, WriteDurability::Buffered)
+    }
+}
+
+impl<Description> TransactableStorage for TtlStorage<Description>
+where
+    Description: DatabaseDescription,
+{
+    fn batch_write_with(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+        durability: WriteDurability,
+    ) -> StorageResult<()> {
+        let _guard = self.purge_lock.read().expect("poisoned");
+        let now = now();
+        self.inner.batch_write_with(
+            &mut entries.map(|(key, column, op)| match (op, self.ttl(column)) {
+                (WriteOperation::Insert(value), Some(_)) => {
+                    (key, column, WriteOperation::Insert(tag(&value, now)))
+                }
+                (op, _) => (key, column, op),
+            }),
+            durability,
+        )
+    }
+
+    fn iter_column(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> ColumnIter<'_> {
+        match self.ttl(column) {
+            Some(_) => ColumnIter::boxed(self.iter_all(column, prefix, start, direction)),
+            None => self.inner.iter_column(column, prefix, start, direction),
+        }
+    }
+
+    fn flush(&self) -> DatabaseResult<()> {
+        self.inner.flush()
+    }
+
+    fn column_stats(&self, column: Self::Column) -> DatabaseResult<ColumnStats> {
+        self.inner.column_stats(column)
+    }
+
+    fn engine_stats(&self) -> DatabaseResult<EngineStats> {
+        self.inner.engine_stats()
+    }
+
+    fn write_pressure(&self) -> WritePressure {
+        self.inner.write_pressure()
+    }
+
+    fn size_of_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: Option<&[u8]>,
+    ) -> DatabaseResult<u64> {
+        self.inner.size_of_range(column, start, end)
+    }
+
+    fn checkpoint(&self, path: &Path) -> DatabaseResult<()> {
+        self.inner.checkpoint(path)
+    }
+
+    fn backup_to(&self, backup_dir: &Path) -> DatabaseResult<()> {
+        self.inner.backup_to(backup_dir)
+    }
+
+    fn compact(
+        &self,
+        column: Option<Self::Column>,
+        range: Option<(Vec<u8>, Vec<u8>)>,
+    ) -> DatabaseResult<()> {
+        self.inner.compact(column, range)
+    }
+
+    fn clear_column(&self, column: Self::Column) -> DatabaseResult<()> {
+        self.inner.clear_column(column)
+    }
+
+    fn multi_delete(
+        &self,
+        column: Self::Column,
+        keys: &[Vec<u8>],
+    ) -> DatabaseResult<usize> {
+        self.inner.multi_delete(column, keys)
+    }
+
+    fn delete_range(
+        &self,
+        column: Self::Column,
+        start: &[u8],
+        end: &[u8],
+    ) -> DatabaseResult<()> {
+        self.inner.delete_range(column, start, end)
+    }
+
+    fn snapshot(&self) -> DatabaseResult<DataSourceInner<Self::Column>> {
+        // The snapshot stores tagged values, so it is wrapped too.
+        Ok(Arc::new(Self {
+            inner: self.inner.snapshot()?,
+            ttls: self.ttls.clone(),
+            purge_lock: RwLock::new(()),
+        }))
+    }
+
+    fn subscribe(
+        &self,
+        column: Self::Column,
+        prefix: Vec<u8>,
+    ) -> DatabaseResult<broadcast::Receiver<StorageEvent<Self::Column>>> {
+        self.inner.subscribe(column, prefix)
+    }
+
+    fn replay_change_log(
+        &self,
+        from_seq: u64,
+        into: &dyn BatchOperations<Column = Self::Column>,
+    ) -> DatabaseResult<u64> {
+        self.inner.replay_change_log(from_seq, into)
+    }
+
+    fn truncate_change_log(&self, seq: u64) -> DatabaseResult<()> {
+        self.inner.truncate_change_log(seq)
+    }
+
+    fn freeze_columns(&self, columns: &[Self::Column]) -> DatabaseResult<()> {
+        self.inner.freeze_columns(columns)
+    }
+
+    fn unfreeze_columns(&self, columns: &[Self::Column]) {
+        self.inner.unfreeze_columns(columns)
+    }
+
+    fn begin_shutdown(&self) {
+        self.inner.begin_shutdown()
+    }
+
+    fn finish_shutdown(&self) -> DatabaseResult<()> {
+        self.inner.finish_shutdown()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::database_description::{
+            on_chain::OnChain,
+            test::MergeDatabase,
+        },
+        state::{
+            column_config::ColumnConfig,
+            in_memory::memory_store::MemoryStore,
+            test_helpers::over_memory_store,
+        },
+    };
+    use fuel_core_storage::column::Column;
+
+    const TTL: Duration = Duration::from_millis(50);
+
+    fn ttl_config() -> ColumnsConfig {
+        ColumnsConfig::default().with_column(
+            OnChain::name(),
+            Column::Coins.name(),
+            ColumnConfig {
+                ttl: Some(TTL),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn ttl_store() -> (Arc<MemoryStore<OnChain>>, TtlStorage<OnChain>) {
+        over_memory_store(|inner| TtlStorage::new(inner, &ttl_config()).unwrap())
+    }
+
+    fn expire() {
+        std::thread::sleep(TTL.saturating_mul(2));
+    }
+
+    #[test]
+    fn expired_entries_are_hidden_from_reads() {
+        // Given
+        let (inner, store) = ttl_store();
+        let value = Arc::new(vec![1, 2, 3]);
+        store.put(&[1], Column::Coins, value.clone()).unwrap();
+        store.put(&[1], Column::Metadata, value.clone()).unwrap();
+        assert_eq!(store.get(&[1], Column::Coins).unwrap(), Some(value.clone()));
+
+        // When
+        expire();
+
+        // Then
+        assert_eq!(store.get(&[1], Column::Coins).unwrap(), None);
+        assert!(!store.exists(&[1], Column::Coins).unwrap());
+        assert_eq!(store.size_of_value(&[1], Column::Coins).unwrap(), None);
+        assert_eq!(
+            store.multi_get(&[vec![1]], Column::Coins).unwrap(),
+            vec![None]
+        );
+        assert_eq!(
+            store
+                .iter_all(Column::Coins, None, None, IterDirection::Forward)
+                .count(),
+            0
+        );
+        assert_eq!(store.iter(Column::Coins, IterConfig::default()).count(), 0);
+        assert_eq!(store.get(&[1], Column::Metadata).unwrap(), Some(value));
+        assert!(inner.exists(&[1], Column::Coins).unwrap());
+    }
+
+    #[test]
+    fn only_values_of_columns_with_ttl_are_tagged() {
+        // Given
+        let (inner, store) = ttl_store();
+        let value = Arc::new(vec![1, 2, 3]);
+
+        // When
+        store.put(&[1], Column::Coins, value.clone()).unwrap();
+        store
+            .batch_write(
+                &mut [(
+                    vec![2],
+                    Column::Metadata,
+                    WriteOperation::Insert(value.clone()),
+                )]
+                .into_iter(),
+            )
+            .unwrap();
+
+        // Then
+        assert_eq!(
+            inner.size_of_value(&[1], Column::Coins).unwrap(),
+            Some(value.len() + TIMESTAMP_SIZE)
+        );
+        assert_eq!(
+            inner.get(&[2], Column::Metadata).unwrap(),
+            Some(value.clone())
+        );
+        let snapshot = store.snapshot().unwrap();
+        assert_eq!(snapshot.get(&[1], Column::Coins).unwrap(), Some(value));
+    }
+
+    #[test]
+    fn purge_removes_only_expired_entries() {
+        // Given
+        let (inner, store) = ttl_store();
+        store.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+        expire();
+        store.put(&[2], Column::Coins, Arc::new(vec![2])).unwrap();
+
+        // When
+        let removed = store.purge_expired().unwrap();
+
+        // Then
+        assert_eq!(removed, 1);
+        assert!(!inner.exists(&[1], Column::Coins).unwrap());
+        assert!(inner.exists(&[2], Column::Coins).unwrap());
+    }
+
+    #[test]
+    fn purge_thread_removes_expired_entries() {
+        // Given
+        let (inner, store) = ttl_store();
+        store.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+        let store = Arc::new(store);
+
+        // When
+        TtlStorage::spawn_purge(&store, Duration::from_millis(10));
+
+        // Then
+        let purged = (0..200).any(|_| {
+            std::thread::sleep(Duration::from_millis(10));
+            !inner.exists(&[1], Column::Coins).unwrap()
+        });
+        assert!(purged);
+    }
+
+    #[test]
+    fn compare_and_swap_compares_values_without_the_tag() {
+        // Given
+        let (_, store) = ttl_store();
+        store.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+
+        // When
+        let swapped = store
+            .compare_and_swap(&[1], Column::Coins, Some(&[1]), Arc::new(vec![2]))
+            .unwrap();
+        let rejected = store
+            .compare_and_swap(&[1], Column::Coins, Some(&[1]), Arc::new(vec![3]))
+            .unwrap();
+
+        // Then
+        assert_eq!(swapped, Ok(()));
+        assert_eq!(rejected, Err(Some(Arc::new(vec![2]))));
+    }
+
+    #[test]
+    fn columns_with_merge_operator_cant_have_ttl() {
+        // Given
+        let config = ColumnsConfig::default().with_column(
+            MergeDatabase::name(),
+            "Counters",
+            ColumnConfig {
+                ttl: Some(TTL),
+                ..Default::default()
+            },
+        );
+
+        // When
+        let result = TtlStorage::<MergeDatabase>::new(
+            Arc::new(MemoryStore::<MergeDatabase>::default()),
+            &config,
+        );
+
+        // Then
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn compaction_removes_expired_entries_of_rocksdb() {
+        use crate::database::{
+            temp_database::TempDatabase,
+            Database,
+        };
+
+        // Given
+        let mut database = TempDatabase::<OnChain>::open_with(|path| {
+            Database::open_with_columns(path, None, &ttl_config())
+        })
+        .unwrap();
+        let storage = database.data_source();
+        storage.put(&[1], Column::Coins, Arc::new(vec![1])).unwrap();
+        storage
+            .put(&[1], Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+        expire();
+
+        // When
+        storage.compact(Some(Column::Coins), None).unwrap();
+        database
+            .reopen_with(|path| Database::open(path, None))
+            .unwrap();
+
+        // Then
+        let db = database.data_source();
+        assert!(!db.exists(&[1], Column::Coins).unwrap());
+        assert_eq!(
+            db.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1]))
+        );
+    }
+}
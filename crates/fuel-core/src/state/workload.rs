@@ -0,0 +1,292 @@
+//! Deterministic synthetic workloads of the storage, shared by benchmarks and tests.
+//!
+//! Each workload is generated from the seed, so runs against different backends
+//! and different builds operate on identical data and are comparable. The shapes
+//! mimic the on-chain columns: small keys and values of coins, the contract state
+//! with `MultiKey` keys and hot slots, large values of transactions, and block
+//! commits that mix them.
+
+use crate::state::in_memory::transaction::WriteEntry;
+use fuel_core_storage::{
+    column::Column,
+    kv_store::{
+        Value,
+        WriteOperation,
+    },
+};
+use rand::{
+    rngs::StdRng,
+    Rng,
+    SeedableRng,
+};
+use std::sync::Arc;
+
+/// The size of ids, e.g. of transactions and contracts.
+const ID_SIZE: usize = 32;
+/// The size of values of the contract state.
+pub const STATE_VALUE_SIZE: usize = 32;
+/// The percent of slots of the contract state that are hot.
+pub const HOT_SLOTS_PERCENT: usize = 10;
+/// The percent of accesses to the contract state that hit hot slots.
+pub const HOT_ACCESSES_PERCENT: u32 = 90;
+
+/// The entries of the contract state generated by [`WorkloadGenerator::contract_state`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractState {
+    /// Ids of contracts, they are prefixes of keys of entries.
+    pub contracts: Vec<[u8; ID_SIZE]>,
+    /// Entries of all slots of all contracts.
+    pub entries: Vec<(Vec<u8>, Value)>,
+}
+
+impl ContractState {
+    /// Returns keys of hot slots, the first [`HOT_SLOTS_PERCENT`] of entries.
+    pub fn hot_keys(&self) -> impl Iterator<Item = &[u8]> {
+        let hot = (self.entries.len().saturating_mul(HOT_SLOTS_PERCENT) / 100).max(1);
+        self.entries.iter().take(hot).map(|(key, _)| key.as_slice())
+    }
+}
+
+/// The shape of the block generated by [`WorkloadGenerator::block`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockShape {
+    /// The number of created coins.
+    pub new_coins: usize,
+    /// The number of spent coins.
+    pub spent_coins: usize,
+    /// The number of updates of the contract state.
+    pub state_updates: usize,
+    /// The number of appended transactions.
+    pub transactions: usize,
+    /// The size of each transaction in bytes.
+    pub transaction_size: usize,
+}
+
+impl Default for BlockShape {
+    fn default() -> Self {
+        Self {
+            new_coins: 200,
+            spent_coins: 100,
+            state_updates: 500,
+            transactions: 100,
+            transaction_size: 1024,
+        }
+    }
+}
+
+/// The generator of workloads. Generators with the same seed produce the same
+/// sequence of workloads.
+#[derive(Debug, Clone)]
+pub struct WorkloadGenerator {
+    rng: StdRng,
+}
+
+impl WorkloadGenerator {
+    /// Creates the generator from the `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    fn bytes(&mut self, size: usize) -> Vec<u8> {
+        let mut bytes = vec![0; size];
+        self.rng.fill(bytes.as_mut_slice());
+        bytes
+    }
+
+    fn id(&mut self) -> [u8; ID_SIZE] {
+        self.rng.gen()
+    }
+
+    /// Returns `count` coins of the [`Column::Coins`]: the key is the transaction id
+    /// followed by the index of the output, and the value is tens of bytes.
+    pub fn coins(&mut self, count: usize) -> Vec<(Vec<u8>, Value)> {
+        (0..count)
+            .map(|_| {
+                let mut key = self.id().to_vec();
+                key.push(self.rng.gen());
+                let size = self.rng.gen_range(64..=96);
+                (key, Arc::new(self.bytes(size)))
+            })
+            .collect()
+    }
+
+    /// Returns `slots` entries of the [`Column::ContractsState`] for each of `contracts`
+    /// contracts: the key is the contract id followed by the slot key, and the value
+    /// is [`STATE_VALUE_SIZE`] bytes.
+    pub fn contract_state(&mut self, contracts: usize, slots: usize) -> ContractState {
+        let contracts = (0..contracts).map(|_| self.id()).collect::<Vec<_>>();
+        let mut entries = Vec::with_capacity(contracts.len().saturating_mul(slots));
+        for contract in &contracts {
+            for _ in 0..slots {
+                let key = [contract.as_slice(), &self.id()].concat();
+                entries.push((key, Arc::new(self.bytes(STATE_VALUE_SIZE))));
+            }
+        }
+        ContractState { contracts, entries }
+    }
+
+    /// Returns `count` keys of the `state` with the skewed distribution:
+    /// [`HOT_ACCESSES_PERCENT`] of keys are [`ContractState::hot_keys`].
+    pub fn skewed_keys(&mut self, state: &ContractState, count: usize) -> Vec<Vec<u8>> {
+        if state.entries.is_empty() {
+            return vec![]
+        }
+        let hot = state.hot_keys().count();
+        (0..count)
+            .map(|_| {
+                let index = if self.rng.gen_range(0..100) < HOT_ACCESSES_PERCENT {
+                    self.rng.gen_range(0..hot)
+                } else {
+                    self.rng.gen_range(0..state.entries.len())
+                };
+                state.entries[index].0.clone()
+            })
+            .collect()
+    }
+
+    /// Returns `count` transactions of the [`Column::Transactions`] with `size` bytes
+    /// each, keyed by their ids. They model large values appended by each block.
+    pub fn transactions(&mut self, count: usize, size: usize) -> Vec<(Vec<u8>, Value)> {
+        (0..count)
+            .map(|_| (self.id().to_vec(), Arc::new(self.bytes(size))))
+            .collect()
+    }
+
+    /// Returns changes of the block with the `shape`. The block spends coins from
+    /// the end of `unspent` and pushes keys of created coins to it, updates slots
+    /// of the `state` with [`Self::skewed_keys`], and appends transactions.
+    pub fn block(
+        &mut self,
+        unspent: &mut Vec<Vec<u8>>,
+        state: &ContractState,
+        shape: BlockShape,
+    ) -> Vec<WriteEntry<Column>> {
+        let mut changes = vec![];
+        for _ in 0..shape.spent_coins {
+            let Some(key) = unspent.pop() else { break };
+            changes.push((key, Column::Coins, WriteOperation::Remove));
+        }
+        for (key, value) in self.coins(shape.new_coins) {
+            unspent.push(key.clone());
+            changes.push((key, Column::Coins, WriteOperation::Insert(value)));
+        }
+        for key in self.skewed_keys(state, shape.state_updates) {
+            let value = Arc::new(self.bytes(STATE_VALUE_SIZE));
+            changes.push((key, Column::ContractsState, WriteOperation::Insert(value)));
+        }
+        let transactions = self.transactions(shape.transactions, shape.transaction_size);
+        for (key, value) in transactions {
+            changes.push((key, Column::Transactions, WriteOperation::Insert(value)));
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::strict_keys::check_key;
+    use std::collections::HashSet;
+
+    #[test]
+    fn generators_with_the_same_seed_produce_the_same_workloads() {
+        // Given
+        let generate = |seed| {
+            let mut generator = WorkloadGenerator::new(seed);
+            let coins = generator.coins(10);
+            let state = generator.contract_state(2, 10);
+            let keys = generator.skewed_keys(&state, 10);
+            let transactions = generator.transactions(2, 100);
+            let mut unspent = coins.iter().map(|(key, _)| key.clone()).collect();
+            let block = generator.block(&mut unspent, &state, BlockShape::default());
+            (coins, state, keys, transactions, block)
+        };
+
+        // When
+        let first = generate(7);
+        let second = generate(7);
+        let another = generate(8);
+
+        // Then
+        assert_eq!(first, second);
+        assert_ne!(first.0, another.0);
+    }
+
+    #[test]
+    fn keys_have_formats_of_their_columns() {
+        // Given
+        let mut generator = WorkloadGenerator::new(0);
+
+        // When
+        let coins = generator.coins(10);
+        let state = generator.contract_state(2, 10);
+        let transactions = generator.transactions(10, 1024);
+
+        // Then
+        for (column, entries) in [
+            (Column::Coins, &coins),
+            (Column::ContractsState, &state.entries),
+            (Column::Transactions, &transactions),
+        ] {
+            for (key, _) in entries {
+                check_key(column, key).unwrap();
+            }
+        }
+        assert!(transactions.iter().all(|(_, value)| value.len() == 1024));
+        assert!(state
+            .entries
+            .iter()
+            .all(|(key, _)| state.contracts.iter().any(|id| key.starts_with(id))));
+    }
+
+    #[test]
+    fn skewed_keys_mostly_hit_hot_slots() {
+        // Given
+        let mut generator = WorkloadGenerator::new(0);
+        let state = generator.contract_state(10, 100);
+        let hot = state.hot_keys().collect::<HashSet<_>>();
+
+        // When
+        let keys = generator.skewed_keys(&state, 10_000);
+
+        // Then
+        let hits = keys
+            .iter()
+            .filter(|key| hot.contains(key.as_slice()))
+            .count();
+        assert!(hits > 8_500, "{hits}");
+    }
+
+    #[test]
+    fn block_spends_only_unspent_coins() {
+        // Given
+        let mut generator = WorkloadGenerator::new(0);
+        let state = generator.contract_state(2, 10);
+        let coins = generator
+            .coins(50)
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        let mut unspent = coins.clone();
+        let shape = BlockShape::default();
+
+        // When
+        let block = generator.block(&mut unspent, &state, shape);
+
+        // Then
+        let spent = block
+            .iter()
+            .filter(|(_, _, operation)| matches!(operation, WriteOperation::Remove))
+            .map(|(key, _, _)| key)
+            .collect::<Vec<_>>();
+        assert_eq!(spent.len(), coins.len());
+        assert!(spent.iter().all(|key| coins.contains(key)));
+        assert_eq!(unspent.len(), shape.new_coins);
+        assert_eq!(
+            block.len(),
+            coins.len() + shape.new_coins + shape.state_updates + shape.transactions
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use prometheus_client::{
+    encoding::EncodeLabelSet,
+    metrics::{
+        counter::Counter,
+        family::Family,
+        gauge::Gauge,
+        histogram::{
+            exponential_buckets,
+            Histogram,
+        },
+    },
+    registry::Registry,
+};
+use std::sync::OnceLock;
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct OperationLabels {
+    pub database: &'static str,
+    pub column: &'static str,
+    pub operation: &'static str,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct ColumnLabels {
+    pub database: &'static str,
+    pub column: &'static str,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct DatabaseLabels {
+    pub database: &'static str,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct WritePressureLabels {
+    pub database: &'static str,
+    pub state: &'static str,
+}
+
+/// The buckets from 1 microsecond to 4 seconds, storage operations are much faster than requests.
+fn duration_buckets() -> impl Iterator<Item = f64> {
+    exponential_buckets(0.000_001, 4.0, 12)
+}
+
+/// The buckets from 1 to 262144 items.
+fn size_buckets() -> impl Iterator<Item = f64> {
+    exponential_buckets(1.0, 4.0, 10)
+}
+
+/// The buckets from 64 bytes to 256 MiB.
+fn bytes_buckets() -> impl Iterator<Item = f64> {
+    exponential_buckets(64.0, 4.0, 12)
+}
+
+pub struct StorageMetrics {
+    pub registry: Registry,
+    pub operations: Family<OperationLabels, Counter>,
+    pub operation_duration: Family<OperationLabels, Histogram>,
+    pub iter_first_item_duration: Family<ColumnLabels, Histogram>,
+    pub iter_items: Family<ColumnLabels, Histogram>,
+    pub batch_write_entries: Family<DatabaseLabels, Histogram>,
+    pub batch_write_duration: Family<DatabaseLabels, Histogram>,
+    pub batch_write_bytes: Family<DatabaseLabels, Histogram>,
+    pub cache_hits: Family<DatabaseLabels, Counter>,
+    pub cache_misses: Family<DatabaseLabels, Counter>,
+    pub engine_block_cache_hits: Family<DatabaseLabels, Gauge>,
+    pub engine_block_cache_misses: Family<DatabaseLabels, Gauge>,
+    pub engine_stall_micros: Family<DatabaseLabels, Gauge>,
+    pub engine_block_cache_usage: Family<DatabaseLabels, Gauge>,
+    pub engine_memtable_usage: Family<DatabaseLabels, Gauge>,
+    pub engine_memory_budget: Family<DatabaseLabels, Gauge>,
+    pub engine_running_compactions: Family<DatabaseLabels, Gauge>,
+    pub engine_writes_stopped: Family<DatabaseLabels, Gauge>,
+    pub engine_pending_compaction_bytes: Family<ColumnLabels, Gauge>,
+    pub engine_immutable_memtables: Family<ColumnLabels, Gauge>,
+    pub engine_level0_files: Family<ColumnLabels, Gauge>,
+    pub write_pressure_micros: Family<WritePressureLabels, Counter>,
+}
+
+impl Default for StorageMetrics {
+    fn default() -> Self {
+        let mut registry = Registry::default();
+
+        let operations = Family::default();
+        let operation_duration =
+            Family::<OperationLabels, Histogram>::new_with_constructor(|| {
+                Histogram::new(duration_buckets())
+            });
+        let iter_first_item_duration =
+            Family::<ColumnLabels, Histogram>::new_with_constructor(|| {
+                Histogram::new(duration_buckets())
+            });
+        let iter_items = Family::<ColumnLabels, Histogram>::new_with_constructor(|| {
+            Histogram::new(size_buckets())
+        });
+        let batch_write_entries =
+            Family::<DatabaseLabels, Histogram>::new_with_constructor(|| {
+                Histogram::new(size_buckets())
+            });
+        let batch_write_duration =
+            Family::<DatabaseLabels, Histogram>::new_with_constructor(|| {
+                Histogram::new(duration_buckets())
+            });
+        let batch_write_bytes =
+            Family::<DatabaseLabels, Histogram>::new_with_constructor(|| {
+                Histogram::new(bytes_buckets())
+            });
+        let cache_hits = Family::default();
+        let cache_misses = Family::default();
+        let engine_block_cache_hits = Family::default();
+        let engine_block_cache_misses = Family::default();
+        let engine_stall_micros = Family::default();
+        let engine_block_cache_usage = Family::default();
+        let engine_memtable_usage = Family::default();
+        let engine_memory_budget = Family::default();
+        let engine_running_compactions = Family::default();
+        let engine_writes_stopped = Family::default();
+        let engine_pending_compaction_bytes = Family::default();
+        let engine_immutable_memtables = Family::default();
+        let engine_level0_files = Family::default();
+        let write_pressure_micros = Family::default();
+
+        registry.register(
+            "storage_operations",
+            "The number of storage operations per column",
+            operations.clone(),
+        );
+        registry.register(
+            "storage_operation_duration_s",
+            "Records the duration of storage operations per column",
+            operation_duration.clone(),
+        );
+        registry.register(
+            "storage_iter_first_item_duration_s",
+            "Records the time from the creation of the iterator to its first item",
+            iter_first_item_duration.clone(),
+        );
+        registry.register(
+            "storage_iter_items",
+            "Records the number of items yielded by the iterator",
+            iter_items.clone(),
+        );
+        registry.register(
+            "storage_batch_write_entries",
+            "Records the number of entries in the batch write",
+            batch_write_entries.clone(),
+        );
+        registry.register(
+            "storage_batch_write_duration_s",
+            "Records the duration of the batch write",
+            batch_write_duration.clone(),
+        );
+        registry.register(
+            "storage_batch_write_bytes",
+            "Records the size of keys and values written by the batch write",
+            batch_write_bytes.clone(),
+        );
+        registry.register(
+            "storage_cache_hits",
+            "The number of reads served by the read cache",
+            cache_hits.clone(),
+        );
+        registry.register(
+            "storage_cache_misses",
+            "The number of reads that missed the read cache",
+            cache_misses.clone(),
+        );
+        registry.register(
+            "storage_engine_block_cache_hits",
+            "The number of reads of blocks served by the block cache of the storage engine",
+            engine_block_cache_hits.clone(),
+        );
+        registry.register(
+            "storage_engine_block_cache_misses",
+            "The number of reads of blocks that missed the block cache of the storage engine",
+            engine_block_cache_misses.clone(),
+        );
+        registry.register(
+            "storage_engine_stall_micros",
+            "The total time of stalled writes of the storage engine in microseconds",
+            engine_stall_micros.clone(),
+        );
+        registry.register(
+            "storage_engine_block_cache_usage_bytes",
+            "The size of entries in the block cache of the storage engine",
+            engine_block_cache_usage.clone(),
+        );
+        registry.register(
+            "storage_engine_memtable_usage_bytes",
+            "The size of memtables of all columns of the storage engine",
+            engine_memtable_usage.clone(),
+        );
+        registry.register(
+            "storage_engine_memory_budget_bytes",
+            "The memory budget of the block cache and memtables of the storage engine",
+            engine_memory_budget.clone(),
+        );
+        registry.register(
+            "storage_engine_running_compactions",
+            "The number of running compactions of the storage engine",
+            engine_running_compactions.clone(),
+        );
+        registry.register(
+            "storage_engine_writes_stopped",
+            "Whether writes of the storage engine are stopped",
+            engine_writes_stopped.clone(),
+        );
+        registry.register(
+            "storage_engine_pending_compaction_bytes",
+            "The estimated size of data that compactions of the column have to rewrite",
+            engine_pending_compaction_bytes.clone(),
+        );
+        registry.register(
+            "storage_engine_immutable_memtables",
+            "The number of memtables of the column waiting for the flush",
+            engine_immutable_memtables.clone(),
+        );
+        registry.register(
+            "storage_engine_level0_files",
+            "The number of files of the column on the first level",
+            engine_level0_files.clone(),
+        );
+        registry.register(
+            "storage_write_pressure_micros",
+            "The time in microseconds spent by writes of the database in each state of the pressure",
+            write_pressure_micros.clone(),
+        );
+
+        Self {
+            registry,
+            operations,
+            operation_duration,
+            iter_first_item_duration,
+            iter_items,
+            batch_write_entries,
+            batch_write_duration,
+            batch_write_bytes,
+            cache_hits,
+            cache_misses,
+            engine_block_cache_hits,
+            engine_block_cache_misses,
+            engine_stall_micros,
+            engine_block_cache_usage,
+            engine_memtable_usage,
+            engine_memory_budget,
+            engine_running_compactions,
+            engine_writes_stopped,
+            engine_pending_compaction_bytes,
+            engine_immutable_memtables,
+            engine_level0_files,
+            write_pressure_micros,
+        }
+    }
+}
+
+static STORAGE_METRICS: OnceLock<StorageMetrics> = OnceLock::new();
+
+pub fn storage_metrics() -> &'static StorageMetrics {
+    STORAGE_METRICS.get_or_init(StorageMetrics::default)
+}
@@ -27,6 +27,7 @@ use fuel_core_storage::{
     vm_storage::VmStorage,
     StorageAsMut,
     StorageAsRef,
+    StorageBlindMutate,
 };
 use fuel_core_types::{
     blockchain::{
@@ -1072,10 +1073,11 @@ where
         let final_tx = tx.into();
 
         // Store tx into the block db transaction
-        tx_st_transaction
-            .as_mut()
-            .storage::<ProcessedTransactions>()
-            .insert(&tx_id, &())?;
+        StorageBlindMutate::<ProcessedTransactions>::put(
+            tx_st_transaction.as_mut(),
+            &tx_id,
+            &(),
+        )?;
 
         // Update `execution_data` data only after all steps.
         execution_data.coinbase = execution_data
@@ -1590,7 +1592,8 @@ where
                     if let Some(Input::Contract(Contract { contract_id, .. })) =
                         inputs.get(contract.input_index as usize)
                     {
-                        db.storage::<ContractsLatestUtxo>().insert(
+                        StorageBlindMutate::<ContractsLatestUtxo>::put(
+                            db,
                             contract_id,
                             &ContractUtxoInfo {
                                 utxo_id,
@@ -1630,7 +1633,8 @@ where
                     db,
                 )?,
                 Output::ContractCreated { contract_id, .. } => {
-                    db.storage::<ContractsLatestUtxo>().insert(
+                    StorageBlindMutate::<ContractsLatestUtxo>::put(
+                        db,
                         contract_id,
                         &ContractUtxoInfo {
                             utxo_id,
@@ -15,6 +15,7 @@ use fuel_core_storage::{
     Error as StorageError,
     MerkleRootStorage,
     StorageBatchMutate,
+    StorageBlindMutate,
     StorageMutate,
     StorageRead,
 };
@@ -72,11 +73,11 @@ pub trait RelayerPort {
 pub trait ExecutorDatabaseTrait<D>:
     StorageInspect<FuelBlocks, Error = StorageError>
     + StorageMutate<Messages, Error = StorageError>
-    + StorageMutate<ProcessedTransactions, Error = StorageError>
+    + StorageBlindMutate<ProcessedTransactions, Error = StorageError>
     + MerkleRootStorage<ContractId, ContractsAssets, Error = StorageError>
     + StorageMutate<Coins, Error = StorageError>
     + StorageMutate<SpentMessages, Error = StorageError>
-    + StorageMutate<ContractsLatestUtxo, Error = StorageError>
+    + StorageBlindMutate<ContractsLatestUtxo, Error = StorageError>
     + StorageMutate<ContractsRawCode, Error = StorageError>
     + StorageRead<ContractsRawCode>
     + StorageMutate<ContractsInfo, Error = StorageError>
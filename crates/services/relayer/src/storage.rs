@@ -4,10 +4,14 @@ use crate::ports::RelayerDb;
 use fuel_core_storage::{
     blueprint::plain::Plain,
     codec::{
+        height::DaHeightKey,
         postcard::Postcard,
         primitive::Primitive,
     },
-    kv_store::StorageColumn,
+    kv_store::{
+        KeyFormat,
+        StorageColumn,
+    },
     structured_storage::TableWithBlueprint,
     transactional::Transactional,
     Error as StorageError,
@@ -32,6 +36,8 @@ use fuel_core_types::{
     strum_macros::IntoStaticStr,
     PartialEq,
     Eq,
+    PartialOrd,
+    Ord,
     enum_iterator::Sequence,
     Hash,
 )]
@@ -62,6 +68,13 @@ impl StorageColumn for Column {
     fn id(&self) -> u32 {
         self.as_u32()
     }
+
+    fn key_format(&self) -> KeyFormat {
+        match self {
+            Column::History => KeyFormat::Fixed(DaHeightKey::LEN),
+            Column::Metadata | Column::RelayerHeight => KeyFormat::Variable,
+        }
+    }
 }
 
 /// Teh table to track the relayer's da height.
@@ -205,4 +218,17 @@ mod tests {
         <EventsHistory as Mappable>::Key::default(),
         vec![Event::Message(Default::default())]
     );
+
+    /// Ids are persisted, so reordering or renumbering columns must fail this test.
+    #[test]
+    fn ids_of_columns_are_stable() {
+        for column in enum_iterator::all::<Column>() {
+            let expected = match column {
+                Column::Metadata => 0,
+                Column::History => 1,
+                Column::RelayerHeight => 2,
+            };
+            assert_eq!(column.id(), expected, "The id of {column:?} changed");
+        }
+    }
 }
@@ -94,12 +94,14 @@ where
     ) -> StorageResult<Option<M::OwnedValue>> {
         let key_encoder = Self::KeyCodec::encode(key);
         let key_bytes = key_encoder.as_bytes();
-        storage
-            .get(key_bytes.as_ref(), column)?
-            .map(|value| {
-                Self::ValueCodec::decode_from_value(value).map_err(crate::Error::Codec)
-            })
+        // Decode the value directly from the storage to avoid copying it.
+        let mut value = None;
+        storage.get_with(key_bytes.as_ref(), column, &mut |bytes| {
+            value = Some(Self::ValueCodec::decode(bytes));
+        })?;
+        value
             .transpose()
+            .map_err(|error| crate::Error::decode(column, key_bytes.as_ref(), error))
     }
 }
 
@@ -14,6 +14,7 @@ use crate::{
         Encoder as EncoderTrait,
     },
     kv_store::{
+        debug_assert_key_format,
         BatchOperations,
         KeyValueStore,
     },
@@ -141,6 +142,7 @@ where
     ) -> StorageResult<()> {
         let key_encoder = KeyCodec::encode(key);
         let key_bytes = key_encoder.as_bytes();
+        debug_assert_key_format(column, key_bytes.as_ref());
         let encoded_value = ValueCodec::encode_as_value(value);
         storage.put(key_bytes.as_ref(), column, encoded_value)?;
         let key = key.to_owned().into();
@@ -155,11 +157,14 @@ where
     ) -> StorageResult<Option<M::OwnedValue>> {
         let key_encoder = KeyCodec::encode(key);
         let key_bytes = key_encoder.as_bytes();
+        debug_assert_key_format(column, key_bytes.as_ref());
         let encoded_value = ValueCodec::encode_as_value(value);
         let prev = storage
             .replace(key_bytes.as_ref(), column, encoded_value)?
             .map(|value| {
-                ValueCodec::decode_from_value(value).map_err(StorageError::Codec)
+                ValueCodec::decode_from_value(value).map_err(|error| {
+                    StorageError::decode(column, key_bytes.as_ref(), error)
+                })
             })
             .transpose()?;
 
@@ -179,11 +184,14 @@ where
     ) -> StorageResult<Option<M::OwnedValue>> {
         let key_encoder = KeyCodec::encode(key);
         let key_bytes = key_encoder.as_bytes();
+        debug_assert_key_format(column, key_bytes.as_ref());
         Self::remove(storage, key_bytes.as_ref(), column)?;
         let prev = storage
             .take(key_bytes.as_ref(), column)?
             .map(|value| {
-                ValueCodec::decode_from_value(value).map_err(StorageError::Codec)
+                ValueCodec::decode_from_value(value).map_err(|error| {
+                    StorageError::decode(column, key_bytes.as_ref(), error)
+                })
             })
             .transpose()?;
         Ok(prev)
@@ -192,6 +200,7 @@ where
     fn delete(storage: &mut S, key: &M::Key, column: S::Column) -> StorageResult<()> {
         let key_encoder = KeyCodec::encode(key);
         let key_bytes = key_encoder.as_bytes();
+        debug_assert_key_format(column, key_bytes.as_ref());
         Self::remove(storage, key_bytes.as_ref(), column)
     }
 }
@@ -278,6 +287,7 @@ where
         for item in set {
             let key_encoder = KeyCodec::encode(item);
             let key_bytes = key_encoder.as_bytes();
+            debug_assert_key_format(column, key_bytes.as_ref());
             Self::remove(storage, key_bytes.as_ref(), column)?;
         }
         Ok(())
@@ -14,6 +14,7 @@ use crate::{
         Encoder,
     },
     kv_store::{
+        debug_assert_key_format,
         BatchOperations,
         KeyValueStore,
         StorageColumn,
@@ -49,6 +50,7 @@ where
     ) -> StorageResult<()> {
         let key_encoder = KeyCodec::encode(key);
         let key_bytes = key_encoder.as_bytes();
+        debug_assert_key_format(column, key_bytes.as_ref());
         let value = ValueCodec::encode_as_value(value);
         storage.put(key_bytes.as_ref(), column, value)
     }
@@ -61,11 +63,14 @@ where
     ) -> StorageResult<Option<M::OwnedValue>> {
         let key_encoder = KeyCodec::encode(key);
         let key_bytes = key_encoder.as_bytes();
+        debug_assert_key_format(column, key_bytes.as_ref());
         let value = ValueCodec::encode_as_value(value);
         storage
             .replace(key_bytes.as_ref(), column, value)?
             .map(|value| {
-                ValueCodec::decode_from_value(value).map_err(StorageError::Codec)
+                ValueCodec::decode_from_value(value).map_err(|error| {
+                    StorageError::decode(column, key_bytes.as_ref(), error)
+                })
             })
             .transpose()
     }
@@ -77,10 +82,13 @@ where
     ) -> StorageResult<Option<M::OwnedValue>> {
         let key_encoder = KeyCodec::encode(key);
         let key_bytes = key_encoder.as_bytes();
+        debug_assert_key_format(column, key_bytes.as_ref());
         storage
             .take(key_bytes.as_ref(), column)?
             .map(|value| {
-                ValueCodec::decode_from_value(value).map_err(StorageError::Codec)
+                ValueCodec::decode_from_value(value).map_err(|error| {
+                    StorageError::decode(column, key_bytes.as_ref(), error)
+                })
             })
             .transpose()
     }
@@ -88,6 +96,7 @@ where
     fn delete(storage: &mut S, key: &M::Key, column: S::Column) -> StorageResult<()> {
         let key_encoder = KeyCodec::encode(key);
         let key_bytes = key_encoder.as_bytes();
+        debug_assert_key_format(column, key_bytes.as_ref());
         storage.delete(key_bytes.as_ref(), column)
     }
 }
@@ -123,6 +132,7 @@ where
         storage.batch_write(&mut set.map(|(key, value)| {
             let key_encoder = <M::Blueprint as Blueprint<M, S>>::KeyCodec::encode(key);
             let key_bytes = key_encoder.as_bytes().to_vec();
+            debug_assert_key_format(column, &key_bytes);
             let value =
                 <M::Blueprint as Blueprint<M, S>>::ValueCodec::encode_as_value(value);
             (key_bytes, column, WriteOperation::Insert(value))
@@ -141,6 +151,7 @@ where
         storage.batch_write(&mut set.map(|key| {
             let key_encoder = <M::Blueprint as Blueprint<M, S>>::KeyCodec::encode(key);
             let key_bytes = key_encoder.as_bytes().to_vec();
+            debug_assert_key_format(column, &key_bytes);
             (key_bytes, column, WriteOperation::Remove)
         }))
     }
@@ -15,6 +15,7 @@ use crate::{
         Encoder,
     },
     kv_store::{
+        debug_assert_key_format,
         BatchOperations,
         KeyValueStore,
         StorageColumn,
@@ -190,6 +191,7 @@ where
     ) -> StorageResult<()> {
         let key_encoder = KeyCodec::encode(key);
         let key_bytes = key_encoder.as_bytes();
+        debug_assert_key_format(column, key_bytes.as_ref());
         let value = ValueCodec::encode_as_value(value);
         storage.put(key_bytes.as_ref(), column, value.clone())?;
         Self::insert_into_tree(storage, key, key_bytes.as_ref(), value.as_ref())
@@ -203,11 +205,14 @@ where
     ) -> StorageResult<Option<M::OwnedValue>> {
         let key_encoder = KeyCodec::encode(key);
         let key_bytes = key_encoder.as_bytes();
+        debug_assert_key_format(column, key_bytes.as_ref());
         let value = ValueCodec::encode_as_value(value);
         let prev = storage
             .replace(key_bytes.as_ref(), column, value.clone())?
             .map(|value| {
-                ValueCodec::decode_from_value(value).map_err(StorageError::Codec)
+                ValueCodec::decode_from_value(value).map_err(|error| {
+                    StorageError::decode(column, key_bytes.as_ref(), error)
+                })
             })
             .transpose()?;
 
@@ -222,10 +227,13 @@ where
     ) -> StorageResult<Option<M::OwnedValue>> {
         let key_encoder = KeyCodec::encode(key);
         let key_bytes = key_encoder.as_bytes();
+        debug_assert_key_format(column, key_bytes.as_ref());
         let prev = storage
             .take(key_bytes.as_ref(), column)?
             .map(|value| {
-                ValueCodec::decode_from_value(value).map_err(StorageError::Codec)
+                ValueCodec::decode_from_value(value).map_err(|error| {
+                    StorageError::decode(column, key_bytes.as_ref(), error)
+                })
             })
             .transpose()?;
         Self::remove_from_tree(storage, key, key_bytes.as_ref())?;
@@ -235,6 +243,7 @@ where
     fn delete(storage: &mut S, key: &M::Key, column: S::Column) -> StorageResult<()> {
         let key_encoder = KeyCodec::encode(key);
         let key_bytes = key_encoder.as_bytes();
+        debug_assert_key_format(column, key_bytes.as_ref());
         storage.delete(key_bytes.as_ref(), column)?;
         Self::remove_from_tree(storage, key, key_bytes.as_ref())
     }
@@ -319,6 +328,7 @@ where
         let encoded_set = set
             .map(|(key, value)| {
                 let key = KeyCodec::encode(key).as_bytes().into_owned();
+                debug_assert_key_format(column, &key);
                 let value = ValueCodec::encode(value).as_bytes().into_owned();
                 (key, value)
             })
@@ -385,6 +395,7 @@ where
         let encoded_set = set
             .map(|(key, value)| {
                 let key = KeyCodec::encode(key).as_bytes().into_owned();
+                debug_assert_key_format(column, &key);
                 let value = ValueCodec::encode(value).as_bytes().into_owned();
                 (key, value)
             })
@@ -440,7 +451,11 @@ where
             .map_err(|err| StorageError::Other(anyhow::anyhow!("{err:?}")))?;
 
         let encoded_set = set
-            .map(|key| KeyCodec::encode(key).as_bytes().into_owned())
+            .map(|key| {
+                let key = KeyCodec::encode(key).as_bytes().into_owned();
+                debug_assert_key_format(column, &key);
+                key
+            })
             .collect_vec();
 
         for key_bytes in encoded_set.iter() {
@@ -7,6 +7,7 @@ use std::{
     ops::Deref,
 };
 
+pub mod height;
 pub mod manual;
 pub mod postcard;
 pub mod primitive;
@@ -0,0 +1,140 @@
+//! The module contains the keys of columns keyed by heights. The key is the fixed-width
+//! big-endian encoding of the height, so the lexicographical order of keys used
+//! by the iteration matches the numeric order of heights.
+
+use crate::codec::Encoder;
+use core::array::TryFromSliceError;
+use fuel_core_types::{
+    blockchain::primitives::DaBlockHeight,
+    fuel_types::BlockHeight,
+};
+use std::borrow::Cow;
+
+/// The key of the height encoded as `SIZE` big-endian bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HeightKey<const SIZE: usize>([u8; SIZE]);
+
+/// The key of the [`BlockHeight`].
+pub type BlockHeightKey = HeightKey<4>;
+
+/// The key of the [`DaBlockHeight`].
+pub type DaHeightKey = HeightKey<8>;
+
+impl<const SIZE: usize> HeightKey<SIZE> {
+    /// The size of the key in bytes.
+    pub const LEN: usize = SIZE;
+
+    /// The key of the zero height. It is the first key of the column.
+    pub const MIN: Self = Self([0; SIZE]);
+
+    /// The key of the maximum height. It is the last key of the column, so the reverse
+    /// iteration from it starts at the latest height.
+    pub const MAX: Self = Self([u8::MAX; SIZE]);
+
+    /// Returns `true` if the `key` has the size of the height key.
+    pub fn is_valid(key: &[u8]) -> bool {
+        key.len() == SIZE
+    }
+}
+
+impl<const SIZE: usize> AsRef<[u8]> for HeightKey<SIZE> {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+impl<const SIZE: usize> Encoder for HeightKey<SIZE> {
+    fn as_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self.as_ref())
+    }
+}
+
+impl<const SIZE: usize> TryFrom<&[u8]> for HeightKey<SIZE> {
+    type Error = TryFromSliceError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Ok(Self(bytes.try_into()?))
+    }
+}
+
+impl From<u32> for BlockHeightKey {
+    fn from(height: u32) -> Self {
+        Self(height.to_be_bytes())
+    }
+}
+
+impl From<BlockHeight> for BlockHeightKey {
+    fn from(height: BlockHeight) -> Self {
+        Self(height.to_be_bytes())
+    }
+}
+
+impl From<BlockHeightKey> for u32 {
+    fn from(key: BlockHeightKey) -> Self {
+        u32::from_be_bytes(key.0)
+    }
+}
+
+impl From<BlockHeightKey> for BlockHeight {
+    fn from(key: BlockHeightKey) -> Self {
+        BlockHeight::from(key.0)
+    }
+}
+
+impl From<u64> for DaHeightKey {
+    fn from(height: u64) -> Self {
+        Self(height.to_be_bytes())
+    }
+}
+
+impl From<DaBlockHeight> for DaHeightKey {
+    fn from(height: DaBlockHeight) -> Self {
+        Self(height.to_be_bytes())
+    }
+}
+
+impl From<DaHeightKey> for u64 {
+    fn from(key: DaHeightKey) -> Self {
+        u64::from_be_bytes(key.0)
+    }
+}
+
+impl From<DaHeightKey> for DaBlockHeight {
+    fn from(key: DaHeightKey) -> Self {
+        DaBlockHeight::from(key.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_ordered_as_heights() {
+        let heights = [
+            0u64,
+            1,
+            255,
+            256,
+            65_535,
+            65_536,
+            u64::from(u32::MAX),
+            1 << 32,
+        ];
+        let keys = heights.map(DaHeightKey::from);
+
+        let mut sorted = keys;
+        sorted.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+        assert_eq!(sorted, keys);
+        assert_eq!(keys.map(u64::from), heights);
+    }
+
+    #[test]
+    fn key_of_wrong_size_is_rejected() {
+        assert!(BlockHeightKey::try_from([0u8; 8].as_slice()).is_err());
+        assert!(!BlockHeightKey::is_valid(&[0; 8]));
+        assert!(BlockHeightKey::is_valid(BlockHeightKey::MAX.as_ref()));
+        assert_eq!(u32::from(BlockHeightKey::MAX), u32::MAX);
+    }
+}
@@ -4,6 +4,10 @@
 //! or could be represented by arrays.
 
 use crate::codec::{
+    height::{
+        BlockHeightKey,
+        DaHeightKey,
+    },
     Decode,
     Encode,
 };
@@ -49,8 +53,6 @@ impl_encode! {
     u8, 1,
     u16, 2,
     u32, 4,
-    BlockHeight, 4,
-    DaBlockHeight, 8,
     u64, 8,
     u128, 16
 }
@@ -63,15 +65,32 @@ impl_decode! {
     u128, 16
 }
 
-impl Decode<BlockHeight> for Primitive<4> {
+// Heights are encoded by their keys, so columns keyed by heights are ordered by heights.
+impl Encode<BlockHeight> for Primitive<{ BlockHeightKey::LEN }> {
+    type Encoder<'a> = BlockHeightKey;
+
+    fn encode(t: &BlockHeight) -> Self::Encoder<'_> {
+        BlockHeightKey::from(*t)
+    }
+}
+
+impl Decode<BlockHeight> for Primitive<{ BlockHeightKey::LEN }> {
     fn decode(bytes: &[u8]) -> anyhow::Result<BlockHeight> {
-        Ok(BlockHeight::from(<[u8; 4]>::try_from(bytes)?))
+        Ok(BlockHeightKey::try_from(bytes)?.into())
+    }
+}
+
+impl Encode<DaBlockHeight> for Primitive<{ DaHeightKey::LEN }> {
+    type Encoder<'a> = DaHeightKey;
+
+    fn encode(t: &DaBlockHeight) -> Self::Encoder<'_> {
+        DaHeightKey::from(*t)
     }
 }
 
-impl Decode<DaBlockHeight> for Primitive<8> {
+impl Decode<DaBlockHeight> for Primitive<{ DaHeightKey::LEN }> {
     fn decode(bytes: &[u8]) -> anyhow::Result<DaBlockHeight> {
-        Ok(DaBlockHeight::from(<[u8; 8]>::try_from(bytes)?))
+        Ok(DaHeightKey::try_from(bytes)?.into())
     }
 }
 
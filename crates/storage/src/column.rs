@@ -2,9 +2,15 @@
 //! In the future, the `Column` enum should contain only the required tables for the execution.
 //! All other tables should live in the downstream creates in the place where they are really used.
 
-use crate::kv_store::StorageColumn;
+use crate::kv_store::{
+    KeyFormat,
+    StorageColumn,
+};
 
 /// Database tables column ids to the corresponding [`crate::Mappable`] table.
+///
+/// The discriminant is the [`StorageColumn::id`] of the column. Ids are persisted,
+/// so new columns get the next free id, and ids of removed columns are not reused.
 #[repr(u32)]
 #[derive(
     Copy,
@@ -14,6 +20,8 @@ use crate::kv_store::StorageColumn;
     strum_macros::IntoStaticStr,
     PartialEq,
     Eq,
+    PartialOrd,
+    Ord,
     enum_iterator::Sequence,
     Hash,
 )]
@@ -64,6 +72,10 @@ pub enum Column {
     Metadata = 17,
     /// See [`SealedBlockConsensus`](crate::tables::SealedBlockConsensus)
     FuelBlockConsensus = 18,
+    /// The undo data of blocks used to roll back the state to the previous height.
+    UndoLog = 19,
+    /// The sequence-numbered entries of committed writes used to replicate the database.
+    ChangeLog = 20,
 }
 
 impl Column {
@@ -84,4 +96,73 @@ impl StorageColumn for Column {
     fn id(&self) -> u32 {
         self.as_u32()
     }
+
+    // The match has no wildcard, so each new column decides the format of its keys.
+    fn key_format(&self) -> KeyFormat {
+        const ID: usize = 32;
+        const HEIGHT: usize = 4;
+        match self {
+            Column::ContractsRawCode
+            | Column::ContractsInfo
+            | Column::ContractsLatestUtxo
+            | Column::Transactions
+            | Column::SpentMessages
+            | Column::ContractsAssetsMerkleData
+            | Column::ContractsAssetsMerkleMetadata
+            | Column::ContractsStateMerkleData
+            | Column::ContractsStateMerkleMetadata
+            | Column::Messages
+            | Column::ProcessedTransactions => KeyFormat::Fixed(ID),
+            // The contract id followed by the state key or the asset id.
+            Column::ContractsState | Column::ContractsAssets => {
+                KeyFormat::MultiKey(ID, ID)
+            }
+            // The transaction id followed by the index of the output.
+            Column::Coins => KeyFormat::Fixed(33),
+            Column::FuelBlocks | Column::FuelBlockConsensus | Column::UndoLog => {
+                KeyFormat::Fixed(HEIGHT)
+            }
+            Column::FuelBlockMerkleData | Column::ChangeLog => {
+                KeyFormat::Fixed(core::mem::size_of::<u64>())
+            }
+            // The postcard encodes the key of the latest metadata and heights with varints.
+            Column::FuelBlockMerkleMetadata | Column::Metadata => KeyFormat::Variable,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ids are persisted, so reordering or renumbering columns must fail this test.
+    #[test]
+    fn ids_of_columns_are_stable() {
+        for column in enum_iterator::all::<Column>() {
+            let expected = match column {
+                Column::ContractsRawCode => 0,
+                Column::ContractsInfo => 1,
+                Column::ContractsState => 2,
+                Column::ContractsLatestUtxo => 3,
+                Column::ContractsAssets => 4,
+                Column::Coins => 5,
+                Column::Transactions => 6,
+                Column::FuelBlocks => 7,
+                Column::FuelBlockMerkleData => 8,
+                Column::FuelBlockMerkleMetadata => 9,
+                Column::SpentMessages => 10,
+                Column::ContractsAssetsMerkleData => 11,
+                Column::ContractsAssetsMerkleMetadata => 12,
+                Column::ContractsStateMerkleData => 13,
+                Column::ContractsStateMerkleMetadata => 14,
+                Column::Messages => 15,
+                Column::ProcessedTransactions => 16,
+                Column::Metadata => 17,
+                Column::FuelBlockConsensus => 18,
+                Column::UndoLog => 19,
+                Column::ChangeLog => 20,
+            };
+            assert_eq!(column.id(), expected, "The id of {column:?} changed");
+        }
+    }
 }
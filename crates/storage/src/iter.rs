@@ -1,9 +1,14 @@
 //! The module defines primitives that allow iterating of the storage.
 
-use crate::kv_store::{
-    KVItem,
-    KeyValueStore,
+use crate::{
+    kv_store::{
+        KVItem,
+        KeyItem,
+        KeyValueStore,
+    },
+    Result as StorageResult,
 };
+use core::ops::Bound;
 
 /// A boxed variant of the iterator that can be used as a return type of the traits.
 pub struct BoxedIter<'a, T> {
@@ -50,9 +55,157 @@ impl Default for IterDirection {
     }
 }
 
+/// The options of reads by the iterator. Backends without the block cache
+/// and the read-ahead ignore them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IterOptions {
+    /// Whether blocks read by the iterator are added to the block cache.
+    /// Large scans should disable it, so they don't evict the hot working set.
+    pub fill_cache: bool,
+    /// The number of bytes read ahead of the iterator, or `None` for the default
+    /// of the backend. Large read-ahead speeds up sequential scans on spinning disks.
+    pub readahead_bytes: Option<usize>,
+    /// Whether the read blocks are pinned in memory while the iterator points into them.
+    pub pin_data: bool,
+}
+
+impl Default for IterOptions {
+    fn default() -> Self {
+        Self {
+            fill_cache: true,
+            readahead_bytes: None,
+            pin_data: false,
+        }
+    }
+}
+
+impl IterOptions {
+    /// Returns the options of maintenance scans over the whole column, e.g. exports,
+    /// prunings, and consistency checks, that don't fill the block cache.
+    pub fn maintenance() -> Self {
+        Self {
+            fill_cache: false,
+            ..Default::default()
+        }
+    }
+}
+
+/// The configuration of the iteration over the column, see [`IteratorableStore::iter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IterConfig<'a> {
+    /// Only keys with the prefix are yielded.
+    pub prefix: Option<&'a [u8]>,
+    /// The iteration starts from the key. The [`Bound::Excluded`] start continues
+    /// the iteration strictly after the key in the direction of the iteration,
+    /// for example, from the [`PageIter::last_key`] of the previous page.
+    pub start: Bound<&'a [u8]>,
+    /// The direction of the iteration.
+    pub direction: IterDirection,
+    /// The maximum number of yielded items.
+    pub limit: Option<usize>,
+    /// The options of reads by the iterator.
+    pub options: IterOptions,
+}
+
+impl<'a> Default for IterConfig<'a> {
+    fn default() -> Self {
+        Self {
+            prefix: None,
+            start: Bound::Unbounded,
+            direction: IterDirection::default(),
+            limit: None,
+            options: IterOptions::default(),
+        }
+    }
+}
+
+impl<'a> IterConfig<'a> {
+    /// Returns the key of the `start`, if any, regardless of its inclusion.
+    pub fn start_key(&self) -> Option<&'a [u8]> {
+        match self.start {
+            Bound::Included(start) | Bound::Excluded(start) => Some(start),
+            Bound::Unbounded => None,
+        }
+    }
+}
+
+/// The iterator returned by the [`IteratorableStore::iter`].
+/// It remembers the last yielded key, so the caller can continue from it with the next page.
+pub struct PageIter<'a> {
+    iter: BoxedIter<'a, KVItem>,
+    /// The buffer is reused for each item, so it doesn't allocate per item.
+    last_key: Vec<u8>,
+    yielded: bool,
+}
+
+impl<'a> PageIter<'a> {
+    /// Wraps the `iter`. The limit of the page should already be applied to it.
+    pub fn new(iter: BoxedIter<'a, KVItem>) -> Self {
+        Self {
+            iter,
+            last_key: vec![],
+            yielded: false,
+        }
+    }
+
+    /// Returns the last yielded key, or `None` if nothing was yielded yet.
+    pub fn last_key(&self) -> Option<&[u8]> {
+        self.yielded.then_some(self.last_key.as_slice())
+    }
+}
+
+impl<'a> Iterator for PageIter<'a> {
+    type Item = KVItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next()?;
+        if let Ok((key, _)) = &item {
+            self.last_key.clear();
+            self.last_key.extend_from_slice(key);
+            self.yielded = true;
+        }
+        Some(item)
+    }
+}
+
 /// A trait for iterating over the storage of [`KeyValueStore`].
 pub trait IteratorableStore: KeyValueStore {
+    /// Returns an iterator over the values in the storage with the `config`.
+    /// Implementations should override it when they can stop reading at the limit
+    /// instead of truncating the iteration, and when they can seek past the excluded
+    /// start instead of skipping it.
+    fn iter(&self, column: Self::Column, config: IterConfig) -> PageIter<'_> {
+        let IterConfig {
+            prefix,
+            direction,
+            limit,
+            ..
+        } = config;
+        let iter = self.iter_all(column, prefix, config.start_key(), direction);
+        let iter = match config.start {
+            Bound::Excluded(start) => {
+                let start = start.to_vec();
+                iter.skip_while(move |item| matches!(item, Ok((key, _)) if key == &start))
+                    .into_boxed()
+            }
+            _ => iter,
+        };
+        match limit {
+            Some(limit) => PageIter::new(iter.take(limit).into_boxed()),
+            None => PageIter::new(iter),
+        }
+    }
+
     /// Returns an iterator over the values in the storage.
+    ///
+    /// The iteration yields the keys with the `prefix` in the `direction` from the `start`.
+    /// The `start` is inclusive and doesn't need to exist in the storage:
+    /// - The forward iteration begins at the smallest key `>= start` with the `prefix`.
+    /// - The reverse iteration begins at the greatest key `<= start` with the `prefix`.
+    ///
+    /// Without the `start`, the iteration begins at the first or the last key with the `prefix`.
+    /// If the `start` doesn't have the `prefix`, the iteration yields nothing,
+    /// see [`is_start_within_prefix`].
     fn iter_all(
         &self,
         column: Self::Column,
@@ -60,4 +213,92 @@ pub trait IteratorableStore: KeyValueStore {
         start: Option<&[u8]>,
         direction: IterDirection,
     ) -> BoxedIter<KVItem>;
+
+    /// Returns an iterator over the values in the storage that stops before the `end` key.
+    /// The `end` is exclusive and is reached in the `direction` of the iteration,
+    /// so in the case of the reverse iteration, it is the lower bound of the keys.
+    fn iter_range(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<'_, KVItem> {
+        let Some(end) = end.map(|end| end.to_vec()) else {
+            return self.iter_all(column, prefix, start, direction)
+        };
+
+        self.iter_all(column, prefix, start, direction)
+            .take_while(move |item| {
+                if let Ok((key, _)) = item {
+                    is_before_end(key, &end, direction)
+                } else {
+                    true
+                }
+            })
+            .into_boxed()
+    }
+
+    /// Returns an iterator over the keys in the storage.
+    /// Implementations should override it when they can read keys without their values.
+    fn iter_keys(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+        start: Option<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<'_, KeyItem> {
+        self.iter_all(column, prefix, start, direction)
+            .map(|item| item.map(|(key, _)| key))
+            .into_boxed()
+    }
+
+    /// Returns `true` if the `column` has any key with the `prefix`. The empty `prefix`
+    /// checks whether the column has any keys. Implementations should override it
+    /// when they can seek to the first key without creating the iterator.
+    fn has_any(&self, column: Self::Column, prefix: &[u8]) -> StorageResult<bool> {
+        let prefix = Some(prefix).filter(|prefix| !prefix.is_empty());
+        Ok(self
+            .iter_keys(column, prefix, None, IterDirection::Forward)
+            .next()
+            .transpose()?
+            .is_some())
+    }
+
+    /// Removes all entries with the `prefix` from the `column` and returns the number of removed entries.
+    /// The `None` or an empty `prefix` removes all entries of the `column`.
+    fn delete_all(
+        &self,
+        column: Self::Column,
+        prefix: Option<&[u8]>,
+    ) -> StorageResult<usize> {
+        let keys = self
+            .iter_keys(column, prefix, None, IterDirection::Forward)
+            .collect::<StorageResult<Vec<_>>>()?;
+
+        for key in keys.iter() {
+            self.delete(key, column)?;
+        }
+        Ok(keys.len())
+    }
+}
+
+/// Returns `true` if the iteration with the `prefix` from the `start` may yield entries.
+/// The `start` outside of the `prefix` yields nothing in both directions, even if
+/// keys with the `prefix` are before the `start` in the direction of the iteration.
+pub fn is_start_within_prefix(prefix: Option<&[u8]>, start: Option<&[u8]>) -> bool {
+    match (prefix, start) {
+        (Some(prefix), Some(start)) => start.starts_with(prefix),
+        _ => true,
+    }
+}
+
+/// Returns `true` if the `key` is reached before the exclusive `end` bound
+/// during the iteration in the `direction`.
+fn is_before_end(key: &[u8], end: &[u8], direction: IterDirection) -> bool {
+    match direction {
+        IterDirection::Forward => key < end,
+        IterDirection::Reverse => key > end,
+    }
 }
@@ -10,6 +10,10 @@ use std::sync::Arc;
 pub type Value = Arc<Vec<u8>>;
 /// The pair of key and value from the storage.
 pub type KVItem = StorageResult<(Vec<u8>, Value)>;
+/// The key from the storage.
+pub type KeyItem = StorageResult<Vec<u8>>;
+/// The function computing the new value from the current one for [`KeyValueStore::update`].
+pub type UpdateFn<'a> = dyn FnMut(Option<&[u8]>) -> Option<Vec<u8>> + 'a;
 
 /// A column of the storage.
 pub trait StorageColumn: Copy + core::fmt::Debug {
@@ -17,12 +21,145 @@ pub trait StorageColumn: Copy + core::fmt::Debug {
     fn name(&self) -> &'static str;
 
     /// Returns the id of the column.
+    ///
+    /// Ids are the compatibility contract: they are stored in undo logs, dumps and
+    /// change logs, so the id of the existing column never changes and is never reused.
     fn id(&self) -> u32;
 
     /// Returns the id of the column as an `usize`.
     fn as_usize(&self) -> usize {
         self.id() as usize
     }
+
+    /// Returns the operator used by [`KeyValueStore::merge`] for the column.
+    /// By default, columns don't support merges.
+    fn merge_operator(&self) -> Option<MergeOperator> {
+        None
+    }
+
+    /// Returns the format of keys of the column. Keys written by tables are checked
+    /// by debug assertions. By default, keys may have any size.
+    fn key_format(&self) -> KeyFormat {
+        KeyFormat::Variable
+    }
+}
+
+/// The expected shape of keys of the column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyFormat {
+    /// Keys have the fixed size in bytes, e.g. ids or heights.
+    Fixed(usize),
+    /// Keys are two parts with fixed sizes in bytes, e.g. the contract id followed by the asset id.
+    MultiKey(usize, usize),
+    /// Keys may have any size.
+    Variable,
+}
+
+impl KeyFormat {
+    /// Returns the size of keys in bytes, or `None` if keys may have any size.
+    pub fn size(&self) -> Option<usize> {
+        match self {
+            KeyFormat::Fixed(size) => Some(*size),
+            KeyFormat::MultiKey(first, second) => Some(first.saturating_add(*second)),
+            KeyFormat::Variable => None,
+        }
+    }
+
+    /// Returns `true` if the `key` has the format.
+    pub fn matches(&self, key: &[u8]) -> bool {
+        !matches!(self.size(), Some(size) if key.len() != size)
+    }
+}
+
+impl core::fmt::Display for KeyFormat {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            KeyFormat::Fixed(size) => write!(f, "{size} bytes"),
+            KeyFormat::MultiKey(first, second) => write!(f, "{first} + {second} bytes"),
+            KeyFormat::Variable => write!(f, "any size"),
+        }
+    }
+}
+
+/// Asserts in debug builds that the `key` has the [`StorageColumn::key_format`] of the `column`.
+pub(crate) fn debug_assert_key_format<Column: StorageColumn>(column: Column, key: &[u8]) {
+    debug_assert!(
+        column.key_format().matches(key),
+        "The key {key:?} of the column `{}` has {} bytes, but expected {}",
+        column.name(),
+        key.len(),
+        column.key_format()
+    );
+}
+
+/// The associative operator that combines the merge operand with the existing value.
+/// Because the operator is associative, operands can be combined before the existing value is known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MergeOperator {
+    /// Values and operands are big-endian `u64` counters, and operands are added to the value.
+    /// The missing value is zero, and the addition saturates at `u64::MAX`.
+    U64Add,
+    /// Operands are appended to the value. The missing value is empty.
+    Append,
+}
+
+impl MergeOperator {
+    /// Returns the name of the operator.
+    pub fn name(&self) -> &'static str {
+        match self {
+            MergeOperator::U64Add => "u64_add",
+            MergeOperator::Append => "append",
+        }
+    }
+
+    /// Applies the `operands` in order to the `existing` value.
+    /// Returns `None` if the value or any of the operands is malformed.
+    pub fn apply<'a>(
+        &self,
+        existing: Option<&[u8]>,
+        operands: impl IntoIterator<Item = &'a [u8]>,
+    ) -> Option<Vec<u8>> {
+        match self {
+            MergeOperator::U64Add => {
+                let decode = |bytes: &[u8]| bytes.try_into().ok().map(u64::from_be_bytes);
+                let mut counter = existing.map_or(Some(0), decode)?;
+                for operand in operands {
+                    counter = counter.saturating_add(decode(operand)?);
+                }
+                Some(counter.to_be_bytes().to_vec())
+            }
+            MergeOperator::Append => {
+                let mut value = existing.map(<[u8]>::to_vec).unwrap_or_default();
+                for operand in operands {
+                    value.extend_from_slice(operand);
+                }
+                Some(value)
+            }
+        }
+    }
+}
+
+/// Merges the `operand` into the `existing` value with the operator of the `column`.
+pub fn merge_value<Column: StorageColumn>(
+    column: Column,
+    existing: Option<&[u8]>,
+    operand: &[u8],
+) -> StorageResult<Value> {
+    let operator = column.merge_operator().ok_or_else(|| {
+        StorageError::Other(anyhow::anyhow!(
+            "The column `{}` doesn't support merges",
+            column.name()
+        ))
+    })?;
+    operator
+        .apply(existing, core::iter::once(operand))
+        .map(Value::new)
+        .ok_or_else(|| {
+            StorageError::Other(anyhow::anyhow!(
+                "Unable to merge the malformed value of the column `{}`",
+                column.name()
+            ))
+        })
 }
 
 // TODO: Use `&mut self` for all mutable methods.
@@ -56,6 +193,7 @@ pub trait KeyValueStore {
         -> StorageResult<usize>;
 
     /// Removes the value from the storage and returns it.
+    /// The default implementation isn't atomic, so storages should override it.
     fn take(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
         // FIXME: This is a race condition. We should use a transaction.
         let old_value = self.get(key, column)?;
@@ -66,6 +204,21 @@ pub trait KeyValueStore {
     /// Removes the value from the storage.
     fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()>;
 
+    /// Merges the `operand` into the value with the [`StorageColumn::merge_operator`] of the `column`.
+    /// The default implementation isn't atomic, so storages should override it.
+    fn merge(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        operand: Value,
+    ) -> StorageResult<()> {
+        // FIXME: This is a race condition. We should use a transaction.
+        let existing = self.get(key, column)?;
+        let value =
+            merge_value(column, existing.as_deref().map(Vec::as_slice), &operand)?;
+        self.put(key, column, value)
+    }
+
     /// Checks if the value exists in the storage.
     fn exists(&self, key: &[u8], column: Self::Column) -> StorageResult<bool> {
         Ok(self.size_of_value(key, column)?.is_some())
@@ -83,6 +236,101 @@ pub trait KeyValueStore {
     /// Returns the value from the storage.
     fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>>;
 
+    /// Passes the reference to the value from the storage into the `f` and returns `true`.
+    /// Returns `false` without calling `f` if the value doesn't exist.
+    ///
+    /// Unlike [`Self::get`], the storage is not required to allocate an owned copy of the value,
+    /// so implementations should override it when they can borrow the value directly.
+    fn get_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> StorageResult<bool> {
+        Ok(self
+            .get(key, column)?
+            .map(|value| f(value.as_ref()))
+            .is_some())
+    }
+
+    /// Returns the values from the storage for each of the `keys` in the same order.
+    /// The missing values are represented by `None`.
+    fn multi_get(
+        &self,
+        keys: &[Vec<u8>],
+        column: Self::Column,
+    ) -> StorageResult<Vec<Option<Value>>> {
+        keys.iter().map(|key| self.get(key, column)).collect()
+    }
+
+    /// Puts the `new` value into the storage only if the current value is equal to the `expected`.
+    /// The `None` as the `expected` means that the value should be absent.
+    /// On mismatch, the storage is not modified, and the current value is returned in the `Err`.
+    ///
+    /// The default implementation isn't atomic, so storages should override it.
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        expected: Option<&[u8]>,
+        new: Value,
+    ) -> StorageResult<Result<(), Option<Value>>> {
+        let current = self.get(key, column)?;
+        if current.as_deref().map(Vec::as_slice) != expected {
+            return Ok(Err(current))
+        }
+        self.put(key, column, new)?;
+        Ok(Ok(()))
+    }
+
+    /// Returns the value of the `key` if it exists, otherwise inserts the output
+    /// of the `init` and returns it. The `init` isn't called if the value exists.
+    ///
+    /// The default implementation inserts the value via [`Self::compare_and_swap`],
+    /// so it never overwrites the value inserted concurrently, but racing callers may
+    /// call their `init` before one of them wins. Storages that can hold their lock
+    /// over the initialization should override it, so the `init` is called once.
+    fn get_or_insert_with(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        init: &mut dyn FnMut() -> Vec<u8>,
+    ) -> StorageResult<Value> {
+        if let Some(value) = self.get(key, column)? {
+            return Ok(value)
+        }
+        let value = Value::new(init());
+        match self.compare_and_swap(key, column, None, value.clone())? {
+            Ok(()) => Ok(value),
+            // The mismatch with the absent value means that the value exists.
+            Err(current) => Ok(current.unwrap_or(value)),
+        }
+    }
+
+    /// Replaces the value of the `key` with the output of the `f`. The `f` receives
+    /// the current value by reference, or `None` if the value is absent, and its `None`
+    /// output removes the value. The write is skipped if the output equals the current value.
+    ///
+    /// The default implementation isn't atomic, so storages should override it
+    /// to hold their lock over the read, the `f`, and the write.
+    fn update(
+        &self,
+        key: &[u8],
+        column: Self::Column,
+        f: &mut UpdateFn<'_>,
+    ) -> StorageResult<UpdateOutcome> {
+        let current = self.get(key, column)?;
+        let current = current.as_deref().map(Vec::as_slice);
+        let new = f(current);
+        let outcome = UpdateOutcome::of(current, new.as_deref());
+        match new {
+            _ if !outcome.is_changed() => {}
+            Some(new) => self.put(key, column, Arc::new(new))?,
+            None => self.delete(key, column)?,
+        }
+        Ok(outcome)
+    }
+
     /// Reads the value from the storage into the `buf` and returns the number of read bytes.
     fn read(
         &self,
@@ -105,35 +353,523 @@ pub trait KeyValueStore {
     }
 }
 
+/// The outcome of the [`KeyValueStore::update`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateOutcome {
+    /// The new value equals the current one, so nothing is written.
+    Unchanged,
+    /// The absent value is inserted.
+    Inserted,
+    /// The existing value is replaced by the different one.
+    Updated,
+    /// The existing value is removed.
+    Removed,
+}
+
+impl UpdateOutcome {
+    /// Returns the outcome of the replacement of the `current` value by the `new` one.
+    pub fn of(current: Option<&[u8]>, new: Option<&[u8]>) -> Self {
+        match (current, new) {
+            (current, new) if current == new => Self::Unchanged,
+            (None, _) => Self::Inserted,
+            (Some(_), Some(_)) => Self::Updated,
+            (Some(_), None) => Self::Removed,
+        }
+    }
+
+    /// Returns `true` if the update wrote into the storage.
+    pub fn is_changed(&self) -> bool {
+        *self != Self::Unchanged
+    }
+}
+
 /// The operation to write into the storage.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum WriteOperation {
     /// Insert the value into the storage.
     Insert(Value),
     /// Remove the value from the storage.
     Remove,
+    /// Merge the operand into the value with the [`StorageColumn::merge_operator`].
+    Merge(Value),
+}
+
+/// The statistics of the batch write.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BatchWriteResult {
+    /// The number of inserts and merges.
+    pub inserted: usize,
+    /// The number of removals. Removals of missing keys are counted as well, because
+    /// the storage writes them without checking whether the key exists.
+    pub removed: usize,
+    /// The total size of keys and values of all operations in bytes.
+    pub bytes_written: usize,
+}
+
+impl BatchWriteResult {
+    /// Accounts the operation with the `key` in the statistics.
+    pub fn record(&mut self, key: &[u8], op: &WriteOperation) {
+        let value = match op {
+            WriteOperation::Insert(value) | WriteOperation::Merge(value) => {
+                self.inserted = self.inserted.saturating_add(1);
+                value.len()
+            }
+            WriteOperation::Remove => {
+                self.removed = self.removed.saturating_add(1);
+                0
+            }
+        };
+        self.bytes_written = self
+            .bytes_written
+            .saturating_add(key.len())
+            .saturating_add(value);
+    }
 }
 
 /// The definition of the key-value store with batch operations.
 #[impl_tools::autoimpl(for<T: trait> &T, &mut T, Box<T>, Arc<T>)]
 pub trait BatchOperations: KeyValueStore {
     /// Writes the batch of the entries into the storage.
+    /// If any of the operations fails, the batch is aborted, the already applied
+    /// operations are reverted, and the error is returned.
     // TODO: Replace `dyn Iterator` with a generic iterator when `Database` will not use `dyn BatchOperations`.
     fn batch_write(
         &self,
         entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
     ) -> StorageResult<()> {
-        // TODO: Optimize implementation for in-memory storages.
+        // The previous values of the modified entries to revert the batch in the case of an error.
+        let mut undo_log = vec![];
+        let mut result = Ok(());
         for (key, column, op) in entries {
-            match op {
-                WriteOperation::Insert(value) => {
-                    self.put(&key, column, value)?;
+            let old_value = match op {
+                WriteOperation::Insert(value) => self.replace(&key, column, value),
+                WriteOperation::Remove => self.take(&key, column),
+                WriteOperation::Merge(operand) => {
+                    self.get(&key, column).and_then(|old_value| {
+                        self.merge(&key, column, operand)?;
+                        Ok(old_value)
+                    })
                 }
-                WriteOperation::Remove => {
-                    self.delete(&key, column)?;
+            };
+            match old_value {
+                Ok(old_value) => undo_log.push((key, column, old_value)),
+                Err(err) => {
+                    result = Err(err);
+                    break
                 }
             }
         }
+
+        if let Err(err) = result {
+            for (key, column, old_value) in undo_log.into_iter().rev() {
+                let reverted = match old_value {
+                    Some(value) => self.put(&key, column, value),
+                    None => self.delete(&key, column),
+                };
+                reverted.map_err(|revert_err| {
+                    StorageError::Other(anyhow::anyhow!(
+                        "Unable to revert the batch after the error `{err}`: {revert_err}"
+                    ))
+                })?;
+            }
+            return Err(err)
+        }
         Ok(())
     }
+
+    /// Writes the batch in the same way as [`Self::batch_write`]
+    /// and returns the statistics of the written batch.
+    fn batch_write_with_stats(
+        &self,
+        entries: &mut dyn Iterator<Item = (Vec<u8>, Self::Column, WriteOperation)>,
+    ) -> StorageResult<BatchWriteResult> {
+        let mut stats = BatchWriteResult::default();
+        self.batch_write(&mut entries.inspect(|(key, _, op)| stats.record(key, op)))?;
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column::Column;
+    use std::{
+        cell::{
+            Cell,
+            RefCell,
+        },
+        collections::HashMap,
+    };
+
+    /// The storage that fails on the `fail_on` write operation.
+    #[derive(Default)]
+    struct FaultyStorage {
+        storage: RefCell<HashMap<(u32, Vec<u8>), Value>>,
+        operations: Cell<usize>,
+        fail_on: usize,
+    }
+
+    impl FaultyStorage {
+        fn new(fail_on: usize) -> Self {
+            Self {
+                fail_on,
+                ..Default::default()
+            }
+        }
+
+        fn count_operation(&self) -> StorageResult<()> {
+            let operation = self.operations.get().saturating_add(1);
+            self.operations.set(operation);
+            if operation == self.fail_on {
+                return Err(StorageError::Other(anyhow::anyhow!("Injected fault")))
+            }
+            Ok(())
+        }
+    }
+
+    impl KeyValueStore for FaultyStorage {
+        type Column = Column;
+
+        fn write(
+            &self,
+            key: &[u8],
+            column: Self::Column,
+            buf: &[u8],
+        ) -> StorageResult<usize> {
+            self.count_operation()?;
+            self.storage
+                .borrow_mut()
+                .insert((column.id(), key.to_vec()), Arc::new(buf.to_vec()));
+            Ok(buf.len())
+        }
+
+        fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+            self.count_operation()?;
+            self.storage
+                .borrow_mut()
+                .remove(&(column.id(), key.to_vec()));
+            Ok(())
+        }
+
+        fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+            Ok(self
+                .storage
+                .borrow()
+                .get(&(column.id(), key.to_vec()))
+                .cloned())
+        }
+    }
+
+    impl BatchOperations for FaultyStorage {}
+
+    fn batch() -> Vec<(Vec<u8>, Column, WriteOperation)> {
+        vec![
+            (
+                vec![1],
+                Column::Metadata,
+                WriteOperation::Insert(Arc::new(vec![10])),
+            ),
+            (vec![2], Column::Metadata, WriteOperation::Remove),
+            (
+                vec![3],
+                Column::Coins,
+                WriteOperation::Insert(Arc::new(vec![30])),
+            ),
+            (
+                vec![4],
+                Column::Metadata,
+                WriteOperation::Insert(Arc::new(vec![40])),
+            ),
+        ]
+    }
+
+    #[test]
+    fn batch_write_reverts_applied_operations_on_error() {
+        for fail_on in 1..=4usize {
+            // Given
+            let storage = FaultyStorage::new(0);
+            storage
+                .put(&[1], Column::Metadata, Arc::new(vec![1]))
+                .unwrap();
+            storage
+                .put(&[2], Column::Metadata, Arc::new(vec![2]))
+                .unwrap();
+            let before = storage.storage.borrow().clone();
+            let storage = FaultyStorage {
+                fail_on: fail_on.saturating_add(storage.operations.get()),
+                ..storage
+            };
+
+            // When
+            let result = storage.batch_write(&mut batch().into_iter());
+
+            // Then
+            assert!(result.is_err(), "The batch should fail on {fail_on}");
+            assert_eq!(*storage.storage.borrow(), before);
+        }
+    }
+
+    #[test]
+    fn batch_write_applies_all_operations() {
+        // Given
+        let storage = FaultyStorage::new(0);
+        storage
+            .put(&[2], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+
+        // When
+        storage.batch_write(&mut batch().into_iter()).unwrap();
+
+        // Then
+        assert_eq!(
+            storage.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![10]))
+        );
+        assert_eq!(storage.get(&[2], Column::Metadata).unwrap(), None);
+        assert_eq!(
+            storage.get(&[3], Column::Coins).unwrap(),
+            Some(Arc::new(vec![30]))
+        );
+        assert_eq!(
+            storage.get(&[4], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![40]))
+        );
+    }
+
+    #[test]
+    fn batch_write_with_stats_counts_all_operations() {
+        // Given
+        let storage = FaultyStorage::new(0);
+        let mut entries = batch();
+        entries.push((vec![5, 5], Column::Coins, WriteOperation::Remove));
+
+        // When
+        let stats = storage
+            .batch_write_with_stats(&mut entries.into_iter())
+            .unwrap();
+
+        // Then
+        // The removed keys don't exist in the storage, but they are counted.
+        assert_eq!(
+            stats,
+            BatchWriteResult {
+                inserted: 3,
+                removed: 2,
+                bytes_written: 9,
+            }
+        );
+    }
+
+    #[test]
+    fn batch_write_with_stats_of_empty_batch_is_zero() {
+        let storage = FaultyStorage::new(0);
+
+        let stats = storage
+            .batch_write_with_stats(&mut core::iter::empty())
+            .unwrap();
+
+        assert_eq!(stats, BatchWriteResult::default());
+    }
+
+    #[test]
+    fn u64_add_sums_operands() {
+        let operands = [1u64.to_be_bytes(), 2u64.to_be_bytes()];
+        let operands = operands.iter().map(|operand| operand.as_slice());
+
+        assert_eq!(
+            MergeOperator::U64Add.apply(Some(&10u64.to_be_bytes()), operands.clone()),
+            Some(13u64.to_be_bytes().to_vec())
+        );
+        assert_eq!(
+            MergeOperator::U64Add.apply(None, operands),
+            Some(3u64.to_be_bytes().to_vec())
+        );
+        assert_eq!(
+            MergeOperator::U64Add.apply(
+                Some(&u64::MAX.to_be_bytes()),
+                [1u64.to_be_bytes().as_slice()]
+            ),
+            Some(u64::MAX.to_be_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn u64_add_rejects_malformed_values() {
+        assert_eq!(MergeOperator::U64Add.apply(Some(&[1]), []), None);
+        assert_eq!(
+            MergeOperator::U64Add.apply(None, [[1u8, 2].as_slice()]),
+            None
+        );
+    }
+
+    #[test]
+    fn append_concatenates_operands() {
+        assert_eq!(
+            MergeOperator::Append.apply(Some(&[1]), [[2u8].as_slice(), &[3, 4]]),
+            Some(vec![1, 2, 3, 4])
+        );
+        assert_eq!(
+            MergeOperator::Append.apply(None, [[2u8].as_slice()]),
+            Some(vec![2])
+        );
+    }
+
+    #[test]
+    fn operands_can_be_combined_before_the_value_is_known() {
+        for operator in [MergeOperator::U64Add, MergeOperator::Append] {
+            let [a, b, c] = [1u64, 2, 3].map(u64::to_be_bytes);
+            let combined = operator.apply(Some(&b), [c.as_slice()]).unwrap();
+
+            assert_eq!(
+                operator.apply(Some(&a), [b.as_slice(), &c]),
+                operator.apply(Some(&a), [combined.as_slice()])
+            );
+        }
+    }
+
+    #[test]
+    fn merge_fails_for_columns_without_operator() {
+        let storage = FaultyStorage::new(0);
+
+        let result = storage.merge(&[1], Column::Metadata, Arc::new(vec![1]));
+
+        assert!(result.is_err());
+        assert_eq!(storage.get(&[1], Column::Metadata).unwrap(), None);
+    }
+
+    #[test]
+    fn fixed_format_matches_only_its_size() {
+        let format = KeyFormat::Fixed(32);
+
+        assert!(format.matches(&[0; 32]));
+        assert!(!format.matches(&[0; 31]));
+        assert!(!format.matches(&[0; 33]));
+        assert!(!format.matches(&[]));
+        assert_eq!(format.to_string(), "32 bytes");
+    }
+
+    #[test]
+    fn multi_key_format_matches_only_both_parts() {
+        let format = KeyFormat::MultiKey(32, 8);
+
+        assert!(format.matches(&[0; 40]));
+        // Only one of parts.
+        assert!(!format.matches(&[0; 32]));
+        assert!(!format.matches(&[0; 8]));
+        // One byte short of or above the boundary of the second part.
+        assert!(!format.matches(&[0; 39]));
+        assert!(!format.matches(&[0; 41]));
+        assert_eq!(format.size(), Some(40));
+        assert_eq!(format.to_string(), "32 + 8 bytes");
+    }
+
+    #[test]
+    fn variable_format_matches_any_key() {
+        let format = KeyFormat::Variable;
+
+        assert!(format.matches(&[]));
+        assert!(format.matches(&[0; 1]));
+        assert!(format.matches(&[0; 1024]));
+        assert_eq!(format.size(), None);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(
+        expected = "The key [0] of the column `Coins` has 1 bytes, but expected 33 bytes"
+    )]
+    fn malformed_key_of_the_table_fails_the_debug_assertion() {
+        debug_assert_key_format(Column::Coins, &[0]);
+    }
+
+    #[test]
+    fn get_or_insert_with_calls_the_initializer_only_for_the_missing_value() {
+        // Given
+        let storage = FaultyStorage::new(0);
+        storage
+            .put(&[1], Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+        let mut calls = 0;
+        let mut init = || {
+            calls += 1;
+            vec![2]
+        };
+
+        // When
+        let existing = storage
+            .get_or_insert_with(&[1], Column::Metadata, &mut init)
+            .unwrap();
+        let inserted = storage
+            .get_or_insert_with(&[2], Column::Metadata, &mut init)
+            .unwrap();
+        let repeated = storage
+            .get_or_insert_with(&[2], Column::Metadata, &mut init)
+            .unwrap();
+
+        // Then
+        assert_eq!(calls, 1);
+        assert_eq!(
+            (existing.to_vec(), inserted.to_vec(), repeated.to_vec()),
+            (vec![1], vec![2], vec![2])
+        );
+        assert_eq!(storage.get(&[2], Column::Metadata).unwrap(), Some(inserted));
+    }
+
+    #[test]
+    fn update_writes_only_changed_values() {
+        // Given
+        let storage = FaultyStorage::new(0);
+        storage
+            .put(&[1], Column::Metadata, Arc::new(vec![1]))
+            .unwrap();
+        storage
+            .put(&[2], Column::Metadata, Arc::new(vec![2]))
+            .unwrap();
+        let writes = storage.operations.get();
+
+        // When
+        let same = storage
+            .update(&[1], Column::Metadata, &mut |current| {
+                current.map(<[u8]>::to_vec)
+            })
+            .unwrap();
+        let absent = storage
+            .update(&[3], Column::Metadata, &mut |_| None)
+            .unwrap();
+        let unchanged_writes = storage.operations.get();
+        let updated = storage
+            .update(&[1], Column::Metadata, &mut |current| {
+                current.map(|current| [current, &[10]].concat())
+            })
+            .unwrap();
+        let removed = storage
+            .update(&[2], Column::Metadata, &mut |_| None)
+            .unwrap();
+        let inserted = storage
+            .update(&[3], Column::Metadata, &mut |_| Some(vec![3]))
+            .unwrap();
+
+        // Then
+        assert_eq!(unchanged_writes, writes);
+        assert_eq!(storage.operations.get(), writes.saturating_add(3));
+        assert_eq!(
+            [same, absent, updated, removed, inserted],
+            [
+                UpdateOutcome::Unchanged,
+                UpdateOutcome::Unchanged,
+                UpdateOutcome::Updated,
+                UpdateOutcome::Removed,
+                UpdateOutcome::Inserted,
+            ]
+        );
+        assert_eq!(
+            storage.get(&[1], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![1, 10]))
+        );
+        assert_eq!(storage.get(&[2], Column::Metadata).unwrap(), None);
+        assert_eq!(
+            storage.get(&[3], Column::Metadata).unwrap(),
+            Some(Arc::new(vec![3]))
+        );
+    }
 }
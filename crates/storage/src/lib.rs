@@ -55,7 +55,7 @@ pub enum Error {
     Codec(anyhow::Error),
     /// Error occurred during interaction with database.
     #[display(fmt = "error occurred in the underlying datastore `{_0:?}`")]
-    DatabaseError(Box<dyn core::fmt::Debug + Send + Sync>),
+    DatabaseError(Box<dyn std::error::Error + Send + Sync>),
     /// This error should be created with `not_found` macro.
     #[display(fmt = "resource of type `{_0}` was not found at the: {_1}")]
     NotFound(&'static str, &'static str),
@@ -65,9 +65,75 @@ pub enum Error {
     Other(anyhow::Error),
 }
 
-impl From<Error> for anyhow::Error {
-    fn from(error: Error) -> Self {
-        anyhow::Error::msg(error)
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Codec(error) | Error::Other(error) => Some(error.as_ref()),
+            Error::DatabaseError(error) => Some(error.as_ref()),
+            Error::NotFound(_, _) => None,
+        }
+    }
+}
+
+/// The value of the column can't be decoded. The typed layer wraps decoding errors
+/// into it inside of the [`Error::Codec`], so the database can report the entry.
+#[derive(Debug, derive_more::Display)]
+#[display(
+    fmt = "unable to decode the value of the column `{column}` at the key {key:?}: {source}"
+)]
+pub struct DecodeError {
+    /// The name of the column.
+    pub column: &'static str,
+    /// The raw key of the entry.
+    pub key: Vec<u8>,
+    /// The decoding error.
+    pub source: anyhow::Error,
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// The database error that has only the debug representation, see [`Error::database`].
+struct DebugError<T>(T);
+
+impl<T: core::fmt::Debug> core::fmt::Debug for DebugError<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Display for DebugError<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: core::fmt::Debug> std::error::Error for DebugError<T> {}
+
+impl Error {
+    /// Creates the [`Error::DatabaseError`] from the `error` that implements only
+    /// `Debug`, as the variant accepted before it required `std::error::Error`.
+    pub fn database<E>(error: E) -> Self
+    where
+        E: core::fmt::Debug + Send + Sync + 'static,
+    {
+        Error::DatabaseError(Box::new(DebugError(error)))
+    }
+
+    /// Creates the [`Error::Codec`] for the value of the `column` at the `key`.
+    pub fn decode<Column: kv_store::StorageColumn>(
+        column: Column,
+        key: &[u8],
+        source: anyhow::Error,
+    ) -> Self {
+        Error::Codec(anyhow::Error::new(DecodeError {
+            column: column.name(),
+            key: key.to_vec(),
+            source,
+        }))
     }
 }
 
@@ -145,6 +211,17 @@ pub trait StorageBatchMutate<Type: Mappable>: StorageMutate<Type> {
         Type::Key: 'a;
 }
 
+/// The trait allows writing into the storage without reading the previous value.
+/// Unlike [`StorageMutate::insert`] and [`StorageMutate::remove`], methods don't return
+/// the old value, so the implementation can skip the read before the write.
+pub trait StorageBlindMutate<Type: Mappable>: StorageMutate<Type> {
+    /// Puts the value under the key into the storage, overwriting the previous value.
+    fn put(&mut self, key: &Type::Key, value: &Type::Value) -> Result<()>;
+
+    /// Removes the value under the key from the storage, if any.
+    fn delete(&mut self, key: &Type::Key) -> Result<()>;
+}
+
 /// Creates `StorageError::NotFound` error with file and line information inside.
 ///
 /// # Examples
@@ -187,4 +264,14 @@ mod test {
             format!("resource of type `fuel_core_types::entities::coins::coin::CompressedCoin` was not found at the: {}:{}", file!(), line!() - 1)
         );
     }
+
+    #[test]
+    fn database_error_keeps_the_debug_output() {
+        let error = crate::Error::database("closed");
+
+        assert_eq!(
+            format!("{error}"),
+            "error occurred in the underlying datastore `\"closed\"`"
+        );
+    }
 }
@@ -17,6 +17,7 @@ use crate::{
     MerkleRoot,
     MerkleRootStorage,
     StorageBatchMutate,
+    StorageBlindMutate,
     StorageInspect,
     StorageMutate,
     StorageSize,
@@ -114,6 +115,26 @@ where
     }
 }
 
+impl<Column, S, M> StorageBlindMutate<M> for StructuredStorage<S>
+where
+    S: KeyValueStore<Column = Column>,
+    M: Mappable + TableWithBlueprint<Column = Column>,
+    M::Blueprint: Blueprint<M, S>,
+{
+    fn put(&mut self, key: &M::Key, value: &M::Value) -> Result<(), Self::Error> {
+        <M as TableWithBlueprint>::Blueprint::put(
+            &mut self.storage,
+            key,
+            M::column(),
+            value,
+        )
+    }
+
+    fn delete(&mut self, key: &M::Key) -> Result<(), Self::Error> {
+        <M as TableWithBlueprint>::Blueprint::delete(&mut self.storage, key, M::column())
+    }
+}
+
 impl<Column, S, M> StorageSize<M> for StructuredStorage<S>
 where
     S: KeyValueStore<Column = Column>,
@@ -246,3 +267,92 @@ pub mod test {
 
     impl<Column> BatchOperations for InMemoryStorage<Column> where Column: StorageColumn {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        column::Column,
+        kv_store::Value,
+        structured_storage::test::InMemoryStorage,
+        tables::Coins,
+        Result as StorageResult,
+        StorageAsMut,
+        StorageAsRef,
+    };
+    use fuel_core_types::{
+        entities::coins::coin::CompressedCoin,
+        fuel_tx::UtxoId,
+    };
+    use std::cell::Cell;
+
+    /// The storage that counts the number of reads.
+    #[derive(Default)]
+    struct ReadCountingStorage {
+        storage: InMemoryStorage<Column>,
+        reads: Cell<usize>,
+    }
+
+    impl KeyValueStore for ReadCountingStorage {
+        type Column = Column;
+
+        fn write(
+            &self,
+            key: &[u8],
+            column: Self::Column,
+            buf: &[u8],
+        ) -> StorageResult<usize> {
+            self.storage.write(key, column, buf)
+        }
+
+        fn delete(&self, key: &[u8], column: Self::Column) -> StorageResult<()> {
+            self.storage.delete(key, column)
+        }
+
+        fn get(&self, key: &[u8], column: Self::Column) -> StorageResult<Option<Value>> {
+            self.reads.set(self.reads.get().saturating_add(1));
+            self.storage.get(key, column)
+        }
+    }
+
+    #[test]
+    fn put_and_delete_do_not_read_the_old_value() {
+        // Given
+        let mut storage = StructuredStorage::new(ReadCountingStorage::default());
+        let utxo_id = UtxoId::default();
+        let coin = CompressedCoin::default();
+        storage
+            .storage_as_mut::<Coins>()
+            .insert(&utxo_id, &coin)
+            .unwrap();
+        let reads = storage.as_ref().reads.get();
+
+        // When
+        StorageBlindMutate::<Coins>::put(&mut storage, &utxo_id, &coin).unwrap();
+        StorageBlindMutate::<Coins>::delete(&mut storage, &utxo_id).unwrap();
+
+        // Then
+        assert_eq!(storage.as_ref().reads.get(), reads);
+        assert!(!storage
+            .storage_as_ref::<Coins>()
+            .contains_key(&utxo_id)
+            .unwrap());
+    }
+
+    #[test]
+    fn put_overwrites_the_previous_value() {
+        // Given
+        let mut storage = StructuredStorage::new(ReadCountingStorage::default());
+        let utxo_id = UtxoId::default();
+        let mut coin = CompressedCoin::default();
+        StorageBlindMutate::<Coins>::put(&mut storage, &utxo_id, &coin).unwrap();
+        coin.set_amount(100);
+
+        // When
+        StorageBlindMutate::<Coins>::put(&mut storage, &utxo_id, &coin).unwrap();
+
+        // Then
+        let stored = storage.storage_as_ref::<Coins>().get(&utxo_id).unwrap();
+        assert_eq!(stored.unwrap().into_owned(), coin);
+    }
+}